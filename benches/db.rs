@@ -0,0 +1,181 @@
+//! Database benchmarks on a synthetic library, each run with SQLite's
+//! defaults and with the `--fast` profile.
+//!
+//! `cargo bench --bench db` builds a 100k-file database (and, for sync, a
+//! library of as many empty files) in a temporary directory first. Set
+//! `PICMAN_BENCH_FILES` for a smaller or bigger one.
+
+use std::fs;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use tempfile::TempDir;
+
+use picman::cli::{run_init, run_sync};
+use picman::db::{set_fast_profile, Database, DuplicateScope, ExposureFilter};
+
+const FILES_PER_DIR: usize = 100;
+
+/// One file in this many shares its hash with the next one
+const DUPLICATE_EVERY: usize = 50;
+
+const PROFILES: [(&str, bool); 2] = [("default", false), ("fast", true)];
+
+fn file_count() -> usize {
+    std::env::var("PICMAN_BENCH_FILES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(100_000)
+}
+
+fn dir_path(dir: usize) -> String {
+    format!("{}/album-{:03}", 2000 + dir / 40, dir)
+}
+
+/// Fill a database with `files` photos over directories of
+/// [`FILES_PER_DIR`], with ratings, tags, exposure and some duplicates
+fn synthetic_db(path: &Path, files: usize) -> Database {
+    let db = Database::open(path).unwrap();
+    db.begin_transaction().unwrap();
+    let mut years = std::collections::HashMap::new();
+    for dir in 0..files.div_ceil(FILES_PER_DIR) {
+        let path = dir_path(dir);
+        let year = path.split('/').next().unwrap().to_string();
+        let parent = *years
+            .entry(year.clone())
+            .or_insert_with(|| db.insert_directory(&year, None, Some(0)).unwrap());
+        let dir_id = db.insert_directory(&path, Some(parent), Some(0)).unwrap();
+
+        for n in 0..FILES_PER_DIR.min(files - dir * FILES_PER_DIR) {
+            let i = dir * FILES_PER_DIR + n;
+            let id = db
+                .insert_file_with_dimensions(
+                    dir_id,
+                    &format!("IMG_{:06}.jpg", i),
+                    1_000_000 + i as i64,
+                    1_600_000_000 + i as i64,
+                    Some("image"),
+                    Some(6000),
+                    Some(4000),
+                )
+                .unwrap();
+            let hash_of = if i % DUPLICATE_EVERY == 1 { i - 1 } else { i };
+            db.set_file_hash(id, &format!("{:016x}", hash_of)).unwrap();
+            if i.is_multiple_of(3) {
+                db.set_file_rating(id, Some((i % 5 + 1) as f64)).unwrap();
+            }
+            if i.is_multiple_of(7) {
+                db.add_file_tag(id, "portrait").unwrap();
+            }
+            db.set_file_exposure(id, Some(100 << (i % 7)), Some(1.4 * (1 + i % 8) as f64), Some(1.0 / (1 + i % 500) as f64))
+                .unwrap();
+        }
+    }
+    db.commit().unwrap();
+    db
+}
+
+/// One database per profile, so WAL (kept in the file) stays apart
+fn profile_dbs(tmp: &TempDir, files: usize) -> Vec<(&'static str, PathBuf)> {
+    PROFILES
+        .iter()
+        .map(|&(name, fast)| {
+            let path = tmp.path().join(format!("{}.db", name));
+            set_fast_profile(fast);
+            synthetic_db(&path, files);
+            (name, path)
+        })
+        .collect()
+}
+
+fn open(path: &Path, fast: bool) -> Database {
+    set_fast_profile(fast);
+    Database::open(path).unwrap()
+}
+
+fn queries(c: &mut Criterion) {
+    let files = file_count();
+    let tmp = TempDir::new().unwrap();
+    let dbs = profile_dbs(&tmp, files);
+
+    let mut group = c.benchmark_group("filter");
+    for ((name, path), (_, fast)) in dbs.iter().zip(PROFILES) {
+        let db = open(path, fast);
+        group.bench_function(BenchmarkId::new("rating", name), |b| {
+            b.iter(|| black_box(db.get_files_by_rating(4.0).unwrap()))
+        });
+        group.bench_function(BenchmarkId::new("tag", name), |b| {
+            b.iter(|| black_box(db.get_files_by_tag("portrait").unwrap()))
+        });
+        let exposure = ExposureFilter { min_iso: Some(3200), max_aperture: Some(2.8), ..Default::default() };
+        group.bench_function(BenchmarkId::new("exposure", name), |b| {
+            b.iter(|| black_box(db.get_file_ids_matching_exposure(&exposure).unwrap()))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("duplicates");
+    for ((name, path), (_, fast)) in dbs.iter().zip(PROFILES) {
+        let db = open(path, fast);
+        group.bench_function(BenchmarkId::new("all", name), |b| {
+            b.iter(|| black_box(db.find_duplicates_with_paths().unwrap()))
+        });
+        let scope = DuplicateScope { min_rating: Some(3.0), ..Default::default() };
+        group.bench_function(BenchmarkId::new("rated", name), |b| {
+            b.iter(|| black_box(db.find_duplicates_in(&scope).unwrap()))
+        });
+    }
+    group.finish();
+
+    // One commit per change, as when rating in the TUI
+    let mut group = c.benchmark_group("writes");
+    for ((name, path), (_, fast)) in dbs.iter().zip(PROFILES) {
+        let db = open(path, fast);
+        let mut id = 0;
+        group.bench_function(BenchmarkId::new("rating", name), |b| {
+            b.iter(|| {
+                id = id % files as i64 + 1;
+                db.set_file_rating(id, Some(4.0)).unwrap();
+            })
+        });
+    }
+    group.finish();
+    set_fast_profile(false);
+}
+
+/// A library of `files` empty photos laid out like [`synthetic_db`]
+fn synthetic_library(root: &Path, files: usize) {
+    for i in 0..files {
+        let dir = root.join(dir_path(i / FILES_PER_DIR));
+        if i.is_multiple_of(FILES_PER_DIR) {
+            fs::create_dir_all(&dir).unwrap();
+        }
+        fs::write(dir.join(format!("IMG_{:06}.jpg", i)), i.to_le_bytes()).unwrap();
+    }
+}
+
+fn sync(c: &mut Criterion) {
+    let files = file_count();
+    let mut group = c.benchmark_group("sync");
+    group.sample_size(10).measurement_time(Duration::from_secs(30));
+    for (name, fast) in PROFILES {
+        let tmp = TempDir::new().unwrap();
+        synthetic_library(tmp.path(), files);
+        set_fast_profile(fast);
+        run_init(tmp.path()).unwrap();
+
+        group.bench_function(BenchmarkId::new("incremental", name), |b| {
+            b.iter(|| black_box(run_sync(tmp.path(), false, false, false).unwrap()))
+        });
+        group.bench_function(BenchmarkId::new("full", name), |b| {
+            b.iter(|| black_box(run_sync(tmp.path(), false, false, true).unwrap()))
+        });
+    }
+    group.finish();
+    set_fast_profile(false);
+}
+
+criterion_group!(benches, queries, sync);
+criterion_main!(benches);
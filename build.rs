@@ -0,0 +1,155 @@
+//! Build script for the embedded web UI.
+//!
+//! Checks that every file the SPA references (scripts, stylesheets, ES module
+//! imports, CSS `@import`s) exists in `src/serve/assets/`, so a missing module
+//! fails the build instead of a page load, and fingerprints the assets for
+//! `/api/version`. With the `frontend-build` feature it first runs the
+//! frontend's npm build when `frontend/package.json` exists; the SPA as
+//! shipped is plain ES modules and needs no build.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const ASSETS_DIR: &str = "src/serve/assets";
+const FRONTEND_DIR: &str = "frontend";
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", ASSETS_DIR);
+
+    if env::var_os("CARGO_FEATURE_FRONTEND_BUILD").is_some() {
+        build_frontend();
+    }
+
+    let assets = Path::new(ASSETS_DIR);
+    let files = asset_files(assets);
+    check_references(assets, &files);
+    println!("cargo:rustc-env=PICMAN_ASSET_HASH={:016x}", hash_assets(assets, &files));
+}
+
+/// Run `npm ci` (first time) and `npm run build` in `frontend/`, which is
+/// expected to write its output into the assets directory
+fn build_frontend() {
+    let frontend = Path::new(FRONTEND_DIR);
+    println!("cargo:rerun-if-changed={}", FRONTEND_DIR);
+    if !frontend.join("package.json").exists() {
+        println!(
+            "cargo:warning=frontend-build: no {}/package.json, embedding {} as is",
+            FRONTEND_DIR, ASSETS_DIR
+        );
+        return;
+    }
+
+    let mut steps = vec![vec!["run", "build"]];
+    if !frontend.join("node_modules").exists() {
+        steps.insert(0, vec!["ci"]);
+    }
+    for args in steps {
+        let status = Command::new("npm")
+            .args(&args)
+            .current_dir(frontend)
+            .status()
+            .unwrap_or_else(|e| panic!("frontend-build: couldn't run npm: {}", e));
+        if !status.success() {
+            panic!("frontend-build: `npm {}` failed ({})", args.join(" "), status);
+        }
+    }
+}
+
+/// Paths of all asset files relative to `dir`, sorted
+fn asset_files(dir: &Path) -> Vec<PathBuf> {
+    fn walk(root: &Path, dir: &Path, out: &mut Vec<PathBuf>) {
+        let entries = fs::read_dir(dir)
+            .unwrap_or_else(|e| panic!("can't read {}: {}", dir.display(), e));
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(root, &path, out);
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                out.push(relative.to_path_buf());
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    walk(dir, dir, &mut files);
+    files.sort();
+    files
+}
+
+/// Panic listing every local reference that doesn't resolve to an asset
+fn check_references(dir: &Path, files: &[PathBuf]) {
+    if !files.iter().any(|f| f == Path::new("index.html")) {
+        panic!("{}/index.html is missing", dir.display());
+    }
+
+    let mut missing = Vec::new();
+    for file in files {
+        let markers: &[&str] = match file.extension().and_then(|e| e.to_str()) {
+            Some("html") => &["src=", "href="],
+            Some("css") => &["url("],
+            Some("js") => &["from ", "import "],
+            _ => continue,
+        };
+        let Ok(text) = fs::read_to_string(dir.join(file)) else {
+            continue;
+        };
+        let base = file.parent().unwrap_or(Path::new(""));
+        for marker in markers {
+            for reference in quoted_after(&text, marker) {
+                if !is_local(&reference) {
+                    continue;
+                }
+                let target = base.join(reference.trim_start_matches("./"));
+                if !files.contains(&target) {
+                    missing.push(format!("{} -> {}", file.display(), reference));
+                }
+            }
+        }
+    }
+
+    if !missing.is_empty() {
+        panic!("web UI references missing assets:\n  {}", missing.join("\n  "));
+    }
+}
+
+/// Quoted strings directly following each occurrence of `marker`
+fn quoted_after(text: &str, marker: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    for (start, _) in text.match_indices(marker) {
+        let rest = &text[start + marker.len()..];
+        let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
+        };
+        if let Some(end) = rest[1..].find(quote) {
+            found.push(rest[1..1 + end].to_string());
+        }
+    }
+    found
+}
+
+fn is_local(reference: &str) -> bool {
+    !reference.is_empty()
+        && !reference.contains("://")
+        && !reference.starts_with('/')
+        && !reference.starts_with('#')
+        && !reference.starts_with("data:")
+}
+
+/// FNV-1a over every asset's path and contents
+fn hash_assets(dir: &Path, files: &[PathBuf]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut feed = |bytes: &[u8]| {
+        for byte in bytes {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+    for file in files {
+        feed(file.to_string_lossy().as_bytes());
+        feed(&[0]);
+        feed(&fs::read(dir.join(file)).unwrap_or_default());
+    }
+    hash
+}
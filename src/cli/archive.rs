@@ -0,0 +1,323 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::db::{ArchiveFilter, ArchivedFile, Database};
+use crate::hash::compute_file_hash_with;
+use crate::process::unix_now;
+use crate::s3::{S3Client, S3Location};
+use crate::vfs::open_library_fs;
+use crate::library_path::LibraryPath;
+
+/// First line of the placeholder left behind by `archive --stub`
+pub const STUB_HEADER: &str = "picman archive stub";
+
+/// Options for the archive command
+#[derive(Debug, Default)]
+pub struct ArchiveOptions {
+    pub filter: ArchiveFilter,
+    /// Replace local originals with small stubs after uploading
+    pub stub: bool,
+    /// Only report what would be uploaded
+    pub dry_run: bool,
+}
+
+/// Outcome of an archive run
+#[derive(Debug, Default, PartialEq)]
+pub struct ArchiveReport {
+    /// Files uploaded (or that would be, for a dry run)
+    pub uploaded: usize,
+    pub bytes: u64,
+    /// Local copies replaced by stubs
+    pub stubbed: usize,
+    /// Files already archived (and stubbed, if requested)
+    pub skipped: usize,
+    /// Files in the database but missing on disk
+    pub missing: usize,
+}
+
+/// Outcome of a restore run
+#[derive(Debug, Default, PartialEq)]
+pub struct RestoreReport {
+    pub restored: usize,
+    /// Files whose local copy is not a stub, left alone
+    pub already_local: usize,
+}
+
+fn open_database(library_path: &Path) -> Result<Database> {
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+    if open_library_fs(library_path)?.is_remote() {
+        bail!("Archiving needs local originals; it is not available for remote libraries");
+    }
+    Database::open(&db_path)
+}
+
+/// Upload originals matching a filter to object storage and record where
+/// they went. With `stub`, each uploaded original is replaced by a small
+/// placeholder that keeps its mtime, so sync leaves the entry alone.
+///
+/// # Arguments
+/// * `library_path` - Path to the library root
+/// * `to` - Destination, `s3://bucket/prefix`; objects keep the library-relative path
+/// * `options` - Which files, and whether to stub them
+pub fn run_archive(library_path: &Path, to: &str, options: &ArchiveOptions) -> Result<ArchiveReport> {
+    let db = open_database(library_path)?;
+    let location = S3Location::parse(to)?;
+    let config = Config::load(library_path)?;
+    let client = S3Client::new(&config.archive)?;
+    let mut report = ArchiveReport::default();
+
+    for (file, dir_path) in db.get_archive_candidates(&options.filter)? {
+        let relative = LibraryPath::file(&dir_path, &file.filename).into_string();
+        let local = library_path.join(&relative);
+        let existing = db.get_archived_file(file.id)?;
+
+        if existing.as_ref().is_some_and(|a| a.stubbed || !options.stub) {
+            report.skipped += 1;
+            continue;
+        }
+        if !local.is_file() {
+            warn!(path = %relative, "skipping file missing on disk");
+            report.missing += 1;
+            continue;
+        }
+
+        // Compare against an earlier upload with the algorithm it was recorded with
+        let algorithm = existing
+            .as_ref()
+            .map_or(config.hashing.algorithm, |a| a.hash_algorithm);
+        let hash = compute_file_hash_with(&local, algorithm, |_| {})?;
+        // Already uploaded and unchanged since; only the stub is missing
+        let archived = match existing.filter(|a| a.hash == hash) {
+            Some(archived) => archived,
+            None => {
+                report.uploaded += 1;
+                report.bytes += file.size.max(0) as u64;
+                if options.dry_run {
+                    continue;
+                }
+
+                let url = location.object_url(&relative);
+                client.upload(&local, &url)?;
+                info!(path = %relative, %url, "archived original");
+                let archived = ArchivedFile {
+                    file_id: file.id,
+                    location: url,
+                    hash: hash.clone(),
+                    hash_algorithm: algorithm,
+                    size: file.size,
+                    archived_at: unix_now(),
+                    stubbed: false,
+                };
+                db.record_archived_file(&archived)?;
+                if file.hash.is_none() && algorithm == config.hashing.algorithm {
+                    db.set_file_hash_with(file.id, &hash, algorithm)?;
+                }
+                archived
+            }
+        };
+
+        if options.stub && !options.dry_run {
+            write_stub(&local, &archived)?;
+            db.set_archive_stubbed(file.id, true)?;
+            report.stubbed += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Download archived originals back over their stubs, verifying the hash
+/// recorded at upload time
+///
+/// # Arguments
+/// * `library_path` - Path to the library root
+/// * `files` - Files to restore (relative to library root)
+pub fn run_restore(library_path: &Path, files: &[PathBuf]) -> Result<RestoreReport> {
+    let db = open_database(library_path)?;
+    let client = S3Client::new(&Config::load(library_path)?.archive)?;
+    let mut report = RestoreReport::default();
+
+    for file_path in files {
+        let relative = file_path.to_string_lossy();
+        let Some(file) = db.get_file_by_path(&relative)? else {
+            bail!("File not found in database: {}", relative);
+        };
+        let Some(archived) = db.get_archived_file(file.id)? else {
+            bail!("{} has not been archived", relative);
+        };
+
+        let local = library_path.join(file_path);
+        if !archived.stubbed && local.is_file() {
+            report.already_local += 1;
+            continue;
+        }
+
+        // The stub carries the original's exact mtime; the database only has seconds
+        let mtime = std::fs::metadata(&local)
+            .and_then(|m| m.modified())
+            .unwrap_or(UNIX_EPOCH + Duration::from_secs(file.mtime.max(0) as u64));
+        let partial = sibling_temp_path(&local, "restore");
+        client.download(&archived.location, &partial)?;
+        let hash = compute_file_hash_with(&partial, archived.hash_algorithm, |_| {})?;
+        if hash != archived.hash {
+            let _ = std::fs::remove_file(&partial);
+            bail!(
+                "Checksum mismatch restoring {}: expected {}, got {}",
+                relative,
+                archived.hash,
+                hash
+            );
+        }
+        set_mtime(&partial, mtime)?;
+        std::fs::rename(&partial, &local)
+            .with_context(|| format!("Failed to replace {}", local.display()))?;
+
+        db.set_archive_stubbed(file.id, false)?;
+        info!(path = %relative, "restored original");
+        report.restored += 1;
+    }
+
+    Ok(report)
+}
+
+/// Replace an original with a placeholder describing where it went. The
+/// placeholder keeps the original's exact mtime so thumbnails cached for it
+/// stay valid.
+fn write_stub(local: &Path, archived: &ArchivedFile) -> Result<()> {
+    let mtime = std::fs::metadata(local)?.modified()?;
+    let partial = sibling_temp_path(local, "stub");
+    let contents = format!(
+        "{}\nlocation: {}\nhash: {}\nsize: {}\nRun 'picman restore' to bring the original back.\n",
+        STUB_HEADER, archived.location, archived.hash, archived.size
+    );
+    std::fs::write(&partial, contents)
+        .with_context(|| format!("Failed to write {}", partial.display()))?;
+    set_mtime(&partial, mtime)?;
+    std::fs::rename(&partial, local)
+        .with_context(|| format!("Failed to replace {}", local.display()))?;
+    Ok(())
+}
+
+/// Hidden temp file next to `path`, so the final rename stays on one filesystem
+pub(super) fn sibling_temp_path(path: &Path, purpose: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.picman-{}", name, purpose))
+}
+
+pub(super) fn set_mtime(path: &Path, mtime: SystemTime) -> Result<()> {
+    let file = std::fs::File::options().write(true).open(path)?;
+    file.set_modified(mtime)?;
+    Ok(())
+}
+
+// The archive tests run a fake `aws` shell script
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::cli::init::run_init;
+    use crate::cli::sync::run_sync;
+    use crate::config::config_path;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    /// A library whose archive goes to a fake `aws` that maps s3:// URLs
+    /// into a local directory
+    fn setup() -> (TempDir, PathBuf, PathBuf) {
+        let temp = TempDir::new().unwrap();
+        let lib = temp.path().join("lib");
+        let bucket_root = temp.path().join("s3");
+        fs::create_dir_all(lib.join("trip")).unwrap();
+        fs::write(lib.join("trip/clip.mp4"), "a very large video").unwrap();
+        fs::write(lib.join("trip/a.jpg"), "jpeg").unwrap();
+
+        let aws = temp.path().join("fake-aws");
+        fs::write(
+            &aws,
+            format!(
+                "#!/bin/sh\nwhile [ \"$1\" != \"cp\" ]; do shift; done\nshift 2\n\
+                 map() {{ case \"$1\" in s3://*) echo \"{}/${{1#s3://}}\";; *) echo \"$1\";; esac; }}\n\
+                 src=$(map \"$1\"); dst=$(map \"$2\")\n\
+                 mkdir -p \"$(dirname \"$dst\")\" && cp \"$src\" \"$dst\"\n",
+                bucket_root.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&aws, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(
+            config_path(&lib),
+            format!("[archive]\naws_command = \"{}\"\n", aws.display()),
+        )
+        .unwrap();
+
+        run_init(&lib).unwrap();
+        (temp, lib, bucket_root)
+    }
+
+    #[test]
+    fn test_archive_stub_sync_restore_roundtrip() {
+        let (_temp, lib, bucket_root) = setup();
+        let clip = lib.join("trip/clip.mp4");
+        let mtime_before = fs::metadata(&clip).unwrap().modified().unwrap();
+        let options = ArchiveOptions {
+            filter: ArchiveFilter { media_type: Some("video".to_string()), ..Default::default() },
+            stub: true,
+            dry_run: false,
+        };
+
+        let dry = run_archive(&lib, "s3://cold/photos", &ArchiveOptions { dry_run: true, ..Default::default() })
+            .unwrap();
+        assert_eq!(dry.uploaded, 2);
+        assert!(!bucket_root.exists(), "dry run must not upload");
+
+        let report = run_archive(&lib, "s3://cold/photos", &options).unwrap();
+        assert_eq!((report.uploaded, report.stubbed), (1, 1));
+        assert_eq!(
+            fs::read_to_string(bucket_root.join("cold/photos/trip/clip.mp4")).unwrap(),
+            "a very large video"
+        );
+        assert!(fs::read_to_string(&clip).unwrap().starts_with(STUB_HEADER));
+        assert_eq!(fs::metadata(&clip).unwrap().modified().unwrap(), mtime_before);
+
+        // Sync must not treat the stub as a modified file
+        let stats = run_sync(&lib, false, false, true).unwrap();
+        assert_eq!(stats.files_modified, 0);
+        let again = run_archive(&lib, "s3://cold/photos", &options).unwrap();
+        assert_eq!((again.uploaded, again.skipped), (0, 1));
+
+        let restored = run_restore(&lib, &[PathBuf::from("trip/clip.mp4")]).unwrap();
+        assert_eq!(restored.restored, 1);
+        assert_eq!(fs::read_to_string(&clip).unwrap(), "a very large video");
+        assert_eq!(fs::metadata(&clip).unwrap().modified().unwrap(), mtime_before);
+        let db = Database::open(&lib.join(".picman.db")).unwrap();
+        let file = db.get_file_by_path("trip/clip.mp4").unwrap().unwrap();
+        assert!(!db.is_archive_stub(file.id).unwrap());
+
+        let noop = run_restore(&lib, &[PathBuf::from("trip/clip.mp4")]).unwrap();
+        assert_eq!(noop.already_local, 1);
+        assert!(run_restore(&lib, &[PathBuf::from("trip/a.jpg")]).is_err());
+    }
+
+    #[test]
+    fn test_restore_rejects_corrupted_object() {
+        let (_temp, lib, bucket_root) = setup();
+        let options = ArchiveOptions { stub: true, ..Default::default() };
+        run_archive(&lib, "s3://cold", &options).unwrap();
+        fs::write(bucket_root.join("cold/trip/a.jpg"), "bitrot").unwrap();
+
+        let err = run_restore(&lib, &[PathBuf::from("trip/a.jpg")]).unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"));
+        assert!(fs::read_to_string(lib.join("trip/a.jpg")).unwrap().starts_with(STUB_HEADER));
+        assert!(!lib.join("trip/.a.jpg.picman-restore").exists());
+    }
+}
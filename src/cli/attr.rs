@@ -0,0 +1,153 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::db::{Activity, Database};
+
+/// What the attr command should do
+#[derive(Debug)]
+pub enum AttrAction {
+    /// Set `key` to `value`
+    Set { key: String, value: String },
+    /// Show one attribute, or all of them when `key` is None
+    Get { key: Option<String> },
+    /// Remove `key`
+    Unset { key: String },
+}
+
+/// Set, get, or remove custom key/value attributes on a file
+///
+/// # Arguments
+/// * `library_path` - Path to the library root
+/// * `file_path` - Path to the file (relative to library root)
+/// * `action` - What to do
+///
+/// # Returns
+/// The attributes the action refers to, as (key, value) sorted by key: the
+/// requested one for `Get { key: Some(_) }`, otherwise all remaining ones
+pub fn run_attr(library_path: &Path, file_path: &Path, action: AttrAction) -> Result<Vec<(String, String)>> {
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+
+    let db = Database::open(&db_path)?;
+
+    let relative_path = file_path.to_string_lossy();
+
+    let file = db.get_file_by_path(&relative_path)?;
+    let file = match file {
+        Some(f) => f,
+        None => anyhow::bail!("File not found in database: {}", relative_path),
+    };
+
+    match action {
+        AttrAction::Set { key, value } => {
+            let key = validate_key(&key)?;
+            db.set_file_attribute(file.id, key, &value)?;
+            db.record_file_activity(file.id, Activity::Edited, chrono::Utc::now().timestamp())?;
+        }
+        AttrAction::Get { key: Some(key) } => {
+            return match db.get_file_attribute(file.id, key.trim())? {
+                Some(value) => Ok(vec![(key.trim().to_string(), value)]),
+                None => anyhow::bail!("{} has no attribute '{}'", relative_path, key.trim()),
+            };
+        }
+        AttrAction::Get { key: None } => {}
+        AttrAction::Unset { key } => {
+            if !db.remove_file_attribute(file.id, key.trim())? {
+                anyhow::bail!("{} has no attribute '{}'", relative_path, key.trim());
+            }
+            db.record_file_activity(file.id, Activity::Edited, chrono::Utc::now().timestamp())?;
+        }
+    }
+
+    db.get_file_attributes(file.id)
+}
+
+/// Keys are trimmed and must be non-empty
+fn validate_key(key: &str) -> Result<&str> {
+    let key = key.trim();
+    if key.is_empty() {
+        anyhow::bail!("Attribute key cannot be empty");
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup_test_library() -> (TempDir, std::path::PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let lib_path = temp_dir.path().to_path_buf();
+
+        fs::write(lib_path.join("photo.jpg"), "fake jpeg").unwrap();
+        crate::cli::run_init(&lib_path).unwrap();
+
+        (temp_dir, lib_path)
+    }
+
+    fn set(lib_path: &Path, key: &str, value: &str) -> Result<Vec<(String, String)>> {
+        run_attr(
+            lib_path,
+            Path::new("photo.jpg"),
+            AttrAction::Set { key: key.to_string(), value: value.to_string() },
+        )
+    }
+
+    #[test]
+    fn test_attr_set_get_unset() {
+        let (_temp_dir, lib_path) = setup_test_library();
+
+        set(&lib_path, " client ", "Acme").unwrap();
+        let all = set(&lib_path, "print_status", "ordered").unwrap();
+        assert_eq!(
+            all,
+            vec![
+                ("client".to_string(), "Acme".to_string()),
+                ("print_status".to_string(), "ordered".to_string()),
+            ]
+        );
+
+        let one = run_attr(
+            &lib_path,
+            Path::new("photo.jpg"),
+            AttrAction::Get { key: Some("client".to_string()) },
+        )
+        .unwrap();
+        assert_eq!(one, vec![("client".to_string(), "Acme".to_string())]);
+
+        let rest = run_attr(
+            &lib_path,
+            Path::new("photo.jpg"),
+            AttrAction::Unset { key: "client".to_string() },
+        )
+        .unwrap();
+        assert_eq!(rest, vec![("print_status".to_string(), "ordered".to_string())]);
+    }
+
+    #[test]
+    fn test_attr_errors() {
+        let (_temp_dir, lib_path) = setup_test_library();
+
+        assert!(set(&lib_path, "  ", "x").is_err());
+        assert!(run_attr(
+            &lib_path,
+            Path::new("photo.jpg"),
+            AttrAction::Get { key: Some("missing".to_string()) },
+        )
+        .is_err());
+        assert!(run_attr(
+            &lib_path,
+            Path::new("nonexistent.jpg"),
+            AttrAction::Get { key: None },
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! Shell completion: `picman completions <shell>` prints the script
+//! clap_complete generates from the CLI definition, followed by a small hook
+//! that first asks `picman __complete` for values only the library's
+//! database knows. When the hook prints nothing, the generated completion
+//! runs, so subcommands, flags and possible values never drift from clap.
+//!
+//! The hook completes arguments by their clap id:
+//!
+//! - `tag`, `add`, `remove`: tag names
+//! - `file`, `files`, `dir`, `subdir`: paths in the library, one directory
+//!   level at a time
+//!
+//! The library is the `library`/`path` argument typed so far, or the current
+//! directory.
+
+use std::path::{Path, PathBuf};
+
+use clap::{Arg, Command, ValueEnum};
+
+use super::init::DB_FILENAME;
+use crate::db::Database;
+use crate::library_path::LibraryPath;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    fn generator(self) -> clap_complete::Shell {
+        match self {
+            Shell::Bash => clap_complete::Shell::Bash,
+            Shell::Zsh => clap_complete::Shell::Zsh,
+            Shell::Fish => clap_complete::Shell::Fish,
+        }
+    }
+}
+
+/// The script to source for `shell`: clap_complete's script for `cmd`,
+/// the CLI's clap definition, with the database hook in front of it
+pub fn completion_script(shell: Shell, cmd: &mut Command) -> String {
+    let mut generated = Vec::new();
+    clap_complete::generate(shell.generator(), cmd, "picman", &mut generated);
+    let generated = String::from_utf8_lossy(&generated).into_owned();
+    match shell {
+        Shell::Bash => format!("{}{}", generated, BASH_HOOK),
+        // Rename the generated entry point, so `_picman` (also what zsh
+        // autoloads from fpath) is the hook, and replace the dispatch at
+        // the end with one for the hook
+        Shell::Zsh => {
+            let generated = generated.replacen("\n_picman() {", "\n_picman_generated() {", 1);
+            let body = generated.rfind("\nif [ \"$funcstack[1]\" = \"_picman\" ]").map_or(generated.as_str(), |end| &generated[..end]);
+            format!("{}\n{}", body, ZSH_HOOK)
+        }
+        Shell::Fish => format!("{}{}", generated, FISH_HOOK),
+    }
+}
+
+const BASH_HOOK: &str = r#"
+_picman_library() {
+    local out
+    out=$(picman __complete "$COMP_CWORD" "${COMP_WORDS[@]}" 2>/dev/null)
+    if [[ -n "$out" ]]; then
+        local IFS=$'\n'
+        COMPREPLY=($(compgen -W "$out" -- "${COMP_WORDS[COMP_CWORD]}"))
+        [[ "${COMPREPLY[0]}" == */ ]] && compopt -o nospace
+    else
+        _picman "$@"
+    fi
+}
+complete -F _picman_library -o bashdefault -o default picman
+"#;
+
+const ZSH_HOOK: &str = r#"_picman() {
+    local -a candidates
+    candidates=("${(@f)$(picman __complete $((CURRENT - 1)) "${words[@]}" 2>/dev/null)}")
+    if [[ -n "${candidates[1]}" ]]; then
+        compadd -a candidates
+    else
+        _picman_generated "$@"
+    fi
+}
+
+if [ "$funcstack[1]" = "_picman" ]; then
+    _picman "$@"
+else
+    compdef _picman picman
+fi
+"#;
+
+const FISH_HOOK: &str = r#"
+function __picman_library_complete
+    set -l words (commandline -opc) (commandline -ct)
+    picman __complete (math (count $words) - 1) $words 2>/dev/null
+end
+complete -c picman -f -a '(__picman_library_complete)'
+"#;
+
+/// Tag names or library paths for `words[index]` of a `picman` command line
+/// (`words[0]` is `picman` itself), walking `cmd`, the CLI's clap
+/// definition, to the argument being typed. Empty for every other argument,
+/// which the generated script completes.
+pub fn complete(cmd: &mut Command, words: &[String], index: usize) -> Vec<String> {
+    cmd.build();
+    let current = words.get(index).map(String::as_str).unwrap_or("");
+
+    let mut cmd: &Command = cmd;
+    let mut positional = 0;
+    let mut pending: Option<&Arg> = None;
+    let mut library: Option<PathBuf> = None;
+    for word in words.iter().take(index).skip(1) {
+        if let Some(arg) = pending.take() {
+            note_library(arg, word, &mut library);
+            continue;
+        }
+        if let Some(long) = word.strip_prefix("--") {
+            if !long.contains('=') {
+                pending = cmd.get_arguments().find(|a| a.get_long() == Some(long)).filter(|a| takes_value(a));
+            }
+        } else if let Some(short) = word.strip_prefix('-').filter(|s| s.chars().count() == 1) {
+            let short = short.chars().next();
+            pending = cmd.get_arguments().find(|a| a.get_short() == short).filter(|a| takes_value(a));
+        } else if let Some(sub) = cmd.find_subcommand(word).filter(|_| positional == 0) {
+            cmd = sub;
+        } else if let Some(arg) = cmd.get_positionals().nth(positional) {
+            note_library(arg, word, &mut library);
+            if arg.get_num_args().is_none_or(|n| n.max_values() <= 1) {
+                positional += 1;
+            }
+        }
+    }
+    let library = library.unwrap_or_else(|| PathBuf::from("."));
+
+    let arg = match pending {
+        Some(arg) => Some(arg),
+        None if current.starts_with('-') => None,
+        None => cmd.get_positionals().nth(positional),
+    };
+    let mut values = arg.map(|arg| library_values(arg, current, &library)).unwrap_or_default();
+    values.retain(|v| v.starts_with(current));
+    values.sort();
+    values.dedup();
+    values
+}
+
+fn takes_value(arg: &Arg) -> bool {
+    arg.get_num_args().is_some_and(|n| n.takes_values())
+}
+
+fn note_library(arg: &Arg, value: &str, library: &mut Option<PathBuf>) {
+    if matches!(arg.get_id().as_str(), "library" | "path") {
+        *library = Some(PathBuf::from(value));
+    }
+}
+
+fn library_values(arg: &Arg, current: &str, library: &Path) -> Vec<String> {
+    match arg.get_id().as_str() {
+        "tag" | "add" | "remove" => open_db(library).and_then(|db| db.get_all_tags().ok()).unwrap_or_default(),
+        "file" | "files" | "dir" | "subdir" => open_db(library).map(|db| library_paths(&db, current)).unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+fn open_db(library: &Path) -> Option<Database> {
+    let db_path = library.join(DB_FILENAME);
+    if !db_path.exists() {
+        return None;
+    }
+    Database::open(&db_path).ok()
+}
+
+/// Subdirectories (with a trailing `/`) and files of the directory `current` is in
+fn library_paths(db: &Database, current: &str) -> Vec<String> {
+    let dir = match current.rsplit_once('/') {
+        Some((dir, _)) => LibraryPath::new(dir),
+        None => LibraryPath::root(),
+    };
+    // Typed with ./ or doubled slashes, candidates keep what was typed
+    let typed_dir = current.rsplit_once('/').map_or("", |(dir, _)| dir);
+    let shown = |path: &str| {
+        let name = LibraryPath::new(path).file_name().to_string();
+        if typed_dir.is_empty() { name } else { format!("{}/{}", typed_dir, name) }
+    };
+
+    let mut paths = Vec::new();
+    let Ok(all) = db.get_all_directories() else {
+        return paths;
+    };
+    for d in &all {
+        if !d.path.is_empty() && LibraryPath::new(&d.path).parent().as_ref() == Some(&dir) {
+            paths.push(format!("{}/", shown(&d.path)));
+        }
+    }
+    if let Some(d) = all.iter().find(|d| d.path == dir.as_str()) {
+        for file in db.get_files_in_directory(d.id).unwrap_or_default() {
+            paths.push(shown(&file.filename));
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn cli() -> Command {
+        Command::new("picman")
+            .arg(Arg::new("library").global(true))
+            .arg(Arg::new("fast").long("fast").global(true).num_args(0))
+            .subcommand(
+                Command::new("rate")
+                    .arg(Arg::new("library").required(true))
+                    .arg(Arg::new("file"))
+                    .arg(Arg::new("rating")),
+            )
+            .subcommand(
+                Command::new("tag")
+                    .arg(Arg::new("library").required(true))
+                    .arg(Arg::new("file").required(true))
+                    .arg(Arg::new("add").long("add").short('a')),
+            )
+            .subcommand(Command::new("list").arg(Arg::new("orientation").long("orientation").value_parser(["landscape", "portrait"])))
+    }
+
+    fn complete_line(line: &str) -> Vec<String> {
+        let mut words: Vec<String> = line.split(' ').map(String::from).collect();
+        if line.ends_with(' ') {
+            words.pop();
+            words.push(String::new());
+        }
+        let index = words.len() - 1;
+        complete(&mut cli(), &words, index)
+    }
+
+    #[test]
+    fn test_generated_script_carries_the_hook() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = completion_script(shell, &mut cli());
+            assert!(script.contains("rate"), "{:?}", shell);
+            assert!(script.contains("orientation"), "{:?}", shell);
+            assert!(script.contains("picman __complete"), "{:?}", shell);
+        }
+        let zsh = completion_script(Shell::Zsh, &mut cli());
+        assert!(zsh.contains("_picman_generated() {"));
+        assert_eq!(zsh.matches("compdef _picman picman").count(), 1);
+    }
+
+    #[test]
+    fn test_leaves_commands_flags_and_possible_values_to_the_script() {
+        assert!(complete_line("picman ra").is_empty());
+        assert!(complete_line("picman list --orientation p").is_empty());
+        assert!(complete_line("picman tag --").is_empty());
+        assert!(complete_line("picman ./ph").is_empty());
+    }
+
+    #[test]
+    fn test_completes_tags_and_paths_from_the_database() {
+        let temp = TempDir::new().unwrap();
+        let lib = temp.path().to_str().unwrap().to_string();
+        fs::create_dir_all(temp.path().join("2024/trip")).unwrap();
+        fs::write(temp.path().join("2024/a.jpg"), "a").unwrap();
+        fs::write(temp.path().join("2024/trip/b.jpg"), "b").unwrap();
+        fs::write(temp.path().join("top.jpg"), "t").unwrap();
+        crate::cli::run_init(temp.path()).unwrap();
+        let db = Database::open(&temp.path().join(DB_FILENAME)).unwrap();
+        let b = db.get_file_by_path("2024/trip/b.jpg").unwrap().unwrap();
+        db.add_file_tag(b.id, "sunset").unwrap();
+
+        assert_eq!(complete_line(&format!("picman rate {} ", lib)), ["2024/", "top.jpg"]);
+        assert_eq!(complete_line(&format!("picman rate {} 2024/", lib)), ["2024/a.jpg", "2024/trip/"]);
+        assert_eq!(complete_line(&format!("picman rate {} ./2024/trip/", lib)), ["./2024/trip/b.jpg"]);
+        assert_eq!(complete_line(&format!("picman tag {} 2024/a.jpg -a s", lib)), ["sunset"]);
+        // No library there: nothing to offer
+        assert!(complete_line("picman tag /nonexistent x.jpg --add ").is_empty());
+    }
+}
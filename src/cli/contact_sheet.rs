@@ -0,0 +1,533 @@
+//! `picman contact-sheet`: a grid of thumbnails with filenames and ratings,
+//! for proofing with clients or printing an index of a shoot.
+//!
+//! Pages are laid out once in points (1/72 inch, top-left origin) and then
+//! written either as a PDF, with the thumbnails embedded as JPEG streams and
+//! captions in the standard Helvetica font, or as one JPEG per page. JPEG
+//! pages have no font to draw filenames with, so they mark ratings with pips.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use image::{imageops, Rgb, RgbImage};
+use rayon::prelude::*;
+
+use super::list::{list_files, FileInfo, ListOptions};
+use crate::config::Config;
+use crate::db::Database;
+use crate::library_path::LibraryPath;
+use crate::thumbnails::{apply_exif_orientation, get_preview_path_for_file, open_image};
+
+const MARGIN: f32 = 36.0;
+const GAP: f32 = 10.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const CAPTION_SIZE: f32 = 7.0;
+/// Filename and rating lines under each thumbnail
+const CAPTION_HEIGHT: f32 = CAPTION_SIZE * 2.0 + 6.0;
+/// Thumbnail pixels per point: 216 dpi, sharp enough in print
+const PDF_PIXELS_PER_POINT: f32 = 3.0;
+/// JPEG page pixels per point: 144 dpi
+const JPEG_PIXELS_PER_POINT: f32 = 2.0;
+const JPEG_QUALITY: u8 = 85;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Paper {
+    #[default]
+    A4,
+    Letter,
+}
+
+impl Paper {
+    /// Portrait width and height in points
+    fn size(self) -> (f32, f32) {
+        match self {
+            Paper::A4 => (595.0, 842.0),
+            Paper::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+/// What goes on the sheet and where it's written
+#[derive(Debug)]
+pub struct ContactSheetOptions {
+    pub filter: ListOptions,
+    /// `.pdf`, or `.jpg` for one image per page (numbered if there are several)
+    pub output: PathBuf,
+    pub columns: u32,
+    pub paper: Paper,
+    /// Printed at the top of every page (the library's name by default)
+    pub title: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ContactSheetReport {
+    pub files: usize,
+    pub pages: usize,
+    /// Files shown as an empty box: no cached thumbnail and not decodable
+    pub missing: usize,
+    /// Files written: the PDF, or each JPEG page
+    pub outputs: Vec<PathBuf>,
+}
+
+/// One thumbnail slot, in points from the page's top-left corner
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Cell {
+    x: f32,
+    y: f32,
+    size: f32,
+}
+
+struct Layout {
+    width: f32,
+    height: f32,
+    cells: Vec<Cell>,
+}
+
+impl Layout {
+    fn new(paper: Paper, columns: u32) -> Self {
+        let (width, height) = paper.size();
+        let columns = columns.max(1);
+        let size = (width - 2.0 * MARGIN - GAP * (columns - 1) as f32) / columns as f32;
+        let row_height = size + CAPTION_HEIGHT + GAP;
+        let rows = (((height - 2.0 * MARGIN - HEADER_HEIGHT + GAP) / row_height).floor() as u32).max(1);
+
+        let mut cells = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                cells.push(Cell {
+                    x: MARGIN + column as f32 * (size + GAP),
+                    y: MARGIN + HEADER_HEIGHT + row as f32 * row_height,
+                    size,
+                });
+            }
+        }
+        Self { width, height, cells }
+    }
+
+    fn per_page(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+/// Lay out the files matching `options.filter` and write the sheet
+pub fn run_contact_sheet(library_path: &Path, options: &ContactSheetOptions) -> Result<ContactSheetReport> {
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+    let db = Database::open(&db_path)?;
+    let scale = Config::load(library_path)?.ratings.scale;
+
+    let mut files = list_files(&db, &options.filter)?;
+    if files.is_empty() {
+        anyhow::bail!("No files match");
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let title = options.title.clone().unwrap_or_else(|| {
+        let root = library_path.canonicalize().unwrap_or_else(|_| library_path.to_path_buf());
+        root.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+    });
+    let layout = Layout::new(options.paper, options.columns);
+    let pages: Vec<&[FileInfo]> = files.chunks(layout.per_page()).collect();
+
+    let extension = options
+        .output
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let sheet = Sheet { library_path, layout: &layout, title: &title, scale_max: scale.max() };
+    let (missing, outputs) = match extension.as_str() {
+        "pdf" => {
+            let (pdf, missing) = sheet.pdf(&pages)?;
+            std::fs::write(&options.output, pdf)
+                .with_context(|| format!("Could not write {}", options.output.display()))?;
+            (missing, vec![options.output.clone()])
+        }
+        "jpg" | "jpeg" => {
+            let mut missing = 0;
+            let mut outputs = Vec::new();
+            for (i, page) in pages.iter().enumerate() {
+                let (image, page_missing) = sheet.jpeg_page(page);
+                missing += page_missing;
+                let path = page_output(&options.output, i, pages.len());
+                let mut out = std::fs::File::create(&path)
+                    .with_context(|| format!("Could not write {}", path.display()))?;
+                let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY);
+                image.write_with_encoder(encoder)?;
+                outputs.push(path);
+            }
+            (missing, outputs)
+        }
+        _ => anyhow::bail!("Unsupported contact sheet format (use .pdf or .jpg): {}", options.output.display()),
+    };
+
+    Ok(ContactSheetReport { files: files.len(), pages: pages.len(), missing, outputs })
+}
+
+/// `sheet.jpg` for a single page, `sheet-1.jpg`, `sheet-2.jpg`... for more
+fn page_output(output: &Path, index: usize, pages: usize) -> PathBuf {
+    if pages == 1 {
+        return output.to_path_buf();
+    }
+    let stem = output.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = output.extension().map(|e| e.to_string_lossy().into_owned()).unwrap_or_default();
+    output.with_file_name(format!("{}-{}.{}", stem, index + 1, extension))
+}
+
+struct Sheet<'a> {
+    library_path: &'a Path,
+    layout: &'a Layout,
+    title: &'a str,
+    scale_max: i32,
+}
+
+impl Sheet<'_> {
+    /// Each file's thumbnail scaled to fit `pixels` square, in parallel
+    fn thumbnails(&self, files: &[FileInfo], pixels: u32) -> Vec<Option<RgbImage>> {
+        crate::workers::pool(0).install(|| {
+            files
+                .par_iter()
+                .map(|f| load_thumbnail(&LibraryPath::new(&f.path).absolute(self.library_path), pixels))
+                .collect()
+        })
+    }
+
+    fn pdf(&self, pages: &[&[FileInfo]]) -> Result<(Vec<u8>, usize)> {
+        let pixels = (self.layout.cells[0].size * PDF_PIXELS_PER_POINT) as u32;
+        let mut pdf = PdfWriter::new();
+        let catalog = pdf.reserve();
+        let page_tree = pdf.reserve();
+        let font = pdf.add(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec());
+
+        let mut page_ids = Vec::new();
+        let mut missing = 0;
+        for (number, files) in pages.iter().enumerate() {
+            let header = format!("Page {} of {}", number + 1, pages.len());
+            let mut content = String::new();
+            self.pdf_text(&mut content, MARGIN, MARGIN + 10.0, 10.0, self.title);
+            let header_x = self.layout.width - MARGIN - text_width(&header, 10.0);
+            self.pdf_text(&mut content, header_x, MARGIN + 10.0, 10.0, &header);
+
+            let mut images = Vec::new();
+            for ((file, cell), thumbnail) in files.iter().zip(&self.layout.cells).zip(self.thumbnails(files, pixels)) {
+                match thumbnail {
+                    Some(thumbnail) => {
+                        let (w, h) = fit(thumbnail.width(), thumbnail.height(), cell.size);
+                        let x = cell.x + (cell.size - w) / 2.0;
+                        let y = self.layout.height - cell.y - (cell.size + h) / 2.0;
+                        let name = format!("Im{}", images.len() + 1);
+                        let _ = writeln!(content, "q {:.2} 0 0 {:.2} {:.2} {:.2} cm /{} Do Q", w, h, x, y, name);
+                        images.push((name, pdf.add(pdf_image(&thumbnail)?)));
+                    }
+                    None => {
+                        missing += 1;
+                        let y = self.layout.height - cell.y - cell.size;
+                        let _ = writeln!(content, "0.9 g {:.2} {:.2} {:.2} {:.2} re f 0 g", cell.x, y, cell.size, cell.size);
+                    }
+                }
+                let name = truncate_to_width(LibraryPath::new(&file.path).file_name(), CAPTION_SIZE, cell.size);
+                let top = cell.y + cell.size + 3.0;
+                self.pdf_text(&mut content, cell.x, top + CAPTION_SIZE, CAPTION_SIZE, &name);
+                if let Some(rating) = file.rating {
+                    let stars = rating_text(rating, self.scale_max);
+                    self.pdf_text(&mut content, cell.x, top + 2.0 * CAPTION_SIZE + 2.0, CAPTION_SIZE, &stars);
+                }
+            }
+
+            let content = pdf.add(pdf_stream("", content.as_bytes()));
+            let xobjects: String = images.iter().map(|(name, id)| format!(" /{} {} 0 R", name, id)).collect();
+            let page = format!(
+                "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 {} 0 R >> /XObject <<{} >> >> /Contents {} 0 R >>",
+                page_tree, self.layout.width, self.layout.height, font, xobjects, content
+            );
+            page_ids.push(pdf.add(page.into_bytes()));
+        }
+
+        let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+        pdf.set(page_tree, format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), page_ids.len()).into_bytes());
+        pdf.set(catalog, format!("<< /Type /Catalog /Pages {} 0 R >>", page_tree).into_bytes());
+        Ok((pdf.finish(catalog), missing))
+    }
+
+    /// Text with its baseline `baseline` points below the top of the page
+    fn pdf_text(&self, content: &mut String, x: f32, baseline: f32, size: f32, text: &str) {
+        let _ = writeln!(
+            content,
+            "BT /F1 {} Tf {:.2} {:.2} Td ({}) Tj ET",
+            size,
+            x,
+            self.layout.height - baseline,
+            pdf_string(text)
+        );
+    }
+
+    fn jpeg_page(&self, files: &[FileInfo]) -> (RgbImage, usize) {
+        let px = |points: f32| (points * JPEG_PIXELS_PER_POINT).round() as u32;
+        let mut page = RgbImage::from_pixel(px(self.layout.width), px(self.layout.height), Rgb([255, 255, 255]));
+        let mut missing = 0;
+        let pixels = px(self.layout.cells[0].size);
+        for ((file, cell), thumbnail) in files.iter().zip(&self.layout.cells).zip(self.thumbnails(files, pixels)) {
+            let (x, y) = (px(cell.x), px(cell.y));
+            match thumbnail {
+                Some(thumbnail) => {
+                    let dx = (pixels - thumbnail.width()) / 2;
+                    let dy = (pixels - thumbnail.height()) / 2;
+                    imageops::replace(&mut page, &thumbnail, (x + dx) as i64, (y + dy) as i64);
+                }
+                None => {
+                    missing += 1;
+                    fill(&mut page, x, y, pixels, pixels, Rgb([230, 230, 230]));
+                }
+            }
+            // One pip per whole rating point, a half-height pip for a half
+            if let Some(rating) = file.rating {
+                let pip = px(CAPTION_SIZE * 0.8);
+                let top = y + pixels + px(4.0);
+                for i in 0..rating.ceil() as u32 {
+                    let height = if (i as f64) + 1.0 > rating { pip / 2 } else { pip };
+                    fill(&mut page, x + i * (pip + pip / 2), top + pip - height, pip, height, Rgb([60, 60, 60]));
+                }
+            }
+        }
+        (page, missing)
+    }
+}
+
+/// The file's cached thumbnail (or the original, if shown directly),
+/// scaled down to fit `pixels` square
+fn load_thumbnail(path: &Path, pixels: u32) -> Option<RgbImage> {
+    let (preview, is_thumbnail) = get_preview_path_for_file(path)?;
+    crate::workers::throttle_read(&preview);
+    let image = open_image(&preview).ok()?;
+    // Cached thumbnails are already upright
+    let image = if is_thumbnail { image } else { apply_exif_orientation(path, image) };
+    let image = if image.width() > pixels || image.height() > pixels {
+        image.resize(pixels, pixels, imageops::FilterType::Triangle)
+    } else {
+        image
+    };
+    Some(image.to_rgb8())
+}
+
+/// Size in points of a `width` x `height` image fit into a `box_size` square
+fn fit(width: u32, height: u32, box_size: f32) -> (f32, f32) {
+    let scale = box_size / width.max(height) as f32;
+    (width as f32 * scale, height as f32 * scale)
+}
+
+fn fill(image: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+/// Stars as `list` prints them, with the scale on a 10-point library
+fn rating_text(rating: f64, scale_max: i32) -> String {
+    let stars = format!("{}{}", "*".repeat(rating as usize), if rating.fract() >= 0.5 { "½" } else { "" });
+    if scale_max == 5 {
+        stars
+    } else {
+        format!("{} ({}/{})", stars, rating, scale_max)
+    }
+}
+
+/// Rough Helvetica width: about half the font size per character
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * size * 0.52
+}
+
+/// `text`, shortened in the middle with `...` to fit `width` points
+fn truncate_to_width(text: &str, size: f32, width: f32) -> String {
+    let max = (width / (size * 0.52)) as usize;
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= max || max < 5 {
+        return text.to_string();
+    }
+    let keep = max - 3;
+    let head: String = chars[..keep.div_ceil(2)].iter().collect();
+    let tail: String = chars[chars.len() - keep / 2..].iter().collect();
+    format!("{}...{}", head, tail)
+}
+
+/// A PDF literal string body in WinAnsi encoding: Latin-1 characters as
+/// octal escapes, anything else as `?`
+fn pdf_string(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(out, "\\{:03o}", c as u32);
+            }
+            _ => out.push('?'),
+        }
+    }
+    out
+}
+
+fn pdf_stream(dictionary: &str, data: &[u8]) -> Vec<u8> {
+    let mut out = format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+    out.extend_from_slice(data);
+    out.extend_from_slice(b"\nendstream");
+    out
+}
+
+/// An image XObject holding `image` as a JPEG
+fn pdf_image(image: &RgbImage) -> Result<Vec<u8>> {
+    let mut jpeg = Vec::new();
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY);
+    image.write_with_encoder(encoder)?;
+    let dictionary = format!(
+        "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode",
+        image.width(),
+        image.height()
+    );
+    Ok(pdf_stream(&dictionary, &jpeg))
+}
+
+/// Just enough of PDF: numbered objects and the cross-reference table
+struct PdfWriter {
+    objects: Vec<Option<Vec<u8>>>,
+}
+
+impl PdfWriter {
+    fn new() -> Self {
+        Self { objects: Vec::new() }
+    }
+
+    /// An object number to fill in later with [`PdfWriter::set`]
+    fn reserve(&mut self) -> usize {
+        self.objects.push(None);
+        self.objects.len()
+    }
+
+    fn set(&mut self, id: usize, body: Vec<u8>) {
+        self.objects[id - 1] = Some(body);
+    }
+
+    fn add(&mut self, body: Vec<u8>) -> usize {
+        self.objects.push(Some(body));
+        self.objects.len()
+    }
+
+    fn finish(self, root: usize) -> Vec<u8> {
+        let mut out = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::with_capacity(self.objects.len());
+        for (i, body) in self.objects.into_iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            out.extend_from_slice(&body.unwrap_or_else(|| b"null".to_vec()));
+            out.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = out.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1);
+        for offset in &offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            table,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            offsets.len() + 1,
+            root,
+            xref
+        );
+        out.extend_from_slice(table.as_bytes());
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn library() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir(temp.path().join("shoot")).unwrap();
+        for i in 0..7 {
+            RgbImage::from_pixel(40, 30, Rgb([200, 40, 40]))
+                .save(temp.path().join(format!("shoot/img_{}.png", i)))
+                .unwrap();
+        }
+        crate::cli::run_init(temp.path()).unwrap();
+        temp
+    }
+
+    #[test]
+    fn test_writes_a_paginated_pdf() {
+        let temp = library();
+        let output = temp.path().join("sheet.pdf");
+        let options = ContactSheetOptions {
+            filter: ListOptions::default(),
+            output: output.clone(),
+            columns: 12,
+            paper: Paper::A4,
+            title: Some("Proofs (draft)".to_string()),
+        };
+        crate::cli::run_rate(temp.path(), Path::new("shoot/img_0.png"), Some(4.5)).unwrap();
+
+        let report = run_contact_sheet(temp.path(), &options).unwrap();
+        assert_eq!((report.files, report.missing), (7, 0));
+        let pdf = fs::read(&output).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert_eq!(text.matches("/Subtype /Image").count(), 7);
+        assert_eq!(text.matches("/Type /Page ").count(), report.pages);
+        assert!(text.contains("(Proofs \\(draft\\)) Tj"));
+        assert!(text.contains("(****\\275) Tj"));
+
+        // The cross-reference table points at each object
+        let xref: usize = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        assert!(pdf[xref..].starts_with(b"xref"));
+        let table = String::from_utf8_lossy(&pdf[xref..]);
+        let catalog = table.lines().nth(3).unwrap();
+        let offset: usize = catalog[..10].parse().unwrap();
+        assert!(pdf[offset..].starts_with(b"1 0 obj\n<< /Type /Catalog"));
+    }
+
+    #[test]
+    fn test_writes_numbered_jpeg_pages() {
+        let temp = library();
+        let layout = Layout::new(Paper::Letter, 2);
+        assert!(layout.per_page() < 7);
+        let options = ContactSheetOptions {
+            filter: ListOptions::default(),
+            output: temp.path().join("sheet.jpg"),
+            columns: 2,
+            paper: Paper::Letter,
+            title: None,
+        };
+        let report = run_contact_sheet(temp.path(), &options).unwrap();
+        assert_eq!(report.pages, 7usize.div_ceil(layout.per_page()));
+        assert_eq!(report.outputs[0], temp.path().join("sheet-1.jpg"));
+        let page = image::open(&report.outputs[0]).unwrap();
+        assert_eq!((page.width(), page.height()), (1224, 1584));
+
+        let options = ContactSheetOptions { output: temp.path().join("sheet.gif"), ..options };
+        assert!(run_contact_sheet(temp.path(), &options).is_err());
+    }
+
+    #[test]
+    fn test_caption_helpers() {
+        assert_eq!(pdf_string("a(b)\\ é ✓"), "a\\(b\\)\\\\ \\351 ?");
+        assert_eq!(truncate_to_width("short.jpg", 7.0, 100.0), "short.jpg");
+        let long = truncate_to_width("a_very_long_filename_from_a_camera.jpg", 7.0, 60.0);
+        assert!(long.contains("...") && long.ends_with(".jpg") && long.chars().count() <= 16);
+        assert_eq!(rating_text(3.5, 5), "***½");
+        assert_eq!(rating_text(7.0, 10), "******* (7/10)");
+    }
+}
@@ -0,0 +1,356 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::db::{Database, File, LinkKind};
+use crate::process::unix_now;
+use crate::vfs::{mtime_secs, open_library_fs};
+
+use super::archive::sibling_temp_path;
+use super::dupes::{format_path, hidden_file_ids, kept_copy, path_in_subdir};
+
+/// Options for `picman dedupe --link`
+#[derive(Debug, Default)]
+pub struct DedupeOptions {
+    /// Only groups with a copy in this directory or below
+    pub subdir: Option<String>,
+    /// Only report what would be linked
+    pub dry_run: bool,
+}
+
+/// Outcome of a dedupe run
+#[derive(Debug, Default, PartialEq)]
+pub struct DedupeReport {
+    /// Copies replaced by a link to the kept file (or that would be, for a dry run)
+    pub linked: usize,
+    /// Of those, how many fell back to hardlinks
+    pub hardlinked: usize,
+    /// Space reclaimed
+    pub bytes: u64,
+    /// Copies already sharing content with the kept file
+    pub already_linked: usize,
+    /// Copies changed since hashing, different on disk, or archive stubs
+    pub skipped: usize,
+    /// Copies that could not be linked, e.g. on another filesystem
+    pub failed: usize,
+}
+
+/// Replace the extra copies in each exact duplicate group with links to one
+/// kept file, so every path stays valid but the content is stored once.
+/// Reflinks are used where the filesystem supports them (btrfs, xfs), since
+/// the copies stay independent; otherwise hardlinks. Files in hidden
+/// directories and pairs marked "keep both" are left alone, as by `picman
+/// dupes`, and the kept file is the one the `[duplicates] keep` policy picks.
+///
+/// # Arguments
+/// * `library_path` - Path to the library root
+/// * `options` - Which groups, and whether to only report
+pub fn run_dedupe_link(library_path: &Path, options: &DedupeOptions) -> Result<DedupeReport> {
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+    if open_library_fs(library_path)?.is_remote() {
+        bail!("Linking duplicates needs local originals; it is not available for remote libraries");
+    }
+    let db = Database::open(&db_path)?;
+    let config = Config::load(library_path)?;
+    let hidden_files = hidden_file_ids(&db, &config, false)?;
+    let ignored = db.get_ignored_duplicate_pairs()?;
+    let mut report = DedupeReport::default();
+
+    for mut group in db.find_duplicates_with_paths()? {
+        group.files.retain(|(f, _)| !hidden_files.contains(&f.id));
+        ignored.retain_duplicates(&mut group.files, |(f, _)| f.id);
+        if let Some(sub) = &options.subdir {
+            if !group.files.iter().any(|(_, p)| path_in_subdir(p, sub)) {
+                continue;
+            }
+        }
+
+        // Stubs share the original's hash but not its content
+        let mut copies = Vec::new();
+        for (file, dir_path) in group.files {
+            if db.is_archive_stub(file.id)? {
+                report.skipped += 1;
+            } else {
+                copies.push((file, dir_path));
+            }
+        }
+        if copies.len() < 2 {
+            continue;
+        }
+
+        // Keep the file earlier runs linked to, so groups don't split, and
+        // otherwise the one the keep policy picks
+        let mut keep_index = kept_copy(&copies, &config.duplicates.keep);
+        for (file, _) in &copies {
+            if let Some((source, _)) = db.get_link_source(file.id)? {
+                if let Some(j) = copies.iter().position(|(f, _)| f.id == source) {
+                    keep_index = j;
+                    break;
+                }
+            }
+        }
+        let mut copies: Vec<(File, String)> = copies
+            .into_iter()
+            .map(|(file, dir_path)| {
+                let relative = format_path(&dir_path, &file.filename);
+                (file, relative)
+            })
+            .collect();
+        let (keep, keep_relative) = copies.remove(keep_index);
+        let keep_path = library_path.join(&keep_relative);
+        if !unchanged_on_disk(&keep_path, &keep) {
+            warn!(path = %keep_relative, "changed since it was hashed; run 'picman sync --hash'");
+            report.skipped += copies.len();
+            continue;
+        }
+
+        for (copy, relative) in copies {
+            if ignored.contains(keep.id, copy.id) {
+                // Marked "keep both"; it stays a copy of its own
+                report.skipped += 1;
+                continue;
+            }
+            if db.get_link_source(copy.id)?.is_some_and(|(source, _)| source == keep.id) {
+                report.already_linked += 1;
+                continue;
+            }
+            let path = library_path.join(&relative);
+            if !unchanged_on_disk(&path, &copy) {
+                warn!(path = %relative, "changed since it was hashed; run 'picman sync --hash'");
+                report.skipped += 1;
+                continue;
+            }
+            if same_inode(&keep_path, &path)? {
+                // Linked outside picman; just record it
+                db.record_file_link(copy.id, keep.id, LinkKind::Hardlink, unix_now())?;
+                report.already_linked += 1;
+                continue;
+            }
+            if !same_contents(&keep_path, &path)? {
+                warn!(path = %relative, kept = %keep_relative, "hashes match but contents differ");
+                report.skipped += 1;
+                continue;
+            }
+
+            if !options.dry_run {
+                let kind = match link_copy(&keep_path, &path) {
+                    Ok(kind) => kind,
+                    Err(e) => {
+                        warn!(path = %relative, "could not link: {:#}", e);
+                        report.failed += 1;
+                        continue;
+                    }
+                };
+                db.record_file_link(copy.id, keep.id, kind, unix_now())?;
+                if kind == LinkKind::Hardlink {
+                    // The copy now shares the kept file's inode, and so its mtime
+                    db.set_file_mtime(copy.id, keep.mtime)?;
+                    report.hardlinked += 1;
+                }
+                info!(path = %relative, kept = %keep_relative, %kind, "linked duplicate");
+            }
+            report.linked += 1;
+            report.bytes += copy.size.max(0) as u64;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Whether the file on disk still matches what was hashed
+fn unchanged_on_disk(path: &Path, file: &File) -> bool {
+    fs::metadata(path)
+        .is_ok_and(|m| m.len() == file.size.max(0) as u64 && mtime_secs(&m) == file.mtime)
+}
+
+#[cfg(unix)]
+fn same_inode(a: &Path, b: &Path) -> Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+    let (a, b) = (fs::metadata(a)?, fs::metadata(b)?);
+    Ok(a.dev() == b.dev() && a.ino() == b.ino())
+}
+
+#[cfg(not(unix))]
+fn same_inode(_a: &Path, _b: &Path) -> Result<bool> {
+    Ok(false)
+}
+
+/// Byte-for-byte comparison, so a hash collision never costs a file
+fn same_contents(a: &Path, b: &Path) -> Result<bool> {
+    let mut a = BufReader::with_capacity(64 * 1024, fs::File::open(a)?);
+    let mut b = BufReader::with_capacity(64 * 1024, fs::File::open(b)?);
+    loop {
+        let (chunk_a, chunk_b) = (a.fill_buf()?, b.fill_buf()?);
+        if chunk_a.is_empty() || chunk_b.is_empty() {
+            return Ok(chunk_a.is_empty() && chunk_b.is_empty());
+        }
+        let n = chunk_a.len().min(chunk_b.len());
+        if chunk_a[..n] != chunk_b[..n] {
+            return Ok(false);
+        }
+        a.consume(n);
+        b.consume(n);
+    }
+}
+
+/// Replace `copy` with a reflink of `keep`, or a hardlink where reflinks
+/// aren't supported. The swap is a rename, so `copy` is never missing.
+fn link_copy(keep: &Path, copy: &Path) -> Result<LinkKind> {
+    let partial = sibling_temp_path(copy, "link");
+    let _ = fs::remove_file(&partial);
+
+    let kind = match reflink(keep, &partial, copy) {
+        Ok(()) => LinkKind::Reflink,
+        Err(_) => {
+            let _ = fs::remove_file(&partial);
+            fs::hard_link(keep, &partial)
+                .with_context(|| format!("Failed to link {} to {}", partial.display(), keep.display()))?;
+            LinkKind::Hardlink
+        }
+    };
+
+    if let Err(e) = fs::rename(&partial, copy) {
+        let _ = fs::remove_file(&partial);
+        return Err(e).with_context(|| format!("Failed to replace {}", copy.display()));
+    }
+    Ok(kind)
+}
+
+/// Clone `keep` into a new file at `dest`, keeping the permissions and
+/// mtime of the `copy` it will replace
+#[cfg(target_os = "linux")]
+fn reflink(keep: &Path, dest: &Path, copy: &Path) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let metadata = fs::metadata(copy)?;
+    let source = fs::File::open(keep)?;
+    let clone = fs::File::create_new(dest)?;
+    let ret = unsafe { libc::ioctl(clone.as_raw_fd(), libc::FICLONE, source.as_raw_fd()) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    clone.set_permissions(metadata.permissions())?;
+    clone.set_modified(metadata.modified()?)?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reflink(_keep: &Path, _dest: &Path, _copy: &Path) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::init::run_init;
+    use crate::cli::sync::run_sync;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, PathBuf) {
+        let temp = TempDir::new().unwrap();
+        let lib = temp.path().to_path_buf();
+        fs::create_dir_all(lib.join("2019")).unwrap();
+        fs::create_dir_all(lib.join("backup")).unwrap();
+        fs::write(lib.join("2019/a.jpg"), "same bytes").unwrap();
+        fs::write(lib.join("backup/a.jpg"), "same bytes").unwrap();
+        fs::write(lib.join("backup/a copy.jpg"), "same bytes").unwrap();
+        fs::write(lib.join("backup/b.jpg"), "different").unwrap();
+        run_init(&lib).unwrap();
+        run_sync(&lib, true, false, true).unwrap();
+        (temp, lib)
+    }
+
+    #[test]
+    fn test_dedupe_link_keeps_paths_and_records_group() {
+        let (_temp, lib) = setup();
+
+        let dry = run_dedupe_link(&lib, &DedupeOptions { dry_run: true, ..Default::default() }).unwrap();
+        assert_eq!((dry.linked, dry.bytes), (2, 20));
+
+        let report = run_dedupe_link(&lib, &DedupeOptions::default()).unwrap();
+        assert_eq!(report.linked, 2);
+        for name in ["2019/a.jpg", "backup/a.jpg", "backup/a copy.jpg"] {
+            assert_eq!(fs::read_to_string(lib.join(name)).unwrap(), "same bytes");
+        }
+        if report.hardlinked == 2 {
+            assert!(same_inode(&lib.join("2019/a.jpg"), &lib.join("backup/a.jpg")).unwrap());
+        }
+        assert!(!lib.join("backup/.a.jpg.picman-link").exists());
+
+        let db = Database::open(&lib.join(".picman.db")).unwrap();
+        let keep = db.get_file_by_path("2019/a.jpg").unwrap().unwrap();
+        assert_eq!(db.get_link_group(keep.id).unwrap().len(), 3);
+        drop(db);
+
+        // Links look unchanged to sync, and a second run has nothing to do
+        let stats = run_sync(&lib, false, false, true).unwrap();
+        assert_eq!(stats.files_modified, 0);
+        let again = run_dedupe_link(&lib, &DedupeOptions::default()).unwrap();
+        assert_eq!((again.linked, again.already_linked), (0, 2));
+    }
+
+    #[test]
+    fn test_dedupe_link_skips_changed_copies() {
+        let (_temp, lib) = setup();
+        // Same size and mtime as when hashed, different bytes
+        let copy = lib.join("backup/a copy.jpg");
+        let mtime = fs::metadata(&copy).unwrap().modified().unwrap();
+        fs::write(&copy, "SAME BYTES").unwrap();
+        fs::File::options().write(true).open(&copy).unwrap().set_modified(mtime).unwrap();
+
+        let subdir = DedupeOptions { subdir: Some("backup".to_string()), ..Default::default() };
+        let report = run_dedupe_link(&lib, &subdir).unwrap();
+        assert_eq!((report.linked, report.skipped), (1, 1));
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "SAME BYTES");
+
+        let none = DedupeOptions { subdir: Some("elsewhere".to_string()), ..Default::default() };
+        assert_eq!(run_dedupe_link(&lib, &none).unwrap(), DedupeReport::default());
+    }
+
+    #[test]
+    fn test_dedupe_link_keeps_copy_chosen_by_policy() {
+        let (_temp, lib) = setup();
+        fs::write(lib.join(".picman.toml"), "[duplicates]\nkeep = \"prefix:2019\"\n").unwrap();
+        // Last among equals would be kept without the policy
+        fs::create_dir_all(lib.join("zz")).unwrap();
+        fs::write(lib.join("zz/a.jpg"), "same bytes").unwrap();
+        run_sync(&lib, true, false, true).unwrap();
+
+        let report = run_dedupe_link(&lib, &DedupeOptions::default()).unwrap();
+        assert_eq!(report.linked, 3);
+        let db = Database::open(&lib.join(".picman.db")).unwrap();
+        let keep = db.get_file_by_path("2019/a.jpg").unwrap().unwrap();
+        assert_eq!(db.get_link_group(keep.id).unwrap()[0], keep.id);
+    }
+
+    #[test]
+    fn test_dedupe_link_leaves_hidden_and_ignored_copies() {
+        let (_temp, lib) = setup();
+        let db = Database::open(&lib.join(".picman.db")).unwrap();
+        let hidden = db.get_directory_by_path("2019").unwrap().unwrap();
+        db.hide_directory(hidden.id, 0).unwrap();
+        let a = db.get_file_by_path("backup/a.jpg").unwrap().unwrap();
+        let copy = db.get_file_by_path("backup/a copy.jpg").unwrap().unwrap();
+        db.ignore_duplicates(&[a.id, copy.id], 0).unwrap();
+        drop(db);
+
+        // 2019/a.jpg is hidden and the two left were marked "keep both"
+        assert_eq!(run_dedupe_link(&lib, &DedupeOptions::default()).unwrap(), DedupeReport::default());
+        for name in ["2019/a.jpg", "backup/a.jpg", "backup/a copy.jpg"] {
+            let db = Database::open(&lib.join(".picman.db")).unwrap();
+            let file = db.get_file_by_path(name).unwrap().unwrap();
+            assert_eq!(db.get_link_group(file.id).unwrap(), vec![file.id]);
+        }
+    }
+}
@@ -1,12 +1,16 @@
-use std::collections::HashSet;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use serde::Serialize;
 
-use crate::db::Database;
+use crate::config::Config;
+use crate::db::{Database, DuplicateScope, File};
+use crate::keep_policy::{choose_keep, KeepCandidate, KeepPolicy};
 use crate::perceptual_hash::{group_by_similarity, hamming_distance};
+use crate::library_path::LibraryPath;
 
+use super::dupes_report::{write_dupes_report, DupesReportOptions};
 use super::init::DB_FILENAME;
 
 /// JSON output format for duplicate groups
@@ -21,12 +25,14 @@ struct DupesOutput {
 struct ExactGroup {
     hash: String,
     size: i64,
+    reclaimable_bytes: i64,
     files: Vec<String>,
 }
 
 #[derive(Serialize)]
 struct SimilarGroup {
     max_distance: u32,
+    reclaimable_bytes: i64,
     files: Vec<SimilarFile>,
 }
 
@@ -44,14 +50,30 @@ struct Summary {
     exact_files: usize,
     similar_groups: usize,
     similar_files: usize,
+    reclaimable_bytes: i64,
+    by_directory: Vec<DirectorySavings>,
 }
 
-/// Run the dupes command: find exact and perceptual duplicates
+/// Reclaimable bytes in one top-level directory
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DirectorySavings {
+    /// First path component, or "(root)" for files at the library root
+    pub directory: String,
+    pub bytes: i64,
+}
+
+/// Run the dupes command: find exact and perceptual duplicates among the
+/// files in `scope`. Files in hidden directories are left out unless
+/// `include_hidden`. With `report`, the groups are also written to an HTML
+/// or CSV file for offline review.
 pub fn run_dupes(
     library_path: &Path,
     subdir: Option<&Path>,
+    scope: &DuplicateScope,
     json: bool,
     threshold: u32,
+    include_hidden: bool,
+    report: Option<&DupesReportOptions>,
 ) -> Result<()> {
     let library_path = library_path
         .canonicalize()
@@ -67,12 +89,22 @@ pub fn run_dupes(
 
     let db = Database::open(&db_path)?;
 
-    // Warn about unhashed files
-    let unhashed = db.get_files_needing_hash()?;
+    // Warn about unhashed files (or ones hashed with another algorithm,
+    // which are never compared with the rest). With the prefilter, files
+    // whose quick hash is unique have no duplicate and need no full hash;
+    // a shared quick hash alone never makes a duplicate.
+    let config = Config::load(&library_path)?;
+    let algorithm = config.hashing.algorithm;
+    let unhashed = if config.hashing.prefilter {
+        db.get_files_needing_hash_prefiltered(algorithm)?
+    } else {
+        db.get_files_needing_hash(algorithm)?
+    };
     if !unhashed.is_empty() {
         eprintln!(
-            "Warning: {} files have no content hash. Run 'picman sync --hash' first.",
-            unhashed.len()
+            "Warning: {} files have no {} content hash. Run 'picman sync --hash' first.",
+            unhashed.len(),
+            algorithm
         );
     }
 
@@ -85,8 +117,20 @@ pub fn run_dupes(
         );
     }
 
+    let hidden_files = hidden_file_ids(&db, &config, include_hidden)?;
+
+    // Pairs marked "keep both" with `--ignore` or in the web view
+    let ignored = db.get_ignored_duplicate_pairs()?;
+
     // === Exact duplicates ===
-    let exact_groups = db.find_duplicates_with_paths()?;
+    let mut exact_groups = db.find_duplicates_in(scope)?;
+    if !hidden_files.is_empty() || !ignored.is_empty() {
+        for group in &mut exact_groups {
+            group.files.retain(|(f, _)| !hidden_files.contains(&f.id));
+            ignored.retain_duplicates(&mut group.files, |(f, _)| f.id);
+        }
+        exact_groups.retain(|g| g.files.len() > 1);
+    }
     let exact_groups: Vec<_> = if let Some(sub) = subdir {
         let sub_str = sub.to_string_lossy();
         exact_groups
@@ -104,10 +148,11 @@ pub fn run_dupes(
         .collect();
 
     // === Perceptual duplicates ===
-    let all_hashes = db.get_all_perceptual_hashes()?;
+    let all_hashes = db.get_perceptual_hashes_in(scope)?;
     // Convert i64 → u64 for comparison
     let hashes_u64: Vec<(i64, u64)> = all_hashes
         .iter()
+        .filter(|(id, _)| !hidden_files.contains(id))
         .map(|(id, h)| (*id, *h as u64))
         .collect();
 
@@ -122,7 +167,6 @@ pub fn run_dupes(
         }
 
         let mut files = Vec::new();
-        let mut group_hashes: Vec<u64> = Vec::new();
         for &file_id in group_ids {
             if let Some((file, dir_path)) = db.get_file_with_path(file_id)? {
                 let full_path = format_path(&dir_path, &file.filename);
@@ -133,14 +177,16 @@ pub fn run_dupes(
                         continue;
                     }
                 }
-                if let Some(phash) = file.perceptual_hash {
-                    group_hashes.push(phash as u64);
-                }
                 files.push((file, dir_path));
             }
         }
+        ignored.retain_duplicates(&mut files, |(f, _)| f.id);
 
         if files.len() >= 2 {
+            let group_hashes: Vec<u64> = files
+                .iter()
+                .filter_map(|(f, _)| f.perceptual_hash.map(|h| h as u64))
+                .collect();
             // Calculate max distance within group
             let max_dist = max_pairwise_distance(&group_hashes);
             similar_groups.push(SimilarGroupInfo {
@@ -151,28 +197,140 @@ pub fn run_dupes(
     }
 
     if json {
-        print_json(&exact_groups, &similar_groups)?;
+        print_json(&exact_groups, &similar_groups, &config.duplicates.keep)?;
     } else {
-        print_human(&exact_groups, &similar_groups);
+        print_human(&exact_groups, &similar_groups, &config.duplicates.keep);
+    }
+
+    if let Some(report) = report {
+        let savings = write_dupes_report(&library_path, &exact_groups, &similar_groups, &config.duplicates.keep, report)?;
+        // Keep stdout clean JSON
+        eprintln!(
+            "Wrote report to {} ({} potential savings)",
+            report.path.display(),
+            format_size(savings)
+        );
     }
 
     Ok(())
 }
 
-struct SimilarGroupInfo {
-    max_distance: u32,
-    files: Vec<(crate::db::File, String)>,
+/// Mark the given files as not duplicates of each other ("keep both"), or
+/// with `ignore = false` report them again. Paths are relative to the
+/// library. Returns how many file pairs changed.
+pub fn run_dupes_ignore(library_path: &Path, files: &[PathBuf], ignore: bool) -> Result<usize> {
+    let db_path = library_path.join(DB_FILENAME);
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            db_path.display()
+        );
+    }
+    let db = Database::open(&db_path)?;
+
+    let mut ids = Vec::new();
+    for file in files {
+        let relative_path = file.to_string_lossy();
+        let Some(found) = db.get_file_by_path(&relative_path)? else {
+            anyhow::bail!("File not found in database: {}", relative_path);
+        };
+        ids.push(found.id);
+    }
+
+    if ignore {
+        db.ignore_duplicates(&ids, chrono::Utc::now().timestamp())
+    } else {
+        db.unignore_duplicates(&ids)
+    }
 }
 
-fn format_path(dir_path: &str, filename: &str) -> String {
-    if dir_path.is_empty() {
-        filename.to_string()
+/// Files duplicate searches leave out: those in hidden directories (unless
+/// `include_hidden`), and dotfiles left from when `[scan] dotfiles` was on,
+/// until the next sync removes them
+pub(super) fn hidden_file_ids(db: &Database, config: &Config, include_hidden: bool) -> Result<HashSet<i64>> {
+    let hidden_dirs = if include_hidden {
+        HashSet::new()
+    } else {
+        db.get_hidden_subtree_ids()?
+    };
+    let mut hidden_files: HashSet<i64> = if hidden_dirs.is_empty() {
+        HashSet::new()
     } else {
-        format!("{}/{}", dir_path, filename)
+        db.get_all_files()?
+            .into_iter()
+            .filter(|f| hidden_dirs.contains(&f.directory_id))
+            .map(|f| f.id)
+            .collect()
+    };
+    if !config.scan.dotfiles {
+        hidden_files.extend(db.get_dotfile_ids()?);
+    }
+    Ok(hidden_files)
+}
+
+pub(super) struct SimilarGroupInfo {
+    pub(super) max_distance: u32,
+    pub(super) files: Vec<(crate::db::File, String)>,
+}
+
+/// Index of the copy a cleanup would keep under the library's keep policy
+pub fn kept_copy(files: &[(File, String)], policy: &KeepPolicy) -> usize {
+    let paths: Vec<String> = files.iter().map(|(f, p)| format_path(p, &f.filename)).collect();
+    let candidates: Vec<KeepCandidate> = files
+        .iter()
+        .zip(&paths)
+        .map(|((f, _), path)| KeepCandidate {
+            path,
+            size: f.size,
+            mtime: f.mtime,
+            width: f.width,
+            height: f.height,
+        })
+        .collect();
+    choose_keep(&candidates, policy)
+}
+
+/// Bytes freed by removing every copy in a group but the kept one
+pub fn reclaimable_bytes(files: &[(File, String)], policy: &KeepPolicy) -> i64 {
+    let keep = kept_copy(files, policy);
+    files
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != keep)
+        .map(|(_, (f, _))| f.size)
+        .sum()
+}
+
+/// Reclaimable bytes across groups, credited to the top-level directory of
+/// each copy that would be removed, largest first
+pub fn reclaimable_by_directory<'a>(
+    groups: impl IntoIterator<Item = &'a [(File, String)]>,
+    policy: &KeepPolicy,
+) -> Vec<DirectorySavings> {
+    let mut by_directory: HashMap<String, i64> = HashMap::new();
+    for files in groups {
+        let keep = kept_copy(files, policy);
+        for (i, (file, dir_path)) in files.iter().enumerate() {
+            if i == keep {
+                continue;
+            }
+            let top = dir_path.split('/').next().filter(|s| !s.is_empty()).unwrap_or("(root)");
+            *by_directory.entry(top.to_string()).or_default() += file.size;
+        }
     }
+    let mut savings: Vec<DirectorySavings> = by_directory
+        .into_iter()
+        .map(|(directory, bytes)| DirectorySavings { directory, bytes })
+        .collect();
+    savings.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.directory.cmp(&b.directory)));
+    savings
 }
 
-fn path_in_subdir(path: &str, subdir: &str) -> bool {
+pub(super) fn format_path(dir_path: &str, filename: &str) -> String {
+    LibraryPath::file(dir_path, filename).into_string()
+}
+
+pub(super) fn path_in_subdir(path: &str, subdir: &str) -> bool {
     path == subdir || path.starts_with(&format!("{}/", subdir))
 }
 
@@ -189,7 +347,7 @@ fn max_pairwise_distance(hashes: &[u64]) -> u32 {
     max
 }
 
-fn format_size(bytes: i64) -> String {
+pub(super) fn format_size(bytes: i64) -> String {
     if bytes < 1024 {
         format!("{} B", bytes)
     } else if bytes < 1024 * 1024 {
@@ -204,6 +362,7 @@ fn format_size(bytes: i64) -> String {
 fn print_human(
     exact_groups: &[crate::db::DuplicateGroup],
     similar_groups: &[SimilarGroupInfo],
+    policy: &KeepPolicy,
 ) {
     if exact_groups.is_empty() && similar_groups.is_empty() {
         println!("No duplicates found.");
@@ -216,10 +375,11 @@ fn print_human(
         for (i, group) in exact_groups.iter().enumerate() {
             let size = group.files.first().map(|(f, _)| f.size).unwrap_or(0);
             println!(
-                "  Group {} ({} files, {} each):",
+                "  Group {} ({} files, {} each, {} reclaimable):",
                 i + 1,
                 group.files.len(),
-                format_size(size)
+                format_size(size),
+                format_size(reclaimable_bytes(&group.files, policy))
             );
             for (file, dir_path) in &group.files {
                 println!("    {}", format_path(dir_path, &file.filename));
@@ -233,10 +393,11 @@ fn print_human(
         println!("Visually similar (different compression/resolution):");
         for (i, group) in similar_groups.iter().enumerate() {
             println!(
-                "  Group {} ({} files, distance {}):",
+                "  Group {} ({} files, distance {}, {} reclaimable):",
                 i + 1,
                 group.files.len(),
-                group.max_distance
+                group.max_distance,
+                format_size(reclaimable_bytes(&group.files, policy))
             );
             for (file, dir_path) in &group.files {
                 let dims = match (file.width, file.height) {
@@ -264,17 +425,43 @@ fn print_human(
         similar_groups.len(),
         similar_files
     );
+
+    let by_directory = reclaimable_by_directory(all_groups(exact_groups, similar_groups), policy);
+    let total: i64 = by_directory.iter().map(|d| d.bytes).sum();
+    println!("Reclaimable by keeping one copy per group: {}", format_size(total));
+    for savings in by_directory.iter().take(MAX_SUMMARY_DIRECTORIES) {
+        println!("  {:>10}  {}", format_size(savings.bytes), savings.directory);
+    }
+    if by_directory.len() > MAX_SUMMARY_DIRECTORIES {
+        println!("  … and {} more directories", by_directory.len() - MAX_SUMMARY_DIRECTORIES);
+    }
+}
+
+/// Top-level directories listed in the human-readable savings breakdown
+const MAX_SUMMARY_DIRECTORIES: usize = 10;
+
+/// The files of every exact and similar group
+fn all_groups<'a>(
+    exact_groups: &'a [crate::db::DuplicateGroup],
+    similar_groups: &'a [SimilarGroupInfo],
+) -> impl Iterator<Item = &'a [(File, String)]> {
+    exact_groups
+        .iter()
+        .map(|g| g.files.as_slice())
+        .chain(similar_groups.iter().map(|g| g.files.as_slice()))
 }
 
 fn print_json(
     exact_groups: &[crate::db::DuplicateGroup],
     similar_groups: &[SimilarGroupInfo],
+    policy: &KeepPolicy,
 ) -> Result<()> {
     let exact: Vec<ExactGroup> = exact_groups
         .iter()
         .map(|g| ExactGroup {
             hash: g.hash.clone(),
             size: g.files.first().map(|(f, _)| f.size).unwrap_or(0),
+            reclaimable_bytes: reclaimable_bytes(&g.files, policy),
             files: g
                 .files
                 .iter()
@@ -287,6 +474,7 @@ fn print_json(
         .iter()
         .map(|g| SimilarGroup {
             max_distance: g.max_distance,
+            reclaimable_bytes: reclaimable_bytes(&g.files, policy),
             files: g
                 .files
                 .iter()
@@ -302,6 +490,7 @@ fn print_json(
 
     let exact_files: usize = exact.iter().map(|g| g.files.len()).sum();
     let similar_files: usize = similar.iter().map(|g| g.files.len()).sum();
+    let by_directory = reclaimable_by_directory(all_groups(exact_groups, similar_groups), policy);
 
     let output = DupesOutput {
         summary: Summary {
@@ -309,6 +498,8 @@ fn print_json(
             exact_files,
             similar_groups: similar.len(),
             similar_files,
+            reclaimable_bytes: by_directory.iter().map(|d| d.bytes).sum(),
+            by_directory,
         },
         exact,
         similar,
@@ -353,21 +544,21 @@ mod tests {
     fn test_run_dupes_finds_exact_duplicates() {
         let (_temp, root) = setup_library_with_dupes();
         // This should not error
-        let result = run_dupes(&root, None, false, 8);
+        let result = run_dupes(&root, None, &DuplicateScope::default(), false, 8, false, None);
         assert!(result.is_ok());
     }
 
     #[test]
     fn test_run_dupes_json_output() {
         let (_temp, root) = setup_library_with_dupes();
-        let result = run_dupes(&root, None, true, 8);
+        let result = run_dupes(&root, None, &DuplicateScope::default(), true, 8, false, None);
         assert!(result.is_ok());
     }
 
     #[test]
     fn test_run_dupes_no_db_errors() {
         let temp = TempDir::new().unwrap();
-        let result = run_dupes(temp.path(), None, false, 8);
+        let result = run_dupes(temp.path(), None, &DuplicateScope::default(), false, 8, false, None);
         assert!(result.is_err());
     }
 
@@ -375,10 +566,46 @@ mod tests {
     fn test_run_dupes_subdir_filter() {
         let (_temp, root) = setup_library_with_dupes();
         let subdir = std::path::Path::new("photos");
-        let result = run_dupes(&root, Some(subdir), false, 8);
+        let result = run_dupes(&root, Some(subdir), &DuplicateScope::default(), false, 8, false, None);
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_run_dupes_writes_report() {
+        let (temp, root) = setup_library_with_dupes();
+        let report = |name: &str| DupesReportOptions { path: temp.path().join(name), link_thumbnails: false };
+
+        run_dupes(&root, None, &DuplicateScope::default(), false, 8, false, Some(&report("dupes.csv"))).unwrap();
+        let csv = fs::read_to_string(temp.path().join("dupes.csv")).unwrap();
+        assert!(csv.contains("backup/beach_copy.jpg"));
+        assert!(csv.contains("photos/beach.jpg"));
+
+        run_dupes(&root, None, &DuplicateScope::default(), false, 8, false, Some(&report("dupes.html"))).unwrap();
+        let html = fs::read_to_string(temp.path().join("dupes.html")).unwrap();
+        assert!(html.contains("Potential savings"));
+
+        assert!(run_dupes(&root, None, &DuplicateScope::default(), false, 8, false, Some(&report("dupes.pdf"))).is_err());
+    }
+
+    #[test]
+    fn test_run_dupes_ignore_leaves_pair_out() {
+        let (temp, root) = setup_library_with_dupes();
+        let pair = [PathBuf::from("photos/beach.jpg"), PathBuf::from("backup/beach_copy.jpg")];
+        let report = DupesReportOptions { path: temp.path().join("dupes.csv"), link_thumbnails: false };
+
+        assert_eq!(run_dupes_ignore(&root, &pair, true).unwrap(), 1);
+        run_dupes(&root, None, &DuplicateScope::default(), false, 8, false, Some(&report)).unwrap();
+        let csv = fs::read_to_string(&report.path).unwrap();
+        assert!(!csv.contains("beach"));
+
+        assert_eq!(run_dupes_ignore(&root, &pair, false).unwrap(), 1);
+        run_dupes(&root, None, &DuplicateScope::default(), false, 8, false, Some(&report)).unwrap();
+        assert!(fs::read_to_string(&report.path).unwrap().contains("beach"));
+
+        let missing = [PathBuf::from("photos/beach.jpg"), PathBuf::from("nowhere.jpg")];
+        assert!(run_dupes_ignore(&root, &missing, true).is_err());
+    }
+
     #[test]
     fn test_format_size() {
         assert_eq!(format_size(500), "500 B");
@@ -395,6 +622,50 @@ mod tests {
         assert!(!path_in_subdir("photography", "photos"));
     }
 
+    #[test]
+    fn test_reclaimable_keeps_highest_resolution_copy() {
+        let file = |id: i64, dir: &str, size: i64, dims: Option<(i32, i32)>| {
+            let file = File {
+                id,
+                directory_id: 1,
+                filename: format!("{}.jpg", id),
+                size,
+                mtime: 0,
+                hash: None,
+                rating: None,
+                media_type: Some("image".to_string()),
+                width: dims.map(|d| d.0),
+                height: dims.map(|d| d.1),
+                perceptual_hash: None,
+                camera: None,
+                lens: None,
+            };
+            (file, dir.to_string())
+        };
+        let similar = vec![
+            file(1, "photos/2024", 500, Some((800, 600))),
+            file(2, "backup", 300, Some((4000, 3000))),
+            file(3, "", 200, None),
+        ];
+        let policy = KeepPolicy::default();
+        assert_eq!(kept_copy(&similar, &policy), 1);
+        assert_eq!(reclaimable_bytes(&similar, &policy), 700);
+
+        let exact = vec![file(4, "backup/old", 100, None), file(5, "photos", 100, None)];
+        assert_eq!(reclaimable_bytes(&exact, &policy), 100);
+
+        let by_directory = reclaimable_by_directory([similar.as_slice(), exact.as_slice()], &policy);
+        let pairs: Vec<(&str, i64)> = by_directory.iter().map(|d| (d.directory.as_str(), d.bytes)).collect();
+        assert_eq!(pairs, vec![("photos", 500), ("(root)", 200), ("backup", 100)]);
+
+        // Keeping what's under photos/ frees the backup copies instead
+        let policy = KeepPolicy::Prefix("photos".to_string());
+        assert_eq!(kept_copy(&similar, &policy), 0);
+        let by_directory = reclaimable_by_directory([similar.as_slice(), exact.as_slice()], &policy);
+        let pairs: Vec<(&str, i64)> = by_directory.iter().map(|d| (d.directory.as_str(), d.bytes)).collect();
+        assert_eq!(pairs, vec![("backup", 400), ("(root)", 200)]);
+    }
+
     #[test]
     fn test_max_pairwise_distance() {
         assert_eq!(max_pairwise_distance(&[]), 0);
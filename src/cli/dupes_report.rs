@@ -0,0 +1,314 @@
+use std::fmt::Write as _;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result};
+use base64::Engine;
+
+use crate::db::{DuplicateGroup, File};
+use crate::keep_policy::KeepPolicy;
+use crate::thumbnails::get_preview_path_for_file;
+
+use super::dupes::{format_path, format_size, kept_copy, reclaimable_bytes, SimilarGroupInfo};
+
+/// Where `picman dupes --report` writes, and how it shows thumbnails
+#[derive(Debug, Clone)]
+pub struct DupesReportOptions {
+    /// `.html` for a standalone page, `.csv` for one row per file
+    pub path: PathBuf,
+    /// Reference thumbnails by a path relative to the report instead of
+    /// embedding them, for a smaller file that only works on this machine
+    pub link_thumbnails: bool,
+}
+
+/// One duplicate group as it appears in the report
+struct ReportGroup {
+    /// "exact" or "similar"
+    kind: &'static str,
+    /// Exact groups: the shared content hash. Similar: max hamming distance
+    detail: String,
+    files: Vec<ReportFile>,
+    /// Bytes freed by removing every file but the kept one
+    savings: i64,
+}
+
+struct ReportFile {
+    path: String,
+    size: i64,
+    width: Option<i32>,
+    height: Option<i32>,
+    /// The copy a cleanup would keep (see [`kept_copy`])
+    keep: bool,
+}
+
+/// Write the duplicate groups to `options.path` in the format its extension
+/// names. Returns the total potential savings in bytes.
+pub(super) fn write_dupes_report(
+    library_path: &Path,
+    exact_groups: &[DuplicateGroup],
+    similar_groups: &[SimilarGroupInfo],
+    policy: &KeepPolicy,
+    options: &DupesReportOptions,
+) -> Result<i64> {
+    let groups = report_groups(exact_groups, similar_groups, policy);
+    let savings = groups.iter().map(|g| g.savings).sum();
+
+    let extension = options
+        .path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let contents = match extension.as_str() {
+        "html" | "htm" => render_html(library_path, &groups, options)?,
+        "csv" => render_csv(&groups),
+        _ => anyhow::bail!(
+            "Unsupported report format '{}': use a .html or .csv file",
+            options.path.display()
+        ),
+    };
+    std::fs::write(&options.path, contents)
+        .with_context(|| format!("Failed to write report to {}", options.path.display()))?;
+    Ok(savings)
+}
+
+fn report_groups(
+    exact_groups: &[DuplicateGroup],
+    similar_groups: &[SimilarGroupInfo],
+    policy: &KeepPolicy,
+) -> Vec<ReportGroup> {
+    let exact = exact_groups
+        .iter()
+        .map(|g| ("exact", g.hash.clone(), g.files.as_slice()));
+    let similar = similar_groups
+        .iter()
+        .map(|g| ("similar", format!("distance {}", g.max_distance), g.files.as_slice()));
+
+    exact
+        .chain(similar)
+        .map(|(kind, detail, files)| ReportGroup {
+            kind,
+            detail,
+            files: report_files(files, kept_copy(files, policy)),
+            savings: reclaimable_bytes(files, policy),
+        })
+        .collect()
+}
+
+fn report_files(files: &[(File, String)], keep: usize) -> Vec<ReportFile> {
+    files
+        .iter()
+        .enumerate()
+        .map(|(i, (file, dir_path))| ReportFile {
+            path: format_path(dir_path, &file.filename),
+            size: file.size,
+            width: file.width,
+            height: file.height,
+            keep: i == keep,
+        })
+        .collect()
+}
+
+fn render_csv(groups: &[ReportGroup]) -> String {
+    let mut out = String::from("group,kind,detail,path,size,width,height,keep,group_savings\n");
+    for (i, group) in groups.iter().enumerate() {
+        for file in &group.files {
+            let dimension = |d: Option<i32>| d.map(|d| d.to_string()).unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{}",
+                i + 1,
+                group.kind,
+                csv_field(&group.detail),
+                csv_field(&file.path),
+                file.size,
+                dimension(file.width),
+                dimension(file.height),
+                file.keep,
+                group.savings
+            );
+        }
+    }
+    out
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_html(library_path: &Path, groups: &[ReportGroup], options: &DupesReportOptions) -> Result<String> {
+    let report_dir = options
+        .path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let report_dir = std::path::absolute(report_dir)?;
+
+    let file_count: usize = groups.iter().map(|g| g.files.len()).sum();
+    let savings: i64 = groups.iter().map(|g| g.savings).sum();
+
+    let mut out = String::new();
+    out.push_str(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>picman duplicates</title>\n<style>\n\
+         body { font-family: sans-serif; margin: 2em; background: #1e1e1e; color: #ddd; }\n\
+         section { border-top: 1px solid #444; padding: 1em 0; }\n\
+         .files { display: flex; flex-wrap: wrap; gap: 1em; }\n\
+         figure { margin: 0; width: 220px; }\n\
+         figure.keep { outline: 2px solid #6a6; }\n\
+         img, .missing { width: 220px; height: 165px; object-fit: contain; background: #111; }\n\
+         .missing { display: flex; align-items: center; justify-content: center; color: #777; }\n\
+         figcaption { font-size: 0.8em; word-break: break-all; }\n\
+         .dim { color: #999; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+    let _ = writeln!(
+        out,
+        "<h1>Duplicates in {}</h1>\n<p>{} groups, {} files. Potential savings: <strong>{}</strong></p>",
+        html_escape(&library_path.display().to_string()),
+        groups.len(),
+        file_count,
+        format_size(savings)
+    );
+
+    for (i, group) in groups.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "<section>\n<h2>Group {} <span class=\"dim\">({}, {}, {} files, saves {})</span></h2>\n<div class=\"files\">",
+            i + 1,
+            group.kind,
+            html_escape(&group.detail),
+            group.files.len(),
+            format_size(group.savings)
+        );
+        for file in &group.files {
+            let image = match thumbnail_src(library_path, &report_dir, &file.path, options.link_thumbnails) {
+                Some(src) => format!("<img src=\"{}\" loading=\"lazy\" alt=\"\">", html_escape(&src)),
+                None => "<div class=\"missing\">no thumbnail</div>".to_string(),
+            };
+            let dims = match (file.width, file.height) {
+                (Some(w), Some(h)) => format!(", {}×{}", w, h),
+                _ => String::new(),
+            };
+            let _ = writeln!(
+                out,
+                "<figure{}>{}<figcaption>{}<br><span class=\"dim\">{}{}{}</span></figcaption></figure>",
+                if file.keep { " class=\"keep\"" } else { "" },
+                image,
+                html_escape(&file.path),
+                format_size(file.size),
+                dims,
+                if file.keep { ", keep" } else { "" }
+            );
+        }
+        out.push_str("</div>\n</section>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    Ok(out)
+}
+
+/// The `src` for a file's cached thumbnail: a data URI, or with `link` a path
+/// relative to the report. Originals are linked (never embedded) when there's
+/// no thumbnail and the browser can show them directly.
+fn thumbnail_src(library_path: &Path, report_dir: &Path, path: &str, link: bool) -> Option<String> {
+    let (preview, is_thumbnail) = get_preview_path_for_file(&library_path.join(path))?;
+    if is_thumbnail && !link {
+        let bytes = std::fs::read(&preview).ok()?;
+        return Some(format!(
+            "data:image/jpeg;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        ));
+    }
+    let relative = relative_path(&preview, report_dir);
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
+
+/// `target` relative to the directory `base`; both must be absolute
+fn relative_path(target: &Path, base: &Path) -> PathBuf {
+    let target: Vec<Component> = target.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    let common = target.iter().zip(&base).take_while(|(a, b)| a == b).count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &target[common..] {
+        relative.push(component);
+    }
+    relative
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(id: i64, filename: &str, size: i64, dims: Option<(i32, i32)>) -> (File, String) {
+        let file = File {
+            id,
+            directory_id: 1,
+            filename: filename.to_string(),
+            size,
+            mtime: 0,
+            hash: None,
+            rating: None,
+            media_type: Some("image".to_string()),
+            width: dims.map(|d| d.0),
+            height: dims.map(|d| d.1),
+            perceptual_hash: None,
+            camera: None,
+            lens: None,
+        };
+        (file, "trip".to_string())
+    }
+
+    #[test]
+    fn test_report_groups_savings_and_kept_copy() {
+        let exact = vec![DuplicateGroup {
+            hash: "abc".to_string(),
+            algorithm: "xxh3".to_string(),
+            files: vec![file(1, "a.jpg", 100, None), file(2, "b.jpg", 100, None), file(3, "c.jpg", 100, None)],
+        }];
+        let similar = vec![SimilarGroupInfo {
+            max_distance: 3,
+            files: vec![file(4, "small.jpg", 300, Some((800, 600))), file(5, "big.jpg", 200, Some((4000, 3000)))],
+        }];
+
+        let groups = report_groups(&exact, &similar, &KeepPolicy::default());
+        assert_eq!(groups[0].savings, 200);
+        assert_eq!(groups[0].files.iter().filter(|f| f.keep).count(), 1);
+        // The higher resolution copy is kept even though it's smaller on disk
+        assert_eq!(groups[1].savings, 300);
+        assert!(groups[1].files[1].keep && !groups[1].files[0].keep);
+
+        let csv = render_csv(&groups);
+        assert_eq!(csv.lines().count(), 6);
+        assert!(csv.contains("2,similar,distance 3,trip/big.jpg,200,4000,3000,true,300"));
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("plain.jpg"), "plain.jpg");
+        assert_eq!(csv_field("a,b.jpg"), "\"a,b.jpg\"");
+        assert_eq!(csv_field("say \"hi\".jpg"), "\"say \"\"hi\"\".jpg\"");
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path(Path::new("/home/me/.cache/t/x.jpg"), Path::new("/home/me/reports")),
+            PathBuf::from("../.cache/t/x.jpg")
+        );
+        assert_eq!(relative_path(Path::new("/lib/a.jpg"), Path::new("/lib")), PathBuf::from("a.jpg"));
+    }
+}
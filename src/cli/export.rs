@@ -0,0 +1,342 @@
+use std::collections::HashSet;
+use std::io::{BufWriter, Cursor};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use image::codecs::jpeg::JpegEncoder;
+use image::{ExtendedColorType, ImageEncoder};
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+
+use super::list::{list_files, ListOptions};
+use super::watermark::{Watermark, WatermarkOptions};
+use crate::db::Database;
+use crate::library_path::LibraryPath;
+use crate::thumbnails::{apply_exif_orientation, is_image_file, open_image};
+use crate::workers;
+
+/// Which EXIF metadata exported copies keep
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExportMetadata {
+    /// Everything the writer can carry over, location included
+    Keep,
+    /// Everything but the GPS block
+    #[default]
+    NoGps,
+    /// No EXIF at all
+    None,
+}
+
+/// Options for `picman export-resized`
+#[derive(Debug)]
+pub struct ExportOptions {
+    pub filter: ListOptions,
+    /// Only files at or below these library paths (files or directories);
+    /// empty for the whole library
+    pub selection: Vec<String>,
+    pub output: PathBuf,
+    /// Longest side in pixels; smaller images keep their size
+    pub max_edge: u32,
+    /// JPEG quality (1-100)
+    pub quality: u8,
+    pub metadata: ExportMetadata,
+    /// Put every file directly in `output` instead of mirroring directories
+    pub flatten: bool,
+    /// Blended into every copy
+    pub watermark: Option<WatermarkOptions>,
+}
+
+#[derive(Debug, Default)]
+pub struct ExportReport {
+    pub exported: usize,
+    /// Matching files that aren't images (videos, documents, ...)
+    pub skipped: usize,
+    /// Library path and reason for each image that could not be exported
+    pub failed: Vec<(String, String)>,
+    /// Total size of the written files
+    pub bytes: u64,
+}
+
+/// Write resized JPEG copies of the matching images to `options.output`
+pub fn run_export_resized(library_path: &Path, options: &ExportOptions) -> Result<ExportReport> {
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+    if options.max_edge == 0 {
+        anyhow::bail!("--max-edge must be at least 1");
+    }
+    if !(1..=100).contains(&options.quality) {
+        anyhow::bail!("--quality must be between 1 and 100");
+    }
+    let watermark = options.watermark.as_ref().map(Watermark::load).transpose()?;
+    let db = Database::open(&db_path)?;
+
+    let selection: Vec<LibraryPath> = options.selection.iter().map(|s| LibraryPath::new(s)).collect();
+    let mut paths: Vec<String> = list_files(&db, &options.filter)?
+        .into_iter()
+        .map(|f| f.path)
+        .filter(|path| selection.is_empty() || selection.iter().any(|s| is_within(path, s)))
+        .collect();
+    paths.sort();
+
+    let mut report = ExportReport::default();
+    let images: Vec<String> = paths
+        .into_iter()
+        .filter(|path| {
+            let image = is_image_file(Path::new(path));
+            if !image {
+                report.skipped += 1;
+            }
+            image
+        })
+        .collect();
+    let jobs = export_targets(&images, options.flatten);
+    if jobs.is_empty() {
+        return Ok(report);
+    }
+    std::fs::create_dir_all(&options.output)
+        .with_context(|| format!("Could not create {}", options.output.display()))?;
+
+    let progress = ProgressBar::new(jobs.len() as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("    {bar:40.cyan/blue} {pos}/{len} ({percent}%) | ETA {eta} | {msg}")
+            .unwrap()
+            .progress_chars("██░"),
+    );
+
+    let exported = AtomicUsize::new(0);
+    let bytes = AtomicU64::new(0);
+    let failed = Mutex::new(Vec::new());
+    workers::pool(0).install(|| {
+        jobs.par_iter().for_each(|(path, target)| {
+            let source = LibraryPath::new(path).absolute(library_path);
+            match export_image(&source, &options.output.join(target), options, watermark.as_ref()) {
+                Ok(size) => {
+                    exported.fetch_add(1, Ordering::Relaxed);
+                    bytes.fetch_add(size, Ordering::Relaxed);
+                }
+                Err(e) => failed.lock().unwrap().push((path.clone(), e.to_string())),
+            }
+            progress.set_message(path.clone());
+            progress.inc(1);
+        });
+    });
+    progress.finish_and_clear();
+
+    report.exported = exported.into_inner();
+    report.bytes = bytes.into_inner();
+    report.failed = failed.into_inner().unwrap();
+    report.failed.sort();
+    Ok(report)
+}
+
+/// Whether the library path `path` is `selected` or inside it
+fn is_within(path: &str, selected: &LibraryPath) -> bool {
+    selected.is_root()
+        || path == selected.as_str()
+        || path.strip_prefix(selected.as_str()).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Where each image goes, relative to the output directory: the same
+/// directories with a `.jpg` name, or all side by side with `flatten`.
+/// Names that would collide get `-2`, `-3`... in path order.
+fn export_targets(paths: &[String], flatten: bool) -> Vec<(String, PathBuf)> {
+    let mut taken = HashSet::new();
+    let mut jobs = Vec::with_capacity(paths.len());
+    for path in paths {
+        let library_path = LibraryPath::new(path);
+        let (dir, name) = library_path.split();
+        let stem = Path::new(name).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let dir = if flatten { "" } else { dir };
+
+        let mut target = LibraryPath::file(dir, &format!("{}.jpg", stem));
+        let mut n = 2;
+        while !taken.insert(target.as_str().to_lowercase()) {
+            target = LibraryPath::file(dir, &format!("{}-{}.jpg", stem, n));
+            n += 1;
+        }
+        jobs.push((path.clone(), PathBuf::from(target)));
+    }
+    jobs
+}
+
+/// Decode, orient, shrink and watermark `source`, then write it to `target`
+/// as a JPEG. Returns the written size.
+fn export_image(source: &Path, target: &Path, options: &ExportOptions, watermark: Option<&Watermark>) -> Result<u64> {
+    workers::throttle_read(source);
+    let image = open_image(source).map_err(|e| anyhow::anyhow!(e.reason))?;
+    let image = apply_exif_orientation(source, image);
+    let image = if image.width().max(image.height()) > options.max_edge {
+        image.resize(options.max_edge, options.max_edge, image::imageops::FilterType::Lanczos3)
+    } else {
+        image
+    };
+    let mut rgb = image.to_rgb8();
+    if let Some(watermark) = watermark {
+        watermark.apply(&mut rgb);
+    }
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::File::create(target).with_context(|| format!("Could not write {}", target.display()))?;
+    let mut encoder = JpegEncoder::new_with_quality(BufWriter::new(file), options.quality);
+    if let Some(exif) = exported_exif(source, options.metadata) {
+        encoder.set_exif_metadata(exif)?;
+    }
+    encoder.write_image(rgb.as_raw(), rgb.width(), rgb.height(), ExtendedColorType::Rgb8)?;
+    Ok(std::fs::metadata(target)?.len())
+}
+
+/// The source's EXIF to embed in the copy. Orientation is dropped because
+/// the pixels are already upright, the pixel dimensions because they changed,
+/// and the maker note because its internal offsets don't survive rewriting.
+fn exported_exif(source: &Path, metadata: ExportMetadata) -> Option<Vec<u8>> {
+    if metadata == ExportMetadata::None {
+        return None;
+    }
+    let file = std::fs::File::open(source).ok()?;
+    let exif = exif::Reader::new().read_from_container(&mut std::io::BufReader::new(file)).ok()?;
+
+    let mut writer = exif::experimental::Writer::new();
+    let mut any = false;
+    for field in exif.fields() {
+        let dropped = field.ifd_num != exif::In::PRIMARY
+            || matches!(
+                field.tag,
+                exif::Tag::Orientation | exif::Tag::PixelXDimension | exif::Tag::PixelYDimension | exif::Tag::MakerNote
+            )
+            || (metadata == ExportMetadata::NoGps && field.tag.context() == exif::Context::Gps);
+        if !dropped {
+            writer.push_field(field);
+            any = true;
+        }
+    }
+    if !any {
+        return None;
+    }
+    let mut out = Cursor::new(Vec::new());
+    writer.write(&mut out, exif.little_endian()).ok()?;
+    Some(out.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exif::{Field, In, Tag, Value};
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// A 300x200 JPEG with a camera make and a GPS latitude
+    fn write_photo(path: &Path) {
+        let make = Field { tag: Tag::Make, ifd_num: In::PRIMARY, value: Value::Ascii(vec![b"Acme".to_vec()]) };
+        let gps = Field { tag: Tag::GPSLatitudeRef, ifd_num: In::PRIMARY, value: Value::Ascii(vec![b"N".to_vec()]) };
+        let mut writer = exif::experimental::Writer::new();
+        writer.push_field(&make);
+        writer.push_field(&gps);
+        let mut exif = Cursor::new(Vec::new());
+        writer.write(&mut exif, false).unwrap();
+
+        let image = image::RgbImage::from_pixel(300, 200, image::Rgb([10, 120, 200]));
+        let mut encoder = JpegEncoder::new_with_quality(fs::File::create(path).unwrap(), 90);
+        encoder.set_exif_metadata(exif.into_inner()).unwrap();
+        encoder.write_image(image.as_raw(), 300, 200, ExtendedColorType::Rgb8).unwrap();
+    }
+
+    fn read_exif(path: &Path) -> Option<exif::Exif> {
+        let file = fs::File::open(path).unwrap();
+        exif::Reader::new().read_from_container(&mut std::io::BufReader::new(file)).ok()
+    }
+
+    fn options(output: PathBuf) -> ExportOptions {
+        ExportOptions {
+            filter: ListOptions::default(),
+            selection: Vec::new(),
+            output,
+            max_edge: 150,
+            quality: 85,
+            metadata: ExportMetadata::NoGps,
+            flatten: false,
+            watermark: None,
+        }
+    }
+
+    #[test]
+    fn test_exports_resized_copies_without_gps() {
+        let temp = TempDir::new().unwrap();
+        let lib = temp.path().join("lib");
+        fs::create_dir_all(lib.join("trip")).unwrap();
+        write_photo(&lib.join("trip/a.jpg"));
+        fs::write(lib.join("trip/clip.mp4"), "not really").unwrap();
+        crate::cli::run_init(&lib).unwrap();
+
+        let out = temp.path().join("out");
+        let report = run_export_resized(&lib, &options(out.clone())).unwrap();
+        assert_eq!((report.exported, report.skipped), (1, 1));
+        let copy = image::open(out.join("trip/a.jpg")).unwrap();
+        assert_eq!((copy.width(), copy.height()), (150, 100));
+
+        let exif = read_exif(&out.join("trip/a.jpg")).unwrap();
+        assert!(exif.get_field(Tag::Make, In::PRIMARY).is_some());
+        assert!(exif.get_field(Tag::GPSLatitudeRef, In::PRIMARY).is_none());
+
+        let keep = ExportOptions { metadata: ExportMetadata::Keep, ..options(out.clone()) };
+        run_export_resized(&lib, &keep).unwrap();
+        assert!(read_exif(&out.join("trip/a.jpg")).unwrap().get_field(Tag::GPSLatitudeRef, In::PRIMARY).is_some());
+
+        let none = ExportOptions { metadata: ExportMetadata::None, ..options(out.clone()) };
+        run_export_resized(&lib, &none).unwrap();
+        assert!(read_exif(&out.join("trip/a.jpg")).is_none());
+    }
+
+    #[test]
+    fn test_watermarks_only_the_copies() {
+        let temp = TempDir::new().unwrap();
+        let lib = temp.path().join("lib");
+        fs::create_dir_all(&lib).unwrap();
+        write_photo(&lib.join("a.jpg"));
+        crate::cli::run_init(&lib).unwrap();
+        let logo = temp.path().join("logo.png");
+        image::RgbaImage::from_pixel(8, 8, image::Rgba([255, 255, 0, 255])).save(&logo).unwrap();
+
+        let out = temp.path().join("out");
+        let watermark = WatermarkOptions {
+            source: crate::cli::WatermarkSource::Image(logo),
+            position: crate::cli::WatermarkPosition::TopLeft,
+            opacity: 1.0,
+            scale: 0.2,
+        };
+        let options = ExportOptions { watermark: Some(watermark), ..options(out.clone()) };
+        run_export_resized(&lib, &options).unwrap();
+
+        let copy = image::open(out.join("a.jpg")).unwrap().to_rgb8();
+        let marked = copy.get_pixel(10, 10);
+        assert!(marked[0] > 200 && marked[1] > 200 && marked[2] < 80, "{:?}", marked);
+        let plain = copy.get_pixel(140, 90);
+        assert!(plain[2] > 150, "{:?}", plain);
+        let original = image::open(lib.join("a.jpg")).unwrap().to_rgb8();
+        assert!(original.get_pixel(10, 10)[2] > 150);
+    }
+
+    #[test]
+    fn test_flattening_and_selection() {
+        let jobs = export_targets(&["a/x.png".to_string(), "b/x.jpg".to_string(), "b/x.JPG".to_string()], true);
+        let targets: Vec<PathBuf> = jobs.into_iter().map(|(_, t)| t).collect();
+        assert_eq!(targets, [PathBuf::from("x.jpg"), PathBuf::from("x-2.jpg"), PathBuf::from("x-3.jpg")]);
+        let jobs = export_targets(&["a/x.png".to_string(), "b/x.jpg".to_string()], false);
+        assert_eq!(jobs[1].1, PathBuf::from("b/x.jpg"));
+
+        assert!(is_within("trip/a.jpg", &LibraryPath::new("trip/")));
+        assert!(is_within("trip/a.jpg", &LibraryPath::new("trip/a.jpg")));
+        assert!(!is_within("trips/a.jpg", &LibraryPath::new("trip")));
+        assert!(is_within("a.jpg", &LibraryPath::root()));
+    }
+}
@@ -0,0 +1,75 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::library_path::LibraryPath;
+
+/// Hide a directory (with everything below it) from the TUI tree, `list`
+/// and `dupes`, or show it again with `hidden = false`. The files stay
+/// indexed. Returns false if it already was that way.
+pub fn run_hide(library_path: &Path, dir_path: &Path, hidden: bool) -> Result<bool> {
+    let db = open_library_db(library_path)?;
+
+    let relative_path = LibraryPath::from_relative(dir_path);
+    let relative_path = relative_path.as_str();
+    let Some(dir) = db.get_directory_by_path(relative_path)? else {
+        anyhow::bail!("Directory not found in database: {}", relative_path);
+    };
+
+    if hidden {
+        let was_hidden = db.get_hidden_directory_ids()?.contains(&dir.id);
+        db.hide_directory(dir.id, chrono::Utc::now().timestamp())?;
+        Ok(!was_hidden)
+    } else {
+        db.unhide_directory(dir.id)
+    }
+}
+
+/// Paths of the directories marked hidden, sorted
+pub fn list_hidden(library_path: &Path) -> Result<Vec<String>> {
+    let db = open_library_db(library_path)?;
+    let hidden = db.get_hidden_directory_ids()?;
+    let mut paths: Vec<String> = db
+        .get_all_directories()?
+        .into_iter()
+        .filter(|d| hidden.contains(&d.id))
+        .map(|d| d.path)
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+fn open_library_db(library_path: &Path) -> Result<Database> {
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+    Database::open(&db_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hide_unhide_and_list() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("exports/2019")).unwrap();
+        fs::write(tmp.path().join("exports/2019/a.jpg"), "data").unwrap();
+        crate::cli::run_init(tmp.path()).unwrap();
+
+        assert!(run_hide(tmp.path(), Path::new("exports/"), true).unwrap());
+        assert!(!run_hide(tmp.path(), Path::new("exports"), true).unwrap());
+        assert_eq!(list_hidden(tmp.path()).unwrap(), vec!["exports"]);
+
+        assert!(run_hide(tmp.path(), Path::new("exports"), false).unwrap());
+        assert!(list_hidden(tmp.path()).unwrap().is_empty());
+        assert!(run_hide(tmp.path(), Path::new("nowhere"), true).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::embedded_rating::read_embedded_rating;
+use crate::rating::RatingScale;
+use crate::vfs::open_library_fs;
+
+use super::init::DB_FILENAME;
+
+const IMPORT_BATCH_SIZE: usize = 1000;
+
+/// Outcome of importing ratings from file metadata
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportRatingsReport {
+    /// Unrated images whose metadata was read
+    pub checked: usize,
+    /// Files rated from their metadata (paired siblings included)
+    pub imported: usize,
+}
+
+/// Rate unrated images from the XMP or EXIF star rating other tools stored
+/// in them or their sidecar. Ratings already in the database are never
+/// changed; stars are converted to the library's rating scale.
+pub fn run_import_ratings(library_path: &Path) -> Result<ImportRatingsReport> {
+    let library_path = library_path
+        .canonicalize()
+        .with_context(|| format!("Library path does not exist: {}", library_path.display()))?;
+
+    let db_path = library_path.join(DB_FILENAME);
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            db_path.display()
+        );
+    }
+    if open_library_fs(&library_path)?.is_remote() {
+        anyhow::bail!("Importing ratings reads every original; it is not available for remote libraries");
+    }
+
+    let scale = Config::load(&library_path)?.ratings.scale;
+    let db = Database::open(&db_path)?;
+    let files = db.get_unrated_images()?;
+
+    let progress = ProgressBar::new(files.len() as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("    {bar:40.cyan/blue} {pos}/{len} ({percent}%) | {elapsed_precise} | {msg}")
+            .unwrap()
+            .progress_chars("██░"),
+    );
+    progress.set_message("reading ratings");
+
+    let mut report = ImportRatingsReport { checked: files.len(), imported: 0 };
+
+    // Batches keep an interrupted run's work; the next run skips what's rated
+    for batch in files.chunks(IMPORT_BATCH_SIZE) {
+        let ratings: Vec<_> = batch
+            .par_iter()
+            .filter_map(|file| Some((file.id, read_embedded_rating(&library_path.join(&file.path))?)))
+            .collect();
+
+        db.begin_transaction()?;
+        for (id, stars) in ratings {
+            let rating = RatingScale::Five.convert(stars as f64, scale);
+            report.imported += db.set_rating_if_unrated(id, rating)?;
+        }
+        db.commit()?;
+        progress.inc(batch.len() as u64);
+    }
+
+    progress.finish_with_message(format!("{} files rated", report.imported));
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::run_init;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_import_ratings_fills_only_unrated_files() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("trip")).unwrap();
+        let xmp = |rating: u8| {
+            format!(r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:Description xmp:Rating="{rating}"/></x:xmpmeta>"#)
+        };
+        fs::write(root.join("trip/a.jpg"), xmp(4)).unwrap();
+        fs::write(root.join("trip/b.jpg"), xmp(5)).unwrap();
+        fs::write(root.join("trip/c.jpg"), "no metadata").unwrap();
+        fs::write(root.join(".picman.toml"), "[ratings]\nscale = 10\n").unwrap();
+        run_init(root).unwrap();
+
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let id = |name: &str| db.get_file_by_path(name).unwrap().unwrap().id;
+        db.set_file_rating(id("trip/b.jpg"), Some(3.0)).unwrap();
+
+        let report = run_import_ratings(root).unwrap();
+        assert_eq!(report, ImportRatingsReport { checked: 2, imported: 1 });
+
+        let rating = |name: &str| db.get_file_by_path(name).unwrap().unwrap().rating;
+        assert_eq!(rating("trip/a.jpg"), Some(8.0), "4 stars on a 10-point library");
+        assert_eq!(rating("trip/b.jpg"), Some(3.0), "existing rating must be kept");
+        assert_eq!(rating("trip/c.jpg"), None);
+    }
+}
@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 use std::path::Path;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use tracing::{debug, info, instrument};
 
+use crate::config::{config_path, Config};
 use crate::db::Database;
 use crate::scanner::{MediaType, Scanner, read_dimensions};
+use crate::vfs::{open_library_fs, SshTarget};
 
 /// Database filename stored in the library root
 pub const DB_FILENAME: &str = ".picman.db";
@@ -20,12 +22,112 @@ pub fn run_init(library_path: &Path) -> Result<InitStats> {
     let db = Database::open(&db_path)
         .with_context(|| format!("Failed to open database at {}", db_path.display()))?;
 
-    let scanner = Scanner::new(library_path);
+    let fs = open_library_fs(&library_path)?;
+    let scanner = Scanner::with_fs(library_path, fs);
     let stats = populate_database(&db, &scanner)?;
 
     Ok(stats)
 }
 
+/// Point a library at a remote server. `library_path` becomes the local
+/// mirror: it is created if needed and gets a `[remote]` section in its
+/// config. Run before `run_init`; repeating it with the same URL is a no-op.
+pub fn configure_remote(library_path: &Path, url: &str) -> Result<()> {
+    SshTarget::parse(url)?;
+    std::fs::create_dir_all(library_path)
+        .with_context(|| format!("Failed to create {}", library_path.display()))?;
+
+    match Config::load(library_path)?.remote {
+        Some(remote) if remote.url == url => return Ok(()),
+        Some(remote) => bail!("Library already points at {}", remote.url),
+        None => {}
+    }
+
+    append_config_section(
+        library_path,
+        "remote",
+        &format!("url = {}\n", toml::Value::String(url.to_string())),
+    )
+}
+
+/// Add `[scan] exclude` patterns to a library's config. Run before
+/// `run_init` so the first scan already skips them.
+pub fn configure_excludes(library_path: &Path, patterns: &[String]) -> Result<()> {
+    if patterns.is_empty() {
+        return Ok(());
+    }
+    let quoted: Vec<String> = patterns
+        .iter()
+        .map(|p| toml::Value::String(p.clone()).to_string())
+        .collect();
+    append_config_section(library_path, "scan", &format!("exclude = [{}]\n", quoted.join(", ")))
+}
+
+/// Turn on `[scan] follow_symlinks` in a library's config, adding it to an
+/// existing `[scan]` section. It stays on for later syncs, which would
+/// otherwise drop the files found through links.
+pub fn configure_follow_symlinks(library_path: &Path) -> Result<()> {
+    if Config::load(library_path)?.scan.follow_symlinks {
+        return Ok(());
+    }
+    set_scan_option(library_path, "follow_symlinks", "true")
+}
+
+/// Set `[scan] dotfiles` in a library's config. Returns whether it changed,
+/// in which case the next sync has to be a full one: an incremental sync
+/// only looks into directories whose mtime moved.
+pub fn configure_dotfiles(library_path: &Path, dotfiles: bool) -> Result<bool> {
+    if Config::load(library_path)?.scan.dotfiles == dotfiles {
+        return Ok(false);
+    }
+    set_scan_option(library_path, "dotfiles", &dotfiles.to_string())?;
+    Ok(true)
+}
+
+/// Set `key = value` in the config's `[scan]` section, replacing an earlier
+/// value and adding the section if there is none
+fn set_scan_option(library_path: &Path, key: &str, value: &str) -> Result<()> {
+    let path = config_path(library_path);
+    let contents = std::fs::read_to_string(&path).unwrap_or_default();
+    let mut lines: Vec<&str> = contents.lines().collect();
+    let Some(header) = lines.iter().position(|line| line.trim() == "[scan]") else {
+        return append_config_section(library_path, "scan", &format!("{} = {}\n", key, value));
+    };
+    let end = lines[header + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map_or(lines.len(), |i| header + 1 + i);
+    let line = format!("{} = {}", key, value);
+    let existing = (header + 1..end).find(|&i| lines[i].split('=').next().map(str::trim) == Some(key));
+    match existing {
+        Some(i) => lines[i] = &line,
+        None => lines.insert(header + 1, &line),
+    }
+    std::fs::write(&path, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Append a `[section]` to the config file, keeping what's already there
+fn append_config_section(library_path: &Path, section: &str, body: &str) -> Result<()> {
+    let path = config_path(library_path);
+    let mut contents = std::fs::read_to_string(&path).unwrap_or_default();
+    let header = format!("[{}]", section);
+    if contents.lines().any(|line| line.trim() == header) {
+        bail!("{} already has a {} section", path.display(), header);
+    }
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    if !contents.is_empty() {
+        contents.push('\n');
+    }
+    contents.push_str(&format!("{}\n{}", header, body));
+    std::fs::write(&path, contents)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
 /// Statistics from the init operation
 #[derive(Debug, Default)]
 pub struct InitStats {
@@ -33,6 +135,7 @@ pub struct InitStats {
     pub files: usize,
     pub images: usize,
     pub videos: usize,
+    pub documents: usize,
 }
 
 /// Populate the database from a scanner
@@ -46,9 +149,11 @@ fn populate_database(db: &Database, scanner: &Scanner) -> Result<InitStats> {
     // Map from relative path to directory ID
     let mut dir_ids: HashMap<String, i64> = HashMap::new();
 
+    info!("scanning library");
+    let scan = scanner.scan_all()?;
+
     // First pass: insert all directories
-    info!("scanning directories");
-    for dir in scanner.scan_directories() {
+    for dir in scan.directories {
         let parent_id = dir
             .parent_relative_path
             .as_ref()
@@ -61,8 +166,7 @@ fn populate_database(db: &Database, scanner: &Scanner) -> Result<InitStats> {
     info!(count = stats.directories, "directories scanned");
 
     // Second pass: insert all files
-    info!("scanning files");
-    for file in scanner.scan_files() {
+    for file in scan.files {
         // Get or create the directory for this file
         let dir_id = if file.directory.is_empty() {
             // File is in root - need to insert root directory
@@ -105,6 +209,7 @@ fn populate_database(db: &Database, scanner: &Scanner) -> Result<InitStats> {
         match file.media_type {
             MediaType::Image => stats.images += 1,
             MediaType::Video => stats.videos += 1,
+            MediaType::Document => stats.documents += 1,
             MediaType::Other => {}
         }
     }
@@ -112,9 +217,15 @@ fn populate_database(db: &Database, scanner: &Scanner) -> Result<InitStats> {
         files = stats.files,
         images = stats.images,
         videos = stats.videos,
+        documents = stats.documents,
         "files scanned"
     );
 
+    let pairs = db.rebuild_all_pairs()?;
+    debug!(pairs, "paired sibling files");
+    let collisions = db.rebuild_all_name_collisions()?;
+    debug!(collisions, "flagged colliding filenames");
+
     debug!("committing to database");
     db.commit()?;
     info!("init complete");
@@ -155,13 +266,15 @@ mod tests {
         fs::write(root.join("subject1/shoot1/photo2.jpg"), "image data").unwrap();
         fs::write(root.join("subject1/shoot2/video.mp4"), "video data").unwrap();
         fs::write(root.join("subject2/portrait.png"), "image data").unwrap();
+        fs::write(root.join("subject2/release.pdf"), "pdf data").unwrap();
 
         let stats = run_init(root).unwrap();
 
         assert_eq!(stats.directories, 4); // subject1, subject1/shoot1, subject1/shoot2, subject2
-        assert_eq!(stats.files, 4);
+        assert_eq!(stats.files, 5);
         assert_eq!(stats.images, 3);
         assert_eq!(stats.videos, 1);
+        assert_eq!(stats.documents, 1);
 
         // Verify database contents
         let db = Database::open(&root.join(DB_FILENAME)).unwrap();
@@ -203,4 +316,77 @@ mod tests {
         let result = run_init(Path::new("/nonexistent/path"));
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_configure_remote_appends_to_config() {
+        let temp = TempDir::new().unwrap();
+        let mirror = temp.path().join("mirror");
+        fs::create_dir_all(&mirror).unwrap();
+        fs::write(config_path(&mirror), "[maintenance]\ncache_gc = \"@weekly\"").unwrap();
+
+        configure_remote(&mirror, "ssh://me@nas/photos").unwrap();
+        let config = Config::load(&mirror).unwrap();
+        assert_eq!(config.remote.unwrap().url, "ssh://me@nas/photos");
+        assert_eq!(config.maintenance.cache_gc.as_deref(), Some("@weekly"));
+
+        configure_remote(&mirror, "ssh://me@nas/photos").unwrap();
+        assert!(configure_remote(&mirror, "ssh://me@nas/other").is_err());
+        assert!(configure_remote(&temp.path().join("new"), "/mnt/photos").is_err());
+    }
+
+    #[test]
+    fn test_configure_excludes_skips_patterns_on_init() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("@eaDir")).unwrap();
+        fs::write(root.join("@eaDir/thumb.jpg"), "x").unwrap();
+        fs::write(root.join("keep.jpg"), "x").unwrap();
+
+        configure_excludes(root, &["@eaDir".to_string(), "it's*".to_string()]).unwrap();
+        let config = Config::load(root).unwrap();
+        assert_eq!(config.scan.exclude, vec!["@eaDir", "it's*"]);
+        assert!(configure_excludes(root, &["x".to_string()]).is_err());
+
+        let stats = run_init(root).unwrap();
+        assert_eq!((stats.directories, stats.files), (0, 1));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_configure_follow_symlinks_joins_scan_section() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("library");
+        let elsewhere = temp.path().join("elsewhere");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&elsewhere).unwrap();
+        fs::write(elsewhere.join("linked.jpg"), "x").unwrap();
+        fs::write(root.join("keep.jpg"), "x").unwrap();
+        std::os::unix::fs::symlink(&elsewhere, root.join("trip")).unwrap();
+
+        configure_excludes(&root, &["@eaDir".to_string()]).unwrap();
+        configure_follow_symlinks(&root).unwrap();
+        configure_follow_symlinks(&root).unwrap();
+        let config = Config::load(&root).unwrap();
+        assert!(config.scan.follow_symlinks);
+        assert_eq!(config.scan.exclude, vec!["@eaDir"]);
+
+        let stats = run_init(&root).unwrap();
+        assert_eq!((stats.directories, stats.files), (1, 2));
+    }
+
+    #[test]
+    fn test_configure_dotfiles_sets_and_clears() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::write(config_path(root), "[scan]\nexclude = [\"@eaDir\"]\n\n[hashing]\nalgorithm = \"sha256\"\n").unwrap();
+
+        assert!(configure_dotfiles(root, true).unwrap());
+        assert!(!configure_dotfiles(root, true).unwrap());
+        assert!(Config::load(root).unwrap().scan.dotfiles);
+        assert!(configure_dotfiles(root, false).unwrap());
+        let config = Config::load(root).unwrap();
+        assert!(!config.scan.dotfiles);
+        assert_eq!(config.scan.exclude, vec!["@eaDir"]);
+        assert_eq!(config.hashing.algorithm.to_string(), "sha256");
+    }
 }
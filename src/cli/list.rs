@@ -1,22 +1,41 @@
+use std::collections::HashMap;
 use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::Serialize;
 
-use crate::db::{Database, File};
+use crate::db::{Database, ExposureFilter, File};
+use crate::tui::dialogs::{FilterCriteria, Orientation};
+use crate::library_path::LibraryPath;
+use crate::print_size::PrintFilter;
 
 /// Info about a file for display purposes
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct FileInfo {
     pub path: String,
-    pub rating: Option<i32>,
+    pub rating: Option<f64>,
     pub tags: Vec<String>,
 }
 
 /// Options for filtering the file list
 #[derive(Debug, Default)]
 pub struct ListOptions {
-    pub min_rating: Option<i32>,
+    pub min_rating: Option<f64>,
+    /// Only files without a rating
+    pub unrated: bool,
     pub tag: Option<String>,
+    /// Only files in this directory and below (relative to the library)
+    pub dir: Option<String>,
+    /// Name of a filter preset saved from the TUI filter dialog
+    pub preset: Option<String>,
+    /// ISO, aperture and shutter speed ranges
+    pub exposure: ExposureFilter,
+    /// Only files of this shape, from their dimensions
+    pub orientation: Option<Orientation>,
+    /// Only files with the pixels for this print size
+    pub print: Option<PrintFilter>,
+    /// Also list files in hidden directories
+    pub include_hidden: bool,
 }
 
 /// List files from the library, optionally filtered
@@ -30,7 +49,29 @@ pub fn run_list(library_path: &Path, options: ListOptions) -> Result<Vec<FileInf
     }
 
     let db = Database::open(&db_path)?;
+    list_files(&db, &options)
+}
+
+/// The files matching `options`, with their tags
+pub(crate) fn list_files(db: &Database, options: &ListOptions) -> Result<Vec<FileInfo>> {
+    let mut result = Vec::new();
+    for (file, dir_path) in matching_files(db, options)? {
+        let full_path = LibraryPath::file(&dir_path, &file.filename).into_string();
+
+        let tags = db.get_file_tags(file.id)?;
 
+        result.push(FileInfo {
+            path: full_path,
+            rating: file.rating,
+            tags,
+        });
+    }
+
+    Ok(result)
+}
+
+/// The files matching `options`, with their directory paths
+pub(crate) fn matching_files(db: &Database, options: &ListOptions) -> Result<Vec<(File, String)>> {
     // Get files based on filters
     let files_with_paths: Vec<(File, String)> = match (&options.min_rating, &options.tag) {
         (Some(rating), None) => db.get_files_by_rating(*rating)?,
@@ -45,24 +86,114 @@ pub fn run_list(library_path: &Path, options: ListOptions) -> Result<Vec<FileInf
         (None, None) => db.get_all_files_with_paths()?,
     };
 
-    // Convert to FileInfo with tags
-    let mut result = Vec::with_capacity(files_with_paths.len());
-    for (file, dir_path) in files_with_paths {
-        let full_path = if dir_path.is_empty() {
-            file.filename.clone()
-        } else {
-            format!("{}/{}", dir_path, file.filename)
-        };
+    let files_with_paths = match &options.preset {
+        Some(name) => {
+            let preset = db
+                .get_filter_preset(name)?
+                .with_context(|| format!("No filter preset named '{}'", name))?;
+            filter_by_criteria(db, files_with_paths, &preset.criteria)?
+        }
+        None => files_with_paths,
+    };
 
-        let tags = db.get_file_tags(file.id)?;
+    let files_with_paths = if options.exposure.is_active() {
+        let matching = db.get_file_ids_matching_exposure(&options.exposure)?;
+        files_with_paths
+            .into_iter()
+            .filter(|(f, _)| matching.contains(&f.id))
+            .collect()
+    } else {
+        files_with_paths
+    };
 
-        result.push(FileInfo {
-            path: full_path,
-            rating: file.rating,
-            tags,
-        });
-    }
+    let files_with_paths = match options.orientation {
+        Some(orientation) => files_with_paths
+            .into_iter()
+            .filter(|(f, _)| Orientation::of(f.width, f.height) == Some(orientation))
+            .collect(),
+        None => files_with_paths,
+    };
+
+    let files_with_paths = match options.print {
+        Some(print) => files_with_paths
+            .into_iter()
+            .filter(|(f, _)| print.matches(f))
+            .collect(),
+        None => files_with_paths,
+    };
 
+    let files_with_paths = if options.unrated {
+        files_with_paths.into_iter().filter(|(f, _)| f.rating.is_none()).collect()
+    } else {
+        files_with_paths
+    };
+
+    let files_with_paths = match &options.dir {
+        Some(dir) => {
+            let dir = LibraryPath::new(dir);
+            files_with_paths
+                .into_iter()
+                .filter(|(_, path)| {
+                    dir.is_root()
+                        || path == dir.as_str()
+                        || path.strip_prefix(dir.as_str()).is_some_and(|rest| rest.starts_with('/'))
+                })
+                .collect()
+        }
+        None => files_with_paths,
+    };
+
+    let files_with_paths = if options.include_hidden {
+        files_with_paths
+    } else {
+        let hidden = db.get_hidden_subtree_ids()?;
+        files_with_paths
+            .into_iter()
+            .filter(|(f, _)| !hidden.contains(&f.directory_id))
+            .collect()
+    };
+
+    Ok(files_with_paths)
+}
+
+/// Keep the files a TUI filter would show, with tags inherited from
+/// each file's directory and its ancestors
+fn filter_by_criteria(
+    db: &Database,
+    files: Vec<(File, String)>,
+    criteria: &FilterCriteria,
+) -> Result<Vec<(File, String)>> {
+    let dir_ids: HashMap<String, i64> = db
+        .get_all_directories()?
+        .into_iter()
+        .map(|d| (d.path, d.id))
+        .collect();
+    let mut dir_tags_cache: HashMap<String, Vec<String>> = HashMap::new();
+
+    let mut result = Vec::new();
+    for (file, dir_path) in files {
+        if !dir_tags_cache.contains_key(&dir_path) {
+            let mut tags = Vec::new();
+            if !criteria.tags.is_empty() {
+                let mut path = dir_path.as_str();
+                loop {
+                    if let Some(&id) = dir_ids.get(path) {
+                        tags.extend(db.get_directory_tags(id)?);
+                    }
+                    match path.rfind('/') {
+                        Some(i) => path = &path[..i],
+                        None if !path.is_empty() => path = "",
+                        None => break,
+                    }
+                }
+            }
+            dir_tags_cache.insert(dir_path.clone(), tags);
+        }
+        let file_tags = db.get_file_tags(file.id)?;
+        if criteria.matches_file(&file, &file_tags, &dir_tags_cache[&dir_path], false) {
+            result.push((file, dir_path));
+        }
+    }
     Ok(result)
 }
 
@@ -90,8 +221,8 @@ mod tests {
         let file2 = db.get_file_by_path("photo2.jpg").unwrap().unwrap();
         let _file3 = db.get_file_by_path("photo3.jpg").unwrap().unwrap();
 
-        db.set_file_rating(file1.id, Some(5)).unwrap();
-        db.set_file_rating(file2.id, Some(4)).unwrap();
+        db.set_file_rating(file1.id, Some(5.0)).unwrap();
+        db.set_file_rating(file2.id, Some(4.0)).unwrap();
         // file3 unrated
 
         db.add_file_tag(file1.id, "portrait").unwrap();
@@ -116,14 +247,21 @@ mod tests {
         let files = run_list(
             &lib_path,
             ListOptions {
-                min_rating: Some(4),
+                min_rating: Some(4.0),
+                unrated: false,
                 tag: None,
+                dir: None,
+                preset: None,
+                exposure: ExposureFilter::default(),
+                orientation: None,
+                print: None,
+                include_hidden: false,
             },
         )
         .unwrap();
 
         assert_eq!(files.len(), 2);
-        assert!(files.iter().all(|f| f.rating.unwrap() >= 4));
+        assert!(files.iter().all(|f| f.rating.unwrap() >= 4.0));
     }
 
     #[test]
@@ -134,7 +272,14 @@ mod tests {
             &lib_path,
             ListOptions {
                 min_rating: None,
+                unrated: false,
                 tag: Some("portrait".to_string()),
+                dir: None,
+                preset: None,
+                exposure: ExposureFilter::default(),
+                orientation: None,
+                print: None,
+                include_hidden: false,
             },
         )
         .unwrap();
@@ -149,8 +294,15 @@ mod tests {
         let files = run_list(
             &lib_path,
             ListOptions {
-                min_rating: Some(5),
+                min_rating: Some(5.0),
+                unrated: false,
                 tag: Some("portrait".to_string()),
+                dir: None,
+                preset: None,
+                exposure: ExposureFilter::default(),
+                orientation: None,
+                print: None,
+                include_hidden: false,
             },
         )
         .unwrap();
@@ -170,6 +322,140 @@ mod tests {
         assert_eq!(photo2.tags, vec!["outdoor", "portrait"]); // sorted
     }
 
+    #[test]
+    fn test_list_filter_by_preset() {
+        use crate::tui::dialogs::{MediaFilter, RatingFilter};
+
+        let (_temp_dir, lib_path) = setup_test_library();
+        let db = Database::open(&lib_path.join(".picman.db")).unwrap();
+        db.save_filter_preset(
+            "outdoor portraits",
+            &FilterCriteria {
+                rating: RatingFilter::MinRating(4.0),
+                tags: vec!["portrait".to_string(), "outdoor".to_string()],
+                media: MediaFilter::Any,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let options = ListOptions {
+            preset: Some("outdoor portraits".to_string()),
+            ..Default::default()
+        };
+        let files = run_list(&lib_path, options).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "photo2.jpg");
+
+        let options = ListOptions {
+            preset: Some("missing".to_string()),
+            ..Default::default()
+        };
+        assert!(run_list(&lib_path, options).is_err());
+    }
+
+    #[test]
+    fn test_list_filter_by_orientation() {
+        let (_temp_dir, lib_path) = setup_test_library();
+        let db = Database::open(&lib_path.join(".picman.db")).unwrap();
+        for (name, width, height) in [("photo1.jpg", 4000, 6000), ("photo2.jpg", 6000, 4000)] {
+            let file = db.get_file_by_path(name).unwrap().unwrap();
+            db.set_file_dimensions(file.id, width, height).unwrap();
+        }
+
+        // photo1 is tagged "portrait" too, but only its shape counts
+        let options = ListOptions { orientation: Some(Orientation::Landscape), ..Default::default() };
+        let files = run_list(&lib_path, options).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "photo2.jpg");
+    }
+
+    #[test]
+    fn test_list_filter_by_print_size() {
+        let (_temp_dir, lib_path) = setup_test_library();
+        let db = Database::open(&lib_path.join(".picman.db")).unwrap();
+        for (name, width, height) in [("photo1.jpg", 3000, 2400), ("photo2.jpg", 2000, 3000)] {
+            let file = db.get_file_by_path(name).unwrap().unwrap();
+            db.set_file_dimensions(file.id, width, height).unwrap();
+        }
+
+        // 8x10 at 300 dpi needs 2400x3000, either way round
+        let print = PrintFilter::new("8x10".parse().unwrap());
+        let files = run_list(&lib_path, ListOptions { print: Some(print), ..Default::default() }).unwrap();
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["photo1.jpg"]);
+
+        let print = PrintFilter { dpi: 240, ..print };
+        let files = run_list(&lib_path, ListOptions { print: Some(print), ..Default::default() }).unwrap();
+        assert_eq!(files.len(), 2);
+    }
+
+    #[test]
+    fn test_list_unrated_under_a_directory() {
+        let (_temp_dir, lib_path) = setup_test_library();
+        fs::create_dir_all(lib_path.join("2024/june")).unwrap();
+        fs::create_dir_all(lib_path.join("2024-old")).unwrap();
+        fs::write(lib_path.join("2024/a.jpg"), "fake jpeg").unwrap();
+        fs::write(lib_path.join("2024/june/b.jpg"), "fake jpeg").unwrap();
+        fs::write(lib_path.join("2024-old/c.jpg"), "fake jpeg").unwrap();
+        crate::cli::run_sync(&lib_path, false, false, false).unwrap();
+        let db = Database::open(&lib_path.join(".picman.db")).unwrap();
+        let rated = db.get_file_by_path("2024/a.jpg").unwrap().unwrap();
+        db.set_file_rating(rated.id, Some(3.0)).unwrap();
+
+        let paths = |options: ListOptions| -> Vec<String> {
+            let mut paths: Vec<String> = run_list(&lib_path, options).unwrap().into_iter().map(|f| f.path).collect();
+            paths.sort();
+            paths
+        };
+        let dir = Some("2024/".to_string());
+        assert_eq!(paths(ListOptions { dir: dir.clone(), ..Default::default() }), ["2024/a.jpg", "2024/june/b.jpg"]);
+        assert_eq!(paths(ListOptions { dir, unrated: true, ..Default::default() }), ["2024/june/b.jpg"]);
+        assert_eq!(paths(ListOptions { unrated: true, ..Default::default() }).len(), 3);
+    }
+
+    #[test]
+    fn test_list_leaves_out_hidden_directories() {
+        let (_temp, lib_path) = setup_test_library();
+        fs::create_dir_all(lib_path.join("exports")).unwrap();
+        fs::write(lib_path.join("exports/old.jpg"), "fake jpeg").unwrap();
+        crate::cli::run_sync(&lib_path, false, false, false).unwrap();
+        crate::cli::run_hide(&lib_path, Path::new("exports"), true).unwrap();
+
+        let files = run_list(&lib_path, ListOptions::default()).unwrap();
+        assert_eq!(files.len(), 3);
+
+        let options = ListOptions { include_hidden: true, ..Default::default() };
+        let files = run_list(&lib_path, options).unwrap();
+        assert!(files.iter().any(|f| f.path == "exports/old.jpg"));
+    }
+
+    #[test]
+    fn test_list_filter_by_exposure() {
+        let (_temp_dir, lib_path) = setup_test_library();
+        let db = Database::open(&lib_path.join(".picman.db")).unwrap();
+        let file1 = db.get_file_by_path("photo1.jpg").unwrap().unwrap();
+        let file3 = db.get_file_by_path("photo3.jpg").unwrap().unwrap();
+        db.set_file_exposure(file1.id, Some(6400), Some(1.8), Some(1.0 / 30.0)).unwrap();
+        db.set_file_exposure(file3.id, Some(100), Some(5.6), Some(1.0 / 500.0)).unwrap();
+
+        let options = ListOptions {
+            exposure: ExposureFilter { min_iso: Some(3200), max_aperture: Some(2.0), ..Default::default() },
+            ..Default::default()
+        };
+        let files = run_list(&lib_path, options).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "photo1.jpg");
+
+        let options = ListOptions {
+            exposure: ExposureFilter { max_shutter: Some(1.0 / 250.0), ..Default::default() },
+            ..Default::default()
+        };
+        let files = run_list(&lib_path, options).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "photo3.jpg");
+    }
+
     #[test]
     fn test_list_nonexistent_db_errors() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,331 @@
+//! Advisory lock that keeps two syncs from writing one library at once.
+//!
+//! The lock is a file next to the database, created atomically, naming the
+//! process that holds it. A lock left behind by a crashed process on this
+//! machine is replaced automatically; one held from another machine (a
+//! library on a network share) has to be removed with `--force-unlock`.
+
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use tracing::warn;
+
+use crate::process::{is_running, unix_now};
+
+/// Lock file location inside the library (next to `.picman.db`)
+pub fn sync_lock_path(library_path: &Path) -> PathBuf {
+    library_path.join(".picman-sync.lock")
+}
+
+/// The process named in a lock file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub host: String,
+    /// Unix timestamp the lock was taken
+    pub started: i64,
+}
+
+impl LockHolder {
+    fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            host: hostname(),
+            started: unix_now(),
+        }
+    }
+
+    fn parse(contents: &str) -> Option<Self> {
+        let mut lines = contents.lines();
+        let pid = lines.next()?.trim().parse().ok()?;
+        let started = lines.next()?.trim().parse().ok()?;
+        let host = lines.next().unwrap_or_default().trim().to_string();
+        Some(Self { pid, host, started })
+    }
+
+    /// Whether the holder is known to have exited
+    fn is_stale(&self) -> bool {
+        self.host == hostname() && !is_running(self.pid)
+    }
+}
+
+impl std::fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "pid {}", self.pid)?;
+        if !self.host.is_empty() {
+            write!(f, " on {}", self.host)?;
+        }
+        match DateTime::from_timestamp(self.started, 0) {
+            Some(t) => write!(f, ", started {}", t.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Another sync holds the library's lock
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Another sync is already running on this library ({holder}). \
+     If it is no longer running, remove the lock with 'picman sync --force-unlock'"
+)]
+pub struct SyncLocked {
+    pub holder: LockHolder,
+}
+
+/// The library's sync lock, held until dropped
+pub struct SyncLock {
+    path: PathBuf,
+}
+
+impl SyncLock {
+    /// Take the lock, replacing one left by a process that has exited.
+    /// Fails with [`SyncLocked`] if a live process holds it.
+    pub fn acquire(library_path: &Path) -> Result<Self> {
+        let path = sync_lock_path(library_path);
+        let holder = LockHolder::current();
+        let contents = format!("{}\n{}\n{}\n", holder.pid, holder.started, holder.host);
+        // Written aside and linked into place, so the lock never exists
+        // without its contents
+        let partial = unique_sibling(&path, "partial");
+        fs::write(&partial, &contents)
+            .with_context(|| format!("Failed to write lock file {}", partial.display()))?;
+        let result = Self::acquire_from(&path, &partial);
+        let _ = fs::remove_file(&partial);
+        result
+    }
+
+    fn acquire_from(path: &Path, partial: &Path) -> Result<Self> {
+        for _ in 0..LOCK_ATTEMPTS {
+            match place_lock(partial, path) {
+                Ok(()) => return Ok(Self { path: path.to_path_buf() }),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to create lock file {}", path.display()))
+                }
+            }
+
+            match read_holder(path) {
+                Ok(Some(existing)) if !existing.is_stale() => {
+                    return Err(SyncLocked { holder: existing }.into());
+                }
+                Ok(None) if is_being_written(path) => {
+                    // Created without a hard link and not filled in yet
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                existing => {
+                    // The holder exited without cleaning up, or the file is garbage
+                    warn!(holder = ?existing.ok().flatten(), "removing stale sync lock");
+                    remove_stale_lock(path)?;
+                }
+            }
+        }
+        anyhow::bail!("Could not take the sync lock at {}", path.display())
+    }
+}
+
+const LOCK_ATTEMPTS: usize = 5;
+
+/// How long an empty lock file is taken to be mid-write rather than garbage
+const LOCK_WRITE_GRACE: Duration = Duration::from_secs(2);
+
+/// Create `path` with the contents of `partial`, failing with `AlreadyExists`
+/// if it is there. A hard link creates it complete in one step; filesystems
+/// without hard links (exFAT, FAT, many SMB/NAS mounts) get an exclusive
+/// create and a write instead.
+fn place_lock(partial: &Path, path: &Path) -> std::io::Result<()> {
+    match fs::hard_link(partial, path) {
+        Err(e) if e.kind() != ErrorKind::AlreadyExists => write_new(path, &fs::read(partial)?),
+        result => result,
+    }
+}
+
+/// Create `path` holding `contents`, failing with `AlreadyExists` if it is there
+fn write_new(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut file = fs::OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Whether an unreadable lock is fresh enough that its creator may still be
+/// writing it
+fn is_being_written(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .is_some_and(|age| age < LOCK_WRITE_GRACE)
+}
+
+/// Move a stale lock out of the way. The rename is atomic, so when several
+/// processes find the same stale lock only one of them moves it. What it
+/// moved is checked again in case a live sync took the lock in between,
+/// and put back if so.
+fn remove_stale_lock(path: &Path) -> Result<()> {
+    let aside = unique_sibling(path, "stale");
+    match fs::rename(path, &aside) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to remove stale lock {}", path.display())),
+    }
+    let moved = read_holder(&aside);
+    if let Ok(Some(holder)) = moved {
+        if !holder.is_stale() {
+            let _ = place_lock(&aside, path);
+            let _ = fs::remove_file(&aside);
+            return Err(SyncLocked { holder }.into());
+        }
+    }
+    let _ = fs::remove_file(&aside);
+    Ok(())
+}
+
+/// A file name next to the lock that no other process or thread uses
+fn unique_sibling(path: &Path, purpose: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_extension(format!("lock.{}.{}.{}", purpose, std::process::id(), n))
+}
+
+impl Drop for SyncLock {
+    fn drop(&mut self) {
+        // Only remove the file if it is still ours (not force-unlocked and retaken)
+        let ours = read_holder(&self.path).ok().flatten().map(|h| h.pid) == Some(std::process::id());
+        if ours {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Remove the library's sync lock whoever holds it, returning the holder.
+/// Only for locks left behind by a sync that is no longer running.
+pub fn force_unlock(library_path: &Path) -> Result<Option<LockHolder>> {
+    let path = sync_lock_path(library_path);
+    let holder = read_holder(&path)?;
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(holder),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to remove lock file {}", path.display())),
+    }
+}
+
+/// Read the holder named in `path`; None if the file is missing or malformed
+fn read_holder(path: &Path) -> Result<Option<LockHolder>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(LockHolder::parse(&contents)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read lock file {}", path.display())),
+    }
+}
+
+#[cfg(unix)]
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    let ret = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if ret != 0 {
+        return String::new();
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).to_string()
+}
+
+#[cfg(not(unix))]
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sync_lock_excludes_second_holder() {
+        let tmp = TempDir::new().unwrap();
+        let lock = SyncLock::acquire(tmp.path()).unwrap();
+
+        let err = SyncLock::acquire(tmp.path()).err().unwrap();
+        let locked = err.downcast_ref::<SyncLocked>().unwrap();
+        assert_eq!(locked.holder.pid, std::process::id());
+        assert!(err.to_string().contains(&format!("pid {}", std::process::id())));
+
+        drop(lock);
+        assert!(!sync_lock_path(tmp.path()).exists());
+        let _again = SyncLock::acquire(tmp.path()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sync_lock_replaces_stale_lock() {
+        let tmp = TempDir::new().unwrap();
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead_pid = child.id();
+        child.wait().unwrap();
+        fs::write(sync_lock_path(tmp.path()), format!("{}\n0\n{}\n", dead_pid, hostname())).unwrap();
+
+        let _lock = SyncLock::acquire(tmp.path()).unwrap();
+        assert_eq!(
+            read_holder(&sync_lock_path(tmp.path())).unwrap().unwrap().pid,
+            std::process::id()
+        );
+    }
+
+    #[test]
+    fn test_lock_created_without_hard_links() {
+        let tmp = TempDir::new().unwrap();
+        let path = sync_lock_path(tmp.path());
+
+        // The path taken where hard links aren't supported
+        write_new(&path, format!("{}\n0\n{}\n", std::process::id(), hostname()).as_bytes()).unwrap();
+        assert_eq!(write_new(&path, b"").unwrap_err().kind(), ErrorKind::AlreadyExists);
+        assert!(SyncLock::acquire(tmp.path()).err().unwrap().downcast_ref::<SyncLocked>().is_some());
+
+        // An empty lock just created is being written, not garbage
+        fs::write(&path, "").unwrap();
+        assert!(is_being_written(&path));
+        assert!(SyncLock::acquire(tmp.path()).is_err());
+        assert!(path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stale_lock_is_taken_over_once() {
+        let tmp = TempDir::new().unwrap();
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead_pid = child.id();
+        child.wait().unwrap();
+        fs::write(sync_lock_path(tmp.path()), format!("{}\n0\n{}\n", dead_pid, hostname())).unwrap();
+
+        // Every thread sees the same stale lock; only one may end up holding it
+        let barrier = std::sync::Barrier::new(8);
+        let acquired = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    s.spawn(|| {
+                        barrier.wait();
+                        SyncLock::acquire(tmp.path()).ok()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+        });
+        assert_eq!(acquired.iter().filter(|l| l.is_some()).count(), 1);
+    }
+
+    #[test]
+    fn test_force_unlock_removes_foreign_lock() {
+        let tmp = TempDir::new().unwrap();
+        // A live pid on another machine can't be checked, so it's never stale
+        fs::write(sync_lock_path(tmp.path()), "1\n1700000000\nnas\n").unwrap();
+        assert!(SyncLock::acquire(tmp.path()).is_err());
+
+        let holder = force_unlock(tmp.path()).unwrap().unwrap();
+        assert_eq!((holder.pid, holder.host.as_str()), (1, "nas"));
+        assert_eq!(force_unlock(tmp.path()).unwrap(), None);
+        let _lock = SyncLock::acquire(tmp.path()).unwrap();
+    }
+}
@@ -1,22 +1,70 @@
+mod archive;
+mod attr;
+mod completions;
+mod contact_sheet;
+mod dedupe;
 mod dupes;
+mod dupes_report;
+mod export;
+mod hide;
+mod import_ratings;
 mod init;
 mod list;
+mod lock;
+mod open;
+mod phash;
+mod pick;
 mod post_process;
 mod previews;
 mod rate;
 mod repair;
+mod review;
+mod screenshots;
+mod stats;
 mod status;
 mod sync;
 mod tag;
+mod tag_alias;
 mod thumbnails;
+mod verify;
+mod watermark;
 
-pub use dupes::run_dupes;
-pub use init::run_init;
+pub use archive::{run_archive, run_restore, ArchiveOptions, ArchiveReport, RestoreReport};
+pub use attr::{run_attr, AttrAction};
+pub use completions::{complete, completion_script, Shell};
+pub use contact_sheet::{run_contact_sheet, ContactSheetOptions, ContactSheetReport, Paper};
+pub use dedupe::{run_dedupe_link, DedupeOptions, DedupeReport};
+pub use dupes::{reclaimable_by_directory, reclaimable_bytes, run_dupes, run_dupes_ignore, DirectorySavings};
+pub use dupes_report::DupesReportOptions;
+pub use export::{run_export_resized, ExportMetadata, ExportOptions, ExportReport};
+pub use hide::{list_hidden, run_hide};
+pub use import_ratings::{run_import_ratings, ImportRatingsReport};
+pub use init::{configure_dotfiles, configure_excludes, configure_follow_symlinks, configure_remote, run_init, DB_FILENAME};
 pub use list::{run_list, FileInfo, ListOptions};
+pub(crate) use list::{list_files, matching_files};
+pub use lock::{force_unlock, LockHolder, SyncLock, SyncLocked};
+pub use open::{open_in_viewer, run_open, run_open_query, OpenOptions};
+pub use phash::{run_phash, PhashReport};
+pub use pick::{fuzzy_filter, fuzzy_score, pick, pick_file};
 pub use previews::{run_check_previews, run_generate_previews};
-pub use rate::run_rate;
-pub use repair::run_repair;
+pub use rate::{run_rate, run_rate_pick, run_rate_propagate, run_rating_scale, Propagation};
+pub(crate) use rate::rate_file;
+pub use repair::{run_repair, RepairReport};
+pub use review::{describe_filter, run_review, ReviewAction};
+pub(crate) use review::create_review_queue;
+pub use screenshots::{run_flag_screenshots, ScreenshotReport};
+pub use stats::{render_heatmap, run_heatmap, run_stats};
 pub use status::run_status;
-pub use sync::{run_sync, run_sync_incremental, run_sync_with_perceptual};
-pub use tag::{run_tag, TagOptions};
-pub use thumbnails::{run_check_thumbnails, run_generate_thumbnails, run_generate_web_thumbnails};
+pub use sync::{
+    run_sync, run_sync_incremental, run_sync_incremental_with_progress, run_sync_resume, run_sync_subdir,
+    run_sync_with_perceptual, SyncStats,
+};
+pub use tag::{run_retire_orientation_tags, run_tag, run_tag_directory, DirectoryTagReport, TagOptions};
+pub(crate) use tag::tag_file;
+pub use tag_alias::{run_tag_alias, TagAliasAction};
+pub use thumbnails::{
+    run_cache_gc, run_check_thumbnails, run_generate_directory_thumbnails, run_generate_thumbnails,
+    run_generate_web_thumbnails,
+};
+pub use verify::{run_verify, VerifyReport};
+pub use watermark::{Watermark, WatermarkOptions, WatermarkPosition, WatermarkSource};
@@ -0,0 +1,132 @@
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::Result;
+
+use super::list::{list_files, FileInfo, ListOptions};
+use super::pick::pick;
+use crate::db::{Activity, Database};
+use crate::library_path::LibraryPath;
+
+/// What `picman open` looks for
+#[derive(Debug, Default)]
+pub struct OpenOptions {
+    /// Part of a file's path, or one of its tags (case-insensitive)
+    pub query: Option<String>,
+    pub min_rating: Option<f64>,
+    pub tag: Option<String>,
+    /// Also look in hidden directories
+    pub include_hidden: bool,
+}
+
+fn open_library_db(library_path: &Path) -> Result<Database> {
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+    Database::open(&db_path)
+}
+
+/// The files `options` resolves to
+pub fn run_open_query(library_path: &Path, options: &OpenOptions) -> Result<Vec<FileInfo>> {
+    let db = open_library_db(library_path)?;
+    find_files(&db, options)
+}
+
+pub(crate) fn find_files(db: &Database, options: &OpenOptions) -> Result<Vec<FileInfo>> {
+    let list = ListOptions {
+        min_rating: options.min_rating,
+        tag: options.tag.clone(),
+        include_hidden: options.include_hidden,
+        ..Default::default()
+    };
+    let mut files = list_files(db, &list)?;
+    if let Some(query) = &options.query {
+        let query = query.to_lowercase();
+        files.retain(|f| {
+            f.path.to_lowercase().contains(&query) || f.tags.iter().any(|t| t.to_lowercase() == query)
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Open one of `matches` with the system viewer: the only one, or the one
+/// picked on the terminal. Returns the opened path, or None if the pick
+/// was cancelled.
+pub fn run_open(library_path: &Path, matches: &[FileInfo]) -> Result<Option<String>> {
+    let path = match matches {
+        [] => anyhow::bail!("No files match"),
+        [only] => only.path.clone(),
+        _ if !io::stdin().is_terminal() || !io::stderr().is_terminal() => {
+            anyhow::bail!("{} files match; narrow the query to open one", matches.len())
+        }
+        _ => {
+            let paths: Vec<String> = matches.iter().map(|f| f.path.clone()).collect();
+            match pick("Open>", &paths)? {
+                Some(path) => path,
+                None => return Ok(None),
+            }
+        }
+    };
+
+    let db = open_library_db(library_path)?;
+    if let Some(file) = db.get_file_by_path(&path)? {
+        db.record_file_activity(file.id, Activity::Viewed, chrono::Utc::now().timestamp())?;
+    }
+    open_in_viewer(&LibraryPath::new(&path).absolute(library_path))?;
+    Ok(Some(path))
+}
+
+/// Open `path` with the default system viewer, without waiting for it
+pub fn open_in_viewer(path: &Path) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    let program = "open";
+    #[cfg(not(target_os = "macos"))]
+    let program = "xdg-open";
+
+    Command::new(program)
+        .arg(path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Could not run {}: {}", program, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_query_matches_paths_and_tags() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir(temp.path().join("Trip")).unwrap();
+        fs::write(temp.path().join("Trip/beach.jpg"), "a").unwrap();
+        fs::write(temp.path().join("Trip/hotel.jpg"), "b").unwrap();
+        fs::write(temp.path().join("cat.jpg"), "c").unwrap();
+        crate::cli::run_init(temp.path()).unwrap();
+        let db = Database::open(&temp.path().join(".picman.db")).unwrap();
+        let cat = db.get_file_by_path("cat.jpg").unwrap().unwrap();
+        db.add_file_tag(cat.id, "Sunset").unwrap();
+        db.set_file_rating(cat.id, Some(4.0)).unwrap();
+
+        let paths = |options: OpenOptions| -> Vec<String> {
+            find_files(&db, &options).unwrap().into_iter().map(|f| f.path).collect()
+        };
+        let query = |q: &str| OpenOptions { query: Some(q.to_string()), ..Default::default() };
+        assert_eq!(paths(query("trip/")), ["Trip/beach.jpg", "Trip/hotel.jpg"]);
+        assert_eq!(paths(query("BEACH")), ["Trip/beach.jpg"]);
+        assert_eq!(paths(query("sunset")), ["cat.jpg"]);
+        assert_eq!(paths(OpenOptions { min_rating: Some(3.0), ..Default::default() }), ["cat.jpg"]);
+        assert!(paths(query("nothing")).is_empty());
+
+        assert!(run_open(temp.path(), &[]).is_err());
+    }
+}
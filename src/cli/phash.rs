@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::db::Database;
+use crate::vfs::open_library_fs;
+
+use super::init::DB_FILENAME;
+use super::post_process::compute_perceptual_hashes;
+
+/// Outcome of a `picman phash` run
+#[derive(Debug, Default, PartialEq)]
+pub struct PhashReport {
+    pub hashed: usize,
+    pub errors: usize,
+    /// Images that already had a perceptual hash from an earlier run
+    pub already_hashed: usize,
+}
+
+/// Compute perceptual hashes for images that don't have one, without
+/// rescanning the library. Results are committed in batches, so an
+/// interrupted run picks up where it stopped.
+pub fn run_phash(library_path: &Path) -> Result<PhashReport> {
+    let library_path = library_path
+        .canonicalize()
+        .with_context(|| format!("Library path does not exist: {}", library_path.display()))?;
+
+    let db_path = library_path.join(DB_FILENAME);
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            db_path.display()
+        );
+    }
+    if open_library_fs(&library_path)?.is_remote() {
+        anyhow::bail!("Perceptual hashing needs local originals; it is not available for remote libraries");
+    }
+
+    let db = Database::open(&db_path)?;
+    let already_hashed = db.get_all_perceptual_hashes()?.len();
+    let (hashed, errors) = compute_perceptual_hashes(&db, &library_path)?;
+
+    Ok(PhashReport {
+        hashed,
+        errors,
+        already_hashed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::run_init;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_phash_is_resumable() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("photos")).unwrap();
+        image::RgbImage::from_fn(32, 32, |x, _| image::Rgb([(x * 8) as u8, 0, 0]))
+            .save(root.join("photos/a.png"))
+            .unwrap();
+        image::RgbImage::new(32, 32).save(root.join("photos/b.png")).unwrap();
+        fs::write(root.join("photos/broken.jpg"), "not a jpeg").unwrap();
+        run_init(root).unwrap();
+
+        let report = run_phash(root).unwrap();
+        assert_eq!((report.hashed, report.errors, report.already_hashed), (2, 1, 0));
+
+        // Only the image that failed is tried again
+        let again = run_phash(root).unwrap();
+        assert_eq!((again.hashed, again.errors, again.already_hashed), (0, 1, 2));
+    }
+}
@@ -0,0 +1,152 @@
+//! Interactive fuzzy picker for CLI arguments left out on a terminal, e.g.
+//! `picman rate <library>` without a file. Draws a query line and the best
+//! matches below the cursor on stderr, so stdout stays clean for scripts.
+
+use std::io::{self, IsTerminal, Write};
+
+use anyhow::Result;
+use crossterm::cursor::{MoveToColumn, MoveUp};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::{execute, queue};
+
+use crate::db::Database;
+use crate::library_path::LibraryPath;
+
+/// Matches shown under the query line
+const VISIBLE: usize = 10;
+
+/// How well `query` matches `candidate`, or None if its characters don't
+/// all appear in order. Case-insensitive; runs of consecutive characters
+/// and characters starting a path segment or word score higher.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous: Option<usize> = None;
+    for q in query.to_lowercase().chars() {
+        let found = candidate[pos..].iter().position(|&c| c == q)? + pos;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || matches!(candidate[found - 1], '/' | '_' | '-' | '.' | ' ') {
+            score += 8;
+        }
+        previous = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+/// Candidates matching `query`, best first (shorter first among equals)
+pub fn fuzzy_filter<'a>(query: &str, candidates: &'a [String]) -> Vec<&'a String> {
+    let mut scored: Vec<(i64, &String)> = candidates
+        .iter()
+        .filter_map(|c| fuzzy_score(query, c).map(|s| (s, c)))
+        .collect();
+    scored.sort_by(|(sa, a), (sb, b)| sb.cmp(sa).then(a.len().cmp(&b.len())).then(a.cmp(b)));
+    scored.into_iter().map(|(_, c)| c).collect()
+}
+
+/// Let the user pick one of `candidates`; None if they cancel (Esc, Ctrl-C)
+pub fn pick(prompt: &str, candidates: &[String]) -> Result<Option<String>> {
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        anyhow::bail!("Nothing to pick with: not running in a terminal");
+    }
+    let mut err = io::stderr();
+    // Scroll the screen up front so redraws below the cursor stay in place
+    queue!(err, Print("\n".repeat(VISIBLE)), MoveUp(VISIBLE as u16))?;
+    terminal::enable_raw_mode()?;
+    let picked = pick_loop(&mut err, prompt, candidates);
+    let _ = terminal::disable_raw_mode();
+    execute!(err, MoveToColumn(0), Clear(ClearType::FromCursorDown))?;
+    picked
+}
+
+fn pick_loop(err: &mut io::Stderr, prompt: &str, candidates: &[String]) -> Result<Option<String>> {
+    let mut query = String::new();
+    let mut selected = 0;
+    loop {
+        let matches = fuzzy_filter(&query, candidates);
+        selected = selected.min(matches.len().saturating_sub(1));
+
+        queue!(err, MoveToColumn(0), Clear(ClearType::FromCursorDown))?;
+        queue!(err, Print(format!("{} {}", prompt, query)))?;
+        let shown = matches.len().min(VISIBLE);
+        for (i, m) in matches.iter().take(VISIBLE).enumerate() {
+            queue!(err, Print("\r\n"))?;
+            if i == selected {
+                queue!(err, SetAttribute(Attribute::Reverse), Print(m), SetAttribute(Attribute::Reset))?;
+            } else {
+                queue!(err, Print(m))?;
+            }
+        }
+        if shown > 0 {
+            queue!(err, MoveUp(shown as u16))?;
+        }
+        let column = prompt.chars().count() + 1 + query.chars().count();
+        queue!(err, MoveToColumn(column as u16))?;
+        err.flush()?;
+
+        let Event::Key(key) = event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char('c') if ctrl => return Ok(None),
+            KeyCode::Enter => return Ok(matches.get(selected).map(|m| m.to_string())),
+            KeyCode::Up => selected = selected.saturating_sub(1),
+            KeyCode::Char('p') if ctrl => selected = selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Tab => selected += 1,
+            KeyCode::Char('n') if ctrl => selected += 1,
+            KeyCode::Backspace => {
+                query.pop();
+                selected = 0;
+            }
+            KeyCode::Char(c) if !ctrl => {
+                query.push(c);
+                selected = 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Pick a file of the library from its database
+pub fn pick_file(db: &Database) -> Result<Option<String>> {
+    let paths: Vec<String> = db
+        .get_all_files_with_paths()?
+        .into_iter()
+        .map(|(file, dir_path)| LibraryPath::file(&dir_path, &file.filename).into_string())
+        .collect();
+    if paths.is_empty() {
+        anyhow::bail!("The library has no files");
+    }
+    pick("File>", &paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("trp", "2024/trip/a.jpg").is_some());
+        assert!(fuzzy_score("ptr", "2024/trip/a.jpg").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        // Runs from a segment start beat scattered matches
+        assert!(fuzzy_score("trip", "2024/trip/a.jpg") > fuzzy_score("trip", "2024/tarsip.jpg"));
+        assert_eq!(fuzzy_score("TRIP", "trip.jpg"), fuzzy_score("trip", "TRIP.JPG"));
+    }
+
+    #[test]
+    fn test_fuzzy_filter_orders_best_first() {
+        let candidates: Vec<String> =
+            ["travel/rip.jpg", "2024/trip/b.jpg", "2024/trip/a.jpg", "other.jpg"].map(String::from).to_vec();
+        assert_eq!(fuzzy_filter("trip", &candidates), ["2024/trip/a.jpg", "2024/trip/b.jpg", "travel/rip.jpg"]);
+    }
+}
@@ -2,22 +2,26 @@ use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use anyhow::Result;
-use rayon::ThreadPoolBuilder;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use rayon::prelude::*;
 use tracing::{debug, info, instrument, warn};
 
+use crate::config::Config;
 use crate::db::Database;
-use crate::hash::compute_file_hash;
+use crate::hash::{compute_file_hash_with, compute_quick_hash};
 use crate::perceptual_hash::compute_perceptual_hash;
 use crate::scanner::{detect_orientation, read_dimensions_fast};
-use crate::thumbnails::is_image_file;
+use crate::thumbnails::{is_image_file, probe_video_duration};
+use crate::tui::exif::{parse_capture_date, read_exif};
+use crate::workers;
 
 const HASH_BATCH_SIZE: usize = 1000;
-const HASH_THREADS: usize = 2;
+pub(super) const HASH_THREADS: usize = 2;
 const PHASH_BATCH_SIZE: usize = 500;
 const PHASH_THREADS: usize = 2;
 const DIMENSION_BATCH_SIZE: usize = 1000;
+const GEAR_BATCH_SIZE: usize = 1000;
+const DURATION_BATCH_SIZE: usize = 200;
 const ORIENTATION_BATCH_SIZE: usize = 5000;
 
 /// Detect image orientation and add landscape/portrait tags
@@ -69,7 +73,7 @@ pub(super) fn tag_orientation(db: &Database, library_path: &Path) -> Result<usiz
 /// Backfill dimensions for image files with NULL width/height.
 /// Reads only the file header (via imagesize), so it's fast even on HDD.
 #[instrument(skip(db, library_path))]
-pub(super) fn backfill_dimensions(db: &Database, library_path: &Path) -> Result<usize> {
+pub(super) fn backfill_dimensions(db: &Database, library_path: &Path, quiet: bool) -> Result<usize> {
     let files = db.get_files_needing_dimensions()?;
     let total = files.len();
 
@@ -84,6 +88,9 @@ pub(super) fn backfill_dimensions(db: &Database, library_path: &Path) -> Result<
             .unwrap()
             .progress_chars("██░"),
     );
+    if quiet {
+        progress.set_draw_target(ProgressDrawTarget::hidden());
+    }
     progress.set_message("reading dimensions");
 
     let mut backfilled = 0usize;
@@ -107,12 +114,128 @@ pub(super) fn backfill_dimensions(db: &Database, library_path: &Path) -> Result<
     Ok(backfilled)
 }
 
-/// Hash files that have NULL hash values
+/// Record camera, lens, exposure settings and capture date for image files whose EXIF
+/// hasn't been read yet.
+/// Files without EXIF are marked as read too, so they're only opened once.
+#[instrument(skip(db, library_path))]
+pub(super) fn backfill_gear(db: &Database, library_path: &Path, quiet: bool) -> Result<usize> {
+    let files = db.get_files_needing_gear()?;
+    let total = files.len();
+
+    if total == 0 {
+        return Ok(0);
+    }
+
+    let progress = ProgressBar::new(total as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("    {bar:40.cyan/blue} {pos}/{len} ({percent}%) | {elapsed_precise} | {msg}")
+            .unwrap()
+            .progress_chars("██░"),
+    );
+    if quiet {
+        progress.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    progress.set_message("reading camera and lens");
+
+    let mut found = 0usize;
+
+    for batch in files.chunks(GEAR_BATCH_SIZE) {
+        let results: Vec<_> = batch
+            .par_iter()
+            .map(|file| (file.id, read_exif(&library_path.join(&file.path))))
+            .collect();
+
+        db.begin_transaction()?;
+        for (id, exif) in results {
+            let camera = exif.camera();
+            if camera.is_some() || exif.lens.is_some() {
+                found += 1;
+            }
+            db.set_file_gear(id, camera.as_deref(), exif.lens.as_deref())?;
+            db.set_file_exposure(id, exif.iso_speed, exif.f_number, exif.exposure_time)?;
+            db.set_file_capture_date(id, exif.date_taken.as_deref().and_then(parse_capture_date))?;
+        }
+        db.commit()?;
+        progress.inc(batch.len() as u64);
+    }
+
+    progress.finish_with_message(format!("{found} files with camera or lens"));
+
+    Ok(found)
+}
+
+/// Record the length of videos whose duration hasn't been read, with
+/// ffprobe. Skipped (and tried again next sync) when ffprobe isn't installed.
+#[instrument(skip(db, library_path))]
+pub(super) fn backfill_durations(db: &Database, library_path: &Path, quiet: bool) -> Result<usize> {
+    let files = db.get_files_needing_duration()?;
+    let total = files.len();
+
+    if total == 0 {
+        return Ok(0);
+    }
+
+    let progress = ProgressBar::new(total as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("    {bar:40.cyan/blue} {pos}/{len} ({percent}%) | {elapsed_precise} | {msg}")
+            .unwrap()
+            .progress_chars("██░"),
+    );
+    if quiet {
+        progress.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    progress.set_message("reading video durations");
+
+    let mut found = 0usize;
+
+    for batch in files.chunks(DURATION_BATCH_SIZE) {
+        let results: Vec<_> = batch
+            .par_iter()
+            .map(|file| (file.id, probe_video_duration(&library_path.join(&file.path))))
+            .collect();
+
+        db.begin_transaction()?;
+        for (id, duration) in results {
+            match duration {
+                Ok(duration) => {
+                    found += usize::from(duration.is_some());
+                    db.set_file_duration(id, duration)?;
+                }
+                Err(e) => {
+                    db.commit()?;
+                    progress.finish_and_clear();
+                    warn!(error = %e, "ffprobe unavailable, video durations not read");
+                    return Ok(found);
+                }
+            }
+        }
+        db.commit()?;
+        progress.inc(batch.len() as u64);
+    }
+
+    progress.finish_with_message(format!("{found} video durations read"));
+
+    Ok(found)
+}
+
+/// Hash files that have no hash, or one from a different algorithm than the
+/// library's configured `[hashing] algorithm`. With `[hashing] prefilter`,
+/// every file gets a quick hash first and only those sharing one with
+/// another file are hashed in full.
 #[instrument(skip(db, library_path))]
 pub(super) fn hash_files(db: &Database, library_path: &Path) -> Result<(usize, usize)> {
-    let files_to_hash = db.get_files_needing_hash()?;
+    let hashing = Config::load(library_path)?.hashing;
+    let algorithm = hashing.algorithm;
+    let files_to_hash = if hashing.prefilter {
+        quick_hash_files(db, library_path)?;
+        db.get_files_needing_hash_prefiltered(algorithm)?
+    } else {
+        db.get_files_needing_hash(algorithm)?
+    };
     let total = files_to_hash.len();
-    info!(total, "files needing hash");
+    info!(total, %algorithm, "files needing hash");
 
     if total == 0 {
         return Ok((0, 0));
@@ -121,12 +244,21 @@ pub(super) fn hash_files(db: &Database, library_path: &Path) -> Result<(usize, u
     let mut total_hashed = 0usize;
     let mut total_errors = 0usize;
 
+    // Track bytes rather than files: one multi-GB video would otherwise
+    // leave the bar frozen for minutes
+    let total_bytes: u64 = files_to_hash.iter().map(|f| f.size.max(0) as u64).sum();
+    let progress = ProgressBar::new(total_bytes);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("    {bar:40.cyan/blue} {bytes}/{total_bytes} ({binary_bytes_per_sec}) | {elapsed_precise} | ETA {eta} | {msg}")
+            .unwrap()
+            .progress_chars("██░"),
+    );
+    progress.set_message(format!("hashing {} files ({})", total, algorithm));
+
     // Limited parallelism — enough threads to overlap IO and CPU work,
     // few enough to avoid seek thrashing on HDD
-    let hash_pool = ThreadPoolBuilder::new()
-        .num_threads(HASH_THREADS)
-        .build()
-        .expect("failed to create hash thread pool");
+    let hash_pool = workers::pool(HASH_THREADS);
 
     // Process in batches for resumability
     for (batch_idx, batch) in files_to_hash.chunks(HASH_BATCH_SIZE).enumerate() {
@@ -138,7 +270,8 @@ pub(super) fn hash_files(db: &Database, library_path: &Path) -> Result<(usize, u
                 .par_iter()
                 .map(|file| {
                     let full_path = library_path.join(&file.path);
-                    let result = compute_file_hash(&full_path);
+                    let result =
+                        compute_file_hash_with(&full_path, algorithm, |bytes| progress.inc(bytes));
 
                     let current =
                         batch_start + hashed_in_batch.fetch_add(1, Ordering::Relaxed) + 1;
@@ -154,7 +287,7 @@ pub(super) fn hash_files(db: &Database, library_path: &Path) -> Result<(usize, u
         for (id, result) in results {
             match result {
                 Ok(hash) => {
-                    db.set_file_hash(id, &hash)?;
+                    db.set_file_hash_with(id, &hash, algorithm)?;
                     total_hashed += 1;
                 }
                 Err(e) => {
@@ -166,12 +299,66 @@ pub(super) fn hash_files(db: &Database, library_path: &Path) -> Result<(usize, u
         db.commit()?;
     }
 
+    progress.finish_with_message(format!("{total_hashed} files hashed"));
     info!(total_hashed, total_errors, "hashing complete");
 
     Ok((total_hashed, total_errors))
 }
 
-/// Compute perceptual hashes for image files that don't have one yet
+/// Quick-hash the files that have no quick hash. Failures are logged and
+/// left without one, so they still get a full hash.
+fn quick_hash_files(db: &Database, library_path: &Path) -> Result<usize> {
+    let files = db.get_files_needing_quick_hash()?;
+    let total = files.len();
+    info!(total, "files needing quick hash");
+    if total == 0 {
+        return Ok(0);
+    }
+
+    let progress = ProgressBar::new(total as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("    {bar:40.cyan/blue} {pos}/{len} | {elapsed_precise} | ETA {eta} | {msg}")
+            .unwrap()
+            .progress_chars("██░"),
+    );
+    progress.set_message("quick hashing");
+
+    let hash_pool = workers::pool(HASH_THREADS);
+    let mut hashed = 0usize;
+    for batch in files.chunks(HASH_BATCH_SIZE) {
+        let results: Vec<_> = hash_pool.install(|| {
+            batch
+                .par_iter()
+                .map(|file| {
+                    let result = compute_quick_hash(&library_path.join(&file.path));
+                    progress.inc(1);
+                    (file.id, result)
+                })
+                .collect()
+        });
+
+        db.begin_transaction()?;
+        for (id, result) in results {
+            match result {
+                Ok(hash) => {
+                    db.set_file_quick_hash(id, &hash)?;
+                    hashed += 1;
+                }
+                Err(e) => warn!(error = %e, "failed to quick-hash file"),
+            }
+        }
+        db.commit()?;
+    }
+
+    progress.finish_with_message(format!("{hashed} files quick-hashed"));
+    info!(hashed, "quick hashing complete");
+    Ok(hashed)
+}
+
+/// Compute perceptual hashes for image files that don't have one yet.
+/// Each batch is committed on its own, so an interrupted run resumes
+/// with the images that are still missing a hash.
 #[instrument(skip(db, library_path))]
 pub(super) fn compute_perceptual_hashes(db: &Database, library_path: &Path) -> Result<(usize, usize)> {
     let files = db.get_files_needing_perceptual_hash()?;
@@ -192,7 +379,7 @@ pub(super) fn compute_perceptual_hashes(db: &Database, library_path: &Path) -> R
     let progress = ProgressBar::new(total as u64);
     progress.set_style(
         ProgressStyle::default_bar()
-            .template("    {bar:40.cyan/blue} {pos}/{len} ({percent}%) | {elapsed_precise} | {msg}")
+            .template("    {bar:40.cyan/blue} {pos}/{len} ({percent}%) | {per_sec} | {elapsed_precise} | ETA {eta} | {msg}")
             .unwrap()
             .progress_chars("██░"),
     );
@@ -201,10 +388,7 @@ pub(super) fn compute_perceptual_hashes(db: &Database, library_path: &Path) -> R
     let mut total_hashed = 0usize;
     let mut total_errors = 0usize;
 
-    let phash_pool = ThreadPoolBuilder::new()
-        .num_threads(PHASH_THREADS)
-        .build()
-        .expect("failed to create perceptual hash thread pool");
+    let phash_pool = workers::pool(PHASH_THREADS);
 
     for batch in files.chunks(PHASH_BATCH_SIZE) {
         let results: Vec<_> = phash_pool.install(|| {
@@ -398,6 +582,29 @@ mod tests {
         assert_eq!(stats2.files_hashed, 0);
     }
 
+    #[test]
+    fn test_sync_hash_prefilter_full_hashes_only_collisions() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("videos")).unwrap();
+        fs::write(root.join("videos/a.mov"), "same clip").unwrap();
+        fs::write(root.join("videos/b.mov"), "same clip").unwrap();
+        fs::write(root.join("videos/c.mov"), "another clip").unwrap();
+        fs::write(root.join(".picman.toml"), "[hashing]\nprefilter = true\n").unwrap();
+        run_init(root).unwrap();
+
+        let stats = run_sync(root, true, false, true).unwrap();
+        assert_eq!(stats.files_hashed, 2);
+
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let hash_of = |name: &str| db.get_file_by_path(&format!("videos/{}", name)).unwrap().unwrap().hash;
+        assert!(hash_of("a.mov").is_some());
+        assert_eq!(hash_of("a.mov"), hash_of("b.mov"));
+        assert!(hash_of("c.mov").is_none());
+        assert!(db.get_files_needing_hash_prefiltered(Default::default()).unwrap().is_empty());
+        assert_eq!(db.find_duplicates().unwrap().len(), 1);
+    }
+
     #[test]
     fn test_sync_modified_file_gets_rehashed() {
         let temp = TempDir::new().unwrap();
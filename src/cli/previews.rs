@@ -7,11 +7,13 @@ use anyhow::Result;
 use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
 
+use crate::config::Config;
 use crate::db::Database;
 use crate::thumbnails::{
     collect_preview_images_standalone, generate_dir_preview_from_paths, has_dir_preview,
     TempPreviewState,
 };
+use crate::workers;
 
 /// Statistics from preview generation
 pub struct PreviewStats {
@@ -54,8 +56,9 @@ pub fn run_generate_previews(library_path: &Path) -> Result<PreviewStats> {
     // Create temp state for preview generation
     let temp_state = TempPreviewState {
         library_path: library_path.to_path_buf(),
-        db,
-        directories: directories.clone(),
+        db: &db,
+        directories: &directories,
+        cover: Config::load(library_path)?.previews.cover,
     };
 
     // Phase 1: Collect image paths (sequential - needs DB access)
@@ -87,18 +90,20 @@ pub fn run_generate_previews(library_path: &Path) -> Result<PreviewStats> {
     let generated = AtomicUsize::new(0);
     let failed = AtomicUsize::new(0);
 
-    preview_data.par_iter().for_each(|(dir_id, _path, images)| {
-        if !images.is_empty() {
-            if generate_dir_preview_from_paths(*dir_id, images).is_some() {
-                generated.fetch_add(1, Ordering::Relaxed);
-            } else {
-                failed.fetch_add(1, Ordering::Relaxed);
+    workers::pool(0).install(|| {
+        preview_data.par_iter().for_each(|(dir_id, _path, images)| {
+            if !images.is_empty() {
+                if generate_dir_preview_from_paths(*dir_id, images).is_some() {
+                    generated.fetch_add(1, Ordering::Relaxed);
+                } else {
+                    failed.fetch_add(1, Ordering::Relaxed);
+                }
             }
-        }
-        let gen = generated.load(Ordering::Relaxed);
-        let fail = failed.load(Ordering::Relaxed);
-        progress.set_message(format!("{} generated, {} failed", gen, fail));
-        progress.inc(1);
+            let gen = generated.load(Ordering::Relaxed);
+            let fail = failed.load(Ordering::Relaxed);
+            progress.set_message(format!("{} generated, {} failed", gen, fail));
+            progress.inc(1);
+        });
     });
 
     progress.finish_and_clear();
@@ -1,21 +1,54 @@
 use std::path::Path;
+use std::str::FromStr;
 
 use anyhow::Result;
 
-use crate::db::Database;
+use crate::config::{set_config_value, Config};
+use super::pick::pick_file;
+use crate::db::{Activity, Database, RatingAggregate};
+use crate::rating::RatingScale;
+use crate::library_path::LibraryPath;
+
+/// Direction for `rate --propagate`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Propagation {
+    /// Push the directory rating down to unrated descendant files
+    Down,
+    /// Derive the directory rating from its files
+    Up(RatingAggregate),
+}
+
+impl FromStr for Propagation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "down" => Ok(Propagation::Down),
+            "max" => Ok(Propagation::Up(RatingAggregate::Max)),
+            "avg" | "average" => Ok(Propagation::Up(RatingAggregate::Average)),
+            _ => Err(format!("expected down, max, or avg (got '{}')", s)),
+        }
+    }
+}
+
+/// Outcome of a rating propagation
+#[derive(Debug, PartialEq)]
+pub struct PropagationReport {
+    /// Files rated (down) or rated files the directory rating was derived from (max/avg)
+    pub affected: usize,
+    /// The rating pushed down or derived; None if there was nothing to derive from
+    pub rating: Option<f64>,
+}
 
 /// Set or clear a file's rating
 ///
 /// # Arguments
 /// * `library_path` - Path to the library root
 /// * `file_path` - Path to the file (relative to library root)
-/// * `rating` - Rating 1-5, or None to clear
-pub fn run_rate(library_path: &Path, file_path: &Path, rating: Option<i32>) -> Result<()> {
-    // Validate rating
+/// * `rating` - Rating on the library's scale (1-5 or 1-10), or None to clear
+pub fn run_rate(library_path: &Path, file_path: &Path, rating: Option<f64>) -> Result<()> {
     if let Some(r) = rating {
-        if !(1..=5).contains(&r) {
-            anyhow::bail!("Rating must be between 1 and 5 (got {})", r);
-        }
+        Config::load(library_path)?.ratings.scale.validate(r)?;
     }
 
     let db_path = library_path.join(".picman.db");
@@ -27,21 +60,162 @@ pub fn run_rate(library_path: &Path, file_path: &Path, rating: Option<i32>) -> R
     }
 
     let db = Database::open(&db_path)?;
+    rate_file(&db, &file_path.to_string_lossy(), rating)
+}
 
-    // Convert file_path to relative path string
-    let relative_path = file_path.to_string_lossy();
-
-    let file = db.get_file_by_path(&relative_path)?;
+/// Set or clear the rating of the file at `relative_path`, already checked
+/// against the library's scale
+pub(crate) fn rate_file(db: &Database, relative_path: &str, rating: Option<f64>) -> Result<()> {
+    let file = db.get_file_by_path(relative_path)?;
     let file = match file {
         Some(f) => f,
         None => anyhow::bail!("File not found in database: {}", relative_path),
     };
 
     db.set_file_rating(file.id, rating)?;
+    db.record_file_activity(file.id, Activity::Edited, chrono::Utc::now().timestamp())?;
 
     Ok(())
 }
 
+/// Pick a file with the fuzzy picker, ask for its rating and set it
+///
+/// Returns the file and the rating set, or None if the user cancelled.
+pub fn run_rate_pick(library_path: &Path) -> Result<Option<(String, Option<f64>)>> {
+    let scale = Config::load(library_path)?.ratings.scale;
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+    let db = Database::open(&db_path)?;
+
+    let Some(path) = pick_file(&db)? else {
+        return Ok(None);
+    };
+    eprint!("Rating for {} (1-{}, - to clear, empty to cancel): ", path, scale.max());
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    let rating = match answer.trim() {
+        "" => return Ok(None),
+        "-" => None,
+        r => {
+            let r: f64 = r.parse().map_err(|_| anyhow::anyhow!("Not a rating: {}", r))?;
+            scale.validate(r)?;
+            Some(r)
+        }
+    };
+    rate_file(&db, &path, rating)?;
+    Ok(Some((path, rating)))
+}
+
+/// Propagate ratings between a directory and the files below it
+///
+/// # Arguments
+/// * `library_path` - Path to the library root
+/// * `dir_path` - Directory (relative to library root, "." for the root)
+/// * `rating` - For `Down`, set the directory to this rating before pushing it down
+/// * `propagation` - Push down to unrated files, or derive from file ratings
+/// * `dry_run` - Report what would change without writing anything
+pub fn run_rate_propagate(
+    library_path: &Path,
+    dir_path: &Path,
+    rating: Option<f64>,
+    propagation: Propagation,
+    dry_run: bool,
+) -> Result<PropagationReport> {
+    if let Some(r) = rating {
+        Config::load(library_path)?.ratings.scale.validate(r)?;
+        if matches!(propagation, Propagation::Up(_)) {
+            anyhow::bail!("A rating can only be given with --propagate down");
+        }
+    }
+
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+
+    let db = Database::open(&db_path)?;
+
+    let relative_path = LibraryPath::from_relative(dir_path);
+    let relative_path = relative_path.as_str();
+
+    let dir = db.get_directory_by_path(relative_path)?;
+    // The root has no directory row when it holds no files of its own
+    let dir = match dir {
+        Some(d) => Some(d),
+        None if relative_path.is_empty() => None,
+        None => anyhow::bail!("Directory not found in database: {}", relative_path),
+    };
+
+    match propagation {
+        Propagation::Down => {
+            let rating = rating.or(dir.as_ref().and_then(|d| d.rating));
+            let Some(rating) = rating else {
+                anyhow::bail!("{} has no rating to propagate", dir_path.display());
+            };
+            let affected = if dry_run {
+                db.count_unrated_subtree_files(relative_path)?
+            } else {
+                if let Some(dir) = &dir {
+                    db.set_directory_rating(dir.id, Some(rating))?;
+                }
+                db.rate_unrated_subtree_files(relative_path, rating)?
+            };
+            Ok(PropagationReport { affected, rating: Some(rating) })
+        }
+        Propagation::Up(aggregate) => {
+            let (derived, affected) = db.aggregate_subtree_rating(relative_path, aggregate)?;
+            if !dry_run {
+                if let (Some(dir), Some(derived)) = (&dir, derived) {
+                    db.set_directory_rating(dir.id, Some(derived))?;
+                }
+            }
+            Ok(PropagationReport { affected, rating: derived })
+        }
+    }
+}
+
+/// Switch the library to another rating scale, rescaling every stored
+/// rating and saving `[ratings] scale` in the config.
+/// Returns the previous scale and how many ratings were rescaled.
+pub fn run_rating_scale(library_path: &Path, scale: RatingScale) -> Result<(RatingScale, usize)> {
+    let current = Config::load(library_path)?.ratings.scale;
+    if current == scale {
+        return Ok((current, 0));
+    }
+
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+    let db = Database::open(&db_path)?;
+
+    db.begin_transaction()?;
+    let rescaled = match db.rescale_ratings(current, scale).and_then(|rescaled| {
+        set_config_value(library_path, "ratings", "scale", toml::Value::Integer(scale.max().into()))?;
+        Ok(rescaled)
+    }) {
+        Ok(rescaled) => rescaled,
+        Err(e) => {
+            db.rollback()?;
+            return Err(e);
+        }
+    };
+    db.commit()?;
+
+    Ok((current, rescaled))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -62,12 +236,12 @@ mod tests {
     fn test_rate_file() {
         let (_temp_dir, lib_path) = setup_test_library();
 
-        run_rate(&lib_path, Path::new("photo.jpg"), Some(5)).unwrap();
+        run_rate(&lib_path, Path::new("photo.jpg"), Some(5.0)).unwrap();
 
         // Verify the rating was set
         let db = Database::open(&lib_path.join(".picman.db")).unwrap();
         let file = db.get_file_by_path("photo.jpg").unwrap().unwrap();
-        assert_eq!(file.rating, Some(5));
+        assert_eq!(file.rating, Some(5.0));
     }
 
     #[test]
@@ -75,7 +249,7 @@ mod tests {
         let (_temp_dir, lib_path) = setup_test_library();
 
         // Set a rating first
-        run_rate(&lib_path, Path::new("photo.jpg"), Some(3)).unwrap();
+        run_rate(&lib_path, Path::new("photo.jpg"), Some(3.0)).unwrap();
 
         // Clear it
         run_rate(&lib_path, Path::new("photo.jpg"), None).unwrap();
@@ -90,7 +264,7 @@ mod tests {
     fn test_rate_invalid_rating_zero() {
         let (_temp_dir, lib_path) = setup_test_library();
 
-        let result = run_rate(&lib_path, Path::new("photo.jpg"), Some(0));
+        let result = run_rate(&lib_path, Path::new("photo.jpg"), Some(0.0));
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("between 1 and 5"));
     }
@@ -99,16 +273,36 @@ mod tests {
     fn test_rate_invalid_rating_too_high() {
         let (_temp_dir, lib_path) = setup_test_library();
 
-        let result = run_rate(&lib_path, Path::new("photo.jpg"), Some(6));
+        let result = run_rate(&lib_path, Path::new("photo.jpg"), Some(6.0));
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("between 1 and 5"));
     }
 
+    #[test]
+    fn test_rating_scale_rescales_and_widens_validation() {
+        let (_temp_dir, lib_path) = setup_test_library();
+        run_rate(&lib_path, Path::new("photo.jpg"), Some(4.0)).unwrap();
+
+        let (previous, rescaled) = run_rating_scale(&lib_path, RatingScale::Ten).unwrap();
+        assert_eq!(previous, RatingScale::Five);
+        assert_eq!(rescaled, 1);
+        assert_eq!(Config::load(&lib_path).unwrap().ratings.scale, RatingScale::Ten);
+        let db = Database::open(&lib_path.join(".picman.db")).unwrap();
+        assert_eq!(db.get_file_by_path("photo.jpg").unwrap().unwrap().rating, Some(8.0));
+
+        run_rate(&lib_path, Path::new("photo.jpg"), Some(9.0)).unwrap();
+        let result = run_rate(&lib_path, Path::new("photo.jpg"), Some(11.0));
+        assert!(result.unwrap_err().to_string().contains("between 1 and 10"));
+
+        // Switching to the scale already in use changes nothing
+        assert_eq!(run_rating_scale(&lib_path, RatingScale::Ten).unwrap().1, 0);
+    }
+
     #[test]
     fn test_rate_nonexistent_file() {
         let (_temp_dir, lib_path) = setup_test_library();
 
-        let result = run_rate(&lib_path, Path::new("nonexistent.jpg"), Some(5));
+        let result = run_rate(&lib_path, Path::new("nonexistent.jpg"), Some(5.0));
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("not found"));
     }
@@ -116,7 +310,63 @@ mod tests {
     #[test]
     fn test_rate_no_database() {
         let temp_dir = TempDir::new().unwrap();
-        let result = run_rate(temp_dir.path(), Path::new("photo.jpg"), Some(5));
+        let result = run_rate(temp_dir.path(), Path::new("photo.jpg"), Some(5.0));
+        assert!(result.is_err());
+    }
+
+    fn setup_nested_library() -> (TempDir, std::path::PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let lib_path = temp_dir.path().to_path_buf();
+
+        fs::create_dir_all(lib_path.join("trip/day1")).unwrap();
+        fs::write(lib_path.join("trip/a.jpg"), "fake jpeg").unwrap();
+        fs::write(lib_path.join("trip/day1/b.jpg"), "fake jpeg").unwrap();
+        fs::write(lib_path.join("trip/day1/c.jpg"), "fake jpeg").unwrap();
+        crate::cli::run_init(&lib_path).unwrap();
+
+        (temp_dir, lib_path)
+    }
+
+    #[test]
+    fn test_propagate_down_rates_unrated_files() {
+        let (_temp_dir, lib_path) = setup_nested_library();
+        run_rate(&lib_path, Path::new("trip/day1/b.jpg"), Some(2.0)).unwrap();
+
+        let dry = run_rate_propagate(&lib_path, Path::new("trip"), Some(4.0), Propagation::Down, true)
+            .unwrap();
+        assert_eq!(dry, PropagationReport { affected: 2, rating: Some(4.0) });
+        let db = Database::open(&lib_path.join(".picman.db")).unwrap();
+        assert_eq!(db.get_file_by_path("trip/a.jpg").unwrap().unwrap().rating, None);
+        assert_eq!(db.get_directory_by_path("trip").unwrap().unwrap().rating, None);
+
+        let report = run_rate_propagate(&lib_path, Path::new("trip"), Some(4.0), Propagation::Down, false)
+            .unwrap();
+        assert_eq!(report.affected, 2);
+        assert_eq!(db.get_directory_by_path("trip").unwrap().unwrap().rating, Some(4.0));
+        assert_eq!(db.get_file_by_path("trip/a.jpg").unwrap().unwrap().rating, Some(4.0));
+        assert_eq!(db.get_file_by_path("trip/day1/b.jpg").unwrap().unwrap().rating, Some(2.0));
+        assert_eq!(db.get_file_by_path("trip/day1/c.jpg").unwrap().unwrap().rating, Some(4.0));
+    }
+
+    #[test]
+    fn test_propagate_up_derives_directory_rating() {
+        let (_temp_dir, lib_path) = setup_nested_library();
+
+        // Nothing rated yet: nothing to derive, unrated directory rating stays
+        let report = run_rate_propagate(&lib_path, Path::new("trip"), None, "max".parse().unwrap(), false)
+            .unwrap();
+        assert_eq!(report, PropagationReport { affected: 0, rating: None });
+
+        run_rate(&lib_path, Path::new("trip/a.jpg"), Some(5.0)).unwrap();
+        run_rate(&lib_path, Path::new("trip/day1/b.jpg"), Some(2.0)).unwrap();
+
+        let report = run_rate_propagate(&lib_path, Path::new("trip"), None, "avg".parse().unwrap(), false)
+            .unwrap();
+        assert_eq!(report, PropagationReport { affected: 2, rating: Some(3.5) });
+        let db = Database::open(&lib_path.join(".picman.db")).unwrap();
+        assert_eq!(db.get_directory_by_path("trip").unwrap().unwrap().rating, Some(3.5));
+
+        let result = run_rate_propagate(&lib_path, Path::new("trip"), Some(3.0), "max".parse().unwrap(), false);
         assert!(result.is_err());
     }
 }
@@ -6,8 +6,18 @@ use crate::db::Database;
 
 use super::init::DB_FILENAME;
 
+/// What `picman repair` fixed
+#[derive(Debug, Default)]
+pub struct RepairReport {
+    /// Directories whose parent_id didn't match their path
+    pub parents_fixed: usize,
+    /// Whether the kept library totals were off and had to be recomputed
+    pub stats_rebuilt: bool,
+}
+
 /// Run the repair command: fix directory parent_id values based on paths
-pub fn run_repair(library_path: &Path) -> Result<usize> {
+/// and recompute the library totals `status` and `/api/stats` read
+pub fn run_repair(library_path: &Path) -> Result<RepairReport> {
     let library_path = library_path
         .canonicalize()
         .with_context(|| format!("Library path does not exist: {}", library_path.display()))?;
@@ -23,5 +33,8 @@ pub fn run_repair(library_path: &Path) -> Result<usize> {
     let db = Database::open(&db_path)
         .with_context(|| format!("Failed to open database at {}", db_path.display()))?;
 
-    db.repair_directory_parents()
+    Ok(RepairReport {
+        parents_fixed: db.repair_directory_parents()?,
+        stats_rebuilt: db.rebuild_library_stats()?,
+    })
 }
@@ -0,0 +1,158 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::list::{matching_files, ListOptions};
+use crate::db::{Database, ReviewQueue};
+
+/// What `picman review` should do
+#[derive(Debug)]
+pub enum ReviewAction {
+    /// Build a queue called `name` from the files `filter` matches
+    Create { name: String, filter: Box<ListOptions> },
+    Delete { name: String },
+    List,
+}
+
+/// Create, delete or list review queues
+///
+/// # Returns
+/// Every queue afterwards, most recently reviewed first
+pub fn run_review(library_path: &Path, action: ReviewAction) -> Result<Vec<ReviewQueue>> {
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+
+    let db = Database::open(&db_path)?;
+
+    match action {
+        ReviewAction::Create { name, filter } => {
+            create_review_queue(&db, &name, &filter)?;
+        }
+        ReviewAction::Delete { name } => {
+            if !db.delete_review_queue(&name)? {
+                anyhow::bail!("No review queue named '{}'", name);
+            }
+        }
+        ReviewAction::List => {}
+    }
+
+    db.get_review_queues()
+}
+
+/// Save the files `filter` matches as a queue, in path then filename
+/// order; the list is fixed from here on, so rating files doesn't change it
+pub(crate) fn create_review_queue(db: &Database, name: &str, filter: &ListOptions) -> Result<ReviewQueue> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!("Review queue name cannot be empty");
+    }
+    let mut files = matching_files(db, filter)?;
+    if files.is_empty() {
+        anyhow::bail!("No files match {}", describe_filter(filter));
+    }
+    files.sort_by(|(a, a_dir), (b, b_dir)| (a_dir, &a.filename, a.id).cmp(&(b_dir, &b.filename, b.id)));
+    let ids: Vec<i64> = files.iter().map(|(f, _)| f.id).collect();
+
+    db.begin_transaction()?;
+    let id = match db.create_review_queue(name, &describe_filter(filter), &ids, chrono::Utc::now().timestamp()) {
+        Ok(id) => id,
+        Err(e) => {
+            db.rollback()?;
+            return Err(e);
+        }
+    };
+    db.commit()?;
+    db.get_review_queue(id)?
+        .ok_or_else(|| anyhow::anyhow!("Review queue '{}' disappeared", name))
+}
+
+/// The filter a queue was built from, e.g. `unrated, in 2024/`
+pub fn describe_filter(filter: &ListOptions) -> String {
+    let mut parts = Vec::new();
+    if let Some(rating) = filter.min_rating {
+        parts.push(format!("rated {}+", rating));
+    }
+    if filter.unrated {
+        parts.push("unrated".to_string());
+    }
+    if let Some(tag) = &filter.tag {
+        parts.push(format!("tagged {}", tag));
+    }
+    if let Some(dir) = &filter.dir {
+        parts.push(format!("in {}/", dir.trim_end_matches('/')));
+    }
+    if let Some(preset) = &filter.preset {
+        parts.push(format!("preset '{}'", preset));
+    }
+    if let Some(orientation) = filter.orientation {
+        parts.push(orientation.as_str().to_string());
+    }
+    if let Some(print) = filter.print {
+        parts.push(format!("printable at {} ({} dpi)", print.size, print.dpi));
+    }
+    if filter.exposure.is_active() {
+        parts.push("exposure range".to_string());
+    }
+    if filter.include_hidden {
+        parts.push("including hidden".to_string());
+    }
+    if parts.is_empty() {
+        "all files".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::run_init;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_review_create_list_delete() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("2024")).unwrap();
+        for name in ["2024/b.jpg", "2024/a.jpg", "2024/c.jpg", "other.jpg"] {
+            fs::write(temp.path().join(name), "x").unwrap();
+        }
+        run_init(temp.path()).unwrap();
+        let db = Database::open(&temp.path().join(".picman.db")).unwrap();
+        let rated = db.get_file_by_path("2024/c.jpg").unwrap().unwrap();
+        db.set_file_rating(rated.id, Some(4.0)).unwrap();
+
+        let filter = || ListOptions { unrated: true, dir: Some("2024".to_string()), ..Default::default() };
+        let create = |name: &str| ReviewAction::Create { name: name.to_string(), filter: Box::new(filter()) };
+        let queues = run_review(temp.path(), create("June")).unwrap();
+        assert_eq!(queues.len(), 1);
+        assert_eq!((queues[0].name.as_str(), queues[0].total), ("June", 2));
+        assert_eq!(queues[0].filter, "unrated, in 2024/");
+
+        let files: Vec<String> = db
+            .get_review_queue_files(queues[0].id)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.file.filename)
+            .collect();
+        assert_eq!(files, ["a.jpg", "b.jpg"]);
+
+        assert!(run_review(temp.path(), create("june")).is_err());
+        assert!(run_review(temp.path(), create(" ")).is_err());
+        let nothing = ReviewAction::Create {
+            name: "Empty".to_string(),
+            filter: Box::new(ListOptions { tag: Some("none".to_string()), ..Default::default() }),
+        };
+        assert!(run_review(temp.path(), nothing).is_err());
+        assert_eq!(run_review(temp.path(), ReviewAction::List).unwrap().len(), 1);
+
+        let delete = || ReviewAction::Delete { name: "JUNE".to_string() };
+        assert!(run_review(temp.path(), delete()).unwrap().is_empty());
+        assert!(run_review(temp.path(), delete()).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::db::Database;
+use crate::screenshot::{is_likely_screenshot, SCREENSHOT_TAG};
+
+use super::init::DB_FILENAME;
+
+/// Outcome of looking for screenshots
+#[derive(Debug, Default, PartialEq)]
+pub struct ScreenshotReport {
+    /// Images checked for the first time
+    pub checked: usize,
+    /// Of those, tagged `auto:screenshot`
+    pub flagged: usize,
+}
+
+/// Tag likely screenshots `auto:screenshot` so they can be filtered out or
+/// purged. Works from the database alone (filename, dimensions and the
+/// camera read from EXIF), so it runs after sync has read them. Each image
+/// is only checked once: removing the tag from a real photo sticks.
+pub fn run_flag_screenshots(library_path: &Path) -> Result<ScreenshotReport> {
+    let library_path = library_path
+        .canonicalize()
+        .with_context(|| format!("Library path does not exist: {}", library_path.display()))?;
+
+    let db_path = library_path.join(DB_FILENAME);
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            db_path.display()
+        );
+    }
+
+    let db = Database::open(&db_path)?;
+    let files = db.get_files_needing_screenshot_check()?;
+    let mut report = ScreenshotReport { checked: files.len(), flagged: 0 };
+
+    db.begin_transaction()?;
+    for file in files {
+        if is_likely_screenshot(&file.filename, file.width, file.height, file.camera.as_deref()) {
+            db.add_file_tag(file.id, SCREENSHOT_TAG)?;
+            report.flagged += 1;
+        }
+        db.mark_screenshot_checked(file.id)?;
+    }
+    db.commit()?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::run_init;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_flag_screenshots_once() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("phone")).unwrap();
+        fs::write(root.join("phone/Screenshot_20240301-101233.png"), "png").unwrap();
+        fs::write(root.join("phone/IMG_0001.jpg"), "jpeg").unwrap();
+        run_init(root).unwrap();
+
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let id = |name: &str| db.get_file_by_path(name).unwrap().unwrap().id;
+        let shot = id("phone/Screenshot_20240301-101233.png");
+        let photo = id("phone/IMG_0001.jpg");
+        db.set_file_gear(shot, None, None).unwrap();
+        db.set_file_gear(photo, Some("Pixel 7"), None).unwrap();
+
+        let report = run_flag_screenshots(root).unwrap();
+        assert_eq!(report, ScreenshotReport { checked: 2, flagged: 1 });
+        assert_eq!(db.get_file_tags(shot).unwrap(), vec![SCREENSHOT_TAG]);
+        assert!(db.get_file_tags(photo).unwrap().is_empty());
+
+        // Untagging a false positive sticks
+        db.remove_file_tag(shot, SCREENSHOT_TAG).unwrap();
+        assert_eq!(run_flag_screenshots(root).unwrap(), ScreenshotReport::default());
+        assert!(db.get_file_tags(shot).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+
+use crate::db::{CaptureDay, Database, GearField, GearStat};
+
+use super::init::DB_FILENAME;
+
+/// Per-camera or per-lens file counts and ratings. Reflects the EXIF read by
+/// the last sync, so run `picman sync` first for new files to be counted.
+pub fn run_stats(library_path: &Path, by: GearField) -> Result<Vec<GearStat>> {
+    let db_path = library_path.join(DB_FILENAME);
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            db_path.display()
+        );
+    }
+
+    let db = Database::open(&db_path)?;
+    db.get_gear_stats(by)
+}
+
+/// Images captured on each day of `year`, by the EXIF capture date the last
+/// sync read
+pub fn run_heatmap(library_path: &Path, year: i32) -> Result<Vec<CaptureDay>> {
+    let db_path = library_path.join(DB_FILENAME);
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            db_path.display()
+        );
+    }
+
+    let db = Database::open(&db_path)?;
+    db.get_capture_days(year)
+}
+
+/// Shades from a few photos up to the busiest day
+const HEAT_LEVELS: [char; 4] = ['░', '▒', '▓', '█'];
+
+/// Draw a year as a contribution-style calendar: one column per week
+/// (Monday first), one row per weekday, each day shaded by its share of the
+/// busiest day. Days without photos show as `·`.
+pub fn render_heatmap(year: i32, days: &[CaptureDay]) -> Vec<String> {
+    let (Some(jan1), Some(next_jan1)) = (NaiveDate::from_ymd_opt(year, 1, 1), NaiveDate::from_ymd_opt(year + 1, 1, 1))
+    else {
+        return Vec::new();
+    };
+    let offset = jan1.weekday().num_days_from_monday() as usize;
+    let weeks = (offset + (next_jan1 - jan1).num_days() as usize).div_ceil(7);
+    let max = days.iter().map(|d| d.count).max().unwrap_or(0);
+
+    let mut grid = vec![vec![' '; weeks]; 7];
+    for (i, date) in jan1.iter_days().take_while(|d| d.year() == year).enumerate() {
+        let count = days.iter().find(|d| d.date == date).map_or(0, |d| d.count);
+        let cell = if count == 0 { '·' } else { HEAT_LEVELS[(count * 4).div_ceil(max) - 1] };
+        grid[(offset + i) % 7][(offset + i) / 7] = cell;
+    }
+
+    // Month names over the week holding each month's first day
+    let mut months = vec![' '; weeks + 3];
+    let mut free_from = 0;
+    for month in 1..=12 {
+        let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+        let column = (offset + first.ordinal0() as usize) / 7;
+        if column >= free_from {
+            for (j, c) in first.format("%b").to_string().chars().enumerate() {
+                months[column + j] = c;
+            }
+            free_from = column + 4;
+        }
+    }
+
+    let mut lines = vec![format!("    {}", months.iter().collect::<String>().trim_end())];
+    for (row, label) in grid.iter().zip(["Mon", "", "Wed", "", "Fri", "", ""]) {
+        lines.push(format!("{:<3} {}", label, row.iter().collect::<String>().trim_end()));
+    }
+    let total: usize = days.iter().map(|d| d.count).sum();
+    lines.push(String::new());
+    lines.push(format!("    Less · {} More", HEAT_LEVELS.iter().map(char::to_string).collect::<Vec<_>>().join(" ")));
+    lines.push(format!("{} photos on {} days in {}", total, days.len(), year));
+    if let Some(busiest) = days.iter().max_by_key(|d| (d.count, std::cmp::Reverse(d.date))) {
+        lines.push(format!("Busiest day: {} ({} photos)", busiest.date, busiest.count));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_stats_by_lens() {
+        let temp = TempDir::new().unwrap();
+        let db = Database::open(&temp.path().join(DB_FILENAME)).unwrap();
+        let dir = db.insert_directory("", None, None).unwrap();
+        let a = db.insert_file(dir, "a.jpg", 1, 0, Some("image")).unwrap();
+        let b = db.insert_file(dir, "b.jpg", 1, 0, Some("image")).unwrap();
+        db.set_file_gear(a, Some("Fujifilm X-T3"), Some("XF35mmF1.4 R")).unwrap();
+        db.set_file_gear(b, Some("Fujifilm X-T3"), Some("XF35mmF1.4 R")).unwrap();
+        db.set_file_rating(b, Some(3.0)).unwrap();
+        drop(db);
+
+        let stats = run_stats(temp.path(), GearField::Lens).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].name.as_deref(), Some("XF35mmF1.4 R"));
+        assert_eq!((stats[0].files, stats[0].rated), (2, 1));
+        assert_eq!(stats[0].average_rating, Some(3.0));
+
+        assert!(run_stats(&temp.path().join("missing"), GearField::Camera).is_err());
+    }
+
+    #[test]
+    fn test_render_heatmap() {
+        let day = |m, d, count| CaptureDay { date: NaiveDate::from_ymd_opt(2024, m, d).unwrap(), count };
+        // 2024 starts on a Monday
+        let lines = render_heatmap(2024, &[day(1, 1, 8), day(1, 3, 1), day(12, 31, 4)]);
+        assert!(lines[0].starts_with("    Jan Feb"));
+        assert!(lines[1].starts_with("Mon █·"));
+        assert!(lines[3].starts_with("Wed ░·"));
+        assert!(lines[7].starts_with("    ·"));
+        // Dec 31 is a Tuesday, in the 53rd week
+        assert_eq!(lines[2].chars().count(), 4 + 53);
+        assert!(lines[2].ends_with('▒'));
+        assert_eq!(lines[10], "13 photos on 3 days in 2024");
+        assert_eq!(lines[11], "Busiest day: 2024-01-01 (8 photos)");
+    }
+}
@@ -7,8 +7,13 @@ use anyhow::Result;
 use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
 
-use crate::db::Database;
-use crate::thumbnails::{has_dir_preview, has_thumbnail, is_image_file, is_video_file};
+use crate::db::{Database, File};
+use crate::maintenance::{load_schedule, local_from_unix};
+use crate::thumbnails::{
+    has_dir_preview, has_thumbnail, image_decoder, is_image_file, is_thumbnailable_file,
+    ImageDecoder,
+};
+use crate::library_path::LibraryPath;
 
 /// Show library status and health information
 pub fn run_status(library_path: &Path) -> Result<()> {
@@ -22,6 +27,21 @@ pub fn run_status(library_path: &Path) -> Result<()> {
 
     let db = Database::open(&db_path)?;
 
+    // Totals are kept by the database, so these show before the slow checks
+    let summary = db.library_summary()?;
+    println!("Library: {}", library_path.display());
+    println!("  Directories: {}", summary.directories);
+    println!(
+        "  Files: {} ({:.1} GB; {} images, {} videos, {} documents)",
+        summary.files,
+        summary.bytes as f64 / (1024.0 * 1024.0 * 1024.0),
+        summary.images,
+        summary.videos,
+        summary.documents
+    );
+    println!("  Rated: {}, tagged: {}", summary.rated, summary.tagged);
+    println!();
+
     let spinner = ProgressBar::new_spinner();
     spinner.set_style(
         ProgressStyle::default_spinner()
@@ -36,9 +56,6 @@ pub fn run_status(library_path: &Path) -> Result<()> {
     spinner.set_message("Loading files...");
     let files = db.get_all_files()?;
 
-    // Count files without hash
-    let files_without_hash = files.iter().filter(|f| f.hash.is_none()).count();
-
     // Build dir_id -> path lookup
     let dir_paths: HashMap<i64, String> = directories
         .iter()
@@ -60,13 +77,9 @@ pub fn run_status(library_path: &Path) -> Result<()> {
             }
 
             let dir_path = dir_paths.get(&file.directory_id).map(|s| s.as_str()).unwrap_or("");
-            let path = if dir_path.is_empty() {
-                library_path.join(&file.filename)
-            } else {
-                library_path.join(dir_path).join(&file.filename)
-            };
+            let path = LibraryPath::file(dir_path, &file.filename).absolute(library_path);
 
-            if (is_image_file(&path) || is_video_file(&path)) && !has_thumbnail(&path) {
+            if is_thumbnailable_file(&path) && !has_thumbnail(&path) {
                 let top_dir = dir_path.split('/').next().unwrap_or("(root)").to_string();
                 Some(if top_dir.is_empty() { "(root)".to_string() } else { top_dir })
             } else {
@@ -103,12 +116,6 @@ pub fn run_status(library_path: &Path) -> Result<()> {
 
     spinner.finish_and_clear();
 
-    // Print status
-    println!("Library: {}", library_path.display());
-    println!("  Directories: {}", directories.len());
-    println!("  Files: {}", files.len());
-    println!();
-
     // Missing thumbnails
     if total_missing_thumbnails == 0 {
         println!("  Missing thumbnails: none");
@@ -132,10 +139,167 @@ pub fn run_status(library_path: &Path) -> Result<()> {
     }
 
     // Files without hash
-    if files_without_hash == 0 {
+    if summary.without_hash == 0 {
         println!("  Files without hash: none");
     } else {
-        println!("  Files without hash: {}", files_without_hash);
+        println!("  Files without hash: {}", summary.without_hash);
+    }
+
+    let (archived, stubbed) = db.count_archived_files()?;
+    if archived > 0 {
+        println!("  Archived: {} files ({} stubbed locally)", archived, stubbed);
+    }
+
+    let (linked, linked_bytes) = db.count_linked_files()?;
+    if linked > 0 {
+        println!(
+            "  Linked duplicates: {} files ({:.1} MB reclaimed)",
+            linked,
+            linked_bytes as f64 / (1024.0 * 1024.0)
+        );
+    }
+
+    print_undecodable_formats(&files);
+    print_name_collisions(&db)?;
+    print_thumbnail_failures(&db)?;
+    print_maintenance(library_path, &db)?;
+
+    Ok(())
+}
+
+/// Image formats in the library that no installed decoder can read
+fn print_undecodable_formats(files: &[File]) {
+    let mut by_extension: HashMap<String, (usize, &'static str)> = HashMap::new();
+    for file in files {
+        let path = Path::new(&file.filename);
+        if !is_image_file(path) {
+            continue;
+        }
+        if let ImageDecoder::Unavailable(hint) = image_decoder(path) {
+            let ext = path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            by_extension.entry(ext).or_insert((0, hint)).0 += 1;
+        }
+    }
+    if by_extension.is_empty() {
+        return;
+    }
+
+    let mut sorted: Vec<_> = by_extension.into_iter().collect();
+    sorted.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.0.cmp(&b.0)));
+    println!("  Undecodable images:");
+    for (ext, (count, hint)) in sorted {
+        println!("    {:>5}  .{} (install {})", count, ext, hint);
+    }
+}
+
+/// Sibling files whose names clash on case-insensitive filesystems
+fn print_name_collisions(db: &Database) -> Result<()> {
+    let groups = db.get_all_name_collisions()?;
+    if groups.is_empty() {
+        println!("  Name collisions: none");
+        return Ok(());
+    }
+
+    let files: usize = groups.iter().map(|(_, names)| names.len()).sum();
+    println!(
+        "  Name collisions: {} files in {} groups (clash on macOS/Windows)",
+        files,
+        groups.len()
+    );
+    for (dir, names) in groups.iter().take(10) {
+        let dir = if dir.is_empty() { "." } else { dir.as_str() };
+        println!("    {}: {}", dir, names.join(", "));
+    }
+    if groups.len() > 10 {
+        println!("    ... and {} more", groups.len() - 10);
+    }
+
+    Ok(())
+}
+
+/// Recorded web thumbnail failures, with the most common reasons
+fn print_thumbnail_failures(db: &Database) -> Result<()> {
+    let failures = db.get_thumbnail_failures()?;
+    if failures.is_empty() {
+        println!("  Web thumbnail failures: none");
+        return Ok(());
+    }
+
+    let permanent = failures.iter().filter(|f| f.permanent).count();
+    println!(
+        "  Web thumbnail failures: {} ({} permanent, {} will be retried)",
+        failures.len(),
+        permanent,
+        failures.len() - permanent
+    );
+
+    let mut by_reason: HashMap<&str, usize> = HashMap::new();
+    for failure in &failures {
+        *by_reason.entry(failure.reason.as_str()).or_insert(0) += 1;
+    }
+    let mut sorted: Vec<_> = by_reason.into_iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    for (reason, count) in sorted.iter().take(3) {
+        println!("    {:>5}  {}", count, reason);
+    }
+    if permanent > 0 {
+        println!("    (retry with 'picman thumbnails --retry-failed')");
+    }
+
+    Ok(())
+}
+
+/// Last run (and next scheduled run) of each maintenance task
+fn print_maintenance(library_path: &Path, db: &Database) -> Result<()> {
+    let runs = db.get_maintenance_runs()?;
+    let scheduled = match load_schedule(library_path) {
+        Ok(scheduled) => scheduled,
+        Err(e) => {
+            println!();
+            println!("  Maintenance: invalid config ({:#})", e);
+            Vec::new()
+        }
+    };
+
+    if runs.is_empty() && scheduled.is_empty() {
+        return Ok(());
+    }
+
+    let format_time = |t: chrono::NaiveDateTime| t.format("%Y-%m-%d %H:%M").to_string();
+
+    // Configured tasks first (in run order), then any with history only
+    let mut names: Vec<&str> = scheduled.iter().map(|s| s.task.name()).collect();
+    for run in &runs {
+        if !names.contains(&run.task.as_str()) {
+            names.push(&run.task);
+        }
+    }
+
+    println!();
+    println!("  Maintenance:");
+    for name in names {
+        let last = match runs.iter().find(|r| r.task == name) {
+            Some(run) => format!(
+                "last {} {} ({:.1}s){}",
+                local_from_unix(run.last_run).map(format_time).unwrap_or_default(),
+                if run.success { "ok" } else { "FAILED" },
+                run.duration_ms as f64 / 1000.0,
+                run.summary.as_deref().map(|s| format!(": {}", s)).unwrap_or_default()
+            ),
+            None => "never run".to_string(),
+        };
+        let next = scheduled
+            .iter()
+            .find(|s| s.task.name() == name)
+            .map(|s| match s.next_run {
+                Some(t) => format!(" | next {} ({})", format_time(t), s.schedule),
+                None => format!(" | never fires ({})", s.schedule),
+            })
+            .unwrap_or_default();
+        println!("    {:<14} {}{}", name, last, next);
     }
 
     Ok(())
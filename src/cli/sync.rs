@@ -1,49 +1,86 @@
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::Arc;
 
-use anyhow::{Context, Result};
-use indicatif::{ProgressBar, ProgressStyle};
-use tracing::{debug, info, instrument};
+use anyhow::{bail, Context, Result};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde::Serialize;
+use tracing::{debug, info, instrument, warn};
 
-use crate::db::Database;
+use crate::config::Config;
+use crate::db::{Database, Directory, File, MaintenanceRun, SYNC_TASK};
+use crate::hash::compute_file_hash_with;
+use crate::filenames::{match_respelled, normalize};
 use crate::scanner::{read_dimensions, MediaType, ScannedFile, Scanner};
-use crate::thumbnails::{compute_thumbnail_path, compute_video_thumbnail_path, is_image_file, is_video_file};
+use crate::thumbnails::{
+    compute_document_thumbnail_path, compute_thumbnail_path, compute_video_thumbnail_path,
+    is_document_file, is_image_file, is_video_file,
+};
+use crate::vfs::{open_library_fs_with_progress, ScanProgress};
+use crate::library_path::LibraryPath;
 
-use super::post_process::{backfill_dimensions, compute_perceptual_hashes, hash_files, tag_orientation};
+use super::post_process::{
+    backfill_dimensions, backfill_durations, backfill_gear, compute_perceptual_hashes, hash_files, tag_orientation,
+};
 
 use super::init::DB_FILENAME;
+use super::lock::SyncLock;
 
 /// Statistics from the sync operation
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize)]
 pub struct SyncStats {
     pub directories_added: usize,
     pub directories_removed: usize,
     pub directories_moved: usize,
     pub files_added: usize,
+    /// Files renamed within their directory, kept with their metadata
+    pub files_renamed: usize,
+    /// Files moved to another directory, kept with their metadata
+    pub files_moved: usize,
     pub files_removed: usize,
     pub files_modified: usize,
     pub files_hashed: usize,
     pub hash_errors: usize,
     pub orientation_tagged: usize,
     pub dimensions_backfilled: usize,
+    /// Images whose EXIF named a camera or lens, read this sync
+    pub gear_read: usize,
+    /// Videos whose length ffprobe read this sync
+    pub durations_read: usize,
     pub perceptual_hashed: usize,
     pub perceptual_hash_errors: usize,
+    /// Files whose names clash with a sibling on case-insensitive filesystems
+    pub name_collisions: usize,
+    /// Directories with files added or modified, whose thumbnails are now missing
+    #[serde(skip)]
+    pub changed_directories: HashSet<i64>,
 }
 
-/// Metadata to preserve when a file is moved (as part of directory move)
-struct FileMetadata {
-    rating: Option<i32>,
-    tags: Vec<String>,
+impl SyncStats {
+    /// Count another pass of the same sync in
+    fn add(&mut self, other: SyncStats) {
+        self.directories_added += other.directories_added;
+        self.directories_removed += other.directories_removed;
+        self.directories_moved += other.directories_moved;
+        self.files_added += other.files_added;
+        self.files_renamed += other.files_renamed;
+        self.files_moved += other.files_moved;
+        self.files_removed += other.files_removed;
+        self.files_modified += other.files_modified;
+        self.changed_directories.extend(other.changed_directories);
+    }
 }
 
-/// Metadata to preserve when a directory is moved
-struct DirectoryMetadata {
-    rating: Option<i32>,
-    tags: Vec<String>,
-    /// Files in this directory with their metadata (keyed by filename)
-    files: HashMap<String, FileMetadata>,
-    /// The old directory path (for computing old thumbnail paths)
-    old_path: String,
+/// Which part of the library a sync looks at
+#[derive(Clone, Copy)]
+enum SyncMode<'a> {
+    /// Every directory and file; `resume` skips the top-level directories
+    /// an interrupted full sync already committed
+    Full { resume: bool },
+    /// Files only in directories whose mtime changed
+    Incremental,
+    /// One directory and everything below it
+    Subtree(&'a str),
 }
 
 /// Run the sync command: update database to match filesystem.
@@ -52,7 +89,8 @@ struct DirectoryMetadata {
 /// which is much faster for large libraries on slow storage (HDD).
 /// Pass `full: true` to force a complete rescan of all files.
 pub fn run_sync(library_path: &Path, compute_hashes: bool, tag_orientation_flag: bool, full: bool) -> Result<SyncStats> {
-    run_sync_impl(library_path, compute_hashes, tag_orientation_flag, false, !full)
+    let mode = if full { SyncMode::Full { resume: false } } else { SyncMode::Incremental };
+    run_sync_impl(library_path, compute_hashes, tag_orientation_flag, false, mode, None)
 }
 
 /// Run sync with perceptual hashing enabled
@@ -63,7 +101,37 @@ pub fn run_sync_with_perceptual(
     perceptual: bool,
     full: bool,
 ) -> Result<SyncStats> {
-    run_sync_impl(library_path, compute_hashes, tag_orientation_flag, perceptual, !full)
+    let mode = if full { SyncMode::Full { resume: false } } else { SyncMode::Incremental };
+    run_sync_impl(library_path, compute_hashes, tag_orientation_flag, perceptual, mode, None)
+}
+
+/// Sync only `subdir` (library-relative) and everything below it, e.g. after
+/// copying new photos into one folder. Every directory and file in the
+/// subtree is checked; nothing outside it is touched, and the library-wide
+/// dimension backfill is skipped.
+pub fn run_sync_subdir(
+    library_path: &Path,
+    subdir: &str,
+    compute_hashes: bool,
+    tag_orientation_flag: bool,
+    perceptual: bool,
+) -> Result<SyncStats> {
+    let subdir = subdir.trim_start_matches("./").trim_matches('/');
+    let mode = if subdir.is_empty() { SyncMode::Full { resume: false } } else { SyncMode::Subtree(subdir) };
+    run_sync_impl(library_path, compute_hashes, tag_orientation_flag, perceptual, mode, None)
+}
+
+/// Continue a full sync that was interrupted, skipping the top-level
+/// directories it already committed. Without one to continue this is a
+/// plain full sync.
+pub fn run_sync_resume(
+    library_path: &Path,
+    compute_hashes: bool,
+    tag_orientation_flag: bool,
+    perceptual: bool,
+) -> Result<SyncStats> {
+    let mode = SyncMode::Full { resume: true };
+    run_sync_impl(library_path, compute_hashes, tag_orientation_flag, perceptual, mode, None)
 }
 
 /// Run an incremental sync - only scan files in changed directories.
@@ -72,12 +140,20 @@ pub fn run_sync_incremental(library_path: &Path) -> Result<SyncStats> {
     run_sync(library_path, false, false, false)
 }
 
+/// Incremental sync that reports into `progress` instead of drawing progress
+/// bars, for running behind the TUI. Cancelling `progress` abandons the sync
+/// without changes if it hasn't started writing yet.
+pub fn run_sync_incremental_with_progress(library_path: &Path, progress: Arc<ScanProgress>) -> Result<SyncStats> {
+    run_sync_impl(library_path, false, false, false, SyncMode::Incremental, Some(progress))
+}
+
 fn run_sync_impl(
     library_path: &Path,
     compute_hashes: bool,
     tag_orientation_flag: bool,
     perceptual: bool,
-    incremental: bool,
+    mode: SyncMode,
+    progress: Option<Arc<ScanProgress>>,
 ) -> Result<SyncStats> {
     let library_path = library_path
         .canonicalize()
@@ -91,18 +167,59 @@ fn run_sync_impl(
         );
     }
 
+    // Held until the end, including hashing and the other post-processing
+    let _lock = SyncLock::acquire(&library_path)?;
+    let started = std::time::Instant::now();
+
     let db = Database::open(&db_path)
         .with_context(|| format!("Failed to open database at {}", db_path.display()))?;
 
-    let scanner = Scanner::new(library_path.clone());
-    let mut stats = if incremental {
-        sync_database_incremental(&db, &scanner, &library_path)?
-    } else {
-        sync_database(&db, &scanner, &library_path)?
+    let quiet = progress.is_some();
+    let progress = progress.unwrap_or_default();
+    let fs = open_library_fs_with_progress(&library_path, progress.clone())?;
+    if fs.is_remote() && (compute_hashes || tag_orientation_flag || perceptual) {
+        bail!(
+            "Hashing and orientation tagging read every original; \
+             they are not available for remote libraries"
+        );
+    }
+    let remote = fs.is_remote();
+    let scanner = Scanner::with_fs(library_path.clone(), fs);
+    let mut stats = match mode {
+        SyncMode::Full { resume } => sync_database(&db, &scanner, &library_path, resume, &progress, quiet)?,
+        SyncMode::Incremental => sync_database_incremental(&db, &scanner, &progress, quiet)?,
+        SyncMode::Subtree(subdir) => sync_database_subtree(&db, &scanner, subdir, None)?.0,
     };
 
-    // Backfill dimensions for existing image files with NULL width/height
-    stats.dimensions_backfilled = backfill_dimensions(&db, &library_path)?;
+    // Dotfiles indexed while `[scan] dotfiles` was on. An incremental sync
+    // only drops those in directories that changed, so clear them all once
+    // the setting is switched off (or was never recorded).
+    let dotfiles = Config::load(&library_path)?.scan.dotfiles;
+    if db.applied_dotfiles()? != Some(dotfiles) {
+        if !dotfiles {
+            let (dirs, files) = remove_dotfiles(&db)?;
+            if dirs + files > 0 {
+                info!(dirs, files, "removed dotfiles from the database");
+            }
+            stats.directories_removed += dirs;
+            stats.files_removed += files;
+        }
+        db.set_applied_dotfiles(dotfiles)?;
+    }
+
+    stats.name_collisions = db.count_name_collisions()?;
+    if stats.name_collisions > 0 {
+        warn!(files = stats.name_collisions, "filenames collide case-insensitively");
+    }
+
+    // Backfill dimensions for image files with NULL width/height,
+    // camera/lens for images whose EXIF hasn't been read, and video lengths.
+    // Remote originals aren't local, so this would fetch the whole library.
+    if !remote && !matches!(mode, SyncMode::Subtree(_)) {
+        stats.dimensions_backfilled = backfill_dimensions(&db, &library_path, quiet)?;
+        stats.gear_read = backfill_gear(&db, &library_path, quiet)?;
+        stats.durations_read = backfill_durations(&db, &library_path, quiet)?;
+    }
 
     // Tag orientation for image files (only if requested)
     if tag_orientation_flag {
@@ -121,9 +238,47 @@ fn run_sync_impl(
         stats.perceptual_hash_errors = errors;
     }
 
+    // The TUI dashboard and `picman status` show when the library was last synced
+    let scope = match mode {
+        SyncMode::Subtree(subdir) => format!("{}: ", subdir),
+        _ => String::new(),
+    };
+    let run = MaintenanceRun {
+        task: SYNC_TASK.to_string(),
+        last_run: chrono::Utc::now().timestamp(),
+        duration_ms: started.elapsed().as_millis() as i64,
+        success: true,
+        summary: Some(format!(
+            "{}+{} -{} ~{} files",
+            scope, stats.files_added, stats.files_removed, stats.files_modified
+        )),
+    };
+    if let Err(e) = db.record_maintenance_run(&run) {
+        warn!(error = %e, "failed to record sync time");
+    }
+
     Ok(stats)
 }
 
+/// Delete dot-prefixed files and directories (with what's below them) from
+/// the database. Returns the directories and files removed.
+fn remove_dotfiles(db: &Database) -> Result<(usize, usize)> {
+    let file_ids = db.get_dotfile_ids()?;
+    let dirs = db.get_dot_directories()?;
+    if file_ids.is_empty() && dirs.is_empty() {
+        return Ok((0, 0));
+    }
+    db.begin_transaction()?;
+    for &id in &file_ids {
+        db.delete_file(id)?;
+    }
+    for dir in &dirs {
+        db.delete_directory(dir.id)?;
+    }
+    db.commit()?;
+    Ok((dirs.len(), file_ids.len()))
+}
+
 /// Look up the parent directory ID for a given path.
 /// Checks the local cache first, then falls back to a database lookup.
 fn resolve_parent_id(
@@ -174,6 +329,184 @@ fn insert_new_directory(
     Ok(id)
 }
 
+/// Re-record directories the database has under one Unicode spelling and
+/// the scan found under another (decomposed vs composed: a library synced
+/// before names were normalized, or copied between macOS and Linux), so they
+/// keep their ratings, tags and files instead of being dropped and added
+/// back. Deepest first, so renaming a parent never rewrites a child that is
+/// renamed on its own.
+fn respell_directories<'a>(db: &Database, fs_dirs: impl Iterator<Item = &'a String>) -> Result<usize> {
+    let db_dirs = db.get_all_directories()?;
+    let ids: HashMap<&str, i64> = db_dirs.iter().map(|d| (d.path.as_str(), d.id)).collect();
+    let old: HashSet<&str> = ids.keys().copied().collect();
+    let new: HashSet<&str> = fs_dirs.map(String::as_str).collect();
+
+    let mut pairs = match_respelled(&old, &new);
+    pairs.sort_by_key(|(old, _)| std::cmp::Reverse(old.matches('/').count()));
+    for (old, new) in &pairs {
+        debug!(old, new, "directory name changed normalization form");
+        db.rename_directory(ids[old], old, new)?;
+    }
+    Ok(pairs.len())
+}
+
+/// Same for files: rename those of one directory that the scan found there
+/// spelled another way, in the database and in `files`
+fn respell_files<'a>(
+    db: &Database,
+    files: impl IntoIterator<Item = &'a mut File>,
+    scanned: &HashSet<&str>,
+) -> Result<usize> {
+    let mut files: Vec<&mut File> = files.into_iter().collect();
+    let names: Vec<String> = files.iter().map(|f| f.filename.clone()).collect();
+    let old: HashSet<&str> = names.iter().map(String::as_str).collect();
+    let renames: HashMap<&str, &str> = match_respelled(&old, scanned).into_iter().collect();
+
+    for file in files.iter_mut() {
+        if let Some(new) = renames.get(file.filename.as_str()) {
+            debug!(old = file.filename, new, "file name changed normalization form");
+            db.rename_file(file.id, new)?;
+            file.filename = new.to_string();
+        }
+    }
+    Ok(renames.len())
+}
+
+/// Key a moved directory is recognized by: its name, in either Unicode form
+fn move_key(path: &str) -> String {
+    let name = path.rsplit_once('/').map_or(path, |(_, name)| name);
+    normalize(name).into_owned()
+}
+
+/// The scanned files, by directory
+fn scanned_by_dir(fs_files: &[ScannedFile]) -> HashMap<&str, Vec<&ScannedFile>> {
+    let mut by_dir: HashMap<&str, Vec<&ScannedFile>> = HashMap::new();
+    for file in fs_files {
+        by_dir.entry(&file.directory).or_default().push(file);
+    }
+    by_dir
+}
+
+/// Files gone from where the database had them and files new to it, held
+/// until the end of a sync so a file moved to another directory keeps its
+/// row instead of being deleted and added again
+#[derive(Default)]
+struct PendingMoves {
+    /// Rows whose file is gone, with their directory's path
+    missing: Vec<(File, String)>,
+    /// Rows inserted this sync: row ID, directory ID and the file found
+    added: Vec<(i64, i64, ScannedFile)>,
+}
+
+/// Bring the database's files of the directory at `dir_path` in line with
+/// `scanned`, the files the scan found there: respelled names are
+/// re-recorded and files renamed within the directory keep their row
+/// (rating, tags, attributes, hash) under the new name. The rest of the
+/// missing ones go to `pending`; new files are left for [`upsert_file`].
+fn collect_missing_files(
+    db: &Database,
+    mut db_files: Vec<File>,
+    dir_path: &str,
+    scanned: &[&ScannedFile],
+    stats: &mut SyncStats,
+    pending: &mut PendingMoves,
+) -> Result<()> {
+    let names: HashSet<&str> = scanned.iter().map(|f| f.filename.as_str()).collect();
+    respell_files(db, &mut db_files, &names)?;
+
+    let known: HashSet<String> = db_files.iter().map(|f| f.filename.clone()).collect();
+    let (missing, _): (Vec<File>, Vec<File>) = db_files.into_iter().partition(|f| !names.contains(f.filename.as_str()));
+    let added: Vec<&ScannedFile> = scanned.iter().copied().filter(|f| !known.contains(&f.filename)).collect();
+
+    let renames = match_moved_files(db, &missing, &added)?;
+    for (i, file) in missing.into_iter().enumerate() {
+        match renames.get(&i) {
+            Some(&j) => {
+                let new = added[j];
+                debug!(old = file.filename, new = new.filename, "file renamed");
+                db.rename_file(file.id, &new.filename)?;
+                move_thumbnails(&new.path.with_file_name(&file.filename), &new.path);
+                stats.files_renamed += 1;
+            }
+            None => pending.missing.push((file, dir_path.to_string())),
+        }
+    }
+    Ok(())
+}
+
+/// Move each pending missing file that turned up in another directory to
+/// its new place, keeping its row; the row inserted for the new file is
+/// dropped. Returns the missing files that didn't turn up, for the caller
+/// to delete.
+fn apply_moves(db: &Database, root: &Path, pending: PendingMoves, stats: &mut SyncStats) -> Result<Vec<File>> {
+    let PendingMoves { missing, added } = pending;
+    let (files, dir_paths): (Vec<File>, Vec<String>) = missing.into_iter().unzip();
+    let found: Vec<&ScannedFile> = added.iter().map(|(_, _, f)| f).collect();
+    let moves = match_moved_files(db, &files, &found)?;
+
+    let mut gone = Vec::new();
+    for (i, (file, dir_path)) in files.into_iter().zip(dir_paths).enumerate() {
+        let Some(&j) = moves.get(&i) else {
+            gone.push(file);
+            continue;
+        };
+        let (new_id, dir_id, new) = &added[j];
+        db.delete_file(*new_id)?;
+        db.move_file(file.id, *dir_id, &new.filename)?;
+        if file.mtime != new.mtime {
+            db.set_file_mtime(file.id, new.mtime)?;
+        }
+        let old_path = LibraryPath::file(&dir_path, &file.filename).absolute(root);
+        move_thumbnails(&old_path, &new.path);
+        debug!(old = %old_path.display(), new = new.relative_path, "file moved");
+        stats.files_moved += 1;
+        stats.files_added -= 1;
+    }
+    Ok(gone)
+}
+
+/// Which `added` file (by index) each `missing` one (by index) became: the
+/// only one of the same size and mtime, or, when the old file was hashed,
+/// the only one of the same size with its content hash. Pairs that aren't
+/// one-to-one are left alone.
+fn match_moved_files(db: &Database, missing: &[File], added: &[&ScannedFile]) -> Result<HashMap<usize, usize>> {
+    let mut by_size: HashMap<i64, Vec<usize>> = HashMap::new();
+    for (j, file) in added.iter().enumerate() {
+        by_size.entry(file.size as i64).or_default().push(j);
+    }
+
+    let mut matches: HashMap<usize, usize> = HashMap::new();
+    let mut hashes: HashMap<(usize, &str), Option<String>> = HashMap::new();
+    for (i, old) in missing.iter().enumerate() {
+        let same_size = by_size.get(&old.size).map_or(&[][..], Vec::as_slice);
+        let same_mtime: Vec<usize> = same_size.iter().copied().filter(|&j| added[j].mtime == old.mtime).collect();
+        if let [j] = same_mtime[..] {
+            matches.insert(i, j);
+            continue;
+        }
+        let (Some(hash), Some(algorithm)) = (&old.hash, db.get_file_hash_algorithm(old.id)?) else { continue };
+        let same_content: Vec<usize> = same_size
+            .iter()
+            .copied()
+            .filter(|&j| {
+                let new_hash = hashes
+                    .entry((j, algorithm.name()))
+                    .or_insert_with(|| compute_file_hash_with(&added[j].path, algorithm, |_| {}).ok());
+                new_hash.as_ref() == Some(hash)
+            })
+            .collect();
+        if let [j] = same_content[..] {
+            matches.insert(i, j);
+        }
+    }
+
+    let mut claimed: HashMap<usize, usize> = HashMap::new();
+    for &j in matches.values() {
+        *claimed.entry(j).or_default() += 1;
+    }
+    Ok(matches.into_iter().filter(|(_, j)| claimed[j] == 1).collect())
+}
+
 /// Upsert a file: update if modified, insert if new.
 /// Returns `Some(file_id)` when a new file was inserted.
 fn upsert_file(
@@ -185,8 +518,20 @@ fn upsert_file(
     match db.get_file_by_name(dir_id, &file.filename)? {
         Some(db_file) => {
             if db_file.mtime != file.mtime || db_file.size != file.size as i64 {
+                // Archive stubs keep the original's mtime; only the size differs
+                if db_file.mtime == file.mtime && db.is_archive_stub(db_file.id)? {
+                    return Ok(None);
+                }
                 db.update_file_metadata(db_file.id, file.size as i64, file.mtime)?;
+                // Its thumbnail is keyed on the mtime, so the old one no longer counts
+                db.clear_thumbnail_presence(dir_id)?;
+                // A replaced stub is a new local original
+                db.set_archive_stubbed(db_file.id, false)?;
+                // Edited content no longer matches what it was linked to
+                db.clear_file_links(db_file.id)?;
+                db.clear_file_gear(db_file.id)?;
                 stats.files_modified += 1;
+                stats.changed_directories.insert(dir_id);
             }
             Ok(None)
         }
@@ -208,6 +553,8 @@ fn upsert_file(
                 height,
             )?;
             stats.files_added += 1;
+            stats.changed_directories.insert(dir_id);
+            db.clear_thumbnail_presence(dir_id)?;
             Ok(Some(file_id))
         }
     }
@@ -219,7 +566,8 @@ fn upsert_file(
 fn sync_database_incremental(
     db: &Database,
     scanner: &Scanner,
-    _library_path: &Path,
+    progress: &ScanProgress,
+    quiet: bool,
 ) -> Result<SyncStats> {
     let mut stats = SyncStats::default();
 
@@ -233,20 +581,31 @@ fn sync_database_incremental(
             .template("{spinner:.cyan} {msg}")
             .unwrap(),
     );
+    if quiet {
+        spinner.set_draw_target(ProgressDrawTarget::hidden());
+    }
     spinner.set_message("Scanning directories...");
     spinner.enable_steady_tick(std::time::Duration::from_millis(100));
 
     let mut fs_dirs: HashMap<String, i64> = HashMap::new();
-    for dir in scanner.scan_directories() {
+    for dir in scanner.scan_directories()? {
         fs_dirs.insert(dir.relative_path, dir.mtime);
         if fs_dirs.len() % 100 == 0 {
             spinner.set_message(format!("Scanning directories... {}", fs_dirs.len()));
         }
     }
     spinner.finish_with_message(format!("Scanned {} directories", fs_dirs.len()));
+    if progress.is_cancelled() {
+        db.rollback()?;
+        bail!("Sync cancelled");
+    }
     info!(dirs = fs_dirs.len(), "directory scan complete");
 
     // === Phase 2: Load directories from database ===
+    let respelled = respell_directories(db, fs_dirs.keys())?;
+    if respelled > 0 {
+        info!(dirs = respelled, "re-recorded directories under their scanned spelling");
+    }
     debug!("loading directories from database");
     let db_dirs: HashMap<String, (i64, Option<i64>)> = db
         .get_all_directories()?
@@ -305,38 +664,30 @@ fn sync_database_incremental(
                 .template("{spinner:.cyan} {msg}")
                 .unwrap(),
         );
+        if quiet {
+            spinner.set_draw_target(ProgressDrawTarget::hidden());
+        }
         spinner.set_message(format!("Scanning files in {} directories...", dirs_to_scan_files.len()));
         spinner.enable_steady_tick(std::time::Duration::from_millis(100));
 
-        let files = scanner.scan_files_in_directories(&dirs_to_scan_files);
+        let files = scanner.scan_files_in_directories(&dirs_to_scan_files)?;
         spinner.finish_with_message(format!("Found {} files in {} directories", files.len(), dirs_to_scan_files.len()));
         files
     };
+    // Last chance to stop: past here the database is being changed
+    if progress.is_cancelled() {
+        db.rollback()?;
+        bail!("Sync cancelled");
+    }
     debug!(files = fs_files.len(), "file scan complete");
 
-    // Build lookup for new files
-    let fs_file_set: HashSet<(String, String)> = fs_files
-        .iter()
-        .map(|f| (f.directory.clone(), f.filename.clone()))
-        .collect();
-
     // === Phase 6: Handle deleted directories ===
-    // Delete files first (FK constraint), then directories
+    // Their files may have moved elsewhere; the directories go once that's settled
+    let mut pending = PendingMoves::default();
     for (path, id) in &dirs_to_delete {
-        let files_in_dir = db.get_files_in_directory(*id)?;
-        for file in files_in_dir {
-            db.delete_file(file.id)?;
-            stats.files_removed += 1;
+        for file in db.get_files_in_directory(*id)? {
+            pending.missing.push((file, path.clone()));
         }
-        debug!(path, "deleted directory");
-    }
-
-    // Delete directories deepest first
-    let mut dirs_to_delete_sorted = dirs_to_delete;
-    dirs_to_delete_sorted.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
-    for (_path, id) in dirs_to_delete_sorted {
-        db.delete_directory(id)?;
-        stats.directories_removed += 1;
     }
 
     // === Phase 7: Add new directories ===
@@ -366,16 +717,12 @@ fn sync_database_incremental(
     }
 
     // === Phase 9: Handle files in changed directories ===
-    // Delete files that no longer exist in scanned directories
+    // Files that no longer exist in scanned directories, unless renamed
+    let scanned = scanned_by_dir(&fs_files);
     for dir_path in &dirs_to_scan_files {
         if let Some(id) = dir_path_to_id.get(dir_path) {
-            let db_files = db.get_files_in_directory(*id)?;
-            for file in db_files {
-                if !fs_file_set.contains(&(dir_path.clone(), file.filename.clone())) {
-                    db.delete_file(file.id)?;
-                    stats.files_removed += 1;
-                }
-            }
+            let in_dir = scanned.get(dir_path.as_str()).map_or(&[][..], Vec::as_slice);
+            collect_missing_files(db, db.get_files_in_directory(*id)?, dir_path, in_dir, &mut stats, &mut pending)?;
         }
     }
 
@@ -391,7 +738,31 @@ fn sync_database_incremental(
             continue;
         }
 
-        upsert_file(db, dir_id, file, &mut stats)?;
+        if let Some(file_id) = upsert_file(db, dir_id, file, &mut stats)? {
+            pending.added.push((file_id, dir_id, file.clone()));
+        }
+    }
+
+    // Files missing from one directory and new in another were moved;
+    // delete the rest (FK constraint), then directories deepest first
+    for file in apply_moves(db, scanner.root(), pending, &mut stats)? {
+        db.delete_file(file.id)?;
+        stats.files_removed += 1;
+    }
+    let mut dirs_to_delete_sorted = dirs_to_delete;
+    dirs_to_delete_sorted.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
+    for (path, id) in dirs_to_delete_sorted {
+        db.delete_directory(id)?;
+        stats.directories_removed += 1;
+        debug!(path, "deleted directory");
+    }
+
+    // Re-pair siblings in directories whose contents may have changed
+    for dir_path in &dirs_to_scan_files {
+        if let Some(id) = dir_path_to_id.get(dir_path) {
+            db.rebuild_directory_pairs(*id)?;
+            db.rebuild_directory_name_collisions(*id)?;
+        }
     }
 
     debug!("committing to database");
@@ -408,273 +779,319 @@ fn sync_database_incremental(
     Ok(stats)
 }
 
-/// Sync database with filesystem
-#[instrument(skip_all)]
-fn sync_database(db: &Database, scanner: &Scanner, library_path: &Path) -> Result<SyncStats> {
+/// Subtree sync: rescan one directory and everything below it, leaving the
+/// rest of the library alone. Moved directories are not detected here.
+/// As part of a full sync, which removes what's gone itself, missing files
+/// and new ones go to `full_sync` and directories gone from disk are left
+/// in place. Returns the stats and how many files were found.
+#[instrument(skip(db, scanner, full_sync))]
+fn sync_database_subtree(
+    db: &Database,
+    scanner: &Scanner,
+    subdir: &str,
+    full_sync: Option<&mut PendingMoves>,
+) -> Result<(SyncStats, usize)> {
     let mut stats = SyncStats::default();
+    let delete_missing_dirs = full_sync.is_none();
+    let mut own = PendingMoves::default();
+    let pending = full_sync.unwrap_or(&mut own);
+
+    let parent = subdir.rsplit_once('/').map(|(parent, _)| parent);
+    if let Some(parent) = parent {
+        if db.get_directory_by_path(parent)?.is_none() {
+            bail!("{} is not in the database yet; sync a directory above it", parent);
+        }
+    }
 
-    db.begin_transaction()?;
-
-    // === Phase 1: Gather current state ===
-
-    // Single-pass filesystem scan for both directories and files
-    info!("scanning filesystem");
-    let scan_result = scanner.scan_all();
+    info!("scanning subtree");
+    let scan_result = scanner.scan_subtree(subdir)?;
     let fs_dirs: HashMap<String, i64> = scan_result
         .directories
         .iter()
         .map(|d| (d.relative_path.clone(), d.mtime))
         .collect();
     let fs_files = scan_result.files;
-    info!(dirs = fs_dirs.len(), files = fs_files.len(), "scan complete");
 
-    // Get all directories from database
-    debug!("loading directories from database");
-    let db_dirs: HashMap<String, i64> = db.get_all_directories()?
-        .into_iter()
-        .map(|d| (d.path, d.id))
-        .collect();
-    let fs_file_set: HashSet<(String, String)> = fs_files
-        .iter()
-        .map(|f| (f.directory.clone(), f.filename.clone()))
-        .collect();
-
-    // Get all files from database
-    debug!("loading files from database");
-    let db_files = db.get_all_files()?;
-    debug!(count = db_files.len(), "loaded files from database");
+    db.begin_transaction()?;
+    respell_directories(db, fs_dirs.keys())?;
 
-    // Create lookup from directory_id to path
-    let dir_id_to_path: HashMap<i64, String> = db.get_all_directories()?
+    let prefix = format!("{}/", subdir);
+    let db_dirs: HashMap<String, (i64, Option<i64>)> = db
+        .get_all_directories()?
         .into_iter()
-        .map(|d| (d.id, d.path))
+        .filter(|d| d.path == subdir || d.path.starts_with(&prefix))
+        .map(|d| (d.path, (d.id, d.mtime)))
         .collect();
+    if fs_dirs.is_empty() && db_dirs.is_empty() {
+        db.rollback()?;
+        bail!("Directory not found: {}", subdir);
+    }
+    info!(dirs = fs_dirs.len(), files = fs_files.len(), "subtree scan complete");
 
-    // === Phase 2: Detect moved directories and collect metadata ===
-    debug!("detecting changes");
-
-    // Find directories that will be deleted
-    // Note: The "" directory represents root-level files and is never in the filesystem scan,
-    // so we must skip it to avoid FK constraint failures
-    let dirs_to_delete: Vec<_> = db_dirs
+    // Removed directories, deepest first; their files may have moved
+    let mut dirs_to_delete: Vec<_> = db_dirs
         .iter()
-        .filter(|(path, _)| !path.is_empty() && !fs_dirs.contains_key(*path))
+        .filter(|(path, _)| delete_missing_dirs && !fs_dirs.contains_key(*path))
+        .map(|(path, (id, _))| (path.clone(), *id))
         .collect();
+    dirs_to_delete.sort_by_key(|(path, _)| std::cmp::Reverse(path.len()));
+    for (path, id) in &dirs_to_delete {
+        for file in db.get_files_in_directory(*id)? {
+            pending.missing.push((file, path.clone()));
+        }
+    }
 
-    // Find new directories
-    let new_dir_paths: Vec<_> = fs_dirs
-        .keys()
-        .filter(|path| !db_dirs.contains_key(*path))
+    // Add new directories (parents first) and refresh changed mtimes
+    let mut dir_path_to_id: HashMap<String, i64> = db_dirs
+        .iter()
+        .map(|(path, (id, _))| (path.clone(), *id))
         .collect();
-
-    // Early exit optimization: skip expensive metadata collection if no directories to delete
-    let mut deleted_metadata: HashMap<String, DirectoryMetadata> = HashMap::new();
-    if !dirs_to_delete.is_empty() {
-        // Bulk-load all directory tags and file tags upfront (avoids N+1 queries)
-        let all_dir_tags = db.get_all_directory_tags()?;
-        let all_file_tags = db.get_all_file_tags()?;
-
-        // Collect metadata from directories being deleted, keyed by basename
-        // Also collect file metadata for preservation during moves
-        for (path, &id) in &dirs_to_delete {
-            let dir = db.get_directory(id)?;
-            let dir_tags = all_dir_tags.get(&id).cloned().unwrap_or_default();
-            let files_in_dir = db.get_files_in_directory(id)?;
-
-            let has_dir_metadata = dir.as_ref().map(|d| d.rating.is_some()).unwrap_or(false)
-                || !dir_tags.is_empty();
-            let has_files = !files_in_dir.is_empty();
-
-            // Preserve if has metadata OR has files (for thumbnail/metadata migration)
-            if has_dir_metadata || has_files {
-                let basename = Path::new(path)
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_default();
-
-                // Check if this basename exists in new directories (potential move)
-                let matches_in_new: Vec<_> = new_dir_paths
-                    .iter()
-                    .filter(|p| {
-                        Path::new(*p)
-                            .file_name()
-                            .map(|n| n.to_string_lossy().to_string())
-                            .as_ref() == Some(&basename)
-                    })
-                    .collect();
-
-                // Only preserve if exactly one match (unambiguous move)
-                if matches_in_new.len() == 1 {
-                    // Collect file metadata (ratings and tags) for each file
-                    let mut files_metadata: HashMap<String, FileMetadata> = HashMap::new();
-                    for file in files_in_dir {
-                        let file_tags = all_file_tags.get(&file.id).cloned().unwrap_or_default();
-                        // Only store if file has metadata worth preserving
-                        if file.rating.is_some() || !file_tags.is_empty() {
-                            files_metadata.insert(
-                                file.filename.clone(),
-                                FileMetadata {
-                                    rating: file.rating,
-                                    tags: file_tags,
-                                },
-                            );
-                        } else {
-                            // Still track file for thumbnail migration (with empty metadata)
-                            files_metadata.insert(
-                                file.filename.clone(),
-                                FileMetadata {
-                                    rating: None,
-                                    tags: Vec::new(),
-                                },
-                            );
-                        }
-                    }
-
-                    deleted_metadata.insert(
-                        basename,
-                        DirectoryMetadata {
-                            rating: dir.and_then(|d| d.rating),
-                            tags: dir_tags,
-                            files: files_metadata,
-                            old_path: path.to_string(),
-                        },
-                    );
-                }
+    let mut new_dirs: Vec<_> = fs_dirs
+        .iter()
+        .filter(|(path, _)| !db_dirs.contains_key(*path))
+        .collect();
+    new_dirs.sort_by_key(|(path, _)| path.matches('/').count());
+    for (path, mtime) in new_dirs {
+        insert_new_directory(db, path, *mtime, &mut dir_path_to_id, &mut stats)?;
+    }
+    for (path, fs_mtime) in &fs_dirs {
+        if let Some((id, db_mtime)) = db_dirs.get(path) {
+            if *db_mtime != Some(*fs_mtime) {
+                db.set_directory_mtime(*id, *fs_mtime)?;
             }
         }
     }
-    info!(
-        dirs_to_delete = dirs_to_delete.len(),
-        dirs_to_add = new_dir_paths.len(),
-        "change detection complete"
-    );
-
-    // === Phase 3: Delete removed items (files first due to FK constraint) ===
-    debug!("applying changes");
 
-    // Delete removed files
-    for file in &db_files {
-        let dir_path = dir_id_to_path.get(&file.directory_id).cloned().unwrap_or_default();
-        if !fs_file_set.contains(&(dir_path, file.filename.clone())) {
+    // Every surviving directory was listed, so compare all of its files
+    let scanned = scanned_by_dir(&fs_files);
+    for dir_path in fs_dirs.keys() {
+        let Some((id, _)) = db_dirs.get(dir_path) else { continue };
+        let in_dir = scanned.get(dir_path.as_str()).map_or(&[][..], Vec::as_slice);
+        collect_missing_files(db, db.get_files_in_directory(*id)?, dir_path, in_dir, &mut stats, pending)?;
+    }
+    for file in &fs_files {
+        if let Some(&dir_id) = dir_path_to_id.get(&file.directory) {
+            if let Some(file_id) = upsert_file(db, dir_id, file, &mut stats)? {
+                pending.added.push((file_id, dir_id, file.clone()));
+            }
+        }
+    }
+    if delete_missing_dirs {
+        for file in apply_moves(db, scanner.root(), std::mem::take(pending), &mut stats)? {
             db.delete_file(file.id)?;
             stats.files_removed += 1;
         }
+        for (path, id) in &dirs_to_delete {
+            db.delete_directory(*id)?;
+            stats.directories_removed += 1;
+            debug!(path, "deleted directory");
+        }
     }
 
-    // Delete removed directories (deepest first to avoid FK constraint on parent_id)
-    let mut dirs_to_delete_sorted = dirs_to_delete;
-    dirs_to_delete_sorted.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
-    for (_, id) in dirs_to_delete_sorted {
-        db.delete_directory(*id)?;
-        stats.directories_removed += 1;
+    for dir_path in fs_dirs.keys() {
+        if let Some(id) = dir_path_to_id.get(dir_path) {
+            db.rebuild_directory_pairs(*id)?;
+            db.rebuild_directory_name_collisions(*id)?;
+        }
     }
 
-    // === Phase 4: Add new directories ===
-
-    // Collect new directories and sort by depth (parents before children)
-    let mut new_dirs: Vec<_> = fs_dirs
-        .iter()
-        .filter(|(path, _)| !db_dirs.contains_key(*path))
-        .collect();
-    new_dirs.sort_by_key(|(path, _)| path.matches('/').count());
+    debug!("committing to database");
+    db.commit()?;
+    info!(
+        dirs_added = stats.directories_added,
+        dirs_removed = stats.directories_removed,
+        files_added = stats.files_added,
+        files_removed = stats.files_removed,
+        files_modified = stats.files_modified,
+        "subtree sync complete"
+    );
 
-    // Track file metadata from moved directories (keyed by new dir path -> filename -> metadata)
-    let mut moved_file_metadata: HashMap<String, HashMap<String, FileMetadata>> = HashMap::new();
+    let files = fs_files.len();
+    Ok((stats, files))
+}
 
-    let mut dir_path_to_id: HashMap<String, i64> = HashMap::new();
-    for (path, mtime) in new_dirs {
-        let id = insert_new_directory(db, path, *mtime, &mut dir_path_to_id, &mut stats)?;
-
-        // Check if this directory was moved (has metadata to restore)
-        let basename = Path::new(path)
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_default();
-
-        if let Some(metadata) = deleted_metadata.remove(&basename) {
-            // Restore rating
-            if let Some(rating) = metadata.rating {
-                db.set_directory_rating(id, Some(rating))?;
-            }
-            // Restore tags
-            for tag in &metadata.tags {
-                db.add_directory_tag(id, tag)?;
-            }
+/// Full sync, committed one top-level directory at a time so an interrupted
+/// run keeps what it did. Each committed directory is recorded in
+/// `sync_checkpoints`; with `resume` those are skipped. Directories missing
+/// from disk are only removed at the end, once every new directory is in,
+/// so a directory moved between top-level directories still keeps its
+/// ratings and tags.
+#[instrument(skip_all)]
+fn sync_database(
+    db: &Database,
+    scanner: &Scanner,
+    library_path: &Path,
+    resume: bool,
+    progress: &ScanProgress,
+    quiet: bool,
+) -> Result<SyncStats> {
+    let mut stats = SyncStats::default();
 
-            // Move thumbnails for files in this directory
-            for filename in metadata.files.keys() {
-                let old_file_path = library_path.join(&metadata.old_path).join(filename);
-                let new_file_path = library_path.join(path).join(filename);
-
-                // Get mtime from the new file location
-                if let Ok(file_meta) = std::fs::metadata(&new_file_path) {
-                    if let Ok(mtime) = file_meta.modified() {
-                        // Move image thumbnail
-                        if is_image_file(&new_file_path) {
-                            if let (Some(old_thumb), Some(new_thumb)) = (
-                                compute_thumbnail_path(&old_file_path, mtime),
-                                compute_thumbnail_path(&new_file_path, mtime),
-                            ) {
-                                if old_thumb.exists() && old_thumb != new_thumb {
-                                    let _ = std::fs::rename(&old_thumb, &new_thumb);
-                                }
-                            }
-                        }
-                        // Move video thumbnail
-                        else if is_video_file(&new_file_path) {
-                            if let (Some(old_thumb), Some(new_thumb)) = (
-                                compute_video_thumbnail_path(&old_file_path, mtime),
-                                compute_video_thumbnail_path(&new_file_path, mtime),
-                            ) {
-                                if old_thumb.exists() && old_thumb != new_thumb {
-                                    let _ = std::fs::rename(&old_thumb, &new_thumb);
-                                }
-                            }
-                        }
-                    }
-                }
+    // === Phase 1: List directories (fast - no file stats) ===
+    info!("scanning directories");
+    let fs_dirs: HashMap<String, i64> = scanner
+        .scan_directories()?
+        .into_iter()
+        .map(|d| (d.relative_path, d.mtime))
+        .collect();
+    let mut top_level: Vec<&str> = fs_dirs.keys().filter(|p| !p.contains('/')).map(String::as_str).collect();
+    top_level.sort_unstable();
+    info!(dirs = fs_dirs.len(), top_level = top_level.len(), "directory scan complete");
+
+    // Directories added from here on are candidates for moves. An unfinished
+    // run's marker is kept even when starting over, as its additions are in.
+    let checkpoints = db.get_sync_checkpoints()?;
+    let first_new_id = match &checkpoints {
+        Some(c) => c.first_new_directory_id,
+        None => db.next_directory_id()?,
+    };
+    let done = match checkpoints {
+        Some(c) if resume => c.done,
+        Some(c) => {
+            warn!(done = c.done.len(), "starting over an unfinished full sync");
+            if !quiet {
+                eprintln!("Starting over an unfinished full sync; 'picman sync --resume' would have continued it");
             }
-
-            // Store file metadata for restoration after files are inserted
-            moved_file_metadata.insert(path.clone(), metadata.files);
-
-            stats.directories_moved += 1;
+            db.clear_sync_checkpoints()?;
+            HashMap::new()
         }
-    }
+        None => HashMap::new(),
+    };
 
-    // Also populate dir_path_to_id with existing directories
-    for (path, id) in &db_dirs {
-        dir_path_to_id.insert(path.clone(), *id);
+    // Progress against the file count the previous sync left
+    let previous_total = db.library_summary()?.files.max(0) as u64;
+    let mut synced: u64 = done.values().sum::<i64>().max(0) as u64;
+    let bar = ProgressBar::new(previous_total.max(synced).max(1));
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.cyan} [{bar:30.cyan/blue}] {percent}% {msg}")
+            .unwrap()
+            .progress_chars("=> "),
+    );
+    if quiet {
+        bar.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    bar.set_position(synced);
+    if !done.is_empty() {
+        info!(dirs = done.len(), files = synced, "resuming full sync");
     }
 
-    // === Phase 4: Add/update files ===
-
-    for file in &fs_files {
-        let dir_id = if file.directory.is_empty() {
-            get_or_create_root_dir(db, &mut dir_path_to_id)?
+    // === Phase 2: Sync each top-level directory, committing as we go ===
+    // Missing files stay in the database until the end, in case they moved
+    // to a directory synced later
+    let mut pending = PendingMoves::default();
+    let chunks = std::iter::once("").chain(top_level);
+    for dir in chunks {
+        if done.contains_key(dir) {
+            continue;
+        }
+        if progress.is_cancelled() {
+            bar.abandon();
+            bail!("Sync cancelled; 'picman sync --resume' continues where it stopped");
+        }
+        bar.set_message(if dir.is_empty() { "(library root)".to_string() } else { dir.to_string() });
+        let (chunk, files) = if dir.is_empty() {
+            sync_root_files(db, scanner, &mut pending)?
         } else {
-            *dir_path_to_id.get(&file.directory).unwrap_or(&0)
+            sync_database_subtree(db, scanner, dir, Some(&mut pending))?
         };
+        stats.add(chunk);
+        db.record_sync_checkpoint(dir, files as i64, first_new_id)?;
+        synced += files as u64;
+        if synced > bar.length().unwrap_or(0) {
+            bar.set_length(synced);
+        }
+        bar.set_position(synced);
+    }
+    bar.finish_and_clear();
 
-        if dir_id == 0 {
-            continue; // Skip if directory not found (shouldn't happen)
+    // === Phase 3: Remove what's gone, carrying metadata over to moves ===
+    debug!("removing deleted directories");
+    db.begin_transaction()?;
+    let db_dirs = db.get_all_directories()?;
+    let new_dirs: Vec<&Directory> = db_dirs
+        .iter()
+        .filter(|d| d.id >= first_new_id && fs_dirs.contains_key(&d.path))
+        .collect();
+    // Note: The "" directory holds root-level files and is never in the
+    // directory scan
+    let mut dirs_to_delete: Vec<&Directory> = db_dirs
+        .iter()
+        .filter(|d| !d.path.is_empty() && !fs_dirs.contains_key(&d.path))
+        .collect();
+    dirs_to_delete.sort_by_key(|d| std::cmp::Reverse(d.path.len()));
+
+    // Files that moved keep their rows; what's left of a moved directory
+    // gets its metadata carried over by name below
+    for dir in &dirs_to_delete {
+        for file in db.get_files_in_directory(dir.id)? {
+            pending.missing.push((file, dir.path.clone()));
         }
+    }
+    let gone = apply_moves(db, library_path, pending, &mut stats)?;
 
-        if let Some(file_id) = upsert_file(db, dir_id, file, &mut stats)? {
-            // Check if this file was part of a moved directory and restore metadata
-            if let Some(dir_files) = moved_file_metadata.get(&file.directory) {
-                if let Some(file_meta) = dir_files.get(&file.filename) {
-                    if let Some(rating) = file_meta.rating {
-                        db.set_file_rating(file_id, Some(rating))?;
-                    }
-                    for tag in &file_meta.tags {
-                        db.add_file_tag(file_id, tag)?;
-                    }
+    if !dirs_to_delete.is_empty() && !new_dirs.is_empty() {
+        let all_dir_tags = db.get_all_directory_tags()?;
+        let all_file_tags = db.get_all_file_tags()?;
+        let mut all_file_attributes = db.get_all_file_attributes()?;
+        for old in &dirs_to_delete {
+            // Only an unambiguous match counts as a move
+            let key = move_key(&old.path);
+            let mut matches = new_dirs.iter().filter(|d| move_key(&d.path) == key);
+            let (Some(new), None) = (matches.next(), matches.next()) else { continue };
+
+            if let Some(rating) = old.rating {
+                db.set_directory_rating(new.id, Some(rating))?;
+            }
+            for tag in all_dir_tags.get(&old.id).into_iter().flatten() {
+                db.add_directory_tag(new.id, tag)?;
+            }
+            let moved: HashMap<String, i64> = db
+                .get_files_in_directory(new.id)?
+                .into_iter()
+                .map(|f| (normalize(&f.filename).into_owned(), f.id))
+                .collect();
+            for file in db.get_files_in_directory(old.id)? {
+                let Some(&new_id) = moved.get(normalize(&file.filename).as_ref()) else { continue };
+                move_thumbnails(
+                    &library_path.join(&old.path).join(&file.filename),
+                    &library_path.join(&new.path).join(&file.filename),
+                );
+                if let Some(rating) = file.rating {
+                    db.set_file_rating(new_id, Some(rating))?;
+                }
+                for tag in all_file_tags.get(&file.id).into_iter().flatten() {
+                    db.add_file_tag(new_id, tag)?;
+                }
+                for (key, value) in all_file_attributes.remove(&file.id).unwrap_or_default() {
+                    db.set_file_attribute(new_id, &key, &value)?;
                 }
             }
+            debug!(old = old.path, new = new.path, "directory moved");
+            stats.directories_moved += 1;
         }
     }
-    debug!("changes applied");
+
+    // Files first due to FK constraint, then directories deepest first
+    for file in gone {
+        db.delete_file(file.id)?;
+        stats.files_removed += 1;
+    }
+    for dir in &dirs_to_delete {
+        db.delete_directory(dir.id)?;
+        stats.directories_removed += 1;
+    }
+    info!(
+        dirs_removed = dirs_to_delete.len(),
+        dirs_moved = stats.directories_moved,
+        files_moved = stats.files_moved,
+        "change detection complete"
+    );
+
+    let pairs = db.rebuild_all_pairs()?;
+    let collisions = db.rebuild_all_name_collisions()?;
+    debug!(pairs, collisions, "changes applied");
+    db.clear_sync_checkpoints()?;
 
     debug!("committing to database");
     db.commit()?;
@@ -691,6 +1108,61 @@ fn sync_database(db: &Database, scanner: &Scanner, library_path: &Path) -> Resul
     Ok(stats)
 }
 
+/// Sync the files at the library root as part of a full sync; missing and
+/// new files go to `pending`. Returns the stats and how many files were
+/// found.
+fn sync_root_files(db: &Database, scanner: &Scanner, pending: &mut PendingMoves) -> Result<(SyncStats, usize)> {
+    let mut stats = SyncStats::default();
+    let fs_files = scanner.scan_files_in_directories(&HashSet::from([String::new()]))?;
+
+    db.begin_transaction()?;
+    let mut dir_path_to_id = HashMap::new();
+    let root_id = match db.get_directory_by_path("")? {
+        Some(dir) => dir.id,
+        None if fs_files.is_empty() => {
+            db.commit()?;
+            return Ok((stats, 0));
+        }
+        None => get_or_create_root_dir(db, &mut dir_path_to_id)?,
+    };
+
+    let scanned: Vec<&ScannedFile> = fs_files.iter().collect();
+    collect_missing_files(db, db.get_files_in_directory(root_id)?, "", &scanned, &mut stats, pending)?;
+    for file in &fs_files {
+        if let Some(file_id) = upsert_file(db, root_id, file, &mut stats)? {
+            pending.added.push((file_id, root_id, file.clone()));
+        }
+    }
+    db.rebuild_directory_pairs(root_id)?;
+    db.rebuild_directory_name_collisions(root_id)?;
+    db.commit()?;
+
+    Ok((stats, fs_files.len()))
+}
+
+/// Move the thumbnail of a moved or renamed file to where its new path looks
+/// for it
+fn move_thumbnails(old_file_path: &Path, new_file_path: &Path) {
+    // Get mtime from the new file location
+    let Ok(mtime) = std::fs::metadata(new_file_path).and_then(|m| m.modified()) else {
+        return;
+    };
+    let compute: fn(&Path, std::time::SystemTime) -> Option<std::path::PathBuf> = if is_image_file(new_file_path) {
+        compute_thumbnail_path
+    } else if is_video_file(new_file_path) {
+        compute_video_thumbnail_path
+    } else if is_document_file(new_file_path) {
+        compute_document_thumbnail_path
+    } else {
+        return;
+    };
+    if let (Some(old_thumb), Some(new_thumb)) = (compute(old_file_path, mtime), compute(new_file_path, mtime)) {
+        if old_thumb.exists() && old_thumb != new_thumb {
+            let _ = std::fs::rename(&old_thumb, &new_thumb);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -746,6 +1218,38 @@ mod tests {
         let dir = db.get_directory_by_path("photos").unwrap().unwrap();
         let files = db.get_files_in_directory(dir.id).unwrap();
         assert_eq!(files.len(), 2);
+
+        // Recorded for the dashboard and `picman status`
+        let runs = db.get_maintenance_runs().unwrap();
+        let sync = runs.iter().find(|r| r.task == SYNC_TASK).unwrap();
+        assert_eq!(sync.summary.as_deref(), Some("+1 -0 ~0 files"));
+        assert!(db.library_summary().unwrap().last_sync.is_some());
+    }
+
+    #[test]
+    fn test_sync_drops_dotfiles_once_turned_off() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("photos/.edits")).unwrap();
+        fs::write(root.join("photos/a.jpg"), "data").unwrap();
+        fs::write(root.join("photos/.b.jpg"), "data").unwrap();
+        fs::write(root.join("photos/.edits/c.jpg"), "data").unwrap();
+
+        crate::cli::configure_dotfiles(root, true).unwrap();
+        let stats = run_init(root).unwrap();
+        assert_eq!((stats.directories, stats.files), (2, 3));
+
+        // Nothing on disk changed, so an incremental sync scans no directory
+        crate::cli::configure_dotfiles(root, false).unwrap();
+        let stats = run_sync(root, false, false, false).unwrap();
+        assert_eq!((stats.directories_removed, stats.files_removed), (1, 2));
+
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        assert!(db.get_file_by_path("photos/a.jpg").unwrap().is_some());
+        assert!(db.get_file_by_path("photos/.b.jpg").unwrap().is_none());
+        assert!(db.get_directory_by_path("photos/.edits").unwrap().is_none());
+        assert!(db.get_dotfile_ids().unwrap().is_empty());
+        assert_eq!(db.applied_dotfiles().unwrap(), Some(false));
     }
 
     #[test]
@@ -832,6 +1336,84 @@ mod tests {
         assert!(dir.is_none());
     }
 
+    #[test]
+    fn test_sync_subdir_only_touches_subtree() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+
+        fs::create_dir_all(root.join("photos/2024/old")).unwrap();
+        fs::create_dir_all(root.join("videos")).unwrap();
+        fs::write(root.join("photos/2024/a.jpg"), "data").unwrap();
+        fs::write(root.join("photos/2024/old/b.jpg"), "data").unwrap();
+        fs::write(root.join("videos/clip.mp4"), "video").unwrap();
+        run_init(root).unwrap();
+
+        // Changes inside and outside the subtree
+        fs::create_dir_all(root.join("photos/2024/new")).unwrap();
+        fs::write(root.join("photos/2024/new/c.jpg"), "data").unwrap();
+        fs::write(root.join("photos/2024/d.jpg"), "data").unwrap();
+        fs::remove_dir_all(root.join("photos/2024/old")).unwrap();
+        fs::write(root.join("videos/other.mp4"), "video").unwrap();
+
+        let stats = run_sync_subdir(root, "photos/2024/", false, false, false).unwrap();
+        assert_eq!((stats.directories_added, stats.directories_removed), (1, 1));
+        assert_eq!((stats.files_added, stats.files_removed), (2, 1));
+
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let new_dir = db.get_directory_by_path("photos/2024/new").unwrap().unwrap();
+        let parent = db.get_directory_by_path("photos/2024").unwrap().unwrap();
+        assert_eq!(new_dir.parent_id, Some(parent.id));
+        assert!(db.get_directory_by_path("photos/2024/old").unwrap().is_none());
+        let videos = db.get_directory_by_path("videos").unwrap().unwrap();
+        assert_eq!(db.get_files_in_directory(videos.id).unwrap().len(), 1);
+
+        // A removed subtree is dropped; unknown paths are an error
+        fs::remove_dir_all(root.join("photos/2024")).unwrap();
+        let stats = run_sync_subdir(root, "photos/2024", false, false, false).unwrap();
+        assert_eq!((stats.directories_removed, stats.files_removed), (2, 3));
+        assert!(run_sync_subdir(root, "photos/2024", false, false, false).is_err());
+        assert!(run_sync_subdir(root, "nope/deeper", false, false, false).is_err());
+    }
+
+    #[test]
+    fn test_sync_progress_counts_and_cancels() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("photos")).unwrap();
+        fs::write(root.join("photos/a.jpg"), "data").unwrap();
+        run_init(root).unwrap();
+
+        fs::create_dir_all(root.join("photos/trip")).unwrap();
+        fs::write(root.join("photos/trip/b.jpg"), "data").unwrap();
+        let progress = Arc::new(ScanProgress::default());
+        progress.cancel();
+        assert!(run_sync_incremental_with_progress(root, progress).is_err());
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        assert!(db.get_directory_by_path("photos/trip").unwrap().is_none());
+        assert!(!crate::cli::lock::sync_lock_path(root).exists());
+
+        let progress = Arc::new(ScanProgress::default());
+        let stats = run_sync_incremental_with_progress(root, progress.clone()).unwrap();
+        assert_eq!(stats.files_added, 1);
+        assert_eq!((progress.dirs(), progress.files()), (2, 1));
+    }
+
+    #[test]
+    fn test_sync_refuses_while_locked() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("photos")).unwrap();
+        run_init(root).unwrap();
+
+        let lock = SyncLock::acquire(root).unwrap();
+        let err = run_sync(root, false, false, true).unwrap_err();
+        assert!(err.is::<crate::cli::SyncLocked>());
+        drop(lock);
+
+        run_sync(root, false, false, true).unwrap();
+        assert!(!root.join(".picman-sync.lock").exists());
+    }
+
     #[test]
     fn test_sync_modified_file() {
         let temp = TempDir::new().unwrap();
@@ -843,6 +1425,9 @@ mod tests {
 
         // Init
         run_init(root).unwrap();
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let dir = db.get_directory_by_path("photos").unwrap().unwrap();
+        db.set_thumbnail_presence(dir.id, dir.mtime).unwrap();
 
         // Modify file (need to wait for mtime to change)
         sleep(Duration::from_millis(100));
@@ -853,10 +1438,11 @@ mod tests {
         assert_eq!(stats.files_modified, 1);
 
         // Verify size updated in DB
-        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
-        let dir = db.get_directory_by_path("photos").unwrap().unwrap();
         let files = db.get_files_in_directory(dir.id).unwrap();
         assert_eq!(files[0].size, "modified data with more content".len() as i64);
+
+        // The old thumbnail no longer matches, so the directory is checked again
+        assert!(!db.has_thumbnail_presence(dir.id, dir.mtime).unwrap());
     }
 
     #[test]
@@ -907,6 +1493,43 @@ mod tests {
         assert_eq!(vol3.parent_id, Some(hongdan.id));
     }
 
+    #[test]
+    fn test_full_sync_resumes_after_committed_directories() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        for dir in ["2023", "2024"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("a.jpg"), "data").unwrap();
+        }
+        run_init(root).unwrap();
+
+        // As if a full sync had committed 2023 and then been interrupted
+        fs::write(root.join("2023/b.jpg"), "data").unwrap();
+        fs::remove_file(root.join("2024/a.jpg")).unwrap();
+        fs::write(root.join("2024/b.jpg"), "new data").unwrap();
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let first_new = db.next_directory_id().unwrap();
+        db.record_sync_checkpoint("2023", 1, first_new).unwrap();
+        drop(db);
+
+        let stats = run_sync_resume(root, false, false, false).unwrap();
+        // 2023 was skipped, so its new file waits for the next sync
+        assert_eq!((stats.files_added, stats.files_removed), (1, 1));
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        assert!(db.get_file_by_path("2023/b.jpg").unwrap().is_none());
+        assert!(db.get_file_by_path("2024/a.jpg").unwrap().is_none());
+        assert!(db.get_sync_checkpoints().unwrap().is_none());
+
+        // Without --resume, leftover checkpoints are dropped and it starts over
+        db.record_sync_checkpoint("2023", 1, first_new).unwrap();
+        drop(db);
+        let stats = run_sync(root, false, false, true).unwrap();
+        assert_eq!(stats.files_added, 1);
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        assert!(db.get_file_by_path("2023/b.jpg").unwrap().is_some());
+        assert!(db.get_sync_checkpoints().unwrap().is_none());
+    }
+
     #[test]
     fn test_sync_move_preserves_metadata() {
         let temp = TempDir::new().unwrap();
@@ -922,9 +1545,11 @@ mod tests {
         // Add rating and tags to the directory
         let db = Database::open(&root.join(DB_FILENAME)).unwrap();
         let hongdan = db.get_directory_by_path("korean/Hongdan").unwrap().unwrap();
-        db.set_directory_rating(hongdan.id, Some(5)).unwrap();
+        db.set_directory_rating(hongdan.id, Some(5.0)).unwrap();
         db.add_directory_tag(hongdan.id, "favorite").unwrap();
         db.add_directory_tag(hongdan.id, "kpop").unwrap();
+        let image = db.get_file_by_path("korean/Hongdan/image.jpg").unwrap().unwrap();
+        db.set_file_attribute(image.id, "client", "Hongdan").unwrap();
         drop(db);
 
         // Move the directory on disk: korean/Hongdan -> artists/Hongdan
@@ -942,11 +1567,140 @@ mod tests {
         // Verify metadata was preserved
         let db = Database::open(&root.join(DB_FILENAME)).unwrap();
         let new_hongdan = db.get_directory_by_path("artists/Hongdan").unwrap().unwrap();
-        assert_eq!(new_hongdan.rating, Some(5));
+        assert_eq!(new_hongdan.rating, Some(5.0));
 
         let tags = db.get_directory_tags(new_hongdan.id).unwrap();
         assert!(tags.contains(&"favorite".to_string()));
         assert!(tags.contains(&"kpop".to_string()));
+
+        let image = db.get_file_by_path("artists/Hongdan/image.jpg").unwrap().unwrap();
+        assert_eq!(db.get_file_attribute(image.id, "client").unwrap().as_deref(), Some("Hongdan"));
+    }
+
+    #[test]
+    fn test_sync_rename_keeps_file_metadata() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("photos")).unwrap();
+        fs::write(root.join("photos/IMG_0001.jpg"), "first photo").unwrap();
+        fs::write(root.join("photos/IMG_0002.jpg"), "second, longer photo").unwrap();
+        fs::write(root.join("loose.jpg"), "at the root").unwrap();
+        run_init(root).unwrap();
+        run_sync(root, true, false, true).unwrap();
+
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let first = db.get_file_by_path("photos/IMG_0001.jpg").unwrap().unwrap();
+        let second = db.get_file_by_path("photos/IMG_0002.jpg").unwrap().unwrap();
+        let loose = db.get_file_by_path("loose.jpg").unwrap().unwrap();
+        db.set_file_rating(first.id, Some(4.0)).unwrap();
+        db.add_file_tag(first.id, "keeper").unwrap();
+        db.add_file_tag(loose.id, "root").unwrap();
+        db.set_file_rating(second.id, Some(2.0)).unwrap();
+        drop(db);
+
+        // A plain rename keeps size and mtime
+        fs::rename(root.join("photos/IMG_0001.jpg"), root.join("photos/beach.jpg")).unwrap();
+        fs::rename(root.join("loose.jpg"), root.join("loose-renamed.jpg")).unwrap();
+        // A copy under a new name gets a new mtime; the hash still matches
+        fs::copy(root.join("photos/IMG_0002.jpg"), root.join("photos/sunset.jpg")).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(root.join("photos/sunset.jpg"))
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        fs::remove_file(root.join("photos/IMG_0002.jpg")).unwrap();
+
+        let stats = run_sync(root, false, false, true).unwrap();
+        assert_eq!(stats.files_renamed, 3);
+        assert_eq!(stats.files_removed, 0);
+        assert_eq!(stats.files_added, 0);
+
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let beach = db.get_file_by_path("photos/beach.jpg").unwrap().unwrap();
+        assert_eq!(beach.id, first.id);
+        assert_eq!(beach.rating, Some(4.0));
+        assert_eq!(db.get_file_tags(beach.id).unwrap(), vec!["keeper"]);
+        assert_eq!(db.get_file_by_path("photos/sunset.jpg").unwrap().unwrap().rating, Some(2.0));
+        let loose = db.get_file_by_path("loose-renamed.jpg").unwrap().unwrap();
+        assert_eq!(db.get_file_tags(loose.id).unwrap(), vec!["root"]);
+    }
+
+    #[test]
+    fn test_sync_file_moved_between_directories_keeps_row() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        for dir in ["2023/inbox", "2023/best", "2024"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(root.join("2023/inbox/a.jpg"), "photo a").unwrap();
+        fs::write(root.join("2023/inbox/b.jpg"), "photo b, longer").unwrap();
+        fs::write(root.join("2023/inbox/c.jpg"), "photo c, longer still").unwrap();
+        run_init(root).unwrap();
+
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let mut ids = HashMap::new();
+        for name in ["a", "b", "c"] {
+            let file = db.get_file_by_path(&format!("2023/inbox/{}.jpg", name)).unwrap().unwrap();
+            db.set_file_rating(file.id, Some(5.0)).unwrap();
+            db.add_file_tag(file.id, name).unwrap();
+            ids.insert(name, file.id);
+        }
+        drop(db);
+
+        // Incremental: into a sibling directory, under a new name
+        sleep(Duration::from_millis(1100));
+        fs::rename(root.join("2023/inbox/a.jpg"), root.join("2023/best/a-final.jpg")).unwrap();
+        let stats = run_sync(root, false, false, false).unwrap();
+        assert_eq!((stats.files_moved, stats.files_added, stats.files_removed), (1, 0, 0));
+
+        // Full: into another top-level directory, synced in a later chunk
+        fs::rename(root.join("2023/inbox/b.jpg"), root.join("2024/b.jpg")).unwrap();
+        let stats = run_sync(root, false, false, true).unwrap();
+        assert_eq!((stats.files_moved, stats.files_added, stats.files_removed), (1, 0, 0));
+
+        // Subtree: out of a directory that is gone
+        fs::create_dir_all(root.join("2023/kept")).unwrap();
+        fs::rename(root.join("2023/inbox/c.jpg"), root.join("2023/kept/c.jpg")).unwrap();
+        fs::remove_dir(root.join("2023/inbox")).unwrap();
+        let stats = run_sync_subdir(root, "2023", false, false, false).unwrap();
+        assert_eq!((stats.files_moved, stats.files_added, stats.files_removed), (1, 0, 0));
+        assert_eq!(stats.directories_removed, 1);
+
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        for (name, path) in [("a", "2023/best/a-final.jpg"), ("b", "2024/b.jpg"), ("c", "2023/kept/c.jpg")] {
+            let file = db.get_file_by_path(path).unwrap().unwrap();
+            assert_eq!(file.id, ids[name]);
+            assert_eq!(file.rating, Some(5.0));
+            assert_eq!(db.get_file_tags(file.id).unwrap(), vec![name]);
+        }
+        assert_eq!(db.library_summary().unwrap().files, 3);
+    }
+
+    #[test]
+    fn test_sync_rename_ambiguous_is_delete_and_add() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("photos")).unwrap();
+        fs::write(root.join("photos/a.jpg"), "same").unwrap();
+        run_init(root).unwrap();
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let a = db.get_file_by_path("photos/a.jpg").unwrap().unwrap();
+        db.set_file_rating(a.id, Some(3.0)).unwrap();
+        drop(db);
+
+        // Two new files could be the renamed one: neither inherits it
+        let mtime = fs::metadata(root.join("photos/a.jpg")).unwrap().modified().unwrap();
+        fs::rename(root.join("photos/a.jpg"), root.join("photos/b.jpg")).unwrap();
+        fs::write(root.join("photos/c.jpg"), "same").unwrap();
+        fs::File::options().write(true).open(root.join("photos/c.jpg")).unwrap().set_modified(mtime).unwrap();
+
+        let stats = run_sync(root, false, false, true).unwrap();
+        assert_eq!(stats.files_renamed, 0);
+        assert_eq!(stats.files_removed, 1);
+        assert_eq!(stats.files_added, 2);
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        assert_eq!(db.get_file_by_path("photos/b.jpg").unwrap().unwrap().rating, None);
     }
 
     #[test]
@@ -966,7 +1720,7 @@ mod tests {
         // Add rating to a/Photos
         let db = Database::open(&root.join(DB_FILENAME)).unwrap();
         let photos_a = db.get_directory_by_path("a/Photos").unwrap().unwrap();
-        db.set_directory_rating(photos_a.id, Some(5)).unwrap();
+        db.set_directory_rating(photos_a.id, Some(5.0)).unwrap();
         drop(db);
 
         // Move both directories to new locations
@@ -1022,4 +1776,93 @@ mod tests {
         assert_eq!(files.len(), 1);
     }
 
+    #[test]
+    fn test_sync_keeps_entries_recorded_in_another_normalization_form() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("caf\u{E9}")).unwrap();
+        fs::write(root.join("caf\u{E9}/\u{E9}t\u{E9}.jpg"), "data").unwrap();
+        run_init(root).unwrap();
+
+        // Recorded decomposed, as a library synced on macOS before names
+        // were normalized has them
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let dir = db.get_directory_by_path("caf\u{E9}").unwrap().unwrap();
+        db.rename_directory(dir.id, "caf\u{E9}", "cafe\u{301}").unwrap();
+        let file = db.get_files_in_directory(dir.id).unwrap().remove(0);
+        db.rename_file(file.id, "e\u{301}te\u{301}.jpg").unwrap();
+        db.set_file_rating(file.id, Some(4.0)).unwrap();
+        drop(db);
+
+        // Incremental respells the directory, full rescans and respells the file
+        for full in [false, true] {
+            let stats = run_sync(root, false, false, full).unwrap();
+            assert_eq!((stats.directories_removed, stats.files_removed, stats.files_added), (0, 0, 0));
+        }
+
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        assert_eq!(db.get_directory(dir.id).unwrap().unwrap().path, "caf\u{E9}");
+        let files = db.get_files_in_directory(dir.id).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!((files[0].id, files[0].filename.as_str()), (file.id, "\u{E9}t\u{E9}.jpg"));
+        assert_eq!(files[0].rating, Some(4.0));
+    }
+
+    #[test]
+    fn test_sync_move_matches_names_across_normalization_forms() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("2023/Zu\u{308}rich")).unwrap();
+        fs::write(root.join("2023/Zu\u{308}rich/Gru\u{308}n.jpg"), "data").unwrap();
+        run_init(root).unwrap();
+
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let file = db.get_file_by_path("2023/Z\u{FC}rich/Gr\u{FC}n.jpg").unwrap().unwrap();
+        db.set_file_rating(file.id, Some(5.0)).unwrap();
+        drop(db);
+
+        // Moved, and renamed to the composed form on the way
+        fs::create_dir_all(root.join("trips")).unwrap();
+        fs::rename(root.join("2023/Zu\u{308}rich"), root.join("trips/Z\u{FC}rich")).unwrap();
+        fs::rename(
+            root.join("trips/Z\u{FC}rich/Gru\u{308}n.jpg"),
+            root.join("trips/Z\u{FC}rich/Gr\u{FC}n.jpg"),
+        )
+        .unwrap();
+
+        let stats = run_sync(root, false, false, true).unwrap();
+        assert_eq!(stats.directories_moved, 1);
+
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let moved = db.get_file_by_path("trips/Z\u{FC}rich/Gr\u{FC}n.jpg").unwrap().unwrap();
+        assert_eq!(moved.rating, Some(5.0));
+    }
+
+    // ext4 keeps the two forms apart, so they are two files
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_sync_keeps_both_spellings_where_the_filesystem_does() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::write(root.join("caf\u{E9}.jpg"), "composed").unwrap();
+        fs::write(root.join("cafe\u{301}.jpg"), "decomposed").unwrap();
+        run_init(root).unwrap();
+
+        let db = Database::open(&root.join(DB_FILENAME)).unwrap();
+        let root_dir = db.get_directory_by_path("").unwrap().unwrap();
+        let mut names: Vec<String> = db
+            .get_files_in_directory(root_dir.id)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.filename)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["cafe\u{301}.jpg", "caf\u{E9}.jpg"]);
+        // Each recorded path opens the file it names
+        assert_eq!(fs::read_to_string(root.join(&names[0])).unwrap(), "decomposed");
+        drop(db);
+
+        let stats = run_sync(root, false, false, true).unwrap();
+        assert_eq!((stats.files_added, stats.files_removed), (0, 0));
+    }
 }
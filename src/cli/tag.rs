@@ -2,7 +2,8 @@ use std::path::Path;
 
 use anyhow::Result;
 
-use crate::db::Database;
+use crate::db::{Activity, Database};
+use crate::library_path::LibraryPath;
 
 /// Options for the tag command
 #[derive(Debug, Default)]
@@ -10,6 +11,16 @@ pub struct TagOptions {
     pub add: Vec<String>,
     pub remove: Vec<String>,
     pub list: bool,
+    /// Directory mode: copy inherited directory tags onto the files below
+    pub materialize: bool,
+}
+
+/// What a directory-wide tag operation changed
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DirectoryTagReport {
+    pub files_tagged: usize,
+    pub files_untagged: usize,
+    pub dirs_untagged: usize,
 }
 
 /// Add, remove, or list tags on a file
@@ -31,10 +42,12 @@ pub fn run_tag(library_path: &Path, file_path: &Path, options: TagOptions) -> Re
     }
 
     let db = Database::open(&db_path)?;
+    tag_file(&db, &file_path.to_string_lossy(), &options)
+}
 
-    let relative_path = file_path.to_string_lossy();
-
-    let file = db.get_file_by_path(&relative_path)?;
+/// Add and remove tags on the file at `relative_path`, returning its tags
+pub(crate) fn tag_file(db: &Database, relative_path: &str, options: &TagOptions) -> Result<Vec<String>> {
+    let file = db.get_file_by_path(relative_path)?;
     let file = match file {
         Some(f) => f,
         None => anyhow::bail!("File not found in database: {}", relative_path),
@@ -50,10 +63,92 @@ pub fn run_tag(library_path: &Path, file_path: &Path, options: TagOptions) -> Re
         db.remove_file_tag(file.id, tag)?;
     }
 
+    if !options.add.is_empty() || !options.remove.is_empty() {
+        db.record_file_activity(file.id, Activity::Edited, chrono::Utc::now().timestamp())?;
+    }
+
     // Return current tags
     db.get_file_tags(file.id)
 }
 
+/// Apply tags to a directory and everything below it
+///
+/// `add` tags the directory and copies the tags onto every contained file,
+/// `remove` untags the directory, its subdirectories and their files, and
+/// `materialize` writes inherited directory tags onto the files.
+///
+/// # Arguments
+/// * `library_path` - Path to the library root
+/// * `dir_path` - Directory (relative to library root, "." for the root)
+/// * `options` - Tags to add/remove, and whether to materialize
+pub fn run_tag_directory(
+    library_path: &Path,
+    dir_path: &Path,
+    options: TagOptions,
+) -> Result<DirectoryTagReport> {
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+
+    let db = Database::open(&db_path)?;
+
+    let relative_path = LibraryPath::from_relative(dir_path);
+    let relative_path = relative_path.as_str();
+
+    // The root has no directory row when it holds no files of its own
+    let dir = db.get_directory_by_path(relative_path)?;
+    if dir.is_none() && !relative_path.is_empty() {
+        anyhow::bail!("Directory not found in database: {}", relative_path);
+    }
+
+    let mut report = DirectoryTagReport::default();
+
+    for tag in &options.add {
+        if let Some(dir) = &dir {
+            db.add_directory_tag(dir.id, tag)?;
+        }
+        report.files_tagged += db.tag_subtree_files(relative_path, tag)?;
+    }
+
+    for tag in &options.remove {
+        let (files, dirs) = db.untag_subtree(relative_path, tag)?;
+        report.files_untagged += files;
+        report.dirs_untagged += dirs;
+    }
+
+    if options.materialize {
+        report.files_tagged += db.materialize_inherited_tags(relative_path)?;
+    }
+
+    Ok(report)
+}
+
+/// Remove the landscape/portrait tags `sync --orientation` added; filter on
+/// orientation instead. Files whose dimensions haven't been read yet keep
+/// theirs, so run `sync` first.
+///
+/// # Returns
+/// The number of file tags removed
+pub fn run_retire_orientation_tags(library_path: &Path) -> Result<usize> {
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+
+    let db = Database::open(&db_path)?;
+    db.begin_transaction()?;
+    let removed = db.remove_orientation_tags()?;
+    db.commit()?;
+    Ok(removed)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,4 +295,52 @@ mod tests {
         );
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_tag_directory_recursive() {
+        let temp_dir = TempDir::new().unwrap();
+        let lib_path = temp_dir.path().to_path_buf();
+        fs::create_dir_all(lib_path.join("trip/day1")).unwrap();
+        fs::write(lib_path.join("trip/a.jpg"), "fake jpeg").unwrap();
+        fs::write(lib_path.join("trip/day1/b.jpg"), "fake jpeg").unwrap();
+        crate::cli::run_init(&lib_path).unwrap();
+
+        let db = Database::open(&lib_path.join(".picman.db")).unwrap();
+        let day1 = db.get_directory_by_path("trip/day1").unwrap().unwrap();
+        db.add_directory_tag(day1.id, "beach").unwrap();
+
+        let report = run_tag_directory(
+            &lib_path,
+            Path::new("trip"),
+            TagOptions {
+                add: vec!["travel".to_string()],
+                materialize: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(report.files_tagged, 3); // travel x2, beach x1
+
+        let trip = db.get_directory_by_path("trip").unwrap().unwrap();
+        assert_eq!(db.get_directory_tags(trip.id).unwrap(), vec!["travel"]);
+        let b = db.get_file_by_path("trip/day1/b.jpg").unwrap().unwrap();
+        assert_eq!(db.get_file_tags(b.id).unwrap(), vec!["beach", "travel"]);
+
+        let report = run_tag_directory(
+            &lib_path,
+            Path::new("trip"),
+            TagOptions {
+                remove: vec!["beach".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            report,
+            DirectoryTagReport { files_tagged: 0, files_untagged: 1, dirs_untagged: 1 }
+        );
+        assert_eq!(db.get_file_tags(b.id).unwrap(), vec!["travel"]);
+
+        assert!(run_tag_directory(&lib_path, Path::new("nowhere"), TagOptions::default()).is_err());
+    }
 }
@@ -0,0 +1,72 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::db::Database;
+
+/// What `picman tags alias` should do
+#[derive(Debug)]
+pub enum TagAliasAction {
+    /// Make `alias` another name for `canonical`
+    Add { alias: String, canonical: String },
+    /// Stop treating `alias` as another name
+    Remove { alias: String },
+    List,
+}
+
+/// Add, remove or list tag aliases
+///
+/// # Returns
+/// The files and directories retagged from `alias` to `canonical` by `Add`
+/// (0 otherwise), and every alias as (alias, canonical) afterwards
+pub fn run_tag_alias(library_path: &Path, action: TagAliasAction) -> Result<(usize, Vec<(String, String)>)> {
+    let db_path = library_path.join(".picman.db");
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            library_path.display()
+        );
+    }
+
+    let db = Database::open(&db_path)?;
+
+    let retagged = match action {
+        TagAliasAction::Add { alias, canonical } => {
+            db.begin_transaction()?;
+            let retagged = db.add_tag_alias(&alias, &canonical)?;
+            db.commit()?;
+            retagged
+        }
+        TagAliasAction::Remove { alias } => {
+            if !db.remove_tag_alias(&alias)? {
+                anyhow::bail!("'{}' is not a tag alias", alias.trim());
+            }
+            0
+        }
+        TagAliasAction::List => 0,
+    };
+
+    Ok((retagged, db.get_tag_aliases()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::run_init;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_tag_alias_add_list_remove() {
+        let temp = TempDir::new().unwrap();
+        run_init(temp.path()).unwrap();
+
+        let add = TagAliasAction::Add { alias: "nyc".to_string(), canonical: "new york".to_string() };
+        let (retagged, aliases) = run_tag_alias(temp.path(), add).unwrap();
+        assert_eq!(retagged, 0);
+        assert_eq!(aliases, vec![("nyc".to_string(), "new york".to_string())]);
+
+        let remove = || TagAliasAction::Remove { alias: "nyc".to_string() };
+        assert!(run_tag_alias(temp.path(), remove()).unwrap().1.is_empty());
+        assert!(run_tag_alias(temp.path(), remove()).is_err());
+    }
+}
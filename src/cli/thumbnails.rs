@@ -1,17 +1,25 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::time::Duration;
 
 use anyhow::Result;
 use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
 
-use crate::db::Database;
+use crate::db::{Database, ThumbnailFailure};
+use crate::process::unix_now;
 use crate::thumbnails::{
-    generate_image_thumbnail, generate_video_thumbnail, generate_web_thumbnail,
-    generate_web_video_thumbnail, has_thumbnail, has_web_thumbnail, is_image_file, is_video_file,
+    compute_document_thumbnail_path, compute_thumbnail_path, compute_video_thumbnail_path,
+    generate_document_thumbnail, generate_image_thumbnail, generate_video_thumbnail,
+    get_dir_preview_dir, get_resized_dir, get_thumbnail_dir, get_web_thumbnail_dir,
+    get_web_thumbnail_path, has_thumbnail, has_web_thumbnail, is_document_file, is_image_file, is_thumbnailable_file,
+    is_video_file, try_generate_web_document_thumbnail, try_generate_web_thumbnail,
+    try_generate_web_video_thumbnail,
 };
+use crate::workers;
+use crate::library_path::LibraryPath;
 
 /// Statistics from thumbnail generation
 pub struct ThumbnailStats {
@@ -49,7 +57,7 @@ pub fn run_generate_thumbnails(library_path: &Path) -> Result<ThumbnailStats> {
 
     let checked = AtomicUsize::new(0);
 
-    let files_needing_thumbnails: Vec<(String, std::path::PathBuf)> = all_files
+    let files_needing_thumbnails: Vec<(i64, std::path::PathBuf)> = all_files
         .par_iter()
         .filter_map(|file| {
             let count = checked.fetch_add(1, Ordering::Relaxed);
@@ -58,19 +66,10 @@ pub fn run_generate_thumbnails(library_path: &Path) -> Result<ThumbnailStats> {
             }
 
             let dir_path = dir_paths.get(&file.directory_id).map(|s| s.as_str()).unwrap_or("");
-            let path = if dir_path.is_empty() {
-                library_path.join(&file.filename)
-            } else {
-                library_path.join(dir_path).join(&file.filename)
-            };
+            let path = LibraryPath::file(dir_path, &file.filename).absolute(library_path);
 
-            if (is_image_file(&path) || is_video_file(&path)) && !has_thumbnail(&path) {
-                let display_path = if dir_path.is_empty() {
-                    file.filename.clone()
-                } else {
-                    format!("{}/{}", dir_path, file.filename)
-                };
-                Some((display_path, path))
+            if is_thumbnailable_file(&path) && !has_thumbnail(&path) {
+                Some((file.directory_id, path))
             } else {
                 None
             }
@@ -83,6 +82,7 @@ pub fn run_generate_thumbnails(library_path: &Path) -> Result<ThumbnailStats> {
     let skipped = total_files - needing_count;
 
     if needing_count == 0 {
+        record_thumbnail_presence(&db, &directories, &HashSet::new())?;
         println!("All {} files already have thumbnails.", total_files);
         return Ok(ThumbnailStats {
             total: total_files,
@@ -100,39 +100,47 @@ pub fn run_generate_thumbnails(library_path: &Path) -> Result<ThumbnailStats> {
     let progress = ProgressBar::new(needing_count as u64);
     progress.set_style(
         ProgressStyle::default_bar()
-            .template("    {bar:40.cyan/blue} {pos}/{len} ({percent}%) | {msg}")
+            .template("    {bar:40.cyan/blue} {pos}/{len} ({percent}%) | {per_sec} | ETA {eta} | {msg}")
             .unwrap()
             .progress_chars("██░"),
     );
 
     let generated = AtomicUsize::new(0);
     let failed = AtomicUsize::new(0);
+    let incomplete_dirs = Mutex::new(HashSet::new());
+
+    workers::pool(0).install(|| {
+        files_needing_thumbnails
+            .par_iter()
+            .for_each(|(dir_id, path)| {
+                let result = if is_image_file(path) {
+                    generate_image_thumbnail(path).is_some()
+                } else if is_video_file(path) {
+                    generate_video_thumbnail(path).is_some()
+                } else if is_document_file(path) {
+                    generate_document_thumbnail(path).is_some()
+                } else {
+                    false
+                };
 
-    files_needing_thumbnails
-        .par_iter()
-        .for_each(|(_display_path, path)| {
-            let result = if is_image_file(path) {
-                generate_image_thumbnail(path).is_some()
-            } else if is_video_file(path) {
-                generate_video_thumbnail(path).is_some()
-            } else {
-                false
-            };
-
-            if result {
-                generated.fetch_add(1, Ordering::Relaxed);
-            } else {
-                failed.fetch_add(1, Ordering::Relaxed);
-            }
+                if result {
+                    generated.fetch_add(1, Ordering::Relaxed);
+                } else {
+                    failed.fetch_add(1, Ordering::Relaxed);
+                    incomplete_dirs.lock().unwrap().insert(*dir_id);
+                }
 
-            let gen = generated.load(Ordering::Relaxed);
-            let fail = failed.load(Ordering::Relaxed);
-            progress.set_message(format!("{} generated, {} failed", gen, fail));
-            progress.inc(1);
-        });
+                let gen = generated.load(Ordering::Relaxed);
+                let fail = failed.load(Ordering::Relaxed);
+                progress.set_message(format!("{} generated, {} failed", gen, fail));
+                progress.inc(1);
+            });
+    });
 
     progress.finish_and_clear();
 
+    record_thumbnail_presence(&db, &directories, &incomplete_dirs.into_inner().unwrap())?;
+
     let generated = generated.load(Ordering::Relaxed);
     let failed = failed.load(Ordering::Relaxed);
     println!("Done: {} generated, {} failed", generated, failed);
@@ -145,8 +153,26 @@ pub fn run_generate_thumbnails(library_path: &Path) -> Result<ThumbnailStats> {
     })
 }
 
-/// Generate small (400px) web thumbnails for all media files in the library
-pub fn run_generate_web_thumbnails(library_path: &Path) -> Result<ThumbnailStats> {
+/// Remember every directory that now has all its thumbnails, so the TUI's
+/// missing-preview check skips them until they change
+fn record_thumbnail_presence(
+    db: &Database,
+    directories: &[crate::db::Directory],
+    incomplete: &HashSet<i64>,
+) -> Result<()> {
+    db.begin_transaction()?;
+    for dir in directories.iter().filter(|d| !incomplete.contains(&d.id)) {
+        db.set_thumbnail_presence(dir.id, dir.mtime)?;
+    }
+    db.commit()
+}
+
+/// Generate small (400px) web thumbnails for all media files in the library.
+///
+/// Failures are recorded in the database. Files that failed permanently
+/// (corrupt or unsupported content) are skipped until they change, unless
+/// `retry_failed` is set; transient failures are retried on every run.
+pub fn run_generate_web_thumbnails(library_path: &Path, retry_failed: bool) -> Result<ThumbnailStats> {
     let db_path = library_path.join(".picman.db");
     let db = Database::open(&db_path)?;
 
@@ -160,6 +186,12 @@ pub fn run_generate_web_thumbnails(library_path: &Path) -> Result<ThumbnailStats
     let all_files = db.get_all_files()?;
     let total_files = all_files.len();
 
+    let known_failures: HashMap<i64, ThumbnailFailure> = db
+        .get_thumbnail_failures()?
+        .into_iter()
+        .map(|f| (f.file_id, f))
+        .collect();
+
     let spinner = ProgressBar::new_spinner();
     spinner.set_style(
         ProgressStyle::default_spinner()
@@ -169,8 +201,10 @@ pub fn run_generate_web_thumbnails(library_path: &Path) -> Result<ThumbnailStats
     spinner.enable_steady_tick(Duration::from_millis(100));
 
     let checked = AtomicUsize::new(0);
+    let skipped_failed = AtomicUsize::new(0);
 
-    let files_needing_thumbnails: Vec<(String, std::path::PathBuf)> = all_files
+    // (file_id, mtime, path)
+    let files_needing_thumbnails: Vec<(i64, i64, PathBuf)> = all_files
         .par_iter()
         .filter_map(|file| {
             let count = checked.fetch_add(1, Ordering::Relaxed);
@@ -179,32 +213,41 @@ pub fn run_generate_web_thumbnails(library_path: &Path) -> Result<ThumbnailStats
             }
 
             let dir_path = dir_paths.get(&file.directory_id).map(|s| s.as_str()).unwrap_or("");
-            let path = if dir_path.is_empty() {
-                library_path.join(&file.filename)
-            } else {
-                library_path.join(dir_path).join(&file.filename)
-            };
+            let path = LibraryPath::file(dir_path, &file.filename).absolute(library_path);
 
-            if (is_image_file(&path) || is_video_file(&path)) && !has_web_thumbnail(&path) {
-                let display_path = if dir_path.is_empty() {
-                    file.filename.clone()
-                } else {
-                    format!("{}/{}", dir_path, file.filename)
-                };
-                Some((display_path, path))
-            } else {
-                None
+            if !is_thumbnailable_file(&path) || has_web_thumbnail(&path) {
+                return None;
+            }
+
+            let known_permanent = known_failures
+                .get(&file.id)
+                .is_some_and(|f| f.permanent && f.mtime == file.mtime);
+            if known_permanent && !retry_failed {
+                skipped_failed.fetch_add(1, Ordering::Relaxed);
+                return None;
             }
+
+            Some((file.id, file.mtime, path))
         })
         .collect();
 
     spinner.finish_and_clear();
 
     let needing_count = files_needing_thumbnails.len();
+    let skipped_failed = skipped_failed.load(Ordering::Relaxed);
     let skipped = total_files - needing_count;
 
+    if skipped_failed > 0 {
+        println!(
+            "Skipping {} files that failed permanently before (use --retry-failed to retry)",
+            skipped_failed
+        );
+    }
+
     if needing_count == 0 {
-        println!("All {} files already have web thumbnails.", total_files);
+        if skipped_failed == 0 {
+            println!("All {} files already have web thumbnails.", total_files);
+        }
         return Ok(ThumbnailStats {
             total: total_files,
             generated: 0,
@@ -215,57 +258,332 @@ pub fn run_generate_web_thumbnails(library_path: &Path) -> Result<ThumbnailStats
 
     println!(
         "Generating {} web thumbnails ({} already exist)...",
-        needing_count, skipped
+        needing_count,
+        skipped - skipped_failed
     );
 
     let progress = ProgressBar::new(needing_count as u64);
     progress.set_style(
         ProgressStyle::default_bar()
-            .template("    {bar:40.cyan/blue} {pos}/{len} ({percent}%) | {msg}")
+            .template("    {bar:40.cyan/blue} {pos}/{len} ({percent}%) | {per_sec} | ETA {eta} | {msg}")
             .unwrap()
             .progress_chars("██░"),
     );
 
     let generated = AtomicUsize::new(0);
     let failed = AtomicUsize::new(0);
+    let new_failures: Mutex<Vec<ThumbnailFailure>> = Mutex::new(Vec::new());
+    let recovered: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+
+    workers::pool(0).install(|| {
+        files_needing_thumbnails
+            .par_iter()
+            .for_each(|(file_id, mtime, path)| {
+                let result = if is_image_file(path) {
+                    try_generate_web_thumbnail(path)
+                } else if is_video_file(path) {
+                    try_generate_web_video_thumbnail(path)
+                } else {
+                    try_generate_web_document_thumbnail(path)
+                };
 
-    files_needing_thumbnails
-        .par_iter()
-        .for_each(|(_display_path, path)| {
-            let result = if is_image_file(path) {
-                generate_web_thumbnail(path).is_some()
+                match result {
+                    Ok(_) => {
+                        generated.fetch_add(1, Ordering::Relaxed);
+                        if known_failures.contains_key(file_id) {
+                            recovered.lock().unwrap().push(*file_id);
+                        }
+                    }
+                    Err(e) => {
+                        failed.fetch_add(1, Ordering::Relaxed);
+                        new_failures.lock().unwrap().push(ThumbnailFailure {
+                            file_id: *file_id,
+                            mtime: *mtime,
+                            reason: e.reason,
+                            permanent: e.permanent,
+                            failed_at: unix_now(),
+                        });
+                    }
+                }
+
+                let gen = generated.load(Ordering::Relaxed);
+                let fail = failed.load(Ordering::Relaxed);
+                progress.set_message(format!("{} generated, {} failed", gen, fail));
+                progress.inc(1);
+            });
+    });
+
+    progress.finish_and_clear();
+
+    db.begin_transaction()?;
+    for file_id in recovered.into_inner().unwrap() {
+        db.clear_thumbnail_failure(file_id)?;
+    }
+    for failure in new_failures.into_inner().unwrap() {
+        db.record_thumbnail_failure(&failure)?;
+    }
+    db.commit()?;
+
+    let generated = generated.load(Ordering::Relaxed);
+    let failed = failed.load(Ordering::Relaxed);
+    println!("Done: {} generated, {} failed", generated, failed);
+
+    Ok(ThumbnailStats {
+        total: total_files,
+        generated,
+        skipped,
+        failed,
+    })
+}
+
+/// Generate missing thumbnails and web thumbnails for the files in
+/// `dir_ids`, without progress output. Used by `picman daemon` right after
+/// its watcher picks up new files; each file holds a
+/// [`workers::decode_permit`] so this shares the decode cap with the web
+/// server's on-demand previews.
+pub fn run_generate_directory_thumbnails(library_path: &Path, dir_ids: &[i64]) -> Result<ThumbnailStats> {
+    let db_path = library_path.join(".picman.db");
+    let db = Database::open(&db_path)?;
+
+    let known_failures: HashSet<i64> = db
+        .get_thumbnail_failures()?
+        .into_iter()
+        .map(|f| f.file_id)
+        .collect();
+
+    let mut directories = Vec::new();
+    // (dir_id, file_id, mtime, path)
+    let mut candidates: Vec<(i64, i64, i64, PathBuf)> = Vec::new();
+    for &dir_id in dir_ids {
+        // Gone since the sync that reported it
+        let Some(dir) = db.get_directory(dir_id)? else {
+            continue;
+        };
+        let dir_path = library_path.join(&dir.path);
+        for file in db.get_files_in_directory(dir_id)? {
+            let path = dir_path.join(&file.filename);
+            if is_thumbnailable_file(&path) {
+                candidates.push((dir_id, file.id, file.mtime, path));
+            }
+        }
+        directories.push(dir);
+    }
+
+    let total = candidates.len();
+    let needing: Vec<_> = candidates
+        .into_iter()
+        .filter(|(_, _, _, path)| !has_thumbnail(path) || !has_web_thumbnail(path))
+        .collect();
+    let skipped = total - needing.len();
+
+    let generated = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+    let incomplete_dirs = Mutex::new(HashSet::new());
+    let new_failures: Mutex<Vec<ThumbnailFailure>> = Mutex::new(Vec::new());
+    let recovered: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+
+    workers::pool(0).install(|| {
+        needing.par_iter().for_each(|(dir_id, file_id, mtime, path)| {
+            let _permit = workers::decode_permit();
+
+            let thumb_ok = has_thumbnail(path)
+                || if is_image_file(path) {
+                    generate_image_thumbnail(path).is_some()
+                } else if is_video_file(path) {
+                    generate_video_thumbnail(path).is_some()
+                } else {
+                    generate_document_thumbnail(path).is_some()
+                };
+
+            let web_result = if has_web_thumbnail(path) {
+                Ok(())
+            } else if is_image_file(path) {
+                try_generate_web_thumbnail(path).map(|_| ())
             } else if is_video_file(path) {
-                generate_web_video_thumbnail(path).is_some()
+                try_generate_web_video_thumbnail(path).map(|_| ())
             } else {
-                false
+                try_generate_web_document_thumbnail(path).map(|_| ())
             };
+            let web_ok = web_result.is_ok();
+
+            match web_result {
+                Ok(()) if known_failures.contains(file_id) => recovered.lock().unwrap().push(*file_id),
+                Ok(()) => {}
+                Err(e) => new_failures.lock().unwrap().push(ThumbnailFailure {
+                    file_id: *file_id,
+                    mtime: *mtime,
+                    reason: e.reason,
+                    permanent: e.permanent,
+                    failed_at: unix_now(),
+                }),
+            }
 
-            if result {
+            if thumb_ok && web_ok {
                 generated.fetch_add(1, Ordering::Relaxed);
             } else {
                 failed.fetch_add(1, Ordering::Relaxed);
             }
-
-            let gen = generated.load(Ordering::Relaxed);
-            let fail = failed.load(Ordering::Relaxed);
-            progress.set_message(format!("{} generated, {} failed", gen, fail));
-            progress.inc(1);
+            if !thumb_ok {
+                incomplete_dirs.lock().unwrap().insert(*dir_id);
+            }
         });
+    });
 
-    progress.finish_and_clear();
-
-    let generated = generated.load(Ordering::Relaxed);
-    let failed = failed.load(Ordering::Relaxed);
-    println!("Done: {} generated, {} failed", generated, failed);
+    db.begin_transaction()?;
+    for file_id in recovered.into_inner().unwrap() {
+        db.clear_thumbnail_failure(file_id)?;
+    }
+    for failure in new_failures.into_inner().unwrap() {
+        db.record_thumbnail_failure(&failure)?;
+    }
+    db.commit()?;
+    record_thumbnail_presence(&db, &directories, &incomplete_dirs.into_inner().unwrap())?;
 
     Ok(ThumbnailStats {
-        total: total_files,
-        generated,
+        total,
+        generated: generated.load(Ordering::Relaxed),
         skipped,
-        failed,
+        failed: failed.load(Ordering::Relaxed),
     })
 }
 
+/// Statistics from cache garbage collection
+pub struct CacheGcStats {
+    pub scanned: usize,
+    pub removed: usize,
+    pub bytes_freed: u64,
+}
+
+/// Remove cached thumbnails, web thumbnails, resized images, and directory
+/// previews that no longer belong to any file or directory in the library.
+///
+/// Cache keys hash the file path and mtime, so every edited, moved, or deleted
+/// file leaves an orphan behind. The cache is shared between libraries, so GC
+/// in one library also drops other libraries' entries; those are regenerated
+/// by their next thumbnail run.
+pub fn run_cache_gc(library_path: &Path) -> Result<CacheGcStats> {
+    let db_path = library_path.join(".picman.db");
+    let db = Database::open(&db_path)?;
+
+    let directories = db.get_all_directories()?;
+    let all_files = db.get_all_files()?;
+
+    let dir_paths: HashMap<i64, String> = directories
+        .iter()
+        .map(|d| (d.id, d.path.clone()))
+        .collect();
+
+    // Thumbnail keys hash the path as it was passed in, so keep entries for
+    // both the given and the canonical library path
+    let mut roots = vec![library_path.to_path_buf()];
+    if let Ok(canonical) = library_path.canonicalize() {
+        if canonical != library_path {
+            roots.push(canonical);
+        }
+    }
+
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.cyan} {msg}")
+            .unwrap(),
+    );
+    spinner.enable_steady_tick(Duration::from_millis(100));
+
+    let checked = AtomicUsize::new(0);
+    let total_files = all_files.len();
+
+    let live: HashSet<PathBuf> = all_files
+        .par_iter()
+        .flat_map_iter(|file| {
+            let count = checked.fetch_add(1, Ordering::Relaxed);
+            if count.is_multiple_of(1000) {
+                spinner.set_message(format!("Collecting live cache entries... {}/{}", count, total_files));
+            }
+
+            let dir_path = dir_paths.get(&file.directory_id).map(|s| s.as_str()).unwrap_or("");
+            let mut keep = Vec::new();
+            for root in &roots {
+                let path = LibraryPath::file(dir_path, &file.filename).absolute(root);
+                if !is_thumbnailable_file(&path) {
+                    break;
+                }
+                let Some(mtime) = std::fs::metadata(&path).ok().and_then(|m| m.modified().ok())
+                else {
+                    break;
+                };
+                keep.extend(compute_thumbnail_path(&path, mtime));
+                keep.extend(compute_video_thumbnail_path(&path, mtime));
+                keep.extend(compute_document_thumbnail_path(&path, mtime));
+                keep.extend(get_web_thumbnail_path(&path));
+            }
+            keep
+        })
+        .collect();
+
+    let live_previews: HashSet<String> = directories
+        .iter()
+        .map(|d| format!("{}.jpg", d.id))
+        .collect();
+
+    spinner.set_message("Removing orphaned cache entries...");
+
+    let mut stats = CacheGcStats {
+        scanned: 0,
+        removed: 0,
+        bytes_freed: 0,
+    };
+
+    let mut sweep = |dir: Option<PathBuf>, is_live: &dyn Fn(&Path) -> bool| {
+        let Some(dir) = dir else { return };
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("jpg") {
+                continue;
+            }
+            stats.scanned += 1;
+            if is_live(&path) {
+                continue;
+            }
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            if std::fs::remove_file(&path).is_ok() {
+                stats.removed += 1;
+                stats.bytes_freed += size;
+            }
+        }
+    };
+
+    sweep(get_thumbnail_dir(), &|p| live.contains(p));
+    sweep(get_web_thumbnail_dir(), &|p| live.contains(p));
+    // Resized versions are named `<web thumbnail key>_<size>.jpg`
+    if let Some(web_dir) = get_web_thumbnail_dir() {
+        sweep(get_resized_dir(), &|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.split_once('_'))
+                .is_some_and(|(key, _)| live.contains(&web_dir.join(format!("{}.jpg", key))))
+        });
+    }
+    sweep(get_dir_preview_dir(), &|p| {
+        p.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| live_previews.contains(n))
+    });
+
+    spinner.finish_and_clear();
+    println!(
+        "Cache GC: removed {} of {} entries ({:.1} MB freed)",
+        stats.removed,
+        stats.scanned,
+        stats.bytes_freed as f64 / 1_048_576.0
+    );
+
+    Ok(stats)
+}
+
 /// Check which directories have missing thumbnails without generating them
 pub fn run_check_thumbnails(library_path: &Path) -> Result<()> {
     let db_path = library_path.join(".picman.db");
@@ -304,13 +622,9 @@ pub fn run_check_thumbnails(library_path: &Path) -> Result<()> {
             }
 
             let dir_path = dir_paths.get(&file.directory_id).map(|s| s.as_str()).unwrap_or("");
-            let path = if dir_path.is_empty() {
-                library_path.join(&file.filename)
-            } else {
-                library_path.join(dir_path).join(&file.filename)
-            };
+            let path = LibraryPath::file(dir_path, &file.filename).absolute(library_path);
 
-            if (is_image_file(&path) || is_video_file(&path)) && !has_thumbnail(&path) {
+            if is_thumbnailable_file(&path) && !has_thumbnail(&path) {
                 // Return top-level directory
                 let top_dir = dir_path.split('/').next().unwrap_or("").to_string();
                 Some(if top_dir.is_empty() { "(root)".to_string() } else { top_dir })
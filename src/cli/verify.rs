@@ -0,0 +1,127 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use tracing::{info, warn};
+
+use crate::db::Database;
+use crate::hash::compute_file_hash_with;
+use crate::vfs::open_library_fs;
+use crate::workers;
+
+use super::init::DB_FILENAME;
+use super::post_process::HASH_THREADS;
+
+/// What `picman verify` found
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// Files rehashed
+    pub checked: usize,
+    /// Files whose content no longer matches their stored hash
+    pub mismatched: Vec<PathBuf>,
+    /// Files that couldn't be read (deleted since the last sync, or unreadable)
+    pub unreadable: Vec<PathBuf>,
+}
+
+/// Rehash every hashed file with the algorithm its stored hash was made
+/// with and compare. Sync drops the hash of a file whose size or mtime
+/// changed, so a mismatch means the content changed underneath it (bit rot,
+/// a bad copy).
+pub fn run_verify(library_path: &Path) -> Result<VerifyReport> {
+    let library_path = library_path
+        .canonicalize()
+        .with_context(|| format!("Library path does not exist: {}", library_path.display()))?;
+
+    let db_path = library_path.join(DB_FILENAME);
+    if !db_path.exists() {
+        anyhow::bail!(
+            "No database found at {}. Run 'picman init' first.",
+            db_path.display()
+        );
+    }
+    if open_library_fs(&library_path)?.is_remote() {
+        anyhow::bail!("Verifying reads every original; it is not available for remote libraries");
+    }
+
+    let db = Database::open(&db_path)?;
+    let files = db.get_hashed_files()?;
+    info!(total = files.len(), "files to verify");
+
+    let total_bytes: u64 = files.iter().map(|f| f.size.max(0) as u64).sum();
+    let progress = ProgressBar::new(total_bytes);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("    {bar:40.cyan/blue} {bytes}/{total_bytes} ({binary_bytes_per_sec}) | {elapsed_precise} | ETA {eta} | {msg}")
+            .unwrap()
+            .progress_chars("██░"),
+    );
+    progress.set_message(format!("verifying {} files", files.len()));
+
+    let results: Vec<_> = workers::pool(HASH_THREADS).install(|| {
+        files
+            .par_iter()
+            .map(|file| {
+                let full_path = library_path.join(&file.path);
+                let result = compute_file_hash_with(&full_path, file.algorithm, |bytes| progress.inc(bytes));
+                (file, result)
+            })
+            .collect()
+    });
+
+    let mut report = VerifyReport::default();
+    for (file, result) in results {
+        match result {
+            Ok(hash) if hash == file.hash => report.checked += 1,
+            Ok(hash) => {
+                warn!(path = %file.path.display(), expected = %file.hash, actual = %hash, "hash mismatch");
+                report.checked += 1;
+                report.mismatched.push(file.path.clone());
+            }
+            Err(e) => {
+                warn!(path = %file.path.display(), error = %e, "failed to verify file");
+                report.unreadable.push(file.path.clone());
+            }
+        }
+    }
+
+    progress.finish_with_message(format!("{} files verified", report.checked));
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{run_init, run_sync};
+    use crate::config::config_path;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_verify_finds_changed_and_missing_files() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("photos")).unwrap();
+        fs::write(root.join("photos/a.jpg"), "aaaa").unwrap();
+        fs::write(root.join("photos/b.jpg"), "bbbb").unwrap();
+        fs::write(root.join("photos/c.jpg"), "cccc").unwrap();
+        run_init(root).unwrap();
+        fs::write(config_path(root), "[hashing]\nalgorithm = \"blake3\"\n").unwrap();
+        run_sync(root, true, false, false).unwrap();
+
+        let report = run_verify(root).unwrap();
+        assert_eq!((report.checked, report.mismatched.len(), report.unreadable.len()), (3, 0, 0));
+
+        // Same size and mtime, different content: sync keeps the hash
+        let b = root.join("photos/b.jpg");
+        let mtime = fs::metadata(&b).unwrap().modified().unwrap();
+        fs::write(&b, "BBBB").unwrap();
+        fs::File::options().write(true).open(&b).unwrap().set_modified(mtime).unwrap();
+        fs::remove_file(root.join("photos/c.jpg")).unwrap();
+
+        let report = run_verify(root).unwrap();
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.mismatched, vec![PathBuf::from("photos/b.jpg")]);
+        assert_eq!(report.unreadable, vec![PathBuf::from("photos/c.jpg")]);
+    }
+}
@@ -0,0 +1,221 @@
+//! Watermarks for `picman export-resized`: a PNG overlay, or text rendered
+//! once with ImageMagick, scaled to each exported image and blended in.
+//! Originals are never touched.
+
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use image::{imageops, Rgb, RgbImage, RgbaImage};
+
+use crate::thumbnails::tool_available;
+
+/// Font size text is rendered at before scaling to each image
+const TEXT_POINT_SIZE: u32 = 120;
+
+/// Where the watermark goes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum WatermarkPosition {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    #[default]
+    BottomRight,
+    /// Repeated across the whole image
+    Tile,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatermarkSource {
+    Text(String),
+    /// A PNG, usually with transparency
+    Image(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct WatermarkOptions {
+    pub source: WatermarkSource,
+    pub position: WatermarkPosition,
+    /// 0 (invisible) to 1 (as drawn)
+    pub opacity: f32,
+    /// Watermark width as a fraction of the image width
+    pub scale: f32,
+}
+
+/// A loaded watermark, ready to apply to any number of images
+#[derive(Debug)]
+pub struct Watermark {
+    mark: RgbaImage,
+    position: WatermarkPosition,
+    opacity: f32,
+    scale: f32,
+}
+
+impl Watermark {
+    pub fn load(options: &WatermarkOptions) -> Result<Self> {
+        if !(0.0..=1.0).contains(&options.opacity) {
+            anyhow::bail!("Watermark opacity must be between 0 and 1");
+        }
+        if !(options.scale > 0.0 && options.scale <= 1.0) {
+            anyhow::bail!("Watermark scale must be more than 0 and at most 1");
+        }
+        let mark = match &options.source {
+            WatermarkSource::Image(path) => image::open(path)
+                .with_context(|| format!("Could not read watermark {}", path.display()))?
+                .to_rgba8(),
+            WatermarkSource::Text(text) => render_text(text)?,
+        };
+        if mark.width() == 0 || mark.height() == 0 {
+            anyhow::bail!("Watermark is empty");
+        }
+        Ok(Self { mark, position: options.position, opacity: options.opacity, scale: options.scale })
+    }
+
+    /// Blend the watermark into `image`
+    pub fn apply(&self, image: &mut RgbImage) {
+        let width = ((image.width() as f32 * self.scale).round() as u32).max(1);
+        let height = ((self.mark.height() as f64 * width as f64 / self.mark.width() as f64).round() as u32).max(1);
+        let mark = imageops::resize(&self.mark, width, height, imageops::FilterType::Triangle);
+
+        if self.position == WatermarkPosition::Tile {
+            // Half a mark of space between copies, every other row shifted
+            let (step_x, step_y) = (width + width / 2, height * 2);
+            let mut row = 0;
+            let mut y = 0;
+            while y < image.height() {
+                let mut x = if row % 2 == 0 { 0 } else { -((step_x / 2) as i64) };
+                while x < image.width() as i64 {
+                    self.blend(image, &mark, x, y as i64);
+                    x += step_x as i64;
+                }
+                y += step_y;
+                row += 1;
+            }
+            return;
+        }
+
+        let margin = (image.width().min(image.height()) / 50) as i64;
+        let (free_x, free_y) = (image.width() as i64 - width as i64, image.height() as i64 - height as i64);
+        let (column, row) = match self.position {
+            WatermarkPosition::TopLeft => (0, 0),
+            WatermarkPosition::Top => (1, 0),
+            WatermarkPosition::TopRight => (2, 0),
+            WatermarkPosition::Left => (0, 1),
+            WatermarkPosition::Center | WatermarkPosition::Tile => (1, 1),
+            WatermarkPosition::Right => (2, 1),
+            WatermarkPosition::BottomLeft => (0, 2),
+            WatermarkPosition::Bottom => (1, 2),
+            WatermarkPosition::BottomRight => (2, 2),
+        };
+        let place = |anchor: i32, free: i64| match anchor {
+            0 => margin,
+            1 => free / 2,
+            _ => free - margin,
+        };
+        self.blend(image, &mark, place(column, free_x), place(row, free_y));
+    }
+
+    fn blend(&self, image: &mut RgbImage, mark: &RgbaImage, left: i64, top: i64) {
+        for (mx, my, pixel) in mark.enumerate_pixels() {
+            let (x, y) = (left + mx as i64, top + my as i64);
+            if x < 0 || y < 0 || x >= image.width() as i64 || y >= image.height() as i64 {
+                continue;
+            }
+            let alpha = pixel[3] as f32 / 255.0 * self.opacity;
+            if alpha <= 0.0 {
+                continue;
+            }
+            let under = image.get_pixel_mut(x as u32, y as u32);
+            let mix = |m: u8, u: u8| (m as f32 * alpha + u as f32 * (1.0 - alpha)).round() as u8;
+            *under = Rgb([mix(pixel[0], under[0]), mix(pixel[1], under[1]), mix(pixel[2], under[2])]);
+        }
+    }
+}
+
+/// White text with a dark outline on a transparent background
+fn render_text(text: &str) -> Result<RgbaImage> {
+    let tool = ["magick", "convert"]
+        .into_iter()
+        .find(|tool| tool_available(tool))
+        .context("Text watermarks need ImageMagick (magick or convert); install it or use a PNG watermark")?;
+    let output = std::env::temp_dir().join(format!("picman-watermark-{}.png", std::process::id()));
+    let status = Command::new(tool)
+        .args(["-background", "none", "-fill", "white", "-stroke", "#00000080", "-strokewidth", "2"])
+        .arg("-pointsize")
+        .arg(TEXT_POINT_SIZE.to_string())
+        // `label:` would read a file for text starting with @
+        .arg(format!("label:{}", text.strip_prefix('@').map_or(text.to_string(), |t| format!("\\@{}", t))))
+        .arg(&output)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .with_context(|| format!("Could not run {}", tool))?;
+    let rendered = if status.success() { image::open(&output).ok() } else { None };
+    let _ = std::fs::remove_file(&output);
+    rendered
+        .map(|image| image.to_rgba8())
+        .with_context(|| format!("{} could not render the watermark text", tool))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn watermark(position: WatermarkPosition, opacity: f32) -> Watermark {
+        // A white square, transparent on its right half
+        let mut mark = RgbaImage::from_pixel(10, 10, Rgba([255, 255, 255, 255]));
+        for y in 0..10 {
+            for x in 5..10 {
+                mark.put_pixel(x, y, Rgba([255, 255, 255, 0]));
+            }
+        }
+        Watermark { mark, position, opacity, scale: 0.1 }
+    }
+
+    #[test]
+    fn test_places_and_blends_the_mark() {
+        let mut image = RgbImage::from_pixel(200, 100, Rgb([0, 0, 0]));
+        watermark(WatermarkPosition::BottomRight, 0.5).apply(&mut image);
+        // 20x20 mark, 2px margin: opaque half at x 178..188, y 78..98
+        assert_eq!(*image.get_pixel(180, 90), Rgb([128, 128, 128]));
+        assert_eq!(*image.get_pixel(192, 90), Rgb([0, 0, 0]));
+        assert_eq!(*image.get_pixel(10, 10), Rgb([0, 0, 0]));
+
+        let mut image = RgbImage::from_pixel(200, 100, Rgb([0, 0, 0]));
+        watermark(WatermarkPosition::TopLeft, 1.0).apply(&mut image);
+        assert_eq!(*image.get_pixel(3, 3), Rgb([255, 255, 255]));
+        assert_eq!(*image.get_pixel(180, 90), Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_tiles_across_the_image() {
+        let mut image = RgbImage::from_pixel(200, 100, Rgb([0, 0, 0]));
+        watermark(WatermarkPosition::Tile, 1.0).apply(&mut image);
+        let marked = image.pixels().filter(|p| p[0] == 255).count();
+        // Roughly a sixth covered: half-opaque marks every 1.5 widths, every other row
+        assert!(marked > 200 * 100 / 10 && marked < 200 * 100 / 3, "{}", marked);
+        assert_eq!(*image.get_pixel(0, 0), Rgb([255, 255, 255]));
+        // Rows of marks are 20px tall, 40px apart
+        assert_eq!(*image.get_pixel(0, 30), Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_rejects_bad_options() {
+        let options = |opacity, scale| WatermarkOptions {
+            source: WatermarkSource::Image(PathBuf::from("/nonexistent.png")),
+            position: WatermarkPosition::Center,
+            opacity,
+            scale,
+        };
+        assert!(Watermark::load(&options(1.5, 0.2)).is_err());
+        assert!(Watermark::load(&options(0.5, 0.0)).is_err());
+        assert!(Watermark::load(&options(0.5, 0.2)).is_err());
+    }
+}
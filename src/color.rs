@@ -0,0 +1,132 @@
+//! Color management for decoded originals.
+//!
+//! Cameras and editors embed ICC profiles (AdobeRGB, Display P3) in wide-gamut
+//! photos. Terminals, browsers showing our thumbnails, and the JPEG thumbnails
+//! themselves all assume sRGB, so such pixels look dull or shifted unless they
+//! are converted first. [`crate::thumbnails::open_image`] decodes through
+//! [`open_srgb`], which covers TUI previews, cached thumbnails and web thumbs.
+
+use std::path::Path;
+
+use image::{DynamicImage, ImageDecoder, ImageReader, ImageResult};
+use moxcms::{ColorProfile, DataColorSpace, Layout, TransformOptions};
+use tracing::debug;
+
+/// Decode an image and convert it to sRGB if it carries an ICC profile
+pub fn open_srgb(path: &Path) -> ImageResult<DynamicImage> {
+    let mut decoder = ImageReader::open(path)?.with_guessed_format()?.into_decoder()?;
+    // A broken profile shouldn't cost the image
+    let icc = decoder.icc_profile().unwrap_or(None);
+    let image = DynamicImage::from_decoder(decoder)?;
+    Ok(match icc {
+        Some(icc) => convert_to_srgb(image, &icc),
+        None => image,
+    })
+}
+
+/// Convert RGB pixels from the color space described by `icc` to sRGB.
+/// Images already in sRGB, grayscale or CMYK profiles, and profiles that
+/// can't be parsed are returned unchanged.
+pub fn convert_to_srgb(image: DynamicImage, icc: &[u8]) -> DynamicImage {
+    let profile = match ColorProfile::new_from_slice(icc) {
+        Ok(profile) => profile,
+        Err(e) => {
+            debug!(error = ?e, "ignoring unreadable ICC profile");
+            return image;
+        }
+    };
+    if profile.color_space != DataColorSpace::Rgb || !image.color().has_color() || is_srgb(&profile) {
+        return image;
+    }
+
+    let srgb = ColorProfile::new_srgb();
+    let options = TransformOptions::default();
+    if image.color().has_alpha() {
+        let mut pixels = image.to_rgba8();
+        let Ok(transform) = profile.create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgba, options) else {
+            return image;
+        };
+        let source = pixels.as_raw().clone();
+        if transform.transform(&source, &mut pixels).is_err() {
+            return image;
+        }
+        DynamicImage::ImageRgba8(pixels)
+    } else {
+        let mut pixels = image.to_rgb8();
+        let Ok(transform) = profile.create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, options) else {
+            return image;
+        };
+        let source = pixels.as_raw().clone();
+        if transform.transform(&source, &mut pixels).is_err() {
+            return image;
+        }
+        DynamicImage::ImageRgb8(pixels)
+    }
+}
+
+/// Whether the profile's primaries are sRGB's. Most JPEGs embed an sRGB
+/// profile; converting those would cost a pass over every pixel for nothing.
+fn is_srgb(profile: &ColorProfile) -> bool {
+    let srgb = ColorProfile::new_srgb();
+    let close = |a: moxcms::Xyzd, b: moxcms::Xyzd| {
+        (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3 && (a.z - b.z).abs() < 1e-3
+    };
+    close(profile.red_colorant, srgb.red_colorant)
+        && close(profile.green_colorant, srgb.green_colorant)
+        && close(profile.blue_colorant, srgb.blue_colorant)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn pure_green() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(2, 2, Rgb([0, 255, 0])))
+    }
+
+    #[test]
+    fn test_wide_gamut_pixels_are_converted() {
+        // The same stored values mean a more saturated green in AdobeRGB and
+        // Display P3 than in sRGB, so converting pushes red and blue down
+        let stored = Rgb([100, 150, 100]);
+        for profile in [ColorProfile::new_adobe_rgb(), ColorProfile::new_display_p3()] {
+            let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, stored));
+            let Rgb([r, g, b]) = *convert_to_srgb(image, &profile.encode().unwrap()).to_rgb8().get_pixel(0, 0);
+            assert!(r < 100 && b < 100, "got {:?}", (r, g, b));
+            assert!(g >= 150);
+        }
+    }
+
+    #[test]
+    fn test_srgb_and_unreadable_profiles_leave_pixels_alone() {
+        let icc = ColorProfile::new_srgb().encode().unwrap();
+        assert_eq!(convert_to_srgb(pure_green(), &icc), pure_green());
+        assert_eq!(convert_to_srgb(pure_green(), b"not a profile"), pure_green());
+    }
+
+    #[test]
+    fn test_open_srgb_reads_embedded_profile() {
+        use image::codecs::png::PngEncoder;
+        use image::ImageEncoder;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("wide.png");
+        let source = RgbImage::from_pixel(1, 1, Rgb([100, 150, 100]));
+        let file = std::fs::File::create(&path).unwrap();
+        let mut encoder = PngEncoder::new(file);
+        encoder
+            .set_icc_profile(ColorProfile::new_adobe_rgb().encode().unwrap())
+            .unwrap();
+        encoder
+            .write_image(source.as_raw(), 1, 1, image::ExtendedColorType::Rgb8)
+            .unwrap();
+
+        let decoded = open_srgb(&path).unwrap().to_rgb8();
+        assert_ne!(*decoded.get_pixel(0, 0), Rgb([100, 150, 100]));
+        // Without a profile the pixels come through as stored
+        let plain = tmp.path().join("plain.png");
+        source.save(&plain).unwrap();
+        assert_eq!(*open_srgb(&plain).unwrap().to_rgb8().get_pixel(0, 0), Rgb([100, 150, 100]));
+    }
+}
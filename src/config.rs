@@ -0,0 +1,488 @@
+//! Per-library configuration loaded from `<library>/.picman.toml`.
+//!
+//! ```toml
+//! [maintenance]
+//! hash_backfill = "0 3 * * *"   # nightly at 03:00
+//! thumbnails = "30 3 * * *"
+//! cache_gc = "0 4 * * sun"      # weekly
+//!
+//! [remote]
+//! url = "ssh://me@nas/volume1/photos"
+//! ssh_command = "ssh -i ~/.ssh/nas"   # optional, defaults to "ssh"
+//!
+//! [hashing]
+//! algorithm = "xxh3"   # or "xxh3-128", "sha256", "blake3"
+//! prefilter = true     # full-hash only files whose quick hash collides
+//!
+//! [archive]
+//! aws_command = "aws --profile cold"   # optional, defaults to "aws"
+//! endpoint_url = "https://s3.eu-central-003.backblazeb2.com"
+//!
+//! [scan]
+//! exclude = ["@eaDir", "*.tmp"]   # names of files or directories to skip
+//! follow_symlinks = true   # scan through symlinks (skipped by default)
+//! dotfiles = true          # index dot-prefixed files and directories
+//!
+//! [notifications]
+//! enabled = true     # desktop notifications when long jobs finish
+//! sync = false       # per job type: thumbnails, hashing, sync
+//! min_seconds = 60   # only for jobs that ran at least this long
+//!
+//! [tui]
+//! tree_sort = "size"   # name (default), rating, newest or size; set with `O`
+//! preview_resolution = 2560   # widest decoded preview in pixels (default 1920)
+//! preview_memory_mb = 400      # memory for cached previews (default 1000)
+//!
+//! [previews]
+//! cover = "recent"   # rating (default), recent or random
+//!
+//! [ratings]
+//! scale = 10   # 5 (default) or 10; change with `picman rating-scale`
+//!
+//! [duplicates]
+//! keep = "prefix:photos/sorted"   # resolution (default), oldest, raw, shortest-path
+//! ```
+//!
+//! The file is optional; a missing file means defaults everywhere.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::cron::CronSchedule;
+use crate::hash::HashAlgorithm;
+use crate::keep_policy::KeepPolicy;
+use crate::maintenance::MaintenanceTask;
+use crate::rating::RatingScale;
+use crate::thumbnails::PreviewCover;
+use crate::tui::preview_loader::DEFAULT_PREVIEW_RESOLUTION;
+use crate::tui::state::TreeSort;
+use crate::vfs::SshTarget;
+
+pub const CONFIG_FILENAME: &str = ".picman.toml";
+
+/// Location of the config file for a library
+pub fn config_path(library_path: &Path) -> PathBuf {
+    library_path.join(CONFIG_FILENAME)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    pub remote: Option<RemoteConfig>,
+    #[serde(default)]
+    pub hashing: HashingConfig,
+    #[serde(default)]
+    pub archive: ArchiveConfig,
+    #[serde(default)]
+    pub scan: ScanConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub tui: TuiConfig,
+    #[serde(default)]
+    pub previews: PreviewsConfig,
+    #[serde(default)]
+    pub ratings: RatingsConfig,
+    #[serde(default)]
+    pub duplicates: DuplicatesConfig,
+}
+
+/// How duplicate groups are reviewed
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DuplicatesConfig {
+    /// Which copy `picman dupes` and the web view suggest keeping
+    #[serde(default)]
+    pub keep: KeepPolicy,
+}
+
+/// Directory preview collages
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PreviewsConfig {
+    /// Which images a collage shows first
+    #[serde(default)]
+    pub cover: PreviewCover,
+}
+
+/// How ratings are scored in this library
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RatingsConfig {
+    #[serde(default)]
+    pub scale: RatingScale,
+}
+
+/// Terminal UI preferences
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct TuiConfig {
+    pub tree_sort: TreeSort,
+    /// Widest image the preview decodes, in pixels. Higher shows more detail
+    /// on large terminals at the cost of slower loads and more memory.
+    pub preview_resolution: u32,
+    /// Memory the TUI may hold decoded previews in, in megabytes. Fewer
+    /// previews stay cached when they're large.
+    pub preview_memory_mb: u32,
+}
+
+/// Smallest `preview_resolution` accepted; below it previews turn to mush
+const MIN_PREVIEW_RESOLUTION: u32 = 320;
+
+const DEFAULT_PREVIEW_MEMORY_MB: u32 = 1000;
+
+/// Smallest `preview_memory_mb` accepted: a few full-size previews
+const MIN_PREVIEW_MEMORY_MB: u32 = 64;
+
+impl Default for TuiConfig {
+    fn default() -> Self {
+        Self {
+            tree_sort: TreeSort::default(),
+            preview_resolution: DEFAULT_PREVIEW_RESOLUTION,
+            preview_memory_mb: DEFAULT_PREVIEW_MEMORY_MB,
+        }
+    }
+}
+
+/// What scanning leaves out of the library
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScanConfig {
+    /// File or directory name patterns (`*` and `?` wildcards); a matching
+    /// directory is skipped with everything below it
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Follow symlinked directories and files instead of skipping them.
+    /// Each directory is entered once, so links that loop back are harmless.
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    /// Index dot-prefixed files and directories, which are skipped by
+    /// default. picman's own (`.picman-trash` and the like) never are.
+    #[serde(default)]
+    pub dotfiles: bool,
+}
+
+/// Desktop notifications for background jobs that finish while the TUI or
+/// daemon runs. Off unless `enabled`; then every job type is on by default.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct NotificationsConfig {
+    pub enabled: bool,
+    pub thumbnails: bool,
+    pub hashing: bool,
+    pub sync: bool,
+    /// Jobs that finish faster than this don't notify
+    pub min_seconds: u64,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            thumbnails: true,
+            hashing: true,
+            sync: true,
+            min_seconds: 30,
+        }
+    }
+}
+
+/// Content hashing settings. Changing the algorithm makes the next hash
+/// backfill rehash files hashed with another one.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HashingConfig {
+    #[serde(default)]
+    pub algorithm: HashAlgorithm,
+    /// Quick-hash every file (size plus first and last 64KB) and full-hash
+    /// only those whose quick hash another file shares
+    #[serde(default)]
+    pub prefilter: bool,
+}
+
+/// Cron schedules for maintenance tasks. Unset tasks are not scheduled.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MaintenanceConfig {
+    pub hash_backfill: Option<String>,
+    pub thumbnails: Option<String>,
+    pub cache_gc: Option<String>,
+}
+
+/// Remote library location. When set, the library directory is a local
+/// mirror holding the database and fetched originals.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteConfig {
+    pub url: String,
+    pub ssh_command: Option<String>,
+}
+
+/// How `picman archive` reaches object storage
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArchiveConfig {
+    pub aws_command: Option<String>,
+    /// For S3-compatible stores other than AWS
+    pub endpoint_url: Option<String>,
+}
+
+impl Config {
+    /// Load the library's config, or defaults if there is no config file
+    pub fn load(library_path: &Path) -> Result<Self> {
+        let path = config_path(library_path);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&contents)
+                .with_context(|| format!("Invalid config file {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        let config: Self = toml::from_str(contents)?;
+        // Surface bad cron expressions at load time, not at 03:00
+        config.maintenance.schedules()?;
+        if let Some(remote) = &config.remote {
+            SshTarget::parse(&remote.url).context("remote.url")?;
+        }
+        if config.tui.preview_resolution < MIN_PREVIEW_RESOLUTION {
+            bail!("tui.preview_resolution must be at least {}", MIN_PREVIEW_RESOLUTION);
+        }
+        if config.tui.preview_memory_mb < MIN_PREVIEW_MEMORY_MB {
+            bail!("tui.preview_memory_mb must be at least {}", MIN_PREVIEW_MEMORY_MB);
+        }
+        Ok(config)
+    }
+}
+
+/// Set `key` in `[section]` of the library's config file, creating either
+/// if needed. The rest of the file, comments included, is left as it is.
+pub fn set_config_value(library_path: &Path, section: &str, key: &str, value: toml::Value) -> Result<()> {
+    let path = config_path(library_path);
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let entry = format!("{} = {}", key, value);
+    let header = format!("[{}]", section);
+
+    let mut lines: Vec<String> = contents.lines().map(String::from).collect();
+    match lines.iter().position(|line| line.trim() == header) {
+        Some(start) => {
+            let end = lines[start + 1..]
+                .iter()
+                .position(|line| line.trim_start().starts_with('['))
+                .map_or(lines.len(), |i| start + 1 + i);
+            let existing = (start + 1..end).find(|&i| {
+                lines[i]
+                    .split_once('=')
+                    .is_some_and(|(name, _)| name.trim() == key)
+            });
+            match existing {
+                Some(i) => lines[i] = entry,
+                None => lines.insert(start + 1, entry),
+            }
+        }
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(header);
+            lines.push(entry);
+        }
+    }
+
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    // Don't leave a file the next start can't read
+    Config::parse(&updated).with_context(|| format!("Refusing to write an invalid {}", path.display()))?;
+    std::fs::write(&path, updated).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+impl MaintenanceConfig {
+    /// Parsed schedules for every configured task, in run order
+    pub fn schedules(&self) -> Result<Vec<(MaintenanceTask, CronSchedule)>> {
+        let entries = [
+            (MaintenanceTask::HashBackfill, &self.hash_backfill),
+            (MaintenanceTask::Thumbnails, &self.thumbnails),
+            (MaintenanceTask::CacheGc, &self.cache_gc),
+        ];
+
+        let mut schedules = Vec::new();
+        for (task, expr) in entries {
+            if let Some(expr) = expr {
+                let schedule = CronSchedule::parse(expr)
+                    .with_context(|| format!("maintenance.{}", task.name()))?;
+                schedules.push((task, schedule));
+            }
+        }
+        Ok(schedules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_file_is_default() {
+        let tmp = TempDir::new().unwrap();
+        let config = Config::load(tmp.path()).unwrap();
+        assert!(config.maintenance.schedules().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_maintenance_schedules() {
+        let config = Config::parse(
+            r#"
+            [maintenance]
+            hash_backfill = "0 3 * * *"
+            cache_gc = "@weekly"
+            "#,
+        )
+        .unwrap();
+
+        let schedules = config.maintenance.schedules().unwrap();
+        let tasks: Vec<MaintenanceTask> = schedules.iter().map(|(t, _)| *t).collect();
+        assert_eq!(tasks, vec![MaintenanceTask::HashBackfill, MaintenanceTask::CacheGc]);
+        assert_eq!(schedules[0].1.to_string(), "0 3 * * *");
+    }
+
+    #[test]
+    fn test_rejects_bad_cron_and_unknown_keys() {
+        let err = Config::parse("[maintenance]\nhash_backfill = \"0 25 * * *\"").unwrap_err();
+        assert!(format!("{:#}", err).contains("maintenance.hash_backfill"));
+
+        assert!(Config::parse("[maintenance]\nvacuum = \"@daily\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_notifications() {
+        assert!(!Config::default().notifications.enabled);
+        let config = Config::parse("[notifications]\nenabled = true\nsync = false\n").unwrap();
+        let notifications = config.notifications;
+        assert!(notifications.enabled && notifications.hashing && !notifications.sync);
+        assert_eq!(notifications.min_seconds, 30);
+        assert!(Config::parse("[notifications]\norientation = true\n").is_err());
+    }
+
+    #[test]
+    fn test_load_reads_library_file() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(
+            config_path(tmp.path()),
+            "[maintenance]\nthumbnails = \"30 3 * * *\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(tmp.path()).unwrap();
+        assert_eq!(config.maintenance.thumbnails.as_deref(), Some("30 3 * * *"));
+    }
+
+    #[test]
+    fn test_parse_hash_algorithm() {
+        assert_eq!(Config::default().hashing.algorithm, HashAlgorithm::Xxh3);
+        let config = Config::parse("[hashing]\nalgorithm = \"sha256\"\n").unwrap();
+        assert_eq!(config.hashing.algorithm, HashAlgorithm::Sha256);
+        assert!(Config::parse("[hashing]\nalgorithm = \"md5\"\n").is_err());
+        assert!(!config.hashing.prefilter);
+        assert!(Config::parse("[hashing]\nprefilter = true\n").unwrap().hashing.prefilter);
+    }
+
+    #[test]
+    fn test_parse_scan_excludes() {
+        assert!(Config::default().scan.exclude.is_empty());
+        let config = Config::parse("[scan]\nexclude = [\"@eaDir\", \"*.tmp\"]\n").unwrap();
+        assert_eq!(config.scan.exclude, vec!["@eaDir", "*.tmp"]);
+        assert!(!config.scan.dotfiles);
+        assert!(Config::parse("[scan]\ndotfiles = true\n").unwrap().scan.dotfiles);
+    }
+
+    #[test]
+    fn test_parse_remote() {
+        let config = Config::parse("[remote]\nurl = \"ssh://me@nas/photos\"\n").unwrap();
+        assert_eq!(config.remote.unwrap().url, "ssh://me@nas/photos");
+        assert!(Config::default().remote.is_none());
+
+        let err = Config::parse("[remote]\nurl = \"/mnt/nas\"\n").unwrap_err();
+        assert!(format!("{:#}", err).contains("remote.url"));
+    }
+
+    #[test]
+    fn test_parse_tui_tree_sort() {
+        assert_eq!(Config::default().tui.tree_sort, TreeSort::Name);
+        let config = Config::parse("[tui]\ntree_sort = \"newest\"\n").unwrap();
+        assert_eq!(config.tui.tree_sort, TreeSort::Newest);
+        assert!(Config::parse("[tui]\ntree_sort = \"random\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_tui_preview_resolution() {
+        assert_eq!(Config::default().tui.preview_resolution, DEFAULT_PREVIEW_RESOLUTION);
+        let config = Config::parse("[tui]\npreview_resolution = 3840\n").unwrap();
+        assert_eq!(config.tui.preview_resolution, 3840);
+        assert_eq!(config.tui.tree_sort, TreeSort::Name);
+
+        let err = Config::parse("[tui]\npreview_resolution = 10\n").unwrap_err();
+        assert!(format!("{:#}", err).contains("tui.preview_resolution"));
+    }
+
+    #[test]
+    fn test_parse_tui_preview_memory() {
+        assert_eq!(Config::default().tui.preview_memory_mb, DEFAULT_PREVIEW_MEMORY_MB);
+        let config = Config::parse("[tui]\npreview_memory_mb = 256\n").unwrap();
+        assert_eq!(config.tui.preview_memory_mb, 256);
+
+        let err = Config::parse("[tui]\npreview_memory_mb = 8\n").unwrap_err();
+        assert!(format!("{:#}", err).contains("tui.preview_memory_mb"));
+    }
+
+    #[test]
+    fn test_parse_rating_scale() {
+        assert_eq!(Config::default().ratings.scale, RatingScale::Five);
+        let config = Config::parse("[ratings]\nscale = 10\n").unwrap();
+        assert_eq!(config.ratings.scale, RatingScale::Ten);
+        assert!(Config::parse("[ratings]\nscale = 7\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_duplicates_keep_policy() {
+        assert_eq!(Config::default().duplicates.keep, KeepPolicy::Resolution);
+        let config = Config::parse("[duplicates]\nkeep = \"prefix:photos/sorted\"\n").unwrap();
+        assert_eq!(config.duplicates.keep, KeepPolicy::Prefix("photos/sorted".to_string()));
+        assert!(Config::parse("[duplicates]\nkeep = \"newest\"\n").is_err());
+    }
+
+    #[test]
+    fn test_set_config_value_keeps_the_rest_of_the_file() {
+        let tmp = TempDir::new().unwrap();
+        let sort = |name: &str| toml::Value::String(name.to_string());
+
+        // Missing file: the section is created
+        set_config_value(tmp.path(), "tui", "tree_sort", sort("size")).unwrap();
+        assert_eq!(Config::load(tmp.path()).unwrap().tui.tree_sort, TreeSort::Size);
+
+        std::fs::write(
+            config_path(tmp.path()),
+            "# my library\n[tui]\ntree_sort = \"size\"  # biggest first\n\n[scan]\nexclude = [\"@eaDir\"]\n",
+        )
+        .unwrap();
+        set_config_value(tmp.path(), "tui", "tree_sort", sort("rating")).unwrap();
+        let contents = std::fs::read_to_string(config_path(tmp.path())).unwrap();
+        assert_eq!(
+            contents,
+            "# my library\n[tui]\ntree_sort = \"rating\"\n\n[scan]\nexclude = [\"@eaDir\"]\n"
+        );
+
+        // Values the config can't hold are refused
+        assert!(set_config_value(tmp.path(), "tui", "tree_sort", sort("random")).is_err());
+    }
+}
@@ -0,0 +1,335 @@
+//! Control socket for `picman daemon --control-socket`: a Unix domain socket
+//! speaking JSON-RPC 2.0, one request or response per line, so editor
+//! plugins and scripts can drive a running daemon without starting picman
+//! for every command.
+//!
+//! Methods (params are an object; omitted fields take their defaults):
+//!
+//! - `sync` `{full, hashes, perceptual, subdir}` → sync statistics. Fails if
+//!   another sync (e.g. the watcher's) holds the library's lock.
+//! - `query` `{min_rating, tag, preset, include_hidden}` → `[{path, rating, tags}]`
+//! - `rate` `{path, rating}` → `null`; a `null` rating clears it
+//! - `tag` `{path, add, remove}` → the file's tags
+//! - `jobs.status` → what `/api/health` reports under `daemon`
+//! - `jobs.run` `{job}` → `null`; queues `watch` or a maintenance task
+//!   (`hash_backfill`, `thumbnails`, `cache_gc`) on the scheduler thread
+//!
+//! The socket is created readable and writable by its owner only.
+
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{debug, info, warn};
+
+use crate::cli::{ListOptions, SyncStats};
+use crate::daemon::{DaemonStatus, JobRequest};
+use crate::library::{Library, SyncOptions};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The method ran and failed (file not found, sync already running, ...)
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(SERVER_ERROR, format!("{:#}", e))
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SyncParams {
+    full: bool,
+    hashes: bool,
+    perceptual: bool,
+    subdir: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct QueryParams {
+    min_rating: Option<f64>,
+    tag: Option<String>,
+    preset: Option<String>,
+    include_hidden: bool,
+}
+
+#[derive(Deserialize)]
+struct RateParams {
+    path: String,
+    rating: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct TagParams {
+    path: String,
+    #[serde(default)]
+    add: Vec<String>,
+    #[serde(default)]
+    remove: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RunParams {
+    job: String,
+}
+
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn to_value<T: serde::Serialize>(result: T) -> Result<Value, RpcError> {
+    serde_json::to_value(result).map_err(|e| RpcError::new(SERVER_ERROR, e.to_string()))
+}
+
+/// Run one method
+fn dispatch(library: &Library, status: &DaemonStatus, method: &str, raw: Value) -> Result<Value, RpcError> {
+    match method {
+        "sync" => {
+            let p: SyncParams = params(raw)?;
+            let stats: SyncStats = library.sync(&SyncOptions {
+                hashes: p.hashes,
+                perceptual: p.perceptual,
+                full: p.full,
+                subdir: p.subdir,
+                ..Default::default()
+            })?;
+            to_value(stats)
+        }
+        "query" => {
+            let p: QueryParams = params(raw)?;
+            to_value(library.query(&ListOptions {
+                min_rating: p.min_rating,
+                tag: p.tag,
+                preset: p.preset,
+                include_hidden: p.include_hidden,
+                ..Default::default()
+            })?)
+        }
+        "rate" => {
+            let p: RateParams = params(raw)?;
+            library.rate(&p.path, p.rating)?;
+            Ok(Value::Null)
+        }
+        "tag" => {
+            let p: TagParams = params(raw)?;
+            let mut tags = library.tags(&p.path)?;
+            for tag in &p.add {
+                tags = library.tag(&p.path, tag)?;
+            }
+            for tag in &p.remove {
+                tags = library.untag(&p.path, tag)?;
+            }
+            to_value(tags)
+        }
+        "jobs.status" => to_value(status.snapshot()),
+        "jobs.run" => {
+            let p: RunParams = params(raw)?;
+            let job = JobRequest::from_name(&p.job)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Unknown job: {}", p.job)))?;
+            status.request(job);
+            Ok(Value::Null)
+        }
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("Unknown method: {}", method))),
+    }
+}
+
+/// Answer one line; None for notifications (requests without an id)
+fn handle_line(library: &Library, status: &DaemonStatus, line: &str) -> Option<Value> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return Some(response(Value::Null, Err(RpcError::new(PARSE_ERROR, e.to_string())))),
+    };
+    let id = request.get("id").cloned();
+    let method = request.get("method").and_then(Value::as_str);
+    let result = match (request.get("jsonrpc").and_then(Value::as_str), method) {
+        (Some("2.0"), Some(method)) => {
+            debug!(method, "control request");
+            dispatch(library, status, method, request.get("params").cloned().unwrap_or(Value::Null))
+        }
+        _ => Err(RpcError::new(INVALID_REQUEST, "Expected a JSON-RPC 2.0 request")),
+    };
+    id.map(|id| response(id, result))
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": e.code, "message": e.message } }),
+    }
+}
+
+fn serve_client(stream: UnixStream, library_path: &Path, status: &DaemonStatus) -> Result<()> {
+    let library = Library::open(library_path)?;
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_line(&library, status, &line) {
+            writeln!(writer, "{}", response)?;
+        }
+    }
+    Ok(())
+}
+
+/// Take over `path`, unless a live daemon is answering on it
+fn bind(path: &Path) -> Result<UnixListener> {
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            anyhow::bail!("Control socket {} is in use by another daemon", path.display());
+        }
+        fs::remove_file(path).with_context(|| format!("Failed to remove stale socket {}", path.display()))?;
+    }
+    let listener =
+        UnixListener::bind(path).with_context(|| format!("Failed to create control socket {}", path.display()))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Listen on `path` until `stop` is set, one thread per client. The socket
+/// file is removed when the listener stops.
+pub fn spawn(
+    path: &Path,
+    library_path: PathBuf,
+    status: Arc<DaemonStatus>,
+    stop: Arc<AtomicBool>,
+) -> Result<JoinHandle<()>> {
+    let listener = bind(path)?;
+    listener.set_nonblocking(true)?;
+    info!(path = %path.display(), "control socket listening");
+
+    let path = path.to_path_buf();
+    Ok(thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let library_path = library_path.clone();
+                    let status = status.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = stream.set_nonblocking(false) {
+                            warn!(error = %e, "control client setup failed");
+                            return;
+                        }
+                        if let Err(e) = serve_client(stream, &library_path, &status) {
+                            warn!(error = %format!("{:#}", e), "control client failed");
+                        }
+                    });
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(100)),
+                Err(e) => {
+                    warn!(error = %e, "control socket accept failed");
+                    thread::sleep(Duration::from_millis(100));
+                }
+            }
+        }
+        let _ = fs::remove_file(&path);
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, Library) {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("a.jpg"), "a").unwrap();
+        let library = Library::init(temp.path()).unwrap();
+        (temp, library)
+    }
+
+    fn call(library: &Library, status: &DaemonStatus, request: Value) -> Value {
+        handle_line(library, status, &request.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_methods_and_errors() {
+        let (_temp, library) = setup();
+        let status = DaemonStatus::new();
+
+        let reply = call(&library, &status, json!({"jsonrpc": "2.0", "id": 1, "method": "rate", "params": {"path": "a.jpg", "rating": 4}}));
+        assert_eq!(reply, json!({"jsonrpc": "2.0", "id": 1, "result": null}));
+
+        let reply = call(&library, &status, json!({"jsonrpc": "2.0", "id": 2, "method": "tag", "params": {"path": "a.jpg", "add": ["keeper"]}}));
+        assert_eq!(reply["result"], json!(["keeper"]));
+
+        let reply = call(&library, &status, json!({"jsonrpc": "2.0", "id": 3, "method": "query", "params": {"min_rating": 4}}));
+        assert_eq!(reply["result"], json!([{"path": "a.jpg", "rating": 4.0, "tags": ["keeper"]}]));
+
+        let reply = call(&library, &status, json!({"jsonrpc": "2.0", "id": 4, "method": "rate", "params": {"path": "gone.jpg", "rating": 1}}));
+        assert_eq!(reply["error"]["code"], SERVER_ERROR);
+        let reply = call(&library, &status, json!({"jsonrpc": "2.0", "id": 5, "method": "rate", "params": {}}));
+        assert_eq!(reply["error"]["code"], INVALID_PARAMS);
+        let reply = call(&library, &status, json!({"jsonrpc": "2.0", "id": 6, "method": "explode"}));
+        assert_eq!(reply["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(handle_line(&library, &status, "{").unwrap()["error"]["code"], PARSE_ERROR);
+
+        // Notifications get no reply
+        assert!(handle_line(&library, &status, r#"{"jsonrpc": "2.0", "method": "jobs.status"}"#).is_none());
+    }
+
+    #[test]
+    fn test_jobs_run_queues_for_the_scheduler() {
+        let (_temp, library) = setup();
+        let status = DaemonStatus::new();
+
+        let reply = call(&library, &status, json!({"jsonrpc": "2.0", "id": 1, "method": "jobs.run", "params": {"job": "thumbnails"}}));
+        assert!(reply.get("error").is_none());
+        call(&library, &status, json!({"jsonrpc": "2.0", "id": 2, "method": "jobs.run", "params": {"job": "thumbnails"}}));
+        let reply = call(&library, &status, json!({"jsonrpc": "2.0", "id": 3, "method": "jobs.run", "params": {"job": "defrag"}}));
+        assert_eq!(reply["error"]["code"], INVALID_PARAMS);
+
+        let reply = call(&library, &status, json!({"jsonrpc": "2.0", "id": 4, "method": "jobs.status"}));
+        assert_eq!(reply["result"]["maintenance"]["runs"], 0);
+    }
+
+    #[test]
+    fn test_socket_round_trip() {
+        let (temp, library) = setup();
+        let socket = temp.path().join("control.sock");
+        let status = Arc::new(DaemonStatus::new());
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = spawn(&socket, library.root().to_path_buf(), status, stop.clone()).unwrap();
+        assert!(spawn(&socket, library.root().to_path_buf(), Arc::new(DaemonStatus::new()), stop.clone()).is_err());
+
+        let mut stream = UnixStream::connect(&socket).unwrap();
+        writeln!(stream, r#"{{"jsonrpc": "2.0", "id": "s", "method": "sync", "params": {{"full": true}}}}"#).unwrap();
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        let reply: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(reply["id"], "s");
+        assert_eq!(reply["result"]["files_added"], 0);
+
+        stop.store(true, Ordering::Relaxed);
+        handle.join().unwrap();
+        assert!(!socket.exists());
+    }
+}
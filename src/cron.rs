@@ -0,0 +1,291 @@
+//! Minimal 5-field cron expressions for scheduled maintenance.
+//!
+//! Supports `minute hour day-of-month month day-of-week` with `*`, numbers,
+//! ranges (`1-5`), lists (`1,15`), steps (`*/15`, `0-30/10`), three-letter
+//! month/weekday names, and the `@hourly`/`@daily`/`@weekly`/`@monthly`/
+//! `@yearly` shortcuts. Times are interpreted in local time.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// How far ahead `next_after` searches before giving up (covers leap days)
+const SEARCH_LIMIT_DAYS: i64 = 366 * 5;
+
+/// A parsed cron schedule. Each field is a bitmask of allowed values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Whether day-of-month / day-of-week were restricted (not `*`).
+    /// Classic cron fires when *either* restricted day field matches.
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let trimmed = expr.trim();
+        let expanded = match trimmed {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other if other.starts_with('@') => bail!("Unknown cron shortcut '{}'", other),
+            other => other,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            bail!(
+                "Cron expression '{}' must have 5 fields (minute hour day month weekday), got {}",
+                trimmed,
+                fields.len()
+            );
+        }
+
+        let minutes = parse_field(fields[0], 0, 59, None).context("minute field")?;
+        let hours = parse_field(fields[1], 0, 23, None).context("hour field")?;
+        let days_of_month = parse_field(fields[2], 1, 31, None).context("day-of-month field")?;
+        let months = parse_field(fields[3], 1, 12, Some(&MONTH_NAMES)).context("month field")?;
+        // 7 is an alias for Sunday
+        let mut days_of_week =
+            parse_field(fields[4], 0, 7, Some(&WEEKDAY_NAMES)).context("day-of-week field")?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+
+        Ok(Self {
+            source: trimmed.to_string(),
+            minutes,
+            hours,
+            days_of_month,
+            months,
+            days_of_week,
+            dom_restricted: fields[2] != "*",
+            dow_restricted: fields[4] != "*",
+        })
+    }
+
+    /// Whether the schedule fires at the given minute
+    pub fn matches(&self, t: NaiveDateTime) -> bool {
+        bit(self.minutes, t.minute())
+            && bit(self.hours, t.hour())
+            && bit(self.months, t.month())
+            && self.day_matches(t.date())
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let dom = bit(self.days_of_month, date.day());
+        let dow = bit(self.days_of_week, date.weekday().num_days_from_sunday());
+        match (self.dom_restricted, self.dow_restricted) {
+            (true, true) => dom || dow,
+            (true, false) => dom,
+            (false, true) => dow,
+            (false, false) => true,
+        }
+    }
+
+    /// The first matching minute strictly after `after`, or None if the
+    /// schedule can never fire (e.g. `0 0 31 2 *`)
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = start + Duration::days(SEARCH_LIMIT_DAYS);
+        let mut t = start;
+
+        while t < limit {
+            if !bit(self.months, t.month()) || !self.day_matches(t.date()) {
+                // Skip to midnight of the next day
+                t = (t.date() + Duration::days(1)).and_time(NaiveTime::MIN);
+                continue;
+            }
+            if !bit(self.hours, t.hour()) {
+                t = t.with_minute(0)? + Duration::hours(1);
+                continue;
+            }
+            if !bit(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+                continue;
+            }
+            return Some(t);
+        }
+
+        None
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.source)
+    }
+}
+
+fn bit(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Parse one field into a bitmask of allowed values in `min..=max`
+fn parse_field(field: &str, min: u32, max: u32, names: Option<&[&str]>) -> Result<u64> {
+    let mut mask = 0u64;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .with_context(|| format!("invalid step '{}'", step))?;
+                if step == 0 {
+                    bail!("step must be at least 1");
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (lo, hi) = if range == "*" {
+            (min, max)
+        } else if let Some((lo, hi)) = range.split_once('-') {
+            (parse_value(lo, min, max, names)?, parse_value(hi, min, max, names)?)
+        } else {
+            let value = parse_value(range, min, max, names)?;
+            // `5/10` means "from 5 to the end, every 10"
+            if step > 1 {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+
+        if lo > hi {
+            bail!("range {}-{} is backwards", lo, hi);
+        }
+
+        let mut v = lo;
+        while v <= hi {
+            mask |= 1 << v;
+            v += step;
+        }
+    }
+
+    Ok(mask)
+}
+
+fn parse_value(s: &str, min: u32, max: u32, names: Option<&[&str]>) -> Result<u32> {
+    let lower = s.to_ascii_lowercase();
+    let value = match names.and_then(|n| n.iter().position(|name| *name == lower)) {
+        // Month names are 1-based, weekday names 0-based
+        Some(idx) => idx as u32 + min,
+        None => s
+            .parse::<u32>()
+            .with_context(|| format!("invalid value '{}'", s))?,
+    };
+
+    if value < min || value > max {
+        bail!("value {} out of range {}-{}", value, min, max);
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, mo, d)
+            .unwrap()
+            .and_hms_opt(h, mi, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_nightly_at_three() {
+        let s = CronSchedule::parse("0 3 * * *").unwrap();
+        assert_eq!(s.next_after(at(2024, 5, 1, 12, 0)), Some(at(2024, 5, 2, 3, 0)));
+        assert_eq!(s.next_after(at(2024, 5, 1, 2, 59)), Some(at(2024, 5, 1, 3, 0)));
+        // Strictly after: a run at exactly 03:00 schedules the next day
+        assert_eq!(s.next_after(at(2024, 5, 1, 3, 0)), Some(at(2024, 5, 2, 3, 0)));
+    }
+
+    #[test]
+    fn test_weekly_on_sunday() {
+        // 2024-05-01 is a Wednesday
+        let s = CronSchedule::parse("30 4 * * sun").unwrap();
+        assert_eq!(s.next_after(at(2024, 5, 1, 0, 0)), Some(at(2024, 5, 5, 4, 30)));
+        // 7 is also Sunday
+        assert_eq!(CronSchedule::parse("30 4 * * 7").unwrap().days_of_week, s.days_of_week);
+    }
+
+    #[test]
+    fn test_steps_ranges_and_lists() {
+        let s = CronSchedule::parse("*/15 9-17 * * mon-fri").unwrap();
+        assert!(s.matches(at(2024, 5, 1, 9, 45)));
+        assert!(!s.matches(at(2024, 5, 1, 9, 50)));
+        assert!(!s.matches(at(2024, 5, 4, 10, 0))); // Saturday
+        assert!(!s.matches(at(2024, 5, 1, 18, 0)));
+
+        let s = CronSchedule::parse("0,30 0 1,15 * *").unwrap();
+        assert_eq!(s.next_after(at(2024, 5, 1, 0, 30)), Some(at(2024, 5, 15, 0, 0)));
+    }
+
+    #[test]
+    fn test_day_of_month_or_day_of_week() {
+        // Fires on the 1st OR on Mondays
+        let s = CronSchedule::parse("0 0 1 * mon").unwrap();
+        assert!(s.matches(at(2024, 5, 1, 0, 0))); // Wednesday the 1st
+        assert!(s.matches(at(2024, 5, 6, 0, 0))); // Monday
+        assert!(!s.matches(at(2024, 5, 7, 0, 0)));
+    }
+
+    #[test]
+    fn test_shortcuts() {
+        assert_eq!(
+            CronSchedule::parse("@daily").unwrap().next_after(at(2024, 12, 31, 23, 59)),
+            Some(at(2025, 1, 1, 0, 0))
+        );
+        assert_eq!(
+            CronSchedule::parse("@monthly").unwrap().next_after(at(2024, 1, 31, 0, 0)),
+            Some(at(2024, 2, 1, 0, 0))
+        );
+        assert!(CronSchedule::parse("@fortnightly").is_err());
+    }
+
+    #[test]
+    fn test_leap_day_and_impossible_dates() {
+        let s = CronSchedule::parse("0 0 29 feb *").unwrap();
+        assert_eq!(s.next_after(at(2025, 3, 1, 0, 0)), Some(at(2028, 2, 29, 0, 0)));
+
+        let never = CronSchedule::parse("0 0 31 2 *").unwrap();
+        assert_eq!(never.next_after(at(2024, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(CronSchedule::parse("0 3 * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("0 24 * * *").is_err());
+        assert!(CronSchedule::parse("0 0 0 * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("5-1 * * * *").is_err());
+        assert!(CronSchedule::parse("0 0 * foo *").is_err());
+    }
+}
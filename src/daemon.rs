@@ -0,0 +1,666 @@
+//! `picman daemon`: web server, filesystem watcher, and maintenance scheduler
+//! in one long-running process (e.g. a systemd unit on an always-on photo box).
+//!
+//! The watcher polls with an incremental sync (directory mtimes only), which is
+//! cheap enough on HDDs to run every few seconds and needs no inotify limits.
+//! Directories the watcher finds new or edited files in get their thumbnails
+//! and web thumbnails once they have been quiet for a moment, so a folder
+//! still being copied isn't decoded over and over.
+//! Maintenance (hash backfill, thumbnail top-up) runs on a longer interval,
+//! or on the cron schedules from `.picman.toml` when any are configured.
+//! Everything shares one scheduler thread so jobs never contend for the disk.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::cli::{run_generate_directory_thumbnails, run_sync_incremental};
+use crate::maintenance::{
+    load_schedule, local_now, record_run, unix_from_local, MaintenanceTask, ScheduledTask,
+};
+use crate::notify::{JobKind, Notifier};
+use crate::process::unix_now;
+use crate::serve::{run_serve, ServeOptions, TlsOptions};
+
+/// Options for `picman daemon`
+pub struct DaemonOptions {
+    pub port: u16,
+    pub tls: Option<TlsOptions>,
+    pub pid_file: Option<PathBuf>,
+    /// How often to poll the filesystem for changes
+    pub watch_interval: Duration,
+    /// How often to run hash backfill and thumbnail top-up when
+    /// `.picman.toml` configures no maintenance schedule
+    pub maintenance_interval: Duration,
+    /// Where to listen for JSON-RPC control requests (Unix only)
+    pub control_socket: Option<PathBuf>,
+}
+
+/// Progress of one recurring daemon job, reported by `/api/health`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JobStatus {
+    pub running: bool,
+    pub runs: u64,
+    /// Unix timestamp (seconds) when the last run finished
+    pub last_run: Option<i64>,
+    pub last_duration_ms: Option<u64>,
+    pub last_summary: Option<String>,
+    pub last_error: Option<String>,
+    /// Unix timestamp of the next scheduled run (cron tasks only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run: Option<i64>,
+}
+
+/// Point-in-time copy of [`DaemonStatus`] for serialization
+#[derive(Debug, Clone, Serialize)]
+pub struct DaemonSnapshot {
+    pub uptime_secs: u64,
+    pub watcher: JobStatus,
+    /// Thumbnails for files the watcher picked up
+    pub thumbnails: JobStatus,
+    pub maintenance: JobStatus,
+    /// Cron-scheduled maintenance tasks, keyed by task name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tasks: BTreeMap<String, JobStatus>,
+}
+
+impl DaemonSnapshot {
+    /// True if any job's most recent run failed
+    pub fn has_errors(&self) -> bool {
+        self.watcher.last_error.is_some()
+            || self.thumbnails.last_error.is_some()
+            || self.maintenance.last_error.is_some()
+            || self.tasks.values().any(|t| t.last_error.is_some())
+    }
+}
+
+/// A job asked for out of schedule (over the control socket), run by the
+/// scheduler thread between its other jobs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobRequest {
+    /// The watcher's incremental sync
+    Watch,
+    Task(MaintenanceTask),
+}
+
+impl JobRequest {
+    /// `watch` or a maintenance task name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "watch" => Some(Self::Watch),
+            _ => MaintenanceTask::from_name(name).map(Self::Task),
+        }
+    }
+}
+
+/// Shared status of the daemon's background jobs
+pub struct DaemonStatus {
+    started: Instant,
+    watcher: Mutex<JobStatus>,
+    thumbnails: Mutex<JobStatus>,
+    maintenance: Mutex<JobStatus>,
+    tasks: BTreeMap<&'static str, Mutex<JobStatus>>,
+    requested: Mutex<Vec<JobRequest>>,
+}
+
+impl Default for DaemonStatus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DaemonStatus {
+    pub fn new() -> Self {
+        Self::with_tasks(&[])
+    }
+
+    /// Status with a slot for each cron-scheduled task
+    pub fn with_tasks(tasks: &[ScheduledTask]) -> Self {
+        Self {
+            started: Instant::now(),
+            watcher: Mutex::new(JobStatus::default()),
+            thumbnails: Mutex::new(JobStatus::default()),
+            maintenance: Mutex::new(JobStatus::default()),
+            tasks: tasks
+                .iter()
+                .map(|t| {
+                    let status = JobStatus {
+                        next_run: t.next_run.and_then(unix_from_local),
+                        ..JobStatus::default()
+                    };
+                    (t.task.name(), Mutex::new(status))
+                })
+                .collect(),
+            requested: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Queue `job` for the scheduler; asking again before it ran is a no-op
+    pub fn request(&self, job: JobRequest) {
+        if let Ok(mut requested) = self.requested.lock() {
+            if !requested.contains(&job) {
+                requested.push(job);
+            }
+        }
+    }
+
+    fn take_requests(&self) -> Vec<JobRequest> {
+        self.requested.lock().map(|mut r| std::mem::take(&mut *r)).unwrap_or_default()
+    }
+
+    pub fn snapshot(&self) -> DaemonSnapshot {
+        DaemonSnapshot {
+            uptime_secs: self.started.elapsed().as_secs(),
+            watcher: self.watcher.lock().map(|s| s.clone()).unwrap_or_default(),
+            thumbnails: self.thumbnails.lock().map(|s| s.clone()).unwrap_or_default(),
+            maintenance: self.maintenance.lock().map(|s| s.clone()).unwrap_or_default(),
+            tasks: self
+                .tasks
+                .iter()
+                .map(|(name, slot)| {
+                    (name.to_string(), slot.lock().map(|s| s.clone()).unwrap_or_default())
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Run `job`, recording its outcome in `slot`
+fn record_job<F>(slot: &Mutex<JobStatus>, job: F)
+where
+    F: FnOnce() -> Result<String>,
+{
+    if let Ok(mut s) = slot.lock() {
+        s.running = true;
+    }
+
+    let start = Instant::now();
+    let result = job();
+    let elapsed = start.elapsed();
+
+    if let Ok(mut s) = slot.lock() {
+        s.running = false;
+        s.runs += 1;
+        s.last_run = Some(unix_now());
+        s.last_duration_ms = Some(elapsed.as_millis() as u64);
+        match result {
+            Ok(summary) => {
+                s.last_summary = Some(summary);
+                s.last_error = None;
+            }
+            Err(e) => {
+                warn!(error = %e, "daemon job failed");
+                s.last_error = Some(format!("{:#}", e));
+            }
+        }
+    }
+}
+
+/// Whether a recurring job last run at `last` is due again at `now`
+fn is_due(last: Option<Instant>, interval: Duration, now: Instant) -> bool {
+    match last {
+        None => true,
+        Some(t) => now.duration_since(t) >= interval,
+    }
+}
+
+/// How long a directory must go without new changes before its thumbnails
+/// are generated
+const THUMBNAIL_DEBOUNCE: Duration = Duration::from_secs(3);
+
+/// Directories with new files waiting for thumbnails, keyed by when the
+/// watcher last saw them change
+#[derive(Default)]
+struct PendingThumbnails {
+    changed: HashMap<i64, Instant>,
+}
+
+impl PendingThumbnails {
+    fn touch(&mut self, dir_ids: &HashSet<i64>, now: Instant) {
+        for &id in dir_ids {
+            self.changed.insert(id, now);
+        }
+    }
+
+    /// Remove and return the directories quiet for at least `debounce`
+    fn take_quiet(&mut self, debounce: Duration, now: Instant) -> Vec<i64> {
+        let mut quiet: Vec<i64> = self
+            .changed
+            .iter()
+            .filter(|(_, &t)| now.duration_since(t) >= debounce)
+            .map(|(&id, _)| id)
+            .collect();
+        quiet.sort_unstable();
+        for id in &quiet {
+            self.changed.remove(id);
+        }
+        quiet
+    }
+}
+
+fn watch_once(library_path: &Path, pending: &mut PendingThumbnails) -> Result<String> {
+    let start = Instant::now();
+    let stats = run_sync_incremental(library_path)?;
+    pending.touch(&stats.changed_directories, Instant::now());
+    let changed = stats.directories_added
+        + stats.directories_removed
+        + stats.files_added
+        + stats.files_removed
+        + stats.files_modified;
+    if changed > 0 {
+        info!(
+            files_added = stats.files_added,
+            files_removed = stats.files_removed,
+            files_modified = stats.files_modified,
+            "watcher picked up changes"
+        );
+    }
+    let summary = format!(
+        "+{} -{} ~{} files",
+        stats.files_added, stats.files_removed, stats.files_modified
+    );
+    // Polls that found nothing aren't worth a notification, however slow
+    if changed > 0 {
+        Notifier::for_library(library_path).job_finished(JobKind::Sync, start.elapsed(), &summary);
+    }
+    Ok(summary)
+}
+
+/// Run one maintenance task and persist its outcome for `picman status`
+fn run_task(library_path: &Path, task: MaintenanceTask) -> Result<String> {
+    let start = Instant::now();
+    let result = task.run(library_path);
+    if let Err(e) = record_run(library_path, task, start.elapsed(), &result) {
+        warn!(task = task.name(), error = %e, "failed to record maintenance run");
+    }
+    let kind = match task {
+        MaintenanceTask::HashBackfill => Some(JobKind::Hashing),
+        MaintenanceTask::Thumbnails => Some(JobKind::Thumbnails),
+        MaintenanceTask::CacheGc => None,
+    };
+    if let (Some(kind), Ok(summary)) = (kind, &result) {
+        Notifier::for_library(library_path).job_finished(kind, start.elapsed(), summary);
+    }
+    result
+}
+
+fn thumbnail_changed(library_path: &Path, dir_ids: &[i64]) -> Result<String> {
+    let stats = run_generate_directory_thumbnails(library_path, dir_ids)?;
+    if stats.generated > 0 || stats.failed > 0 {
+        info!(
+            directories = dir_ids.len(),
+            generated = stats.generated,
+            failed = stats.failed,
+            "generated thumbnails for new files"
+        );
+    }
+    Ok(format!(
+        "{} generated, {} failed in {} directories",
+        stats.generated,
+        stats.failed,
+        dir_ids.len()
+    ))
+}
+
+fn maintain_once(library_path: &Path) -> Result<String> {
+    let hashed = run_task(library_path, MaintenanceTask::HashBackfill)?;
+    let thumbs = run_task(library_path, MaintenanceTask::Thumbnails)?;
+    Ok(format!("{}, {}", hashed, thumbs))
+}
+
+/// Which recurring jobs the scheduler thread runs
+struct Jobs {
+    /// Polling watcher interval (None = no watcher)
+    watch_interval: Option<Duration>,
+    /// Interval maintenance (None when cron schedules are configured)
+    maintenance_interval: Option<Duration>,
+    scheduled: Vec<ScheduledTask>,
+}
+
+/// Scheduler loop: runs the watcher and maintenance jobs until `stop` is set
+fn run_scheduler(
+    library_path: PathBuf,
+    mut jobs: Jobs,
+    status: Arc<DaemonStatus>,
+    stop: Arc<AtomicBool>,
+) {
+    let mut last_watch: Option<Instant> = None;
+    let mut pending = PendingThumbnails::default();
+    // First maintenance run waits one interval so startup IO stays light
+    let mut last_maintenance: Option<Instant> = Some(Instant::now());
+
+    while !stop.load(Ordering::Relaxed) {
+        for job in status.take_requests() {
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            match job {
+                JobRequest::Watch => {
+                    record_job(&status.watcher, || watch_once(&library_path, &mut pending));
+                    last_watch = Some(Instant::now());
+                }
+                JobRequest::Task(task) => {
+                    info!(task = task.name(), "running requested maintenance");
+                    let slot = status.tasks.get(task.name()).unwrap_or(&status.maintenance);
+                    record_job(slot, || run_task(&library_path, task));
+                }
+            }
+        }
+
+        let now = Instant::now();
+
+        if let Some(interval) = jobs.watch_interval {
+            if is_due(last_watch, interval, now) {
+                record_job(&status.watcher, || watch_once(&library_path, &mut pending));
+                last_watch = Some(Instant::now());
+            }
+        }
+
+        let quiet = pending.take_quiet(THUMBNAIL_DEBOUNCE, Instant::now());
+        if !quiet.is_empty() && !stop.load(Ordering::Relaxed) {
+            record_job(&status.thumbnails, || thumbnail_changed(&library_path, &quiet));
+        }
+
+        if let Some(interval) = jobs.maintenance_interval {
+            if !stop.load(Ordering::Relaxed) && is_due(last_maintenance, interval, now) {
+                info!("running interval maintenance");
+                record_job(&status.maintenance, || maintain_once(&library_path));
+                last_maintenance = Some(Instant::now());
+            }
+        }
+
+        for scheduled in &mut jobs.scheduled {
+            if stop.load(Ordering::Relaxed) || !scheduled.is_due(local_now()) {
+                continue;
+            }
+
+            let name = scheduled.task.name();
+            info!(task = name, "running scheduled maintenance");
+            let Some(slot) = status.tasks.get(name) else {
+                continue;
+            };
+            record_job(slot, || run_task(&library_path, scheduled.task));
+
+            scheduled.advance(local_now());
+            if let Ok(mut s) = slot.lock() {
+                s.next_run = scheduled.next_run.and_then(unix_from_local);
+            }
+        }
+
+        thread::sleep(Duration::from_millis(250));
+    }
+}
+
+/// Serve `library_path` with a scheduler thread running `jobs` alongside,
+/// and the control socket at `control_socket` if given
+fn serve_with_jobs(
+    library_path: &Path,
+    mut options: ServeOptions,
+    jobs: Jobs,
+    control_socket: Option<&Path>,
+) -> Result<()> {
+    let status = Arc::new(DaemonStatus::with_tasks(&jobs.scheduled));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let control = match control_socket {
+        Some(path) => Some(spawn_control(path, library_path, &status, &stop)?),
+        None => None,
+    };
+
+    let scheduler = thread::spawn({
+        let library_path = library_path.to_path_buf();
+        let status = status.clone();
+        let stop = stop.clone();
+        move || run_scheduler(library_path, jobs, status, stop)
+    });
+
+    options.daemon = Some(status);
+    let result = run_serve(library_path, options);
+
+    // Let an in-progress job finish its current transaction before exiting
+    stop.store(true, Ordering::Relaxed);
+    println!("Waiting for background jobs to finish...");
+    let _ = scheduler.join();
+    if let Some(control) = control {
+        let _ = control.join();
+    }
+
+    result
+}
+
+#[cfg(unix)]
+fn spawn_control(
+    path: &Path,
+    library_path: &Path,
+    status: &Arc<DaemonStatus>,
+    stop: &Arc<AtomicBool>,
+) -> Result<thread::JoinHandle<()>> {
+    println!("Control socket: {}", path.display());
+    crate::control::spawn(path, library_path.to_path_buf(), status.clone(), stop.clone())
+}
+
+#[cfg(not(unix))]
+fn spawn_control(
+    _path: &Path,
+    _library_path: &Path,
+    _status: &Arc<DaemonStatus>,
+    _stop: &Arc<AtomicBool>,
+) -> Result<thread::JoinHandle<()>> {
+    anyhow::bail!("The control socket needs Unix domain sockets")
+}
+
+fn print_schedule(scheduled: &[ScheduledTask]) {
+    println!("Maintenance schedule (.picman.toml):");
+    for s in scheduled {
+        let next = s
+            .next_run
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "never".to_string());
+        println!("  {:<14} {:<16} next: {}", s.task.name(), s.schedule, next);
+    }
+}
+
+/// `picman serve`: run the web server, plus the maintenance schedule from
+/// `.picman.toml` if one is configured
+pub fn run_serve_scheduled(library_path: &Path, options: ServeOptions) -> Result<()> {
+    let scheduled = load_schedule(library_path)?;
+    if scheduled.is_empty() {
+        return run_serve(library_path, options);
+    }
+
+    let library_path = library_path
+        .canonicalize()
+        .with_context(|| format!("Library path does not exist: {}", library_path.display()))?;
+    print_schedule(&scheduled);
+
+    serve_with_jobs(
+        &library_path,
+        options,
+        Jobs {
+            watch_interval: None,
+            maintenance_interval: None,
+            scheduled,
+        },
+        None,
+    )
+}
+
+/// Run the daemon until Ctrl+C / SIGTERM (or `picman serve --stop`)
+pub fn run_daemon(library_path: &Path, options: DaemonOptions) -> Result<()> {
+    let library_path = library_path
+        .canonicalize()
+        .with_context(|| format!("Library path does not exist: {}", library_path.display()))?;
+
+    let scheduled = load_schedule(&library_path)?;
+
+    println!("Watching for changes every {:?}", options.watch_interval);
+    let maintenance_interval = if scheduled.is_empty() {
+        println!("Maintenance every {:?}", options.maintenance_interval);
+        Some(options.maintenance_interval)
+    } else {
+        print_schedule(&scheduled);
+        None
+    };
+
+    serve_with_jobs(
+        &library_path,
+        ServeOptions {
+            port: options.port,
+            tls: options.tls,
+            pid_file: options.pid_file,
+            daemon: None,
+        },
+        Jobs {
+            watch_interval: Some(options.watch_interval),
+            maintenance_interval,
+            scheduled,
+        },
+        options.control_socket.as_deref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_due() {
+        let now = Instant::now();
+        let interval = Duration::from_secs(60);
+
+        assert!(is_due(None, interval, now));
+        assert!(!is_due(Some(now), interval, now));
+        assert!(is_due(Some(now), interval, now + Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn test_pending_thumbnails_debounce_per_directory() {
+        let start = Instant::now();
+        let mut pending = PendingThumbnails::default();
+        pending.touch(&HashSet::from([1, 2]), start);
+
+        // Directory 2 keeps changing; directory 1 goes quiet
+        let later = start + Duration::from_secs(2);
+        pending.touch(&HashSet::from([2]), later);
+        assert!(pending.take_quiet(THUMBNAIL_DEBOUNCE, later).is_empty());
+
+        let after = start + Duration::from_secs(4);
+        assert_eq!(pending.take_quiet(THUMBNAIL_DEBOUNCE, after), vec![1]);
+        assert_eq!(pending.take_quiet(THUMBNAIL_DEBOUNCE, after + Duration::from_secs(1)), vec![2]);
+        assert!(pending.take_quiet(THUMBNAIL_DEBOUNCE, after + Duration::from_secs(10)).is_empty());
+    }
+
+    #[test]
+    fn test_record_job_success_and_failure() {
+        let status = DaemonStatus::new();
+
+        record_job(&status.watcher, || Ok("+1 -0 ~0 files".to_string()));
+        let snap = status.snapshot();
+        assert_eq!(snap.watcher.runs, 1);
+        assert!(!snap.watcher.running);
+        assert!(snap.watcher.last_run.is_some());
+        assert_eq!(snap.watcher.last_summary.as_deref(), Some("+1 -0 ~0 files"));
+        assert!(!snap.has_errors());
+
+        record_job(&status.watcher, || anyhow::bail!("disk went away"));
+        let snap = status.snapshot();
+        assert_eq!(snap.watcher.runs, 2);
+        assert_eq!(snap.watcher.last_error.as_deref(), Some("disk went away"));
+        assert!(snap.has_errors());
+
+        // A later success clears the error
+        record_job(&status.watcher, || Ok(String::new()));
+        assert!(!status.snapshot().has_errors());
+    }
+
+    #[test]
+    fn test_snapshot_reports_scheduled_tasks() {
+        let schedule = crate::cron::CronSchedule::parse("0 3 * * *").unwrap();
+        let task = ScheduledTask::new(MaintenanceTask::CacheGc, schedule, None, local_now());
+        let status = DaemonStatus::with_tasks(&[task]);
+
+        let snap = status.snapshot();
+        assert!(snap.tasks["cache_gc"].next_run.is_some());
+        assert!(!snap.has_errors());
+
+        record_job(&status.tasks["cache_gc"], || anyhow::bail!("cache dir unreadable"));
+        assert!(status.snapshot().has_errors());
+    }
+
+    #[test]
+    fn test_scheduler_runs_watcher_and_stops() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("a.jpg"), b"x").unwrap();
+        crate::cli::run_init(tmp.path()).unwrap();
+
+        let status = Arc::new(DaemonStatus::new());
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = thread::spawn({
+            let path = tmp.path().to_path_buf();
+            let status = status.clone();
+            let stop = stop.clone();
+            move || {
+                let jobs = Jobs {
+                    watch_interval: Some(Duration::from_secs(3600)),
+                    maintenance_interval: Some(Duration::from_secs(3600)),
+                    scheduled: Vec::new(),
+                };
+                run_scheduler(path, jobs, status, stop)
+            }
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while status.snapshot().watcher.runs == 0 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        stop.store(true, Ordering::Relaxed);
+        handle.join().unwrap();
+
+        let snap = status.snapshot();
+        assert_eq!(snap.watcher.runs, 1);
+        assert!(snap.watcher.last_error.is_none());
+        // Maintenance is deferred by one interval after startup
+        assert_eq!(snap.maintenance.runs, 0);
+    }
+
+    #[test]
+    fn test_scheduler_runs_requested_jobs() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("a.jpg"), b"x").unwrap();
+        crate::cli::run_init(tmp.path()).unwrap();
+
+        let status = Arc::new(DaemonStatus::new());
+        status.request(JobRequest::from_name("watch").unwrap());
+        status.request(JobRequest::Task(MaintenanceTask::CacheGc));
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = thread::spawn({
+            let path = tmp.path().to_path_buf();
+            let status = status.clone();
+            let stop = stop.clone();
+            move || {
+                let jobs = Jobs { watch_interval: None, maintenance_interval: None, scheduled: Vec::new() };
+                run_scheduler(path, jobs, status, stop)
+            }
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while status.snapshot().maintenance.runs == 0 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        stop.store(true, Ordering::Relaxed);
+        handle.join().unwrap();
+
+        let snap = status.snapshot();
+        assert_eq!(snap.watcher.runs, 1);
+        // Without a schedule slot for the task, it reports as maintenance
+        assert_eq!(snap.maintenance.runs, 1);
+        assert!(JobRequest::from_name("defrag").is_none());
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use rusqlite::params;
+
+use super::files::file_from_row;
+use super::{Database, File};
+
+/// The two kinds of file activity that are tracked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    /// Previewed or opened, in the TUI or the web UI
+    Viewed,
+    /// Rating, tags or attributes changed by the user
+    Edited,
+}
+
+impl Activity {
+    fn column(self) -> &'static str {
+        match self {
+            Activity::Viewed => "viewed_at",
+            Activity::Edited => "edited_at",
+        }
+    }
+}
+
+impl Database {
+    /// Note that a file was viewed or edited at `at` (Unix seconds)
+    pub fn record_file_activity(&self, file_id: i64, activity: Activity, at: i64) -> Result<()> {
+        let column = activity.column();
+        self.connection().execute(
+            &format!(
+                "INSERT INTO file_activity (file_id, {column}) VALUES (?1, ?2)
+                 ON CONFLICT(file_id) DO UPDATE SET {column} = excluded.{column}"
+            ),
+            params![file_id, at],
+        )?;
+        Ok(())
+    }
+
+    /// The most recently viewed or edited files with their directory paths,
+    /// newest first
+    pub fn get_recent_files(&self, activity: Activity, limit: usize) -> Result<Vec<(File, String)>> {
+        let column = activity.column();
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT f.id, f.directory_id, f.filename, f.size, f.mtime, f.hash, f.rating, f.media_type, f.width, f.height, f.perceptual_hash, f.camera, f.lens, d.path
+             FROM file_activity a
+             JOIN files f ON a.file_id = f.id
+             JOIN directories d ON f.directory_id = d.id
+             WHERE a.{column} IS NOT NULL
+             ORDER BY a.{column} DESC, f.id DESC
+             LIMIT ?1"
+        ))?;
+
+        let rows = stmt.query_map([limit as i64], |row| {
+            let file = file_from_row(row)?;
+            let dir_path: String = row.get(13)?;
+            Ok((file, dir_path))
+        })?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_files_newest_first() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let a = db.insert_file(dir, "a.jpg", 100, 0, None).unwrap();
+        let b = db.insert_file(dir, "b.jpg", 100, 0, None).unwrap();
+        let c = db.insert_file(dir, "c.jpg", 100, 0, None).unwrap();
+
+        db.record_file_activity(a, Activity::Viewed, 100).unwrap();
+        db.record_file_activity(b, Activity::Viewed, 200).unwrap();
+        db.record_file_activity(c, Activity::Edited, 150).unwrap();
+        // Viewing again moves it back to the top
+        db.record_file_activity(a, Activity::Viewed, 300).unwrap();
+
+        let viewed: Vec<String> = db
+            .get_recent_files(Activity::Viewed, 10)
+            .unwrap()
+            .into_iter()
+            .map(|(f, _)| f.filename)
+            .collect();
+        assert_eq!(viewed, vec!["a.jpg", "b.jpg"]);
+
+        let edited = db.get_recent_files(Activity::Edited, 10).unwrap();
+        assert_eq!(edited.len(), 1);
+        assert_eq!(edited[0].0.filename, "c.jpg");
+        assert_eq!(edited[0].1, "trip");
+
+        assert_eq!(db.get_recent_files(Activity::Viewed, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_viewing_keeps_edit_time() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("", None, None).unwrap();
+        let file = db.insert_file(dir, "a.jpg", 100, 0, None).unwrap();
+
+        db.record_file_activity(file, Activity::Edited, 100).unwrap();
+        db.record_file_activity(file, Activity::Viewed, 200).unwrap();
+
+        assert_eq!(db.get_recent_files(Activity::Edited, 10).unwrap().len(), 1);
+        assert_eq!(db.get_recent_files(Activity::Viewed, 10).unwrap().len(), 1);
+    }
+}
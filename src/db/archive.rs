@@ -0,0 +1,195 @@
+use anyhow::Result;
+use rusqlite::{params, OptionalExtension, Row};
+
+use super::directories::SUBTREE_FILES;
+use super::files::file_from_row;
+use super::{Database, File};
+use crate::hash::HashAlgorithm;
+
+/// A file whose original was uploaded to cold storage
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivedFile {
+    pub file_id: i64,
+    /// Full object URL, e.g. `s3://bucket/prefix/trip/clip.mp4`
+    pub location: String,
+    /// Hash of the uploaded original, computed with `hash_algorithm`
+    pub hash: String,
+    pub hash_algorithm: HashAlgorithm,
+    pub size: i64,
+    pub archived_at: i64,
+    /// Whether the local copy was replaced by a stub
+    pub stubbed: bool,
+}
+
+/// Which files `picman archive` considers
+#[derive(Debug, Default, Clone)]
+pub struct ArchiveFilter {
+    /// Only files in this directory and its descendants
+    pub dir: Option<String>,
+    pub tag: Option<String>,
+    pub media_type: Option<String>,
+    pub min_size: Option<i64>,
+}
+
+fn archived_from_row(row: &Row) -> rusqlite::Result<ArchivedFile> {
+    Ok(ArchivedFile {
+        file_id: row.get(0)?,
+        location: row.get(1)?,
+        hash: row.get(2)?,
+        size: row.get(3)?,
+        archived_at: row.get(4)?,
+        stubbed: row.get(5)?,
+        // Unknown names only come from a newer picman; restore then fails the checksum
+        hash_algorithm: row.get::<_, String>(6)?.parse().unwrap_or_default(),
+    })
+}
+
+impl Database {
+    /// Record an uploaded original, replacing any earlier record for the file
+    pub fn record_archived_file(&self, archived: &ArchivedFile) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO archived_files (file_id, location, hash, size, archived_at, stubbed, hash_algorithm)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                archived.file_id,
+                archived.location,
+                archived.hash,
+                archived.size,
+                archived.archived_at,
+                archived.stubbed,
+                archived.hash_algorithm.name()
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_archived_file(&self, file_id: i64) -> Result<Option<ArchivedFile>> {
+        let archived = self
+            .connection()
+            .query_row(
+                "SELECT file_id, location, hash, size, archived_at, stubbed, hash_algorithm
+                 FROM archived_files WHERE file_id = ?1",
+                [file_id],
+                archived_from_row,
+            )
+            .optional()?;
+        Ok(archived)
+    }
+
+    /// Mark whether the local copy of an archived file is a stub
+    pub fn set_archive_stubbed(&self, file_id: i64, stubbed: bool) -> Result<()> {
+        self.connection().execute(
+            "UPDATE archived_files SET stubbed = ?2 WHERE file_id = ?1",
+            params![file_id, stubbed],
+        )?;
+        Ok(())
+    }
+
+    /// Whether the file on disk is a stub left by `picman archive`
+    pub fn is_archive_stub(&self, file_id: i64) -> Result<bool> {
+        Ok(self.get_archived_file(file_id)?.is_some_and(|a| a.stubbed))
+    }
+
+    /// Number of archived files and how many of them are stubbed locally
+    pub fn count_archived_files(&self) -> Result<(usize, usize)> {
+        let (archived, stubbed): (i64, i64) = self.connection().query_row(
+            "SELECT COUNT(*), COALESCE(SUM(stubbed), 0) FROM archived_files",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok((archived as usize, stubbed as usize))
+    }
+
+    /// Files matching an archive filter, with their directory paths,
+    /// ordered by path
+    pub fn get_archive_candidates(&self, filter: &ArchiveFilter) -> Result<Vec<(File, String)>> {
+        let mut sql = String::from(
+            "SELECT f.id, f.directory_id, f.filename, f.size, f.mtime, f.hash, f.rating, f.media_type, f.width, f.height, f.perceptual_hash, f.camera, f.lens, d.path
+             FROM files f
+             JOIN directories d ON f.directory_id = d.id
+             WHERE 1 = 1",
+        );
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+
+        // SUBTREE_FILES binds ?1, so the directory goes first
+        if let Some(dir) = &filter.dir {
+            values.push(dir.clone().into());
+            sql.push_str(&format!(" AND f.id IN ({SUBTREE_FILES})"));
+        }
+        if let Some(tag) = &filter.tag {
+            values.push(self.canonical_tag(tag)?.into());
+            sql.push_str(&format!(
+                " AND f.id IN (SELECT ft.file_id FROM file_tags ft JOIN tags t ON ft.tag_id = t.id WHERE t.name = ?{})",
+                values.len()
+            ));
+        }
+        if let Some(media_type) = &filter.media_type {
+            values.push(media_type.clone().into());
+            sql.push_str(&format!(" AND f.media_type = ?{}", values.len()));
+        }
+        if let Some(min_size) = filter.min_size {
+            values.push(min_size.into());
+            sql.push_str(&format!(" AND f.size >= ?{}", values.len()));
+        }
+        sql.push_str(" ORDER BY d.path, f.filename");
+
+        let mut stmt = self.connection().prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+            let file = file_from_row(row)?;
+            let dir_path: String = row.get(13)?;
+            Ok((file, dir_path))
+        })?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_records_and_candidates() {
+        let db = Database::open_in_memory().unwrap();
+        let trip = db.insert_directory("trip", None, None).unwrap();
+        let other = db.insert_directory("trips", None, None).unwrap();
+        let clip = db.insert_file(trip, "clip.mp4", 5_000, 1000, Some("video")).unwrap();
+        db.insert_file(trip, "a.jpg", 10, 1000, Some("image")).unwrap();
+        db.insert_file(other, "big.mp4", 9_000, 1000, Some("video")).unwrap();
+
+        let filter = ArchiveFilter {
+            dir: Some("trip".to_string()),
+            media_type: Some("video".to_string()),
+            min_size: Some(1_000),
+            ..Default::default()
+        };
+        let names: Vec<String> = db
+            .get_archive_candidates(&filter)
+            .unwrap()
+            .into_iter()
+            .map(|(f, _)| f.filename)
+            .collect();
+        assert_eq!(names, vec!["clip.mp4"]);
+        assert_eq!(db.get_archive_candidates(&ArchiveFilter::default()).unwrap().len(), 3);
+
+        db.add_file_tag(clip, "cold").unwrap();
+        let tagged = ArchiveFilter { tag: Some("cold".to_string()), ..Default::default() };
+        assert_eq!(db.get_archive_candidates(&tagged).unwrap().len(), 1);
+
+        assert!(!db.is_archive_stub(clip).unwrap());
+        let archived = ArchivedFile {
+            file_id: clip,
+            location: "s3://bucket/trip/clip.mp4".to_string(),
+            hash: "abc".to_string(),
+            hash_algorithm: HashAlgorithm::Sha256,
+            size: 5_000,
+            archived_at: 42,
+            stubbed: false,
+        };
+        db.record_archived_file(&archived).unwrap();
+        assert_eq!(db.get_archived_file(clip).unwrap(), Some(archived));
+        db.set_archive_stubbed(clip, true).unwrap();
+        assert!(db.is_archive_stub(clip).unwrap());
+        assert_eq!(db.count_archived_files().unwrap(), (1, 1));
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rusqlite::{params, OptionalExtension};
+
+use super::Database;
+
+impl Database {
+    /// Set a custom attribute on a file, replacing any earlier value for the key
+    pub fn set_file_attribute(&self, file_id: i64, key: &str, value: &str) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO file_attributes (file_id, key, value) VALUES (?1, ?2, ?3)
+             ON CONFLICT (file_id, key) DO UPDATE SET value = excluded.value",
+            params![file_id, key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Remove a custom attribute, returns whether it existed
+    pub fn remove_file_attribute(&self, file_id: i64, key: &str) -> Result<bool> {
+        let removed = self.connection().execute(
+            "DELETE FROM file_attributes WHERE file_id = ?1 AND key = ?2",
+            params![file_id, key],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Get a single custom attribute
+    pub fn get_file_attribute(&self, file_id: i64, key: &str) -> Result<Option<String>> {
+        let value = self
+            .connection()
+            .query_row(
+                "SELECT value FROM file_attributes WHERE file_id = ?1 AND key = ?2",
+                params![file_id, key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value)
+    }
+
+    /// All custom attributes of a file as (key, value), sorted by key
+    pub fn get_file_attributes(&self, file_id: i64) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT key, value FROM file_attributes WHERE file_id = ?1 ORDER BY key",
+        )?;
+
+        let attributes = stmt
+            .query_map([file_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(attributes)
+    }
+
+    /// Get all file attributes at once, keyed by file ID (avoids N+1 queries)
+    pub fn get_all_file_attributes(&self) -> Result<HashMap<i64, Vec<(String, String)>>> {
+        let mut stmt = self
+            .connection()
+            .prepare("SELECT file_id, key, value FROM file_attributes ORDER BY file_id, key")?;
+
+        let mut result: HashMap<i64, Vec<(String, String)>> = HashMap::new();
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        })?;
+        for row in rows {
+            let (file_id, key, value) = row?;
+            result.entry(file_id).or_default().push((key, value));
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_get_remove_attributes() {
+        let db = Database::open_in_memory().unwrap();
+        let dir_id = db.insert_directory("shoot", None, None).unwrap();
+        let file_id = db.insert_file(dir_id, "a.jpg", 100, 1000, Some("image")).unwrap();
+
+        db.set_file_attribute(file_id, "client", "Acme").unwrap();
+        db.set_file_attribute(file_id, "camera_body", "X-T5").unwrap();
+        db.set_file_attribute(file_id, "client", "Globex").unwrap();
+
+        assert_eq!(db.get_file_attribute(file_id, "client").unwrap().as_deref(), Some("Globex"));
+        assert_eq!(
+            db.get_file_attributes(file_id).unwrap(),
+            vec![
+                ("camera_body".to_string(), "X-T5".to_string()),
+                ("client".to_string(), "Globex".to_string()),
+            ]
+        );
+        assert_eq!(db.get_all_file_attributes().unwrap()[&file_id].len(), 2);
+
+        assert!(db.remove_file_attribute(file_id, "client").unwrap());
+        assert!(!db.remove_file_attribute(file_id, "client").unwrap());
+        assert_eq!(db.get_file_attribute(file_id, "client").unwrap(), None);
+    }
+
+    #[test]
+    fn test_deleting_file_clears_attributes() {
+        let db = Database::open_in_memory().unwrap();
+        let dir_id = db.insert_directory("shoot", None, None).unwrap();
+        let file_id = db.insert_file(dir_id, "a.jpg", 100, 1000, Some("image")).unwrap();
+        db.set_file_attribute(file_id, "print_status", "ordered").unwrap();
+
+        db.delete_file(file_id).unwrap();
+        assert!(db.get_all_file_attributes().unwrap().is_empty());
+    }
+}
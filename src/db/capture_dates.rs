@@ -0,0 +1,95 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use rusqlite::params;
+
+use super::Database;
+
+/// Images captured on one day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureDay {
+    pub date: NaiveDate,
+    pub count: usize,
+}
+
+impl Database {
+    /// Record the day a file was captured, from its EXIF
+    pub fn set_file_capture_date(&self, id: i64, date: Option<NaiveDate>) -> Result<()> {
+        let date = date.map(|d| d.format("%Y-%m-%d").to_string());
+        self.connection()
+            .execute("UPDATE files SET taken_at = ?1 WHERE id = ?2", params![date, id])?;
+        Ok(())
+    }
+
+    /// Images captured on each day of `year` with any, in date order
+    pub fn get_capture_days(&self, year: i32) -> Result<Vec<CaptureDay>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT taken_at, COUNT(*) FROM files
+             WHERE media_type = 'image' AND taken_at >= ?1 AND taken_at < ?2
+             GROUP BY taken_at
+             ORDER BY taken_at",
+        )?;
+        let rows = stmt.query_map(
+            params![format!("{:04}-01-01", year), format!("{:04}-01-01", year + 1)],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+        )?;
+        let mut days = Vec::new();
+        for row in rows {
+            let (date, count) = row?;
+            let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")?;
+            days.push(CaptureDay { date, count: count as usize });
+        }
+        Ok(days)
+    }
+
+    /// Years with at least one image capture date, oldest first
+    pub fn get_capture_years(&self) -> Result<Vec<i32>> {
+        let years = self
+            .connection()
+            .prepare(
+                "SELECT DISTINCT CAST(substr(taken_at, 1, 4) AS INTEGER) FROM files
+                 WHERE media_type = 'image' AND taken_at IS NOT NULL
+                 ORDER BY 1",
+            )?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(years)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_days_count_images_per_day() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        for (name, media_type, date) in [
+            ("a.jpg", "image", day(2024, 3, 15)),
+            ("b.jpg", "image", day(2024, 3, 15)),
+            ("c.jpg", "image", day(2024, 12, 31)),
+            ("d.jpg", "image", day(2023, 6, 1)),
+            ("e.jpg", "image", None),
+            ("clip.mp4", "video", day(2024, 3, 15)),
+        ] {
+            let id = db.insert_file(dir, name, 1, 0, Some(media_type)).unwrap();
+            db.set_file_capture_date(id, date).unwrap();
+        }
+
+        assert_eq!(
+            db.get_capture_days(2024).unwrap(),
+            vec![
+                CaptureDay { date: day(2024, 3, 15).unwrap(), count: 2 },
+                CaptureDay { date: day(2024, 12, 31).unwrap(), count: 1 },
+            ]
+        );
+        assert!(db.get_capture_days(2022).unwrap().is_empty());
+        assert_eq!(db.get_capture_years().unwrap(), vec![2023, 2024]);
+
+        // Edited files lose their date until the next sync reads it again
+        let a = db.get_file_by_name(dir, "a.jpg").unwrap().unwrap();
+        db.clear_file_gear(a.id).unwrap();
+        assert_eq!(db.get_capture_days(2024).unwrap()[0].count, 1);
+    }
+}
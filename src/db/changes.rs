@@ -0,0 +1,266 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use rusqlite::OptionalExtension;
+
+use super::Database;
+
+/// Rows `metadata_changes` keeps
+const METADATA_CHANGES_KEPT: i64 = 10_000;
+
+/// Files and directories whose rating or tags changed since some version
+#[derive(Debug, Default, PartialEq)]
+pub struct MetadataChanges {
+    /// Version to ask from next time
+    pub version: i64,
+    pub files: HashSet<i64>,
+    pub directories: HashSet<i64>,
+    /// More changed than the log keeps: reload everything instead
+    pub overflowed: bool,
+}
+
+/// A write was based on an older version of a file or directory than the
+/// one now stored: someone else changed its rating or tags in between
+#[derive(Debug, thiserror::Error)]
+#[error("Changed by someone else since version {expected} (now version {current}); reload and try again")]
+pub struct VersionConflict {
+    pub expected: i64,
+    pub current: i64,
+}
+
+impl VersionConflict {
+    /// Fail unless the stored version `current` is the `expected` one
+    pub fn check(expected: i64, current: i64) -> Result<()> {
+        if expected != current {
+            return Err(VersionConflict { expected, current }.into());
+        }
+        Ok(())
+    }
+}
+
+impl MetadataChanges {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.directories.is_empty() && !self.overflowed
+    }
+}
+
+impl Database {
+    /// Log files and directories whose rating or tags change, and bump
+    /// their `version`, with triggers so every process's edits (CLI, web,
+    /// TUI, daemon) count. A running TUI polls the log to refresh what
+    /// others changed; web writes compare versions.
+    pub(super) fn create_metadata_change_log(&self) -> Result<()> {
+        self.connection().execute_batch(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS metadata_changes (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                file_id INTEGER,
+                directory_id INTEGER
+            );
+            CREATE TRIGGER IF NOT EXISTS metadata_changes_prune AFTER INSERT ON metadata_changes
+            BEGIN
+                DELETE FROM metadata_changes WHERE seq <= NEW.seq - {METADATA_CHANGES_KEPT};
+            END;
+            CREATE TRIGGER IF NOT EXISTS file_rating_changed AFTER UPDATE OF rating ON files
+            WHEN OLD.rating IS NOT NEW.rating
+            BEGIN
+                INSERT INTO metadata_changes (file_id) VALUES (NEW.id);
+            END;
+            CREATE TRIGGER IF NOT EXISTS file_tag_added AFTER INSERT ON file_tags
+            BEGIN
+                INSERT INTO metadata_changes (file_id) VALUES (NEW.file_id);
+            END;
+            CREATE TRIGGER IF NOT EXISTS file_tag_removed AFTER DELETE ON file_tags
+            BEGIN
+                INSERT INTO metadata_changes (file_id) VALUES (OLD.file_id);
+            END;
+            CREATE TRIGGER IF NOT EXISTS directory_rating_changed AFTER UPDATE OF rating ON directories
+            WHEN OLD.rating IS NOT NEW.rating
+            BEGIN
+                INSERT INTO metadata_changes (directory_id) VALUES (NEW.id);
+            END;
+            CREATE TRIGGER IF NOT EXISTS directory_tag_added AFTER INSERT ON directory_tags
+            BEGIN
+                INSERT INTO metadata_changes (directory_id) VALUES (NEW.directory_id);
+            END;
+            CREATE TRIGGER IF NOT EXISTS directory_tag_removed AFTER DELETE ON directory_tags
+            BEGIN
+                INSERT INTO metadata_changes (directory_id) VALUES (OLD.directory_id);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS metadata_changes_file_version AFTER INSERT ON metadata_changes
+            WHEN NEW.file_id IS NOT NULL
+            BEGIN
+                UPDATE files SET version = version + 1 WHERE id = NEW.file_id;
+            END;
+            CREATE TRIGGER IF NOT EXISTS metadata_changes_directory_version AFTER INSERT ON metadata_changes
+            WHEN NEW.directory_id IS NOT NULL
+            BEGIN
+                UPDATE directories SET version = version + 1 WHERE id = NEW.directory_id;
+            END;
+            "#
+        ))?;
+        Ok(())
+    }
+
+    /// Version of a file's rating and tags, or None if there's no such file
+    pub fn get_file_version(&self, id: i64) -> Result<Option<i64>> {
+        let version = self
+            .connection()
+            .query_row("SELECT version FROM files WHERE id = ?1", [id], |row| row.get(0))
+            .optional()?;
+        Ok(version)
+    }
+
+    /// Version of a directory's rating and tags, or None if there's no such directory
+    pub fn get_directory_version(&self, id: i64) -> Result<Option<i64>> {
+        let version = self
+            .connection()
+            .query_row("SELECT version FROM directories WHERE id = ?1", [id], |row| row.get(0))
+            .optional()?;
+        Ok(version)
+    }
+
+    /// Versions of several files at once, for listings
+    pub fn get_file_versions(&self, ids: &[i64]) -> Result<HashMap<i64, i64>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders = vec!["?"; ids.len()].join(",");
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT id, version FROM files WHERE id IN ({})",
+            placeholders
+        ))?;
+        let versions = stmt
+            .query_map(rusqlite::params_from_iter(ids), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(versions)
+    }
+
+    /// Versions of all directories, for the directory listing
+    pub fn get_directory_versions(&self) -> Result<HashMap<i64, i64>> {
+        let mut stmt = self.connection().prepare("SELECT id, version FROM directories")?;
+        let versions = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(versions)
+    }
+
+    /// Counter bumped by every rating or tag change, from any process.
+    /// A single index lookup, cheap enough to poll.
+    pub fn metadata_version(&self) -> Result<i64> {
+        let version = self.connection().query_row(
+            "SELECT COALESCE(MAX(seq), 0) FROM metadata_changes",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(version)
+    }
+
+    /// What changed after `version` (from [`Database::metadata_version`])
+    pub fn get_metadata_changes_since(&self, version: i64) -> Result<MetadataChanges> {
+        let oldest: Option<i64> =
+            self.connection()
+                .query_row("SELECT MIN(seq) FROM metadata_changes", [], |row| row.get(0))?;
+        let mut changes = MetadataChanges {
+            version,
+            overflowed: oldest.is_some_and(|oldest| oldest > version + 1),
+            ..Default::default()
+        };
+
+        let mut stmt = self.connection().prepare(
+            "SELECT seq, file_id, directory_id FROM metadata_changes WHERE seq > ?1 ORDER BY seq",
+        )?;
+        let rows = stmt.query_map([version], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i64>>(1)?, row.get::<_, Option<i64>>(2)?))
+        })?;
+        for row in rows {
+            let (seq, file_id, directory_id) = row?;
+            changes.version = seq;
+            changes.files.extend(file_id);
+            changes.directories.extend(directory_id);
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rating_and_tag_writes_are_logged() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let a = db.insert_file(dir, "a.jpg", 100, 0, Some("image")).unwrap();
+        let b = db.insert_file(dir, "b.jpg", 100, 0, Some("image")).unwrap();
+        let start = db.metadata_version().unwrap();
+
+        db.set_file_rating(a, Some(4.0)).unwrap();
+        // Setting the same rating again isn't a change
+        db.set_file_rating(a, Some(4.0)).unwrap();
+        db.add_file_tag(b, "beach").unwrap();
+        db.add_directory_tag(dir, "2024").unwrap();
+
+        let changes = db.get_metadata_changes_since(start).unwrap();
+        assert_eq!(changes.files, HashSet::from([a, b]));
+        assert_eq!(changes.directories, HashSet::from([dir]));
+        assert!(!changes.overflowed);
+        assert_eq!(changes.version, db.metadata_version().unwrap());
+        assert_eq!(changes.version, start + 3);
+        assert!(db.get_metadata_changes_since(changes.version).unwrap().is_empty());
+
+        db.remove_file_tag(b, "beach").unwrap();
+        db.set_directory_rating(dir, Some(3.0)).unwrap();
+        let changes = db.get_metadata_changes_since(changes.version).unwrap();
+        assert_eq!(changes.files, HashSet::from([b]));
+        assert_eq!(changes.directories, HashSet::from([dir]));
+    }
+
+    #[test]
+    fn test_versions_count_changes_from_any_writer() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let a = db.insert_file(dir, "a.jpg", 100, 0, Some("image")).unwrap();
+        let b = db.insert_file(dir, "b.jpg", 100, 0, Some("image")).unwrap();
+        assert_eq!(db.get_file_version(a).unwrap(), Some(0));
+
+        db.set_file_rating(a, Some(4.0)).unwrap();
+        db.add_file_tag(a, "beach").unwrap();
+        // Unchanged rating, tag already there: no new version
+        db.set_file_rating(a, Some(4.0)).unwrap();
+        db.add_file_tag(a, "beach").unwrap();
+        db.add_directory_tag(dir, "2024").unwrap();
+
+        assert_eq!(db.get_file_versions(&[a, b]).unwrap(), HashMap::from([(a, 2), (b, 0)]));
+        assert_eq!(db.get_directory_version(dir).unwrap(), Some(1));
+        assert_eq!(db.get_file_version(9999).unwrap(), None);
+
+        let err = VersionConflict::check(1, 2).unwrap_err();
+        assert_eq!(err.downcast_ref::<VersionConflict>().unwrap().current, 2);
+        assert!(VersionConflict::check(2, 2).is_ok());
+    }
+
+    #[test]
+    fn test_pruned_log_reports_overflow() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let file = db.insert_file(dir, "a.jpg", 100, 0, Some("image")).unwrap();
+        let start = db.metadata_version().unwrap();
+
+        db.begin_transaction().unwrap();
+        for i in 0..=METADATA_CHANGES_KEPT {
+            db.set_file_rating(file, Some(if i % 2 == 0 { 2.0 } else { 3.0 })).unwrap();
+        }
+        db.commit().unwrap();
+
+        let count: i64 = db
+            .connection()
+            .query_row("SELECT COUNT(*) FROM metadata_changes", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, METADATA_CHANGES_KEPT);
+        assert!(db.get_metadata_changes_since(start).unwrap().overflowed);
+        let recent = db.metadata_version().unwrap() - 5;
+        assert!(!db.get_metadata_changes_since(recent).unwrap().overflowed);
+    }
+}
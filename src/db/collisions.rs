@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::directories::BRANCH_DIRS;
+use super::Database;
+use crate::filenames::{collision_key, find_name_collisions};
+
+impl Database {
+    /// Recompute case-insensitive / Unicode-normalization name collisions for
+    /// one directory. Returns the number of flagged files.
+    pub fn rebuild_directory_name_collisions(&self, directory_id: i64) -> Result<usize> {
+        let files = self.get_files_in_directory(directory_id)?;
+        self.connection().execute(
+            "DELETE FROM name_collisions WHERE file_id IN (SELECT id FROM files WHERE directory_id = ?1)",
+            [directory_id],
+        )?;
+
+        let names: Vec<&str> = files.iter().map(|f| f.filename.as_str()).collect();
+        let mut flagged = 0;
+        for group in find_name_collisions(&names) {
+            for i in group {
+                self.connection().execute(
+                    "INSERT INTO name_collisions (file_id, collision_key) VALUES (?1, ?2)",
+                    params![files[i].id, collision_key(&files[i].filename)],
+                )?;
+                flagged += 1;
+            }
+        }
+        Ok(flagged)
+    }
+
+    /// Recompute name collisions for the whole library
+    pub fn rebuild_all_name_collisions(&self) -> Result<usize> {
+        self.connection().execute("DELETE FROM name_collisions", [])?;
+
+        // Only the columns every schema version has: this runs mid-migration,
+        // before later columns are added back to a rebuilt files table
+        let mut by_directory: HashMap<i64, Vec<(i64, String)>> = HashMap::new();
+        let mut stmt = self.connection().prepare("SELECT id, directory_id, filename FROM files")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get(2)?)))?;
+        for row in rows {
+            let (id, directory_id, filename) = row?;
+            by_directory.entry(directory_id).or_default().push((id, filename));
+        }
+
+        let mut flagged = 0;
+        for files in by_directory.values() {
+            let names: Vec<&str> = files.iter().map(|(_, name)| name.as_str()).collect();
+            for group in find_name_collisions(&names) {
+                for i in group {
+                    self.connection().execute(
+                        "INSERT INTO name_collisions (file_id, collision_key) VALUES (?1, ?2)",
+                        params![files[i].0, collision_key(&files[i].1)],
+                    )?;
+                    flagged += 1;
+                }
+            }
+        }
+        Ok(flagged)
+    }
+
+    /// Number of files whose name collides with a sibling
+    pub fn count_name_collisions(&self) -> Result<usize> {
+        let count: i64 =
+            self.connection()
+                .query_row("SELECT COUNT(*) FROM name_collisions", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// File ID -> names of the siblings it collides with, for a directory
+    pub fn get_directory_name_collisions(&self, directory_id: i64) -> Result<HashMap<i64, Vec<String>>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT a.file_id, fb.filename FROM name_collisions a
+             JOIN files fa ON fa.id = a.file_id
+             JOIN name_collisions b ON b.collision_key = a.collision_key AND b.file_id != a.file_id
+             JOIN files fb ON fb.id = b.file_id AND fb.directory_id = fa.directory_id
+             WHERE fa.directory_id = ?1
+             ORDER BY fb.filename",
+        )?;
+
+        let mut result: HashMap<i64, Vec<String>> = HashMap::new();
+        let rows = stmt.query_map([directory_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (file_id, other) = row?;
+            result.entry(file_id).or_default().push(other);
+        }
+        Ok(result)
+    }
+
+    /// [`Database::get_directory_name_collisions`] for the directory at
+    /// `path` and everything below it (the root's own files only for "")
+    pub fn get_branch_name_collisions(&self, path: &str) -> Result<HashMap<i64, Vec<String>>> {
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT a.file_id, fb.filename FROM name_collisions a
+             JOIN files fa ON fa.id = a.file_id
+             JOIN name_collisions b ON b.collision_key = a.collision_key AND b.file_id != a.file_id
+             JOIN files fb ON fb.id = b.file_id AND fb.directory_id = fa.directory_id
+             WHERE fa.directory_id IN ({})
+             ORDER BY fb.filename",
+            BRANCH_DIRS
+        ))?;
+
+        let mut result: HashMap<i64, Vec<String>> = HashMap::new();
+        let rows = stmt.query_map([path], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (file_id, other) = row?;
+            result.entry(file_id).or_default().push(other);
+        }
+        Ok(result)
+    }
+
+    /// All colliding groups in the library as (directory path, filenames),
+    /// ordered by directory path
+    pub fn get_all_name_collisions(&self) -> Result<Vec<(String, Vec<String>)>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT d.path, nc.collision_key, f.filename FROM name_collisions nc
+             JOIN files f ON f.id = nc.file_id
+             JOIN directories d ON d.id = f.directory_id
+             ORDER BY d.path, nc.collision_key, f.filename",
+        )?;
+
+        let mut groups: Vec<(String, String, Vec<String>)> = Vec::new();
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        })?;
+        for row in rows {
+            let (path, key, filename) = row?;
+            match groups.last_mut() {
+                Some((p, k, names)) if *p == path && *k == key => names.push(filename),
+                _ => groups.push((path, key, vec![filename])),
+            }
+        }
+        Ok(groups.into_iter().map(|(path, _, names)| (path, names)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebuild_directory_name_collisions() {
+        let db = Database::open_in_memory().unwrap();
+        let dir_id = db.insert_directory("export", None, None).unwrap();
+        let other_id = db.insert_directory("other", None, None).unwrap();
+        let a = db.insert_file(dir_id, "IMG_1.jpg", 100, 1000, Some("image")).unwrap();
+        let b = db.insert_file(dir_id, "img_1.JPG", 100, 1000, Some("image")).unwrap();
+        let c = db.insert_file(dir_id, "caf\u{E9}.png", 100, 1000, Some("image")).unwrap();
+        let d = db.insert_file(dir_id, "cafe\u{301}.png", 100, 1000, Some("image")).unwrap();
+        db.insert_file(dir_id, "unique.jpg", 100, 1000, Some("image")).unwrap();
+        // Same name in another directory is not a collision
+        db.insert_file(other_id, "img_1.jpg", 100, 1000, Some("image")).unwrap();
+
+        assert_eq!(db.rebuild_directory_name_collisions(dir_id).unwrap(), 4);
+        assert_eq!(db.rebuild_all_name_collisions().unwrap(), 4);
+        assert_eq!(db.count_name_collisions().unwrap(), 4);
+
+        let collisions = db.get_directory_name_collisions(dir_id).unwrap();
+        assert_eq!(collisions.len(), 4);
+        assert_eq!(collisions[&a], vec!["img_1.JPG"]);
+        assert_eq!(collisions[&b], vec!["IMG_1.jpg"]);
+        assert_eq!(collisions[&c], vec!["cafe\u{301}.png"]);
+        assert_eq!(collisions[&d], vec!["caf\u{E9}.png"]);
+
+        let all = db.get_all_name_collisions().unwrap();
+        assert_eq!(all.len(), 2);
+        assert!(all.contains(&("export".to_string(), vec!["IMG_1.jpg".to_string(), "img_1.JPG".to_string()])));
+
+        // Deleting one side clears the flag once the directory is rebuilt
+        db.delete_file(b).unwrap();
+        assert_eq!(db.rebuild_directory_name_collisions(dir_id).unwrap(), 2);
+        assert!(!db.get_directory_name_collisions(dir_id).unwrap().contains_key(&a));
+    }
+}
@@ -1,9 +1,27 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use rusqlite::params;
 
 use super::Database;
+use crate::filenames::spellings;
+use crate::library_path::LibraryPath;
+
+/// Directories at path `?1` and everything below it. The root ("") covers
+/// the whole library.
+pub(super) const SUBTREE_DIRS: &str = "SELECT d.id FROM directories d
+     WHERE ?1 = '' OR d.path = ?1 OR d.path LIKE ?1 || '/%'";
+
+/// Like [`SUBTREE_DIRS`], except that the root ("") stands for the
+/// directory holding the root-level files only
+pub(super) const BRANCH_DIRS: &str = "SELECT d.id FROM directories d
+     WHERE d.path = ?1 OR (?1 != '' AND d.path LIKE ?1 || '/%')";
+
+/// Files in the directory at path `?1` and everything below it
+pub(super) const SUBTREE_FILES: &str = "SELECT f.id FROM files f
+     JOIN directories d ON d.id = f.directory_id
+     WHERE ?1 = '' OR d.path = ?1 OR d.path LIKE ?1 || '/%'";
 
 /// Represents a directory in the database
 #[derive(Debug, Clone, PartialEq)]
@@ -11,27 +29,28 @@ pub struct Directory {
     pub id: i64,
     pub path: String,
     pub parent_id: Option<i64>,
-    pub rating: Option<i32>,
+    pub rating: Option<f64>,
     pub mtime: Option<i64>,
 }
 
+/// Totals for a directory and everything below it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirectoryTotals {
+    pub files: i64,
+    pub size: i64,
+    /// Newest file mtime
+    pub newest: Option<i64>,
+}
+
 impl Directory {
     /// Build the absolute path for a file inside this directory
     pub fn file_path(&self, library_path: &Path, filename: &str) -> PathBuf {
-        if self.path.is_empty() {
-            library_path.join(filename)
-        } else {
-            library_path.join(&self.path).join(filename)
-        }
+        LibraryPath::file(&self.path, filename).absolute(library_path)
     }
 
     /// Build the absolute path for this directory itself
     pub fn full_path(&self, library_path: &Path) -> PathBuf {
-        if self.path.is_empty() {
-            library_path.to_path_buf()
-        } else {
-            library_path.join(&self.path)
-        }
+        LibraryPath::new(&self.path).absolute(library_path)
     }
 }
 
@@ -52,6 +71,16 @@ impl Database {
 
     /// Get a directory by its path
     pub fn get_directory_by_path(&self, path: &str) -> Result<Option<Directory>> {
+        // A path typed composed finds one recorded decomposed, and vice versa
+        for spelling in spellings(path) {
+            if let Some(dir) = self.get_directory_by_exact_path(&spelling)? {
+                return Ok(Some(dir));
+            }
+        }
+        Ok(None)
+    }
+
+    fn get_directory_by_exact_path(&self, path: &str) -> Result<Option<Directory>> {
         use rusqlite::OptionalExtension;
         let result = self
             .connection()
@@ -126,7 +155,7 @@ impl Database {
     }
 
     /// Update directory rating
-    pub fn set_directory_rating(&self, id: i64, rating: Option<i32>) -> Result<()> {
+    pub fn set_directory_rating(&self, id: i64, rating: Option<f64>) -> Result<()> {
         self.connection().execute(
             "UPDATE directories SET rating = ?1 WHERE id = ?2",
             params![rating, id],
@@ -143,6 +172,27 @@ impl Database {
         Ok(())
     }
 
+    /// Dot-prefixed directories and everything below them, deepest first
+    pub fn get_dot_directories(&self) -> Result<Vec<Directory>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT id, path, parent_id, rating, mtime FROM directories
+             WHERE path LIKE '.%' OR path LIKE '%/.%'
+             ORDER BY length(path) DESC",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(Directory {
+                id: row.get(0)?,
+                path: row.get(1)?,
+                parent_id: row.get(2)?,
+                rating: row.get(3)?,
+                mtime: row.get(4)?,
+            })
+        })?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
     /// Delete a directory by ID
     pub fn delete_directory(&self, id: i64) -> Result<()> {
         self.connection()
@@ -166,7 +216,7 @@ impl Database {
              WHERE path LIKE ?3 AND id != ?4",
             params![
                 new_path,
-                old_path.len() + 1,  // +1 to skip the old prefix
+                old_path.chars().count() + 1,  // substr() counts characters; +1 to skip the old prefix
                 format!("{}/%", old_path),
                 id
             ],
@@ -254,6 +304,34 @@ impl Database {
 
         rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
     }
+
+    /// Recursive size and newest file of every directory, in one pass
+    pub fn get_directory_totals(&self) -> Result<HashMap<i64, DirectoryTotals>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT d.id, d.parent_id, COUNT(f.id), COALESCE(SUM(f.size), 0), MAX(f.mtime)
+             FROM directories d
+             LEFT JOIN files f ON f.directory_id = d.id
+             GROUP BY d.id",
+        )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))?
+            .collect::<Result<Vec<(i64, Option<i64>, i64, i64, Option<i64>)>, _>>()?;
+
+        let parents: HashMap<i64, Option<i64>> = rows.iter().map(|r| (r.0, r.1)).collect();
+        let mut totals: HashMap<i64, DirectoryTotals> = HashMap::new();
+        for (id, _, files, size, newest) in rows {
+            // Roll each directory's own files up into every ancestor
+            let mut current = Some(id);
+            while let Some(dir_id) = current {
+                let total = totals.entry(dir_id).or_default();
+                total.files += files;
+                total.size += size;
+                total.newest = total.newest.max(newest);
+                current = parents.get(&dir_id).copied().flatten();
+            }
+        }
+        Ok(totals)
+    }
 }
 
 #[cfg(test)]
@@ -285,9 +363,9 @@ mod tests {
         assert_eq!(roots.len(), 1);
         assert_eq!(roots[0].path, "photos");
 
-        db.set_directory_rating(root_id, Some(4)).unwrap();
+        db.set_directory_rating(root_id, Some(4.0)).unwrap();
         let updated = db.get_directory(root_id).unwrap().unwrap();
-        assert_eq!(updated.rating, Some(4));
+        assert_eq!(updated.rating, Some(4.0));
 
         db.delete_directory(child_id).unwrap();
         let children = db.get_child_directories(Some(root_id)).unwrap();
@@ -374,4 +452,31 @@ mod tests {
         let fixed = db.repair_directory_parents().unwrap();
         assert_eq!(fixed, 0);
     }
+
+    #[test]
+    fn test_get_directory_totals_rolls_up() {
+        let db = Database::open_in_memory().unwrap();
+        let trip = db.insert_directory("trip", None, None).unwrap();
+        let day1 = db.insert_directory("trip/day1", Some(trip), None).unwrap();
+        let empty = db.insert_directory("empty", None, None).unwrap();
+        db.insert_file(trip, "a.jpg", 100, 10, Some("image")).unwrap();
+        db.insert_file(day1, "b.jpg", 50, 30, Some("image")).unwrap();
+
+        let totals = db.get_directory_totals().unwrap();
+        assert_eq!(totals[&trip], DirectoryTotals { files: 2, size: 150, newest: Some(30) });
+        assert_eq!(totals[&day1], DirectoryTotals { files: 1, size: 50, newest: Some(30) });
+        assert_eq!(totals[&empty], DirectoryTotals::default());
+    }
+
+    #[test]
+    fn test_rename_directory_with_non_ascii_prefix() {
+        let db = Database::open_in_memory().unwrap();
+        let zurich = db.insert_directory("Z\u{FC}rich", None, None).unwrap();
+        let day = db.insert_directory("Z\u{FC}rich/day 1", Some(zurich), None).unwrap();
+
+        db.rename_directory(zurich, "Z\u{FC}rich", "Zu\u{308}rich").unwrap();
+        assert_eq!(db.get_directory(day).unwrap().unwrap().path, "Zu\u{308}rich/day 1");
+        // Found under either spelling
+        assert_eq!(db.get_directory_by_path("Z\u{FC}rich").unwrap().unwrap().id, zurich);
+    }
 }
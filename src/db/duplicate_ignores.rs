@@ -0,0 +1,169 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::{Database, DuplicateGroup, DuplicateScope};
+
+/// File pairs marked "not a duplicate, keep both", so detection stops
+/// reporting them together
+#[derive(Debug, Default, Clone)]
+pub struct IgnoredPairs(HashSet<(i64, i64)>);
+
+/// Order a pair the way it's stored, smaller id first
+fn ordered(a: i64, b: i64) -> (i64, i64) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+impl IgnoredPairs {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, a: i64, b: i64) -> bool {
+        self.0.contains(&ordered(a, b))
+    }
+
+    /// Drop the members of a duplicate group that are ignored against every
+    /// other member. What's left still has at least one real duplicate each,
+    /// unless fewer than two files remain.
+    pub fn retain_duplicates<T>(&self, group: &mut Vec<T>, id: impl Fn(&T) -> i64) {
+        if self.is_empty() {
+            return;
+        }
+        let ids: Vec<i64> = group.iter().map(&id).collect();
+        group.retain(|item| {
+            let this = id(item);
+            ids.iter().any(|&other| other != this && !self.contains(this, other))
+        });
+    }
+}
+
+impl Database {
+    /// Mark every pair among `file_ids` as not duplicates. Returns how many
+    /// pairs were newly ignored.
+    pub fn ignore_duplicates(&self, file_ids: &[i64], ignored_at: i64) -> Result<usize> {
+        let mut stmt = self.connection().prepare(
+            "INSERT OR IGNORE INTO duplicate_ignores (file_a, file_b, ignored_at) VALUES (?1, ?2, ?3)",
+        )?;
+        let mut added = 0;
+        for (i, &a) in file_ids.iter().enumerate() {
+            for &b in &file_ids[i + 1..] {
+                if a != b {
+                    let (a, b) = ordered(a, b);
+                    added += stmt.execute(params![a, b, ignored_at])?;
+                }
+            }
+        }
+        Ok(added)
+    }
+
+    /// Undo [`Database::ignore_duplicates`] for every pair among `file_ids`.
+    /// Returns how many pairs were removed.
+    pub fn unignore_duplicates(&self, file_ids: &[i64]) -> Result<usize> {
+        let mut stmt = self
+            .connection()
+            .prepare("DELETE FROM duplicate_ignores WHERE file_a = ?1 AND file_b = ?2")?;
+        let mut removed = 0;
+        for (i, &a) in file_ids.iter().enumerate() {
+            for &b in &file_ids[i + 1..] {
+                let (a, b) = ordered(a, b);
+                removed += stmt.execute([a, b])?;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// [`Database::find_duplicates_with_paths`] without the files marked
+    /// "keep both" with every other copy in their group
+    pub fn find_reported_duplicates_with_paths(&self) -> Result<Vec<DuplicateGroup>> {
+        self.find_reported_duplicates_in(&DuplicateScope::default())
+    }
+
+    /// [`Database::find_reported_duplicates_with_paths`] among the files in
+    /// `scope` only
+    pub fn find_reported_duplicates_in(&self, scope: &DuplicateScope) -> Result<Vec<DuplicateGroup>> {
+        let ignored = self.get_ignored_duplicate_pairs()?;
+        let mut groups = self.find_duplicates_in(scope)?;
+        if !ignored.is_empty() {
+            for group in &mut groups {
+                ignored.retain_duplicates(&mut group.files, |(f, _)| f.id);
+            }
+            groups.retain(|g| g.files.len() > 1);
+        }
+        Ok(groups)
+    }
+
+    pub fn get_ignored_duplicate_pairs(&self) -> Result<IgnoredPairs> {
+        let pairs = self
+            .connection()
+            .prepare("SELECT file_a, file_b FROM duplicate_ignores")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashSet<_>, _>>()?;
+        Ok(IgnoredPairs(pairs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_and_unignore_pairs() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let a = db.insert_file(dir, "a.jpg", 1, 0, Some("image")).unwrap();
+        let b = db.insert_file(dir, "b.jpg", 1, 0, Some("image")).unwrap();
+        let c = db.insert_file(dir, "c.jpg", 1, 0, Some("image")).unwrap();
+
+        assert_eq!(db.ignore_duplicates(&[b, a], 10).unwrap(), 1);
+        assert_eq!(db.ignore_duplicates(&[a, b, c], 11).unwrap(), 2);
+        let pairs = db.get_ignored_duplicate_pairs().unwrap();
+        assert!(pairs.contains(a, b) && pairs.contains(b, a) && pairs.contains(c, a));
+
+        assert_eq!(db.unignore_duplicates(&[c, b]).unwrap(), 1);
+        let pairs = db.get_ignored_duplicate_pairs().unwrap();
+        assert!(!pairs.contains(b, c));
+
+        // Deleting a file forgets its pairs
+        db.delete_file(a).unwrap();
+        assert!(db.get_ignored_duplicate_pairs().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reported_duplicates_leave_out_ignored_pairs() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let a = db.insert_file(dir, "a.jpg", 1, 0, Some("image")).unwrap();
+        let b = db.insert_file(dir, "b.jpg", 1, 0, Some("image")).unwrap();
+        db.set_file_hash(a, "same").unwrap();
+        db.set_file_hash(b, "same").unwrap();
+        assert_eq!(db.find_reported_duplicates_with_paths().unwrap().len(), 1);
+
+        db.ignore_duplicates(&[a, b], 0).unwrap();
+        assert!(db.find_reported_duplicates_with_paths().unwrap().is_empty());
+        assert_eq!(db.find_duplicates_with_paths().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_retain_duplicates() {
+        let pairs = IgnoredPairs([(1, 2), (1, 3)].into_iter().collect());
+
+        // 1 is a false positive against both others; 2 and 3 still match
+        let mut group = vec![1, 2, 3];
+        pairs.retain_duplicates(&mut group, |&id| id);
+        assert_eq!(group, vec![2, 3]);
+
+        let mut group = vec![1, 2];
+        pairs.retain_duplicates(&mut group, |&id| id);
+        assert!(group.is_empty());
+
+        let mut group = vec![2, 3, 4];
+        pairs.retain_duplicates(&mut group, |&id| id);
+        assert_eq!(group, vec![2, 3, 4]);
+    }
+}
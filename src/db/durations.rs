@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::files::FileToHash;
+use super::Database;
+use crate::library_path::LibraryPath;
+
+impl Database {
+    /// Record a video's length in seconds; `None` when ffprobe couldn't
+    /// read it, so it isn't probed again until the file changes
+    pub fn set_file_duration(&self, id: i64, duration: Option<f64>) -> Result<()> {
+        self.connection().execute(
+            "UPDATE files SET duration = ?1, duration_checked = 1 WHERE id = ?2",
+            params![duration, id],
+        )?;
+        Ok(())
+    }
+
+    /// Videos whose duration hasn't been read yet
+    pub fn get_files_needing_duration(&self) -> Result<Vec<FileToHash>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT f.id, d.path, f.filename, f.size
+             FROM files f
+             JOIN directories d ON f.directory_id = d.id
+             WHERE f.media_type = 'video' AND f.duration_checked = 0
+             ORDER BY d.path, f.filename",
+        )?;
+        let files = stmt
+            .query_map([], |row| {
+                let dir_path: String = row.get(1)?;
+                let filename: String = row.get(2)?;
+                let path = LibraryPath::file(&dir_path, &filename).into();
+                Ok(FileToHash { id: row.get(0)?, path, size: row.get(3)? })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(files)
+    }
+
+    /// Durations of those of `ids` that have one
+    pub fn get_file_durations(&self, ids: &[i64]) -> Result<HashMap<i64, f64>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders = vec!["?"; ids.len()].join(",");
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT id, duration FROM files WHERE id IN ({}) AND duration IS NOT NULL",
+            placeholders
+        ))?;
+        let durations = stmt
+            .query_map(rusqlite::params_from_iter(ids), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(durations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_durations_are_read_once_per_content() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("clips", None, None).unwrap();
+        let clip = db.insert_file(dir, "a.mp4", 100, 0, Some("video")).unwrap();
+        let broken = db.insert_file(dir, "b.mp4", 100, 0, Some("video")).unwrap();
+        db.insert_file(dir, "c.jpg", 100, 0, Some("image")).unwrap();
+
+        let needing: Vec<i64> = db.get_files_needing_duration().unwrap().iter().map(|f| f.id).collect();
+        assert_eq!(needing, [clip, broken]);
+
+        db.set_file_duration(clip, Some(12.5)).unwrap();
+        db.set_file_duration(broken, None).unwrap();
+        assert!(db.get_files_needing_duration().unwrap().is_empty());
+        assert_eq!(db.get_file_durations(&[clip, broken]).unwrap(), HashMap::from([(clip, 12.5)]));
+
+        // A changed file is probed again
+        db.clear_file_gear(clip).unwrap();
+        assert_eq!(db.get_files_needing_duration().unwrap().len(), 1);
+        assert!(db.get_file_durations(&[clip]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::Database;
+
+/// Range filters on capture settings, e.g. ISO >= 3200 for noisy shots or
+/// exposures slower than 1/60 s for possibly blurry ones. Bounds are
+/// inclusive; files without the setting in their EXIF never match a bound
+/// on it.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ExposureFilter {
+    pub min_iso: Option<i64>,
+    pub max_iso: Option<i64>,
+    /// f-number, e.g. 2.0 for f/2
+    pub min_aperture: Option<f64>,
+    pub max_aperture: Option<f64>,
+    /// Exposure time in seconds; a minimum finds shots slower than it
+    pub min_shutter: Option<f64>,
+    pub max_shutter: Option<f64>,
+}
+
+impl ExposureFilter {
+    pub fn is_active(&self) -> bool {
+        !self.bounds().is_empty()
+    }
+
+    /// Each bound as a comparison on a `files f` column and the value to
+    /// bind after it, e.g. `("f.iso >=", 3200.0)`
+    pub fn bounds(&self) -> Vec<(&'static str, f64)> {
+        [
+            ("f.iso >=", self.min_iso.map(|v| v as f64)),
+            ("f.iso <=", self.max_iso.map(|v| v as f64)),
+            ("f.aperture >=", self.min_aperture),
+            ("f.aperture <=", self.max_aperture),
+            ("f.exposure_time >=", self.min_shutter),
+            ("f.exposure_time <=", self.max_shutter),
+        ]
+        .into_iter()
+        .filter_map(|(comparison, value)| Some((comparison, value?)))
+        .collect()
+    }
+}
+
+/// Parse an exposure time as photographers write it: `1/60`, `0.5` or `2`
+/// (seconds), with an optional trailing `s`
+pub fn parse_shutter(s: &str) -> Result<f64, String> {
+    let trimmed = s.trim().trim_end_matches('s').trim();
+    let seconds = match trimmed.split_once('/') {
+        Some((num, denom)) => {
+            let num: f64 = num.trim().parse().map_err(|_| format!("invalid shutter speed '{}'", s))?;
+            let denom: f64 = denom.trim().parse().map_err(|_| format!("invalid shutter speed '{}'", s))?;
+            num / denom
+        }
+        None => trimmed.parse().map_err(|_| format!("invalid shutter speed '{}'", s))?,
+    };
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err(format!("shutter speed must be positive (got '{}')", s));
+    }
+    Ok(seconds)
+}
+
+impl Database {
+    /// Record the ISO, f-number and exposure time read from a file's EXIF
+    pub fn set_file_exposure(
+        &self,
+        id: i64,
+        iso: Option<u32>,
+        aperture: Option<f64>,
+        exposure_time: Option<f64>,
+    ) -> Result<()> {
+        self.connection().execute(
+            "UPDATE files SET iso = ?1, aperture = ?2, exposure_time = ?3 WHERE id = ?4",
+            params![iso, aperture, exposure_time, id],
+        )?;
+        Ok(())
+    }
+
+    /// Ids of files within every bound of the filter
+    pub fn get_file_ids_matching_exposure(&self, filter: &ExposureFilter) -> Result<HashSet<i64>> {
+        let bounds = filter.bounds();
+        let conditions: Vec<String> = bounds
+            .iter()
+            .enumerate()
+            .map(|(i, (comparison, _))| format!("{} ?{}", comparison, i + 1))
+            .collect();
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let ids = self
+            .connection()
+            .prepare(&format!("SELECT f.id FROM files f {}", where_clause))?
+            .query_map(rusqlite::params_from_iter(bounds.iter().map(|(_, v)| v)), |row| row.get(0))?
+            .collect::<Result<HashSet<_>, _>>()?;
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_shutter() {
+        assert_eq!(parse_shutter("1/60"), Ok(1.0 / 60.0));
+        assert_eq!(parse_shutter("0.5"), Ok(0.5));
+        assert_eq!(parse_shutter("2s"), Ok(2.0));
+        assert!(parse_shutter("fast").is_err());
+        assert!(parse_shutter("1/0").is_err());
+        assert!(parse_shutter("0").is_err());
+    }
+
+    #[test]
+    fn test_exposure_filter_ranges() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("night", None, None).unwrap();
+        let noisy = db.insert_file(dir, "noisy.jpg", 1, 0, Some("image")).unwrap();
+        let blurry = db.insert_file(dir, "blurry.jpg", 1, 0, Some("image")).unwrap();
+        let bare = db.insert_file(dir, "bare.jpg", 1, 0, Some("image")).unwrap();
+        db.set_file_exposure(noisy, Some(6400), Some(1.4), Some(1.0 / 250.0)).unwrap();
+        db.set_file_exposure(blurry, Some(200), Some(8.0), Some(1.0 / 15.0)).unwrap();
+
+        let ids = |filter: ExposureFilter| db.get_file_ids_matching_exposure(&filter).unwrap();
+        assert_eq!(ids(ExposureFilter { min_iso: Some(3200), ..Default::default() }), HashSet::from([noisy]));
+        assert_eq!(ids(ExposureFilter { max_aperture: Some(2.0), ..Default::default() }), HashSet::from([noisy]));
+        assert_eq!(
+            ids(ExposureFilter { min_shutter: Some(1.0 / 60.0), ..Default::default() }),
+            HashSet::from([blurry])
+        );
+        assert!(ids(ExposureFilter { min_iso: Some(3200), min_aperture: Some(4.0), ..Default::default() }).is_empty());
+
+        // No bounds matches everything, including files without EXIF
+        assert!(!ExposureFilter::default().is_active());
+        assert_eq!(ids(ExposureFilter::default()), HashSet::from([noisy, blurry, bare]));
+
+        // Cleared along with gear when the file changes
+        db.clear_file_gear(noisy).unwrap();
+        assert_eq!(ids(ExposureFilter { min_iso: Some(100), ..Default::default() }), HashSet::from([blurry]));
+    }
+}
@@ -1,12 +1,18 @@
+use std::collections::HashSet;
 use std::path::PathBuf;
 
 use anyhow::Result;
-use rusqlite::{params, OptionalExtension, Row};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, OptionalExtension, Row};
 
+use super::directories::BRANCH_DIRS;
 use super::Database;
+use crate::filenames::spellings;
+use crate::hash::HashAlgorithm;
+use crate::library_path::LibraryPath;
 
-/// Map a row to a File struct (columns: id, directory_id, filename, size, mtime, hash, rating, media_type, width, height, perceptual_hash)
-fn file_from_row(row: &Row) -> rusqlite::Result<File> {
+/// Map a row to a File struct (columns: id, directory_id, filename, size, mtime, hash, rating, media_type, width, height, perceptual_hash, camera, lens)
+pub(super) fn file_from_row(row: &Row) -> rusqlite::Result<File> {
     Ok(File {
         id: row.get(0)?,
         directory_id: row.get(1)?,
@@ -19,6 +25,8 @@ fn file_from_row(row: &Row) -> rusqlite::Result<File> {
         width: row.get(8)?,
         height: row.get(9)?,
         perceptual_hash: row.get(10)?,
+        camera: row.get(11)?,
+        lens: row.get(12)?,
     })
 }
 
@@ -31,11 +39,14 @@ pub struct File {
     pub size: i64,
     pub mtime: i64,
     pub hash: Option<String>,
-    pub rating: Option<i32>,
+    pub rating: Option<f64>,
     pub media_type: Option<String>,
     pub width: Option<i32>,
     pub height: Option<i32>,
     pub perceptual_hash: Option<i64>,
+    /// Camera make and model from EXIF, e.g. "Fujifilm X-T3"
+    pub camera: Option<String>,
+    pub lens: Option<String>,
 }
 
 /// Represents a file that needs hashing (id + full path)
@@ -43,6 +54,17 @@ pub struct File {
 pub struct FileToHash {
     pub id: i64,
     pub path: PathBuf,
+    pub size: i64,
+}
+
+/// A file's stored full hash, for `picman verify`
+#[derive(Debug, Clone)]
+pub struct HashedFile {
+    pub id: i64,
+    pub path: PathBuf,
+    pub size: i64,
+    pub hash: String,
+    pub algorithm: HashAlgorithm,
 }
 
 impl Database {
@@ -94,7 +116,7 @@ impl Database {
         let result = self
             .connection()
             .query_row(
-                "SELECT id, directory_id, filename, size, mtime, hash, rating, media_type, width, height, perceptual_hash
+                "SELECT id, directory_id, filename, size, mtime, hash, rating, media_type, width, height, perceptual_hash, camera, lens
                  FROM files WHERE directory_id = ?1 AND filename = ?2",
                 params![directory_id, filename],
                 |row| file_from_row(row),
@@ -106,7 +128,7 @@ impl Database {
     /// Get all files in a directory
     pub fn get_files_in_directory(&self, directory_id: i64) -> Result<Vec<File>> {
         let mut stmt = self.connection().prepare(
-            "SELECT id, directory_id, filename, size, mtime, hash, rating, media_type, width, height, perceptual_hash
+            "SELECT id, directory_id, filename, size, mtime, hash, rating, media_type, width, height, perceptual_hash, camera, lens
              FROM files WHERE directory_id = ?1 ORDER BY filename",
         )?;
 
@@ -115,10 +137,24 @@ impl Database {
         rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
     }
 
+    /// Files in the directory at `path` and every directory below it, by
+    /// directory then name. For the root ("") only its own files.
+    pub fn get_files_in_branch(&self, path: &str) -> Result<Vec<File>> {
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT id, directory_id, filename, size, mtime, hash, rating, media_type, width, height, perceptual_hash, camera, lens
+             FROM files WHERE directory_id IN ({}) ORDER BY directory_id, filename",
+            BRANCH_DIRS
+        ))?;
+
+        let rows = stmt.query_map([path], file_from_row)?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
     /// Get all files in the database
     pub fn get_all_files(&self) -> Result<Vec<File>> {
         let mut stmt = self.connection().prepare(
-            "SELECT id, directory_id, filename, size, mtime, hash, rating, media_type, width, height, perceptual_hash
+            "SELECT id, directory_id, filename, size, mtime, hash, rating, media_type, width, height, perceptual_hash, camera, lens
              FROM files ORDER BY directory_id, filename",
         )?;
 
@@ -127,35 +163,107 @@ impl Database {
         rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
     }
 
-    /// Update file hash
+    /// IDs of dot-prefixed files and of every file below a dot-prefixed
+    /// directory, which scanning skips unless `[scan] dotfiles` is on
+    pub fn get_dotfile_ids(&self) -> Result<HashSet<i64>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT f.id FROM files f JOIN directories d ON d.id = f.directory_id
+             WHERE f.filename LIKE '.%' OR d.path LIKE '.%' OR d.path LIKE '%/.%'",
+        )?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<Result<HashSet<_>, _>>().map_err(Into::into)
+    }
+
+    /// Update file hash (an xxh3 hash, see `set_file_hash_with`)
     pub fn set_file_hash(&self, id: i64, hash: &str) -> Result<()> {
-        self.connection()
-            .execute("UPDATE files SET hash = ?1 WHERE id = ?2", params![hash, id])?;
-        Ok(())
+        self.set_file_hash_with(id, hash, HashAlgorithm::Xxh3)
     }
 
-    /// Update file rating
-    pub fn set_file_rating(&self, id: i64, rating: Option<i32>) -> Result<()> {
+    /// Update file hash, recording the algorithm that produced it
+    pub fn set_file_hash_with(&self, id: i64, hash: &str, algorithm: HashAlgorithm) -> Result<()> {
         self.connection().execute(
-            "UPDATE files SET rating = ?1 WHERE id = ?2",
-            params![rating, id],
+            "UPDATE files SET hash = ?1, hash_algorithm = ?2 WHERE id = ?3",
+            params![hash, algorithm.name(), id],
         )?;
         Ok(())
     }
 
+    /// Update file rating, for the file and any paired siblings
+    pub fn set_file_rating(&self, id: i64, rating: Option<f64>) -> Result<()> {
+        for member in self.get_pair_group(id)? {
+            self.connection().execute(
+                "UPDATE files SET rating = ?1 WHERE id = ?2",
+                params![rating, member],
+            )?;
+        }
+        Ok(())
+    }
+
     /// Update file mtime and size (for sync)
     pub fn update_file_metadata(&self, id: i64, size: i64, mtime: i64) -> Result<()> {
         self.connection().execute(
-            "UPDATE files SET size = ?1, mtime = ?2, hash = NULL WHERE id = ?3",
+            "UPDATE files SET size = ?1, mtime = ?2, hash = NULL, hash_algorithm = NULL, quick_hash = NULL WHERE id = ?3",
             params![size, mtime, id],
         )?;
         Ok(())
     }
 
+    /// Update mtime alone; the content (and so the hash) is unchanged
+    pub fn set_file_mtime(&self, id: i64, mtime: i64) -> Result<()> {
+        self.connection()
+            .execute("UPDATE files SET mtime = ?1 WHERE id = ?2", params![mtime, id])?;
+        Ok(())
+    }
+
+    /// Record a new name for a file in the same directory
+    pub fn rename_file(&self, id: i64, filename: &str) -> Result<()> {
+        self.connection()
+            .execute("UPDATE files SET filename = ?1 WHERE id = ?2", params![filename, id])?;
+        Ok(())
+    }
+
+    /// Record that a file now lives in another directory, under `filename`.
+    /// Its pairs are dropped; the directories' pairs are rebuilt after.
+    pub fn move_file(&self, id: i64, directory_id: i64, filename: &str) -> Result<()> {
+        self.connection()
+            .execute("DELETE FROM file_pairs WHERE file_id = ?1 OR primary_id = ?1", [id])?;
+        self.connection().execute(
+            "UPDATE files SET directory_id = ?1, filename = ?2 WHERE id = ?3",
+            params![directory_id, filename, id],
+        )?;
+        Ok(())
+    }
+
+    /// Algorithm a file's stored hash was made with, if it has one
+    pub fn get_file_hash_algorithm(&self, id: i64) -> Result<Option<HashAlgorithm>> {
+        let algorithm: Option<Option<String>> = self
+            .connection()
+            .query_row(
+                "SELECT hash_algorithm FROM files WHERE id = ?1 AND hash IS NOT NULL",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        // Hashes from before the column existed are xxHash3
+        Ok(algorithm.map(|name| name.and_then(|n| n.parse().ok()).unwrap_or(HashAlgorithm::Xxh3)))
+    }
+
     /// Delete a file by ID
     pub fn delete_file(&self, id: i64) -> Result<()> {
+        // Pairs reference the file, so they go first
+        self.connection()
+            .execute("DELETE FROM file_pairs WHERE file_id = ?1 OR primary_id = ?1", [id])?;
         self.connection()
             .execute("DELETE FROM files WHERE id = ?1", [id])?;
+        // thumbnail_failures and duplicate_ignores have no foreign key, and
+        // review queues shouldn't keep the ID for a new file, so clean them
+        // up by hand
+        self.connection()
+            .execute("DELETE FROM thumbnail_failures WHERE file_id = ?1", [id])?;
+        self.connection()
+            .execute("DELETE FROM duplicate_ignores WHERE file_a = ?1 OR file_b = ?1", [id])?;
+        self.connection()
+            .execute("DELETE FROM review_queue_files WHERE file_id = ?1", [id])?;
         Ok(())
     }
 
@@ -173,7 +281,7 @@ impl Database {
         let mut duplicates = Vec::new();
         for hash in hashes {
             let mut stmt = self.connection().prepare(
-                "SELECT id, directory_id, filename, size, mtime, hash, rating, media_type, width, height, perceptual_hash
+                "SELECT id, directory_id, filename, size, mtime, hash, rating, media_type, width, height, perceptual_hash, camera, lens
                  FROM files WHERE hash = ?1",
             )?;
 
@@ -189,29 +297,30 @@ impl Database {
 
     /// Get a file by its relative path (e.g., "photos/vacation/beach.jpg")
     pub fn get_file_by_path(&self, relative_path: &str) -> Result<Option<File>> {
-        let path = std::path::Path::new(relative_path);
-        let filename = match path.file_name() {
-            Some(name) => name.to_string_lossy().to_string(),
-            None => return Ok(None),
-        };
-        let dir_path = path
-            .parent()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_default();
+        let path = LibraryPath::new(relative_path);
+        let (dir_path, filename) = path.split();
+        if filename.is_empty() {
+            return Ok(None);
+        }
 
-        let dir = self.get_directory_by_path(&dir_path)?;
+        let dir = self.get_directory_by_path(dir_path)?;
         let dir = match dir {
             Some(d) => d,
             None => return Ok(None),
         };
 
-        self.get_file_by_name(dir.id, &filename)
+        for spelling in spellings(filename) {
+            if let Some(file) = self.get_file_by_name(dir.id, &spelling)? {
+                return Ok(Some(file));
+            }
+        }
+        Ok(None)
     }
 
     /// Get all files with their directory paths
     pub fn get_all_files_with_paths(&self) -> Result<Vec<(File, String)>> {
         let mut stmt = self.connection().prepare(
-            "SELECT f.id, f.directory_id, f.filename, f.size, f.mtime, f.hash, f.rating, f.media_type, f.width, f.height, f.perceptual_hash, d.path
+            "SELECT f.id, f.directory_id, f.filename, f.size, f.mtime, f.hash, f.rating, f.media_type, f.width, f.height, f.perceptual_hash, f.camera, f.lens, d.path
              FROM files f
              JOIN directories d ON f.directory_id = d.id
              ORDER BY d.path, f.filename",
@@ -219,7 +328,7 @@ impl Database {
 
         let rows = stmt.query_map([], |row| {
             let file = file_from_row(row)?;
-            let dir_path: String = row.get(11)?;
+            let dir_path: String = row.get(13)?;
             Ok((file, dir_path))
         })?;
 
@@ -227,9 +336,9 @@ impl Database {
     }
 
     /// Get files filtered by minimum rating
-    pub fn get_files_by_rating(&self, min_rating: i32) -> Result<Vec<(File, String)>> {
+    pub fn get_files_by_rating(&self, min_rating: f64) -> Result<Vec<(File, String)>> {
         let mut stmt = self.connection().prepare(
-            "SELECT f.id, f.directory_id, f.filename, f.size, f.mtime, f.hash, f.rating, f.media_type, f.width, f.height, f.perceptual_hash, d.path
+            "SELECT f.id, f.directory_id, f.filename, f.size, f.mtime, f.hash, f.rating, f.media_type, f.width, f.height, f.perceptual_hash, f.camera, f.lens, d.path
              FROM files f
              JOIN directories d ON f.directory_id = d.id
              WHERE f.rating >= ?1
@@ -238,17 +347,17 @@ impl Database {
 
         let rows = stmt.query_map([min_rating], |row| {
             let file = file_from_row(row)?;
-            let dir_path: String = row.get(11)?;
+            let dir_path: String = row.get(13)?;
             Ok((file, dir_path))
         })?;
 
         rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
     }
 
-    /// Get files that have a specific tag
+    /// Get files that have a specific tag (or the tag an alias stands for)
     pub fn get_files_by_tag(&self, tag: &str) -> Result<Vec<(File, String)>> {
         let mut stmt = self.connection().prepare(
-            "SELECT f.id, f.directory_id, f.filename, f.size, f.mtime, f.hash, f.rating, f.media_type, f.width, f.height, f.perceptual_hash, d.path
+            "SELECT f.id, f.directory_id, f.filename, f.size, f.mtime, f.hash, f.rating, f.media_type, f.width, f.height, f.perceptual_hash, f.camera, f.lens, d.path
              FROM files f
              JOIN directories d ON f.directory_id = d.id
              JOIN file_tags ft ON f.id = ft.file_id
@@ -257,9 +366,9 @@ impl Database {
              ORDER BY d.path, f.filename",
         )?;
 
-        let rows = stmt.query_map([tag], |row| {
+        let rows = stmt.query_map([self.canonical_tag(tag)?], |row| {
             let file = file_from_row(row)?;
-            let dir_path: String = row.get(11)?;
+            let dir_path: String = row.get(13)?;
             Ok((file, dir_path))
         })?;
 
@@ -269,7 +378,7 @@ impl Database {
     /// Get image files that don't have landscape/portrait tags yet
     pub fn get_files_needing_orientation(&self) -> Result<Vec<FileToHash>> {
         let mut stmt = self.connection().prepare(
-            "SELECT f.id, d.path, f.filename
+            "SELECT f.id, d.path, f.filename, f.size
              FROM files f
              JOIN directories d ON f.directory_id = d.id
              WHERE f.media_type = 'image'
@@ -286,14 +395,11 @@ impl Database {
                 let id: i64 = row.get(0)?;
                 let dir_path: String = row.get(1)?;
                 let filename: String = row.get(2)?;
+                let size: i64 = row.get(3)?;
 
-                let path = if dir_path.is_empty() {
-                    PathBuf::from(&filename)
-                } else {
-                    PathBuf::from(&dir_path).join(&filename)
-                };
+                let path = LibraryPath::file(&dir_path, &filename).into();
 
-                Ok(FileToHash { id, path })
+                Ok(FileToHash { id, path, size })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
@@ -304,7 +410,7 @@ impl Database {
     /// Get image files that have NULL width (need dimension reading)
     pub fn get_files_needing_dimensions(&self) -> Result<Vec<FileToHash>> {
         let mut stmt = self.connection().prepare(
-            "SELECT f.id, d.path, f.filename
+            "SELECT f.id, d.path, f.filename, f.size
              FROM files f
              JOIN directories d ON f.directory_id = d.id
              WHERE f.media_type = 'image' AND f.width IS NULL
@@ -316,42 +422,96 @@ impl Database {
                 let id: i64 = row.get(0)?;
                 let dir_path: String = row.get(1)?;
                 let filename: String = row.get(2)?;
+                let size: i64 = row.get(3)?;
 
-                let path = if dir_path.is_empty() {
-                    PathBuf::from(filename)
-                } else {
-                    PathBuf::from(format!("{}/{}", dir_path, filename))
-                };
+                let path = LibraryPath::file(&dir_path, &filename).into();
 
-                Ok(FileToHash { id, path })
+                Ok(FileToHash { id, path, size })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(files)
     }
 
-    pub fn get_files_needing_hash(&self) -> Result<Vec<FileToHash>> {
+    pub fn get_files_needing_hash(&self, algorithm: HashAlgorithm) -> Result<Vec<FileToHash>> {
+        self.files_to_hash("f.hash IS NULL OR f.hash_algorithm IS NOT ?1", [algorithm.name()])
+    }
+
+    /// Files needing a full hash whose quick hash is missing or shared with
+    /// another file: a unique quick hash already rules out duplicates
+    pub fn get_files_needing_hash_prefiltered(&self, algorithm: HashAlgorithm) -> Result<Vec<FileToHash>> {
+        self.files_to_hash(
+            "(f.hash IS NULL OR f.hash_algorithm IS NOT ?1)
+             AND (f.quick_hash IS NULL OR f.quick_hash IN (
+                 SELECT quick_hash FROM files WHERE quick_hash IS NOT NULL
+                 GROUP BY quick_hash HAVING COUNT(*) > 1))",
+            [algorithm.name()],
+        )
+    }
+
+    /// Files with a full hash, with the algorithm that made it. Originals
+    /// archived and stubbed locally are left out: their content is elsewhere.
+    pub fn get_hashed_files(&self) -> Result<Vec<HashedFile>> {
         let mut stmt = self.connection().prepare(
-            "SELECT f.id, d.path, f.filename
+            "SELECT f.id, d.path, f.filename, f.size, f.hash, f.hash_algorithm
              FROM files f
              JOIN directories d ON f.directory_id = d.id
-             WHERE f.hash IS NULL
+             WHERE f.hash IS NOT NULL
+               AND f.id NOT IN (SELECT file_id FROM archived_files WHERE stubbed)
              ORDER BY d.path, f.filename",
         )?;
 
-        let files: Vec<FileToHash> = stmt
+        let files = stmt
             .query_map([], |row| {
+                let dir_path: String = row.get(1)?;
+                let filename: String = row.get(2)?;
+                let algorithm: Option<String> = row.get(5)?;
+                Ok(HashedFile {
+                    id: row.get(0)?,
+                    path: LibraryPath::file(&dir_path, &filename).into(),
+                    size: row.get(3)?,
+                    hash: row.get(4)?,
+                    // Hashes from before the column existed are xxHash3
+                    algorithm: algorithm.and_then(|n| n.parse().ok()).unwrap_or(HashAlgorithm::Xxh3),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(files)
+    }
+
+    /// Files without a quick hash
+    pub fn get_files_needing_quick_hash(&self) -> Result<Vec<FileToHash>> {
+        self.files_to_hash("f.quick_hash IS NULL", [])
+    }
+
+    /// Record a file's quick hash (see [`crate::hash::compute_quick_hash`])
+    pub fn set_file_quick_hash(&self, id: i64, quick_hash: &str) -> Result<()> {
+        self.connection()
+            .execute("UPDATE files SET quick_hash = ?1 WHERE id = ?2", params![quick_hash, id])?;
+        Ok(())
+    }
+
+    fn files_to_hash(&self, condition: &str, params: impl rusqlite::Params) -> Result<Vec<FileToHash>> {
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT f.id, d.path, f.filename, f.size
+             FROM files f
+             JOIN directories d ON f.directory_id = d.id
+             WHERE {}
+             ORDER BY d.path, f.filename",
+            condition
+        ))?;
+
+        let files: Vec<FileToHash> = stmt
+            .query_map(params, |row| {
                 let id: i64 = row.get(0)?;
                 let dir_path: String = row.get(1)?;
                 let filename: String = row.get(2)?;
+                let size: i64 = row.get(3)?;
 
-                let path = if dir_path.is_empty() {
-                    PathBuf::from(&filename)
-                } else {
-                    PathBuf::from(&dir_path).join(&filename)
-                };
+                let path = LibraryPath::file(&dir_path, &filename).into();
 
-                Ok(FileToHash { id, path })
+                Ok(FileToHash { id, path, size })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
@@ -370,7 +530,7 @@ impl Database {
     /// Get image files that need perceptual hash computation (where perceptual_hash IS NULL)
     pub fn get_files_needing_perceptual_hash(&self) -> Result<Vec<FileToHash>> {
         let mut stmt = self.connection().prepare(
-            "SELECT f.id, d.path, f.filename
+            "SELECT f.id, d.path, f.filename, f.size
              FROM files f
              JOIN directories d ON f.directory_id = d.id
              WHERE f.media_type = 'image' AND f.perceptual_hash IS NULL
@@ -382,14 +542,11 @@ impl Database {
                 let id: i64 = row.get(0)?;
                 let dir_path: String = row.get(1)?;
                 let filename: String = row.get(2)?;
+                let size: i64 = row.get(3)?;
 
-                let path = if dir_path.is_empty() {
-                    PathBuf::from(&filename)
-                } else {
-                    PathBuf::from(&dir_path).join(&filename)
-                };
+                let path = LibraryPath::file(&dir_path, &filename).into();
 
-                Ok(FileToHash { id, path })
+                Ok(FileToHash { id, path, size })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
@@ -413,36 +570,61 @@ impl Database {
         Ok(rows)
     }
 
-    /// Find exact duplicate files (same xxHash) with directory paths.
+    /// Perceptual hashes of the files in `scope`, as (file_id, hash) pairs
+    pub fn get_perceptual_hashes_in(&self, scope: &DuplicateScope) -> Result<Vec<(i64, i64)>> {
+        let (conditions, values) = scope.sql(self)?;
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT f.id, f.perceptual_hash FROM files f WHERE f.perceptual_hash IS NOT NULL{}",
+            conditions
+        ))?;
+        let rows = stmt
+            .query_map(params_from_iter(values), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Find exact duplicate files (same content hash) with directory paths.
+    /// Hashes from different algorithms are never compared.
     pub fn find_duplicates_with_paths(&self) -> Result<Vec<DuplicateGroup>> {
-        let mut hash_stmt = self.connection().prepare(
-            "SELECT hash FROM files WHERE hash IS NOT NULL
-             GROUP BY hash HAVING COUNT(*) > 1",
-        )?;
+        self.find_duplicates_in(&DuplicateScope::default())
+    }
 
-        let hashes: Vec<String> = hash_stmt
-            .query_map([], |row| row.get(0))?
+    /// [`Database::find_duplicates_with_paths`] among the files in `scope`
+    /// only: files outside it are left out before grouping, so every copy in
+    /// a group matches
+    pub fn find_duplicates_in(&self, scope: &DuplicateScope) -> Result<Vec<DuplicateGroup>> {
+        let (conditions, values) = scope.sql(self)?;
+        let mut hash_stmt = self.connection().prepare(&format!(
+            "SELECT f.hash_algorithm, f.hash FROM files f WHERE f.hash IS NOT NULL{}
+             GROUP BY f.hash_algorithm, f.hash HAVING COUNT(*) > 1",
+            conditions
+        ))?;
+
+        let hashes: Vec<(Option<String>, String)> = hash_stmt
+            .query_map(params_from_iter(values.iter()), |row| Ok((row.get(0)?, row.get(1)?)))?
             .collect::<Result<Vec<_>, _>>()?;
 
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT f.id, f.directory_id, f.filename, f.size, f.mtime, f.hash, f.rating, f.media_type, f.width, f.height, f.perceptual_hash, f.camera, f.lens, d.path
+             FROM files f
+             JOIN directories d ON f.directory_id = d.id
+             WHERE f.hash = ?1 AND f.hash_algorithm IS ?2{}
+             ORDER BY d.path, f.filename",
+            conditions
+        ))?;
         let mut groups = Vec::new();
-        for hash in hashes {
-            let mut stmt = self.connection().prepare(
-                "SELECT f.id, f.directory_id, f.filename, f.size, f.mtime, f.hash, f.rating, f.media_type, f.width, f.height, f.perceptual_hash, d.path
-                 FROM files f
-                 JOIN directories d ON f.directory_id = d.id
-                 WHERE f.hash = ?1
-                 ORDER BY d.path, f.filename",
-            )?;
-
+        for (algorithm, hash) in hashes {
+            let group_params = [Value::Text(hash.clone()), algorithm.clone().map_or(Value::Null, Value::Text)];
             let files: Vec<(File, String)> = stmt
-                .query_map([&hash], |row| {
+                .query_map(params_from_iter(group_params.iter().chain(&values)), |row| {
                     let file = file_from_row(row)?;
-                    let dir_path: String = row.get(11)?;
+                    let dir_path: String = row.get(13)?;
                     Ok((file, dir_path))
                 })?
                 .collect::<Result<Vec<_>, _>>()?;
 
-            groups.push(DuplicateGroup { hash, files });
+            let algorithm = algorithm.unwrap_or_else(|| HashAlgorithm::Xxh3.name().to_string());
+            groups.push(DuplicateGroup { hash, algorithm, files });
         }
 
         Ok(groups)
@@ -453,14 +635,14 @@ impl Database {
         let result = self
             .connection()
             .query_row(
-                "SELECT f.id, f.directory_id, f.filename, f.size, f.mtime, f.hash, f.rating, f.media_type, f.width, f.height, f.perceptual_hash, d.path
+                "SELECT f.id, f.directory_id, f.filename, f.size, f.mtime, f.hash, f.rating, f.media_type, f.width, f.height, f.perceptual_hash, f.camera, f.lens, d.path
                  FROM files f
                  JOIN directories d ON f.directory_id = d.id
                  WHERE f.id = ?1",
                 params![file_id],
                 |row| {
                     let file = file_from_row(row)?;
-                    let dir_path: String = row.get(11)?;
+                    let dir_path: String = row.get(13)?;
                     Ok((file, dir_path))
                 },
             )
@@ -469,13 +651,54 @@ impl Database {
     }
 }
 
-/// A group of exact duplicate files (same xxHash)
+/// A group of exact duplicate files (same content hash)
 #[derive(Debug, Clone)]
 pub struct DuplicateGroup {
     pub hash: String,
+    /// Name of the algorithm that produced `hash`
+    pub algorithm: String,
     pub files: Vec<(File, String)>, // (file, directory_path)
 }
 
+/// Which files a duplicate search looks at, e.g. only those tagged
+/// `auto:screenshot` or only unrated ones. The default is every file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DuplicateScope {
+    /// Only files with this tag (or the tag it's an alias of)
+    pub tag: Option<String>,
+    /// Only files rated at least this
+    pub min_rating: Option<f64>,
+    /// Only unrated files
+    pub unrated: bool,
+}
+
+impl DuplicateScope {
+    pub fn is_active(&self) -> bool {
+        self.tag.is_some() || self.min_rating.is_some() || self.unrated
+    }
+
+    /// `AND ...` conditions on `files f` for the scope, with their values
+    /// bound as `?` after any numbered parameters
+    fn sql(&self, db: &Database) -> Result<(String, Vec<Value>)> {
+        let mut conditions = String::new();
+        let mut values = Vec::new();
+        if let Some(tag) = &self.tag {
+            conditions.push_str(
+                " AND EXISTS (SELECT 1 FROM file_tags ft JOIN tags t ON ft.tag_id = t.id WHERE ft.file_id = f.id AND t.name = ?)",
+            );
+            values.push(Value::Text(db.canonical_tag(&tag.trim().to_lowercase())?));
+        }
+        if let Some(rating) = self.min_rating {
+            conditions.push_str(" AND f.rating >= ?");
+            values.push(Value::Real(rating));
+        }
+        if self.unrated {
+            conditions.push_str(" AND f.rating IS NULL");
+        }
+        Ok((conditions, values))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -502,15 +725,35 @@ mod tests {
         let updated = db.get_file_by_name(dir_id, "photo.jpg").unwrap().unwrap();
         assert_eq!(updated.hash, Some("abc123".to_string()));
 
-        db.set_file_rating(file_id, Some(5)).unwrap();
+        db.set_file_rating(file_id, Some(5.0)).unwrap();
         let updated = db.get_file_by_name(dir_id, "photo.jpg").unwrap().unwrap();
-        assert_eq!(updated.rating, Some(5));
+        assert_eq!(updated.rating, Some(5.0));
 
         db.delete_file(file_id).unwrap();
         let files = db.get_files_in_directory(dir_id).unwrap();
         assert!(files.is_empty());
     }
 
+    #[test]
+    fn test_get_files_in_branch() {
+        let db = Database::open_in_memory().unwrap();
+        let root = db.insert_directory("", None, None).unwrap();
+        let trip = db.insert_directory("trip", None, None).unwrap();
+        let day = db.insert_directory("trip/day1", Some(trip), None).unwrap();
+        let other = db.insert_directory("trips", None, None).unwrap();
+        db.insert_file(root, "top.jpg", 1, 0, Some("image")).unwrap();
+        db.insert_file(day, "b.jpg", 1, 0, Some("image")).unwrap();
+        db.insert_file(trip, "a.jpg", 1, 0, Some("image")).unwrap();
+        db.insert_file(other, "c.jpg", 1, 0, Some("image")).unwrap();
+
+        let names = |path: &str| -> Vec<String> {
+            db.get_files_in_branch(path).unwrap().into_iter().map(|f| f.filename).collect()
+        };
+        assert_eq!(names("trip"), vec!["a.jpg", "b.jpg"]);
+        // The root stands for its own files, not the whole library
+        assert_eq!(names(""), vec!["top.jpg"]);
+    }
+
     #[test]
     fn test_find_duplicates() {
         let db = Database::open_in_memory().unwrap();
@@ -544,6 +787,7 @@ mod tests {
 
         let file = db.get_file_by_path("photos/vacation/beach.jpg").unwrap();
         assert!(file.is_some());
+        assert!(db.get_file_by_path("./photos//vacation/beach.jpg").unwrap().is_some());
 
         assert!(db.get_file_by_path("photos/nonexistent.jpg").unwrap().is_none());
         assert!(db.get_file_by_path("nonexistent/image.jpg").unwrap().is_none());
@@ -578,8 +822,10 @@ mod tests {
 
         db.set_file_hash(file1_id, "abc123").unwrap();
 
-        let files = db.get_files_needing_hash().unwrap();
+        let files = db.get_files_needing_hash(HashAlgorithm::Xxh3).unwrap();
         assert_eq!(files.len(), 2);
+        // Switching algorithm makes already hashed files due again
+        assert_eq!(db.get_files_needing_hash(HashAlgorithm::Sha256).unwrap().len(), 3);
 
         let ids: Vec<i64> = files.iter().map(|f| f.id).collect();
         assert!(ids.contains(&file2_id));
@@ -616,18 +862,18 @@ mod tests {
         let file3_id = db.insert_file(dir_id, "ok.jpg", 1024, 12347, Some("image")).unwrap();
         db.insert_file(dir_id, "unrated.jpg", 1024, 12348, Some("image")).unwrap();
 
-        db.set_file_rating(file1_id, Some(5)).unwrap();
-        db.set_file_rating(file2_id, Some(4)).unwrap();
-        db.set_file_rating(file3_id, Some(3)).unwrap();
+        db.set_file_rating(file1_id, Some(5.0)).unwrap();
+        db.set_file_rating(file2_id, Some(4.0)).unwrap();
+        db.set_file_rating(file3_id, Some(3.0)).unwrap();
 
-        let files = db.get_files_by_rating(4).unwrap();
+        let files = db.get_files_by_rating(4.0).unwrap();
         assert_eq!(files.len(), 2);
 
-        let files = db.get_files_by_rating(5).unwrap();
+        let files = db.get_files_by_rating(5.0).unwrap();
         assert_eq!(files.len(), 1);
         assert_eq!(files[0].0.filename, "great.jpg");
 
-        let files = db.get_files_by_rating(3).unwrap();
+        let files = db.get_files_by_rating(3.0).unwrap();
         assert_eq!(files.len(), 3);
         assert_eq!(files[0].1, "photos");
     }
@@ -773,6 +1019,43 @@ mod tests {
         let paths: Vec<&str> = group.files.iter().map(|(_, p)| p.as_str()).collect();
         assert!(paths.contains(&"photos"));
         assert!(paths.contains(&"backup"));
+
+        // Equal digests from different algorithms are not duplicates
+        db.set_file_hash_with(f2, "samehash", HashAlgorithm::Sha256).unwrap();
+        assert!(db.find_duplicates_with_paths().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_find_duplicates_in_scope() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("photos", None, None).unwrap();
+        let mut ids = Vec::new();
+        for (name, hash) in [("a.png", "one"), ("b.png", "one"), ("c.png", "one"), ("d.jpg", "two"), ("e.jpg", "two")] {
+            let id = db.insert_file(dir, name, 100, 0, Some("image")).unwrap();
+            db.set_file_hash(id, hash).unwrap();
+            db.set_perceptual_hash(id, 42).unwrap();
+            ids.push(id);
+        }
+        db.add_file_tag(ids[0], "screenshot").unwrap();
+        db.add_file_tag(ids[1], "screenshot").unwrap();
+        db.set_file_rating(ids[3], Some(4.0)).unwrap();
+
+        let scope = DuplicateScope { tag: Some("Screenshot".into()), ..Default::default() };
+        let groups = db.find_duplicates_in(&scope).unwrap();
+        assert_eq!(groups.len(), 1);
+        let names: Vec<&str> = groups[0].files.iter().map(|(f, _)| f.filename.as_str()).collect();
+        assert_eq!(names, ["a.png", "b.png"]);
+        assert_eq!(db.get_perceptual_hashes_in(&scope).unwrap().len(), 2);
+
+        // One of the "two" copies is rated, so only "one" is left among unrated files
+        let scope = DuplicateScope { unrated: true, ..Default::default() };
+        let groups = db.find_duplicates_in(&scope).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].hash, "one");
+
+        let scope = DuplicateScope { min_rating: Some(3.0), ..Default::default() };
+        assert!(db.find_duplicates_in(&scope).unwrap().is_empty());
+        assert_eq!(db.find_duplicates_in(&DuplicateScope::default()).unwrap().len(), 2);
     }
 
     #[test]
@@ -790,4 +1073,19 @@ mod tests {
         // Non-existent file
         assert!(db.get_file_with_path(9999).unwrap().is_none());
     }
+
+    #[test]
+    fn test_get_file_by_path_in_either_normalization_form() {
+        let db = Database::open_in_memory().unwrap();
+        // Recorded decomposed, as a Linux copy of a macOS library is
+        let dir_id = db.insert_directory("cafe\u{301}", None, None).unwrap();
+        let file_id = db.insert_file(dir_id, "e\u{301}te\u{301}.jpg", 1, 0, Some("image")).unwrap();
+
+        let file = db.get_file_by_path("caf\u{E9}/\u{E9}t\u{E9}.jpg").unwrap().unwrap();
+        assert_eq!(file.id, file_id);
+        assert!(db.get_file_by_path("cafe/ete.jpg").unwrap().is_none());
+
+        db.rename_file(file_id, "\u{E9}t\u{E9}.jpg").unwrap();
+        assert_eq!(db.get_file_by_name(dir_id, "\u{E9}t\u{E9}.jpg").unwrap().unwrap().id, file_id);
+    }
 }
@@ -4,23 +4,33 @@ use anyhow::Result;
 use tracing::{debug, instrument};
 
 use super::Database;
-use crate::tui::dialogs::RatingFilter;
+use crate::tui::dialogs::{MediaFilter, Orientation, RatingFilter};
 
 impl Database {
     /// Get IDs of directories containing files that match the filter criteria,
     /// OR directories that themselves have matching tags.
     /// Also includes ancestor directories to maintain tree structure.
     /// For multiple tags, uses AND logic (must have ALL tags).
+    /// `camera` and `lens` are exact names, matched on files only, as is
+    /// `orientation`, which comes from each file's dimensions.
     #[instrument(skip(self))]
     pub fn get_directories_with_matching_files(
         &self,
         rating_filter: RatingFilter,
         tags: &[String],
-        video_only: bool,
+        media: MediaFilter,
+        camera: Option<&str>,
+        lens: Option<&str>,
+        orientation: Option<Orientation>,
     ) -> Result<HashSet<i64>> {
+        let tags = &self.canonical_tags(tags)?[..];
         let mut matching_dir_ids: HashSet<i64> = HashSet::new();
+        // Directories have no media type, gear or shape, so these rule out
+        // matching on directory ratings and tags
+        let file_only =
+            media != MediaFilter::Any || camera.is_some() || lens.is_some() || orientation.is_some();
 
-        if rating_filter == RatingFilter::Any && tags.is_empty() && !video_only {
+        if rating_filter == RatingFilter::Any && tags.is_empty() && !file_only {
             // No filter - return empty set (caller should show all)
             return Ok(matching_dir_ids);
         }
@@ -28,8 +38,12 @@ impl Database {
         // === Part 1: Find directories with matching FILES ===
         let mut file_conditions = Vec::new();
 
-        if video_only {
-            file_conditions.push("f.media_type = 'video'".to_string());
+        if let Some(media_type) = media.media_type() {
+            file_conditions.push(format!("f.media_type = '{}'", media_type));
+        }
+
+        if let Some(orientation) = orientation {
+            file_conditions.push(orientation.sql_condition().to_string());
         }
 
         let min_rating = match rating_filter {
@@ -57,6 +71,20 @@ impl Database {
             ));
         }
 
+        // Gear names are bound after the rating and tag parameters
+        let mut next_param = 1 + usize::from(min_rating.is_some());
+        if !tags.is_empty() {
+            next_param += tags.len() + 1;
+        }
+        let gear: Vec<(&str, &str)> = [("camera", camera), ("lens", lens)]
+            .into_iter()
+            .filter_map(|(column, value)| Some((column, value?)))
+            .collect();
+        for (column, _) in &gear {
+            file_conditions.push(format!("f.{} = ?{}", column, next_param));
+            next_param += 1;
+        }
+
         // Only query files if we have conditions
         if !file_conditions.is_empty() {
             let query = format!(
@@ -79,6 +107,8 @@ impl Database {
                 params.extend(tags.iter().map(|t| t.clone().into()));
             }
 
+            params.extend(gear.iter().map(|(_, v)| v.to_string().into()));
+
             let dir_ids: Vec<i64> = stmt
                 .query_map(rusqlite::params_from_iter(params), |row| row.get(0))?
                 .collect::<Result<Vec<_>, _>>()?;
@@ -97,13 +127,13 @@ impl Database {
             .collect();
 
         // Fetch all directory tags in one query (instead of N queries)
-        let all_dir_tags = if !tags.is_empty() && !video_only {
+        let all_dir_tags = if !tags.is_empty() && !file_only {
             self.get_all_directory_tags()?
         } else {
             HashMap::new()
         };
 
-        if !video_only {
+        if !file_only {
             for dir in &all_dirs {
                 // Check rating filter on directory
                 let dir_matches_rating = match rating_filter {
@@ -187,27 +217,35 @@ mod tests {
         let file2_id = db.insert_file(vacation_id, "beach.jpg", 1024, 12346, Some("image")).unwrap();
         let file3_id = db.insert_file(work_id, "doc.jpg", 1024, 12347, Some("image")).unwrap();
 
-        db.set_file_rating(file1_id, Some(3)).unwrap();
-        db.set_file_rating(file2_id, Some(5)).unwrap();
-        db.set_file_rating(file3_id, Some(2)).unwrap();
+        db.set_file_rating(file1_id, Some(3.0)).unwrap();
+        db.set_file_rating(file2_id, Some(5.0)).unwrap();
+        db.set_file_rating(file3_id, Some(2.0)).unwrap();
 
         db.add_file_tag(file1_id, "family").unwrap();
         db.add_file_tag(file2_id, "family").unwrap();
         db.add_file_tag(file2_id, "vacation").unwrap();
 
         // No filter returns empty set
-        let result = db.get_directories_with_matching_files(RatingFilter::Any, &[], false).unwrap();
+        let result = db.get_directories_with_matching_files(RatingFilter::Any, &[], MediaFilter::Any, None, None, None).unwrap();
         assert!(result.is_empty());
 
         // Rating filter only
-        let result = db.get_directories_with_matching_files(RatingFilter::MinRating(4), &[], false).unwrap();
+        let result = db.get_directories_with_matching_files(RatingFilter::MinRating(4.0), &[], MediaFilter::Any, None, None, None).unwrap();
         assert!(result.contains(&vacation_id));
         assert!(result.contains(&photos_id));
         assert!(result.contains(&root_id));
         assert!(!result.contains(&work_id));
 
+        // Half-star minimums compare as reals
+        db.set_file_rating(file3_id, Some(2.5)).unwrap();
+        let result = db.get_directories_with_matching_files(RatingFilter::MinRating(2.5), &[], MediaFilter::Any, None, None, None).unwrap();
+        assert!(result.contains(&work_id));
+        let result = db.get_directories_with_matching_files(RatingFilter::MinRating(3.5), &[], MediaFilter::Any, None, None, None).unwrap();
+        assert!(!result.contains(&work_id));
+        assert!(result.contains(&vacation_id));
+
         // Tag filter (single)
-        let result = db.get_directories_with_matching_files(RatingFilter::Any, &["family".to_string()], false).unwrap();
+        let result = db.get_directories_with_matching_files(RatingFilter::Any, &["family".to_string()], MediaFilter::Any, None, None, None).unwrap();
         assert!(result.contains(&photos_id));
         assert!(result.contains(&vacation_id));
         assert!(!result.contains(&work_id));
@@ -216,15 +254,21 @@ mod tests {
         let result = db.get_directories_with_matching_files(
             RatingFilter::Any,
             &["family".to_string(), "vacation".to_string()],
-            false,
+            MediaFilter::Any,
+            None,
+            None,
+            None,
         ).unwrap();
         assert!(result.contains(&vacation_id));
 
         // Combined rating and tag
         let result = db.get_directories_with_matching_files(
-            RatingFilter::MinRating(4),
+            RatingFilter::MinRating(4.0),
             &["family".to_string()],
-            false,
+            MediaFilter::Any,
+            None,
+            None,
+            None,
         ).unwrap();
         assert!(result.contains(&vacation_id));
         assert!(result.contains(&photos_id));
@@ -246,7 +290,7 @@ mod tests {
         db.add_directory_tag(vacation_id, "travel").unwrap();
 
         let result = db.get_directories_with_matching_files(
-            RatingFilter::Any, &["travel".to_string()], false,
+            RatingFilter::Any, &["travel".to_string()], MediaFilter::Any, None, None, None,
         ).unwrap();
 
         assert!(result.contains(&vacation_id));
@@ -266,10 +310,10 @@ mod tests {
         db.insert_file(vacation_id, "trip.jpg", 1024, 12346, Some("image")).unwrap();
         db.insert_file(beach_id, "sunset.jpg", 1024, 12347, Some("image")).unwrap();
 
-        db.set_directory_rating(vacation_id, Some(5)).unwrap();
+        db.set_directory_rating(vacation_id, Some(5.0)).unwrap();
 
         let result = db.get_directories_with_matching_files(
-            RatingFilter::MinRating(4), &[], false,
+            RatingFilter::MinRating(4.0), &[], MediaFilter::Any, None, None, None,
         ).unwrap();
 
         assert!(result.contains(&vacation_id));
@@ -290,9 +334,91 @@ mod tests {
         db.add_file_tag(file1_id, "favorite").unwrap();
 
         let result = db.get_directories_with_matching_files(
-            RatingFilter::Any, &["favorite".to_string()], false,
+            RatingFilter::Any, &["favorite".to_string()], MediaFilter::Any, None, None, None,
         ).unwrap();
 
         assert!(result.contains(&photos_id));
     }
+
+    #[test]
+    fn test_camera_and_lens_filters_match_files_only() {
+        let db = Database::open_in_memory().unwrap();
+
+        let root_id = db.insert_directory("", None, None).unwrap();
+        let fuji_id = db.insert_directory("fuji", Some(root_id), None).unwrap();
+        let phone_id = db.insert_directory("phone", Some(root_id), None).unwrap();
+
+        let fuji = db.insert_file(fuji_id, "a.jpg", 1024, 0, Some("image")).unwrap();
+        let phone = db.insert_file(phone_id, "b.jpg", 1024, 0, Some("image")).unwrap();
+        db.set_file_gear(fuji, Some("FUJIFILM X-T3"), Some("XF35mmF1.4 R")).unwrap();
+        db.set_file_gear(phone, Some("iPhone 13"), None).unwrap();
+        db.set_file_rating(phone, Some(5.0)).unwrap();
+        // A highly rated directory doesn't stand in for the camera
+        db.set_directory_rating(phone_id, Some(5.0)).unwrap();
+
+        let result = db.get_directories_with_matching_files(
+            RatingFilter::Any, &[], MediaFilter::Any, Some("FUJIFILM X-T3"), None, None,
+        ).unwrap();
+        assert!(result.contains(&fuji_id));
+        assert!(result.contains(&root_id));
+        assert!(!result.contains(&phone_id));
+
+        let result = db.get_directories_with_matching_files(
+            RatingFilter::MinRating(4.0), &[], MediaFilter::Any, Some("FUJIFILM X-T3"), None, None,
+        ).unwrap();
+        assert!(!result.contains(&fuji_id));
+        assert!(!result.contains(&phone_id));
+
+        let result = db.get_directories_with_matching_files(
+            RatingFilter::Any, &[], MediaFilter::Any, None, Some("XF35mmF1.4 R"), None,
+        ).unwrap();
+        assert!(result.contains(&fuji_id));
+        assert!(!result.contains(&phone_id));
+    }
+
+    #[test]
+    fn test_orientation_filter_matches_dimensions() {
+        let db = Database::open_in_memory().unwrap();
+
+        let root_id = db.insert_directory("", None, None).unwrap();
+        let wide_id = db.insert_directory("wide", Some(root_id), None).unwrap();
+        let tall_id = db.insert_directory("tall", Some(root_id), None).unwrap();
+        let square_id = db.insert_directory("square", Some(root_id), None).unwrap();
+        let strip_id = db.insert_directory("strip", Some(root_id), None).unwrap();
+
+        db.insert_file_with_dimensions(wide_id, "pano.jpg", 1, 0, Some("image"), Some(9000), Some(3000)).unwrap();
+        db.insert_file_with_dimensions(tall_id, "a.jpg", 1, 0, Some("image"), Some(3000), Some(4000)).unwrap();
+        db.insert_file_with_dimensions(square_id, "b.jpg", 1, 0, Some("image"), Some(1080), Some(1080)).unwrap();
+        db.insert_file_with_dimensions(strip_id, "d.jpg", 1, 0, Some("image"), Some(2000), Some(8000)).unwrap();
+        // Not backfilled yet, so no orientation, whatever it's tagged
+        let unknown = db.insert_file(wide_id, "c.jpg", 1, 0, Some("image")).unwrap();
+        db.add_file_tag(unknown, "portrait").unwrap();
+        // A rated directory doesn't stand in for a shape
+        db.set_directory_rating(wide_id, Some(5.0)).unwrap();
+
+        let matching = |orientation| {
+            db.get_directories_with_matching_files(
+                RatingFilter::Any, &[], MediaFilter::Any, None, None, Some(orientation),
+            ).unwrap()
+        };
+        let result = matching(Orientation::Portrait);
+        assert!(result.contains(&tall_id));
+        assert!(!result.contains(&wide_id));
+        assert!(!result.contains(&square_id));
+
+        let result = matching(Orientation::Panorama);
+        assert!(result.contains(&wide_id));
+        assert!(!result.contains(&tall_id));
+        assert!(!result.contains(&strip_id));
+
+        let result = matching(Orientation::Tall);
+        assert!(result.contains(&strip_id));
+        assert!(!result.contains(&tall_id));
+
+        let result = matching(Orientation::Square);
+        assert!(result.contains(&square_id));
+        assert!(!result.contains(&wide_id));
+
+        assert!(!matching(Orientation::Landscape).contains(&wide_id));
+    }
 }
@@ -0,0 +1,166 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::files::FileToHash;
+use super::Database;
+use crate::library_path::LibraryPath;
+
+/// Which piece of gear to group or filter by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GearField {
+    Camera,
+    Lens,
+}
+
+impl GearField {
+    fn column(self) -> &'static str {
+        match self {
+            GearField::Camera => "camera",
+            GearField::Lens => "lens",
+        }
+    }
+}
+
+impl FromStr for GearField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "camera" => Ok(GearField::Camera),
+            "lens" => Ok(GearField::Lens),
+            _ => Err(format!("expected camera or lens (got '{}')", s)),
+        }
+    }
+}
+
+/// Files shot with one camera or lens
+#[derive(Debug, Clone, PartialEq)]
+pub struct GearStat {
+    /// None for files whose EXIF doesn't name one
+    pub name: Option<String>,
+    pub files: usize,
+    pub rated: usize,
+    pub average_rating: Option<f64>,
+}
+
+impl Database {
+    /// Record the camera and lens read from a file's EXIF
+    pub fn set_file_gear(&self, id: i64, camera: Option<&str>, lens: Option<&str>) -> Result<()> {
+        self.connection().execute(
+            "UPDATE files SET camera = ?1, lens = ?2, gear_checked = 1 WHERE id = ?3",
+            params![camera, lens, id],
+        )?;
+        Ok(())
+    }
+
+    /// Forget a file's gear, exposure settings and capture date (and a video's duration)
+    /// so the next sync reads them again (its content changed). The
+    /// screenshot check, which depends on them, is redone too.
+    pub fn clear_file_gear(&self, id: i64) -> Result<()> {
+        self.connection().execute(
+            "UPDATE files SET camera = NULL, lens = NULL, iso = NULL, aperture = NULL, exposure_time = NULL,
+             taken_at = NULL, gear_checked = 0, screenshot_checked = 0, duration = NULL, duration_checked = 0
+             WHERE id = ?1",
+            [id],
+        )?;
+        Ok(())
+    }
+
+    /// Image files whose EXIF hasn't been read for camera, lens and exposure
+    /// settings yet
+    pub fn get_files_needing_gear(&self) -> Result<Vec<FileToHash>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT f.id, d.path, f.filename, f.size
+             FROM files f
+             JOIN directories d ON f.directory_id = d.id
+             WHERE f.media_type = 'image' AND f.gear_checked = 0
+             ORDER BY d.path, f.filename",
+        )?;
+        let files = stmt
+            .query_map([], |row| {
+                let dir_path: String = row.get(1)?;
+                let filename: String = row.get(2)?;
+                let path = LibraryPath::file(&dir_path, &filename).into();
+                Ok(FileToHash { id: row.get(0)?, path, size: row.get(3)? })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(files)
+    }
+
+    /// Every distinct camera or lens in the library, sorted by name
+    pub fn get_gear_names(&self, field: GearField) -> Result<Vec<String>> {
+        let column = field.column();
+        let names = self
+            .connection()
+            .prepare(&format!(
+                "SELECT DISTINCT {column} FROM files WHERE {column} IS NOT NULL ORDER BY {column} COLLATE NOCASE"
+            ))?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(names)
+    }
+
+    /// File counts and ratings per camera or lens, most used first. Only
+    /// images whose EXIF has been read are counted.
+    pub fn get_gear_stats(&self, field: GearField) -> Result<Vec<GearStat>> {
+        let column = field.column();
+        let stats = self
+            .connection()
+            .prepare(&format!(
+                "SELECT {column}, COUNT(*), COUNT(rating), AVG(rating) FROM files
+                 WHERE media_type = 'image' AND gear_checked = 1
+                 GROUP BY {column}
+                 ORDER BY COUNT(*) DESC, {column} COLLATE NOCASE"
+            ))?
+            .query_map([], |row| {
+                Ok(GearStat {
+                    name: row.get(0)?,
+                    files: row.get::<_, i64>(1)? as usize,
+                    rated: row.get::<_, i64>(2)? as usize,
+                    average_rating: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gear_stats_group_by_camera_and_lens() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let a = db.insert_file(dir, "a.jpg", 1, 0, Some("image")).unwrap();
+        let b = db.insert_file(dir, "b.jpg", 1, 0, Some("image")).unwrap();
+        let c = db.insert_file(dir, "c.png", 1, 0, Some("image")).unwrap();
+        db.insert_file(dir, "clip.mp4", 1, 0, Some("video")).unwrap();
+
+        assert_eq!(db.get_files_needing_gear().unwrap().len(), 3);
+        db.set_file_gear(a, Some("Fujifilm X-T3"), Some("XF35mmF1.4 R")).unwrap();
+        db.set_file_gear(b, Some("Fujifilm X-T3"), Some("XF16mmF2.8 R WR")).unwrap();
+        db.set_file_gear(c, None, None).unwrap();
+        db.set_file_rating(a, Some(4.0)).unwrap();
+        assert!(db.get_files_needing_gear().unwrap().is_empty());
+
+        let cameras = db.get_gear_stats(GearField::Camera).unwrap();
+        assert_eq!(
+            cameras,
+            vec![
+                GearStat { name: Some("Fujifilm X-T3".into()), files: 2, rated: 1, average_rating: Some(4.0) },
+                GearStat { name: None, files: 1, rated: 0, average_rating: None },
+            ]
+        );
+        assert_eq!(db.get_gear_stats(GearField::Lens).unwrap().len(), 3);
+        assert_eq!(db.get_gear_names(GearField::Camera).unwrap(), vec!["Fujifilm X-T3"]);
+
+        // Edited files are read again
+        db.clear_file_gear(a).unwrap();
+        assert_eq!(db.get_files_needing_gear().unwrap().len(), 1);
+        assert_eq!(db.get_file_by_name(dir, "a.jpg").unwrap().unwrap().camera, None);
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::Database;
+
+impl Database {
+    /// Hide a directory and everything below it; hiding it again is a no-op
+    pub fn hide_directory(&self, directory_id: i64, hidden_at: i64) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR IGNORE INTO hidden_directories (directory_id, hidden_at) VALUES (?1, ?2)",
+            params![directory_id, hidden_at],
+        )?;
+        Ok(())
+    }
+
+    /// Show a hidden directory again. Returns false if it wasn't hidden
+    pub fn unhide_directory(&self, directory_id: i64) -> Result<bool> {
+        let deleted = self
+            .connection()
+            .execute("DELETE FROM hidden_directories WHERE directory_id = ?1", [directory_id])?;
+        Ok(deleted > 0)
+    }
+
+    /// IDs of the directories marked hidden (not their subdirectories).
+    /// Directories that sync has since removed are skipped.
+    pub fn get_hidden_directory_ids(&self) -> Result<HashSet<i64>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT h.directory_id FROM hidden_directories h
+             JOIN directories d ON h.directory_id = d.id",
+        )?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<HashSet<_>, _>>()?;
+        Ok(ids)
+    }
+
+    /// IDs of every directory that is hidden or inside a hidden directory
+    pub fn get_hidden_subtree_ids(&self) -> Result<HashSet<i64>> {
+        let mut stmt = self.connection().prepare(
+            "WITH RECURSIVE hidden(id) AS (
+                SELECT d.id FROM hidden_directories h
+                JOIN directories d ON h.directory_id = d.id
+                UNION
+                SELECT d.id FROM directories d
+                JOIN hidden ON d.parent_id = hidden.id
+            )
+            SELECT id FROM hidden",
+        )?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<HashSet<_>, _>>()?;
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hide_and_unhide_directories() {
+        let db = Database::open_in_memory().unwrap();
+        let exports = db.insert_directory("exports", None, None).unwrap();
+        let old = db.insert_directory("exports/2019", Some(exports), None).unwrap();
+        let photos = db.insert_directory("photos", None, None).unwrap();
+
+        db.hide_directory(exports, 100).unwrap();
+        db.hide_directory(exports, 200).unwrap();
+        assert_eq!(db.get_hidden_directory_ids().unwrap(), HashSet::from([exports]));
+        assert_eq!(db.get_hidden_subtree_ids().unwrap(), HashSet::from([exports, old]));
+        assert!(!db.get_hidden_subtree_ids().unwrap().contains(&photos));
+
+        assert!(db.unhide_directory(exports).unwrap());
+        assert!(!db.unhide_directory(exports).unwrap());
+        assert!(db.get_hidden_subtree_ids().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use rusqlite::params;
+
+use super::Database;
+
+/// How many operations the history keeps; older ones are dropped
+const HISTORY_KEPT: i64 = 200;
+
+/// A finished TUI operation (thumbnails, hashing, ...)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationRecord {
+    /// Stable operation name, e.g. "hash"
+    pub operation: String,
+    /// Directory it ran on, relative to the library ("" is the root)
+    pub directory: String,
+    /// Unix timestamp (seconds) when it finished
+    pub finished_at: i64,
+    pub duration_ms: i64,
+    /// Files or directories processed
+    pub items: i64,
+    pub cancelled: bool,
+}
+
+impl Database {
+    /// Add an operation to the history, dropping the oldest beyond the limit
+    pub fn record_operation(&self, record: &OperationRecord) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO operation_history (operation, directory, finished_at, duration_ms, items, cancelled)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                record.operation,
+                record.directory,
+                record.finished_at,
+                record.duration_ms,
+                record.items,
+                record.cancelled
+            ],
+        )?;
+        self.connection().execute(
+            "DELETE FROM operation_history WHERE id <= (SELECT MAX(id) FROM operation_history) - ?1",
+            [HISTORY_KEPT],
+        )?;
+        Ok(())
+    }
+
+    /// The most recent operations, newest first
+    pub fn get_operation_history(&self, limit: usize) -> Result<Vec<OperationRecord>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT operation, directory, finished_at, duration_ms, items, cancelled
+             FROM operation_history ORDER BY id DESC LIMIT ?1",
+        )?;
+
+        let records = stmt
+            .query_map([limit as i64], |row| {
+                Ok(OperationRecord {
+                    operation: row.get(0)?,
+                    directory: row.get(1)?,
+                    finished_at: row.get(2)?,
+                    duration_ms: row.get(3)?,
+                    items: row.get(4)?,
+                    cancelled: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(operation: &str, finished_at: i64) -> OperationRecord {
+        OperationRecord {
+            operation: operation.to_string(),
+            directory: "photos/2024".to_string(),
+            finished_at,
+            duration_ms: 1500,
+            items: 42,
+            cancelled: false,
+        }
+    }
+
+    #[test]
+    fn test_operation_history_newest_first() {
+        let db = Database::open_in_memory().unwrap();
+        assert!(db.get_operation_history(10).unwrap().is_empty());
+
+        db.record_operation(&record("thumbnails", 100)).unwrap();
+        db.record_operation(&record("hash", 200)).unwrap();
+
+        let history = db.get_operation_history(10).unwrap();
+        assert_eq!(history, vec![record("hash", 200), record("thumbnails", 100)]);
+        assert_eq!(db.get_operation_history(1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_operation_history_is_capped() {
+        let db = Database::open_in_memory().unwrap();
+        for i in 0..HISTORY_KEPT + 5 {
+            db.record_operation(&record("hash", i)).unwrap();
+        }
+
+        let history = db.get_operation_history(1000).unwrap();
+        assert_eq!(history.len(), HISTORY_KEPT as usize);
+        assert_eq!(history.last().unwrap().finished_at, 5);
+    }
+}
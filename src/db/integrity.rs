@@ -0,0 +1,315 @@
+//! Open-time health check of the library database, so a damaged file stops
+//! picman with a clear message (or is swapped for the backup) before the TUI
+//! takes over the terminal, instead of failing halfway through a query.
+//!
+//! The check is cheap: the SQLite header, recovery of any unfinished write
+//! (journal or WAL), that the file is as long as its page count says, that
+//! the schema reads, and that its version isn't newer than this build.
+//! `PRAGMA integrity_check` would read every page and isn't run.
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use tracing::warn;
+
+use super::schema::SCHEMA_VERSION;
+use super::Database;
+use crate::process::unix_now;
+
+/// How old the backup gets before the next open replaces it
+const BACKUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long the check waits for another process's write lock
+const PROBE_BUSY_TIMEOUT: Duration = if cfg!(test) {
+    Duration::from_millis(200)
+} else {
+    Duration::from_secs(5)
+};
+
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// The library database can't be read and there was no usable backup
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Database {} is damaged ({reason}). Restore a copy of it, or move it aside \
+     and run 'picman init' to rebuild it (ratings and tags will be lost)",
+    .path.display()
+)]
+pub struct DatabaseDamaged {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// The database was written by a newer picman
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Database {} has schema version {found}, newer than this picman supports ({}). \
+     Upgrade picman to open it",
+    .path.display(),
+    SCHEMA_VERSION
+)]
+pub struct DatabaseTooNew {
+    pub path: PathBuf,
+    pub found: i32,
+}
+
+enum Problem {
+    Damaged(String),
+    TooNew(i32),
+    /// The file couldn't be read right now (locked by another process,
+    /// permissions, ...), which says nothing about its contents
+    Unreadable(anyhow::Error),
+}
+
+fn damaged(e: impl std::fmt::Display) -> Problem {
+    Problem::Damaged(e.to_string())
+}
+
+/// Only SQLite's own verdict that the file is not a database or is corrupt
+/// counts as damage; busy, locked, can't-open and I/O errors are passed on
+fn sqlite_problem(e: rusqlite::Error) -> Problem {
+    match e.sqlite_error_code() {
+        Some(rusqlite::ErrorCode::NotADatabase | rusqlite::ErrorCode::DatabaseCorrupt) => damaged(e),
+        _ => Problem::Unreadable(e.into()),
+    }
+}
+
+/// Where [`Database::open`] keeps the copy it falls back to
+pub fn backup_path(db_path: &Path) -> PathBuf {
+    with_suffix(db_path, ".bak")
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Look a database file over without changing anything but an interrupted
+/// write, which SQLite finishes or rolls back on first read
+fn probe(path: &Path) -> std::result::Result<(), Problem> {
+    let len = match fs::metadata(path) {
+        Ok(meta) => meta.len(),
+        // Created by the open
+        Err(_) => return Ok(()),
+    };
+    if len == 0 {
+        return Ok(());
+    }
+
+    let mut header = [0u8; 16];
+    let unreadable = |e: std::io::Error| {
+        Problem::Unreadable(anyhow::Error::new(e).context(format!("Failed to read {}", path.display())))
+    };
+    let mut file = fs::File::open(path).map_err(unreadable)?;
+    match file.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Err(damaged("not a SQLite database")),
+        Err(e) => return Err(unreadable(e)),
+    }
+    if &header != SQLITE_HEADER {
+        return Err(damaged("not a SQLite database"));
+    }
+
+    let conn = Connection::open(path).map_err(sqlite_problem)?;
+    conn.busy_timeout(PROBE_BUSY_TIMEOUT).map_err(sqlite_problem)?;
+    conn.query_row("PRAGMA journal_mode", [], |row| row.get::<_, String>(0))
+        .map_err(sqlite_problem)?;
+
+    // Pages still in a WAL aren't in the main file yet
+    if !with_suffix(path, "-wal").exists() {
+        let page_size: u64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0)).map_err(sqlite_problem)?;
+        let page_count: u64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0)).map_err(sqlite_problem)?;
+        let expected = page_size * page_count;
+        if len < expected {
+            return Err(Problem::Damaged(format!("truncated: {} of {} bytes", len, expected)));
+        }
+    }
+
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+        .map_err(sqlite_problem)?;
+    let version: i32 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(sqlite_problem)?;
+    if version > SCHEMA_VERSION {
+        return Err(Problem::TooNew(version));
+    }
+    Ok(())
+}
+
+/// Check the database at `path` before opening it. A damaged file is moved
+/// aside (`.picman.db.damaged-<time>`) and replaced by its backup when a
+/// sound one exists; otherwise this fails with [`DatabaseDamaged`] or
+/// [`DatabaseTooNew`]. A database that can't be read right now, say while
+/// another process holds a write lock, is an ordinary error and left as is.
+pub fn check_database(path: &Path) -> Result<()> {
+    match probe(path) {
+        Ok(()) => Ok(()),
+        Err(Problem::TooNew(found)) => Err(DatabaseTooNew { path: path.to_path_buf(), found }.into()),
+        Err(Problem::Damaged(reason)) => restore_backup(path, reason),
+        Err(Problem::Unreadable(e)) => Err(e.context(format!("Failed to check database {}", path.display()))),
+    }
+}
+
+fn restore_backup(path: &Path, reason: String) -> Result<()> {
+    let backup = backup_path(path);
+    match probe(&backup) {
+        _ if !backup.exists() => return Err(DatabaseDamaged { path: path.to_path_buf(), reason }.into()),
+        Ok(()) => {}
+        Err(Problem::Unreadable(e)) => {
+            return Err(e.context(format!("Failed to check backup {}", backup.display())))
+        }
+        Err(_) => return Err(DatabaseDamaged { path: path.to_path_buf(), reason }.into()),
+    }
+
+    let aside = with_suffix(path, &format!(".damaged-{}", unix_now()));
+    fs::rename(path, &aside)
+        .with_context(|| format!("Failed to move damaged database to {}", aside.display()))?;
+    // A journal belongs to the damaged file, not to the restored one
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let sidecar = with_suffix(path, suffix);
+        if sidecar.exists() {
+            let _ = fs::rename(&sidecar, with_suffix(&aside, suffix));
+        }
+    }
+    fs::copy(&backup, path).with_context(|| format!("Failed to restore {}", backup.display()))?;
+
+    let taken = fs::metadata(&backup)
+        .and_then(|m| m.modified())
+        .map(|t| chrono::DateTime::<chrono::Local>::from(t).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| "an unknown time".to_string());
+    warn!(path = %path.display(), reason = %reason, "restored damaged database from backup");
+    eprintln!(
+        "Warning: {} was damaged ({}). Restored the backup from {}; changes since then are lost. \
+         The damaged file was kept as {}",
+        path.display(),
+        reason,
+        taken,
+        aside.display()
+    );
+    Ok(())
+}
+
+impl Database {
+    /// Replace the backup at [`backup_path`] if it's missing or a day old.
+    /// Written aside and renamed into place, so a crash never leaves half a
+    /// backup; failures are only logged.
+    pub(super) fn refresh_backup(&self, db_path: &Path) {
+        let backup = backup_path(db_path);
+        let due = match fs::metadata(&backup).and_then(|m| m.modified()) {
+            Ok(modified) => modified.elapsed().map_or(true, |age| age >= BACKUP_INTERVAL),
+            Err(_) => true,
+        };
+        if !due {
+            return;
+        }
+
+        let partial = with_suffix(&backup, &format!(".{}", std::process::id()));
+        let _ = fs::remove_file(&partial);
+        let result = self
+            .connection()
+            .execute("VACUUM INTO ?1", [partial.to_string_lossy()])
+            .map_err(anyhow::Error::from)
+            .and_then(|_| fs::rename(&partial, &backup).map_err(anyhow::Error::from));
+        if let Err(e) = result {
+            let _ = fs::remove_file(&partial);
+            warn!(path = %backup.display(), error = %e, "failed to back up database");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn library_db(tmp: &TempDir) -> PathBuf {
+        let path = tmp.path().join(".picman.db");
+        let db = Database::open(&path).unwrap();
+        db.insert_directory("photos", None, None).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_sound_database_passes_and_is_backed_up() {
+        let tmp = TempDir::new().unwrap();
+        let path = library_db(&tmp);
+        // A new database has nothing worth backing up yet
+        assert!(!backup_path(&path).exists());
+
+        check_database(&path).unwrap();
+        Database::open(&path).unwrap();
+        assert!(backup_path(&path).exists());
+    }
+
+    #[test]
+    fn test_damaged_database_without_backup_fails_clearly() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join(".picman.db");
+        fs::write(&path, b"this is not a database").unwrap();
+
+        let err = Database::open(&path).err().unwrap();
+        let damaged = err.downcast_ref::<DatabaseDamaged>().unwrap();
+        assert_eq!(damaged.reason, "not a SQLite database");
+    }
+
+    #[test]
+    fn test_truncated_database_falls_back_to_backup() {
+        let tmp = TempDir::new().unwrap();
+        let path = library_db(&tmp);
+        Database::open(&path).unwrap();
+        assert!(backup_path(&path).exists());
+
+        let len = fs::metadata(&path).unwrap().len();
+        fs::File::options().write(true).open(&path).unwrap().set_len(len / 2).unwrap();
+
+        let db = Database::open(&path).unwrap();
+        assert!(db.get_directory_by_path("photos").unwrap().is_some());
+        let kept_aside = fs::read_dir(tmp.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .any(|e| e.file_name().to_string_lossy().starts_with(".picman.db.damaged-"));
+        assert!(kept_aside);
+    }
+
+    #[test]
+    fn test_locked_database_is_not_treated_as_damaged() {
+        let tmp = TempDir::new().unwrap();
+        let path = library_db(&tmp);
+        Database::open(&path).unwrap();
+        assert!(backup_path(&path).exists());
+
+        // Another process in the middle of a write
+        let writer = Connection::open(&path).unwrap();
+        writer.execute_batch("BEGIN EXCLUSIVE; INSERT INTO directories (path) VALUES ('new');").unwrap();
+
+        let err = check_database(&path).err().unwrap();
+        assert!(err.downcast_ref::<DatabaseDamaged>().is_none(), "{:#}", err);
+        writer.execute_batch("COMMIT").unwrap();
+
+        let moved_aside = fs::read_dir(tmp.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .any(|e| e.file_name().to_string_lossy().starts_with(".picman.db.damaged-"));
+        assert!(!moved_aside);
+        let db = Database::open(&path).unwrap();
+        assert!(db.get_directory_by_path("new").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_newer_schema_is_refused() {
+        let tmp = TempDir::new().unwrap();
+        let path = library_db(&tmp);
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION + 1))
+            .unwrap();
+
+        let err = Database::open(&path).err().unwrap();
+        assert_eq!(err.downcast_ref::<DatabaseTooNew>().unwrap().found, SCHEMA_VERSION + 1);
+    }
+}
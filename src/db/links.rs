@@ -0,0 +1,118 @@
+use std::fmt;
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::Database;
+
+/// How a deduplicated file shares its content with the copy that was kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    /// Same inode; edits to one path show up in the other
+    Hardlink,
+    /// Copy-on-write clone sharing extents (btrfs, xfs)
+    Reflink,
+}
+
+impl LinkKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LinkKind::Hardlink => "hardlink",
+            LinkKind::Reflink => "reflink",
+        }
+    }
+}
+
+impl fmt::Display for LinkKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Database {
+    /// Record that `file_id` now shares its content with `source_id`
+    pub fn record_file_link(&self, file_id: i64, source_id: i64, kind: LinkKind, linked_at: i64) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO file_links (file_id, source_id, kind, linked_at) VALUES (?1, ?2, ?3, ?4)",
+            params![file_id, source_id, kind.as_str(), linked_at],
+        )?;
+        Ok(())
+    }
+
+    /// The kept copy a file was linked to, if any
+    pub fn get_link_source(&self, file_id: i64) -> Result<Option<(i64, LinkKind)>> {
+        let mut stmt = self
+            .connection()
+            .prepare("SELECT source_id, kind FROM file_links WHERE file_id = ?1")?;
+        let mut rows = stmt.query([file_id])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let kind = match row.get::<_, String>(1)?.as_str() {
+            "reflink" => LinkKind::Reflink,
+            _ => LinkKind::Hardlink,
+        };
+        Ok(Some((row.get(0)?, kind)))
+    }
+
+    /// All file IDs sharing content with `file_id`, the kept copy first.
+    /// An unlinked file is a group of one.
+    pub fn get_link_group(&self, file_id: i64) -> Result<Vec<i64>> {
+        let source = self.get_link_source(file_id)?.map_or(file_id, |(source, _)| source);
+        let mut stmt = self
+            .connection()
+            .prepare("SELECT file_id FROM file_links WHERE source_id = ?1 ORDER BY file_id")?;
+        let mut group = vec![source];
+        for linked in stmt.query_map([source], |row| row.get(0))? {
+            group.push(linked?);
+        }
+        Ok(group)
+    }
+
+    /// Forget links involving a file whose content changed
+    pub fn clear_file_links(&self, file_id: i64) -> Result<()> {
+        self.connection().execute(
+            "DELETE FROM file_links WHERE file_id = ?1 OR source_id = ?1",
+            [file_id],
+        )?;
+        Ok(())
+    }
+
+    /// Number of linked files and the bytes they no longer take up
+    pub fn count_linked_files(&self) -> Result<(usize, u64)> {
+        let (count, bytes): (i64, i64) = self.connection().query_row(
+            "SELECT COUNT(*), COALESCE(SUM(f.size), 0) FROM file_links l JOIN files f ON l.file_id = f.id",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok((count as usize, bytes.max(0) as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_groups() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let keep = db.insert_file(dir, "a.jpg", 100, 0, Some("image")).unwrap();
+        let copy1 = db.insert_file(dir, "b.jpg", 100, 0, Some("image")).unwrap();
+        let copy2 = db.insert_file(dir, "c.jpg", 100, 0, Some("image")).unwrap();
+
+        assert_eq!(db.get_link_group(keep).unwrap(), vec![keep]);
+        db.record_file_link(copy1, keep, LinkKind::Hardlink, 1).unwrap();
+        db.record_file_link(copy2, keep, LinkKind::Reflink, 1).unwrap();
+
+        assert_eq!(db.get_link_group(copy2).unwrap(), vec![keep, copy1, copy2]);
+        assert_eq!(db.get_link_source(copy2).unwrap(), Some((keep, LinkKind::Reflink)));
+        assert_eq!(db.count_linked_files().unwrap(), (2, 200));
+
+        db.clear_file_links(copy1).unwrap();
+        assert_eq!(db.count_linked_files().unwrap(), (1, 100));
+        // Deleting the kept copy drops the rest of the group
+        db.delete_file(keep).unwrap();
+        assert_eq!(db.count_linked_files().unwrap(), (0, 0));
+    }
+}
@@ -0,0 +1,90 @@
+use anyhow::Result;
+use rusqlite::params;
+
+use super::Database;
+
+/// Outcome of the most recent run of a maintenance task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintenanceRun {
+    pub task: String,
+    /// Unix timestamp (seconds) when the run finished
+    pub last_run: i64,
+    pub duration_ms: i64,
+    pub success: bool,
+    /// Short result summary, or the error message for failed runs
+    pub summary: Option<String>,
+}
+
+impl Database {
+    /// Record a maintenance run, replacing the previous one for the same task
+    pub fn record_maintenance_run(&self, run: &MaintenanceRun) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO maintenance_runs (task, last_run, duration_ms, success, summary)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![run.task, run.last_run, run.duration_ms, run.success, run.summary],
+        )?;
+        Ok(())
+    }
+
+    /// Last run of every maintenance task that has ever run, ordered by task name
+    pub fn get_maintenance_runs(&self) -> Result<Vec<MaintenanceRun>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT task, last_run, duration_ms, success, summary
+             FROM maintenance_runs ORDER BY task",
+        )?;
+
+        let runs = stmt
+            .query_map([], |row| {
+                Ok(MaintenanceRun {
+                    task: row.get(0)?,
+                    last_run: row.get(1)?,
+                    duration_ms: row.get(2)?,
+                    success: row.get(3)?,
+                    summary: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(runs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(task: &str, last_run: i64, success: bool) -> MaintenanceRun {
+        MaintenanceRun {
+            task: task.to_string(),
+            last_run,
+            duration_ms: 1200,
+            success,
+            summary: Some("done".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_record_and_get_maintenance_runs() {
+        let db = Database::open_in_memory().unwrap();
+        assert!(db.get_maintenance_runs().unwrap().is_empty());
+
+        db.record_maintenance_run(&run("thumbnails", 100, true)).unwrap();
+        db.record_maintenance_run(&run("hash_backfill", 200, false)).unwrap();
+
+        let runs = db.get_maintenance_runs().unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0], run("hash_backfill", 200, false));
+        assert_eq!(runs[1].task, "thumbnails");
+    }
+
+    #[test]
+    fn test_record_replaces_previous_run() {
+        let db = Database::open_in_memory().unwrap();
+
+        db.record_maintenance_run(&run("cache_gc", 100, false)).unwrap();
+        db.record_maintenance_run(&run("cache_gc", 300, true)).unwrap();
+
+        let runs = db.get_maintenance_runs().unwrap();
+        assert_eq!(runs, vec![run("cache_gc", 300, true)]);
+    }
+}
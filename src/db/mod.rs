@@ -1,9 +1,59 @@
+mod activity;
+mod archive;
+mod attributes;
+mod capture_dates;
+mod changes;
+mod collisions;
 mod directories;
+mod duplicate_ignores;
+mod durations;
+mod exposure;
 mod files;
 mod filters;
+mod gear;
+mod hidden;
+mod history;
+mod integrity;
+mod links;
+mod maintenance;
+mod pairs;
+mod pins;
+mod presets;
+mod ratings;
+mod recent_tags;
+mod review_queues;
+mod scan_state;
 mod schema;
+mod screenshots;
+mod stacks;
+mod summary;
+mod sync_checkpoints;
+mod tag_aliases;
 mod tags;
+mod thumbnail_failures;
+mod thumbnail_presence;
+mod tuning;
+mod usage;
 
-pub use directories::Directory;
-pub use files::{DuplicateGroup, File, FileToHash};
+pub use activity::Activity;
+pub use archive::{ArchiveFilter, ArchivedFile};
+pub use capture_dates::CaptureDay;
+pub use changes::{MetadataChanges, VersionConflict};
+pub use directories::{Directory, DirectoryTotals};
+pub use duplicate_ignores::IgnoredPairs;
+pub use exposure::{parse_shutter, ExposureFilter};
+pub use files::{DuplicateGroup, DuplicateScope, File, FileToHash, HashedFile};
+pub use gear::{GearField, GearStat};
+pub use history::OperationRecord;
+pub use integrity::{backup_path, check_database, DatabaseDamaged, DatabaseTooNew};
+pub use links::LinkKind;
+pub use maintenance::MaintenanceRun;
+pub use presets::FilterPreset;
+pub use ratings::RatingAggregate;
+pub use review_queues::{ReviewDecision, ReviewEntry, ReviewQueue};
 pub use schema::Database;
+pub use summary::{LibrarySummary, SYNC_TASK};
+pub use sync_checkpoints::SyncCheckpoints;
+pub use thumbnail_failures::ThumbnailFailure;
+pub use tuning::{fast_profile, set_fast_profile};
+pub use usage::UsageNode;
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::directories::BRANCH_DIRS;
+use super::Database;
+use crate::scanner::pair_siblings;
+
+impl Database {
+    /// Recompute sibling pairs (live photo video, RAW next to JPEG) for one directory
+    pub fn rebuild_directory_pairs(&self, directory_id: i64) -> Result<usize> {
+        let files = self.get_files_in_directory(directory_id)?;
+        self.connection().execute(
+            "DELETE FROM file_pairs WHERE file_id IN (SELECT id FROM files WHERE directory_id = ?1)",
+            [directory_id],
+        )?;
+
+        let names: Vec<&str> = files.iter().map(|f| f.filename.as_str()).collect();
+        let pairs = pair_siblings(&names);
+        for &(companion, primary) in &pairs {
+            self.connection().execute(
+                "INSERT INTO file_pairs (file_id, primary_id) VALUES (?1, ?2)",
+                params![files[companion].id, files[primary].id],
+            )?;
+        }
+        Ok(pairs.len())
+    }
+
+    /// Recompute sibling pairs for the whole library
+    pub fn rebuild_all_pairs(&self) -> Result<usize> {
+        self.connection().execute("DELETE FROM file_pairs", [])?;
+
+        let mut by_directory: HashMap<i64, Vec<(i64, String)>> = HashMap::new();
+        for file in self.get_all_files()? {
+            by_directory.entry(file.directory_id).or_default().push((file.id, file.filename));
+        }
+
+        let mut total = 0;
+        for files in by_directory.values() {
+            let names: Vec<&str> = files.iter().map(|(_, name)| name.as_str()).collect();
+            for (companion, primary) in pair_siblings(&names) {
+                self.connection().execute(
+                    "INSERT INTO file_pairs (file_id, primary_id) VALUES (?1, ?2)",
+                    params![files[companion].0, files[primary].0],
+                )?;
+                total += 1;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Companion file ID -> primary file ID for a directory
+    pub fn get_directory_pairs(&self, directory_id: i64) -> Result<HashMap<i64, i64>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT fp.file_id, fp.primary_id FROM file_pairs fp
+             JOIN files f ON fp.file_id = f.id
+             WHERE f.directory_id = ?1",
+        )?;
+
+        let pairs = stmt
+            .query_map([directory_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(pairs)
+    }
+
+    /// [`Database::get_directory_pairs`] for the directory at `path` and
+    /// everything below it (the root's own files only for "")
+    pub fn get_branch_pairs(&self, path: &str) -> Result<HashMap<i64, i64>> {
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT fp.file_id, fp.primary_id FROM file_pairs fp
+             JOIN files f ON fp.file_id = f.id
+             WHERE f.directory_id IN ({})",
+            BRANCH_DIRS
+        ))?;
+
+        let pairs = stmt
+            .query_map([path], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(pairs)
+    }
+
+    /// All file IDs in the same logical asset as `file_id`, primary first.
+    /// An unpaired file is a group of one.
+    pub fn get_pair_group(&self, file_id: i64) -> Result<Vec<i64>> {
+        let primary: i64 = self.connection().query_row(
+            "SELECT COALESCE((SELECT primary_id FROM file_pairs WHERE file_id = ?1), ?1)",
+            [file_id],
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = self
+            .connection()
+            .prepare("SELECT file_id FROM file_pairs WHERE primary_id = ?1 ORDER BY file_id")?;
+        let mut group = vec![primary];
+        for companion in stmt.query_map([primary], |row| row.get(0))? {
+            group.push(companion?);
+        }
+        Ok(group)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebuild_directory_pairs() {
+        let db = Database::open_in_memory().unwrap();
+        let dir_id = db.insert_directory("photos", None, None).unwrap();
+        let jpg = db.insert_file(dir_id, "IMG_1.JPG", 100, 1000, Some("image")).unwrap();
+        let mov = db.insert_file(dir_id, "IMG_1.MOV", 100, 1000, Some("video")).unwrap();
+        let lone = db.insert_file(dir_id, "IMG_2.JPG", 100, 1000, Some("image")).unwrap();
+
+        assert_eq!(db.rebuild_directory_pairs(dir_id).unwrap(), 1);
+        assert_eq!(db.get_directory_pairs(dir_id).unwrap(), HashMap::from([(mov, jpg)]));
+        assert_eq!(db.get_pair_group(mov).unwrap(), vec![jpg, mov]);
+        assert_eq!(db.get_pair_group(lone).unwrap(), vec![lone]);
+
+        // Rebuilding is idempotent, and the whole-library pass agrees
+        assert_eq!(db.rebuild_directory_pairs(dir_id).unwrap(), 1);
+        assert_eq!(db.rebuild_all_pairs().unwrap(), 1);
+        assert_eq!(db.get_pair_group(jpg).unwrap(), vec![jpg, mov]);
+    }
+
+    #[test]
+    fn test_rating_and_tags_apply_to_whole_group() {
+        let db = Database::open_in_memory().unwrap();
+        let dir_id = db.insert_directory("photos", None, None).unwrap();
+        let jpg = db.insert_file(dir_id, "DSC_1.jpg", 100, 1000, Some("image")).unwrap();
+        let raw = db.insert_file(dir_id, "DSC_1.NEF", 100, 1000, Some("image")).unwrap();
+        db.rebuild_directory_pairs(dir_id).unwrap();
+
+        db.set_file_rating(jpg, Some(4.0)).unwrap();
+        db.add_file_tag(raw, "keeper").unwrap();
+        for id in [jpg, raw] {
+            let file = db.get_file_with_path(id).unwrap().unwrap().0;
+            assert_eq!(file.rating, Some(4.0));
+            assert_eq!(db.get_file_tags(id).unwrap(), vec!["keeper"]);
+        }
+
+        db.remove_file_tag(jpg, "keeper").unwrap();
+        assert!(db.get_file_tags(raw).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_deleting_primary_clears_pairs() {
+        let db = Database::open_in_memory().unwrap();
+        let dir_id = db.insert_directory("photos", None, None).unwrap();
+        let jpg = db.insert_file(dir_id, "IMG_1.jpg", 100, 1000, Some("image")).unwrap();
+        let mov = db.insert_file(dir_id, "IMG_1.mov", 100, 1000, Some("video")).unwrap();
+        db.rebuild_directory_pairs(dir_id).unwrap();
+
+        db.delete_file(jpg).unwrap();
+        assert!(db.get_directory_pairs(dir_id).unwrap().is_empty());
+        assert_eq!(db.get_pair_group(mov).unwrap(), vec![mov]);
+    }
+}
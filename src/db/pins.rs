@@ -0,0 +1,62 @@
+use anyhow::Result;
+use rusqlite::params;
+
+use super::Database;
+
+impl Database {
+    /// Pin a directory; pinning it again keeps its place
+    pub fn pin_directory(&self, directory_id: i64, pinned_at: i64) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR IGNORE INTO pinned_directories (directory_id, pinned_at) VALUES (?1, ?2)",
+            params![directory_id, pinned_at],
+        )?;
+        Ok(())
+    }
+
+    /// Unpin a directory. Returns false if it wasn't pinned
+    pub fn unpin_directory(&self, directory_id: i64) -> Result<bool> {
+        let deleted = self
+            .connection()
+            .execute("DELETE FROM pinned_directories WHERE directory_id = ?1", [directory_id])?;
+        Ok(deleted > 0)
+    }
+
+    /// IDs of pinned directories, oldest pin first. Pins of directories
+    /// that sync has since removed are skipped.
+    pub fn get_pinned_directory_ids(&self) -> Result<Vec<i64>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT p.directory_id FROM pinned_directories p
+             JOIN directories d ON p.directory_id = d.id
+             ORDER BY p.pinned_at, p.rowid",
+        )?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_and_unpin_directories() {
+        let db = Database::open_in_memory().unwrap();
+        let photos = db.insert_directory("photos", None, None).unwrap();
+        let videos = db.insert_directory("videos", None, None).unwrap();
+
+        db.pin_directory(videos, 100).unwrap();
+        db.pin_directory(photos, 200).unwrap();
+        // Re-pinning doesn't move it to the end
+        db.pin_directory(videos, 300).unwrap();
+        assert_eq!(db.get_pinned_directory_ids().unwrap(), vec![videos, photos]);
+
+        assert!(db.unpin_directory(videos).unwrap());
+        assert!(!db.unpin_directory(videos).unwrap());
+        assert_eq!(db.get_pinned_directory_ids().unwrap(), vec![photos]);
+
+        db.delete_directory(photos).unwrap();
+        assert!(db.get_pinned_directory_ids().unwrap().is_empty());
+    }
+}
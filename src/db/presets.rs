@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, OptionalExtension};
+
+use super::Database;
+use crate::tui::dialogs::{FilterCriteria, MediaFilter, Orientation, RatingFilter};
+
+/// A named, saved set of filter criteria
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterPreset {
+    pub name: String,
+    pub criteria: FilterCriteria,
+}
+
+impl Database {
+    /// Save `criteria` under `name`, replacing any preset of that name
+    pub fn save_filter_preset(&self, name: &str, criteria: &FilterCriteria) -> Result<()> {
+        let (min_rating, unrated) = match criteria.rating {
+            RatingFilter::Any => (None, false),
+            RatingFilter::Unrated => (None, true),
+            RatingFilter::MinRating(r) => (Some(r), false),
+        };
+        self.connection().execute(
+            "INSERT OR REPLACE INTO filter_presets (name, min_rating, unrated, media_type, tags, camera, lens, orientation)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                name,
+                min_rating,
+                unrated,
+                criteria.media.media_type(),
+                serde_json::to_string(&criteria.tags)?,
+                criteria.camera,
+                criteria.lens,
+                criteria.orientation.map(Orientation::as_str)
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// All presets, sorted by name
+    pub fn get_filter_presets(&self) -> Result<Vec<FilterPreset>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT name, min_rating, unrated, media_type, tags, camera, lens, orientation FROM filter_presets ORDER BY name",
+        )?;
+        let rows = stmt
+            .query_map([], preset_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|row| self.canonicalize_preset(row.into_preset()?))
+            .collect()
+    }
+
+    pub fn get_filter_preset(&self, name: &str) -> Result<Option<FilterPreset>> {
+        let row = self
+            .connection()
+            .query_row(
+                "SELECT name, min_rating, unrated, media_type, tags, camera, lens, orientation FROM filter_presets WHERE name = ?1",
+                [name],
+                preset_row,
+            )
+            .optional()?;
+        row.map(|row| self.canonicalize_preset(row.into_preset()?)).transpose()
+    }
+
+    /// Presets saved before a tag became an alias filter on its canonical tag
+    fn canonicalize_preset(&self, mut preset: FilterPreset) -> Result<FilterPreset> {
+        preset.criteria.tags = self.canonical_tags(&preset.criteria.tags)?;
+        Ok(preset)
+    }
+
+    /// Delete a preset. Returns false if there was none of that name
+    pub fn delete_filter_preset(&self, name: &str) -> Result<bool> {
+        let deleted = self
+            .connection()
+            .execute("DELETE FROM filter_presets WHERE name = ?1", [name])?;
+        Ok(deleted > 0)
+    }
+}
+
+struct PresetRow {
+    name: String,
+    min_rating: Option<f64>,
+    unrated: bool,
+    media_type: Option<String>,
+    tags: String,
+    camera: Option<String>,
+    lens: Option<String>,
+    orientation: Option<String>,
+}
+
+fn preset_row(row: &rusqlite::Row) -> rusqlite::Result<PresetRow> {
+    Ok(PresetRow {
+        name: row.get(0)?,
+        min_rating: row.get(1)?,
+        unrated: row.get(2)?,
+        media_type: row.get(3)?,
+        tags: row.get(4)?,
+        camera: row.get(5)?,
+        lens: row.get(6)?,
+        orientation: row.get(7)?,
+    })
+}
+
+impl PresetRow {
+    fn into_preset(self) -> Result<FilterPreset> {
+        let rating = match (self.min_rating, self.unrated) {
+            (Some(r), _) => RatingFilter::MinRating(r),
+            (None, true) => RatingFilter::Unrated,
+            (None, false) => RatingFilter::Any,
+        };
+        let media = MediaFilter::from_media_type(self.media_type.as_deref())
+            .with_context(|| format!("Preset '{}' has an unknown media type", self.name))?;
+        let tags = serde_json::from_str(&self.tags)
+            .with_context(|| format!("Preset '{}' has unreadable tags", self.name))?;
+        let orientation = self
+            .orientation
+            .map(|o| o.parse::<Orientation>())
+            .transpose()
+            .map_err(anyhow::Error::msg)
+            .with_context(|| format!("Preset '{}' has an unknown orientation", self.name))?;
+        Ok(FilterPreset {
+            name: self.name,
+            criteria: FilterCriteria { rating, tags, media, camera: self.camera, lens: self.lens, orientation },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_preset_round_trip() {
+        let db = Database::open_in_memory().unwrap();
+        let criteria = FilterCriteria {
+            rating: RatingFilter::MinRating(4.0),
+            tags: vec!["portrait".to_string(), "outdoor".to_string()],
+            media: MediaFilter::Video,
+            camera: Some("Sony A7 IV".to_string()),
+            lens: None,
+            orientation: Some(Orientation::Panorama),
+        };
+        db.save_filter_preset("best clips", &criteria).unwrap();
+        db.save_filter_preset("to rate", &FilterCriteria {
+            rating: RatingFilter::Unrated,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let preset = db.get_filter_preset("best clips").unwrap().unwrap();
+        assert_eq!(preset.criteria, criteria);
+
+        let names: Vec<String> = db.get_filter_presets().unwrap().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["best clips", "to rate"]);
+        assert_eq!(
+            db.get_filter_preset("to rate").unwrap().unwrap().criteria.rating,
+            RatingFilter::Unrated
+        );
+    }
+
+    #[test]
+    fn test_filter_preset_overwrite_and_delete() {
+        let db = Database::open_in_memory().unwrap();
+        db.save_filter_preset("p", &FilterCriteria::default()).unwrap();
+        let updated = FilterCriteria {
+            rating: RatingFilter::MinRating(2.0),
+            ..Default::default()
+        };
+        db.save_filter_preset("p", &updated).unwrap();
+        assert_eq!(db.get_filter_presets().unwrap().len(), 1);
+        assert_eq!(db.get_filter_preset("p").unwrap().unwrap().criteria, updated);
+
+        assert!(db.delete_filter_preset("p").unwrap());
+        assert!(!db.delete_filter_preset("p").unwrap());
+        assert!(db.get_filter_preset("p").unwrap().is_none());
+    }
+}
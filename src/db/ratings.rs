@@ -0,0 +1,224 @@
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::directories::SUBTREE_FILES;
+use super::{Database, FileToHash};
+use crate::rating::RatingScale;
+use crate::library_path::LibraryPath;
+
+/// How a directory rating is derived from the ratings of its files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RatingAggregate {
+    Max,
+    Average,
+}
+
+impl Database {
+    /// Count unrated files in a directory and all its descendants
+    pub fn count_unrated_subtree_files(&self, dir_path: &str) -> Result<usize> {
+        let count: i64 = self.connection().query_row(
+            &format!("SELECT COUNT(*) FROM files WHERE rating IS NULL AND id IN ({SUBTREE_FILES})"),
+            [dir_path],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Give every unrated file in a directory and its descendants the given
+    /// rating. Files that already have a rating are left alone.
+    /// Returns the number of files updated.
+    pub fn rate_unrated_subtree_files(&self, dir_path: &str, rating: f64) -> Result<usize> {
+        let updated = self.connection().execute(
+            &format!("UPDATE files SET rating = ?2 WHERE rating IS NULL AND id IN ({SUBTREE_FILES})"),
+            params![dir_path, rating],
+        )?;
+        Ok(updated)
+    }
+
+    /// Aggregate the ratings of rated files in a directory and its descendants.
+    /// Returns the derived rating (None if no file is rated) and how many
+    /// rated files it was derived from. Averages round to the nearest half star.
+    pub fn aggregate_subtree_rating(
+        &self,
+        dir_path: &str,
+        aggregate: RatingAggregate,
+    ) -> Result<(Option<f64>, usize)> {
+        let expr = match aggregate {
+            RatingAggregate::Max => "MAX(rating)",
+            RatingAggregate::Average => "ROUND(AVG(rating) * 2) / 2.0",
+        };
+        let (rating, count): (Option<f64>, i64) = self.connection().query_row(
+            &format!(
+                "SELECT {expr}, COUNT(*) FROM files WHERE rating IS NOT NULL AND id IN ({SUBTREE_FILES})"
+            ),
+            [dir_path],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok((rating, count as usize))
+    }
+
+    /// Unrated image files, for importing ratings stored in their metadata
+    pub fn get_unrated_images(&self) -> Result<Vec<FileToHash>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT f.id, d.path, f.filename, f.size
+             FROM files f
+             JOIN directories d ON f.directory_id = d.id
+             WHERE f.media_type = 'image' AND f.rating IS NULL
+             ORDER BY d.path, f.filename",
+        )?;
+        let files = stmt
+            .query_map([], |row| {
+                let dir_path: String = row.get(1)?;
+                let filename: String = row.get(2)?;
+                let path = LibraryPath::file(&dir_path, &filename).into();
+                Ok(FileToHash { id: row.get(0)?, path, size: row.get(3)? })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(files)
+    }
+
+    /// Rate a file and its paired siblings, leaving any that already have a
+    /// rating alone. Returns the number of files rated.
+    pub fn set_rating_if_unrated(&self, id: i64, rating: f64) -> Result<usize> {
+        let mut rated = 0;
+        for member in self.get_pair_group(id)? {
+            rated += self.connection().execute(
+                "UPDATE files SET rating = ?1 WHERE id = ?2 AND rating IS NULL",
+                params![rating, member],
+            )?;
+        }
+        Ok(rated)
+    }
+
+    /// Move every file, directory and filter preset rating from one scale
+    /// to the other. Callers wrap this in a transaction so a library is
+    /// never left half rescaled. Returns the number of rows changed.
+    pub fn rescale_ratings(&self, from: RatingScale, to: RatingScale) -> Result<usize> {
+        if from == to {
+            return Ok(0);
+        }
+        let mut changed = 0;
+        for (table, column) in [
+            ("files", "rating"),
+            ("directories", "rating"),
+            ("filter_presets", "min_rating"),
+        ] {
+            // Map each distinct value, so half steps rescale like whole ones
+            let values: Vec<f64> = self
+                .connection()
+                .prepare(&format!("SELECT DISTINCT {column} FROM {table} WHERE {column} IS NOT NULL"))?
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            if values.is_empty() {
+                continue;
+            }
+            let arms: String = values
+                .iter()
+                .map(|&r| format!(" WHEN {:?} THEN {:?}", r, from.convert(r, to)))
+                .collect();
+            changed += self.connection().execute(
+                &format!(
+                    "UPDATE {table} SET {column} = CASE {column}{arms} ELSE {column} END
+                     WHERE {column} IS NOT NULL"
+                ),
+                [],
+            )?;
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (Database, [i64; 4]) {
+        let db = Database::open_in_memory().unwrap();
+        let trip = db.insert_directory("trip", None, None).unwrap();
+        let day1 = db.insert_directory("trip/day1", Some(trip), None).unwrap();
+        let other = db.insert_directory("trips", None, None).unwrap();
+        let a = db.insert_file(trip, "a.jpg", 100, 1000, Some("image")).unwrap();
+        let b = db.insert_file(day1, "b.jpg", 100, 1000, Some("image")).unwrap();
+        let c = db.insert_file(day1, "c.jpg", 100, 1000, Some("image")).unwrap();
+        let d = db.insert_file(other, "d.jpg", 100, 1000, Some("image")).unwrap();
+        (db, [a, b, c, d])
+    }
+
+    #[test]
+    fn test_rate_unrated_subtree_files() {
+        let (db, [a, b, c, d]) = setup();
+        db.set_file_rating(b, Some(2.0)).unwrap();
+
+        assert_eq!(db.count_unrated_subtree_files("trip").unwrap(), 2);
+        assert_eq!(db.rate_unrated_subtree_files("trip", 4.0).unwrap(), 2);
+
+        let rating = |id| db.get_file_with_path(id).unwrap().unwrap().0.rating;
+        assert_eq!(rating(a), Some(4.0));
+        assert_eq!(rating(b), Some(2.0), "existing rating must be kept");
+        assert_eq!(rating(c), Some(4.0));
+        assert_eq!(rating(d), None, "sibling with shared prefix must be untouched");
+        assert_eq!(db.count_unrated_subtree_files("").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_aggregate_subtree_rating() {
+        let (db, [a, b, _c, d]) = setup();
+        assert_eq!(
+            db.aggregate_subtree_rating("trip", RatingAggregate::Max).unwrap(),
+            (None, 0)
+        );
+
+        db.set_file_rating(a, Some(5.0)).unwrap();
+        db.set_file_rating(b, Some(2.0)).unwrap();
+        db.set_file_rating(d, Some(1.0)).unwrap();
+
+        assert_eq!(
+            db.aggregate_subtree_rating("trip", RatingAggregate::Max).unwrap(),
+            (Some(5.0), 2)
+        );
+        assert_eq!(
+            db.aggregate_subtree_rating("trip", RatingAggregate::Average).unwrap(),
+            (Some(3.5), 2)
+        );
+        assert_eq!(
+            db.aggregate_subtree_rating("", RatingAggregate::Average).unwrap(),
+            (Some(2.5), 3)
+        );
+    }
+
+    #[test]
+    fn test_rescale_ratings() {
+        let (db, [a, b, c, _d]) = setup();
+        let trip = db.get_directory_by_path("trip").unwrap().unwrap().id;
+        db.set_file_rating(a, Some(5.0)).unwrap();
+        db.set_file_rating(b, Some(1.0)).unwrap();
+        db.set_directory_rating(trip, Some(3.0)).unwrap();
+
+        assert_eq!(db.rescale_ratings(RatingScale::Five, RatingScale::Ten).unwrap(), 3);
+        let rating = |id| db.get_file_with_path(id).unwrap().unwrap().0.rating;
+        assert_eq!(rating(a), Some(10.0));
+        assert_eq!(rating(b), Some(2.0));
+        assert_eq!(rating(c), None);
+        assert_eq!(db.get_directory_by_path("trip").unwrap().unwrap().rating, Some(6.0));
+
+        db.set_file_rating(b, Some(7.0)).unwrap();
+        db.rescale_ratings(RatingScale::Ten, RatingScale::Five).unwrap();
+        assert_eq!(rating(a), Some(5.0));
+        assert_eq!(rating(b), Some(3.5));
+        assert_eq!(db.get_directory_by_path("trip").unwrap().unwrap().rating, Some(3.0));
+    }
+
+    #[test]
+    fn test_set_rating_if_unrated() {
+        let (db, [a, b, ..]) = setup();
+        db.set_file_rating(b, Some(2.0)).unwrap();
+        assert_eq!(db.get_unrated_images().unwrap().len(), 3);
+
+        assert_eq!(db.set_rating_if_unrated(a, 4.0).unwrap(), 1);
+        assert_eq!(db.set_rating_if_unrated(b, 5.0).unwrap(), 0);
+        let rating = |id| db.get_file_with_path(id).unwrap().unwrap().0.rating;
+        assert_eq!((rating(a), rating(b)), (Some(4.0), Some(2.0)));
+        assert_eq!(db.get_unrated_images().unwrap().len(), 2);
+    }
+}
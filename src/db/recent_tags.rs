@@ -0,0 +1,60 @@
+use anyhow::Result;
+use rusqlite::params;
+
+use super::Database;
+
+impl Database {
+    /// Note that `user` applied `tag` at `at` (Unix seconds)
+    pub fn record_tag_use(&self, user: &str, tag: &str, at: i64) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO recent_tags (user, tag, used_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(user, tag) DO UPDATE SET used_at = excluded.used_at",
+            params![user, tag, at],
+        )?;
+        Ok(())
+    }
+
+    /// The tags `user` applied most recently, newest first. Tags that no
+    /// longer exist are left out.
+    pub fn get_recent_tags(&self, user: &str, limit: usize) -> Result<Vec<String>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT r.tag FROM recent_tags r
+             JOIN tags t ON t.name = r.tag
+             WHERE r.user = ?1
+             ORDER BY r.used_at DESC, r.tag
+             LIMIT ?2",
+        )?;
+        let tags = stmt
+            .query_map(params![user, limit as i64], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_tags_per_user_newest_first() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let file = db.insert_file(dir, "a.jpg", 1, 0, Some("image")).unwrap();
+        for tag in ["beach", "family", "sunset"] {
+            db.add_file_tag(file, tag).unwrap();
+        }
+
+        db.record_tag_use("ann", "beach", 100).unwrap();
+        db.record_tag_use("ann", "family", 200).unwrap();
+        db.record_tag_use("bob", "sunset", 300).unwrap();
+        // Using a tag again moves it back to the front
+        db.record_tag_use("ann", "beach", 300).unwrap();
+        // Never created, so there's nothing to toggle
+        db.record_tag_use("ann", "gone", 400).unwrap();
+
+        assert_eq!(db.get_recent_tags("ann", 9).unwrap(), vec!["beach", "family"]);
+        assert_eq!(db.get_recent_tags("ann", 1).unwrap(), vec!["beach"]);
+        assert_eq!(db.get_recent_tags("bob", 9).unwrap(), vec!["sunset"]);
+        assert!(db.get_recent_tags("eve", 9).unwrap().is_empty());
+    }
+}
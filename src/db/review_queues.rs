@@ -0,0 +1,281 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use rusqlite::{params, OptionalExtension};
+
+use super::files::file_from_row;
+use super::{Database, File};
+
+/// What happened to a file in a review queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewDecision {
+    /// Rated (or had its rating cleared) while reviewing
+    Rated,
+    /// Passed over without a rating
+    Skipped,
+}
+
+impl ReviewDecision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReviewDecision::Rated => "rated",
+            ReviewDecision::Skipped => "skipped",
+        }
+    }
+}
+
+impl FromStr for ReviewDecision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rated" => Ok(ReviewDecision::Rated),
+            "skipped" => Ok(ReviewDecision::Skipped),
+            other => Err(format!("Unknown decision '{}' (expected rated or skipped)", other)),
+        }
+    }
+}
+
+/// A saved review session: the files a filter matched when it was built,
+/// in review order, and how far the review has got
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewQueue {
+    pub id: i64,
+    pub name: String,
+    /// The filter it was built from, as shown to the user
+    pub filter: String,
+    /// Index of the file the review is at (`total` once past the last)
+    pub position: usize,
+    pub total: usize,
+    /// Files rated or skipped so far
+    pub decided: usize,
+    pub created_at: i64,
+    /// Last time the position moved or a decision was made
+    pub updated_at: i64,
+}
+
+impl ReviewQueue {
+    pub fn is_finished(&self) -> bool {
+        self.decided >= self.total
+    }
+}
+
+/// A file in a review queue, with its directory path
+#[derive(Debug, Clone)]
+pub struct ReviewEntry {
+    pub file: File,
+    pub dir_path: String,
+    pub decision: Option<ReviewDecision>,
+}
+
+/// Queue columns with the counts worked out from its files
+const QUEUE_SELECT: &str = "SELECT q.id, q.name, q.filter, q.created_at, q.updated_at,
+        (SELECT COUNT(*) FROM review_queue_files r JOIN files f ON r.file_id = f.id
+         WHERE r.queue_id = q.id AND r.seq < q.position),
+        (SELECT COUNT(*) FROM review_queue_files r JOIN files f ON r.file_id = f.id
+         WHERE r.queue_id = q.id),
+        (SELECT COUNT(*) FROM review_queue_files r JOIN files f ON r.file_id = f.id
+         WHERE r.queue_id = q.id AND r.decision IS NOT NULL)
+     FROM review_queues q";
+
+fn queue_from_row(row: &rusqlite::Row) -> rusqlite::Result<ReviewQueue> {
+    Ok(ReviewQueue {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        filter: row.get(2)?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+        position: row.get::<_, i64>(5)? as usize,
+        total: row.get::<_, i64>(6)? as usize,
+        decided: row.get::<_, i64>(7)? as usize,
+    })
+}
+
+impl Database {
+    /// Save a queue of `file_ids`, reviewed in the order given. Fails if a
+    /// queue already has the name (ignoring case)
+    pub fn create_review_queue(&self, name: &str, filter: &str, file_ids: &[i64], at: i64) -> Result<i64> {
+        if self.find_review_queue(name)?.is_some() {
+            anyhow::bail!("A review queue named '{}' already exists", name);
+        }
+        self.connection().execute(
+            "INSERT INTO review_queues (name, filter, position, created_at, updated_at) VALUES (?1, ?2, 0, ?3, ?3)",
+            params![name, filter, at],
+        )?;
+        let id = self.connection().last_insert_rowid();
+        let mut stmt = self
+            .connection()
+            .prepare("INSERT INTO review_queue_files (queue_id, seq, file_id) VALUES (?1, ?2, ?3)")?;
+        for (seq, file_id) in file_ids.iter().enumerate() {
+            stmt.execute(params![id, seq as i64, file_id])?;
+        }
+        Ok(id)
+    }
+
+    /// All queues, most recently reviewed first
+    pub fn get_review_queues(&self) -> Result<Vec<ReviewQueue>> {
+        let mut stmt = self
+            .connection()
+            .prepare(&format!("{} ORDER BY q.updated_at DESC, q.id DESC", QUEUE_SELECT))?;
+        let queues = stmt.query_map([], queue_from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(queues)
+    }
+
+    pub fn get_review_queue(&self, id: i64) -> Result<Option<ReviewQueue>> {
+        let queue = self
+            .connection()
+            .query_row(&format!("{} WHERE q.id = ?1", QUEUE_SELECT), [id], queue_from_row)
+            .optional()?;
+        Ok(queue)
+    }
+
+    /// The queue with this name, ignoring case
+    pub fn find_review_queue(&self, name: &str) -> Result<Option<ReviewQueue>> {
+        let queue = self
+            .connection()
+            .query_row(&format!("{} WHERE q.name = ?1 COLLATE NOCASE", QUEUE_SELECT), [name], queue_from_row)
+            .optional()?;
+        Ok(queue)
+    }
+
+    /// The queue's files still in the library, in review order
+    pub fn get_review_queue_files(&self, id: i64) -> Result<Vec<ReviewEntry>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT f.id, f.directory_id, f.filename, f.size, f.mtime, f.hash, f.rating, f.media_type, f.width, f.height, f.perceptual_hash, f.camera, f.lens, d.path, r.decision
+             FROM review_queue_files r
+             JOIN files f ON r.file_id = f.id
+             JOIN directories d ON f.directory_id = d.id
+             WHERE r.queue_id = ?1
+             ORDER BY r.seq",
+        )?;
+        let rows = stmt.query_map([id], |row| {
+            Ok((file_from_row(row)?, row.get::<_, String>(13)?, row.get::<_, Option<String>>(14)?))
+        })?;
+        let mut entries = Vec::new();
+        for row in rows {
+            let (file, dir_path, decision) = row?;
+            let decision = decision.map(|d| d.parse()).transpose().map_err(anyhow::Error::msg)?;
+            entries.push(ReviewEntry { file, dir_path, decision });
+        }
+        Ok(entries)
+    }
+
+    /// Move the review to `file_id`. Returns false if the file isn't in
+    /// the queue
+    pub fn set_review_position(&self, id: i64, file_id: i64, at: i64) -> Result<bool> {
+        let updated = self.connection().execute(
+            "UPDATE review_queues SET updated_at = ?3,
+                 position = (SELECT seq FROM review_queue_files WHERE queue_id = ?1 AND file_id = ?2)
+             WHERE id = ?1 AND EXISTS (SELECT 1 FROM review_queue_files WHERE queue_id = ?1 AND file_id = ?2)",
+            params![id, file_id, at],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Record what was done with `file_id` and move the review on to the
+    /// file after it. Skipping a file already rated keeps it rated. Returns
+    /// false if the file isn't in the queue
+    pub fn record_review_decision(&self, id: i64, file_id: i64, decision: ReviewDecision, at: i64) -> Result<bool> {
+        let updated = self.connection().execute(
+            "UPDATE review_queue_files
+             SET decided_at = CASE WHEN ?3 = 'skipped' AND decision = 'rated' THEN decided_at ELSE ?4 END,
+                 decision = CASE WHEN ?3 = 'skipped' AND decision = 'rated' THEN decision ELSE ?3 END
+             WHERE queue_id = ?1 AND file_id = ?2",
+            params![id, file_id, decision.as_str(), at],
+        )?;
+        if updated == 0 {
+            return Ok(false);
+        }
+        // The next file still in the library, or past the end
+        self.connection().execute(
+            "UPDATE review_queues SET updated_at = ?3, position = COALESCE(
+                 (SELECT MIN(r.seq) FROM review_queue_files r JOIN files f ON r.file_id = f.id
+                  WHERE r.queue_id = ?1
+                    AND r.seq > (SELECT seq FROM review_queue_files WHERE queue_id = ?1 AND file_id = ?2)),
+                 (SELECT MAX(seq) + 1 FROM review_queue_files WHERE queue_id = ?1))
+             WHERE id = ?1",
+            params![id, file_id, at],
+        )?;
+        Ok(true)
+    }
+
+    /// Delete a queue (its files stay in the library). Returns false if
+    /// there was none of that name
+    pub fn delete_review_queue(&self, name: &str) -> Result<bool> {
+        let Some(queue) = self.find_review_queue(name)? else {
+            return Ok(false);
+        };
+        self.connection()
+            .execute("DELETE FROM review_queue_files WHERE queue_id = ?1", [queue.id])?;
+        self.connection().execute("DELETE FROM review_queues WHERE id = ?1", [queue.id])?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (Database, Vec<i64>) {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("2024", None, None).unwrap();
+        let ids = ["a.jpg", "b.jpg", "c.jpg"]
+            .iter()
+            .map(|name| db.insert_file(dir, name, 100, 0, None).unwrap())
+            .collect();
+        (db, ids)
+    }
+
+    #[test]
+    fn test_review_progress_survives_reopening() {
+        let (db, ids) = setup();
+        let id = db.create_review_queue("June", "unrated", &ids, 100).unwrap();
+        assert!(db.create_review_queue("june", "all", &ids, 100).is_err());
+
+        let queue = db.find_review_queue("JUNE").unwrap().unwrap();
+        assert_eq!((queue.position, queue.total, queue.decided), (0, 3, 0));
+
+        assert!(db.record_review_decision(id, ids[0], ReviewDecision::Rated, 200).unwrap());
+        assert!(db.record_review_decision(id, ids[1], ReviewDecision::Skipped, 300).unwrap());
+        let queue = db.get_review_queue(id).unwrap().unwrap();
+        assert_eq!((queue.position, queue.decided, queue.updated_at), (2, 2, 300));
+
+        let decisions: Vec<_> = db.get_review_queue_files(id).unwrap().into_iter().map(|e| e.decision).collect();
+        assert_eq!(decisions, [Some(ReviewDecision::Rated), Some(ReviewDecision::Skipped), None]);
+
+        // Going back moves the position but keeps the decisions
+        assert!(db.set_review_position(id, ids[0], 400).unwrap());
+        db.record_review_decision(id, ids[0], ReviewDecision::Skipped, 400).unwrap();
+        db.set_review_position(id, ids[0], 400).unwrap();
+        assert_eq!(db.get_review_queue_files(id).unwrap()[0].decision, Some(ReviewDecision::Rated));
+        let queue = db.get_review_queue(id).unwrap().unwrap();
+        assert_eq!((queue.position, queue.decided), (0, 2));
+        assert!(!queue.is_finished());
+
+        db.record_review_decision(id, ids[2], ReviewDecision::Rated, 500).unwrap();
+        let queue = db.get_review_queue(id).unwrap().unwrap();
+        assert_eq!(queue.position, 3);
+        assert!(queue.is_finished());
+
+        let other = db.insert_directory("other", None, None).unwrap();
+        let outside = db.insert_file(other, "d.jpg", 100, 0, None).unwrap();
+        assert!(!db.record_review_decision(id, outside, ReviewDecision::Rated, 600).unwrap());
+        assert!(!db.set_review_position(id, outside, 600).unwrap());
+    }
+
+    #[test]
+    fn test_deleted_files_leave_the_queue() {
+        let (db, ids) = setup();
+        let id = db.create_review_queue("June", "", &ids, 100).unwrap();
+        db.set_review_position(id, ids[2], 200).unwrap();
+        db.delete_file(ids[0]).unwrap();
+
+        let queue = db.get_review_queue(id).unwrap().unwrap();
+        assert_eq!((queue.position, queue.total), (1, 2));
+        assert_eq!(db.get_review_queue_files(id).unwrap().len(), 2);
+
+        assert!(db.delete_review_queue("june").unwrap());
+        assert!(!db.delete_review_queue("june").unwrap());
+        assert!(db.get_review_queues().unwrap().is_empty());
+    }
+}
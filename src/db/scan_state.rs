@@ -0,0 +1,41 @@
+use anyhow::Result;
+use rusqlite::OptionalExtension;
+
+use super::Database;
+
+impl Database {
+    /// The `[scan] dotfiles` setting the last sync applied, if any sync has
+    /// recorded one
+    pub fn applied_dotfiles(&self) -> Result<Option<bool>> {
+        let dotfiles = self
+            .connection()
+            .query_row("SELECT dotfiles FROM scan_state WHERE id = 1", [], |row| row.get(0))
+            .optional()?;
+        Ok(dotfiles)
+    }
+
+    /// Record the `[scan] dotfiles` setting a sync applied
+    pub fn set_applied_dotfiles(&self, dotfiles: bool) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO scan_state (id, dotfiles) VALUES (1, ?1)
+             ON CONFLICT(id) DO UPDATE SET dotfiles = excluded.dotfiles",
+            [dotfiles],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_applied_dotfiles_round_trip() {
+        let db = Database::open_in_memory().unwrap();
+        assert_eq!(db.applied_dotfiles().unwrap(), None);
+        db.set_applied_dotfiles(true).unwrap();
+        assert_eq!(db.applied_dotfiles().unwrap(), Some(true));
+        db.set_applied_dotfiles(false).unwrap();
+        assert_eq!(db.applied_dotfiles().unwrap(), Some(false));
+    }
+}
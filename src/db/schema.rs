@@ -2,17 +2,34 @@ use anyhow::Result;
 use rusqlite::Connection;
 use std::path::Path;
 
+use super::integrity::check_database;
+use super::tuning::fast_profile;
+
+/// `user_version` the last migration sets; bump it with each new one
+pub(super) const SCHEMA_VERSION: i32 = 16;
+
 /// Database wrapper for picman
 pub struct Database {
     conn: Connection,
 }
 
 impl Database {
-    /// Open or create database at the given path
+    /// Open or create database at the given path. A damaged database is
+    /// replaced by its backup, or fails with a clear error, before anything
+    /// reads it (see [`check_database`]); a sound one is backed up daily.
     pub fn open(path: &Path) -> Result<Self> {
+        check_database(path)?;
+        let existed = std::fs::metadata(path).is_ok_and(|m| m.len() > 0);
+
         let conn = Connection::open(path)?;
+        // Other picman processes (daemon jobs, CLI, TUI) may hold the write lock briefly
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
         let db = Self { conn };
+        db.apply_tuning(fast_profile())?;
         db.initialize_schema()?;
+        if existed {
+            db.refresh_backup(path);
+        }
         Ok(db)
     }
 
@@ -27,6 +44,8 @@ impl Database {
     fn initialize_schema(&self) -> Result<()> {
         self.create_tables()?;
         self.run_migrations()?;
+        self.create_metadata_change_log()?;
+        self.create_library_stats()?;
         Ok(())
     }
 
@@ -37,8 +56,9 @@ impl Database {
                 id INTEGER PRIMARY KEY,
                 path TEXT UNIQUE NOT NULL,
                 parent_id INTEGER REFERENCES directories(id),
-                rating INTEGER CHECK (rating IS NULL OR (rating >= 1 AND rating <= 5)),
-                mtime INTEGER
+                rating REAL CHECK (rating IS NULL OR (rating >= 1 AND rating <= 10)),
+                mtime INTEGER,
+                version INTEGER NOT NULL DEFAULT 0
             );
 
             CREATE TABLE IF NOT EXISTS files (
@@ -48,11 +68,23 @@ impl Database {
                 size INTEGER NOT NULL,
                 mtime INTEGER NOT NULL,
                 hash TEXT,
-                rating INTEGER CHECK (rating IS NULL OR (rating >= 1 AND rating <= 5)),
-                media_type TEXT CHECK (media_type IN ('image', 'video', 'other')),
+                rating REAL CHECK (rating IS NULL OR (rating >= 1 AND rating <= 10)),
+                media_type TEXT CHECK (media_type IN ('image', 'video', 'document', 'other')),
                 width INTEGER,
                 height INTEGER,
                 perceptual_hash INTEGER,
+                stack_id INTEGER,
+                hash_algorithm TEXT,
+                camera TEXT,
+                lens TEXT,
+                gear_checked INTEGER NOT NULL DEFAULT 0,
+                iso INTEGER,
+                aperture REAL,
+                exposure_time REAL,
+                screenshot_checked INTEGER NOT NULL DEFAULT 0,
+                duration REAL,
+                duration_checked INTEGER NOT NULL DEFAULT 0,
+                version INTEGER NOT NULL DEFAULT 0,
                 UNIQUE(directory_id, filename)
             );
 
@@ -73,9 +105,184 @@ impl Database {
                 PRIMARY KEY (directory_id, tag_id)
             );
 
+            CREATE TABLE IF NOT EXISTS maintenance_runs (
+                task TEXT PRIMARY KEY,
+                last_run INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                success INTEGER NOT NULL,
+                summary TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS operation_history (
+                id INTEGER PRIMARY KEY,
+                operation TEXT NOT NULL,
+                directory TEXT NOT NULL,
+                finished_at INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                items INTEGER NOT NULL,
+                cancelled INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE TABLE IF NOT EXISTS filter_presets (
+                name TEXT PRIMARY KEY,
+                min_rating REAL,
+                unrated INTEGER NOT NULL DEFAULT 0,
+                media_type TEXT,
+                tags TEXT NOT NULL DEFAULT '[]',
+                camera TEXT,
+                lens TEXT,
+                orientation TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS thumbnail_failures (
+                file_id INTEGER PRIMARY KEY,
+                mtime INTEGER NOT NULL,
+                reason TEXT NOT NULL,
+                permanent INTEGER NOT NULL,
+                failed_at INTEGER NOT NULL
+            );
+
+            -- Arbitrary user metadata (camera body, client, print status, ...)
+            CREATE TABLE IF NOT EXISTS file_attributes (
+                file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (file_id, key)
+            );
+
+            -- Same-stem siblings shown as one asset (IMG_1.MOV belongs to IMG_1.JPG)
+            CREATE TABLE IF NOT EXISTS file_pairs (
+                file_id INTEGER PRIMARY KEY REFERENCES files(id),
+                primary_id INTEGER NOT NULL REFERENCES files(id)
+            );
+
+            -- Siblings whose names clash on case-insensitive or normalizing
+            -- filesystems (IMG_1.jpg vs img_1.JPG), keyed by the folded name
+            CREATE TABLE IF NOT EXISTS name_collisions (
+                file_id INTEGER PRIMARY KEY REFERENCES files(id) ON DELETE CASCADE,
+                collision_key TEXT NOT NULL
+            );
+
+            -- Originals uploaded to cold storage by `picman archive`; stubbed
+            -- files have a placeholder on disk until `picman restore`
+            CREATE TABLE IF NOT EXISTS archived_files (
+                file_id INTEGER PRIMARY KEY REFERENCES files(id) ON DELETE CASCADE,
+                location TEXT NOT NULL,
+                hash TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                archived_at INTEGER NOT NULL,
+                stubbed INTEGER NOT NULL DEFAULT 0,
+                hash_algorithm TEXT NOT NULL DEFAULT 'xxh3'
+            );
+
+            -- Directories pinned to the top of the TUI tree, in pin order
+            CREATE TABLE IF NOT EXISTS pinned_directories (
+                directory_id INTEGER PRIMARY KEY REFERENCES directories(id) ON DELETE CASCADE,
+                pinned_at INTEGER NOT NULL
+            );
+
+            -- Directories kept indexed but hidden, with everything below
+            -- them, from the TUI tree, `list` and `dupes`
+            CREATE TABLE IF NOT EXISTS hidden_directories (
+                directory_id INTEGER PRIMARY KEY REFERENCES directories(id) ON DELETE CASCADE,
+                hidden_at INTEGER NOT NULL
+            );
+
+            -- Directories found to have all their thumbnails, at the
+            -- directory mtime of that check; a changed mtime means recheck
+            CREATE TABLE IF NOT EXISTS thumbnail_presence (
+                directory_id INTEGER PRIMARY KEY REFERENCES directories(id) ON DELETE CASCADE,
+                mtime INTEGER
+            );
+
+            -- When each file was last looked at (TUI preview/open, web
+            -- preview) and last had its rating, tags or attributes edited
+            CREATE TABLE IF NOT EXISTS file_activity (
+                file_id INTEGER PRIMARY KEY REFERENCES files(id) ON DELETE CASCADE,
+                viewed_at INTEGER,
+                edited_at INTEGER
+            );
+
+            -- Exact duplicates replaced by `picman dedupe --link` with a
+            -- hardlink or reflink to the copy that was kept
+            CREATE TABLE IF NOT EXISTS file_links (
+                file_id INTEGER PRIMARY KEY REFERENCES files(id) ON DELETE CASCADE,
+                source_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                kind TEXT NOT NULL CHECK (kind IN ('hardlink', 'reflink')),
+                linked_at INTEGER NOT NULL
+            );
+
+            -- File pairs marked "not a duplicate, keep both" (file_a < file_b),
+            -- left out of exact and similar duplicate groups
+            CREATE TABLE IF NOT EXISTS duplicate_ignores (
+                file_a INTEGER NOT NULL,
+                file_b INTEGER NOT NULL,
+                ignored_at INTEGER NOT NULL,
+                PRIMARY KEY (file_a, file_b)
+            );
+
+            -- When each user last applied each tag, for the TUI tag
+            -- popup's row of recent tags on the number keys
+            CREATE TABLE IF NOT EXISTS recent_tags (
+                user TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                used_at INTEGER NOT NULL,
+                PRIMARY KEY (user, tag)
+            );
+
+            -- Other names for a tag ("nyc" for "new york"): tagging, filtering
+            -- and tag search with an alias use the canonical tag
+            CREATE TABLE IF NOT EXISTS tag_aliases (
+                alias TEXT COLLATE NOCASE PRIMARY KEY,
+                canonical TEXT NOT NULL
+            );
+
+            -- Top-level directories an unfinished full sync has committed,
+            -- so `sync --resume` can skip them
+            CREATE TABLE IF NOT EXISTS sync_checkpoints (
+                directory TEXT PRIMARY KEY,
+                files INTEGER NOT NULL,
+                first_new_directory_id INTEGER NOT NULL
+            );
+
+            -- The `[scan] dotfiles` setting the last sync applied, so sync
+            -- only purges dotfiles when it is switched off
+            CREATE TABLE IF NOT EXISTS scan_state (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                dotfiles INTEGER NOT NULL
+            );
+
+            -- Review sessions (`picman review`): the files a filter matched,
+            -- in review order (seq), what was decided for each, and the seq
+            -- the review is at, so a review can be resumed later
+            CREATE TABLE IF NOT EXISTS review_queues (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                filter TEXT NOT NULL,
+                position INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS review_queue_files (
+                queue_id INTEGER NOT NULL REFERENCES review_queues(id) ON DELETE CASCADE,
+                seq INTEGER NOT NULL,
+                file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                decision TEXT CHECK (decision IN ('rated', 'skipped')),
+                decided_at INTEGER,
+                PRIMARY KEY (queue_id, seq)
+            );
+
             CREATE INDEX IF NOT EXISTS idx_files_hash ON files(hash);
             CREATE INDEX IF NOT EXISTS idx_files_directory ON files(directory_id);
             CREATE INDEX IF NOT EXISTS idx_directories_parent ON directories(parent_id);
+            CREATE INDEX IF NOT EXISTS idx_file_pairs_primary ON file_pairs(primary_id);
+            CREATE INDEX IF NOT EXISTS idx_name_collisions_key ON name_collisions(collision_key);
+            CREATE INDEX IF NOT EXISTS idx_file_activity_viewed ON file_activity(viewed_at);
+            CREATE INDEX IF NOT EXISTS idx_file_activity_edited ON file_activity(edited_at);
+            CREATE INDEX IF NOT EXISTS idx_file_links_source ON file_links(source_id);
+            CREATE INDEX IF NOT EXISTS idx_duplicate_ignores_b ON duplicate_ignores(file_b);
+            CREATE INDEX IF NOT EXISTS idx_review_queue_files_file ON review_queue_files(file_id);
             "#,
         )?;
         Ok(())
@@ -111,6 +318,317 @@ impl Database {
             )?;
         }
 
+        if version < 3 {
+            // Allow media_type 'document'. SQLite can't alter a CHECK
+            // constraint, so rebuild the files table (ids are preserved).
+            // Dropping `files` would cascade into `file_tags`, so foreign
+            // keys are off meanwhile, as in v7.
+            let foreign_keys: bool = self
+                .conn
+                .query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+            self.conn.execute_batch("PRAGMA foreign_keys = OFF")?;
+            self.conn.execute_batch(
+                r#"
+                BEGIN;
+                CREATE TABLE files_new (
+                    id INTEGER PRIMARY KEY,
+                    directory_id INTEGER NOT NULL REFERENCES directories(id),
+                    filename TEXT NOT NULL,
+                    size INTEGER NOT NULL,
+                    mtime INTEGER NOT NULL,
+                    hash TEXT,
+                    rating INTEGER CHECK (rating IS NULL OR (rating >= 1 AND rating <= 5)),
+                    media_type TEXT CHECK (media_type IN ('image', 'video', 'document', 'other')),
+                    width INTEGER,
+                    height INTEGER,
+                    perceptual_hash INTEGER,
+                    UNIQUE(directory_id, filename)
+                );
+                INSERT INTO files_new
+                    SELECT id, directory_id, filename, size, mtime, hash, rating, media_type,
+                           width, height, perceptual_hash
+                    FROM files;
+                DROP TABLE files;
+                ALTER TABLE files_new RENAME TO files;
+                CREATE INDEX IF NOT EXISTS idx_files_hash ON files(hash);
+                CREATE INDEX IF NOT EXISTS idx_files_directory ON files(directory_id);
+                CREATE INDEX IF NOT EXISTS idx_files_perceptual_hash ON files(perceptual_hash);
+                PRAGMA user_version = 3;
+                COMMIT;
+                "#,
+            )?;
+            if foreign_keys {
+                self.conn.execute_batch("PRAGMA foreign_keys = ON")?;
+            }
+        }
+
+        if version < 4 {
+            // Manual stacks: files sharing a stack_id collapse into one entry
+            let _ = self
+                .conn
+                .execute("ALTER TABLE files ADD COLUMN stack_id INTEGER", []);
+            self.conn.execute_batch(
+                "CREATE INDEX IF NOT EXISTS idx_files_stack ON files(stack_id);
+                 PRAGMA user_version = 4;",
+            )?;
+        }
+
+        if version < 5 {
+            // Name collisions are otherwise only found for directories that
+            // sync rescans, so flag the existing library once
+            self.rebuild_all_name_collisions()?;
+            self.conn.execute_batch("PRAGMA user_version = 5")?;
+        }
+
+        if version < 6 {
+            // Hashes record their algorithm; everything hashed so far was xxh3
+            let _ = self
+                .conn
+                .execute("ALTER TABLE files ADD COLUMN hash_algorithm TEXT", []);
+            let _ = self.conn.execute(
+                "ALTER TABLE archived_files ADD COLUMN hash_algorithm TEXT NOT NULL DEFAULT 'xxh3'",
+                [],
+            );
+            self.conn.execute_batch(
+                "UPDATE files SET hash_algorithm = 'xxh3'
+                 WHERE hash IS NOT NULL AND hash_algorithm IS NULL;
+                 PRAGMA user_version = 6;",
+            )?;
+        }
+
+        if version < 7 {
+            // Libraries may use a 1-10 rating scale, so widen the rating
+            // CHECK on both tables. Values are only rescaled by `picman
+            // rating-scale`, never here. Dropping `directories` would trip
+            // the foreign keys pointing at it, so they are off meanwhile
+            // (the pragma is a no-op inside a transaction).
+            let foreign_keys: bool = self
+                .conn
+                .query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+            self.conn.execute_batch("PRAGMA foreign_keys = OFF")?;
+            self.conn.execute_batch(
+                r#"
+                BEGIN;
+                CREATE TABLE directories_new (
+                    id INTEGER PRIMARY KEY,
+                    path TEXT UNIQUE NOT NULL,
+                    parent_id INTEGER REFERENCES directories(id),
+                    rating INTEGER CHECK (rating IS NULL OR (rating >= 1 AND rating <= 10)),
+                    mtime INTEGER
+                );
+                INSERT INTO directories_new
+                    SELECT id, path, parent_id, rating, mtime FROM directories;
+                DROP TABLE directories;
+                ALTER TABLE directories_new RENAME TO directories;
+                CREATE INDEX IF NOT EXISTS idx_directories_parent ON directories(parent_id);
+
+                CREATE TABLE files_new (
+                    id INTEGER PRIMARY KEY,
+                    directory_id INTEGER NOT NULL REFERENCES directories(id),
+                    filename TEXT NOT NULL,
+                    size INTEGER NOT NULL,
+                    mtime INTEGER NOT NULL,
+                    hash TEXT,
+                    rating INTEGER CHECK (rating IS NULL OR (rating >= 1 AND rating <= 10)),
+                    media_type TEXT CHECK (media_type IN ('image', 'video', 'document', 'other')),
+                    width INTEGER,
+                    height INTEGER,
+                    perceptual_hash INTEGER,
+                    stack_id INTEGER,
+                    hash_algorithm TEXT,
+                    UNIQUE(directory_id, filename)
+                );
+                INSERT INTO files_new
+                    SELECT id, directory_id, filename, size, mtime, hash, rating, media_type,
+                           width, height, perceptual_hash, stack_id, hash_algorithm
+                    FROM files;
+                DROP TABLE files;
+                ALTER TABLE files_new RENAME TO files;
+                CREATE INDEX IF NOT EXISTS idx_files_hash ON files(hash);
+                CREATE INDEX IF NOT EXISTS idx_files_directory ON files(directory_id);
+                CREATE INDEX IF NOT EXISTS idx_files_perceptual_hash ON files(perceptual_hash);
+                CREATE INDEX IF NOT EXISTS idx_files_stack ON files(stack_id);
+                PRAGMA user_version = 7;
+                COMMIT;
+                "#,
+            )?;
+            if foreign_keys {
+                self.conn.execute_batch("PRAGMA foreign_keys = ON")?;
+            }
+        }
+
+        if version < 8 {
+            // Half-star ratings: store ratings as REAL. Rebuilt the same way
+            // as v7; whole-number ratings keep their values.
+            let foreign_keys: bool = self
+                .conn
+                .query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+            self.conn.execute_batch("PRAGMA foreign_keys = OFF")?;
+            self.conn.execute_batch(
+                r#"
+                BEGIN;
+                CREATE TABLE directories_new (
+                    id INTEGER PRIMARY KEY,
+                    path TEXT UNIQUE NOT NULL,
+                    parent_id INTEGER REFERENCES directories(id),
+                    rating REAL CHECK (rating IS NULL OR (rating >= 1 AND rating <= 10)),
+                    mtime INTEGER
+                );
+                INSERT INTO directories_new
+                    SELECT id, path, parent_id, rating, mtime FROM directories;
+                DROP TABLE directories;
+                ALTER TABLE directories_new RENAME TO directories;
+                CREATE INDEX IF NOT EXISTS idx_directories_parent ON directories(parent_id);
+
+                CREATE TABLE files_new (
+                    id INTEGER PRIMARY KEY,
+                    directory_id INTEGER NOT NULL REFERENCES directories(id),
+                    filename TEXT NOT NULL,
+                    size INTEGER NOT NULL,
+                    mtime INTEGER NOT NULL,
+                    hash TEXT,
+                    rating REAL CHECK (rating IS NULL OR (rating >= 1 AND rating <= 10)),
+                    media_type TEXT CHECK (media_type IN ('image', 'video', 'document', 'other')),
+                    width INTEGER,
+                    height INTEGER,
+                    perceptual_hash INTEGER,
+                    stack_id INTEGER,
+                    hash_algorithm TEXT,
+                    UNIQUE(directory_id, filename)
+                );
+                INSERT INTO files_new
+                    SELECT id, directory_id, filename, size, mtime, hash, rating, media_type,
+                           width, height, perceptual_hash, stack_id, hash_algorithm
+                    FROM files;
+                DROP TABLE files;
+                ALTER TABLE files_new RENAME TO files;
+                CREATE INDEX IF NOT EXISTS idx_files_hash ON files(hash);
+                CREATE INDEX IF NOT EXISTS idx_files_directory ON files(directory_id);
+                CREATE INDEX IF NOT EXISTS idx_files_perceptual_hash ON files(perceptual_hash);
+                CREATE INDEX IF NOT EXISTS idx_files_stack ON files(stack_id);
+
+                CREATE TABLE filter_presets_new (
+                    name TEXT PRIMARY KEY,
+                    min_rating REAL,
+                    unrated INTEGER NOT NULL DEFAULT 0,
+                    media_type TEXT,
+                    tags TEXT NOT NULL DEFAULT '[]'
+                );
+                INSERT INTO filter_presets_new
+                    SELECT name, min_rating, unrated, media_type, tags FROM filter_presets;
+                DROP TABLE filter_presets;
+                ALTER TABLE filter_presets_new RENAME TO filter_presets;
+                PRAGMA user_version = 8;
+                COMMIT;
+                "#,
+            )?;
+            if foreign_keys {
+                self.conn.execute_batch("PRAGMA foreign_keys = ON")?;
+            }
+        }
+
+        if version < 9 {
+            // Camera body and lens from EXIF, filled in by sync. gear_checked
+            // marks files already read so ones without EXIF aren't re-read
+            for sql in [
+                "ALTER TABLE files ADD COLUMN camera TEXT",
+                "ALTER TABLE files ADD COLUMN lens TEXT",
+                "ALTER TABLE files ADD COLUMN gear_checked INTEGER NOT NULL DEFAULT 0",
+                "ALTER TABLE filter_presets ADD COLUMN camera TEXT",
+                "ALTER TABLE filter_presets ADD COLUMN lens TEXT",
+            ] {
+                let _ = self.conn.execute(sql, []);
+            }
+            self.conn.execute_batch(
+                "CREATE INDEX IF NOT EXISTS idx_files_camera ON files(camera);
+                 CREATE INDEX IF NOT EXISTS idx_files_lens ON files(lens);
+                 PRAGMA user_version = 9;",
+            )?;
+        }
+
+        if version < 10 {
+            // ISO, f-number and exposure time (seconds) for range filters.
+            // Images already read for gear are read again to fill them in
+            for sql in [
+                "ALTER TABLE files ADD COLUMN iso INTEGER",
+                "ALTER TABLE files ADD COLUMN aperture REAL",
+                "ALTER TABLE files ADD COLUMN exposure_time REAL",
+            ] {
+                let _ = self.conn.execute(sql, []);
+            }
+            self.conn.execute_batch(
+                "UPDATE files SET gear_checked = 0 WHERE media_type = 'image';
+                 CREATE INDEX IF NOT EXISTS idx_files_iso ON files(iso);
+                 CREATE INDEX IF NOT EXISTS idx_files_aperture ON files(aperture);
+                 CREATE INDEX IF NOT EXISTS idx_files_exposure_time ON files(exposure_time);
+                 PRAGMA user_version = 10;",
+            )?;
+        }
+
+        if version < 11 {
+            // Presets can filter on orientation, worked out from dimensions
+            // rather than the landscape/portrait tags sync used to add
+            let _ = self
+                .conn
+                .execute("ALTER TABLE filter_presets ADD COLUMN orientation TEXT", []);
+            self.conn.execute_batch("PRAGMA user_version = 11;")?;
+        }
+
+        if version < 12 {
+            // Marks images `sync --screenshots` has looked at, so a removed
+            // auto:screenshot tag isn't added back
+            let _ = self.conn.execute(
+                "ALTER TABLE files ADD COLUMN screenshot_checked INTEGER NOT NULL DEFAULT 0",
+                [],
+            );
+            self.conn.execute_batch("PRAGMA user_version = 12;")?;
+        }
+
+        if version < 13 {
+            // Video length in seconds, read with ffprobe by sync.
+            // duration_checked marks videos ffprobe couldn't read
+            for sql in [
+                "ALTER TABLE files ADD COLUMN duration REAL",
+                "ALTER TABLE files ADD COLUMN duration_checked INTEGER NOT NULL DEFAULT 0",
+            ] {
+                let _ = self.conn.execute(sql, []);
+            }
+            self.conn.execute_batch("PRAGMA user_version = 13;")?;
+        }
+
+        if version < 14 {
+            // Bumped by triggers on each rating or tag change, so web clients
+            // can tell when a write would overwrite someone else's
+            for sql in [
+                "ALTER TABLE files ADD COLUMN version INTEGER NOT NULL DEFAULT 0",
+                "ALTER TABLE directories ADD COLUMN version INTEGER NOT NULL DEFAULT 0",
+            ] {
+                let _ = self.conn.execute(sql, []);
+            }
+            self.conn.execute_batch("PRAGMA user_version = 14;")?;
+        }
+
+        if version < 15 {
+            // Hash of each file's size and first and last 64KB, so only
+            // files that share one need a full hash to find duplicates
+            let _ = self.conn.execute("ALTER TABLE files ADD COLUMN quick_hash TEXT", []);
+            self.conn.execute_batch(
+                "CREATE INDEX IF NOT EXISTS idx_files_quick_hash ON files(quick_hash);
+                 PRAGMA user_version = 15;",
+            )?;
+        }
+
+        if version < 16 {
+            // Capture day ("YYYY-MM-DD") from EXIF, for the activity heatmap.
+            // Images already read for gear are read again to fill it in
+            let _ = self.conn.execute("ALTER TABLE files ADD COLUMN taken_at TEXT", []);
+            self.conn.execute_batch(
+                "UPDATE files SET gear_checked = 0 WHERE media_type = 'image';
+                 CREATE INDEX IF NOT EXISTS idx_files_taken_at ON files(taken_at);
+                 PRAGMA user_version = 16;",
+            )?;
+        }
+
         Ok(())
     }
 
@@ -167,8 +685,8 @@ mod tests {
     fn test_directory_rating_constraints() {
         let db = Database::open_in_memory().unwrap();
 
-        // Valid ratings (1-5)
-        for rating in 1..=5 {
+        // Valid ratings (1-10, wide enough for either rating scale)
+        for rating in 1..=10 {
             db.conn
                 .execute(
                     &format!(
@@ -188,12 +706,12 @@ mod tests {
             )
             .expect("Should accept NULL rating");
 
-        // Rating too high (6)
+        // Rating too high (11)
         let result = db.conn.execute(
-            "INSERT INTO directories (path, rating) VALUES ('test_high', 6)",
+            "INSERT INTO directories (path, rating) VALUES ('test_high', 11)",
             [],
         );
-        assert!(result.is_err(), "Should reject rating > 5");
+        assert!(result.is_err(), "Should reject rating > 10");
 
         // Rating too low (0)
         let result = db.conn.execute(
@@ -341,7 +859,7 @@ mod tests {
             .unwrap();
 
         // Valid media types
-        for media_type in ["image", "video", "other"] {
+        for media_type in ["image", "video", "document", "other"] {
             db.conn
                 .execute(
                     "INSERT INTO files (directory_id, filename, size, mtime, media_type)
@@ -359,4 +877,177 @@ mod tests {
         );
         assert!(result.is_err(), "Should reject invalid media_type");
     }
+
+    #[test]
+    fn test_migration_v2_to_v3_allows_documents() {
+        // Simulate a version-2 database with the old media_type constraint
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE directories (
+                id INTEGER PRIMARY KEY,
+                path TEXT UNIQUE NOT NULL,
+                parent_id INTEGER REFERENCES directories(id),
+                rating INTEGER,
+                mtime INTEGER
+            );
+            CREATE TABLE files (
+                id INTEGER PRIMARY KEY,
+                directory_id INTEGER NOT NULL REFERENCES directories(id),
+                filename TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                mtime INTEGER NOT NULL,
+                hash TEXT,
+                rating INTEGER,
+                media_type TEXT CHECK (media_type IN ('image', 'video', 'other')),
+                width INTEGER,
+                height INTEGER,
+                perceptual_hash INTEGER,
+                UNIQUE(directory_id, filename)
+            );
+            CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT UNIQUE NOT NULL);
+            CREATE TABLE file_tags (file_id INTEGER, tag_id INTEGER, PRIMARY KEY (file_id, tag_id));
+            CREATE TABLE directory_tags (directory_id INTEGER, tag_id INTEGER, PRIMARY KEY (directory_id, tag_id));
+            PRAGMA user_version = 2;
+            "#,
+        )
+        .unwrap();
+
+        conn.execute("INSERT INTO directories (path) VALUES ('scans')", [])
+            .unwrap();
+        conn.execute(
+            "INSERT INTO files (id, directory_id, filename, size, mtime, hash, rating, media_type, perceptual_hash)
+             VALUES (7, 1, 'img.jpg', 100, 0, 'abc', 4, 'image', 42)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO files (id, directory_id, filename, size, mtime, media_type)
+             VALUES (8, 1, 'IMG.JPG', 100, 0, 'image')",
+            [],
+        )
+        .unwrap();
+
+        let db = Database { conn };
+        db.initialize_schema().expect("Migration from v2 to v3 should succeed");
+
+        // Existing rows keep their ids and data
+        let file = db.get_file_by_name(1, "img.jpg").unwrap().unwrap();
+        assert_eq!(file.id, 7);
+        assert_eq!(file.rating, Some(4.0));
+        assert_eq!(file.perceptual_hash, Some(42));
+
+        db.insert_file(1, "scan.pdf", 100, 0, Some("document"))
+            .expect("document media_type should be accepted after migration");
+
+        // v5 flags name collisions already in the library
+        assert_eq!(db.count_name_collisions().unwrap(), 2);
+
+        // v6 records that existing hashes are xxh3
+        let algorithm: Option<String> = db
+            .conn
+            .query_row("SELECT hash_algorithm FROM files WHERE id = 7", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(algorithm.as_deref(), Some("xxh3"));
+
+        // v7 widens the rating CHECK for 1-10 libraries and keeps later columns
+        db.conn
+            .execute("UPDATE files SET rating = 8, stack_id = 3 WHERE id = 7", [])
+            .expect("rating 8 should be accepted after migration");
+        let file = db.get_file_by_name(1, "img.jpg").unwrap().unwrap();
+        assert_eq!(file.rating, Some(8.0));
+        assert_eq!(file.hash.as_deref(), Some("abc"));
+
+        // v8 stores ratings as REAL for half stars
+        let declared: String = db
+            .conn
+            .query_row(
+                "SELECT type FROM pragma_table_info('files') WHERE name = 'rating'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(declared, "REAL");
+        assert_eq!(db.get_file_by_name(1, "img.jpg").unwrap().unwrap().rating, Some(8.0));
+    }
+
+    #[test]
+    fn test_upgrade_from_baseline_keeps_file_tags() {
+        // The original schema, with cascading file_tags and foreign keys on
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            PRAGMA foreign_keys = ON;
+            CREATE TABLE directories (
+                id INTEGER PRIMARY KEY,
+                path TEXT UNIQUE NOT NULL,
+                parent_id INTEGER REFERENCES directories(id),
+                rating INTEGER CHECK (rating IS NULL OR (rating >= 1 AND rating <= 5)),
+                mtime INTEGER
+            );
+            CREATE TABLE files (
+                id INTEGER PRIMARY KEY,
+                directory_id INTEGER NOT NULL REFERENCES directories(id),
+                filename TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                mtime INTEGER NOT NULL,
+                hash TEXT,
+                rating INTEGER CHECK (rating IS NULL OR (rating >= 1 AND rating <= 5)),
+                media_type TEXT CHECK (media_type IN ('image', 'video', 'other')),
+                width INTEGER,
+                height INTEGER,
+                perceptual_hash INTEGER,
+                UNIQUE(directory_id, filename)
+            );
+            CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT UNIQUE NOT NULL);
+            CREATE TABLE file_tags (
+                file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                PRIMARY KEY (file_id, tag_id)
+            );
+            CREATE TABLE directory_tags (
+                directory_id INTEGER NOT NULL REFERENCES directories(id) ON DELETE CASCADE,
+                tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                PRIMARY KEY (directory_id, tag_id)
+            );
+            INSERT INTO directories (id, path) VALUES (1, 'trip');
+            INSERT INTO files (id, directory_id, filename, size, mtime, media_type)
+                VALUES (1, 1, 'a.jpg', 100, 0, 'image'), (2, 1, 'b.jpg', 100, 0, 'image');
+            INSERT INTO tags (id, name) VALUES (1, 'beach'), (2, 'family');
+            INSERT INTO file_tags VALUES (1, 1), (1, 2), (2, 1);
+            INSERT INTO directory_tags VALUES (1, 2);
+            PRAGMA user_version = 2;
+            "#,
+        )
+        .unwrap();
+
+        let db = Database { conn };
+        db.initialize_schema().expect("Upgrade from the baseline schema should succeed");
+
+        assert_eq!(db.get_file_tags(1).unwrap(), vec!["beach", "family"]);
+        assert_eq!(db.get_file_tags(2).unwrap(), vec!["beach"]);
+        assert_eq!(db.get_directory_tags(1).unwrap(), vec!["family"]);
+        let foreign_keys: bool = db
+            .conn
+            .query_row("PRAGMA foreign_keys", [], |row| row.get(0))
+            .unwrap();
+        assert!(foreign_keys, "foreign keys should be back on after the upgrade");
+    }
+
+    #[test]
+    fn test_fresh_database_has_stack_id() {
+        let db = Database::open_in_memory().unwrap();
+        let version: i32 = db
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, 16);
+
+        // The v3 table rebuild drops columns it doesn't know about; v4 restores stack_id
+        let dir = db.insert_directory("burst", None, None).unwrap();
+        let file = db.insert_file(dir, "a.jpg", 100, 0, Some("image")).unwrap();
+        db.conn
+            .execute("UPDATE files SET stack_id = 1 WHERE id = ?1", [file])
+            .expect("stack_id column should exist after migration");
+    }
 }
@@ -0,0 +1,54 @@
+use anyhow::Result;
+
+use super::files::{file_from_row, File};
+use super::Database;
+
+impl Database {
+    /// Images not yet checked for looking like a screenshot. Only those
+    /// whose EXIF has been read qualify, since a missing camera is evidence.
+    pub fn get_files_needing_screenshot_check(&self) -> Result<Vec<File>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT id, directory_id, filename, size, mtime, hash, rating, media_type, width, height, perceptual_hash, camera, lens
+             FROM files
+             WHERE media_type = 'image' AND gear_checked = 1 AND screenshot_checked = 0
+             ORDER BY id",
+        )?;
+        let files = stmt
+            .query_map([], file_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(files)
+    }
+
+    /// Record that a file was checked, so removing a wrongly added
+    /// screenshot tag sticks
+    pub fn mark_screenshot_checked(&self, id: i64) -> Result<()> {
+        self.connection()
+            .execute("UPDATE files SET screenshot_checked = 1 WHERE id = ?1", [id])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screenshot_check_needs_gear_and_happens_once() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("shots", None, None).unwrap();
+        let read = db.insert_file(dir, "a.png", 1, 0, Some("image")).unwrap();
+        db.insert_file(dir, "b.png", 1, 0, Some("image")).unwrap();
+        db.set_file_gear(read, None, None).unwrap();
+
+        let ids: Vec<i64> = db.get_files_needing_screenshot_check().unwrap().iter().map(|f| f.id).collect();
+        assert_eq!(ids, vec![read]);
+
+        db.mark_screenshot_checked(read).unwrap();
+        assert!(db.get_files_needing_screenshot_check().unwrap().is_empty());
+
+        // A changed file is read and checked again
+        db.clear_file_gear(read).unwrap();
+        db.set_file_gear(read, None, None).unwrap();
+        assert_eq!(db.get_files_needing_screenshot_check().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::directories::BRANCH_DIRS;
+use super::Database;
+
+impl Database {
+    /// Stack the given files together, returns the new stack ID.
+    /// Files already in another stack are moved into the new one.
+    pub fn create_stack(&self, file_ids: &[i64]) -> Result<i64> {
+        let stack_id: i64 = self.connection().query_row(
+            "SELECT COALESCE(MAX(stack_id), 0) + 1 FROM files",
+            [],
+            |row| row.get(0),
+        )?;
+        for file_id in file_ids {
+            self.connection().execute(
+                "UPDATE files SET stack_id = ?1 WHERE id = ?2",
+                params![stack_id, file_id],
+            )?;
+        }
+        Ok(stack_id)
+    }
+
+    /// Split a stack back into individual files
+    pub fn dissolve_stack(&self, stack_id: i64) -> Result<()> {
+        self.connection()
+            .execute("UPDATE files SET stack_id = NULL WHERE stack_id = ?1", [stack_id])?;
+        Ok(())
+    }
+
+    /// File ID -> stack ID for stacked files in a directory
+    pub fn get_directory_stacks(&self, directory_id: i64) -> Result<HashMap<i64, i64>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT id, stack_id FROM files WHERE directory_id = ?1 AND stack_id IS NOT NULL",
+        )?;
+
+        let stacks = stmt
+            .query_map([directory_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(stacks)
+    }
+
+    /// [`Database::get_directory_stacks`] for the directory at `path` and
+    /// everything below it (the root's own files only for "")
+    pub fn get_branch_stacks(&self, path: &str) -> Result<HashMap<i64, i64>> {
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT id, stack_id FROM files WHERE directory_id IN ({}) AND stack_id IS NOT NULL",
+            BRANCH_DIRS
+        ))?;
+
+        let stacks = stmt
+            .query_map([path], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(stacks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_and_dissolve_stack() {
+        let db = Database::open_in_memory().unwrap();
+        let dir_id = db.insert_directory("burst", None, None).unwrap();
+        let a = db.insert_file(dir_id, "a.jpg", 100, 1000, Some("image")).unwrap();
+        let b = db.insert_file(dir_id, "b.jpg", 100, 1000, Some("image")).unwrap();
+        let c = db.insert_file(dir_id, "c.jpg", 100, 1000, Some("image")).unwrap();
+
+        let first = db.create_stack(&[a, b]).unwrap();
+        assert_eq!(db.get_directory_stacks(dir_id).unwrap(), HashMap::from([(a, first), (b, first)]));
+
+        // Restacking moves files out of their old stack
+        let second = db.create_stack(&[b, c]).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(
+            db.get_directory_stacks(dir_id).unwrap(),
+            HashMap::from([(a, first), (b, second), (c, second)])
+        );
+
+        db.dissolve_stack(second).unwrap();
+        assert_eq!(db.get_directory_stacks(dir_id).unwrap(), HashMap::from([(a, first)]));
+    }
+}
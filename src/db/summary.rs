@@ -0,0 +1,245 @@
+use anyhow::Result;
+
+use super::Database;
+
+/// Task name sync runs are recorded under in `maintenance_runs`
+pub const SYNC_TASK: &str = "sync";
+
+/// Library totals and outstanding background work, for the TUI dashboard
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LibrarySummary {
+    pub directories: i64,
+    pub files: i64,
+    pub bytes: i64,
+    pub images: i64,
+    pub videos: i64,
+    pub documents: i64,
+    pub rated: i64,
+    pub tagged: i64,
+    /// Files `sync --hash` hasn't hashed yet
+    pub without_hash: i64,
+    /// Images whose dimensions haven't been read yet
+    pub without_dimensions: i64,
+    /// Files thumbnail generation gave up on
+    pub thumbnail_failures: i64,
+    /// Unix timestamp (seconds) of the last finished sync
+    pub last_sync: Option<i64>,
+}
+
+/// The totals `library_stats` keeps, computed from scratch
+const LIBRARY_TOTALS: &str = "SELECT
+        (SELECT COUNT(*) FROM directories),
+        COUNT(*),
+        COALESCE(SUM(size), 0),
+        COALESCE(SUM(media_type = 'image'), 0),
+        COALESCE(SUM(media_type = 'video'), 0),
+        COALESCE(SUM(media_type = 'document'), 0),
+        COALESCE(SUM(rating IS NOT NULL), 0),
+        (SELECT COUNT(DISTINCT file_id) FROM file_tags),
+        COALESCE(SUM(hash IS NULL), 0),
+        COALESCE(SUM(media_type = 'image' AND width IS NULL), 0)
+    FROM files";
+
+/// Columns of `library_stats`, in [`LIBRARY_TOTALS`] order
+const LIBRARY_STATS_COLUMNS: &str =
+    "directories, files, bytes, images, videos, documents, rated, tagged, without_hash, without_dimensions";
+
+impl Database {
+    /// Keep the library totals in the one-row `library_stats` table,
+    /// updated by triggers on every write to files, directories and file
+    /// tags, so the summary doesn't scan the files table. Filled in from
+    /// scratch when the table is new.
+    pub(super) fn create_library_stats(&self) -> Result<()> {
+        // A file counts in a column when the expression is 1 for it
+        let counted = [
+            ("bytes", "{}.size"),
+            ("images", "({}.media_type IS 'image')"),
+            ("videos", "({}.media_type IS 'video')"),
+            ("documents", "({}.media_type IS 'document')"),
+            ("rated", "({}.rating IS NOT NULL)"),
+            ("without_hash", "({}.hash IS NULL)"),
+            ("without_dimensions", "({}.media_type IS 'image' AND {}.width IS NULL)"),
+        ];
+        let change = |sign: &str, row: &str| {
+            counted
+                .iter()
+                .map(|(column, expr)| format!("{column} = {column} {sign} {}", expr.replace("{}", row)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let added = change("+", "NEW");
+        let removed = change("-", "OLD");
+        let updated = counted
+            .iter()
+            .map(|(column, expr)| {
+                format!("{column} = {column} - {} + {}", expr.replace("{}", "OLD"), expr.replace("{}", "NEW"))
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        self.connection().execute_batch(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS library_stats (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                directories INTEGER NOT NULL,
+                files INTEGER NOT NULL,
+                bytes INTEGER NOT NULL,
+                images INTEGER NOT NULL,
+                videos INTEGER NOT NULL,
+                documents INTEGER NOT NULL,
+                rated INTEGER NOT NULL,
+                tagged INTEGER NOT NULL,
+                without_hash INTEGER NOT NULL,
+                without_dimensions INTEGER NOT NULL
+            );
+            INSERT OR IGNORE INTO library_stats (id, {LIBRARY_STATS_COLUMNS}) SELECT 1, * FROM ({LIBRARY_TOTALS});
+
+            CREATE TRIGGER IF NOT EXISTS library_stats_file_added AFTER INSERT ON files
+            BEGIN
+                UPDATE library_stats SET files = files + 1, {added};
+            END;
+            CREATE TRIGGER IF NOT EXISTS library_stats_file_removed AFTER DELETE ON files
+            BEGIN
+                UPDATE library_stats SET files = files - 1, {removed};
+            END;
+            CREATE TRIGGER IF NOT EXISTS library_stats_file_changed
+            AFTER UPDATE OF size, media_type, rating, hash, width ON files
+            BEGIN
+                UPDATE library_stats SET {updated};
+            END;
+            CREATE TRIGGER IF NOT EXISTS library_stats_directory_added AFTER INSERT ON directories
+            BEGIN
+                UPDATE library_stats SET directories = directories + 1;
+            END;
+            CREATE TRIGGER IF NOT EXISTS library_stats_directory_removed AFTER DELETE ON directories
+            BEGIN
+                UPDATE library_stats SET directories = directories - 1;
+            END;
+            CREATE TRIGGER IF NOT EXISTS library_stats_file_tagged AFTER INSERT ON file_tags
+            WHEN (SELECT COUNT(*) FROM file_tags WHERE file_id = NEW.file_id) = 1
+            BEGIN
+                UPDATE library_stats SET tagged = tagged + 1;
+            END;
+            CREATE TRIGGER IF NOT EXISTS library_stats_file_untagged AFTER DELETE ON file_tags
+            WHEN NOT EXISTS (SELECT 1 FROM file_tags WHERE file_id = OLD.file_id)
+            BEGIN
+                UPDATE library_stats SET tagged = tagged - 1;
+            END;
+            "#
+        ))?;
+        Ok(())
+    }
+
+    /// Recompute `library_stats` from the files table, e.g. after the
+    /// database was edited by hand. Returns whether the kept totals were off.
+    pub fn rebuild_library_stats(&self) -> Result<bool> {
+        let before = self.library_summary()?;
+        self.connection().execute(
+            &format!("REPLACE INTO library_stats (id, {LIBRARY_STATS_COLUMNS}) SELECT 1, * FROM ({LIBRARY_TOTALS})"),
+            [],
+        )?;
+        Ok(self.library_summary()? != before)
+    }
+
+    /// Everything in [`LibrarySummary`], from the kept totals
+    pub fn library_summary(&self) -> Result<LibrarySummary> {
+        let summary = self.connection().query_row(
+            &format!(
+                "SELECT {LIBRARY_STATS_COLUMNS},
+                     (SELECT COUNT(*) FROM thumbnail_failures),
+                     (SELECT last_run FROM maintenance_runs WHERE task = ?1 AND success)
+                 FROM library_stats"
+            ),
+            [SYNC_TASK],
+            |row| {
+                Ok(LibrarySummary {
+                    directories: row.get(0)?,
+                    files: row.get(1)?,
+                    bytes: row.get(2)?,
+                    images: row.get(3)?,
+                    videos: row.get(4)?,
+                    documents: row.get(5)?,
+                    rated: row.get(6)?,
+                    tagged: row.get(7)?,
+                    without_hash: row.get(8)?,
+                    without_dimensions: row.get(9)?,
+                    thumbnail_failures: row.get(10)?,
+                    last_sync: row.get(11)?,
+                })
+            },
+        )?;
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MaintenanceRun;
+
+    #[test]
+    fn test_library_summary() {
+        let db = Database::open_in_memory().unwrap();
+        assert_eq!(db.library_summary().unwrap(), LibrarySummary::default());
+
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let photo = db.insert_file(dir, "a.jpg", 100, 0, Some("image")).unwrap();
+        db.insert_file_with_dimensions(dir, "b.jpg", 200, 0, Some("image"), Some(40), Some(30))
+            .unwrap();
+        db.insert_file(dir, "c.mp4", 700, 0, Some("video")).unwrap();
+        db.set_file_rating(photo, Some(4.0)).unwrap();
+        db.add_file_tag(photo, "beach").unwrap();
+        db.set_file_hash(photo, "abc").unwrap();
+        db.record_maintenance_run(&MaintenanceRun {
+            task: SYNC_TASK.to_string(),
+            last_run: 1_700_000_000,
+            duration_ms: 10,
+            success: true,
+            summary: None,
+        })
+        .unwrap();
+
+        let summary = db.library_summary().unwrap();
+        assert_eq!(summary.directories, 1);
+        assert_eq!((summary.files, summary.bytes), (3, 1000));
+        assert_eq!((summary.images, summary.videos, summary.documents), (2, 1, 0));
+        assert_eq!((summary.rated, summary.tagged), (1, 1));
+        assert_eq!(summary.without_hash, 2);
+        assert_eq!(summary.without_dimensions, 1);
+        assert_eq!(summary.last_sync, Some(1_700_000_000));
+        assert!(!db.rebuild_library_stats().unwrap());
+    }
+
+    #[test]
+    fn test_library_stats_follow_writes() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let a = db.insert_file(dir, "a.jpg", 100, 0, Some("image")).unwrap();
+        let b = db.insert_file(dir, "b.mp4", 300, 0, Some("video")).unwrap();
+        db.add_file_tag(a, "beach").unwrap();
+        db.add_file_tag(a, "sunset").unwrap();
+        db.add_file_tag(b, "beach").unwrap();
+        db.set_file_rating(b, Some(3.0)).unwrap();
+        db.set_file_hash(a, "abc").unwrap();
+        db.set_file_dimensions(a, 40, 30).unwrap();
+        db.update_file_metadata(b, 500, 1).unwrap();
+        db.remove_file_tag(a, "beach").unwrap();
+        db.remove_file_tag(b, "beach").unwrap();
+        db.delete_file(a).unwrap();
+        let other = db.insert_directory("other", None, None).unwrap();
+        db.delete_directory(other).unwrap();
+
+        let kept = db.library_summary().unwrap();
+        assert_eq!((kept.directories, kept.files, kept.bytes), (1, 1, 500));
+        assert_eq!((kept.images, kept.videos, kept.rated), (0, 1, 1));
+        assert_eq!((kept.without_hash, kept.without_dimensions), (1, 0));
+        // Deleting a took its remaining tag with it
+        assert_eq!(kept.tagged, 0);
+        assert!(!db.rebuild_library_stats().unwrap());
+
+        // Writes that bypass the triggers' tables are fixed by a rebuild
+        db.connection().execute_batch("UPDATE library_stats SET files = 7").unwrap();
+        assert!(db.rebuild_library_stats().unwrap());
+        assert_eq!(db.library_summary().unwrap(), kept);
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::Database;
+
+/// What an unfinished full sync has committed so far
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncCheckpoints {
+    /// Directories with this ID or higher were added by the unfinished
+    /// sync; the final pass looks among them for moved directories
+    pub first_new_directory_id: i64,
+    /// Top-level directories already synced ("" for the files at the
+    /// library root), with how many files each had
+    pub done: HashMap<String, i64>,
+}
+
+impl Database {
+    /// Checkpoints left by a full sync that didn't finish, if any
+    pub fn get_sync_checkpoints(&self) -> Result<Option<SyncCheckpoints>> {
+        let mut stmt = self
+            .connection()
+            .prepare("SELECT directory, files, first_new_directory_id FROM sync_checkpoints")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        let Some(first_new_directory_id) = rows.iter().map(|(_, _, id)| *id).min() else {
+            return Ok(None);
+        };
+        let done = rows.into_iter().map(|(dir, files, _)| (dir, files)).collect();
+        Ok(Some(SyncCheckpoints { first_new_directory_id, done }))
+    }
+
+    /// Record that a full sync has committed `directory`
+    pub fn record_sync_checkpoint(&self, directory: &str, files: i64, first_new_directory_id: i64) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO sync_checkpoints (directory, files, first_new_directory_id)
+             VALUES (?1, ?2, ?3)",
+            params![directory, files, first_new_directory_id],
+        )?;
+        Ok(())
+    }
+
+    /// Forget the checkpoints once a full sync has finished
+    pub fn clear_sync_checkpoints(&self) -> Result<()> {
+        self.connection().execute("DELETE FROM sync_checkpoints", [])?;
+        Ok(())
+    }
+
+    /// The ID the next new directory gets
+    pub fn next_directory_id(&self) -> Result<i64> {
+        let id = self
+            .connection()
+            .query_row("SELECT COALESCE(MAX(id), 0) + 1 FROM directories", [], |row| row.get(0))?;
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_checkpoints_round_trip() {
+        let db = Database::open_in_memory().unwrap();
+        assert!(db.get_sync_checkpoints().unwrap().is_none());
+        let first = db.next_directory_id().unwrap();
+        db.insert_directory("2023", None, None).unwrap();
+        assert_eq!(db.next_directory_id().unwrap(), first + 1);
+
+        db.record_sync_checkpoint("2023", 120, first).unwrap();
+        db.record_sync_checkpoint("", 3, first).unwrap();
+        let checkpoints = db.get_sync_checkpoints().unwrap().unwrap();
+        assert_eq!(checkpoints.first_new_directory_id, first);
+        assert_eq!(checkpoints.done.get("2023"), Some(&120));
+        assert_eq!(checkpoints.done.len(), 2);
+
+        db.clear_sync_checkpoints().unwrap();
+        assert!(db.get_sync_checkpoints().unwrap().is_none());
+    }
+}
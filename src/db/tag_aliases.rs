@@ -0,0 +1,162 @@
+use anyhow::{bail, Result};
+use rusqlite::{params, OptionalExtension};
+
+use super::Database;
+
+impl Database {
+    /// The tag `name` stands for: its canonical tag if it's an alias, else
+    /// `name` itself. Aliases match case-insensitively.
+    pub fn canonical_tag(&self, name: &str) -> Result<String> {
+        let canonical: Option<String> = self
+            .connection()
+            .query_row(
+                "SELECT canonical FROM tag_aliases WHERE alias = ?1",
+                [name.trim()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(canonical.unwrap_or_else(|| name.to_string()))
+    }
+
+    /// [`Database::canonical_tag`] for each name, without duplicates
+    pub fn canonical_tags(&self, names: &[String]) -> Result<Vec<String>> {
+        let mut tags = Vec::with_capacity(names.len());
+        for name in names {
+            let tag = self.canonical_tag(name)?;
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        Ok(tags)
+    }
+
+    /// Make `alias` stand for `canonical` from now on. Files and directories
+    /// already tagged `alias` are retagged `canonical`, the `alias` tag is
+    /// deleted, and aliases pointing at `alias` are pointed at `canonical`.
+    /// Returns the number of files and directories retagged.
+    pub fn add_tag_alias(&self, alias: &str, canonical: &str) -> Result<usize> {
+        let alias = alias.trim();
+        let canonical = self.canonical_tag(canonical.trim())?;
+        if alias.is_empty() || canonical.is_empty() {
+            bail!("Tag names cannot be empty");
+        }
+        if alias.eq_ignore_ascii_case(&canonical) {
+            bail!("'{}' can't be an alias of itself", alias);
+        }
+
+        self.connection().execute(
+            "INSERT INTO tag_aliases (alias, canonical) VALUES (?1, ?2)
+             ON CONFLICT(alias) DO UPDATE SET canonical = excluded.canonical",
+            params![alias, canonical],
+        )?;
+        self.connection().execute(
+            "UPDATE tag_aliases SET canonical = ?2 WHERE canonical = ?1",
+            params![alias, canonical],
+        )?;
+
+        let old_id: Option<i64> = self
+            .connection()
+            .query_row("SELECT id FROM tags WHERE name = ?1", [alias], |row| row.get(0))
+            .optional()?;
+        let Some(old_id) = old_id else {
+            return Ok(0);
+        };
+        let new_id = self.get_or_create_tag(&canonical)?;
+        let mut retagged = 0;
+        for table in ["file_tags", "directory_tags"] {
+            retagged += self.connection().execute(
+                &format!("UPDATE OR IGNORE {table} SET tag_id = ?2 WHERE tag_id = ?1"),
+                params![old_id, new_id],
+            )?;
+            // Rows left behind already had the canonical tag too
+            self.connection()
+                .execute(&format!("DELETE FROM {table} WHERE tag_id = ?1"), [old_id])?;
+        }
+        self.connection().execute("DELETE FROM tags WHERE id = ?1", [old_id])?;
+        Ok(retagged)
+    }
+
+    /// Stop treating `alias` as another name for its tag. Returns false if
+    /// it wasn't an alias.
+    pub fn remove_tag_alias(&self, alias: &str) -> Result<bool> {
+        let removed = self
+            .connection()
+            .execute("DELETE FROM tag_aliases WHERE alias = ?1", [alias.trim()])?;
+        Ok(removed > 0)
+    }
+
+    /// Every alias as (alias, canonical), sorted by canonical tag then alias
+    pub fn get_tag_aliases(&self) -> Result<Vec<(String, String)>> {
+        let mut stmt = self
+            .connection()
+            .prepare("SELECT alias, canonical FROM tag_aliases ORDER BY canonical, alias")?;
+        let aliases = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(aliases)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aliases_canonicalize_tagging_and_filtering() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let a = db.insert_file(dir, "a.jpg", 1, 0, Some("image")).unwrap();
+
+        db.add_tag_alias("nyc", "new york").unwrap();
+        db.add_file_tag(a, "NYC").unwrap();
+        assert_eq!(db.get_file_tags(a).unwrap(), vec!["new york"]);
+        assert_eq!(db.get_files_by_tag("nyc").unwrap().len(), 1);
+
+        db.remove_file_tag(a, "nyc").unwrap();
+        assert!(db.get_file_tags(a).unwrap().is_empty());
+        assert_eq!(db.get_all_tags().unwrap(), vec!["new york"]);
+    }
+
+    #[test]
+    fn test_add_alias_merges_existing_tag() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let a = db.insert_file(dir, "a.jpg", 1, 0, Some("image")).unwrap();
+        let b = db.insert_file(dir, "b.jpg", 1, 0, Some("image")).unwrap();
+        db.add_file_tag(a, "nyc").unwrap();
+        db.add_file_tag(b, "nyc").unwrap();
+        db.add_file_tag(b, "new york").unwrap();
+        db.add_directory_tag(dir, "nyc").unwrap();
+
+        // b already had both, so only a and the directory change
+        assert_eq!(db.add_tag_alias("nyc", "new york").unwrap(), 2);
+        assert_eq!(db.get_file_tags(a).unwrap(), vec!["new york"]);
+        assert_eq!(db.get_file_tags(b).unwrap(), vec!["new york"]);
+        assert_eq!(db.get_directory_tags(dir).unwrap(), vec!["new york"]);
+        assert_eq!(db.get_all_tags().unwrap(), vec!["new york"]);
+    }
+
+    #[test]
+    fn test_alias_chains_and_removal() {
+        let db = Database::open_in_memory().unwrap();
+        db.add_tag_alias("ny", "nyc").unwrap();
+        // "nyc" becoming an alias repoints "ny" instead of chaining
+        db.add_tag_alias("nyc", "new york").unwrap();
+        // The target of a new alias is resolved too
+        db.add_tag_alias("big apple", "ny").unwrap();
+        assert_eq!(
+            db.get_tag_aliases().unwrap(),
+            vec![
+                ("big apple".to_string(), "new york".to_string()),
+                ("ny".to_string(), "new york".to_string()),
+                ("nyc".to_string(), "new york".to_string()),
+            ]
+        );
+        assert!(db.add_tag_alias("New York", "new york").is_err());
+
+        assert!(db.remove_tag_alias("ny").unwrap());
+        assert!(!db.remove_tag_alias("ny").unwrap());
+        assert_eq!(db.canonical_tag("ny").unwrap(), "ny");
+        assert_eq!(db.canonical_tags(&["nyc".into(), "new york".into()]).unwrap(), vec!["new york"]);
+    }
+}
@@ -4,11 +4,15 @@ use anyhow::Result;
 use rusqlite::{params, OptionalExtension};
 use tracing::{debug, instrument};
 
+use super::directories::{BRANCH_DIRS, SUBTREE_DIRS, SUBTREE_FILES};
 use super::Database;
 
 impl Database {
-    /// Get or create a tag by name, returns its ID
+    /// Get or create a tag by name (an alias gets its canonical tag),
+    /// returns its ID
     pub fn get_or_create_tag(&self, name: &str) -> Result<i64> {
+        let name = self.canonical_tag(name)?;
+        let name = name.as_str();
         let existing: Option<i64> = self
             .connection()
             .query_row("SELECT id FROM tags WHERE name = ?1", [name], |row| {
@@ -25,22 +29,27 @@ impl Database {
         Ok(self.connection().last_insert_rowid())
     }
 
-    /// Add a tag to a file
+    /// Add a tag to a file and any paired siblings
     pub fn add_file_tag(&self, file_id: i64, tag_name: &str) -> Result<()> {
         let tag_id = self.get_or_create_tag(tag_name)?;
-        self.connection().execute(
-            "INSERT OR IGNORE INTO file_tags (file_id, tag_id) VALUES (?1, ?2)",
-            params![file_id, tag_id],
-        )?;
+        for member in self.get_pair_group(file_id)? {
+            self.connection().execute(
+                "INSERT OR IGNORE INTO file_tags (file_id, tag_id) VALUES (?1, ?2)",
+                params![member, tag_id],
+            )?;
+        }
         Ok(())
     }
 
-    /// Remove a tag from a file
+    /// Remove a tag from a file and any paired siblings
     pub fn remove_file_tag(&self, file_id: i64, tag_name: &str) -> Result<()> {
-        self.connection().execute(
-            "DELETE FROM file_tags WHERE file_id = ?1 AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
-            params![file_id, tag_name],
-        )?;
+        let tag_name = self.canonical_tag(tag_name)?;
+        for member in self.get_pair_group(file_id)? {
+            self.connection().execute(
+                "DELETE FROM file_tags WHERE file_id = ?1 AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+                params![member, tag_name],
+            )?;
+        }
         Ok(())
     }
 
@@ -71,6 +80,7 @@ impl Database {
 
     /// Remove a tag from a directory
     pub fn remove_directory_tag(&self, directory_id: i64, tag_name: &str) -> Result<()> {
+        let tag_name = self.canonical_tag(tag_name)?;
         self.connection().execute(
             "DELETE FROM directory_tags WHERE directory_id = ?1 AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
             params![directory_id, tag_name],
@@ -174,6 +184,54 @@ impl Database {
         Ok(result)
     }
 
+    /// File tags for every file in the directory at `path` and below it
+    /// (the root's own files only for ""), as file_id -> tag names
+    pub fn get_file_tags_in_branch(&self, path: &str) -> Result<HashMap<i64, Vec<String>>> {
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT ft.file_id, t.name FROM file_tags ft
+             JOIN tags t ON ft.tag_id = t.id
+             JOIN files f ON ft.file_id = f.id
+             WHERE f.directory_id IN ({})
+             ORDER BY ft.file_id, t.name",
+            BRANCH_DIRS
+        ))?;
+
+        let mut result: HashMap<i64, Vec<String>> = HashMap::new();
+        let rows = stmt.query_map([path], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (file_id, tag_name) = row?;
+            result.entry(file_id).or_default().push(tag_name);
+        }
+        Ok(result)
+    }
+
+    /// File tags for the files in `ids`, as file_id -> tag names
+    pub fn get_file_tags_for_files(&self, ids: &[i64]) -> Result<HashMap<i64, Vec<String>>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders = vec!["?"; ids.len()].join(",");
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT ft.file_id, t.name FROM file_tags ft
+             JOIN tags t ON ft.tag_id = t.id
+             WHERE ft.file_id IN ({})
+             ORDER BY ft.file_id, t.name",
+            placeholders
+        ))?;
+
+        let mut result: HashMap<i64, Vec<String>> = HashMap::new();
+        let rows = stmt.query_map(rusqlite::params_from_iter(ids), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (file_id, tag_name) = row?;
+            result.entry(file_id).or_default().push(tag_name);
+        }
+        Ok(result)
+    }
+
     /// Get all tags in the database
     pub fn get_all_tags(&self) -> Result<Vec<String>> {
         let mut stmt = self
@@ -186,6 +244,93 @@ impl Database {
 
         Ok(tags)
     }
+
+    // ==================== Tag Propagation ====================
+
+    /// Copy a tag onto every file in a directory and its descendants.
+    /// Returns the number of files that did not have it yet.
+    pub fn tag_subtree_files(&self, dir_path: &str, tag_name: &str) -> Result<usize> {
+        let tag_id = self.get_or_create_tag(tag_name)?;
+        let added = self.connection().execute(
+            &format!(
+                "INSERT OR IGNORE INTO file_tags (file_id, tag_id)
+                 SELECT id, ?2 FROM files WHERE id IN ({SUBTREE_FILES})"
+            ),
+            params![dir_path, tag_id],
+        )?;
+        Ok(added)
+    }
+
+    /// Write the tags files inherit from their directory and its ancestors
+    /// onto the files themselves, for every file in a directory and its
+    /// descendants. Returns the number of file tags added.
+    pub fn materialize_inherited_tags(&self, dir_path: &str) -> Result<usize> {
+        let added = self.connection().execute(
+            &format!(
+                "INSERT OR IGNORE INTO file_tags (file_id, tag_id)
+                 SELECT f.id, dt.tag_id FROM files f
+                 JOIN directories d ON d.id = f.directory_id
+                 JOIN directories a
+                   ON a.path = '' OR a.path = d.path OR d.path LIKE a.path || '/%'
+                 JOIN directory_tags dt ON dt.directory_id = a.id
+                 WHERE f.id IN ({SUBTREE_FILES})"
+            ),
+            [dir_path],
+        )?;
+        Ok(added)
+    }
+
+    /// Remove a tag from a directory and every directory and file below it.
+    /// Returns (files untagged, directories untagged).
+    pub fn untag_subtree(&self, dir_path: &str, tag_name: &str) -> Result<(usize, usize)> {
+        let tag_name = self.canonical_tag(tag_name)?;
+        let files = self.connection().execute(
+            &format!(
+                "DELETE FROM file_tags
+                 WHERE tag_id = (SELECT id FROM tags WHERE name = ?2)
+                   AND file_id IN ({SUBTREE_FILES})"
+            ),
+            params![dir_path, tag_name],
+        )?;
+        let dirs = self.connection().execute(
+            &format!(
+                "DELETE FROM directory_tags
+                 WHERE tag_id = (SELECT id FROM tags WHERE name = ?2)
+                   AND directory_id IN ({SUBTREE_DIRS})"
+            ),
+            params![dir_path, tag_name],
+        )?;
+        Ok((files, dirs))
+    }
+
+    // ==================== Orientation Tags ====================
+
+    /// Remove the landscape/portrait tags `sync --orientation` added, now
+    /// that orientation is filtered on from dimensions. Only file tags the
+    /// old rule would have produced (wider than tall for landscape, taller
+    /// than wide for portrait) go, so "landscape" the genre survives on
+    /// other shapes; directory tags are left alone. Tags no longer used
+    /// anywhere are deleted. Returns the number of file tags removed.
+    pub fn remove_orientation_tags(&self) -> Result<usize> {
+        let mut removed = 0;
+        for (tag, shape) in [("landscape", "f.width > f.height"), ("portrait", "f.height > f.width")] {
+            removed += self.connection().execute(
+                &format!(
+                    "DELETE FROM file_tags
+                     WHERE tag_id = (SELECT id FROM tags WHERE name = ?1)
+                       AND file_id IN (SELECT f.id FROM files f WHERE {shape})"
+                ),
+                [tag],
+            )?;
+            self.connection().execute(
+                "DELETE FROM tags WHERE name = ?1
+                   AND NOT EXISTS (SELECT 1 FROM file_tags WHERE tag_id = tags.id)
+                   AND NOT EXISTS (SELECT 1 FROM directory_tags WHERE tag_id = tags.id)",
+                [tag],
+            )?;
+        }
+        Ok(removed)
+    }
 }
 
 #[cfg(test)]
@@ -248,6 +393,26 @@ mod tests {
         assert!(all_tags.get(&file3_id).is_none());
     }
 
+    #[test]
+    fn test_get_file_tags_for_files() {
+        let db = Database::open_in_memory().unwrap();
+        let dir_id = db.insert_directory("photos", None, None).unwrap();
+
+        let file1_id = db.insert_file(dir_id, "photo1.jpg", 1024, 12345, Some("image")).unwrap();
+        let file2_id = db.insert_file(dir_id, "photo2.jpg", 1024, 12346, Some("image")).unwrap();
+        let file3_id = db.insert_file(dir_id, "photo3.jpg", 1024, 12347, Some("image")).unwrap();
+
+        db.add_file_tag(file1_id, "vacation").unwrap();
+        db.add_file_tag(file1_id, "landscape").unwrap();
+        db.add_file_tag(file2_id, "portrait").unwrap();
+
+        let tags = db.get_file_tags_for_files(&[file1_id, file3_id]).unwrap();
+        assert_eq!(tags.get(&file1_id).unwrap(), &vec!["landscape".to_string(), "vacation".to_string()]);
+        assert!(!tags.contains_key(&file2_id));
+        assert!(!tags.contains_key(&file3_id));
+        assert!(db.get_file_tags_for_files(&[]).unwrap().is_empty());
+    }
+
     #[test]
     fn test_get_file_tags_for_directory() {
         let db = Database::open_in_memory().unwrap();
@@ -269,4 +434,59 @@ mod tests {
         assert!(dir1_tags.contains_key(&file2_id));
         assert!(!dir1_tags.contains_key(&file3_id));
     }
+
+    #[test]
+    fn test_materialize_and_untag_subtree() {
+        let db = Database::open_in_memory().unwrap();
+        let trip = db.insert_directory("trip", None, None).unwrap();
+        let day1 = db.insert_directory("trip/day1", Some(trip), None).unwrap();
+        let other = db.insert_directory("trips", None, None).unwrap();
+        let a = db.insert_file(trip, "a.jpg", 100, 1000, Some("image")).unwrap();
+        let b = db.insert_file(day1, "b.jpg", 100, 1000, Some("image")).unwrap();
+        let c = db.insert_file(other, "c.jpg", 100, 1000, Some("image")).unwrap();
+
+        db.add_directory_tag(trip, "travel").unwrap();
+        db.add_directory_tag(day1, "beach").unwrap();
+        db.add_file_tag(b, "travel").unwrap();
+
+        // Materializing only the day1 subtree still pulls in tags from trip
+        assert_eq!(db.materialize_inherited_tags("trip/day1").unwrap(), 1);
+        assert_eq!(db.get_file_tags(a).unwrap(), Vec::<String>::new());
+        assert_eq!(db.get_file_tags(b).unwrap(), vec!["beach", "travel"]);
+
+        assert_eq!(db.materialize_inherited_tags("trip").unwrap(), 1);
+        assert_eq!(db.get_file_tags(a).unwrap(), vec!["travel"]);
+        assert!(db.get_file_tags(c).unwrap().is_empty());
+
+        assert_eq!(db.tag_subtree_files("trip", "2024").unwrap(), 2);
+        assert_eq!(db.tag_subtree_files("trip", "2024").unwrap(), 0);
+
+        assert_eq!(db.untag_subtree("trip", "travel").unwrap(), (2, 1));
+        assert!(db.get_directory_tags(trip).unwrap().is_empty());
+        assert_eq!(db.get_directory_tags(day1).unwrap(), vec!["beach"]);
+        assert_eq!(db.get_file_tags(b).unwrap(), vec!["2024", "beach"]);
+    }
+
+    #[test]
+    fn test_remove_orientation_tags_keeps_genre_tags() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let wide = db.insert_file_with_dimensions(dir, "wide.jpg", 1, 0, Some("image"), Some(6000), Some(4000)).unwrap();
+        let tall = db.insert_file_with_dimensions(dir, "tall.jpg", 1, 0, Some("image"), Some(4000), Some(6000)).unwrap();
+        let unknown = db.insert_file(dir, "new.jpg", 1, 0, Some("image")).unwrap();
+        db.add_file_tag(wide, "landscape").unwrap();
+        db.add_file_tag(tall, "portrait").unwrap();
+        // A mountain shot framed upright: "landscape" here is the genre
+        db.add_file_tag(tall, "landscape").unwrap();
+        db.add_file_tag(unknown, "portrait").unwrap();
+
+        assert_eq!(db.remove_orientation_tags().unwrap(), 2);
+        assert!(db.get_file_tags(wide).unwrap().is_empty());
+        assert_eq!(db.get_file_tags(tall).unwrap(), vec!["landscape"]);
+        assert_eq!(db.get_file_tags(unknown).unwrap(), vec!["portrait"]);
+
+        db.remove_file_tag(unknown, "portrait").unwrap();
+        db.remove_orientation_tags().unwrap();
+        assert_eq!(db.get_all_tags().unwrap(), vec!["landscape"], "unused tag is deleted");
+    }
 }
@@ -0,0 +1,108 @@
+use anyhow::Result;
+use rusqlite::params;
+
+use super::Database;
+
+/// A file whose web thumbnail could not be generated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThumbnailFailure {
+    pub file_id: i64,
+    /// File mtime at the time of failure; a changed file is retried
+    pub mtime: i64,
+    pub reason: String,
+    /// Corrupt or unsupported content, skipped until the file changes
+    pub permanent: bool,
+    /// Unix timestamp (seconds) of the last failed attempt
+    pub failed_at: i64,
+}
+
+impl Database {
+    /// Record a web thumbnail failure, replacing any earlier one for the file
+    pub fn record_thumbnail_failure(&self, failure: &ThumbnailFailure) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO thumbnail_failures (file_id, mtime, reason, permanent, failed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                failure.file_id,
+                failure.mtime,
+                failure.reason,
+                failure.permanent,
+                failure.failed_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Forget a recorded failure (after a successful retry)
+    pub fn clear_thumbnail_failure(&self, file_id: i64) -> Result<()> {
+        self.connection()
+            .execute("DELETE FROM thumbnail_failures WHERE file_id = ?1", [file_id])?;
+        Ok(())
+    }
+
+    /// All recorded web thumbnail failures, most recent first
+    pub fn get_thumbnail_failures(&self) -> Result<Vec<ThumbnailFailure>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT file_id, mtime, reason, permanent, failed_at
+             FROM thumbnail_failures ORDER BY failed_at DESC, file_id",
+        )?;
+
+        let failures = stmt
+            .query_map([], |row| {
+                Ok(ThumbnailFailure {
+                    file_id: row.get(0)?,
+                    mtime: row.get(1)?,
+                    reason: row.get(2)?,
+                    permanent: row.get(3)?,
+                    failed_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(failures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(file_id: i64, permanent: bool, failed_at: i64) -> ThumbnailFailure {
+        ThumbnailFailure {
+            file_id,
+            mtime: 1000,
+            reason: "corrupt".to_string(),
+            permanent,
+            failed_at,
+        }
+    }
+
+    #[test]
+    fn test_record_and_clear_thumbnail_failures() {
+        let db = Database::open_in_memory().unwrap();
+        assert!(db.get_thumbnail_failures().unwrap().is_empty());
+
+        db.record_thumbnail_failure(&failure(1, true, 100)).unwrap();
+        db.record_thumbnail_failure(&failure(2, false, 200)).unwrap();
+        // A later attempt replaces the earlier record
+        db.record_thumbnail_failure(&failure(1, false, 300)).unwrap();
+
+        let failures = db.get_thumbnail_failures().unwrap();
+        assert_eq!(failures, vec![failure(1, false, 300), failure(2, false, 200)]);
+
+        db.clear_thumbnail_failure(1).unwrap();
+        assert_eq!(db.get_thumbnail_failures().unwrap(), vec![failure(2, false, 200)]);
+    }
+
+    #[test]
+    fn test_deleting_file_clears_failure() {
+        let db = Database::open_in_memory().unwrap();
+        let dir_id = db.insert_directory("photos", None, None).unwrap();
+        let file_id = db.insert_file(dir_id, "a.jpg", 100, 1000, None).unwrap();
+
+        db.record_thumbnail_failure(&failure(file_id, true, 100)).unwrap();
+        db.delete_file(file_id).unwrap();
+
+        assert!(db.get_thumbnail_failures().unwrap().is_empty());
+    }
+}
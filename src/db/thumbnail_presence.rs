@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::directories::SUBTREE_DIRS;
+use super::Database;
+
+impl Database {
+    /// Whether the directory was found to have all its thumbnails when last
+    /// checked at this `mtime`. False if it was never checked, was missing
+    /// some, or it or its files changed since.
+    pub fn has_thumbnail_presence(&self, directory_id: i64, mtime: Option<i64>) -> Result<bool> {
+        let found = self.connection().query_row(
+            "SELECT EXISTS (SELECT 1 FROM thumbnail_presence WHERE directory_id = ?1 AND mtime IS ?2)",
+            params![directory_id, mtime],
+            |row| row.get(0),
+        )?;
+        Ok(found)
+    }
+
+    /// Every directory found to have all its thumbnails, with the `mtime`
+    /// it had then
+    pub fn get_all_thumbnail_presence(&self) -> Result<HashMap<i64, Option<i64>>> {
+        let mut stmt = self
+            .connection()
+            .prepare("SELECT directory_id, mtime FROM thumbnail_presence")?;
+        let presence = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(presence)
+    }
+
+    /// Remember that the directory had all its thumbnails at this `mtime`
+    pub fn set_thumbnail_presence(&self, directory_id: i64, mtime: Option<i64>) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO thumbnail_presence (directory_id, mtime) VALUES (?1, ?2)",
+            params![directory_id, mtime],
+        )?;
+        Ok(())
+    }
+
+    /// Forget the check for one directory, e.g. after sync changed a file in it
+    pub fn clear_thumbnail_presence(&self, directory_id: i64) -> Result<()> {
+        self.connection()
+            .execute("DELETE FROM thumbnail_presence WHERE directory_id = ?1", [directory_id])?;
+        Ok(())
+    }
+
+    /// Forget the checks for `path` and everything below it ("" for the
+    /// whole library)
+    pub fn clear_thumbnail_presence_below(&self, path: &str) -> Result<usize> {
+        let cleared = self.connection().execute(
+            &format!("DELETE FROM thumbnail_presence WHERE directory_id IN ({})", SUBTREE_DIRS),
+            [path],
+        )?;
+        Ok(cleared)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thumbnail_presence_follows_mtime() {
+        let db = Database::open_in_memory().unwrap();
+        let trip = db.insert_directory("trip", None, Some(100)).unwrap();
+        let day = db.insert_directory("trip/day1", Some(trip), Some(100)).unwrap();
+        let other = db.insert_directory("other", None, None).unwrap();
+
+        assert!(!db.has_thumbnail_presence(trip, Some(100)).unwrap());
+        db.set_thumbnail_presence(trip, Some(100)).unwrap();
+        db.set_thumbnail_presence(day, Some(100)).unwrap();
+        db.set_thumbnail_presence(other, None).unwrap();
+        assert!(db.has_thumbnail_presence(trip, Some(100)).unwrap());
+        assert!(db.has_thumbnail_presence(other, None).unwrap());
+
+        // A changed directory is checked again
+        assert!(!db.has_thumbnail_presence(trip, Some(200)).unwrap());
+
+        assert_eq!(db.clear_thumbnail_presence_below("trip").unwrap(), 2);
+        assert!(!db.has_thumbnail_presence(day, Some(100)).unwrap());
+        assert!(db.has_thumbnail_presence(other, None).unwrap());
+
+        db.clear_thumbnail_presence(other).unwrap();
+        assert!(!db.has_thumbnail_presence(other, None).unwrap());
+    }
+}
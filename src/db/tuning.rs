@@ -0,0 +1,98 @@
+//! Opt-in SQLite tuning for big libraries, turned on by the global `--fast`
+//! flag via [`set_fast_profile`] before any database is opened.
+//!
+//! The profile trades some crash safety for speed:
+//!
+//! - `journal_mode = WAL` lets readers (the TUI, the web server) keep going
+//!   while sync writes, and turns each commit into an append to the `-wal`
+//!   file. WAL needs shared memory between processes, so it must not be used
+//!   for a database on a network filesystem. The mode is stored in the
+//!   database file and stays on for later opens without `--fast`.
+//! - `synchronous = NORMAL` stops fsyncing every commit; the WAL is synced
+//!   at checkpoints instead. A power cut or OS crash can lose the last
+//!   commits (ratings or tags set seconds before), but it cannot corrupt
+//!   the database. A crash of picman alone loses nothing.
+//! - `mmap_size` reads pages straight from the page cache instead of copying
+//!   them. An IO error on a mapped page kills the process instead of
+//!   returning an error.
+//! - `cache_size` keeps more pages in memory per connection.
+//!
+//! Without `--fast` the connection keeps SQLite's defaults.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::Result;
+
+use super::Database;
+
+/// Bytes of the database file to memory-map
+const FAST_MMAP_SIZE: i64 = 256 * 1024 * 1024;
+
+/// Page cache per connection; negative values are KiB, so 64 MiB
+const FAST_CACHE_SIZE: i64 = -64 * 1024;
+
+static FAST: AtomicBool = AtomicBool::new(false);
+
+/// Use the tuned profile for every database opened from now on
+pub fn set_fast_profile(enabled: bool) {
+    FAST.store(enabled, Ordering::Relaxed);
+}
+
+pub fn fast_profile() -> bool {
+    FAST.load(Ordering::Relaxed)
+}
+
+impl Database {
+    /// Apply the tuned pragmas when `fast` is set (normally from
+    /// [`fast_profile`]). In-memory databases are left alone.
+    pub(super) fn apply_tuning(&self, fast: bool) -> Result<()> {
+        if !fast {
+            return Ok(());
+        }
+        let conn = self.connection();
+        conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get::<_, String>(0))?;
+        conn.execute_batch(&format!(
+            "PRAGMA synchronous = NORMAL;
+             PRAGMA mmap_size = {};
+             PRAGMA cache_size = {};",
+            FAST_MMAP_SIZE, FAST_CACHE_SIZE
+        ))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn pragma<T: rusqlite::types::FromSql>(db: &Database, name: &str) -> T {
+        db.connection()
+            .query_row(&format!("PRAGMA {}", name), [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_fast_profile_sets_pragmas() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join(".picman.db");
+
+        let db = Database::open(&path).unwrap();
+        db.apply_tuning(false).unwrap();
+        assert_eq!(pragma::<String>(&db, "journal_mode"), "delete");
+        // FULL
+        assert_eq!(pragma::<i64>(&db, "synchronous"), 2);
+
+        db.apply_tuning(true).unwrap();
+        assert_eq!(pragma::<String>(&db, "journal_mode"), "wal");
+        // NORMAL
+        assert_eq!(pragma::<i64>(&db, "synchronous"), 1);
+        assert_eq!(pragma::<i64>(&db, "cache_size"), FAST_CACHE_SIZE);
+        drop(db);
+
+        // WAL is kept in the file; NORMAL is per connection
+        let db = Database::open(&path).unwrap();
+        assert_eq!(pragma::<String>(&db, "journal_mode"), "wal");
+        assert_eq!(pragma::<i64>(&db, "synchronous"), 2);
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::Database;
+use crate::library_path::LibraryPath;
+
+/// A directory (with everything below it) or a file in a disk usage tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageNode {
+    /// Last path component ("" for the library root)
+    pub name: String,
+    /// Library-relative path
+    pub path: String,
+    /// Directory or file ID; `None` for a root with no files of its own
+    pub id: Option<i64>,
+    pub is_dir: bool,
+    /// Total bytes, including subdirectories
+    pub size: i64,
+    pub file_count: i64,
+    /// Largest entries first; empty below the requested depth
+    pub children: Vec<UsageNode>,
+    /// Bytes in entries left out by the child limit
+    pub other_size: i64,
+}
+
+/// Recursive per-directory totals, keyed by path
+struct DirTotals {
+    ids: HashMap<String, i64>,
+    totals: HashMap<String, (i64, i64)>,
+    children: HashMap<String, Vec<String>>,
+}
+
+fn parent_path(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
+
+impl Database {
+    /// Disk usage below the directory at `root` ("" for the whole library),
+    /// shaped for a treemap. Nodes are expanded `depth` levels down and keep
+    /// their `limit` largest children (directories and files mixed); the rest
+    /// is summed into `other_size`. Returns `None` if `root` doesn't exist.
+    pub fn get_usage_tree(&self, root: &str, depth: usize, limit: usize) -> Result<Option<UsageNode>> {
+        let dirs = self.directory_totals()?;
+        if !root.is_empty() && !dirs.ids.contains_key(root) {
+            return Ok(None);
+        }
+        self.usage_node(&dirs, root, depth, limit).map(Some)
+    }
+
+    fn directory_totals(&self) -> Result<DirTotals> {
+        let mut stmt = self.connection().prepare(
+            "SELECT d.id, d.path, COALESCE(SUM(f.size), 0), COUNT(f.id)
+             FROM directories d
+             LEFT JOIN files f ON f.directory_id = d.id
+             GROUP BY d.id",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect::<Result<Vec<(i64, String, i64, i64)>, _>>()?;
+
+        let mut dirs = DirTotals {
+            ids: HashMap::new(),
+            totals: HashMap::new(),
+            children: HashMap::new(),
+        };
+        for (id, path, size, count) in rows {
+            // Roll each directory's own files up into every ancestor
+            let mut ancestor = path.as_str();
+            loop {
+                let total = dirs.totals.entry(ancestor.to_string()).or_default();
+                total.0 += size;
+                total.1 += count;
+                if ancestor.is_empty() {
+                    break;
+                }
+                ancestor = parent_path(ancestor);
+            }
+            if !path.is_empty() {
+                dirs.children.entry(parent_path(&path).to_string()).or_default().push(path.clone());
+            }
+            dirs.ids.insert(path, id);
+        }
+        Ok(dirs)
+    }
+
+    fn usage_node(&self, dirs: &DirTotals, path: &str, depth: usize, limit: usize) -> Result<UsageNode> {
+        let (size, file_count) = dirs.totals.get(path).copied().unwrap_or_default();
+        let mut node = UsageNode {
+            name: path.rsplit('/').next().unwrap_or_default().to_string(),
+            path: path.to_string(),
+            id: dirs.ids.get(path).copied(),
+            is_dir: true,
+            size,
+            file_count,
+            children: Vec::new(),
+            other_size: 0,
+        };
+        if depth == 0 {
+            return Ok(node);
+        }
+
+        // Placeholders for subdirectories; only the ones kept get expanded
+        let mut children: Vec<UsageNode> = dirs
+            .children
+            .get(path)
+            .into_iter()
+            .flatten()
+            .map(|child| {
+                let (size, file_count) = dirs.totals[child];
+                UsageNode {
+                    name: child.rsplit('/').next().unwrap_or_default().to_string(),
+                    path: child.clone(),
+                    id: dirs.ids.get(child).copied(),
+                    is_dir: true,
+                    size,
+                    file_count,
+                    children: Vec::new(),
+                    other_size: 0,
+                }
+            })
+            .collect();
+
+        if let Some(dir_id) = node.id {
+            let mut stmt = self.connection().prepare(
+                "SELECT id, filename, size FROM files WHERE directory_id = ?1
+                 ORDER BY size DESC, filename LIMIT ?2",
+            )?;
+            let files = stmt.query_map(params![dir_id, limit as i64], |row| {
+                let filename: String = row.get(1)?;
+                Ok(UsageNode {
+                    path: LibraryPath::file(path, &filename).into_string(),
+                    name: filename,
+                    id: Some(row.get(0)?),
+                    is_dir: false,
+                    size: row.get(2)?,
+                    file_count: 1,
+                    children: Vec::new(),
+                    other_size: 0,
+                })
+            })?;
+            for file in files {
+                children.push(file?);
+            }
+        }
+
+        children.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        children.truncate(limit);
+        for child in &mut children {
+            if child.is_dir {
+                *child = self.usage_node(dirs, &child.path, depth - 1, limit)?;
+            }
+        }
+        node.other_size = node.size - children.iter().map(|c| c.size).sum::<i64>();
+        node.children = children;
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_tree_rolls_up_sizes() {
+        let db = Database::open_in_memory().unwrap();
+        let root = db.insert_directory("", None, None).unwrap();
+        let trips = db.insert_directory("trips", None, None).unwrap();
+        let rome = db.insert_directory("trips/rome", Some(trips), None).unwrap();
+        let misc = db.insert_directory("misc", None, None).unwrap();
+        db.insert_file(root, "notes.txt", 5, 0, Some("other")).unwrap();
+        db.insert_file(trips, "map.jpg", 100, 0, Some("image")).unwrap();
+        db.insert_file(rome, "clip.mp4", 1_000, 0, Some("video")).unwrap();
+        db.insert_file(rome, "a.jpg", 50, 0, Some("image")).unwrap();
+        db.insert_file(misc, "b.jpg", 20, 0, Some("image")).unwrap();
+
+        let tree = db.get_usage_tree("", 2, 10).unwrap().unwrap();
+        assert_eq!((tree.size, tree.file_count), (1_175, 5));
+        let names: Vec<&str> = tree.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["trips", "misc", "notes.txt"]);
+
+        let trips_node = &tree.children[0];
+        assert_eq!((trips_node.size, trips_node.file_count), (1_150, 3));
+        assert_eq!(trips_node.children[0].path, "trips/rome");
+        // Depth 2 stops before listing rome's files
+        assert!(trips_node.children[0].children.is_empty());
+
+        let limited = db.get_usage_tree("trips/rome", 1, 1).unwrap().unwrap();
+        assert_eq!(limited.children.len(), 1);
+        assert_eq!(limited.children[0].path, "trips/rome/clip.mp4");
+        assert!(!limited.children[0].is_dir);
+        assert_eq!(limited.other_size, 50);
+
+        assert!(db.get_usage_tree("nope", 1, 10).unwrap().is_none());
+    }
+}
@@ -0,0 +1,124 @@
+//! Star ratings other tools already wrote into files, for `picman import-ratings`
+//! and `picman sync --import-ratings`.
+//!
+//! Lightroom, darktable, digiKam and most cameras store `xmp:Rating` (in an
+//! XMP sidecar or a packet embedded in the file); Windows Explorer and some
+//! cameras use the EXIF `Rating` tag. Both are 0-5 stars, where 0 means
+//! unrated and -1 rejected.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::scanner::find_xmp_sidecar;
+
+/// EXIF `Rating` (0x4746) in IFD0, a Microsoft extension
+const EXIF_RATING: exif::Tag = exif::Tag(exif::Context::Tiff, 0x4746);
+
+/// How far into a file to look for an embedded XMP packet. JPEG puts it in
+/// APP1 at the start; TIFF-based RAW formats keep it near the first IFD.
+const XMP_SCAN_BYTES: u64 = 1024 * 1024;
+
+/// The file's star rating (1-5), from its XMP sidecar, then an embedded XMP
+/// packet, then EXIF. `None` if none of them rates it, or it's rated 0 or
+/// rejected.
+pub fn read_embedded_rating(path: &Path) -> Option<u8> {
+    if let Some(sidecar) = find_xmp_sidecar(path) {
+        if let Some(rating) = std::fs::read_to_string(sidecar).ok().and_then(|xmp| parse_xmp_rating(&xmp)) {
+            return stars(rating);
+        }
+    }
+
+    let mut head = Vec::new();
+    File::open(path).ok()?.take(XMP_SCAN_BYTES).read_to_end(&mut head).ok()?;
+    if let Some(rating) = embedded_xmp(&head).and_then(parse_xmp_rating) {
+        return stars(rating);
+    }
+
+    read_exif_rating(path).and_then(stars)
+}
+
+/// Only 1-5 is a rating; 0 is unrated and negative values mark rejects
+fn stars(rating: i32) -> Option<u8> {
+    (1..=5).contains(&rating).then_some(rating as u8)
+}
+
+fn read_exif_rating(path: &Path) -> Option<i32> {
+    let file = File::open(path).ok()?;
+    let exif = exif::Reader::new().read_from_container(&mut BufReader::new(file)).ok()?;
+    let field = exif.get_field(EXIF_RATING, exif::In::PRIMARY)?;
+    field.value.get_uint(0).map(|v| v as i32)
+}
+
+/// The `<x:xmpmeta>` element in a file's leading bytes
+fn embedded_xmp(bytes: &[u8]) -> Option<&str> {
+    let start = find(bytes, b"<x:xmpmeta")?;
+    let end = start + find(&bytes[start..], b"</x:xmpmeta>")? + b"</x:xmpmeta>".len();
+    std::str::from_utf8(&bytes[start..end]).ok()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// `xmp:Rating` written as an attribute (`xmp:Rating="4"`) or an element
+/// (`<xmp:Rating>4</xmp:Rating>`). Old Adobe files use the `xap` prefix.
+fn parse_xmp_rating(xmp: &str) -> Option<i32> {
+    let value = ["xmp:Rating", "xap:Rating"].iter().find_map(|name| {
+        let at = xmp.find(name)? + name.len();
+        let rest = xmp[at..].trim_start();
+        if let Some(rest) = rest.strip_prefix('=') {
+            let rest = rest.trim_start();
+            let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let rest = &rest[1..];
+            Some(&rest[..rest.find(quote)?])
+        } else {
+            let rest = rest.strip_prefix('>')?;
+            Some(&rest[..rest.find('<')?])
+        }
+    })?;
+    // Some tools write "4.0"
+    value.trim().parse::<f64>().ok().map(|v| v.round() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_xmp_rating() {
+        assert_eq!(parse_xmp_rating(r#"<rdf:Description xmp:Rating="4" xmp:Label="Red"/>"#), Some(4));
+        assert_eq!(parse_xmp_rating("<xmp:Rating>3</xmp:Rating>"), Some(3));
+        assert_eq!(parse_xmp_rating("xap:Rating = '5.0'"), Some(5));
+        assert_eq!(parse_xmp_rating(r#"xmp:Rating="-1""#), Some(-1));
+        assert_eq!(parse_xmp_rating(r#"xmp:Label="Red""#), None);
+    }
+
+    #[test]
+    fn test_read_embedded_rating() {
+        let temp = TempDir::new().unwrap();
+        let xmp = |rating: &str| {
+            format!(r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:Description xmp:Rating="{rating}"/></x:xmpmeta>"#)
+        };
+
+        // A packet embedded in the file
+        let jpeg = temp.path().join("a.jpg");
+        fs::write(&jpeg, [b"\xFF\xD8\xFF\xE1junk".as_slice(), xmp("4").as_bytes()].concat()).unwrap();
+        assert_eq!(read_embedded_rating(&jpeg), Some(4));
+
+        // The sidecar wins over the file
+        fs::write(temp.path().join("a.xmp"), xmp("2")).unwrap();
+        assert_eq!(read_embedded_rating(&jpeg), Some(2));
+
+        // Unrated and rejected files have no rating to import
+        let raw = temp.path().join("b.NEF");
+        fs::write(&raw, b"raw").unwrap();
+        assert_eq!(read_embedded_rating(&raw), None);
+        fs::write(temp.path().join("b.NEF.xmp"), xmp("0")).unwrap();
+        assert_eq!(read_embedded_rating(&raw), None);
+        fs::write(temp.path().join("b.NEF.xmp"), xmp("-1")).unwrap();
+        assert_eq!(read_embedded_rating(&raw), None);
+    }
+}
@@ -0,0 +1,135 @@
+//! Filename comparison the way case-insensitive filesystems see it.
+//!
+//! macOS (APFS/HFS+) and Windows (NTFS) treat names that differ only in case
+//! as the same file, and macOS also equates Unicode composed and decomposed
+//! forms ("é" vs "e" + U+0301). Two such names can coexist on Linux but one
+//! silently overwrites the other when the library is copied or exported.
+//!
+//! Names are recorded composed (NFC), whatever form the filesystem hands
+//! them out in, so a name typed on the command line, read from the scan or
+//! hashed into a thumbnail path is spelled the same everywhere.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+/// Key under which filenames collide on case-insensitive, normalizing filesystems
+pub fn collision_key(name: &str) -> String {
+    normalize(name).to_lowercase()
+}
+
+/// The composed (NFC) spelling of a name or path: "e" + U+0301 becomes "é".
+/// Borrowed when it already is, which is nearly always.
+pub fn normalize(name: &str) -> Cow<'_, str> {
+    if is_nfc_quick(name.chars()) == IsNormalized::Yes {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(name.nfc().collect())
+    }
+}
+
+/// A name as typed, then composed and decomposed if those differ, for
+/// looking up a name that may have been recorded in either form
+pub fn spellings(name: &str) -> Vec<String> {
+    let mut spellings = vec![name.to_string()];
+    for spelling in [name.nfc().collect::<String>(), name.nfd().collect()] {
+        if !spellings.contains(&spelling) {
+            spellings.push(spelling);
+        }
+    }
+    spellings
+}
+
+/// Pair each `old` name missing from `new` with the `new` name, missing from
+/// `old`, that it only differs from in normalization form: the same entry
+/// showing up spelled another way. Pairs are sorted by old name; names with
+/// more than one candidate on either side are left unpaired.
+pub fn match_respelled<'a>(old: &HashSet<&'a str>, new: &HashSet<&'a str>) -> Vec<(&'a str, &'a str)> {
+    let mut by_key: HashMap<Cow<'a, str>, (Vec<&'a str>, Vec<&'a str>)> = HashMap::new();
+    for &name in old.difference(new) {
+        by_key.entry(normalize(name)).or_default().0.push(name);
+    }
+    for &name in new.difference(old) {
+        if let Some(group) = by_key.get_mut(&normalize(name)) {
+            group.1.push(name);
+        }
+    }
+
+    let mut pairs: Vec<(&str, &str)> = by_key
+        .into_values()
+        .filter_map(|(old, new)| match (old.as_slice(), new.as_slice()) {
+            ([old], [new]) => Some((*old, *new)),
+            _ => None,
+        })
+        .collect();
+    pairs.sort();
+    pairs
+}
+
+/// Group filenames that collide with each other. Returns index groups of two
+/// or more names, each sorted, in order of their first member.
+pub fn find_name_collisions(filenames: &[&str]) -> Vec<Vec<usize>> {
+    let mut by_key: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, name) in filenames.iter().enumerate() {
+        by_key.entry(collision_key(name)).or_default().push(i);
+    }
+
+    let mut groups: Vec<Vec<usize>> = by_key.into_values().filter(|g| g.len() > 1).collect();
+    groups.sort();
+    groups
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collision_key_folds_case_and_normalization() {
+        assert_eq!(collision_key("IMG_0001.JPG"), collision_key("img_0001.jpg"));
+        // "café" precomposed vs decomposed
+        assert_eq!(collision_key("caf\u{E9}.jpg"), collision_key("cafe\u{301}.jpg"));
+        // Stacked marks compose step by step: e + dot below + circumflex
+        assert_eq!(collision_key("Vi\u{1EC7}t.png"), collision_key("Vie\u{323}\u{302}t.png"));
+        assert_ne!(collision_key("cafe.jpg"), collision_key("caf\u{E9}.jpg"));
+    }
+
+    #[test]
+    fn test_normalize_composes_and_borrows() {
+        assert_eq!(normalize("cafe\u{301}.jpg"), "caf\u{E9}.jpg");
+        assert!(matches!(normalize("caf\u{E9}.jpg"), Cow::Borrowed(_)));
+        // Beyond Latin: Hangul jamo and Greek with stacked marks
+        assert_eq!(normalize("\u{1100}\u{1161}"), "\u{AC00}");
+        assert_eq!(normalize("\u{3B1}\u{313}\u{301}"), "\u{1F04}");
+    }
+
+    #[test]
+    fn test_spellings() {
+        assert_eq!(spellings("IMG_1.jpg"), vec!["IMG_1.jpg"]);
+        assert_eq!(spellings("caf\u{E9}"), vec!["caf\u{E9}", "cafe\u{301}"]);
+        assert_eq!(spellings("cafe\u{301}"), vec!["cafe\u{301}", "caf\u{E9}"]);
+    }
+
+    #[test]
+    fn test_match_respelled() {
+        let old: HashSet<&str> = ["cafe\u{301}", "same", "gone", "Z\u{FC}rich"].into();
+        let new: HashSet<&str> = ["caf\u{E9}", "same", "added", "Zu\u{308}rich", "Zu\u{308}rich "].into();
+        assert_eq!(
+            match_respelled(&old, &new),
+            vec![("Z\u{FC}rich", "Zu\u{308}rich"), ("cafe\u{301}", "caf\u{E9}")]
+        );
+
+        // Both forms on disk as separate entries: nothing to pair
+        let old: HashSet<&str> = ["cafe\u{301}"].into();
+        let new: HashSet<&str> = ["cafe\u{301}", "caf\u{E9}"].into();
+        assert!(match_respelled(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn test_find_name_collisions() {
+        let names = ["a.jpg", "B.jpg", "A.JPG", "b.jpg", "c.jpg", "a.jpeg"];
+        assert_eq!(find_name_collisions(&names), vec![vec![0, 2], vec![1, 3]]);
+        assert!(find_name_collisions(&["x.jpg", "y.jpg"]).is_empty());
+    }
+}
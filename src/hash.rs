@@ -1,20 +1,98 @@
+use std::fmt;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
+use std::str::FromStr;
 
 use anyhow::{Context, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use xxhash_rust::xxh3::Xxh3;
 
 const BUFFER_SIZE: usize = 64 * 1024; // 64KB buffer for streaming
 
-/// Compute xxHash3-64 hash of a file, returning a 16-char hex string
+/// Bytes read from each end of a file for its quick hash
+const QUICK_HASH_SPAN: u64 = 64 * 1024;
+
+/// Content hash algorithm. Each stored hash records the algorithm that
+/// produced it, and hashes are only compared within one algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum HashAlgorithm {
+    /// xxHash3-64, 16 hex chars (the default, and what older databases hold)
+    #[default]
+    #[serde(rename = "xxh3")]
+    Xxh3,
+    /// xxHash3-128, 32 hex chars; fewer accidental collisions in huge libraries
+    #[serde(rename = "xxh3-128")]
+    Xxh3_128,
+    /// SHA-256, 64 hex chars; slower, but matches checksums from other tools
+    #[serde(rename = "sha256")]
+    Sha256,
+    /// BLAKE3, 64 hex chars; cryptographic and about as fast as xxh3-128
+    #[serde(rename = "blake3")]
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Xxh3 => "xxh3",
+            HashAlgorithm::Xxh3_128 => "xxh3-128",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "xxh3" => Ok(HashAlgorithm::Xxh3),
+            "xxh3-128" => Ok(HashAlgorithm::Xxh3_128),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            _ => Err(format!("unknown hash algorithm '{}' (expected xxh3, xxh3-128, sha256 or blake3)", s)),
+        }
+    }
+}
+
+enum Hasher {
+    Xxh3(Box<Xxh3>),
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+/// Compute the xxHash3-64 hash of a file, returning a 16-char hex string
 pub fn compute_file_hash(path: &Path) -> Result<String> {
+    compute_file_hash_with(path, HashAlgorithm::Xxh3, |_| {})
+}
+
+/// Hash a file with the given algorithm, streaming it in 64KB chunks.
+/// `on_progress` is called with the byte count of each chunk read, so callers
+/// can show progress through multi-GB videos.
+pub fn compute_file_hash_with(
+    path: &Path,
+    algorithm: HashAlgorithm,
+    mut on_progress: impl FnMut(u64),
+) -> Result<String> {
     let file = File::open(path)
         .with_context(|| format!("Failed to open file: {}", path.display()))?;
 
     let mut reader = BufReader::with_capacity(BUFFER_SIZE, file);
     let mut buffer = vec![0u8; BUFFER_SIZE];
-    let mut hasher = Xxh3::new();
+    let mut hasher = match algorithm {
+        HashAlgorithm::Xxh3 | HashAlgorithm::Xxh3_128 => Hasher::Xxh3(Box::new(Xxh3::new())),
+        HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+        HashAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+    };
 
     loop {
         let bytes_read = reader
@@ -25,9 +103,55 @@ pub fn compute_file_hash(path: &Path) -> Result<String> {
             break;
         }
 
-        hasher.update(&buffer[..bytes_read]);
+        crate::workers::throttle(bytes_read as u64);
+        match &mut hasher {
+            Hasher::Xxh3(h) => h.update(&buffer[..bytes_read]),
+            Hasher::Sha256(h) => h.update(&buffer[..bytes_read]),
+            Hasher::Blake3(h) => {
+                h.update(&buffer[..bytes_read]);
+            }
+        }
+        on_progress(bytes_read as u64);
     }
 
+    Ok(match (hasher, algorithm) {
+        (Hasher::Xxh3(h), HashAlgorithm::Xxh3_128) => format!("{:032x}", h.digest128()),
+        (Hasher::Xxh3(h), _) => format!("{:016x}", h.digest()),
+        (Hasher::Sha256(h), _) => h
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+        (Hasher::Blake3(h), _) => h.finalize().to_hex().to_string(),
+    })
+}
+
+/// Quick hash of a file: xxHash3-64 of its size and its first and last
+/// 64KB, as 16 hex chars. Reads at most 128KB however big the file is.
+/// Files with different quick hashes differ; ones that share it may still
+/// differ in the middle, so only a full hash says they're duplicates.
+pub fn compute_quick_hash(path: &Path) -> Result<String> {
+    let mut file = File::open(path)
+        .with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let size = file
+        .metadata()
+        .with_context(|| format!("Failed to read file: {}", path.display()))?
+        .len();
+
+    let mut hasher = Xxh3::new();
+    hasher.update(&size.to_le_bytes());
+    let mut buffer = vec![0u8; QUICK_HASH_SPAN as usize];
+    // Files up to twice the span are read whole
+    let head = size.min(QUICK_HASH_SPAN);
+    let tail_start = size.saturating_sub(QUICK_HASH_SPAN).max(head);
+    for (start, len) in [(0, head), (tail_start, size - tail_start)] {
+        let chunk = &mut buffer[..len as usize];
+        file.seek(SeekFrom::Start(start))
+            .and_then(|_| file.read_exact(chunk))
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        crate::workers::throttle(len);
+        hasher.update(chunk);
+    }
     Ok(format!("{:016x}", hasher.digest()))
 }
 
@@ -88,6 +212,60 @@ mod tests {
         assert_eq!(hash.len(), 16);
     }
 
+    #[test]
+    fn test_algorithms_and_progress() {
+        let temp = TempDir::new().unwrap();
+        let file_path = temp.path().join("big.bin");
+        std::fs::write(&file_path, vec![7u8; BUFFER_SIZE * 2 + 10]).unwrap();
+
+        let mut progress = Vec::new();
+        let xxh3 = compute_file_hash_with(&file_path, HashAlgorithm::Xxh3, |n| progress.push(n)).unwrap();
+        assert_eq!(xxh3, compute_file_hash(&file_path).unwrap());
+        assert_eq!(progress.iter().sum::<u64>(), (BUFFER_SIZE * 2 + 10) as u64);
+        assert!(progress.len() >= 3);
+
+        let xxh128 = compute_file_hash_with(&file_path, HashAlgorithm::Xxh3_128, |_| {}).unwrap();
+        assert_eq!(xxh128.len(), 32);
+
+        std::fs::write(&file_path, b"abc").unwrap();
+        let sha = compute_file_hash_with(&file_path, HashAlgorithm::Sha256, |_| {}).unwrap();
+        assert_eq!(sha, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        let blake3 = compute_file_hash_with(&file_path, HashAlgorithm::Blake3, |_| {}).unwrap();
+        assert_eq!(blake3, "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85");
+
+        for algorithm in [HashAlgorithm::Xxh3, HashAlgorithm::Xxh3_128, HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+            assert_eq!(algorithm.name().parse::<HashAlgorithm>(), Ok(algorithm));
+        }
+        assert!("blake2".parse::<HashAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn test_quick_hash_reads_only_the_ends() {
+        let temp = TempDir::new().unwrap();
+        let a = temp.path().join("a.mov");
+        let b = temp.path().join("b.mov");
+        let mut content = vec![1u8; QUICK_HASH_SPAN as usize * 3];
+        std::fs::write(&a, &content).unwrap();
+        // A change in the middle goes unnoticed; one at either end doesn't
+        content[QUICK_HASH_SPAN as usize + 5] = 2;
+        std::fs::write(&b, &content).unwrap();
+        assert_eq!(compute_quick_hash(&a).unwrap(), compute_quick_hash(&b).unwrap());
+        assert_ne!(compute_file_hash(&a).unwrap(), compute_file_hash(&b).unwrap());
+
+        let last = content.len() - 1;
+        content[last] = 2;
+        std::fs::write(&b, &content).unwrap();
+        assert_ne!(compute_quick_hash(&a).unwrap(), compute_quick_hash(&b).unwrap());
+
+        // Small files are hashed whole, with their size
+        std::fs::write(&a, b"abc").unwrap();
+        std::fs::write(&b, b"abd").unwrap();
+        assert_ne!(compute_quick_hash(&a).unwrap(), compute_quick_hash(&b).unwrap());
+        std::fs::write(&b, b"").unwrap();
+        assert_eq!(compute_quick_hash(&b).unwrap().len(), 16);
+    }
+
     #[test]
     fn test_compute_hash_nonexistent_file() {
         let result = compute_file_hash(Path::new("/nonexistent/file.txt"));
@@ -0,0 +1,172 @@
+//! Which copy of a duplicate group to suggest keeping.
+//!
+//! Shared by `picman dupes` and the web duplicates view. The policy comes from
+//! `[duplicates] keep` in `.picman.toml` and can be overridden per request
+//! with `/api/duplicates?keep=`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+/// Extensions of camera RAW formats, preferred over JPEGs by [`KeepPolicy::Raw`]
+const RAW_EXTENSIONS: &[&str] = &["raw", "cr2", "cr3", "nef", "arw", "orf", "rw2", "dng", "raf"];
+
+/// How to pick the copy to keep. Every policy falls back to the highest
+/// resolution, then the largest file, when it doesn't single one out.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum KeepPolicy {
+    /// Most pixels, then the largest file
+    #[default]
+    Resolution,
+    /// Earliest modification time, usually the original import
+    Oldest,
+    /// RAW files over JPEGs and other formats
+    Raw,
+    /// Fewest characters in the library-relative path
+    ShortestPath,
+    /// Copies under this directory, e.g. `prefix:photos/sorted`
+    Prefix(String),
+}
+
+impl fmt::Display for KeepPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeepPolicy::Resolution => f.write_str("resolution"),
+            KeepPolicy::Oldest => f.write_str("oldest"),
+            KeepPolicy::Raw => f.write_str("raw"),
+            KeepPolicy::ShortestPath => f.write_str("shortest-path"),
+            KeepPolicy::Prefix(prefix) => write!(f, "prefix:{}", prefix),
+        }
+    }
+}
+
+impl FromStr for KeepPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(prefix) = s.strip_prefix("prefix:") {
+            let prefix = prefix.trim_matches('/');
+            if prefix.is_empty() {
+                return Err("prefix:<directory> needs a directory".to_string());
+            }
+            return Ok(KeepPolicy::Prefix(prefix.to_string()));
+        }
+        match s {
+            "resolution" => Ok(KeepPolicy::Resolution),
+            "oldest" => Ok(KeepPolicy::Oldest),
+            "raw" => Ok(KeepPolicy::Raw),
+            "shortest-path" => Ok(KeepPolicy::ShortestPath),
+            _ => Err(format!(
+                "unknown keep policy '{}' (expected resolution, oldest, raw, shortest-path or prefix:<directory>)",
+                s
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for KeepPolicy {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// What the policy looks at for one copy
+#[derive(Debug, Clone, Copy)]
+pub struct KeepCandidate<'a> {
+    /// Relative to the library root
+    pub path: &'a str,
+    pub size: i64,
+    pub mtime: i64,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+}
+
+impl KeepCandidate<'_> {
+    fn resolution_key(&self) -> (i64, i64) {
+        let pixels = self.width.unwrap_or(0) as i64 * self.height.unwrap_or(0) as i64;
+        (pixels, self.size)
+    }
+
+    fn is_raw(&self) -> bool {
+        self.path
+            .rsplit_once('.')
+            .is_some_and(|(_, ext)| RAW_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+    }
+}
+
+/// Index of the copy to keep. Among equally good copies the last one wins,
+/// as with `Iterator::max_by_key`.
+pub fn choose_keep(candidates: &[KeepCandidate], policy: &KeepPolicy) -> usize {
+    candidates
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| compare(a, b, policy))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+/// How strongly the policy prefers keeping `a` over `b`
+fn compare(a: &KeepCandidate, b: &KeepCandidate, policy: &KeepPolicy) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let preferred = match policy {
+        KeepPolicy::Resolution => Ordering::Equal,
+        // Lower is better for these, so compare b to a
+        KeepPolicy::Oldest => b.mtime.cmp(&a.mtime),
+        KeepPolicy::ShortestPath => b.path.len().cmp(&a.path.len()),
+        KeepPolicy::Raw => a.is_raw().cmp(&b.is_raw()),
+        KeepPolicy::Prefix(prefix) => {
+            let under = |c: &KeepCandidate| {
+                c.path.strip_prefix(prefix.as_str()).is_some_and(|rest| rest.starts_with('/'))
+            };
+            under(a).cmp(&under(b))
+        }
+    };
+    preferred.then_with(|| a.resolution_key().cmp(&b.resolution_key()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(path: &str, size: i64, mtime: i64, dims: Option<(i32, i32)>) -> KeepCandidate<'_> {
+        KeepCandidate { path, size, mtime, width: dims.map(|d| d.0), height: dims.map(|d| d.1) }
+    }
+
+    #[test]
+    fn test_parse_keep_policy() {
+        assert_eq!("oldest".parse(), Ok(KeepPolicy::Oldest));
+        assert_eq!("shortest-path".parse(), Ok(KeepPolicy::ShortestPath));
+        assert_eq!("prefix:photos/sorted/".parse(), Ok(KeepPolicy::Prefix("photos/sorted".to_string())));
+        assert!("prefix:".parse::<KeepPolicy>().is_err());
+        assert!("newest".parse::<KeepPolicy>().is_err());
+        assert_eq!(KeepPolicy::Prefix("a/b".to_string()).to_string(), "prefix:a/b");
+    }
+
+    #[test]
+    fn test_choose_keep_policies() {
+        let files = [
+            candidate("backup/2024/IMG_1.JPG", 3_000, 200, Some((4000, 3000))),
+            candidate("photos/IMG_1.CR2", 25_000, 100, Some((4000, 3000))),
+            candidate("a/IMG_1.jpg", 900, 300, Some((1600, 1200))),
+        ];
+        assert_eq!(choose_keep(&files, &KeepPolicy::Resolution), 1);
+        assert_eq!(choose_keep(&files, &KeepPolicy::Oldest), 1);
+        assert_eq!(choose_keep(&files, &KeepPolicy::Raw), 1);
+        assert_eq!(choose_keep(&files, &KeepPolicy::ShortestPath), 2);
+        assert_eq!(choose_keep(&files, &KeepPolicy::Prefix("backup".to_string())), 0);
+        // A prefix only matches whole directory names
+        assert_eq!(choose_keep(&files, &KeepPolicy::Prefix("back".to_string())), 1);
+    }
+
+    #[test]
+    fn test_choose_keep_ties_keep_last() {
+        let files = [candidate("b/x.jpg", 100, 0, None), candidate("a/x.jpg", 100, 0, None)];
+        assert_eq!(choose_keep(&files, &KeepPolicy::Resolution), 1);
+        assert_eq!(choose_keep(&[], &KeepPolicy::Oldest), 0);
+    }
+}
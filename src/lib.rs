@@ -1,10 +1,30 @@
 pub mod cli;
+pub mod color;
+pub mod config;
+#[cfg(unix)]
+pub mod control;
+pub mod cron;
+pub mod daemon;
 pub mod db;
+pub mod embedded_rating;
+pub mod filenames;
 pub mod hash;
+pub mod keep_policy;
+pub mod library;
+pub mod library_path;
 pub mod logging;
+pub mod maintenance;
+pub mod notify;
 pub mod perceptual_hash;
+pub mod print_size;
+pub mod process;
+pub mod rating;
+pub mod s3;
 pub mod scanner;
+pub mod screenshot;
 pub mod serve;
 pub mod suggestions;
 pub mod thumbnails;
 pub mod tui;
+pub mod vfs;
+pub mod workers;
@@ -0,0 +1,179 @@
+//! A picman library for other Rust programs: indexing, queries, ratings and
+//! tags without the CLI, TUI or web server.
+//!
+//! ```no_run
+//! use picman::cli::ListOptions;
+//! use picman::library::{Library, SyncOptions};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let library = Library::open("/photos")?;
+//! library.sync(&SyncOptions::default())?;
+//! library.rate("2024/trip/IMG_0001.jpg", Some(5.0))?;
+//! library.tag("2024/trip/IMG_0001.jpg", "keeper")?;
+//! let best = library.query(&ListOptions { min_rating: Some(4.0), ..Default::default() })?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Paths are relative to the library root, as [`LibraryPath`] normalizes
+//! them. For anything not covered here, [`Library::database`] gives the
+//! underlying [`Database`].
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::cli::{
+    list_files, rate_file, run_init, run_sync_subdir, run_sync_with_perceptual, tag_file, FileInfo, ListOptions,
+    SyncStats, TagOptions, DB_FILENAME,
+};
+use crate::config::Config;
+use crate::db::{Database, File};
+use crate::library_path::LibraryPath;
+
+/// An indexed library and an open connection to its database
+pub struct Library {
+    root: PathBuf,
+    db: Database,
+}
+
+/// What [`Library::sync`] does besides finding new, changed and removed files
+#[derive(Debug, Clone, Default)]
+pub struct SyncOptions {
+    /// Compute content hashes of new and changed files (for duplicates)
+    pub hashes: bool,
+    /// Tag images landscape, portrait or square
+    pub orientation_tags: bool,
+    /// Compute perceptual hashes (for similar images)
+    pub perceptual: bool,
+    /// Rescan every directory instead of only those whose mtime changed
+    pub full: bool,
+    /// Only sync this directory and everything below it
+    pub subdir: Option<String>,
+}
+
+impl Library {
+    /// Open a library indexed before (with `picman init` or [`Library::init`])
+    pub fn open(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref();
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("Library path does not exist: {}", root.display()))?;
+        let db_path = root.join(DB_FILENAME);
+        if !db_path.exists() {
+            anyhow::bail!("No database found at {}. Run 'picman init' first.", root.display());
+        }
+        let db = Database::open(&db_path)?;
+        Ok(Self { root, db })
+    }
+
+    /// Index the directory at `root` and open it
+    pub fn init(root: impl AsRef<Path>) -> Result<Self> {
+        run_init(root.as_ref())?;
+        Self::open(root)
+    }
+
+    /// The library's directory, canonicalized
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn database(&self) -> &Database {
+        &self.db
+    }
+
+    /// The library's `.picman.toml` settings
+    pub fn config(&self) -> Result<Config> {
+        Config::load(&self.root)
+    }
+
+    /// Bring the index up to date with the files on disk
+    pub fn sync(&self, options: &SyncOptions) -> Result<SyncStats> {
+        match &options.subdir {
+            Some(subdir) => run_sync_subdir(
+                &self.root,
+                LibraryPath::new(subdir).as_str(),
+                options.hashes,
+                options.orientation_tags,
+                options.perceptual,
+            ),
+            None => run_sync_with_perceptual(
+                &self.root,
+                options.hashes,
+                options.orientation_tags,
+                options.perceptual,
+                options.full,
+            ),
+        }
+    }
+
+    /// The files matching `filter`, with their paths, ratings and tags
+    pub fn query(&self, filter: &ListOptions) -> Result<Vec<FileInfo>> {
+        list_files(&self.db, filter)
+    }
+
+    /// The indexed file at `path`, if any
+    pub fn file(&self, path: &str) -> Result<Option<File>> {
+        self.db.get_file_by_path(path)
+    }
+
+    /// Where the file or directory at `path` is on disk
+    pub fn absolute(&self, path: &str) -> PathBuf {
+        LibraryPath::new(path).absolute(&self.root)
+    }
+
+    /// Set or clear a file's rating, on the library's rating scale
+    pub fn rate(&self, path: &str, rating: Option<f64>) -> Result<()> {
+        if let Some(r) = rating {
+            self.config()?.ratings.scale.validate(r)?;
+        }
+        rate_file(&self.db, path, rating)
+    }
+
+    /// A file's own tags
+    pub fn tags(&self, path: &str) -> Result<Vec<String>> {
+        tag_file(&self.db, path, &TagOptions::default())
+    }
+
+    /// Add a tag to a file, returning its tags
+    pub fn tag(&self, path: &str, tag: &str) -> Result<Vec<String>> {
+        tag_file(&self.db, path, &TagOptions { add: vec![tag.to_string()], ..Default::default() })
+    }
+
+    /// Remove a tag from a file, returning its tags
+    pub fn untag(&self, path: &str, tag: &str) -> Result<Vec<String>> {
+        tag_file(&self.db, path, &TagOptions { remove: vec![tag.to_string()], ..Default::default() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_library_round_trip() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir(temp.path().join("trip")).unwrap();
+        fs::write(temp.path().join("trip/a.jpg"), "a").unwrap();
+
+        let library = Library::init(temp.path()).unwrap();
+        library.rate("./trip/a.jpg", Some(4.0)).unwrap();
+        assert!(library.rate("trip/a.jpg", Some(9.0)).is_err());
+        assert_eq!(library.tag("trip/a.jpg", "keeper").unwrap(), ["keeper"]);
+
+        fs::write(temp.path().join("trip/b.jpg"), "b").unwrap();
+        let stats = library.sync(&SyncOptions { full: true, ..Default::default() }).unwrap();
+        assert_eq!(stats.files_added, 1);
+        assert!(library.file("trip/b.jpg").unwrap().is_some());
+
+        let rated = library.query(&ListOptions { min_rating: Some(4.0), ..Default::default() }).unwrap();
+        assert_eq!(rated.len(), 1);
+        assert_eq!(rated[0].path, "trip/a.jpg");
+        assert_eq!(rated[0].tags, ["keeper"]);
+
+        assert!(library.untag("trip/a.jpg", "keeper").unwrap().is_empty());
+        assert!(Library::open(temp.path().join("trip")).is_err());
+    }
+}
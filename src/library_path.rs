@@ -0,0 +1,156 @@
+//! Paths inside a library, as the database records them: relative to the
+//! library root, `/`-separated whatever the platform, with no `./`, empty
+//! or trailing components. The root itself is the empty path, which is why
+//! joining needs care: `Path::join("")` leaves a trailing slash, and
+//! `format!("{}/{}")` a leading one.
+
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+/// A directory or file path relative to the library root
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LibraryPath(String);
+
+impl LibraryPath {
+    /// The library root
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Normalize a `/`-separated path as typed or stored: `./photos//2024/`
+    /// becomes `photos/2024`, and `.` the root
+    pub fn new(path: &str) -> Self {
+        let parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty() && *p != ".").collect();
+        Self(parts.join("/"))
+    }
+
+    /// A relative path from the filesystem or the command line, with the
+    /// platform's separators
+    pub fn from_relative(path: &Path) -> Self {
+        let parts: Vec<String> = path
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+                Component::ParentDir => Some("..".to_string()),
+                _ => None,
+            })
+            .collect();
+        Self(parts.join("/"))
+    }
+
+    /// `path` relative to `library`, or None if it's outside it
+    pub fn strip_library(library: &Path, path: &Path) -> Option<Self> {
+        path.strip_prefix(library).ok().map(Self::from_relative)
+    }
+
+    /// A file in the directory recorded as `dir` ("" for the root)
+    pub fn file(dir: &str, filename: &str) -> Self {
+        if dir.is_empty() {
+            Self(filename.to_string())
+        } else {
+            Self(format!("{}/{}", dir, filename))
+        }
+    }
+
+    /// This path with `name` appended
+    pub fn join(&self, name: &str) -> Self {
+        Self::file(&self.0, name)
+    }
+
+    /// The containing directory and the last component; the root-level
+    /// entry `a.jpg` splits into `("", "a.jpg")`
+    pub fn split(&self) -> (&str, &str) {
+        self.0.rsplit_once('/').unwrap_or(("", &self.0))
+    }
+
+    /// The containing directory, or None for the root
+    pub fn parent(&self) -> Option<Self> {
+        if self.is_root() {
+            return None;
+        }
+        Some(Self(self.split().0.to_string()))
+    }
+
+    pub fn file_name(&self) -> &str {
+        self.split().1
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// Where this is on disk, in the library at `library`
+    pub fn absolute(&self, library: &Path) -> PathBuf {
+        if self.is_root() {
+            library.to_path_buf()
+        } else {
+            library.join(&self.0)
+        }
+    }
+}
+
+impl fmt::Display for LibraryPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for LibraryPath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<LibraryPath> for String {
+    fn from(path: LibraryPath) -> Self {
+        path.0
+    }
+}
+
+/// The relative path, e.g. for [`crate::db::FileToHash`]
+impl From<LibraryPath> for PathBuf {
+    fn from(path: LibraryPath) -> Self {
+        PathBuf::from(path.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalizes_typed_paths() {
+        assert_eq!(LibraryPath::new("./photos//2024/").as_str(), "photos/2024");
+        assert!(LibraryPath::new(".").is_root());
+        assert!(LibraryPath::new("").is_root());
+        assert_eq!(LibraryPath::from_relative(Path::new("./photos/a.jpg")).as_str(), "photos/a.jpg");
+        assert_eq!(
+            LibraryPath::strip_library(Path::new("/lib"), Path::new("/lib/photos/a.jpg")).unwrap().as_str(),
+            "photos/a.jpg"
+        );
+        assert!(LibraryPath::strip_library(Path::new("/lib"), Path::new("/other/a.jpg")).is_none());
+    }
+
+    #[test]
+    fn test_joins_at_the_root_and_below() {
+        let lib = Path::new("/lib");
+        assert_eq!(LibraryPath::file("", "a.jpg").as_str(), "a.jpg");
+        assert_eq!(LibraryPath::file("photos", "a.jpg").absolute(lib), PathBuf::from("/lib/photos/a.jpg"));
+        assert_eq!(LibraryPath::root().absolute(lib), PathBuf::from("/lib"));
+        assert_eq!(LibraryPath::root().join("photos").as_str(), "photos");
+
+        let path = LibraryPath::new("photos/2024/a.jpg");
+        assert_eq!(path.split(), ("photos/2024", "a.jpg"));
+        assert_eq!(path.parent().unwrap().as_str(), "photos/2024");
+        assert_eq!(LibraryPath::new("a.jpg").parent(), Some(LibraryPath::root()));
+        assert_eq!(LibraryPath::root().parent(), None);
+    }
+}
@@ -1,15 +1,29 @@
 use std::path::PathBuf;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use picman::cli::{
-    run_check_previews, run_check_thumbnails, run_dupes, run_generate_previews,
-    run_generate_thumbnails, run_generate_web_thumbnails, run_init, run_list, run_rate,
-    run_repair, run_status, run_sync_with_perceptual, run_tag, ListOptions, TagOptions,
+    complete, completion_script, configure_dotfiles, configure_follow_symlinks, configure_remote,
+    force_unlock, list_hidden, render_heatmap, run_archive, run_attr, run_check_previews,
+    run_check_thumbnails, run_contact_sheet, run_dedupe_link, run_dupes, run_dupes_ignore,
+    run_export_resized, run_flag_screenshots, run_generate_previews, run_generate_thumbnails,
+    run_generate_web_thumbnails, run_heatmap, run_hide, run_import_ratings, run_init, run_list,
+    run_open, run_open_query, run_phash, run_rate, run_rate_pick, run_rate_propagate,
+    run_rating_scale, run_repair, run_restore, run_retire_orientation_tags, run_review, run_stats,
+    run_status, run_sync_resume, run_sync_subdir, run_sync_with_perceptual, run_tag, run_tag_alias,
+    run_tag_directory, run_verify, ArchiveOptions, AttrAction, ContactSheetOptions, DedupeOptions,
+    DupesReportOptions, ExportMetadata, ExportOptions, ListOptions, OpenOptions, Paper,
+    Propagation, ReviewAction, Shell, TagAliasAction, TagOptions, WatermarkOptions,
+    WatermarkPosition, WatermarkSource,
 };
+use picman::daemon::{run_daemon, run_serve_scheduled, DaemonOptions};
+use picman::db::{parse_shutter, ArchiveFilter, DuplicateScope, ExposureFilter, GearField};
 use picman::logging::init_logging;
-use picman::serve::run_serve;
+use picman::print_size::{PrintFilter, PrintSize, DEFAULT_PRINT_DPI};
+use picman::rating::RatingScale;
+use picman::serve::{stop_serve, ServeOptions, TlsOptions};
+use picman::tui::dialogs::Orientation;
 use picman::tui::run_tui;
 
 #[derive(Parser)]
@@ -26,14 +40,41 @@ struct Cli {
     /// Skip filesystem sync on TUI startup (faster, but won't detect changes)
     #[arg(long)]
     skip_sync: bool,
+
+    /// Worker threads for thumbnails, previews, and hashing (default: per-operation)
+    #[arg(long, global = true)]
+    jobs: Option<usize>,
+
+    /// Cap bulk file reads at this many MB/s (e.g. for a library on a NAS)
+    #[arg(long, global = true, value_name = "MB_PER_SEC")]
+    io_limit: Option<f64>,
+
+    /// Run at lower CPU and IO priority
+    #[arg(long, global = true)]
+    nice: bool,
+
+    /// Faster database writes (WAL, no fsync per commit): a power cut can
+    /// lose the last changes. Not for a database on a network filesystem
+    #[arg(long, global = true)]
+    fast: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Initialize database for a library
     Init {
-        /// Path to library root
+        /// Path to library root (the local mirror when --remote is given)
         path: PathBuf,
+        /// Library on a server, as ssh://[user@]host[:port]/path.
+        /// The database and fetched originals are kept under PATH.
+        #[arg(long, value_name = "URL")]
+        remote: Option<String>,
+        /// Follow symlinked directories and files (saved as [scan] follow_symlinks)
+        #[arg(long)]
+        follow_symlinks: bool,
+        /// Index dot-prefixed files and directories (saved as [scan] dotfiles)
+        #[arg(long)]
+        dotfiles: bool,
     },
     /// Sync database with filesystem changes
     Sync {
@@ -46,12 +87,42 @@ enum Commands {
         /// Compute perceptual hashes for image files (for duplicate detection)
         #[arg(long)]
         perceptual: bool,
-        /// Auto-tag image orientation (landscape/portrait)
-        #[arg(long)]
+        /// Deprecated: tag images landscape/portrait. Orientation is now a
+        /// filter worked out from dimensions (`list --orientation`)
+        #[arg(long, hide = true)]
         orientation: bool,
         /// Full rescan (default is incremental: only scan directories with changed mtime)
         #[arg(long)]
         full: bool,
+        /// Continue an interrupted full sync, skipping the top-level
+        /// directories it already committed
+        #[arg(long, conflicts_with = "subdir")]
+        resume: bool,
+        /// Only rescan this directory (relative to the library) and everything below it
+        #[arg(long, conflicts_with = "full")]
+        subdir: Option<String>,
+        /// Remove a sync lock left behind by a sync that is no longer running
+        #[arg(long)]
+        force_unlock: bool,
+        /// Rate unrated images from the XMP/EXIF star rating stored in them (see import-ratings)
+        #[arg(long)]
+        import_ratings: bool,
+        /// Tag likely screenshots auto:screenshot (screenshot filenames, or
+        /// screen-sized PNGs without a camera in their EXIF)
+        #[arg(long)]
+        screenshots: bool,
+        /// Follow symlinked directories and files from now on (saved as
+        /// [scan] follow_symlinks; symlinks are skipped by default)
+        #[arg(long)]
+        follow_symlinks: bool,
+        /// Index dot-prefixed files and directories from now on (saved as
+        /// [scan] dotfiles); switching it rescans the whole library
+        #[arg(long, conflicts_with = "no_dotfiles")]
+        dotfiles: bool,
+        /// Stop indexing dot-prefixed files and directories, and remove
+        /// the ones already in the database
+        #[arg(long)]
+        no_dotfiles: bool,
     },
     /// Find duplicate files (exact and visually similar)
     Dupes {
@@ -67,32 +138,225 @@ enum Commands {
         /// Hamming distance threshold for perceptual similarity (default: 8)
         #[arg(long, default_value = "8")]
         threshold: u32,
+        /// Only look for duplicates among files with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only look for duplicates among files rated at least this
+        #[arg(long, conflicts_with = "unrated")]
+        rating: Option<f64>,
+        /// Only look for duplicates among unrated files
+        #[arg(long)]
+        unrated: bool,
+        /// Include files in hidden directories
+        #[arg(long)]
+        include_hidden: bool,
+        /// Also write the groups, thumbnails and potential savings to a .html or .csv file
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+        /// Link thumbnails from the HTML report by relative path instead of embedding them
+        #[arg(long, requires = "report")]
+        link_thumbnails: bool,
+        /// Mark these files (relative to library) as not duplicates of each other
+        #[arg(long, num_args = 2.., value_name = "FILE", conflicts_with_all = ["json", "report", "unignore"])]
+        ignore: Vec<PathBuf>,
+        /// Report files marked with --ignore as duplicates again
+        #[arg(long, num_args = 2.., value_name = "FILE", conflicts_with_all = ["json", "report"])]
+        unignore: Vec<PathBuf>,
+    },
+    /// Reclaim the space taken by exact duplicates
+    Dedupe {
+        /// Path to library root (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Replace extra copies with reflinks (btrfs, xfs) or hardlinks to one kept file
+        #[arg(long, required = true)]
+        link: bool,
+        /// Only groups with a copy in this directory or below
+        #[arg(long)]
+        subdir: Option<String>,
+        /// Only report what would be linked
+        #[arg(long)]
+        dry_run: bool,
     },
     /// List files matching criteria
     List {
         /// Path to library root
         path: PathBuf,
-        /// Minimum rating (1-5)
+        /// Minimum rating (on the library's rating scale)
+        #[arg(long)]
+        rating: Option<f64>,
+        /// Only files without a rating
+        #[arg(long, conflicts_with = "rating")]
+        unrated: bool,
+        /// Filter by tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only files in this directory and below (relative to library)
+        #[arg(long)]
+        dir: Option<String>,
+        /// Apply a filter preset saved in the TUI filter dialog
+        #[arg(long)]
+        preset: Option<String>,
+        /// Only images shot at this ISO or higher
+        #[arg(long)]
+        min_iso: Option<i64>,
+        /// Only images shot at this ISO or lower
+        #[arg(long)]
+        max_iso: Option<i64>,
+        /// Only images shot at this f-number or narrower (e.g. 8 for f/8)
+        #[arg(long)]
+        min_aperture: Option<f64>,
+        /// Only images shot at this f-number or wider (e.g. 2 for f/2)
+        #[arg(long)]
+        max_aperture: Option<f64>,
+        /// Only images exposed this long or longer (e.g. 1/60)
+        #[arg(long, value_parser = parse_shutter)]
+        min_shutter: Option<f64>,
+        /// Only images exposed this long or shorter (e.g. 1/1000)
+        #[arg(long, value_parser = parse_shutter)]
+        max_shutter: Option<f64>,
+        /// Only files of this shape: landscape, portrait, square, panorama or tall
+        #[arg(long)]
+        orientation: Option<Orientation>,
+        /// Only files with enough pixels to print this size: 8x10 (inches), 20x30cm, A4...
+        #[arg(long, value_name = "SIZE")]
+        print_size: Option<PrintSize>,
+        /// Resolution --print-size needs
+        #[arg(long, value_name = "DPI", default_value_t = DEFAULT_PRINT_DPI, requires = "print_size", value_parser = clap::value_parser!(u32).range(1..))]
+        print_dpi: u32,
+        /// Include files in hidden directories
+        #[arg(long)]
+        include_hidden: bool,
+    },
+    /// Find files by name, tag or rating and open one with the system viewer
+    Open {
+        /// Path to library root
+        library: PathBuf,
+        /// Part of the file's path, or one of its tags (case-insensitive)
+        query: Option<String>,
+        /// Minimum rating (on the library's rating scale)
+        #[arg(long)]
+        rating: Option<f64>,
+        /// Only files with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Include files in hidden directories
+        #[arg(long)]
+        include_hidden: bool,
+    },
+    /// Lay out thumbnails with filenames and ratings as a PDF (or JPEG) proof sheet
+    ContactSheet {
+        /// Path to library root
+        library: PathBuf,
+        /// Where to write: .pdf, or .jpg for one image per page
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Minimum rating (on the library's rating scale)
         #[arg(long)]
-        rating: Option<i32>,
+        rating: Option<f64>,
         /// Filter by tag
         #[arg(long)]
         tag: Option<String>,
+        /// Apply a filter preset saved in the TUI filter dialog
+        #[arg(long)]
+        preset: Option<String>,
+        /// Only files of this shape: landscape, portrait, square, panorama or tall
+        #[arg(long)]
+        orientation: Option<Orientation>,
+        /// Include files in hidden directories
+        #[arg(long)]
+        include_hidden: bool,
+        /// Thumbnails per row
+        #[arg(long, default_value_t = 4)]
+        columns: u32,
+        /// Page size
+        #[arg(long, value_enum, default_value = "a4")]
+        paper: Paper,
+        /// Heading on every page (default: the library's directory name)
+        #[arg(long)]
+        title: Option<String>,
     },
-    /// Rate a file (1-5 stars)
+    /// Write downsized JPEG copies of images for sharing, without location data by default
+    ExportResized {
+        /// Path to library root
+        library: PathBuf,
+        /// Files or directories to export (relative to library; default: everything)
+        files: Vec<String>,
+        /// Directory to write the copies to
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Longest side in pixels (smaller images keep their size)
+        #[arg(long, default_value_t = 2048)]
+        max_edge: u32,
+        /// JPEG quality (1-100)
+        #[arg(long, default_value_t = 85)]
+        quality: u8,
+        /// EXIF to keep: everything, everything but GPS, or nothing
+        #[arg(long, value_enum, default_value = "no-gps")]
+        metadata: ExportMetadata,
+        /// Put all copies directly in the output directory
+        #[arg(long)]
+        flatten: bool,
+        /// Watermark text (needs ImageMagick)
+        #[arg(long, value_name = "TEXT")]
+        watermark_text: Option<String>,
+        /// Watermark image, e.g. a logo PNG with transparency
+        #[arg(long, value_name = "PNG", conflicts_with = "watermark_text")]
+        watermark_image: Option<PathBuf>,
+        /// Where the watermark goes: top-left, top, ..., bottom-right, or tile
+        #[arg(long, value_enum, default_value = "bottom-right")]
+        watermark_position: WatermarkPosition,
+        /// Watermark opacity, 0 to 1
+        #[arg(long, default_value_t = 0.5)]
+        watermark_opacity: f32,
+        /// Watermark width as a fraction of the image width
+        #[arg(long, default_value_t = 0.25)]
+        watermark_scale: f32,
+        /// Minimum rating (on the library's rating scale)
+        #[arg(long)]
+        rating: Option<f64>,
+        /// Filter by tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Apply a filter preset saved in the TUI filter dialog
+        #[arg(long)]
+        preset: Option<String>,
+        /// Include files in hidden directories
+        #[arg(long)]
+        include_hidden: bool,
+    },
+    /// Rate a file (1-5 stars, or 1-10 on a 10-point library)
     Rate {
         /// Path to library root
         library: PathBuf,
-        /// File to rate (relative to library)
-        file: PathBuf,
-        /// Rating 1-5 stars (omit to clear)
-        rating: Option<i32>,
+        /// File to rate (relative to library); a directory with --propagate.
+        /// Omit to pick one interactively
+        file: Option<PathBuf>,
+        /// Rating on the library's scale, in half steps like 3.5 (omit to clear)
+        rating: Option<f64>,
+        /// Propagate directory ratings: "down" to unrated files, or derive from files with "max"/"avg"
+        #[arg(long, value_name = "MODE")]
+        propagate: Option<Propagation>,
+        /// With --propagate, only report how many files would be affected
+        #[arg(long, requires = "propagate")]
+        dry_run: bool,
+        /// Pick the file with a fuzzy finder, then type its rating
+        #[arg(long, conflicts_with_all = ["file", "propagate"])]
+        pick: bool,
+    },
+    /// Switch the library between a 5 and 10 point rating scale,
+    /// rescaling existing ratings
+    RatingScale {
+        /// Path to library root
+        library: PathBuf,
+        /// New scale: 5 or 10
+        scale: RatingScale,
     },
     /// Add or remove tags from a file
     Tag {
         /// Path to library root
         library: PathBuf,
-        /// File to tag (relative to library)
+        /// File to tag (relative to library); a directory with --recursive or --materialize
         file: PathBuf,
         /// Tags to add
         #[arg(short, long)]
@@ -101,8 +365,75 @@ enum Commands {
         #[arg(short, long)]
         remove: Vec<String>,
         /// List current tags
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with_all = ["recursive", "materialize"])]
         list: bool,
+        /// Tag a directory and all files below it (--remove untags subdirectories too)
+        #[arg(short = 'R', long)]
+        recursive: bool,
+        /// Copy tags inherited from directories onto the files below a directory
+        #[arg(long)]
+        materialize: bool,
+    },
+    /// Manage the library's tag vocabulary
+    Tags {
+        #[command(subcommand)]
+        action: TagsCommand,
+    },
+    /// Review sessions: queues of files built from a filter, resumed from the
+    /// TUI or the web API where the last session stopped
+    Review {
+        #[command(subcommand)]
+        action: ReviewCommand,
+    },
+    /// Hide a directory and everything below it from the TUI tree, list and dupes
+    /// (lists hidden directories when no directory is given)
+    Hide {
+        /// Path to library root
+        library: PathBuf,
+        /// Directory to hide (relative to library)
+        dir: Option<PathBuf>,
+        /// Show the directory again
+        #[arg(long, requires = "dir")]
+        undo: bool,
+    },
+    /// Set, get, or remove custom key/value attributes on a file
+    Attr {
+        #[command(subcommand)]
+        action: AttrCommand,
+    },
+    /// Upload originals to object storage (cold archive tier)
+    Archive {
+        /// Path to library root
+        library: PathBuf,
+        /// Destination, e.g. s3://bucket/prefix
+        #[arg(long, value_name = "URL")]
+        to: String,
+        /// Only files in this directory and below (relative to library)
+        #[arg(long)]
+        dir: Option<String>,
+        /// Only files with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only files of this media type
+        #[arg(long, value_parser = ["image", "video", "document"])]
+        media: Option<String>,
+        /// Only files of at least this many megabytes
+        #[arg(long, value_name = "MB")]
+        min_size: Option<u64>,
+        /// Replace uploaded originals with small stubs to free local space
+        #[arg(long)]
+        stub: bool,
+        /// Only report what would be uploaded
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Download archived originals back over their stubs
+    Restore {
+        /// Path to library root
+        library: PathBuf,
+        /// Files to restore (relative to library)
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
     },
     /// Create symlink view of filtered files
     View {
@@ -140,13 +471,48 @@ enum Commands {
         /// Generate small (400px) web thumbnails for grid display
         #[arg(long)]
         web: bool,
+        /// Retry web thumbnails that failed permanently before (implies --web)
+        #[arg(long)]
+        retry_failed: bool,
+    },
+    /// Rate unrated images from the XMP or EXIF star rating other tools stored
+    /// in them or their .xmp sidecar
+    ImportRatings {
+        /// Path to library root (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Compute missing perceptual hashes (resumable; use --jobs for threads)
+    Phash {
+        /// Path to library root (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Rehash hashed files and report any whose content no longer matches
+    Verify {
+        /// Path to library root (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
     },
-    /// Repair directory parent relationships based on paths
+    /// Repair directory parent relationships based on paths and rebuild the
+    /// library statistics
     Repair {
         /// Path to library root (defaults to current directory)
         #[arg(default_value = ".")]
         path: PathBuf,
     },
+    /// Show file counts and ratings per camera or lens, or photos per day
+    Stats {
+        /// Path to library root (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Group by `camera` or `lens`
+        #[arg(long, default_value = "camera")]
+        by: GearField,
+        /// Show photos per day of YEAR, by EXIF capture date, as a calendar heatmap
+        #[arg(long, value_name = "YEAR")]
+        heatmap: Option<i32>,
+    },
     /// Show library status and health
     Status {
         /// Path to library root (defaults to current directory)
@@ -160,10 +526,194 @@ enum Commands {
         /// Port to listen on
         #[arg(long, default_value = "3000")]
         port: u16,
+        /// TLS certificate chain (PEM) — serves HTTPS when given with --tls-key
+        #[arg(long, requires = "tls_key")]
+        tls_cert: Option<PathBuf>,
+        /// TLS private key (PEM)
+        #[arg(long, requires = "tls_cert")]
+        tls_key: Option<PathBuf>,
+        /// Also listen for plain HTTP on this port and redirect to HTTPS
+        #[arg(long, requires = "tls_cert")]
+        redirect_port: Option<u16>,
+        /// PID file location (default: <library>/.picman-serve.pid)
+        #[arg(long)]
+        pid_file: Option<PathBuf>,
+        /// Stop a running server (via its pid file) instead of starting one
+        #[arg(long)]
+        stop: bool,
+    },
+    /// Run web server, change watcher, and maintenance scheduler in one process
+    Daemon {
+        /// Path to library root
+        path: PathBuf,
+        /// Port to listen on
+        #[arg(long, default_value = "3000")]
+        port: u16,
+        /// TLS certificate chain (PEM)
+        #[arg(long, requires = "tls_key")]
+        tls_cert: Option<PathBuf>,
+        /// TLS private key (PEM)
+        #[arg(long, requires = "tls_cert")]
+        tls_key: Option<PathBuf>,
+        /// Also listen for plain HTTP on this port and redirect to HTTPS
+        #[arg(long, requires = "tls_cert")]
+        redirect_port: Option<u16>,
+        /// PID file location (default: <library>/.picman-serve.pid)
+        #[arg(long)]
+        pid_file: Option<PathBuf>,
+        /// Seconds between filesystem change checks
+        #[arg(long, default_value = "30")]
+        watch_interval: u64,
+        /// Minutes between maintenance runs (hash backfill, thumbnail top-up)
+        #[arg(long, default_value = "60")]
+        maintenance_interval: u64,
+        /// Accept JSON-RPC requests (sync, query, rate, tag, jobs) on a Unix
+        /// socket (default location: <library>/.picman.sock)
+        #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "")]
+        control_socket: Option<PathBuf>,
+    },
+    /// Print a shell completion script (completes tags and library paths too)
+    Completions { shell: Shell },
+}
+
+#[derive(Subcommand)]
+enum TagsCommand {
+    /// Other names for a tag ("nyc" for "new york"), used in its place when
+    /// tagging, filtering and searching
+    Alias {
+        #[command(subcommand)]
+        action: TagAliasCommand,
+    },
+    /// Remove the landscape/portrait tags `sync --orientation` added; filter
+    /// with `list --orientation` or `o` in the TUI filter dialog instead
+    RetireOrientation {
+        /// Path to library root
+        library: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReviewCommand {
+    /// Build a queue from the files a filter matches, in path order
+    Create {
+        /// Path to library root
+        library: PathBuf,
+        /// Queue name, e.g. "2024 unrated"
+        name: String,
+        /// Minimum rating (on the library's rating scale)
+        #[arg(long)]
+        rating: Option<f64>,
+        /// Only files without a rating
+        #[arg(long, conflicts_with = "rating")]
+        unrated: bool,
+        /// Filter by tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only files in this directory and below (relative to library)
+        #[arg(long)]
+        dir: Option<String>,
+        /// Apply a filter preset saved in the TUI filter dialog
+        #[arg(long)]
+        preset: Option<String>,
+        /// Only files of this shape: landscape, portrait, square, panorama or tall
+        #[arg(long)]
+        orientation: Option<Orientation>,
+        /// Include files in hidden directories
+        #[arg(long)]
+        include_hidden: bool,
+    },
+    /// List queues with how far each review got
+    List {
+        /// Path to library root
+        library: PathBuf,
+    },
+    /// Delete a queue (ratings made while reviewing are kept)
+    Delete {
+        /// Path to library root
+        library: PathBuf,
+        /// Queue to delete
+        name: String,
     },
 }
 
+#[derive(Subcommand)]
+enum TagAliasCommand {
+    /// Make ALIAS another name for TAG; files and directories tagged ALIAS are retagged
+    Add {
+        /// Path to library root
+        library: PathBuf,
+        /// The other name, e.g. "nyc"
+        alias: String,
+        /// The tag it stands for, e.g. "new york"
+        tag: String,
+    },
+    /// List aliases
+    List {
+        /// Path to library root
+        library: PathBuf,
+    },
+    /// Remove an alias (the tag itself is kept)
+    Remove {
+        /// Path to library root
+        library: PathBuf,
+        /// Alias to remove
+        alias: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AttrCommand {
+    /// Set an attribute (replaces any existing value)
+    Set {
+        /// Path to library root
+        library: PathBuf,
+        /// File (relative to library)
+        file: PathBuf,
+        /// Attribute name (e.g. "client")
+        key: String,
+        /// Attribute value
+        value: String,
+    },
+    /// Show one attribute, or all of them
+    Get {
+        /// Path to library root
+        library: PathBuf,
+        /// File (relative to library)
+        file: PathBuf,
+        /// Attribute name (omit to list all)
+        key: Option<String>,
+    },
+    /// Remove an attribute
+    Unset {
+        /// Path to library root
+        library: PathBuf,
+        /// File (relative to library)
+        file: PathBuf,
+        /// Attribute name
+        key: String,
+    },
+}
+
+/// `picman __complete <index> <words...>`, called by the completion scripts:
+/// tag names or library paths for the word at `index` of a picman command
+/// line, one per line (nothing for other arguments).
+/// Handled before clap, which would parse the words as arguments.
+fn run_complete(args: &[String]) {
+    let Some(index) = args.first().and_then(|i| i.parse().ok()) else {
+        return;
+    };
+    for line in complete(&mut Cli::command(), &args[1..], index) {
+        println!("{}", line);
+    }
+}
+
 fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("__complete") {
+        run_complete(&args[2..]);
+        return Ok(());
+    }
+
     // Initialize logging - guard must be held for logs to flush
     let _guard = init_logging().ok();
     let verbose = std::env::var("PICMAN_LOG").is_ok();
@@ -171,6 +721,16 @@ fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
+    picman::workers::configure(picman::workers::WorkerOptions {
+        jobs: cli.jobs,
+        io_limit: cli
+            .io_limit
+            .filter(|mb| *mb > 0.0)
+            .map(|mb| (mb * 1024.0 * 1024.0) as u64),
+        nice: cli.nice,
+    });
+    picman::db::set_fast_profile(cli.fast);
+
     let result = run_command(cli);
 
     if verbose {
@@ -183,16 +743,69 @@ fn main() -> Result<()> {
 
 fn run_command(cli: Cli) -> Result<()> {
     match cli.command {
-        Some(Commands::Init { path }) => {
-            println!("Initializing library at: {}", path.display());
+        Some(Commands::Init {
+            path,
+            remote,
+            follow_symlinks,
+            dotfiles,
+        }) => {
+            if let Some(url) = &remote {
+                configure_remote(&path, url)?;
+                println!(
+                    "Initializing remote library {} into: {}",
+                    url,
+                    path.display()
+                );
+            } else {
+                println!("Initializing library at: {}", path.display());
+            }
+            if follow_symlinks {
+                configure_follow_symlinks(&path)?;
+            }
+            if dotfiles {
+                configure_dotfiles(&path, true)?;
+            }
             let stats = run_init(&path)?;
             println!(
-                "Initialized: {} directories, {} files ({} images, {} videos)",
-                stats.directories, stats.files, stats.images, stats.videos
+                "Initialized: {} directories, {} files ({} images, {} videos, {} documents)",
+                stats.directories, stats.files, stats.images, stats.videos, stats.documents
             );
         }
-        Some(Commands::Sync { path, hash, perceptual, orientation, full }) => {
-            let stats = run_sync_with_perceptual(&path, hash, orientation, perceptual, full)?;
+        Some(Commands::Sync {
+            path,
+            hash,
+            perceptual,
+            orientation,
+            full,
+            resume,
+            subdir,
+            force_unlock: unlock,
+            import_ratings,
+            screenshots,
+            follow_symlinks,
+            dotfiles,
+            no_dotfiles,
+        }) => {
+            if follow_symlinks {
+                configure_follow_symlinks(&path)?;
+            }
+            // Dotfiles in directories that haven't changed are only found
+            // by a full rescan
+            let mut full = full;
+            if dotfiles || no_dotfiles {
+                full |= configure_dotfiles(&path, dotfiles)?;
+            }
+            if unlock {
+                match force_unlock(&path)? {
+                    Some(holder) => println!("Removed sync lock held by {}", holder),
+                    None => println!("No sync lock to remove"),
+                }
+            }
+            let stats = match subdir {
+                Some(subdir) => run_sync_subdir(&path, &subdir, hash, orientation, perceptual)?,
+                None if resume => run_sync_resume(&path, hash, orientation, perceptual)?,
+                None => run_sync_with_perceptual(&path, hash, orientation, perceptual, full)?,
+            };
             println!(
                 "Synced: +{} -{} directories, +{} -{} ~{} files",
                 stats.directories_added,
@@ -207,6 +820,15 @@ fn run_command(cli: Cli) -> Result<()> {
                     stats.directories_moved
                 );
             }
+            if stats.files_renamed > 0 {
+                println!(
+                    "Renamed: {} files (metadata preserved)",
+                    stats.files_renamed
+                );
+            }
+            if stats.files_moved > 0 {
+                println!("Moved: {} files (metadata preserved)", stats.files_moved);
+            }
             if hash {
                 println!(
                     "Hashed: {} files ({} errors)",
@@ -222,20 +844,156 @@ fn run_command(cli: Cli) -> Result<()> {
             if orientation {
                 println!("Orientation tagged: {} files", stats.orientation_tagged);
             }
+            if import_ratings {
+                let report = run_import_ratings(&path)?;
+                println!("Ratings imported: {} files", report.imported);
+            }
+            if screenshots {
+                let report = run_flag_screenshots(&path)?;
+                println!(
+                    "Screenshots: {} of {} new images tagged auto:screenshot",
+                    report.flagged, report.checked
+                );
+            }
+            if stats.name_collisions > 0 {
+                println!(
+                    "Name collisions: {} files clash on case-insensitive filesystems (see 'picman status')",
+                    stats.name_collisions
+                );
+            }
+        }
+        Some(Commands::Dupes { path, ignore, .. }) if !ignore.is_empty() => {
+            let pairs = run_dupes_ignore(&path, &ignore, true)?;
+            println!("Marked {} file pairs as not duplicates", pairs);
         }
-        Some(Commands::Dupes { path, subdir, json, threshold }) => {
-            run_dupes(&path, subdir.as_deref(), json, threshold)?;
+        Some(Commands::Dupes { path, unignore, .. }) if !unignore.is_empty() => {
+            let pairs = run_dupes_ignore(&path, &unignore, false)?;
+            println!("{} file pairs will be reported as duplicates again", pairs);
         }
-        Some(Commands::List { path, rating, tag }) => {
+        Some(Commands::Dupes {
+            path,
+            subdir,
+            tag,
+            rating,
+            unrated,
+            json,
+            threshold,
+            include_hidden,
+            report,
+            link_thumbnails,
+            ..
+        }) => {
+            let report = report.map(|path| DupesReportOptions {
+                path,
+                link_thumbnails,
+            });
+            let scope = DuplicateScope {
+                tag,
+                min_rating: rating,
+                unrated,
+            };
+            run_dupes(
+                &path,
+                subdir.as_deref(),
+                &scope,
+                json,
+                threshold,
+                include_hidden,
+                report.as_ref(),
+            )?;
+        }
+        Some(Commands::Dedupe {
+            path,
+            subdir,
+            dry_run,
+            ..
+        }) => {
+            let options = DedupeOptions {
+                subdir: subdir.map(|d| d.trim_end_matches('/').to_string()),
+                dry_run,
+            };
+            let report = run_dedupe_link(&path, &options)?;
+            let megabytes = report.bytes as f64 / (1024.0 * 1024.0);
+            if dry_run {
+                println!(
+                    "Would link {} duplicate copies ({:.1} MB)",
+                    report.linked, megabytes
+                );
+            } else {
+                println!(
+                    "Linked {} duplicate copies ({:.1} MB reclaimed)",
+                    report.linked, megabytes
+                );
+                if report.hardlinked > 0 {
+                    println!(
+                        "{} are hardlinks (no reflink support): editing one path changes the others",
+                        report.hardlinked
+                    );
+                }
+            }
+            if report.already_linked > 0 {
+                println!("{} copies were already linked", report.already_linked);
+            }
+            if report.skipped > 0 {
+                println!(
+                    "Skipped {} copies that changed since hashing or are archive stubs; run 'picman sync --hash'",
+                    report.skipped
+                );
+            }
+            if report.failed > 0 {
+                println!("{} copies could not be linked (see log)", report.failed);
+            }
+        }
+        Some(Commands::List {
+            path,
+            rating,
+            unrated,
+            tag,
+            dir,
+            preset,
+            min_iso,
+            max_iso,
+            min_aperture,
+            max_aperture,
+            min_shutter,
+            max_shutter,
+            orientation,
+            print_size,
+            print_dpi,
+            include_hidden,
+        }) => {
             let options = ListOptions {
                 min_rating: rating,
+                unrated,
                 tag,
+                dir,
+                preset,
+                exposure: ExposureFilter {
+                    min_iso,
+                    max_iso,
+                    min_aperture,
+                    max_aperture,
+                    min_shutter,
+                    max_shutter,
+                },
+                orientation,
+                print: print_size.map(|size| PrintFilter {
+                    size,
+                    dpi: print_dpi,
+                }),
+                include_hidden,
             };
             let files = run_list(&path, options)?;
             for file in &files {
                 let rating_str = file
                     .rating
-                    .map(|r| format!(" [{}]", "*".repeat(r as usize)))
+                    .map(|r| {
+                        format!(
+                            " [{}{}]",
+                            "*".repeat(r as usize),
+                            if r.fract() >= 0.5 { "½" } else { "" }
+                        )
+                    })
                     .unwrap_or_default();
                 let tags_str = if file.tags.is_empty() {
                     String::new()
@@ -246,10 +1004,175 @@ fn run_command(cli: Cli) -> Result<()> {
             }
             println!("{} files", files.len());
         }
+        Some(Commands::Open {
+            library,
+            query,
+            rating,
+            tag,
+            include_hidden,
+        }) => {
+            let options = OpenOptions {
+                query,
+                min_rating: rating,
+                tag,
+                include_hidden,
+            };
+            let matches = run_open_query(&library, &options)?;
+            for file in &matches {
+                println!("{}", file.path);
+            }
+            if let Some(path) = run_open(&library, &matches)? {
+                eprintln!("Opened {}", path);
+            }
+        }
+        Some(Commands::ContactSheet {
+            library,
+            output,
+            rating,
+            tag,
+            preset,
+            orientation,
+            include_hidden,
+            columns,
+            paper,
+            title,
+        }) => {
+            let options = ContactSheetOptions {
+                filter: ListOptions {
+                    min_rating: rating,
+                    tag,
+                    preset,
+                    orientation,
+                    include_hidden,
+                    ..Default::default()
+                },
+                output,
+                columns,
+                paper,
+                title,
+            };
+            let report = run_contact_sheet(&library, &options)?;
+            println!(
+                "Contact sheet: {} files on {} pages",
+                report.files, report.pages
+            );
+            if report.missing > 0 {
+                println!(
+                    "  {} without a thumbnail (run 'picman thumbnails' first)",
+                    report.missing
+                );
+            }
+            for path in &report.outputs {
+                println!("  {}", path.display());
+            }
+        }
+        Some(Commands::ExportResized {
+            library,
+            files,
+            output,
+            max_edge,
+            quality,
+            metadata,
+            flatten,
+            watermark_text,
+            watermark_image,
+            watermark_position,
+            watermark_opacity,
+            watermark_scale,
+            rating,
+            tag,
+            preset,
+            include_hidden,
+        }) => {
+            let source = match (watermark_text, watermark_image) {
+                (Some(text), _) => Some(WatermarkSource::Text(text)),
+                (None, Some(path)) => Some(WatermarkSource::Image(path)),
+                (None, None) => None,
+            };
+            let watermark = source.map(|source| WatermarkOptions {
+                source,
+                position: watermark_position,
+                opacity: watermark_opacity,
+                scale: watermark_scale,
+            });
+            let options = ExportOptions {
+                filter: ListOptions {
+                    min_rating: rating,
+                    tag,
+                    preset,
+                    include_hidden,
+                    ..Default::default()
+                },
+                selection: files,
+                output,
+                max_edge,
+                quality,
+                metadata,
+                flatten,
+                watermark,
+            };
+            let report = run_export_resized(&library, &options)?;
+            println!(
+                "Exported {} images ({:.1} MB) to {}",
+                report.exported,
+                report.bytes as f64 / (1024.0 * 1024.0),
+                options.output.display()
+            );
+            if report.skipped > 0 {
+                println!("  {} files that are not images skipped", report.skipped);
+            }
+            for (path, reason) in &report.failed {
+                eprintln!("  failed: {} ({})", path, reason);
+            }
+        }
         Some(Commands::Rate {
             library,
             file,
             rating,
+            propagate: Some(propagation),
+            dry_run,
+            ..
+        }) => {
+            let Some(file) = file else {
+                anyhow::bail!("--propagate needs a directory");
+            };
+            let report = run_rate_propagate(&library, &file, rating, propagation, dry_run)?;
+            let prefix = if dry_run { "Would update" } else { "Updated" };
+            match (propagation, report.rating) {
+                (Propagation::Down, Some(r)) => println!(
+                    "{} {} unrated files under {} to {} stars",
+                    prefix,
+                    report.affected,
+                    file.display(),
+                    r
+                ),
+                (Propagation::Up(_), Some(r)) => println!(
+                    "{} {} to {} stars (from {} rated files)",
+                    prefix,
+                    file.display(),
+                    r,
+                    report.affected
+                ),
+                (_, None) => println!("No rated files under {}", file.display()),
+            }
+        }
+        Some(Commands::Rate {
+            library,
+            file: None,
+            ..
+        }) => {
+            if let Some((file, rating)) = run_rate_pick(&library)? {
+                match rating {
+                    Some(r) => println!("Rated {} with {} stars", file, r),
+                    None => println!("Cleared rating from {}", file),
+                }
+            }
+        }
+        Some(Commands::Rate {
+            library,
+            file: Some(file),
+            rating,
+            ..
         }) => {
             run_rate(&library, &file, rating)?;
             match rating {
@@ -257,14 +1180,77 @@ fn run_command(cli: Cli) -> Result<()> {
                 None => println!("Cleared rating from {}", file.display()),
             }
         }
+        Some(Commands::RatingScale { library, scale }) => {
+            let (previous, rescaled) = run_rating_scale(&library, scale)?;
+            if previous == scale {
+                println!("Library already rates on a scale of {}", scale);
+            } else {
+                println!(
+                    "Switched rating scale from {} to {} ({} ratings rescaled)",
+                    previous, scale, rescaled
+                );
+            }
+        }
+        Some(Commands::Hide {
+            library,
+            dir: Some(dir),
+            undo,
+        }) => {
+            let changed = run_hide(&library, &dir, !undo)?;
+            match (undo, changed) {
+                (false, true) => println!("Hid {}", dir.display()),
+                (false, false) => println!("{} was already hidden", dir.display()),
+                (true, true) => println!("Unhid {}", dir.display()),
+                (true, false) => println!("{} was not hidden", dir.display()),
+            }
+        }
+        Some(Commands::Hide {
+            library, dir: None, ..
+        }) => {
+            let hidden = list_hidden(&library)?;
+            for path in &hidden {
+                println!("{}", path);
+            }
+            println!("{} hidden directories", hidden.len());
+        }
+        Some(Commands::Tag {
+            library,
+            file,
+            add,
+            remove,
+            recursive,
+            materialize,
+            ..
+        }) if recursive || materialize => {
+            let options = TagOptions {
+                add,
+                remove,
+                materialize,
+                ..Default::default()
+            };
+            let report = run_tag_directory(&library, &file, options)?;
+            println!(
+                "{}: tagged {} files, untagged {} files and {} directories",
+                file.display(),
+                report.files_tagged,
+                report.files_untagged,
+                report.dirs_untagged
+            );
+        }
         Some(Commands::Tag {
             library,
             file,
             add,
             remove,
             list,
+            ..
         }) => {
-            let options = TagOptions { add, remove, list };
+            let options = TagOptions {
+                add,
+                remove,
+                list,
+                ..Default::default()
+            };
             let tags = run_tag(&library, &file, options)?;
             if tags.is_empty() {
                 println!("{}: no tags", file.display());
@@ -272,7 +1258,202 @@ fn run_command(cli: Cli) -> Result<()> {
                 println!("{}: {}", file.display(), tags.join(", "));
             }
         }
-        Some(Commands::View { rating, tag, output }) => {
+        Some(Commands::Attr { action }) => {
+            let (library, file, action) = match action {
+                AttrCommand::Set {
+                    library,
+                    file,
+                    key,
+                    value,
+                } => (library, file, AttrAction::Set { key, value }),
+                AttrCommand::Get { library, file, key } => (library, file, AttrAction::Get { key }),
+                AttrCommand::Unset { library, file, key } => {
+                    (library, file, AttrAction::Unset { key })
+                }
+            };
+            let single = matches!(action, AttrAction::Get { key: Some(_) });
+            let attributes = run_attr(&library, &file, action)?;
+            if single {
+                // Bare value, so scripts can use `$(picman attr get ...)`
+                for (_, value) in &attributes {
+                    println!("{}", value);
+                }
+            } else if attributes.is_empty() {
+                println!("{}: no attributes", file.display());
+            } else {
+                for (key, value) in &attributes {
+                    println!("{}={}", key, value);
+                }
+            }
+        }
+        Some(Commands::Tags {
+            action: TagsCommand::Alias { action },
+        }) => {
+            let (library, action) = match action {
+                TagAliasCommand::Add {
+                    library,
+                    alias,
+                    tag,
+                } => (
+                    library,
+                    TagAliasAction::Add {
+                        alias,
+                        canonical: tag,
+                    },
+                ),
+                TagAliasCommand::List { library } => (library, TagAliasAction::List),
+                TagAliasCommand::Remove { library, alias } => {
+                    (library, TagAliasAction::Remove { alias })
+                }
+            };
+            let listing = matches!(action, TagAliasAction::List);
+            let (retagged, aliases) = run_tag_alias(&library, action)?;
+            if retagged > 0 {
+                println!("Retagged {} files and directories", retagged);
+            }
+            if listing {
+                if aliases.is_empty() {
+                    println!("No tag aliases");
+                }
+                for (alias, canonical) in &aliases {
+                    println!("{} -> {}", alias, canonical);
+                }
+            }
+        }
+        Some(Commands::Review { action }) => {
+            let (library, action) = match action {
+                ReviewCommand::Create {
+                    library,
+                    name,
+                    rating,
+                    unrated,
+                    tag,
+                    dir,
+                    preset,
+                    orientation,
+                    include_hidden,
+                } => {
+                    let filter = ListOptions {
+                        min_rating: rating,
+                        unrated,
+                        tag,
+                        dir,
+                        preset,
+                        orientation,
+                        include_hidden,
+                        ..Default::default()
+                    };
+                    (
+                        library,
+                        ReviewAction::Create {
+                            name,
+                            filter: Box::new(filter),
+                        },
+                    )
+                }
+                ReviewCommand::List { library } => (library, ReviewAction::List),
+                ReviewCommand::Delete { library, name } => (library, ReviewAction::Delete { name }),
+            };
+            let (created, deleted) = match &action {
+                ReviewAction::Create { name, .. } => (Some(name.trim().to_lowercase()), None),
+                ReviewAction::Delete { name } => (None, Some(name.clone())),
+                ReviewAction::List => (None, None),
+            };
+            let queues = run_review(&library, action)?;
+            if let Some(created) = created {
+                if let Some(queue) = queues.iter().find(|q| q.name.to_lowercase() == created) {
+                    println!(
+                        "Created review queue '{}' with {} files",
+                        queue.name, queue.total
+                    );
+                }
+            } else if let Some(deleted) = deleted {
+                println!("Deleted review queue '{}'", deleted);
+            } else if queues.is_empty() {
+                println!("No review queues");
+            } else {
+                for queue in &queues {
+                    let progress = if queue.is_finished() {
+                        "done".to_string()
+                    } else {
+                        format!("at {}", (queue.position + 1).min(queue.total))
+                    };
+                    println!(
+                        "{}: {}/{} reviewed, {} ({})",
+                        queue.name, queue.decided, queue.total, progress, queue.filter
+                    );
+                }
+            }
+        }
+        Some(Commands::Tags {
+            action: TagsCommand::RetireOrientation { library },
+        }) => {
+            let removed = run_retire_orientation_tags(&library)?;
+            println!("Removed {} orientation tags", removed);
+        }
+        Some(Commands::Archive {
+            library,
+            to,
+            dir,
+            tag,
+            media,
+            min_size,
+            stub,
+            dry_run,
+        }) => {
+            let dir = dir.map(|d| {
+                let d = d.trim_end_matches('/');
+                if d == "." {
+                    String::new()
+                } else {
+                    d.to_string()
+                }
+            });
+            let options = ArchiveOptions {
+                filter: ArchiveFilter {
+                    dir,
+                    tag,
+                    media_type: media,
+                    min_size: min_size.map(|mb| (mb * 1024 * 1024) as i64),
+                },
+                stub,
+                dry_run,
+            };
+            let report = run_archive(&library, &to, &options)?;
+            let megabytes = report.bytes as f64 / (1024.0 * 1024.0);
+            if dry_run {
+                println!(
+                    "Would upload {} files ({:.1} MB)",
+                    report.uploaded, megabytes
+                );
+            } else {
+                println!("Uploaded {} files ({:.1} MB)", report.uploaded, megabytes);
+                if stub {
+                    println!("Replaced {} files with stubs", report.stubbed);
+                }
+            }
+            if report.skipped > 0 {
+                println!("Skipped {} already archived files", report.skipped);
+            }
+            if report.missing > 0 {
+                println!("Skipped {} files missing on disk", report.missing);
+            }
+        }
+        Some(Commands::Restore { library, files }) => {
+            let report = run_restore(&library, &files)?;
+            println!("Restored {} files", report.restored);
+            if report.already_local > 0 {
+                println!(
+                    "{} files were not stubs and were left alone",
+                    report.already_local
+                );
+            }
+        }
+        Some(Commands::View {
+            rating,
+            tag,
+            output,
+        }) => {
             println!("Creating view at: {}", output.display());
             // TODO: Implement view
             let _ = (rating, tag);
@@ -296,11 +1477,16 @@ fn run_command(cli: Cli) -> Result<()> {
                 );
             }
         }
-        Some(Commands::Thumbnails { path, check, web }) => {
+        Some(Commands::Thumbnails {
+            path,
+            check,
+            web,
+            retry_failed,
+        }) => {
             if check {
                 run_check_thumbnails(&path)?;
-            } else if web {
-                let stats = run_generate_web_thumbnails(&path)?;
+            } else if web || retry_failed {
+                let stats = run_generate_web_thumbnails(&path, retry_failed)?;
                 println!(
                     "Done: {} generated, {} skipped, {} failed, {} total",
                     stats.generated, stats.skipped, stats.failed, stats.total
@@ -313,19 +1499,175 @@ fn run_command(cli: Cli) -> Result<()> {
                 );
             }
         }
+        Some(Commands::ImportRatings { path }) => {
+            let report = run_import_ratings(&path)?;
+            println!(
+                "Imported ratings for {} files ({} unrated images checked)",
+                report.imported, report.checked
+            );
+        }
+        Some(Commands::Phash { path }) => {
+            let report = run_phash(&path)?;
+            if report.hashed == 0 && report.errors == 0 {
+                println!(
+                    "All {} images already have perceptual hashes.",
+                    report.already_hashed
+                );
+            } else {
+                println!(
+                    "Perceptual hashes: {} computed, {} failed ({} already hashed)",
+                    report.hashed, report.errors, report.already_hashed
+                );
+            }
+        }
+        Some(Commands::Verify { path }) => {
+            let report = run_verify(&path)?;
+            for file in &report.mismatched {
+                println!("MISMATCH {}", file.display());
+            }
+            for file in &report.unreadable {
+                println!("UNREADABLE {}", file.display());
+            }
+            println!(
+                "Verified {} files: {} mismatched, {} unreadable",
+                report.checked,
+                report.mismatched.len(),
+                report.unreadable.len()
+            );
+            if !report.mismatched.is_empty() || !report.unreadable.is_empty() {
+                anyhow::bail!(
+                    "{} files failed verification",
+                    report.mismatched.len() + report.unreadable.len()
+                );
+            }
+        }
         Some(Commands::Repair { path }) => {
-            let fixed = run_repair(&path)?;
-            if fixed == 0 {
+            let report = run_repair(&path)?;
+            if report.parents_fixed == 0 {
                 println!("All directory parent relationships are correct.");
             } else {
-                println!("Fixed {} directory parent relationships.", fixed);
+                println!(
+                    "Fixed {} directory parent relationships.",
+                    report.parents_fixed
+                );
+            }
+            if report.stats_rebuilt {
+                println!("Rebuilt library statistics, which were out of date.");
+            }
+        }
+        Some(Commands::Stats {
+            path,
+            heatmap: Some(year),
+            ..
+        }) => {
+            let days = run_heatmap(&path, year)?;
+            if days.is_empty() {
+                println!(
+                    "No photos with a capture date in {}. Run 'picman sync' to read new files.",
+                    year
+                );
+            } else {
+                for line in render_heatmap(year, &days) {
+                    println!("{}", line);
+                }
+            }
+        }
+        Some(Commands::Stats {
+            path,
+            by,
+            heatmap: None,
+        }) => {
+            let stats = run_stats(&path, by)?;
+            if stats.is_empty() {
+                println!("No images with EXIF read yet. Run 'picman sync' first.");
+            }
+            for stat in stats {
+                let average = stat
+                    .average_rating
+                    .map(|r| format!("  avg {:.1} ({} rated)", r, stat.rated))
+                    .unwrap_or_default();
+                println!(
+                    "{:>7}  {}{}",
+                    stat.files,
+                    stat.name.as_deref().unwrap_or("(unknown)"),
+                    average
+                );
             }
         }
         Some(Commands::Status { path }) => {
             run_status(&path)?;
         }
-        Some(Commands::Serve { path, port }) => {
-            run_serve(&path, port)?;
+        Some(Commands::Serve {
+            path,
+            port,
+            tls_cert,
+            tls_key,
+            redirect_port,
+            pid_file,
+            stop,
+        }) => {
+            if stop {
+                let pid = stop_serve(&path, pid_file.as_deref())?;
+                println!("Stopped server (pid {})", pid);
+                return Ok(());
+            }
+            let tls = match (tls_cert, tls_key) {
+                (Some(cert), Some(key)) => Some(TlsOptions {
+                    cert,
+                    key,
+                    redirect_port,
+                }),
+                _ => None,
+            };
+            run_serve_scheduled(
+                &path,
+                ServeOptions {
+                    port,
+                    tls,
+                    pid_file,
+                    daemon: None,
+                },
+            )?;
+        }
+        Some(Commands::Daemon {
+            path,
+            port,
+            tls_cert,
+            tls_key,
+            redirect_port,
+            pid_file,
+            watch_interval,
+            maintenance_interval,
+            control_socket,
+        }) => {
+            let tls = match (tls_cert, tls_key) {
+                (Some(cert), Some(key)) => Some(TlsOptions {
+                    cert,
+                    key,
+                    redirect_port,
+                }),
+                _ => None,
+            };
+            run_daemon(
+                &path,
+                DaemonOptions {
+                    port,
+                    tls,
+                    pid_file,
+                    watch_interval: Duration::from_secs(watch_interval),
+                    maintenance_interval: Duration::from_secs(maintenance_interval * 60),
+                    control_socket: control_socket.map(|socket| {
+                        if socket.as_os_str().is_empty() {
+                            path.join(".picman.sock")
+                        } else {
+                            socket
+                        }
+                    }),
+                },
+            )?;
+        }
+        Some(Commands::Completions { shell }) => {
+            print!("{}", completion_script(shell, &mut Cli::command()));
         }
         None => {
             // Launch TUI
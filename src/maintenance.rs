@@ -0,0 +1,240 @@
+//! Maintenance tasks that can be scheduled from `.picman.toml` (see
+//! [`crate::config`]) and run by `picman daemon` / `picman serve`.
+//!
+//! The outcome of each run is stored in the `maintenance_runs` table so
+//! `picman status` can show it and missed runs are caught up after restarts.
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDateTime};
+
+use crate::cli::{run_cache_gc, run_generate_thumbnails, run_generate_web_thumbnails, run_sync};
+use crate::config::Config;
+use crate::cron::CronSchedule;
+use crate::db::{Database, MaintenanceRun};
+use crate::process::unix_now;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaintenanceTask {
+    /// Incremental sync plus hashing of files without a hash
+    HashBackfill,
+    /// Generate missing thumbnails and web thumbnails
+    Thumbnails,
+    /// Remove orphaned cache entries
+    CacheGc,
+}
+
+impl MaintenanceTask {
+    pub const ALL: [Self; 3] = [Self::HashBackfill, Self::Thumbnails, Self::CacheGc];
+
+    /// The task called `name`, as [`MaintenanceTask::name`] gives it
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.name() == name)
+    }
+
+    /// Name used in `.picman.toml`, the DB, and `/api/health`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::HashBackfill => "hash_backfill",
+            Self::Thumbnails => "thumbnails",
+            Self::CacheGc => "cache_gc",
+        }
+    }
+
+    /// Run the task, returning a one-line summary
+    pub fn run(self, library_path: &Path) -> Result<String> {
+        match self {
+            Self::HashBackfill => {
+                let sync = run_sync(library_path, true, false, false)?;
+                Ok(format!("{} hashed ({} errors)", sync.files_hashed, sync.hash_errors))
+            }
+            Self::Thumbnails => {
+                let thumbs = run_generate_thumbnails(library_path)?;
+                let web = run_generate_web_thumbnails(library_path, false)?;
+                Ok(format!(
+                    "{} thumbnails, {} web thumbnails",
+                    thumbs.generated, web.generated
+                ))
+            }
+            Self::CacheGc => {
+                let gc = run_cache_gc(library_path)?;
+                Ok(format!(
+                    "{} removed, {:.1} MB freed",
+                    gc.removed,
+                    gc.bytes_freed as f64 / 1_048_576.0
+                ))
+            }
+        }
+    }
+}
+
+/// Store the outcome of a task run in the library database
+pub fn record_run(
+    library_path: &Path,
+    task: MaintenanceTask,
+    duration: Duration,
+    result: &Result<String>,
+) -> Result<()> {
+    let db = Database::open(&library_path.join(".picman.db"))?;
+    db.record_maintenance_run(&MaintenanceRun {
+        task: task.name().to_string(),
+        last_run: unix_now(),
+        duration_ms: duration.as_millis() as i64,
+        success: result.is_ok(),
+        summary: match result {
+            Ok(summary) => Some(summary.clone()),
+            Err(e) => Some(format!("{:#}", e)),
+        },
+    })
+}
+
+/// A task with its cron schedule and the next time it should run
+#[derive(Debug, Clone)]
+pub struct ScheduledTask {
+    pub task: MaintenanceTask,
+    pub schedule: CronSchedule,
+    pub next_run: Option<NaiveDateTime>,
+}
+
+impl ScheduledTask {
+    /// Schedule relative to the last recorded run, so a run missed while
+    /// picman was down is due immediately. Never-run tasks wait for their
+    /// first slot.
+    pub fn new(
+        task: MaintenanceTask,
+        schedule: CronSchedule,
+        last_run: Option<i64>,
+        now: NaiveDateTime,
+    ) -> Self {
+        let from = last_run.and_then(local_from_unix).unwrap_or(now);
+        let next_run = schedule.next_after(from);
+        Self {
+            task,
+            schedule,
+            next_run,
+        }
+    }
+
+    pub fn is_due(&self, now: NaiveDateTime) -> bool {
+        self.next_run.is_some_and(|next| now >= next)
+    }
+
+    /// Move to the first slot after `now` (skips slots missed while running)
+    pub fn advance(&mut self, now: NaiveDateTime) {
+        self.next_run = self.schedule.next_after(now);
+    }
+}
+
+/// Build the schedule configured in the library's `.picman.toml`,
+/// seeded with last-run times from the database
+pub fn load_schedule(library_path: &Path) -> Result<Vec<ScheduledTask>> {
+    let config = Config::load(library_path)?;
+    let schedules = config.maintenance.schedules()?;
+    if schedules.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Don't create a database just to read run history
+    let db_path = library_path.join(".picman.db");
+    let runs = if db_path.exists() {
+        Database::open(&db_path)?.get_maintenance_runs()?
+    } else {
+        Vec::new()
+    };
+    let now = local_now();
+
+    Ok(schedules
+        .into_iter()
+        .map(|(task, schedule)| {
+            let last_run = runs
+                .iter()
+                .find(|r| r.task == task.name())
+                .map(|r| r.last_run);
+            ScheduledTask::new(task, schedule, last_run, now)
+        })
+        .collect())
+}
+
+pub fn local_now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+/// Convert a Unix timestamp to local wall-clock time
+pub fn local_from_unix(ts: i64) -> Option<NaiveDateTime> {
+    DateTime::from_timestamp(ts, 0).map(|dt| dt.with_timezone(&Local).naive_local())
+}
+
+/// Convert local wall-clock time to a Unix timestamp
+pub fn unix_from_local(t: NaiveDateTime) -> Option<i64> {
+    t.and_local_timezone(Local).earliest().map(|dt| dt.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(d: u32, h: u32, mi: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, d)
+            .unwrap()
+            .and_hms_opt(h, mi, 0)
+            .unwrap()
+    }
+
+    fn nightly() -> CronSchedule {
+        CronSchedule::parse("0 3 * * *").unwrap()
+    }
+
+    #[test]
+    fn test_never_run_task_waits_for_first_slot() {
+        let task = ScheduledTask::new(MaintenanceTask::HashBackfill, nightly(), None, at(1, 12, 0));
+        assert_eq!(task.next_run, Some(at(2, 3, 0)));
+        assert!(!task.is_due(at(1, 12, 0)));
+        assert!(task.is_due(at(2, 3, 0)));
+    }
+
+    #[test]
+    fn test_missed_run_is_due_immediately() {
+        // Last ran on the 1st at 03:00; picman was down over the 2nd's slot
+        let last = unix_from_local(at(1, 3, 0)).unwrap();
+        let task = ScheduledTask::new(MaintenanceTask::Thumbnails, nightly(), Some(last), at(2, 9, 0));
+        assert_eq!(task.next_run, Some(at(2, 3, 0)));
+        assert!(task.is_due(at(2, 9, 0)));
+    }
+
+    #[test]
+    fn test_advance_skips_past_slots() {
+        let mut task = ScheduledTask::new(MaintenanceTask::CacheGc, nightly(), None, at(1, 0, 0));
+        task.advance(at(4, 5, 0));
+        assert_eq!(task.next_run, Some(at(5, 3, 0)));
+    }
+
+    #[test]
+    fn test_record_run_and_load_schedule() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        crate::cli::run_init(tmp.path()).unwrap();
+        std::fs::write(
+            crate::config::config_path(tmp.path()),
+            "[maintenance]\nhash_backfill = \"0 3 * * *\"\n",
+        )
+        .unwrap();
+
+        let result: Result<String> = Err(anyhow::anyhow!("disk full"));
+        record_run(tmp.path(), MaintenanceTask::HashBackfill, Duration::from_millis(5), &result)
+            .unwrap();
+
+        let db = Database::open(&tmp.path().join(".picman.db")).unwrap();
+        let runs = db.get_maintenance_runs().unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].task, "hash_backfill");
+        assert!(!runs[0].success);
+        assert_eq!(runs[0].summary.as_deref(), Some("disk full"));
+
+        let schedule = load_schedule(tmp.path()).unwrap();
+        assert_eq!(schedule.len(), 1);
+        assert_eq!(schedule[0].task, MaintenanceTask::HashBackfill);
+        assert!(schedule[0].next_run.is_some());
+    }
+}
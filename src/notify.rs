@@ -0,0 +1,111 @@
+//! Desktop notifications for long background jobs.
+//!
+//! Notifications go through `notify-rust`: the freedesktop notification
+//! service over D-Bus on Linux and the BSDs, Notification Center on macOS,
+//! toasts on Windows. Without a notification service (no desktop session,
+//! a headless server) the first failure is logged as a warning and later
+//! ones at debug level; a notification is never worth failing a job over.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use notify_rust::Notification;
+use tracing::{debug, warn};
+
+use crate::config::{Config, NotificationsConfig};
+
+/// Set once a notification has failed, so a headless daemon warns only once
+static FAILURE_REPORTED: AtomicBool = AtomicBool::new(false);
+
+/// The kinds of job a notification can be configured for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    Thumbnails,
+    Hashing,
+    Sync,
+}
+
+impl JobKind {
+    fn title(self) -> &'static str {
+        match self {
+            Self::Thumbnails => "Thumbnails ready",
+            Self::Hashing => "Hashing finished",
+            Self::Sync => "Sync finished",
+        }
+    }
+}
+
+/// Sends notifications as a library's `[notifications]` config allows
+#[derive(Debug, Clone, Default)]
+pub struct Notifier {
+    config: NotificationsConfig,
+}
+
+impl Notifier {
+    pub fn new(config: NotificationsConfig) -> Self {
+        Self { config }
+    }
+
+    /// Notifier for a library; notifications stay off if its config is unreadable
+    pub fn for_library(library_path: &Path) -> Self {
+        match Config::load(library_path) {
+            Ok(config) => Self::new(config.notifications),
+            Err(e) => {
+                warn!(error = %format!("{:#}", e), "not sending notifications");
+                Self::default()
+            }
+        }
+    }
+
+    /// Whether a job of `kind` that ran for `elapsed` should notify
+    pub fn wants(&self, kind: JobKind, elapsed: Duration) -> bool {
+        let enabled_for_kind = match kind {
+            JobKind::Thumbnails => self.config.thumbnails,
+            JobKind::Hashing => self.config.hashing,
+            JobKind::Sync => self.config.sync,
+        };
+        self.config.enabled && enabled_for_kind && elapsed.as_secs() >= self.config.min_seconds
+    }
+
+    /// Announce a finished job, e.g. "1200 files hashed", if configured to.
+    /// Returns immediately; the notification is sent on its own thread.
+    pub fn job_finished(&self, kind: JobKind, elapsed: Duration, summary: &str) {
+        if !self.wants(kind, elapsed) {
+            return;
+        }
+        let title = kind.title();
+        let body = summary.to_string();
+        thread::spawn(move || {
+            if let Err(e) = Notification::new().appname("picman").summary(title).body(&body).show() {
+                if FAILURE_REPORTED.swap(true, Ordering::Relaxed) {
+                    debug!(error = %e, "failed to show notification");
+                } else {
+                    warn!(error = %e, "no desktop notification service; notifications are not shown");
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wants_respects_config() {
+        let long = Duration::from_secs(120);
+        assert!(!Notifier::default().wants(JobKind::Hashing, long));
+
+        let notifier = Notifier::new(NotificationsConfig {
+            enabled: true,
+            sync: false,
+            ..Default::default()
+        });
+        assert!(notifier.wants(JobKind::Hashing, long));
+        assert!(notifier.wants(JobKind::Thumbnails, long));
+        assert!(!notifier.wants(JobKind::Sync, long));
+        assert!(!notifier.wants(JobKind::Hashing, Duration::from_secs(5)));
+    }
+}
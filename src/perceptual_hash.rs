@@ -3,12 +3,14 @@ use std::path::Path;
 use anyhow::Result;
 use image::imageops::FilterType;
 
-use crate::thumbnails::{apply_exif_orientation, get_preview_path_for_file, is_image_file};
+use crate::thumbnails::{
+    apply_exif_orientation, get_preview_path_for_file, get_web_thumbnail_path, is_image_file, open_image,
+};
 
 /// Compute dHash (difference hash) for an image. Returns 64-bit perceptual hash.
 ///
 /// Algorithm:
-/// 1. Load image (prefer cached thumbnail for speed, fall back to original)
+/// 1. Load image (prefer cached web or TUI thumbnail for speed, fall back to original)
 /// 2. Apply EXIF orientation correction
 /// 3. Resize to 9×8 grayscale
 /// 4. For each of 8 rows, compare 8 adjacent pixel pairs → 64 bits
@@ -24,11 +26,22 @@ pub fn compute_perceptual_hash(path: &Path) -> Result<u64> {
     Ok(dhash_from_image(&img))
 }
 
-/// Load an image, preferring cached thumbnail for speed.
+/// Load an image, preferring the smallest cached thumbnail for speed.
+/// The hash only looks at 9×8 pixels, so any downscaled copy will do.
 fn load_image(path: &Path) -> Result<image::DynamicImage> {
-    // Try cached thumbnail first (much faster than loading full-res original)
+    // 400px web thumbnails decode fastest
+    if let Some(web_thumb) = get_web_thumbnail_path(path).filter(|p| p.exists()) {
+        crate::workers::throttle_read(&web_thumb);
+        if let Ok(img) = image::open(&web_thumb) {
+            // Web thumbnails have EXIF applied too
+            return Ok(img);
+        }
+    }
+
+    // Then the 1440p TUI thumbnail (much faster than loading full-res original)
     if let Some((preview_path, is_thumbnail)) = get_preview_path_for_file(path) {
         if is_thumbnail {
+            crate::workers::throttle_read(&preview_path);
             if let Ok(img) = image::open(&preview_path) {
                 // Thumbnails already have EXIF applied during generation
                 return Ok(img);
@@ -37,8 +50,9 @@ fn load_image(path: &Path) -> Result<image::DynamicImage> {
     }
 
     // Fall back to original with EXIF correction
-    let img = image::open(path)
-        .map_err(|e| anyhow::anyhow!("Failed to open image {}: {}", path.display(), e))?;
+    crate::workers::throttle_read(path);
+    let img = open_image(path)
+        .map_err(|e| anyhow::anyhow!("Failed to open image {}: {}", path.display(), e.reason))?;
     Ok(apply_exif_orientation(path, img))
 }
 
@@ -81,6 +95,75 @@ pub fn group_by_similarity(hashes: &[(i64, u64)], threshold: u32) -> Vec<Vec<i64
         return Vec::new();
     }
 
+    // Compare all pairs — O(n²) but with tiny constant (popcount on u64)
+    let pairs = (0..n).flat_map(|i| ((i + 1)..n).map(move |j| (i, j))).filter(|&(i, j)| {
+        hamming_distance(hashes[i].1, hashes[j].1) <= threshold
+    });
+    connected_groups(hashes, pairs)
+}
+
+/// Widest threshold a [`SimilarityIndex`] answers for
+pub const MAX_INDEXED_THRESHOLD: u32 = 16;
+
+/// Every pair of perceptual hashes within [`MAX_INDEXED_THRESHOLD`] of each
+/// other, found once so grouping at any threshold up to it is a pass over
+/// the close pairs instead of another all-pairs comparison. Built for the
+/// web duplicates view, where a threshold slider regroups on every change.
+#[derive(Debug)]
+pub struct SimilarityIndex {
+    hashes: Vec<(i64, u64)>,
+    /// (index into `hashes`, index into `hashes`, distance), closest first
+    pairs: Vec<(usize, usize, u32)>,
+}
+
+impl SimilarityIndex {
+    pub fn build(hashes: Vec<(i64, u64)>) -> Self {
+        Self::build_counting(hashes, &std::sync::atomic::AtomicUsize::new(0))
+    }
+
+    /// Like [`SimilarityIndex::build`], adding one to `compared` for each
+    /// hash compared with the rest (out of `hashes.len()`)
+    pub fn build_counting(hashes: Vec<(i64, u64)>, compared: &std::sync::atomic::AtomicUsize) -> Self {
+        use rayon::prelude::*;
+        use std::sync::atomic::Ordering;
+
+        let mut pairs: Vec<(usize, usize, u32)> = (0..hashes.len())
+            .into_par_iter()
+            .flat_map_iter(|i| {
+                let hashes = &hashes;
+                compared.fetch_add(1, Ordering::Relaxed);
+                ((i + 1)..hashes.len()).filter_map(move |j| {
+                    let distance = hamming_distance(hashes[i].1, hashes[j].1);
+                    (distance <= MAX_INDEXED_THRESHOLD).then_some((i, j, distance))
+                })
+            })
+            .collect();
+        pairs.sort_by_key(|&(i, j, distance)| (distance, i, j));
+        Self { hashes, pairs }
+    }
+
+    /// The (file id, hash) list the index was built from; a different list
+    /// means the index is stale
+    pub fn hashes(&self) -> &[(i64, u64)] {
+        &self.hashes
+    }
+
+    /// Same groups as [`group_by_similarity`], for thresholds up to
+    /// [`MAX_INDEXED_THRESHOLD`]
+    pub fn groups(&self, threshold: u32) -> Vec<Vec<i64>> {
+        let close = self
+            .pairs
+            .iter()
+            .take_while(|&&(_, _, distance)| distance <= threshold)
+            .map(|&(i, j, _)| (i, j));
+        connected_groups(&self.hashes, close)
+    }
+}
+
+/// Connected components of 2+ files, given pairs of indices into `hashes`.
+/// Members keep the order of `hashes`, and groups are ordered by their first member.
+fn connected_groups(hashes: &[(i64, u64)], pairs: impl Iterator<Item = (usize, usize)>) -> Vec<Vec<i64>> {
+    let n = hashes.len();
     let mut parent: Vec<usize> = (0..n).collect();
     let mut rank: Vec<usize> = vec![0; n];
 
@@ -109,27 +192,24 @@ pub fn group_by_similarity(hashes: &[(i64, u64)], threshold: u32) -> Vec<Vec<i64
         }
     }
 
-    // Compare all pairs — O(n²) but with tiny constant (popcount on u64)
-    for i in 0..n {
-        for j in (i + 1)..n {
-            if hamming_distance(hashes[i].1, hashes[j].1) <= threshold {
-                union(&mut parent, &mut rank, i, j);
-            }
-        }
+    for (i, j) in pairs {
+        union(&mut parent, &mut rank, i, j);
     }
 
-    // Collect connected components
-    let mut groups: std::collections::HashMap<usize, Vec<i64>> = std::collections::HashMap::new();
+    // Collect connected components, in order of first appearance
+    let mut group_of_root: std::collections::HashMap<usize, usize> = std::collections::HashMap::new();
+    let mut groups: Vec<Vec<i64>> = Vec::new();
     for (i, (file_id, _)) in hashes.iter().enumerate() {
         let root = find(&mut parent, i);
-        groups.entry(root).or_default().push(*file_id);
+        let group = *group_of_root.entry(root).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(*file_id);
     }
 
     // Return only groups with 2+ members
-    groups
-        .into_values()
-        .filter(|g| g.len() >= 2)
-        .collect()
+    groups.into_iter().filter(|g| g.len() >= 2).collect()
 }
 
 #[cfg(test)]
@@ -307,6 +387,25 @@ mod tests {
         assert!(!group_with_3.contains(&1));
     }
 
+    #[test]
+    fn test_similarity_index_matches_direct_grouping() {
+        let hashes = vec![
+            (1, 0x0000_0000_0000_0000u64),
+            (2, 0x0000_0000_0000_0003u64), // distance 2 from #1
+            (3, 0x0000_0000_0000_00FFu64), // distance 6 from #2
+            (4, 0xFFFF_FFFF_FFFF_FFFFu64),
+            (5, 0xFFFF_FFFF_FFFF_FFFEu64), // distance 1 from #4
+        ];
+        let index = SimilarityIndex::build(hashes.clone());
+        for threshold in 0..=MAX_INDEXED_THRESHOLD {
+            assert_eq!(index.groups(threshold), group_by_similarity(&hashes, threshold), "threshold {}", threshold);
+        }
+        assert_eq!(index.groups(1), vec![vec![4, 5]]);
+        assert_eq!(index.groups(2), vec![vec![1, 2], vec![4, 5]]);
+        assert_eq!(index.groups(6), vec![vec![1, 2, 3], vec![4, 5]]);
+        assert_eq!(index.hashes(), hashes.as_slice());
+    }
+
     /// Helper to create a test image with a given pixel generator
     fn create_test_image(
         width: u32,
@@ -0,0 +1,169 @@
+//! Print sizes from stored pixel dimensions: how large an image prints at
+//! common resolutions (300 dpi for close viewing, 240 for most photo labs,
+//! 150 for posters seen from a distance), and whether it's enough for a
+//! given paper size. Dimensions are stored with EXIF rotation applied, and
+//! a print fits in either orientation.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::db::File;
+
+/// Resolutions the details panel and `/api/files/{id}` report
+pub const PRINT_DPIS: [u32; 3] = [300, 240, 150];
+
+/// Resolution `--print-size` checks against unless told otherwise
+pub const DEFAULT_PRINT_DPI: u32 = 300;
+
+const MM_PER_INCH: f64 = 25.4;
+
+/// Width and height in inches of the largest print of a `width` x `height`
+/// image at `dpi`
+pub fn print_inches(width: i32, height: i32, dpi: u32) -> (f64, f64) {
+    (width as f64 / dpi as f64, height as f64 / dpi as f64)
+}
+
+/// Whether a file of this media type gets print sizes: images and
+/// documents, not video frames
+pub fn is_printable(media_type: Option<&str>) -> bool {
+    media_type != Some("video")
+}
+
+/// `13.3×20.0 in (34×51 cm)`
+pub fn describe_inches(width: f64, height: f64) -> String {
+    format!(
+        "{:.1}×{:.1} in ({:.0}×{:.0} cm)",
+        width,
+        height,
+        width * MM_PER_INCH / 10.0,
+        height * MM_PER_INCH / 10.0
+    )
+}
+
+/// A paper size, in inches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrintSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl PrintSize {
+    /// Pixels needed along each side at `dpi`
+    pub fn pixels(self, dpi: u32) -> (i64, i64) {
+        let px = |inches: f64| (inches * dpi as f64).round() as i64;
+        (px(self.width), px(self.height))
+    }
+}
+
+impl FromStr for PrintSize {
+    type Err = String;
+
+    /// `8x10` (inches), `8x10in`, `20x30cm`, `130x180mm`, or an ISO A size
+    /// (`A4`, `a3`...)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("unknown print size '{}' (expected e.g. 8x10, 20x30cm or A4)", s);
+        let lower = s.trim().to_lowercase();
+
+        if let Some(n) = lower.strip_prefix('a').and_then(|n| n.parse::<u32>().ok()).filter(|n| *n <= 10) {
+            // A0 is 1 m², sides in a √2 ratio; each size halves the previous
+            let (mut long, mut short) = (1189.0_f64, 841.0_f64);
+            for _ in 0..n {
+                (long, short) = (short, (long / 2.0).floor());
+            }
+            return Ok(Self { width: short / MM_PER_INCH, height: long / MM_PER_INCH });
+        }
+
+        let number = lower.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '"');
+        let per_inch = match &lower[number.len()..] {
+            "" | "in" | "inch" | "\"" => 1.0,
+            "cm" => MM_PER_INCH / 10.0,
+            "mm" => MM_PER_INCH,
+            _ => return Err(error()),
+        };
+        let (w, h) = number.split_once(['x', '×']).ok_or_else(error)?;
+        let side = |v: &str| v.trim().parse::<f64>().ok().filter(|v| *v > 0.0).ok_or_else(error);
+        Ok(Self { width: side(w)? / per_inch, height: side(h)? / per_inch })
+    }
+}
+
+impl fmt::Display for PrintSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}×{} in", self.width, self.height)
+    }
+}
+
+/// Files big enough to print at `size` with at least `dpi`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrintFilter {
+    pub size: PrintSize,
+    pub dpi: u32,
+}
+
+impl PrintFilter {
+    pub fn new(size: PrintSize) -> Self {
+        Self { size, dpi: DEFAULT_PRINT_DPI }
+    }
+
+    /// Whether a `width` x `height` file has the pixels, either way round;
+    /// false without dimensions
+    pub fn fits(self, width: Option<i32>, height: Option<i32>) -> bool {
+        let (Some(w), Some(h)) = (width, height) else {
+            return false;
+        };
+        let (need_w, need_h) = self.size.pixels(self.dpi);
+        let (w, h) = (w as i64, h as i64);
+        (w >= need_w && h >= need_h) || (w >= need_h && h >= need_w)
+    }
+
+    /// Whether `file` is printable and big enough
+    pub fn matches(self, file: &File) -> bool {
+        is_printable(file.media_type.as_deref()) && self.fits(file.width, file.height)
+    }
+
+    /// SQL condition on `f.width`, `f.height` and `f.media_type` matching
+    /// the files [`PrintFilter::matches`] accepts
+    pub fn sql_condition(self) -> String {
+        let (w, h) = self.size.pixels(self.dpi);
+        format!(
+            "(f.media_type IS NOT 'video' AND ((f.width >= {w} AND f.height >= {h}) OR (f.width >= {h} AND f.height >= {w})))",
+            w = w,
+            h = h
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_print_sizes() {
+        assert_eq!("8x10".parse(), Ok(PrintSize { width: 8.0, height: 10.0 }));
+        assert_eq!("8 x 10 in".parse(), Ok(PrintSize { width: 8.0, height: 10.0 }));
+        assert_eq!("25.4x50.8cm".parse::<PrintSize>().unwrap().pixels(100), (1000, 2000));
+        assert_eq!("254×508mm".parse::<PrintSize>().unwrap().pixels(100), (1000, 2000));
+        // The standard 300 dpi A sizes
+        assert_eq!("A4".parse::<PrintSize>().unwrap().pixels(300), (2480, 3508));
+        assert_eq!("a3".parse::<PrintSize>().unwrap().pixels(300), (3508, 4961));
+        assert!("8x".parse::<PrintSize>().is_err());
+        assert!("8x10ft".parse::<PrintSize>().is_err());
+        assert!("A11".parse::<PrintSize>().is_err());
+    }
+
+    #[test]
+    fn test_fits_either_way_round() {
+        let filter = PrintFilter::new("8x10".parse().unwrap());
+        assert!(filter.fits(Some(2400), Some(3000)));
+        assert!(filter.fits(Some(3000), Some(2400)));
+        assert!(!filter.fits(Some(2399), Some(3000)));
+        assert!(!filter.fits(None, Some(3000)));
+        assert!(PrintFilter { dpi: 150, ..filter }.fits(Some(1200), Some(1500)));
+        assert_eq!(
+            filter.sql_condition(),
+            "(f.media_type IS NOT 'video' AND ((f.width >= 2400 AND f.height >= 3000) OR (f.width >= 3000 AND f.height >= 2400)))"
+        );
+
+        assert_eq!(print_inches(6000, 4000, 300), (20.0, 4000.0 / 300.0));
+        assert_eq!(describe_inches(20.0, 13.333), "20.0×13.3 in (51×34 cm)");
+    }
+}
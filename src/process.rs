@@ -0,0 +1,48 @@
+//! Process helpers shared by the CLI, the server and the daemon: whether a
+//! PID is alive (sync locks, server PID files) and the current Unix time.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Whether a process with this PID exists
+#[cfg(unix)]
+pub fn is_running(pid: u32) -> bool {
+    let Some(pid) = signal_target(pid) else {
+        return false;
+    };
+    // Signal 0 performs error checking only; EPERM still means "exists"
+    let ret = unsafe { libc::kill(pid, 0) };
+    ret == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// The pid as `kill` takes it, or None for values that would signal a
+/// process group instead of a single process
+#[cfg(unix)]
+pub fn signal_target(pid: u32) -> Option<libc::pid_t> {
+    libc::pid_t::try_from(pid).ok().filter(|&pid| pid > 0)
+}
+
+#[cfg(not(unix))]
+pub fn is_running(_pid: u32) -> bool {
+    false
+}
+
+/// Seconds since the Unix epoch
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_running_ignores_group_pids() {
+        assert!(!is_running(0));
+        assert!(!is_running(u32::MAX));
+        assert!(is_running(std::process::id()));
+    }
+}
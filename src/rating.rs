@@ -0,0 +1,108 @@
+//! Rating scales. A library rates on 1-5 (the default) or 1-10, set with
+//! `[ratings] scale` in `.picman.toml` and changed with `picman rating-scale`,
+//! which rescales the ratings already stored. Ratings go in half steps on
+//! either scale (3.5 is half a star above 3).
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(try_from = "i64")]
+pub enum RatingScale {
+    #[default]
+    Five,
+    Ten,
+}
+
+impl RatingScale {
+    /// Highest rating on this scale
+    pub fn max(self) -> i32 {
+        match self {
+            RatingScale::Five => 5,
+            RatingScale::Ten => 10,
+        }
+    }
+
+    /// Reject ratings outside 1..=max or between half steps
+    pub fn validate(self, rating: f64) -> Result<()> {
+        if !(1.0..=self.max() as f64).contains(&rating) {
+            bail!("Rating must be between 1 and {}", self.max());
+        }
+        if (rating * 2.0).fract() != 0.0 {
+            bail!("Ratings go in half steps (got {})", rating);
+        }
+        Ok(())
+    }
+
+    /// Map a rating on this scale to the same place on `to`. Going down,
+    /// values round up to the next half step (7 becomes 3.5, 7.5 becomes 4)
+    /// and never drop below 1.
+    pub fn convert(self, rating: f64, to: RatingScale) -> f64 {
+        match (self, to) {
+            (RatingScale::Five, RatingScale::Ten) => rating * 2.0,
+            (RatingScale::Ten, RatingScale::Five) => (rating.ceil() / 2.0).max(1.0),
+            _ => rating,
+        }
+    }
+}
+
+impl TryFrom<i64> for RatingScale {
+    type Error = String;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        match value {
+            5 => Ok(RatingScale::Five),
+            10 => Ok(RatingScale::Ten),
+            _ => Err(format!("unsupported rating scale {} (expected 5 or 10)", value)),
+        }
+    }
+}
+
+impl FromStr for RatingScale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: i64 = s
+            .parse()
+            .map_err(|_| format!("unsupported rating scale '{}' (expected 5 or 10)", s))?;
+        RatingScale::try_from(value)
+    }
+}
+
+impl fmt::Display for RatingScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.max())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_round_trips_five_through_ten() {
+        for halves in 2..=10 {
+            let rating = halves as f64 / 2.0;
+            let ten = RatingScale::Five.convert(rating, RatingScale::Ten);
+            assert_eq!(RatingScale::Ten.convert(ten, RatingScale::Five), rating);
+        }
+        assert_eq!(RatingScale::Ten.convert(1.0, RatingScale::Five), 1.0);
+        assert_eq!(RatingScale::Ten.convert(7.0, RatingScale::Five), 3.5);
+        assert_eq!(RatingScale::Ten.convert(7.5, RatingScale::Five), 4.0);
+        assert_eq!(RatingScale::Ten.convert(10.0, RatingScale::Five), 5.0);
+    }
+
+    #[test]
+    fn test_validate_and_parse() {
+        assert!(RatingScale::Five.validate(6.0).is_err());
+        assert!(RatingScale::Five.validate(4.5).is_ok());
+        assert!(RatingScale::Five.validate(4.25).is_err());
+        assert!(RatingScale::Ten.validate(6.0).is_ok());
+        assert!(RatingScale::Ten.validate(0.5).is_err());
+        assert_eq!("10".parse::<RatingScale>(), Ok(RatingScale::Ten));
+        assert!("7".parse::<RatingScale>().is_err());
+    }
+}
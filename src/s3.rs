@@ -0,0 +1,122 @@
+//! Object storage access for `picman archive`, via the AWS CLI.
+//!
+//! Uploads and downloads shell out to `aws s3 cp`, so credentials, profiles
+//! and regions come from the usual AWS configuration. S3-compatible stores
+//! (MinIO, Backblaze B2, Wasabi) work through `archive.endpoint_url`.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::ArchiveConfig;
+
+/// A bucket and key prefix parsed from `s3://bucket/prefix`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Location {
+    pub bucket: String,
+    /// Key prefix without leading or trailing slashes (may be empty)
+    pub prefix: String,
+}
+
+impl S3Location {
+    pub fn parse(url: &str) -> Result<Self> {
+        let Some(rest) = url.strip_prefix("s3://") else {
+            bail!("Expected s3://bucket/prefix, got '{}'", url);
+        };
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            bail!("Archive URL '{}' has no bucket", url);
+        }
+        Ok(Self {
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+        })
+    }
+
+    /// Object URL for a library-relative path
+    pub fn object_url(&self, relative_path: &str) -> String {
+        if self.prefix.is_empty() {
+            format!("s3://{}/{}", self.bucket, relative_path)
+        } else {
+            format!("s3://{}/{}/{}", self.bucket, self.prefix, relative_path)
+        }
+    }
+}
+
+/// Copies files to and from object storage with the AWS CLI
+pub struct S3Client {
+    command: Vec<String>,
+    endpoint_url: Option<String>,
+}
+
+impl S3Client {
+    pub fn new(config: &ArchiveConfig) -> Result<Self> {
+        let command: Vec<String> = config
+            .aws_command
+            .as_deref()
+            .unwrap_or("aws")
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+        if command.is_empty() {
+            bail!("archive.aws_command is empty");
+        }
+        Ok(Self {
+            command,
+            endpoint_url: config.endpoint_url.clone(),
+        })
+    }
+
+    pub fn upload(&self, local: &Path, url: &str) -> Result<()> {
+        self.copy(&local.to_string_lossy(), url)
+            .with_context(|| format!("Failed to upload {} to {}", local.display(), url))
+    }
+
+    pub fn download(&self, url: &str, local: &Path) -> Result<()> {
+        self.copy(url, &local.to_string_lossy())
+            .with_context(|| format!("Failed to download {} to {}", url, local.display()))
+    }
+
+    fn copy(&self, from: &str, to: &str) -> Result<()> {
+        let mut cmd = Command::new(&self.command[0]);
+        cmd.args(&self.command[1..]);
+        if let Some(endpoint) = &self.endpoint_url {
+            cmd.arg("--endpoint-url").arg(endpoint);
+        }
+        let output = cmd
+            .args(["s3", "cp", "--only-show-errors", from, to])
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    anyhow::anyhow!("aws CLI not found; install it or set archive.aws_command")
+                } else {
+                    anyhow::Error::new(e).context("Failed to run aws")
+                }
+            })?;
+        if !output.status.success() {
+            bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_s3_location() {
+        let location = S3Location::parse("s3://cold/photos/archive/").unwrap();
+        assert_eq!(location.bucket, "cold");
+        assert_eq!(location.prefix, "photos/archive");
+        assert_eq!(location.object_url("trip/clip.mp4"), "s3://cold/photos/archive/trip/clip.mp4");
+
+        let bare = S3Location::parse("s3://cold").unwrap();
+        assert_eq!(bare.object_url("clip.mp4"), "s3://cold/clip.mp4");
+
+        assert!(S3Location::parse("s3:///prefix").is_err());
+        assert!(S3Location::parse("https://cold/photos").is_err());
+    }
+}
@@ -0,0 +1,106 @@
+//! Spotting screenshots among photos, for `picman sync --screenshots`.
+//!
+//! Nothing in a screenshot says it is one, so this goes on what's already
+//! in the database: the filename phones and desktops give them, and PNGs
+//! the exact size of a common screen with no camera in their EXIF.
+
+/// Tag added to likely screenshots. The `auto:` prefix marks tags picman
+/// adds on its own, so they're easy to tell from the user's.
+pub const SCREENSHOT_TAG: &str = "auto:screenshot";
+
+/// Words screenshot tools put in filenames (lowercased), in the languages
+/// macOS, Windows, iOS and Android name them in
+const SCREENSHOT_NAMES: &[&str] = &[
+    "screenshot",
+    "screen shot",
+    "screen_shot",
+    "screencap",
+    "bildschirmfoto",
+    "captura de pantalla",
+    "capture d'écran",
+    "capture d’écran",
+    "schermafbeelding",
+    "schermata",
+    "skärmbild",
+    "skjermbilde",
+];
+
+/// Screen sizes (landscape; portrait is checked too) of common monitors,
+/// laptops and phones
+const SCREEN_SIZES: &[(i32, i32)] = &[
+    // Monitors and laptops
+    (1280, 720),
+    (1280, 800),
+    (1366, 768),
+    (1440, 900),
+    (1536, 864),
+    (1600, 900),
+    (1680, 1050),
+    (1920, 1080),
+    (1920, 1200),
+    (2560, 1440),
+    (2560, 1600),
+    (2880, 1800),
+    (3024, 1964),
+    (3440, 1440),
+    (3456, 2234),
+    (3840, 2160),
+    (5120, 2880),
+    // Phones
+    (1334, 750),
+    (1792, 828),
+    (2208, 1242),
+    (2340, 1080),
+    (2400, 1080),
+    (2436, 1125),
+    (2532, 1170),
+    (2556, 1179),
+    (2688, 1242),
+    (2778, 1284),
+    (2796, 1290),
+    (3200, 1440),
+];
+
+/// Whether a file looks like a screenshot: a screenshot-style name, or a
+/// PNG exactly the size of a screen whose EXIF names no camera. `camera`
+/// must come from EXIF that has been read; photos edited and saved as PNG
+/// keep their camera and aren't flagged.
+pub fn is_likely_screenshot(filename: &str, width: Option<i32>, height: Option<i32>, camera: Option<&str>) -> bool {
+    let name = filename.to_lowercase();
+    if SCREENSHOT_NAMES.iter().any(|pattern| name.contains(pattern)) {
+        return true;
+    }
+
+    let is_png = name.ends_with(".png");
+    let screen_sized = match (width, height) {
+        (Some(w), Some(h)) => SCREEN_SIZES.iter().any(|&(a, b)| (w, h) == (a, b) || (w, h) == (b, a)),
+        _ => false,
+    };
+    is_png && screen_sized && camera.is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screenshot_names() {
+        assert!(is_likely_screenshot("Screenshot 2024-03-01 at 10.12.33.png", None, None, None));
+        assert!(is_likely_screenshot("Screen Shot 2019-05-02 at 9.41.10 AM.png", None, None, None));
+        assert!(is_likely_screenshot("Screenshot_20240301-101233_Chrome.jpg", None, None, Some("Pixel 7")));
+        assert!(is_likely_screenshot("Bildschirmfoto 2023-01-01 um 12.00.00.png", None, None, None));
+        assert!(!is_likely_screenshot("IMG_1234.jpg", Some(4032), Some(3024), Some("iPhone 13")));
+    }
+
+    #[test]
+    fn test_screen_sized_png_without_camera() {
+        assert!(is_likely_screenshot("IMG_0042.PNG", Some(1170), Some(2532), None));
+        assert!(is_likely_screenshot("export.png", Some(1920), Some(1080), None));
+        // Same size with a camera: an edited photo exported as PNG
+        assert!(!is_likely_screenshot("export.png", Some(1920), Some(1080), Some("Canon EOS R5")));
+        // A JPEG at screen size is usually a resized photo
+        assert!(!is_likely_screenshot("wallpaper.jpg", Some(1920), Some(1080), None));
+        assert!(!is_likely_screenshot("diagram.png", Some(1000), Some(700), None));
+        assert!(!is_likely_screenshot("unknown.png", None, None, None));
+    }
+}
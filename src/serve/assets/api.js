@@ -9,17 +9,24 @@ async function fetchJson(url) {
     return res.json();
 }
 
-async function apiRequest(url, method, body) {
-    const opts = { method, headers: {} };
+// Errors carry the HTTP status, so callers can tell a 409 (someone else
+// changed it first) from a failure.
+async function apiRequest(url, method, body, headers = {}) {
+    const opts = { method, headers: { ...headers } };
     if (body !== undefined) {
         opts.headers["Content-Type"] = "application/json";
         opts.body = JSON.stringify(body);
     }
     const res = await fetch(url, opts);
-    if (!res.ok) throw new Error(`HTTP ${res.status}`);
+    if (!res.ok) throw Object.assign(new Error(`HTTP ${res.status}`), { status: res.status });
     return res.json();
 }
 
+// Edits send the version they were based on; "*" when it isn't known
+function ifMatch(version) {
+    return { "If-Match": version === undefined ? "*" : `"${version}"` };
+}
+
 export async function loadDirectories() {
     state.directories = await fetchJson("/api/directories");
     state.dirMap.clear();
@@ -28,6 +35,15 @@ export async function loadDirectories() {
     }
 }
 
+export async function loadSettings() {
+    const settings = await fetchJson("/api/settings");
+    state.ratingScale = settings.rating_scale;
+}
+
+export async function loadVersion() {
+    return fetchJson("/api/version");
+}
+
 export async function loadTags() {
     state.tags = await fetchJson("/api/tags");
 }
@@ -44,6 +60,7 @@ export async function loadFiles(page = 1) {
             const params = new URLSearchParams();
             if (state.ratingFilter) params.set("rating", state.ratingFilter);
             if (state.tagFilter) params.set("tag", state.tagFilter);
+            if (state.typeFilter) params.set("media_type", state.typeFilter);
             params.set("page", page);
             params.set("per_page", state.perPage);
             data = await fetchJson(`/api/files?${params}`);
@@ -52,6 +69,7 @@ export async function loadFiles(page = 1) {
             params.set("page", page);
             params.set("per_page", state.perPage);
             if (!state.recursive) params.set("recursive", "false");
+            if (state.expandedStacks.size > 0) params.set("expand_stacks", [...state.expandedStacks].join(","));
             data = await fetchJson(`/api/directories/${state.selectedDirId}/files?${params}`);
         } else {
             return false;
@@ -74,32 +92,78 @@ export async function loadFiles(page = 1) {
     }
 }
 
-export async function setDirRating(dirId, value) {
-    return apiRequest(`/api/directories/${dirId}/rating`, "PUT", { rating: value });
+export async function setDirRating(dirId, value, version) {
+    return apiRequest(`/api/directories/${dirId}/rating`, "PUT", { rating: value }, ifMatch(version));
 }
 
-export async function addDirTag(dirId, name) {
-    return apiRequest(`/api/directories/${dirId}/tags`, "POST", { tag: name });
+export async function addDirTag(dirId, name, version) {
+    return apiRequest(`/api/directories/${dirId}/tags`, "POST", { tag: name }, ifMatch(version));
 }
 
-export async function removeDirTag(dirId, tagName) {
+export async function removeDirTag(dirId, tagName, version) {
     return apiRequest(
         `/api/directories/${dirId}/tags/${encodeURIComponent(tagName)}`,
-        "DELETE"
+        "DELETE",
+        undefined,
+        ifMatch(version)
     );
 }
 
+// Inline edit of one file: any of { rating, add_tags, remove_tags }, based
+// on the file's `version`. Resolves to its { id, rating, tags, version }
+// afterwards; fails with status 409 if it was changed elsewhere meanwhile.
+export async function updateFile(fileId, changes, version) {
+    return apiRequest(`/api/files/${fileId}`, "PATCH", changes, ifMatch(version));
+}
+
+// ==================== Jobs ====================
+
+// Resolves with the job's final status, calling onProgress with each one
+// streamed from the server on the way
+export function followJob(id, onProgress = () => {}) {
+    return new Promise((resolve, reject) => {
+        const events = new EventSource(`/api/jobs/${id}/events`);
+        events.onmessage = (e) => {
+            const job = JSON.parse(e.data);
+            onProgress(job);
+            if (job.done) {
+                events.close();
+                job.error ? reject(new Error(job.error)) : resolve(job);
+            }
+        };
+        events.onerror = () => {
+            events.close();
+            reject(new Error('Lost track of the background job'));
+        };
+    });
+}
+
+// Like fetchJson, but a 202 names a background job computing the answer:
+// follow it, then ask again
+async function fetchAfterJob(url, onProgress) {
+    for (;;) {
+        const res = await fetch(url);
+        if (!res.ok) throw new Error(`HTTP ${res.status}`);
+        if (res.status !== 202) return res.json();
+        await followJob((await res.json()).id, onProgress);
+    }
+}
+
 // ==================== Duplicates ====================
 
-export async function fetchDuplicatesSummary(threshold = 8) {
-    return fetchJson(`/api/duplicates/summary?threshold=${threshold}`);
+export async function fetchDuplicatesSummary(threshold = 8, onProgress) {
+    return fetchAfterJob(`/api/duplicates/summary?threshold=${threshold}`, onProgress);
 }
 
-export async function fetchDuplicates(type = 'exact', threshold = 8, page = 1, perPage = 50) {
+export async function fetchDuplicates(type = 'exact', threshold = 8, page = 1, perPage = 50, onProgress) {
     const params = new URLSearchParams({
         type, threshold, page, per_page: perPage,
     });
-    return fetchJson(`/api/duplicates?${params}`);
+    return fetchAfterJob(`/api/duplicates?${params}`, onProgress);
+}
+
+export async function ignoreDuplicates(fileIds) {
+    return apiRequest('/api/duplicates/ignore', 'POST', { file_ids: fileIds });
 }
 
 export async function trashFiles(fileIds) {
@@ -1,6 +1,6 @@
 // Entry point — wires modules together and initializes the app.
 
-import { loadDirectories, loadTags } from './api.js';
+import { loadDirectories, loadSettings, loadTags, loadVersion } from './api.js';
 import { renderDirectoryTree, selectDirectory } from './tree.js';
 import { renderTagChips } from './tags.js';
 import { setupFilterListeners, applyFilters } from './filters.js';
@@ -26,10 +26,12 @@ function navigateTo(route) {
     }
 
     // Restore filter state before navigating
-    if (route.rating || route.tag) {
+    if (route.rating || route.tag || route.media) {
         state.ratingFilter = route.rating;
         state.tagFilter = route.tag;
+        state.typeFilter = route.media;
         document.getElementById('rating-filter').value = route.rating;
+        document.getElementById('type-filter').value = route.media;
         applyFilters({ updateUrl: false });
         renderTagChips();
     } else {
@@ -41,9 +43,24 @@ function navigateTo(route) {
     }
 }
 
+// The server fills in its asset hash when serving index.html. A page loaded
+// from an older build (cached tab, server upgraded since) no longer matches
+// /api/version, so ask for a reload rather than talk to a changed API.
+async function checkVersion() {
+    const loaded = document.querySelector('meta[name="picman-asset-hash"]')?.content;
+    try {
+        const server = await loadVersion();
+        if (loaded && server.asset_hash !== loaded) {
+            document.getElementById('update-banner').classList.remove('hidden');
+        }
+    } catch (err) {
+        console.warn("Version check failed:", err);
+    }
+}
+
 async function init() {
     try {
-        await Promise.all([loadDirectories(), loadTags()]);
+        await Promise.all([loadDirectories(), loadTags(), loadSettings()]);
 
         renderDirectoryTree();
         renderTagChips();
@@ -55,6 +72,11 @@ async function init() {
 
         const route = initRouter(navigateTo);
         navigateTo(route);
+
+        checkVersion();
+        document.addEventListener('visibilitychange', () => {
+            if (document.visibilityState === 'visible') checkVersion();
+        });
     } catch (err) {
         console.error("Failed to initialize:", err);
         document.getElementById("grid").innerHTML =
@@ -2,7 +2,8 @@
 
 import { state } from './state.js';
 import { pushUrl, replaceUrl } from './router.js';
-import { fetchDuplicatesSummary, fetchDuplicates, trashFiles, trashFolderRule } from './api.js';
+import { fetchDuplicatesSummary, fetchDuplicates, ignoreDuplicates, trashFiles, trashFolderRule } from './api.js';
+import { ratingText } from './tags.js';
 
 // ==================== Initialization ====================
 
@@ -11,8 +12,12 @@ export async function initDuplicates() {
     document.getElementById('dupes-back-btn').addEventListener('click', hideDuplicatesView);
     document.getElementById('dupes-type-exact').addEventListener('click', () => setType('exact'));
     document.getElementById('dupes-type-similar').addEventListener('click', () => setType('similar'));
+    const slider = document.getElementById('dupes-threshold-slider');
+    slider.addEventListener('input', () => previewThreshold(parseInt(slider.value)));
+    slider.addEventListener('change', () => setThreshold(parseInt(slider.value)));
     document.getElementById('dupes-prev').addEventListener('click', prevGroup);
     document.getElementById('dupes-skip').addEventListener('click', skipGroup);
+    document.getElementById('dupes-ignore').addEventListener('click', ignoreGroup);
     document.getElementById('dupes-confirm').addEventListener('click', confirmGroup);
     document.addEventListener('keydown', handleKeyboard);
 
@@ -50,6 +55,7 @@ export async function showDuplicatesView(type, { updateUrl = true } = {}) {
         document.getElementById('dupes-type-exact').classList.toggle('active', type === 'exact');
         document.getElementById('dupes-type-similar').classList.toggle('active', type === 'similar');
     }
+    document.getElementById('dupes-threshold').classList.toggle('hidden', state.dupesType !== 'similar');
 
     document.getElementById('library-view').classList.add('hidden');
     document.getElementById('duplicates-view').classList.remove('hidden');
@@ -76,11 +82,29 @@ function setType(type) {
 
     document.getElementById('dupes-type-exact').classList.toggle('active', type === 'exact');
     document.getElementById('dupes-type-similar').classList.toggle('active', type === 'similar');
+    document.getElementById('dupes-threshold').classList.toggle('hidden', type !== 'similar');
 
     replaceUrl();
     loadGroups();
 }
 
+// While dragging: tighten the loaded groups locally from their pairwise
+// distances. Looser than what was fetched needs the server.
+function previewThreshold(threshold) {
+    document.getElementById('dupes-threshold-value').textContent = threshold;
+    if (threshold <= state.dupesLoadedThreshold) {
+        state.dupesThreshold = threshold;
+        regroupLocally();
+    }
+}
+
+function setThreshold(threshold) {
+    state.dupesThreshold = threshold;
+    if (threshold > state.dupesLoadedThreshold) {
+        loadGroups();
+    }
+}
+
 // ==================== Data Loading ====================
 
 async function loadGroups() {
@@ -89,20 +113,15 @@ async function loadGroups() {
 
     try {
         const data = await fetchDuplicates(
-            state.dupesType, 8, state.dupesPage, state.dupesPerPage
+            state.dupesType, state.dupesThreshold, state.dupesPage, state.dupesPerPage, renderJobProgress
         );
         state.dupesGroups = data.groups;
+        state.dupesLoadedGroups = data.groups;
+        state.dupesLoadedThreshold = state.dupesThreshold;
         state.dupesTotalGroups = data.total_groups;
         state.dupesFolderSuperGroups = data.folder_super_groups;
 
-        // Pre-populate auto-suggestions for all groups
-        for (const group of state.dupesGroups) {
-            if (!state.dupesDecisions.has(group.group_index)) {
-                applyAutoSuggestion(group);
-            }
-        }
-
-        renderCurrentGroup();
+        prepareGroups();
     } catch (err) {
         document.getElementById('dupes-cards').innerHTML =
             `<div class="empty-state">Failed to load duplicates: ${err.message}</div>`;
@@ -111,6 +130,57 @@ async function loadGroups() {
     }
 }
 
+// Pre-populate auto-suggestions for all groups and show the current one
+function prepareGroups() {
+    for (const group of state.dupesGroups) {
+        if (!state.dupesDecisions.has(group.group_index)) {
+            applyAutoSuggestion(group);
+        }
+    }
+    renderCurrentGroup();
+}
+
+function regroupLocally() {
+    state.dupesGroups = state.dupesLoadedGroups.flatMap(g => splitGroup(g, state.dupesThreshold));
+    state.dupesCurrentGroupIndex = Math.min(state.dupesCurrentGroupIndex, Math.max(0, state.dupesGroups.length - 1));
+    state.dupesFocusedFileIndex = 0;
+    prepareGroups();
+}
+
+// Split a similar group into the parts still connected at `threshold`.
+// Parts get their own group_index, so decisions made on the whole group
+// don't carry over to them.
+function splitGroup(group, threshold) {
+    const parent = new Map(group.files.map(f => [f.id, f.id]));
+    const find = id => parent.get(id) === id ? id : find(parent.get(id));
+    for (const { a, b, distance } of group.distances || []) {
+        if (distance <= threshold) parent.set(find(a), find(b));
+    }
+
+    const parts = new Map();
+    for (const file of group.files) {
+        const root = find(file.id);
+        if (!parts.has(root)) parts.set(root, []);
+        parts.get(root).push(file);
+    }
+    const kept = [...parts.values()].filter(files => files.length >= 2);
+    if (kept.length === 1 && kept[0].length === group.files.length) return [group];
+
+    return kept.map((files, i) => {
+        const ids = new Set(files.map(f => f.id));
+        const distances = group.distances.filter(d => ids.has(d.a) && ids.has(d.b));
+        return {
+            ...group,
+            group_index: `${group.group_index}.${i}`,
+            files,
+            distances,
+            max_distance: Math.max(...distances.map(d => d.distance)),
+            // The server's pick may have ended up in another part
+            suggested_keep_id: ids.has(group.suggested_keep_id) ? group.suggested_keep_id : files[0].id,
+        };
+    });
+}
+
 // ==================== Auto-Suggestion ====================
 
 function applyAutoSuggestion(group) {
@@ -130,6 +200,15 @@ function renderLoading() {
     document.getElementById('dupes-folder-rule').classList.add('hidden');
 }
 
+// Large libraries group similar images in a background job
+function renderJobProgress(job) {
+    const loading = document.querySelector('#dupes-cards .loading');
+    if (!loading || !job.total) return;
+    const percent = Math.floor(job.completed * 100 / job.total);
+    loading.textContent =
+        `Comparing images: ${job.completed.toLocaleString()} of ${job.total.toLocaleString()} (${percent}%)`;
+}
+
 function renderCurrentGroup() {
     const group = getCurrentGroup();
 
@@ -201,7 +280,8 @@ function renderCards(group) {
             decision === 'trash' ? 'label-trash' : '';
         const suggestedHint = isSuggested && decision === 'keep' ? ' (suggested)' : '';
 
-        const stars = file.rating ? '★'.repeat(file.rating) + '☆'.repeat(5 - file.rating) : '';
+        const stars = !file.rating ? '' :
+            state.ratingScale === 5 ? ratingText(file.rating) + '☆'.repeat(5 - Math.ceil(file.rating)) : ratingText(file.rating);
         const dims = file.width && file.height ? `${file.width}×${file.height}` : '';
         const size = formatSize(file.size);
         const tags = file.tags.length ? file.tags.map(t => `<span class="dupe-tag">#${t}</span>`).join(' ') : '';
@@ -428,6 +508,21 @@ function skipGroup() {
     advanceGroup();
 }
 
+// Mark the group as a false positive: nothing is trashed and its files are
+// no longer reported as duplicates of each other
+async function ignoreGroup() {
+    const group = getCurrentGroup();
+    if (!group) return;
+
+    try {
+        await ignoreDuplicates(group.files.map(f => f.id));
+        state.dupesResolvedCount++;
+        removeCurrentGroup();
+    } catch (err) {
+        console.error('Failed to mark group as not duplicates:', err);
+    }
+}
+
 function advanceGroup() {
     if (state.dupesCurrentGroupIndex < state.dupesGroups.length - 1) {
         state.dupesCurrentGroupIndex++;
@@ -439,7 +534,14 @@ function advanceGroup() {
 }
 
 function removeCurrentGroup() {
-    state.dupesGroups.splice(state.dupesCurrentGroupIndex, 1);
+    const [removed] = state.dupesGroups.splice(state.dupesCurrentGroupIndex, 1);
+    if (removed) {
+        // Resolved files shouldn't come back when the threshold slider regroups
+        const resolved = new Set(removed.files.map(f => f.id));
+        state.dupesLoadedGroups = state.dupesLoadedGroups
+            .map(g => ({ ...g, files: g.files.filter(f => !resolved.has(f.id)) }))
+            .filter(g => g.files.length >= 2);
+    }
     if (state.dupesCurrentGroupIndex >= state.dupesGroups.length) {
         state.dupesCurrentGroupIndex = Math.max(0, state.dupesGroups.length - 1);
     }
@@ -464,6 +566,7 @@ async function confirmSuperGroup() {
             state.dupesType,
             rule.keepFolder,
             rule.trashFolder,
+            state.dupesThreshold,
         );
 
         if (result.errors.length > 0) {
@@ -600,6 +703,11 @@ function handleKeyboard(e) {
             skipGroup();
             break;
 
+        case 'i':
+            e.preventDefault();
+            ignoreGroup();
+            break;
+
         case 'f': {
             e.preventDefault();
             if (!group) break;
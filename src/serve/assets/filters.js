@@ -8,11 +8,24 @@ import { renderGrid, renderFileCount } from './grid.js';
 import { renderDirRating, renderDirTags, renderTagChips } from './tags.js';
 
 export function setupFilterListeners() {
-    document.getElementById("rating-filter").addEventListener("change", (e) => {
+    const ratingSelect = document.getElementById("rating-filter");
+    for (let i = 1; i <= state.ratingScale; i++) {
+        const option = document.createElement("option");
+        option.value = String(i);
+        option.textContent = i === state.ratingScale ? `★ ${i}` : `★ ${i}+`;
+        ratingSelect.appendChild(option);
+    }
+
+    ratingSelect.addEventListener("change", (e) => {
         state.ratingFilter = e.target.value;
         applyFilters();
     });
 
+    document.getElementById("type-filter").addEventListener("change", (e) => {
+        state.typeFilter = e.target.value;
+        applyFilters();
+    });
+
     // Tag chips dispatch this event when clicked
     document.addEventListener("filters-changed", () => applyFilters());
 }
@@ -21,7 +34,7 @@ export async function applyFilters({ updateUrl = true } = {}) {
     state.currentPage = 1;
     state.currentFiles = [];
 
-    if (state.ratingFilter || state.tagFilter) {
+    if (state.ratingFilter || state.tagFilter || state.typeFilter) {
         state.useFilteredEndpoint = true;
         state.selectedDirId = null;
         renderDirectoryTree();
@@ -1,7 +1,8 @@
 // Photo grid rendering, infinite scroll, and zoom controls.
 
 import { state } from './state.js';
-import { loadFiles } from './api.js';
+import { loadFiles, updateFile } from './api.js';
+import { ratingText } from './tags.js';
 
 // Track estimated column heights for shortest-column-first placement.
 let columnHeights = [];
@@ -39,6 +40,7 @@ export function renderGrid(replace = true) {
         }
     }
 
+    const tileWidth = gridTileWidth(container, columnCount);
     for (let i = existingCount; i < state.currentFiles.length; i++) {
         let minCol = 0;
         for (let c = 1; c < columnCount; c++) {
@@ -49,11 +51,19 @@ export function renderGrid(replace = true) {
         const ratio = (file.width && file.height) ? file.width / file.height : 3 / 2;
         columnHeights[minCol] += 1 / ratio;
 
-        grid.children[minCol].appendChild(createPhotoCell(file, i));
+        grid.children[minCol].appendChild(createPhotoCell(file, i, tileWidth));
     }
 }
 
-function createPhotoCell(file, index) {
+// Pixels a grid tile needs (the server rounds it up to one of a few sizes).
+// Null when the 400px thumbnail is enough.
+function gridTileWidth(container, columnCount) {
+    const px = container.clientWidth / columnCount * (window.devicePixelRatio || 1);
+    if (px <= 400) return null;
+    return Math.min(Math.ceil(px), 4096);
+}
+
+function createPhotoCell(file, index, tileWidth) {
     const cell = document.createElement("div");
     cell.className = "photo-cell";
 
@@ -66,7 +76,7 @@ function createPhotoCell(file, index) {
     } else {
         img.style.aspectRatio = '3 / 2';
     }
-    img.src = `/thumb/${file.id}`;
+    img.src = tileWidth ? `/img/${file.id}?w=${tileWidth}` : `/thumb/${file.id}`;
     img.alt = file.filename;
     img.onerror = () => {
         img.style.display = "none";
@@ -83,15 +93,14 @@ function createPhotoCell(file, index) {
     const fname = document.createElement("span");
     fname.className = "filename";
     fname.textContent = file.filename;
+    if (file.width && file.height) fname.title = `${file.filename} · ${file.width}×${file.height}`;
 
-    overlay.appendChild(fname);
-
-    if (file.rating) {
-        const rating = document.createElement("span");
-        rating.className = "rating";
-        rating.textContent = "★".repeat(file.rating);
-        overlay.appendChild(rating);
-    }
+    const row = document.createElement("div");
+    row.className = "overlay-row";
+    row.appendChild(fname);
+    row.appendChild(createRatingStars(file, cell));
+    overlay.appendChild(row);
+    overlay.appendChild(createTagChips(file, cell));
 
     cell.appendChild(img);
     cell.appendChild(overlay);
@@ -99,7 +108,32 @@ function createPhotoCell(file, index) {
     if (file.media_type === "video") {
         const badge = document.createElement("span");
         badge.className = "video-badge";
-        badge.innerHTML = '<span class="icon" style="font-size:0.7rem">play_arrow</span> Video';
+        const label = file.duration ? formatDuration(file.duration) : "Video";
+        badge.innerHTML = `<span class="icon" style="font-size:0.7rem">play_arrow</span> ${label}`;
+        cell.appendChild(badge);
+    } else if (file.media_type === "document") {
+        const badge = document.createElement("span");
+        badge.className = "doc-badge";
+        const ext = file.filename.split(".").pop().toUpperCase();
+        badge.innerHTML = `<span class="icon" style="font-size:0.7rem">description</span> ${ext}`;
+        cell.appendChild(badge);
+    }
+
+    if (file.stack_size > 1) {
+        cell.appendChild(createStackBadge(file.stack_id, `${file.stack_size}`, "Expand stack"));
+    } else if (file.stack_id !== null && state.expandedStacks.has(file.stack_id)) {
+        cell.classList.add("stack-member");
+        const prev = state.currentFiles[index - 1];
+        if (!prev || prev.stack_id !== file.stack_id) {
+            cell.appendChild(createStackBadge(file.stack_id, "collapse", "Collapse stack"));
+        }
+    }
+
+    if (file.companions && file.companions.length > 0) {
+        const badge = document.createElement("span");
+        badge.className = "pair-badge";
+        badge.textContent = file.companions.map((ext) => `+${ext}`).join(" ");
+        badge.title = "Paired files share this entry's rating and tags";
         cell.appendChild(badge);
     }
 
@@ -112,6 +146,147 @@ function createPhotoCell(file, index) {
     return cell;
 }
 
+// 0:42, 12:05 or 1:02:09
+function formatDuration(seconds) {
+    const total = Math.round(seconds);
+    const h = Math.floor(total / 3600);
+    const m = Math.floor((total % 3600) / 60);
+    const s = String(total % 60).padStart(2, "0");
+    return h > 0 ? `${h}:${String(m).padStart(2, "0")}:${s}` : `${m}:${s}`;
+}
+
+// Clickable stars in the overlay; clicking the current rating clears it.
+// Unrated files only show them on hover.
+function createRatingStars(file, cell) {
+    const stars = document.createElement("span");
+    stars.className = "rating" + (file.rating ? "" : " unrated");
+    if (state.ratingScale !== 5) {
+        // Ten stars don't fit on a thumbnail; show the number and step by one
+        stars.textContent = file.rating ? ratingText(file.rating) : "★";
+        stars.title = "Click to raise the rating, shift-click to lower it";
+        stars.addEventListener("click", (e) => {
+            e.stopPropagation();
+            const current = file.rating || 0;
+            const next = e.shiftKey ? current - 1 : current + 1;
+            editFile(file, cell, { rating: next < 1 || next > state.ratingScale ? null : next });
+        });
+        return stars;
+    }
+    for (let i = 1; i <= state.ratingScale; i++) {
+        const star = document.createElement("span");
+        star.className = "star" + (i <= (file.rating || 0) ? " filled" : "");
+        star.textContent = "★";
+        star.addEventListener("click", (e) => {
+            e.stopPropagation();
+            editFile(file, cell, { rating: i === file.rating ? null : i });
+        });
+        stars.appendChild(star);
+    }
+    return stars;
+}
+
+function createTagChips(file, cell) {
+    const chips = document.createElement("div");
+    chips.className = "file-tags";
+    for (const tag of file.tags) {
+        const chip = document.createElement("span");
+        chip.className = "file-tag";
+        chip.textContent = "#" + tag;
+        chip.title = "Click to remove";
+        chip.addEventListener("click", (e) => {
+            e.stopPropagation();
+            editFile(file, cell, { remove_tags: [tag] });
+        });
+        chips.appendChild(chip);
+    }
+
+    const add = document.createElement("span");
+    add.className = "file-tag add icon";
+    add.textContent = "add";
+    add.title = "Add a tag";
+    add.addEventListener("click", (e) => {
+        e.stopPropagation();
+        const input = document.createElement("input");
+        input.className = "file-tag-input";
+        input.placeholder = "tag…";
+        input.addEventListener("click", (ev) => ev.stopPropagation());
+        input.addEventListener("keydown", (ev) => {
+            ev.stopPropagation();
+            if (ev.key === "Enter" && input.value.trim()) {
+                editFile(file, cell, { add_tags: [input.value.trim()] });
+            } else if (ev.key === "Escape") {
+                input.replaceWith(add);
+            }
+        });
+        input.addEventListener("blur", () => input.replaceWith(add));
+        add.replaceWith(input);
+        input.focus();
+    });
+    chips.appendChild(add);
+    return chips;
+}
+
+// Save an inline edit and redraw just this cell's overlay
+async function editFile(file, cell, changes) {
+    try {
+        const meta = await updateFile(file.id, changes, file.version);
+        file.rating = meta.rating;
+        file.tags = meta.tags;
+        file.version = meta.version;
+        const overlay = cell.querySelector(".overlay");
+        const fname = overlay.querySelector(".filename");
+        overlay.innerHTML = "";
+        const row = document.createElement("div");
+        row.className = "overlay-row";
+        row.appendChild(fname);
+        row.appendChild(createRatingStars(file, cell));
+        overlay.appendChild(row);
+        overlay.appendChild(createTagChips(file, cell));
+    } catch (err) {
+        if (err.status === 409) {
+            // Changed elsewhere since it was loaded: show what it is now
+            // rather than overwrite it
+            await reloadGrid();
+            return;
+        }
+        console.error("Failed to update file:", err);
+    }
+}
+
+// Reload the listing in place, keeping the scroll position
+async function reloadGrid() {
+    const container = document.getElementById("grid");
+    const scrollTop = container.scrollTop;
+    if (await loadFiles(1)) {
+        renderGrid(true);
+        renderFileCount();
+        container.scrollTop = scrollTop;
+    }
+}
+
+function createStackBadge(stackId, label, title) {
+    const badge = document.createElement("span");
+    badge.className = "stack-badge";
+    badge.innerHTML = `<span class="icon" style="font-size:0.7rem">stacks</span> ${label}`;
+    badge.title = title;
+    badge.addEventListener("click", (e) => {
+        e.stopPropagation();
+        toggleStack(stackId);
+    });
+    return badge;
+}
+
+// Expanding or collapsing a stack changes which files the API returns, so reload
+// from the first page and keep the scroll position.
+async function toggleStack(stackId) {
+    if (state.expandedStacks.has(stackId)) {
+        state.expandedStacks.delete(stackId);
+    } else {
+        state.expandedStacks.add(stackId);
+    }
+    await reloadGrid();
+}
+
 export function renderFileCount() {
     const el = document.getElementById("file-count");
     if (state.totalFiles > 0) {
@@ -2,6 +2,7 @@
 
 import { state } from './state.js';
 import { selectDirectory } from './tree.js';
+import { ratingText } from './tags.js';
 
 function openLightbox(index) {
     state.lightboxIndex = index;
@@ -28,7 +29,7 @@ function renderLightbox() {
     img.src = `/preview/${file.id}`;
 
     lb.querySelector(".filename").textContent = file.filename;
-    lb.querySelector(".rating").textContent = file.rating ? "★".repeat(file.rating) : "";
+    lb.querySelector(".rating").textContent = ratingText(file.rating);
     lb.querySelector(".tags").textContent = file.tags.length
         ? file.tags.map(t => "#" + t).join(" ")
         : "";
@@ -19,6 +19,7 @@ function stateToParams() {
     }
     if (state.ratingFilter) params.set('rating', state.ratingFilter);
     if (state.tagFilter) params.set('tag', state.tagFilter);
+    if (state.typeFilter) params.set('media', state.typeFilter);
 
     return params;
 }
@@ -57,6 +58,7 @@ export function readUrl() {
         recursive: params.get('recursive') !== 'false',
         rating: params.get('rating') || '',
         tag: params.get('tag') || '',
+        media: params.get('media') || '',
     };
 }
 
@@ -6,14 +6,17 @@ export const state = {
     tags: [],
     selectedDirId: null,
     expandedDirs: new Set(),
+    expandedStacks: new Set(),
     currentFiles: [],
     totalFiles: 0,
     currentPage: 1,
     perPage: 500,
     loading: false,
     loadGeneration: 0,
+    ratingScale: 5,                 // 5 or 10, from /api/settings
     ratingFilter: "",
     tagFilter: "",
+    typeFilter: "",
     lightboxIndex: -1,
     recursive: true,
     useFilteredEndpoint: false,
@@ -24,6 +27,9 @@ export const state = {
     view: 'library',                // 'library' | 'duplicates'
     dupesType: 'exact',             // 'exact' | 'similar'
     dupesGroups: [],
+    dupesThreshold: 8,              // similar: max perceptual hash distance
+    dupesLoadedGroups: [],          // similar groups as fetched, before splitting locally
+    dupesLoadedThreshold: 8,        // threshold dupesLoadedGroups were fetched at
     dupesTotalGroups: 0,
     dupesPage: 1,
     dupesPerPage: 50,
@@ -1,10 +1,18 @@
 // Directory rating stars, directory tag editing, and sidebar tag chips.
 
 import { state } from './state.js';
-import { setDirRating as apiSetDirRating, addDirTag as apiAddDirTag, removeDirTag as apiRemoveDirTag, loadTags } from './api.js';
+import { setDirRating as apiSetDirRating, addDirTag as apiAddDirTag, removeDirTag as apiRemoveDirTag, loadTags, loadDirectories } from './api.js';
 
 // ==================== Directory Rating ====================
 
+// A rating as shown on thumbnails and in the lightbox: stars (with "½" for
+// a half step) on the 5-point scale, a number out of 10 on the 10-point one
+export function ratingText(rating) {
+    if (!rating) return "";
+    if (state.ratingScale !== 5) return `★ ${rating}/${state.ratingScale}`;
+    return "★".repeat(Math.floor(rating)) + (rating % 1 ? "½" : "");
+}
+
 export function renderDirRating() {
     const container = document.getElementById("dir-rating");
     container.innerHTML = "";
@@ -14,9 +22,9 @@ export function renderDirRating() {
 
     const currentRating = dir.rating || 0;
 
-    for (let i = 1; i <= 5; i++) {
+    for (let i = 1; i <= state.ratingScale; i++) {
         const star = document.createElement("span");
-        star.className = "star" + (i <= currentRating ? " filled" : "");
+        star.className = "star" + (i <= currentRating ? " filled" : i - 0.5 === currentRating ? " half" : "");
         star.textContent = "★";
 
         star.addEventListener("mouseenter", () => {
@@ -29,9 +37,11 @@ export function renderDirRating() {
             container.querySelectorAll(".star").forEach(s => s.classList.remove("preview"));
         });
 
-        star.addEventListener("click", () => {
-            const newRating = (i === currentRating) ? null : i;
-            setDirRating(newRating);
+        // The left half of a star gives half a star less (never below 1)
+        star.addEventListener("click", (e) => {
+            const leftHalf = e.offsetX < star.offsetWidth / 2;
+            const value = leftHalf && i > 1 ? i - 0.5 : i;
+            setDirRating(value === currentRating ? null : value);
         });
 
         container.appendChild(star);
@@ -43,11 +53,16 @@ async function setDirRating(value) {
     if (!dir) return;
 
     try {
-        const meta = await apiSetDirRating(dir.id, value);
+        const meta = await apiSetDirRating(dir.id, value, dir.version);
         dir.rating = meta.rating;
         dir.tags = meta.tags;
+        dir.version = meta.version;
         renderDirRating();
     } catch (err) {
+        if (err.status === 409) {
+            await reloadDirMeta();
+            return;
+        }
         console.error("Failed to set rating:", err);
     }
 }
@@ -201,12 +216,17 @@ async function addDirTag(name) {
     if (!tag) return;
 
     try {
-        const meta = await apiAddDirTag(dir.id, tag);
+        const meta = await apiAddDirTag(dir.id, tag, dir.version);
         dir.rating = meta.rating;
         dir.tags = meta.tags;
+        dir.version = meta.version;
         renderDirTags();
         loadTags().then(() => renderTagChips());
     } catch (err) {
+        if (err.status === 409) {
+            await reloadDirMeta();
+            return;
+        }
         console.error("Failed to add tag:", err);
     }
 }
@@ -216,15 +236,28 @@ async function removeDirTag(name) {
     if (!dir) return;
 
     try {
-        const meta = await apiRemoveDirTag(dir.id, name);
+        const meta = await apiRemoveDirTag(dir.id, name, dir.version);
         dir.rating = meta.rating;
         dir.tags = meta.tags;
+        dir.version = meta.version;
         renderDirTags();
     } catch (err) {
+        if (err.status === 409) {
+            await reloadDirMeta();
+            return;
+        }
         console.error("Failed to remove tag:", err);
     }
 }
 
+// Someone else changed the directory since it was loaded: show what it is
+// now instead of overwriting it
+async function reloadDirMeta() {
+    await loadDirectories();
+    renderDirRating();
+    renderDirTags();
+}
+
 // ==================== Sidebar Tag Chips ====================
 
 export function renderTagChips() {
@@ -130,7 +130,10 @@ export async function selectDirectory(dirId, { recursive = true, updateUrl = tru
     // Clear filters on directory navigation to avoid stale params in URL
     state.ratingFilter = "";
     state.tagFilter = "";
+    state.typeFilter = "";
+    state.expandedStacks.clear();
     document.getElementById("rating-filter").value = "";
+    document.getElementById("type-filter").value = "";
 
     if (updateUrl) pushUrl();
 
@@ -179,6 +182,7 @@ export function renderBreadcrumb() {
         const parts = [];
         if (state.ratingFilter) parts.push("★" + state.ratingFilter + "+");
         if (state.tagFilter) parts.push("#" + state.tagFilter);
+        if (state.typeFilter) parts.push(state.typeFilter + "s");
         label.textContent = "Filtered: " + (parts.join(", ") || "All files");
         container.appendChild(label);
         return;
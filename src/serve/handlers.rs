@@ -1,36 +1,139 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 use axum::body::Body;
 use axum::extract::{Path, Query, State};
-use axum::http::{header, StatusCode};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event, Sse};
 use axum::response::{IntoResponse, Json, Response};
-
-use crate::db::Database;
+use chrono::Datelike;
+use rusqlite::OptionalExtension;
+use utoipa::IntoParams;
+
+use crate::cli::{self, matching_files, reclaimable_by_directory, reclaimable_bytes, ListOptions};
+use crate::config::Config;
+use crate::db::{
+    parse_shutter, Activity, Database, DuplicateGroup, DuplicateScope, ExposureFilter, ReviewDecision, VersionConflict,
+};
+use crate::keep_policy::{choose_keep, KeepCandidate, KeepPolicy};
 use crate::perceptual_hash;
+use crate::print_size::{self, PrintFilter, PrintSize, DEFAULT_PRINT_DPI};
+use crate::rating::RatingScale;
+use crate::scanner::find_xmp_sidecar;
 use crate::thumbnails;
+use crate::tui::dialogs::{Orientation, RatingFilter};
+use crate::workers;
+use crate::library_path::LibraryPath;
 
+use super::jobs;
+use super::metrics::{self, LibraryStats};
 use super::models::*;
-use super::{AppState, Assets};
+use super::safe_path::{self, PathError};
+use super::{AppState, Assets, SimilarityCache, SuperGroupCache};
 
 // ==================== Health ====================
 
-pub async fn health() -> Json<HealthResponse> {
+#[utoipa::path(
+    get, path = "/metrics", tag = "health",
+    responses((status = 200, description = "Prometheus text exposition format", content_type = "text/plain", body = String))
+)]
+pub async fn get_metrics(State(state): State<Arc<AppState>>) -> Result<Response, AppError> {
+    let library = spawn_db(state.db.clone(), LibraryStats::query).await?;
+    let daemon = state.daemon.as_ref().map(|d| d.snapshot());
+    let body = metrics::global().render(&library, daemon.as_ref());
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        body,
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get, path = "/api/health", tag = "health",
+    responses((status = 200, body = HealthResponse))
+)]
+pub async fn health(State(state): State<Arc<AppState>>) -> Json<HealthResponse> {
+    let daemon = state.daemon.as_ref().map(|d| d.snapshot());
+    let status = match &daemon {
+        Some(snapshot) if snapshot.has_errors() => "degraded",
+        _ => "ok",
+    };
     Json(HealthResponse {
-        status: "ok".to_string(),
+        status: status.to_string(),
+        daemon,
+    })
+}
+
+// ==================== Settings ====================
+
+/// The library's rating scale, read per request so `picman rating-scale`
+/// takes effect without restarting the server
+fn rating_scale(state: &AppState) -> Result<RatingScale, AppError> {
+    Config::load(&state.library_path)
+        .map(|config| config.ratings.scale)
+        .map_err(|e| AppError::Internal(e.to_string()))
+}
+
+#[utoipa::path(
+    get, path = "/api/settings", tag = "health",
+    responses((status = 200, body = SettingsResponse))
+)]
+pub async fn get_settings(State(state): State<Arc<AppState>>) -> Result<Json<SettingsResponse>, AppError> {
+    Ok(Json(SettingsResponse {
+        rating_scale: rating_scale(&state)?.max(),
+    }))
+}
+
+/// Fingerprint of `src/serve/assets/`, computed by build.rs
+pub const ASSET_HASH: &str = env!("PICMAN_ASSET_HASH");
+
+#[utoipa::path(
+    get, path = "/api/version", tag = "health",
+    responses((status = 200, body = VersionResponse))
+)]
+pub async fn get_version() -> Json<VersionResponse> {
+    Json(VersionResponse {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        asset_hash: ASSET_HASH.to_string(),
     })
 }
 
 // ==================== Directories ====================
 
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DirectoryParams {
+    /// Only directories pinned in the TUI (`true`) or only unpinned ones (`false`)
+    pub pinned: Option<bool>,
+    /// Include hidden directories and everything below them (default false)
+    pub include_hidden: Option<bool>,
+}
+
+#[utoipa::path(
+    get, path = "/api/directories", tag = "directories",
+    params(DirectoryParams),
+    responses((status = 200, description = "All directories with tags and file counts", body = Vec<DirectoryResponse>))
+)]
 pub async fn get_directories(
     State(state): State<Arc<AppState>>,
+    Query(params): Query<DirectoryParams>,
 ) -> Result<Json<Vec<DirectoryResponse>>, AppError> {
     let db = state.db.clone();
-    let dirs = spawn_db(db.clone(), |db| {
-        let dirs = db.get_all_directories()?;
+    let dirs = spawn_db(db.clone(), move |db| {
+        let mut dirs = db.get_all_directories()?;
+        let pinned: HashSet<i64> = db.get_pinned_directory_ids()?.into_iter().collect();
+        if let Some(want_pinned) = params.pinned {
+            dirs.retain(|d| pinned.contains(&d.id) == want_pinned);
+        }
+        let hidden = db.get_hidden_directory_ids()?;
+        if !params.include_hidden.unwrap_or(false) {
+            let hidden_subtrees = db.get_hidden_subtree_ids()?;
+            dirs.retain(|d| !hidden_subtrees.contains(&d.id));
+        }
         let dir_tags = db.get_all_directory_tags()?;
+        let versions = db.get_directory_versions()?;
         let files = db.get_all_files()?;
 
         // Count files per directory
@@ -54,6 +157,9 @@ pub async fn get_directories(
                     rating: d.rating,
                     tags,
                     file_count,
+                    pinned: pinned.contains(&d.id),
+                    hidden: hidden.contains(&d.id),
+                    version: versions.get(&d.id).copied().unwrap_or_default(),
                 }
             })
             .collect();
@@ -67,19 +173,42 @@ pub async fn get_directories(
 
 // ==================== Directory Files ====================
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PaginationParams {
+    /// 1-based page number (default 1)
     pub page: Option<usize>,
+    /// Page size (default 100, max 500)
     pub per_page: Option<usize>,
+    /// Include files in subdirectories (default true)
     pub recursive: Option<bool>,
+    /// Comma-separated stack IDs to list in full instead of as one cell
+    pub expand_stacks: Option<String>,
 }
 
+/// Stacked files other than the stack's first (by filename) are hidden unless
+/// the stack is listed in the JSON array bound to `?{param}`
+fn stack_visible_clause(param: usize) -> String {
+    format!(
+        "(f.stack_id IS NULL
+          OR f.stack_id IN (SELECT value FROM json_each(?{}))
+          OR f.id = (SELECT s.id FROM files s WHERE s.stack_id = f.stack_id ORDER BY s.filename LIMIT 1))",
+        param
+    )
+}
+
+#[utoipa::path(
+    get, path = "/api/directories/{id}/files", tag = "directories",
+    params(("id" = i64, Path, description = "Directory ID"), PaginationParams),
+    responses((status = 200, body = PaginatedFiles))
+)]
 pub async fn get_directory_files(
     State(state): State<Arc<AppState>>,
     Path(dir_id): Path<i64>,
     Query(params): Query<PaginationParams>,
 ) -> Result<Json<PaginatedFiles>, AppError> {
     let db = state.db.clone();
+    let library_path = state.library_path.clone();
     let result = spawn_db(db, move |db| {
         let conn = db.connection();
 
@@ -87,57 +216,80 @@ pub async fn get_directory_files(
         let per_page = params.per_page.unwrap_or(100).min(500);
         let offset = (page - 1) * per_page;
         let recursive = params.recursive.unwrap_or(true);
+        let expanded: Vec<i64> = params
+            .expand_stacks
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .filter_map(|id| id.trim().parse().ok())
+            .collect();
+        let expanded_json = serde_json::to_string(&expanded).unwrap_or_else(|_| "[]".into());
 
         let total: usize = if recursive {
             conn.query_row(
-                "WITH RECURSIVE descendants(id) AS (
-                     SELECT id FROM directories WHERE id = ?1
-                     UNION ALL
-                     SELECT d.id FROM directories d
-                     JOIN descendants dd ON d.parent_id = dd.id
-                 )
-                 SELECT COUNT(*) FROM files f
-                 WHERE f.directory_id IN (SELECT id FROM descendants)",
-                [dir_id],
+                &format!(
+                    "WITH RECURSIVE descendants(id) AS (
+                         SELECT id FROM directories WHERE id = ?1
+                         UNION ALL
+                         SELECT d.id FROM directories d
+                         JOIN descendants dd ON d.parent_id = dd.id
+                     )
+                     SELECT COUNT(*) FROM files f
+                     WHERE f.directory_id IN (SELECT id FROM descendants)
+                       AND NOT EXISTS (SELECT 1 FROM file_pairs fp WHERE fp.file_id = f.id)
+                       AND {}",
+                    stack_visible_clause(2)
+                ),
+                rusqlite::params![dir_id, expanded_json],
                 |row| row.get(0),
             )?
         } else {
             conn.query_row(
-                "SELECT COUNT(*) FROM files WHERE directory_id = ?1",
-                [dir_id],
+                &format!(
+                    "SELECT COUNT(*) FROM files f WHERE f.directory_id = ?1
+                       AND NOT EXISTS (SELECT 1 FROM file_pairs fp WHERE fp.file_id = f.id)
+                       AND {}",
+                    stack_visible_clause(2)
+                ),
+                rusqlite::params![dir_id, expanded_json],
                 |row| row.get(0),
             )?
         };
 
         let mut stmt = if recursive {
-            conn.prepare(
+            conn.prepare(&format!(
                 "WITH RECURSIVE descendants(id) AS (
                      SELECT id FROM directories WHERE id = ?1
                      UNION ALL
                      SELECT d.id FROM directories d
                      JOIN descendants dd ON d.parent_id = dd.id
                  )
-                 SELECT f.id, f.filename, f.directory_id, d.path, f.size, f.rating, f.media_type, f.width, f.height
+                 SELECT f.id, f.filename, f.directory_id, d.path, f.size, f.rating, f.media_type, f.width, f.height, f.stack_id
                  FROM files f
                  JOIN directories d ON f.directory_id = d.id
                  WHERE f.directory_id IN (SELECT id FROM descendants)
+                   AND NOT EXISTS (SELECT 1 FROM file_pairs fp WHERE fp.file_id = f.id)
+                   AND {}
                  ORDER BY d.path, f.filename
                  LIMIT ?2 OFFSET ?3",
-            )?
+                stack_visible_clause(4)
+            ))?
         } else {
-            conn.prepare(
-                "SELECT f.id, f.filename, f.directory_id, d.path, f.size, f.rating, f.media_type, f.width, f.height
+            conn.prepare(&format!(
+                "SELECT f.id, f.filename, f.directory_id, d.path, f.size, f.rating, f.media_type, f.width, f.height, f.stack_id
                  FROM files f
                  JOIN directories d ON f.directory_id = d.id
-                 WHERE f.directory_id = ?1
+                 WHERE f.directory_id = ?1 AND NOT EXISTS (SELECT 1 FROM file_pairs fp WHERE fp.file_id = f.id)
+                   AND {}
                  ORDER BY f.filename
                  LIMIT ?2 OFFSET ?3",
-            )?
+                stack_visible_clause(4)
+            ))?
         };
 
         #[allow(clippy::type_complexity)]
-        let file_rows: Vec<(i64, String, i64, String, i64, Option<i32>, Option<String>, Option<i32>, Option<i32>)> = stmt
-            .query_map(rusqlite::params![dir_id, per_page as i64, offset as i64], |row| {
+        let file_rows: Vec<(i64, String, i64, String, i64, Option<f64>, Option<String>, Option<i32>, Option<i32>, Option<i64>)> = stmt
+            .query_map(rusqlite::params![dir_id, per_page as i64, offset as i64, expanded_json], |row| {
                 Ok((
                     row.get(0)?,
                     row.get(1)?,
@@ -148,6 +300,7 @@ pub async fn get_directory_files(
                     row.get(6)?,
                     row.get(7)?,
                     row.get(8)?,
+                    row.get(9)?,
                 ))
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -155,11 +308,25 @@ pub async fn get_directory_files(
         // Batch-fetch tags for these files
         let file_ids: Vec<i64> = file_rows.iter().map(|f| f.0).collect();
         let all_file_tags = batch_get_file_tags(conn, &file_ids)?;
+        let mut all_companions = batch_get_companions(conn, &file_ids)?;
+        let collapsed_stacks: Vec<i64> = file_rows
+            .iter()
+            .filter_map(|f| f.9)
+            .filter(|id| !expanded.contains(id))
+            .collect();
+        let stack_sizes = batch_get_stack_sizes(conn, &collapsed_stacks)?;
+        let durations = db.get_file_durations(&file_ids)?;
+        let versions = db.get_file_versions(&file_ids)?;
 
         let files: Vec<FileResponse> = file_rows
             .into_iter()
-            .map(|(id, filename, directory_id, dir_path, size, rating, media_type, width, height)| {
+            .map(|(id, filename, directory_id, dir_path, size, rating, media_type, width, height, stack_id)| {
                 let tags = all_file_tags.get(&id).cloned().unwrap_or_default();
+                let mut companions = all_companions.remove(&id).unwrap_or_default();
+                if find_xmp_sidecar(&LibraryPath::file(&dir_path, &filename).absolute(&library_path)).is_some() {
+                    companions.push("XMP".to_string());
+                }
+                let stack_size = stack_id.and_then(|s| stack_sizes.get(&s)).copied().unwrap_or(0);
                 FileResponse {
                     id,
                     filename,
@@ -170,7 +337,12 @@ pub async fn get_directory_files(
                     media_type,
                     width,
                     height,
+                    duration: durations.get(&id).copied(),
                     tags,
+                    version: versions.get(&id).copied().unwrap_or_default(),
+                    companions,
+                    stack_id,
+                    stack_size,
                 }
             })
             .collect();
@@ -189,6 +361,10 @@ pub async fn get_directory_files(
 
 // ==================== Tags ====================
 
+#[utoipa::path(
+    get, path = "/api/tags", tag = "tags",
+    responses((status = 200, description = "All tags with file/directory counts", body = Vec<TagResponse>))
+)]
 pub async fn get_tags(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<TagResponse>>, AppError> {
@@ -221,64 +397,190 @@ pub async fn get_tags(
     Ok(Json(tags))
 }
 
+#[utoipa::path(
+    get, path = "/api/tags/aliases", tag = "tags",
+    responses((status = 200, description = "Tag aliases, sorted by tag", body = Vec<TagAliasResponse>))
+)]
+pub async fn get_tag_aliases(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<TagAliasResponse>>, AppError> {
+    let db = state.db.clone();
+    let aliases = spawn_db(db, |db| db.get_tag_aliases()).await?;
+    Ok(Json(tag_alias_responses(aliases)))
+}
+
+#[utoipa::path(
+    post, path = "/api/tags/aliases", tag = "tags",
+    request_body = AddTagAliasRequest,
+    responses(
+        (status = 200, description = "All tag aliases after adding", body = Vec<TagAliasResponse>),
+        (status = 400, description = "Empty name, or an alias of itself", body = String),
+    )
+)]
+pub async fn add_tag_alias(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<AddTagAliasRequest>,
+) -> Result<Json<Vec<TagAliasResponse>>, AppError> {
+    let alias = body.alias.trim().to_lowercase();
+    let tag = body.tag.trim().to_lowercase();
+    if alias.is_empty() || tag.is_empty() {
+        return Err(AppError::BadRequest("Tag names cannot be empty".into()));
+    }
+
+    let db = state.db.clone();
+    let aliases = spawn_db(db, move |db| {
+        if db.canonical_tag(&tag)? == alias {
+            return Ok(None);
+        }
+        db.begin_transaction()?;
+        db.add_tag_alias(&alias, &tag)?;
+        db.commit()?;
+        db.get_tag_aliases().map(Some)
+    })
+    .await?
+    .ok_or_else(|| AppError::BadRequest("A tag can't be an alias of itself".into()))?;
+
+    Ok(Json(tag_alias_responses(aliases)))
+}
+
+#[utoipa::path(
+    delete, path = "/api/tags/aliases/{alias}", tag = "tags",
+    params(("alias" = String, Path, description = "Alias to remove")),
+    responses(
+        (status = 200, description = "All tag aliases after removing", body = Vec<TagAliasResponse>),
+        (status = 404, description = "Not an alias"),
+    )
+)]
+pub async fn remove_tag_alias(
+    State(state): State<Arc<AppState>>,
+    Path(alias): Path<String>,
+) -> Result<Json<Vec<TagAliasResponse>>, AppError> {
+    let db = state.db.clone();
+    let aliases = spawn_db(db, move |db| {
+        if !db.remove_tag_alias(&alias)? {
+            return Ok(None);
+        }
+        db.get_tag_aliases().map(Some)
+    })
+    .await?;
+
+    aliases.map(|a| Json(tag_alias_responses(a))).ok_or(AppError::NotFound)
+}
+
+fn tag_alias_responses(aliases: Vec<(String, String)>) -> Vec<TagAliasResponse> {
+    aliases
+        .into_iter()
+        .map(|(alias, tag)| TagAliasResponse { alias, tag })
+        .collect()
+}
+
 // ==================== Directory Mutations ====================
 
+/// Fail with a conflict if the directory changed since `expected`
+fn check_directory_version(db: &Database, dir_id: i64, expected: Option<i64>) -> anyhow::Result<()> {
+    if let Some(expected) = expected {
+        VersionConflict::check(expected, db.get_directory_version(dir_id)?.unwrap_or_default())?;
+    }
+    Ok(())
+}
+
+fn directory_meta(db: &Database, dir_id: i64) -> anyhow::Result<DirectoryMetaResponse> {
+    let rating = db.get_directory(dir_id)?.map(|d| d.rating).unwrap_or(None);
+    let tags = db.get_directory_tags(dir_id)?;
+    let version = db.get_directory_version(dir_id)?.unwrap_or_default();
+    Ok(DirectoryMetaResponse { rating, tags, version })
+}
+
+#[utoipa::path(
+    put, path = "/api/directories/{id}/rating", tag = "directories",
+    params(("id" = i64, Path, description = "Directory ID")),
+    request_body = SetRatingRequest,
+    responses(
+        (status = 200, body = DirectoryMetaResponse),
+        (status = 400, description = "Rating out of range", body = String),
+        (status = 409, description = "Changed by someone else since If-Match", body = String),
+    )
+)]
 pub async fn set_directory_rating(
     State(state): State<Arc<AppState>>,
     Path(dir_id): Path<i64>,
+    headers: HeaderMap,
     Json(body): Json<SetRatingRequest>,
 ) -> Result<Json<DirectoryMetaResponse>, AppError> {
+    let expected = expected_version(&headers, None)?;
     if let Some(r) = body.rating {
-        if !(1..=5).contains(&r) {
-            return Err(AppError::BadRequest("Rating must be between 1 and 5".into()));
-        }
+        rating_scale(&state)?
+            .validate(r)
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
     }
 
     let db = state.db.clone();
-    let meta = spawn_db(db, move |db| {
+    let meta = spawn_db(db, move |db| in_transaction(db, |db| {
+        check_directory_version(db, dir_id, expected)?;
         db.set_directory_rating(dir_id, body.rating)?;
-        let rating = db.get_directory(dir_id)?.map(|d| d.rating).unwrap_or(None);
-        let tags = db.get_directory_tags(dir_id)?;
-        Ok(DirectoryMetaResponse { rating, tags })
-    })
+        directory_meta(db, dir_id)
+    }))
     .await?;
 
     Ok(Json(meta))
 }
 
+#[utoipa::path(
+    post, path = "/api/directories/{id}/tags", tag = "directories",
+    params(("id" = i64, Path, description = "Directory ID")),
+    request_body = AddTagRequest,
+    responses(
+        (status = 200, body = DirectoryMetaResponse),
+        (status = 400, description = "Empty tag name", body = String),
+        (status = 409, description = "Changed by someone else since If-Match", body = String),
+    )
+)]
 pub async fn add_directory_tag(
     State(state): State<Arc<AppState>>,
     Path(dir_id): Path<i64>,
+    headers: HeaderMap,
     Json(body): Json<AddTagRequest>,
 ) -> Result<Json<DirectoryMetaResponse>, AppError> {
+    let expected = expected_version(&headers, None)?;
     let tag = body.tag.trim().to_lowercase();
     if tag.is_empty() {
         return Err(AppError::BadRequest("Tag name cannot be empty".into()));
     }
 
     let db = state.db.clone();
-    let meta = spawn_db(db, move |db| {
+    let meta = spawn_db(db, move |db| in_transaction(db, |db| {
+        check_directory_version(db, dir_id, expected)?;
         db.add_directory_tag(dir_id, &tag)?;
-        let rating = db.get_directory(dir_id)?.map(|d| d.rating).unwrap_or(None);
-        let tags = db.get_directory_tags(dir_id)?;
-        Ok(DirectoryMetaResponse { rating, tags })
-    })
+        directory_meta(db, dir_id)
+    }))
     .await?;
 
     Ok(Json(meta))
 }
 
+#[utoipa::path(
+    delete, path = "/api/directories/{id}/tags/{tag_name}", tag = "directories",
+    params(
+        ("id" = i64, Path, description = "Directory ID"),
+        ("tag_name" = String, Path, description = "Tag to remove"),
+    ),
+    responses(
+        (status = 200, body = DirectoryMetaResponse),
+        (status = 409, description = "Changed by someone else since If-Match", body = String),
+    )
+)]
 pub async fn remove_directory_tag(
     State(state): State<Arc<AppState>>,
     Path((dir_id, tag_name)): Path<(i64, String)>,
+    headers: HeaderMap,
 ) -> Result<Json<DirectoryMetaResponse>, AppError> {
+    let expected = expected_version(&headers, None)?;
     let db = state.db.clone();
-    let meta = spawn_db(db, move |db| {
+    let meta = spawn_db(db, move |db| in_transaction(db, |db| {
+        check_directory_version(db, dir_id, expected)?;
         db.remove_directory_tag(dir_id, &tag_name)?;
-        let rating = db.get_directory(dir_id)?.map(|d| d.rating).unwrap_or(None);
-        let tags = db.get_directory_tags(dir_id)?;
-        Ok(DirectoryMetaResponse { rating, tags })
-    })
+        directory_meta(db, dir_id)
+    }))
     .await?;
 
     Ok(Json(meta))
@@ -286,23 +588,101 @@ pub async fn remove_directory_tag(
 
 // ==================== Filtered Files ====================
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct FileFilterParams {
-    pub rating: Option<i32>,
+    /// Minimum rating, on the library's rating scale
+    pub rating: Option<f64>,
+    /// Only files with this tag
     pub tag: Option<String>,
+    /// Only files of this type: `image`, `video`, or `document`
+    pub media_type: Option<String>,
+    /// Only files shot on this camera, e.g. `Fujifilm X-T3`
+    pub camera: Option<String>,
+    /// Only files shot with this lens
+    pub lens: Option<String>,
+    /// Only files of this shape, from their dimensions: `landscape`,
+    /// `portrait`, `square`, `panorama` or `tall`
+    pub orientation: Option<String>,
+    /// Only files big enough to print at this size, e.g. `8x10` (inches),
+    /// `20x30cm` or `A4`
+    pub print_size: Option<String>,
+    /// Resolution `print_size` is checked at (default 300)
+    pub print_dpi: Option<u32>,
+    /// Only images shot at this ISO or higher
+    pub min_iso: Option<i64>,
+    /// Only images shot at this ISO or lower
+    pub max_iso: Option<i64>,
+    /// Only images shot at this f-number or narrower
+    pub min_aperture: Option<f64>,
+    /// Only images shot at this f-number or wider, e.g. `2` for f/2
+    pub max_aperture: Option<f64>,
+    /// Only images exposed this long or longer, e.g. `1/60`
+    pub min_shutter: Option<String>,
+    /// Only images exposed this long or shorter, e.g. `1/1000`
+    pub max_shutter: Option<String>,
+    /// Also apply a filter preset saved in the TUI filter dialog
+    pub preset: Option<String>,
+    /// 1-based page number (default 1)
     pub page: Option<usize>,
+    /// Page size (default 100, max 500)
     pub per_page: Option<usize>,
 }
 
+#[utoipa::path(
+    get, path = "/api/files", tag = "files",
+    params(FileFilterParams),
+    responses((status = 200, body = PaginatedFiles))
+)]
 pub async fn get_filtered_files(
     State(state): State<Arc<AppState>>,
     Query(params): Query<FileFilterParams>,
 ) -> Result<Json<PaginatedFiles>, AppError> {
+    let preset = match params.preset.clone() {
+        Some(name) => {
+            let preset = spawn_db(state.db.clone(), move |db| db.get_filter_preset(&name)).await?;
+            Some(preset.ok_or_else(|| AppError::BadRequest("Unknown filter preset".into()))?)
+        }
+        None => None,
+    };
+
+    let shutter = |value: &Option<String>| {
+        value.as_deref().map(parse_shutter).transpose().map_err(AppError::BadRequest)
+    };
+    let exposure = ExposureFilter {
+        min_iso: params.min_iso,
+        max_iso: params.max_iso,
+        min_aperture: params.min_aperture,
+        max_aperture: params.max_aperture,
+        min_shutter: shutter(&params.min_shutter)?,
+        max_shutter: shutter(&params.max_shutter)?,
+    };
+
+    let orientation = params
+        .orientation
+        .as_deref()
+        .map(str::parse::<Orientation>)
+        .transpose()
+        .map_err(AppError::BadRequest)?;
+
+    let print = params
+        .print_size
+        .as_deref()
+        .map(str::parse::<PrintSize>)
+        .transpose()
+        .map_err(AppError::BadRequest)?
+        .map(|size| PrintFilter { size, dpi: params.print_dpi.unwrap_or(DEFAULT_PRINT_DPI) });
+    if params.print_dpi == Some(0) {
+        return Err(AppError::BadRequest("print_dpi must be at least 1".into()));
+    }
+
     let db = state.db.clone();
+    let library_path = state.library_path.clone();
     let result = spawn_db(db, move |db| {
         let conn = db.connection();
 
-        let mut conditions = Vec::new();
+        // Paired siblings are reported on their primary, not listed separately
+        let mut conditions = vec!["NOT EXISTS (SELECT 1 FROM file_pairs fp WHERE fp.file_id = f.id)".to_string()];
         let mut sql_params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
 
         if let Some(rating) = params.rating {
@@ -315,14 +695,72 @@ pub async fn get_filtered_files(
                 "EXISTS (SELECT 1 FROM file_tags ft JOIN tags t ON ft.tag_id = t.id WHERE ft.file_id = f.id AND t.name = ?{})",
                 sql_params.len() + 1
             ));
-            sql_params.push(Box::new(tag.clone()));
+            sql_params.push(Box::new(db.canonical_tag(tag)?));
         }
 
-        let where_clause = if conditions.is_empty() {
-            String::new()
-        } else {
-            format!("WHERE {}", conditions.join(" AND "))
-        };
+        if let Some(ref media_type) = params.media_type {
+            conditions.push(format!("f.media_type = ?{}", sql_params.len() + 1));
+            sql_params.push(Box::new(media_type.clone()));
+        }
+
+        if let Some(ref camera) = params.camera {
+            conditions.push(format!("f.camera = ?{}", sql_params.len() + 1));
+            sql_params.push(Box::new(camera.clone()));
+        }
+
+        if let Some(ref lens) = params.lens {
+            conditions.push(format!("f.lens = ?{}", sql_params.len() + 1));
+            sql_params.push(Box::new(lens.clone()));
+        }
+
+        if let Some(orientation) = orientation {
+            conditions.push(orientation.sql_condition().to_string());
+        }
+
+        if let Some(print) = print {
+            conditions.push(print.sql_condition());
+        }
+
+        for (comparison, value) in exposure.bounds() {
+            conditions.push(format!("{} ?{}", comparison, sql_params.len() + 1));
+            sql_params.push(Box::new(value));
+        }
+
+        if let Some(preset) = preset {
+            let criteria = preset.criteria;
+            match criteria.rating {
+                RatingFilter::Any => {}
+                RatingFilter::Unrated => conditions.push("f.rating IS NULL".to_string()),
+                RatingFilter::MinRating(rating) => {
+                    conditions.push(format!("f.rating >= ?{}", sql_params.len() + 1));
+                    sql_params.push(Box::new(rating));
+                }
+            }
+            for tag in criteria.tags {
+                conditions.push(format!(
+                    "EXISTS (SELECT 1 FROM file_tags ft JOIN tags t ON ft.tag_id = t.id WHERE ft.file_id = f.id AND t.name = ?{})",
+                    sql_params.len() + 1
+                ));
+                sql_params.push(Box::new(tag));
+            }
+            if let Some(media_type) = criteria.media.media_type() {
+                conditions.push(format!("f.media_type = ?{}", sql_params.len() + 1));
+                sql_params.push(Box::new(media_type));
+            }
+            if let Some(camera) = criteria.camera {
+                conditions.push(format!("f.camera = ?{}", sql_params.len() + 1));
+                sql_params.push(Box::new(camera));
+            }
+            if let Some(lens) = criteria.lens {
+                conditions.push(format!("f.lens = ?{}", sql_params.len() + 1));
+                sql_params.push(Box::new(lens));
+            }
+            if let Some(orientation) = criteria.orientation {
+                conditions.push(orientation.sql_condition().to_string());
+            }
+        }
+
+        let where_clause = format!("WHERE {}", conditions.join(" AND "));
 
         // Get total count
         let count_sql = format!(
@@ -356,7 +794,7 @@ pub async fn get_filtered_files(
 
         let mut stmt = conn.prepare(&query)?;
         #[allow(clippy::type_complexity)]
-        let file_ids_and_data: Vec<(i64, String, i64, String, i64, Option<i32>, Option<String>, Option<i32>, Option<i32>)> = stmt
+        let file_ids_and_data: Vec<(i64, String, i64, String, i64, Option<f64>, Option<String>, Option<i32>, Option<i32>)> = stmt
             .query_map(
                 rusqlite::params_from_iter(sql_params.iter().map(|p| p.as_ref())),
                 |row| {
@@ -378,11 +816,18 @@ pub async fn get_filtered_files(
         // Batch-fetch tags for these files
         let file_ids: Vec<i64> = file_ids_and_data.iter().map(|f| f.0).collect();
         let all_file_tags = batch_get_file_tags(conn, &file_ids)?;
+        let mut all_companions = batch_get_companions(conn, &file_ids)?;
+        let durations = db.get_file_durations(&file_ids)?;
+        let versions = db.get_file_versions(&file_ids)?;
 
         let files: Vec<FileResponse> = file_ids_and_data
             .into_iter()
             .map(|(id, filename, directory_id, dir_path, size, rating, media_type, width, height)| {
                 let tags = all_file_tags.get(&id).cloned().unwrap_or_default();
+                let mut companions = all_companions.remove(&id).unwrap_or_default();
+                if find_xmp_sidecar(&LibraryPath::file(&dir_path, &filename).absolute(&library_path)).is_some() {
+                    companions.push("XMP".to_string());
+                }
                 FileResponse {
                     id,
                     filename,
@@ -393,7 +838,12 @@ pub async fn get_filtered_files(
                     media_type,
                     width,
                     height,
+                    duration: durations.get(&id).copied(),
                     tags,
+                    version: versions.get(&id).copied().unwrap_or_default(),
+                    companions,
+                    stack_id: None,
+                    stack_size: 0,
                 }
             })
             .collect();
@@ -410,6 +860,489 @@ pub async fn get_filtered_files(
     Ok(Json(result))
 }
 
+// ==================== Review Order ====================
+
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NextFileParams {
+    /// Which files need review: `unrated` (default) or `all`
+    pub filter: Option<String>,
+    /// Continue after this file; omit to start from the beginning
+    pub after: Option<i64>,
+    /// Only files of this type: `image`, `video`, or `document`
+    pub media_type: Option<String>,
+    /// How many files the batch variant returns (default 10, max 100)
+    pub limit: Option<usize>,
+    /// Include files in hidden directories and below them (default false)
+    pub include_hidden: Option<bool>,
+}
+
+#[utoipa::path(
+    get, path = "/api/files/next", tag = "files",
+    params(NextFileParams),
+    responses(
+        (status = 200, body = NextFileResponse),
+        (status = 400, description = "Unknown filter"),
+        (status = 404, description = "No file with the `after` id")
+    )
+)]
+pub async fn get_next_file(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<NextFileParams>,
+) -> Result<Json<NextFileResponse>, AppError> {
+    let (files, remaining) = next_files(state, params, 1).await?;
+    Ok(Json(NextFileResponse {
+        file: files.into_iter().next(),
+        remaining,
+    }))
+}
+
+#[utoipa::path(
+    get, path = "/api/files/next/batch", tag = "files",
+    params(NextFileParams),
+    responses(
+        (status = 200, body = NextFilesResponse),
+        (status = 400, description = "Unknown filter"),
+        (status = 404, description = "No file with the `after` id")
+    )
+)]
+pub async fn get_next_files(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<NextFileParams>,
+) -> Result<Json<NextFilesResponse>, AppError> {
+    let limit = params.limit.unwrap_or(10).clamp(1, 100);
+    let (files, remaining) = next_files(state, params, limit).await?;
+    Ok(Json(NextFilesResponse { files, remaining }))
+}
+
+/// Up to `limit` files needing review after `params.after`, in path then
+/// filename order, and how many more follow them. The order doesn't depend
+/// on ratings, so rating a file doesn't move the position of the rest.
+async fn next_files(
+    state: Arc<AppState>,
+    params: NextFileParams,
+    limit: usize,
+) -> Result<(Vec<FileResponse>, usize), AppError> {
+    let unrated_only = match params.filter.as_deref() {
+        None | Some("unrated") => true,
+        Some("all") => false,
+        Some(other) => {
+            return Err(AppError::BadRequest(format!(
+                "Unknown filter '{}' (expected unrated or all)",
+                other
+            )))
+        }
+    };
+
+    let db = state.db.clone();
+    spawn_db(db, move |db| {
+        let conn = db.connection();
+
+        let mut conditions = vec!["NOT EXISTS (SELECT 1 FROM file_pairs fp WHERE fp.file_id = f.id)".to_string()];
+        let mut sql_params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+
+        if unrated_only {
+            conditions.push("f.rating IS NULL".to_string());
+        }
+
+        if let Some(ref media_type) = params.media_type {
+            conditions.push(format!("f.media_type = ?{}", sql_params.len() + 1));
+            sql_params.push(Box::new(media_type.clone()));
+        }
+
+        if !params.include_hidden.unwrap_or(false) {
+            let hidden = db.get_hidden_subtree_ids()?;
+            if !hidden.is_empty() {
+                let n = sql_params.len();
+                let placeholders: Vec<String> = (1..=hidden.len()).map(|i| format!("?{}", n + i)).collect();
+                conditions.push(format!("f.directory_id NOT IN ({})", placeholders.join(",")));
+                sql_params.extend(hidden.into_iter().map(|id| Box::new(id) as Box<dyn rusqlite::types::ToSql>));
+            }
+        }
+
+        if let Some(after) = params.after {
+            let position: Option<(String, String)> = conn
+                .query_row(
+                    "SELECT d.path, f.filename FROM files f
+                     JOIN directories d ON f.directory_id = d.id
+                     WHERE f.id = ?1",
+                    [after],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            let Some((path, filename)) = position else {
+                return Ok(None);
+            };
+            let n = sql_params.len();
+            conditions.push(format!("(d.path, f.filename, f.id) > (?{}, ?{}, ?{})", n + 1, n + 2, n + 3));
+            sql_params.push(Box::new(path));
+            sql_params.push(Box::new(filename));
+            sql_params.push(Box::new(after));
+        }
+
+        let from_clause = format!(
+            "FROM files f JOIN directories d ON f.directory_id = d.id WHERE {}",
+            conditions.join(" AND ")
+        );
+
+        let total: usize = conn.query_row(
+            &format!("SELECT COUNT(*) {}", from_clause),
+            rusqlite::params_from_iter(sql_params.iter().map(|p| p.as_ref())),
+            |row| row.get(0),
+        )?;
+
+        let query = format!(
+            "SELECT f.id, f.filename, f.directory_id, d.path, f.size, f.rating, f.media_type, f.width, f.height
+             {}
+             ORDER BY d.path, f.filename, f.id
+             LIMIT ?{}",
+            from_clause,
+            sql_params.len() + 1,
+        );
+        sql_params.push(Box::new(limit as i64));
+
+        let mut stmt = conn.prepare(&query)?;
+        let mut files = stmt
+            .query_map(
+                rusqlite::params_from_iter(sql_params.iter().map(|p| p.as_ref())),
+                |row| {
+                    Ok(FileResponse {
+                        id: row.get(0)?,
+                        filename: row.get(1)?,
+                        directory_id: row.get(2)?,
+                        directory_path: row.get(3)?,
+                        size: row.get(4)?,
+                        rating: row.get(5)?,
+                        media_type: row.get(6)?,
+                        width: row.get(7)?,
+                        height: row.get(8)?,
+                        duration: None,
+                        tags: Vec::new(),
+                        version: 0,
+                        companions: Vec::new(),
+                        stack_id: None,
+                        stack_size: 0,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let file_ids: Vec<i64> = files.iter().map(|f| f.id).collect();
+        let all_file_tags = batch_get_file_tags(conn, &file_ids)?;
+        let mut all_companions = batch_get_companions(conn, &file_ids)?;
+        let durations = db.get_file_durations(&file_ids)?;
+        let versions = db.get_file_versions(&file_ids)?;
+        for file in &mut files {
+            file.tags = all_file_tags.get(&file.id).cloned().unwrap_or_default();
+            file.companions = all_companions.remove(&file.id).unwrap_or_default();
+            file.duration = durations.get(&file.id).copied();
+            file.version = versions.get(&file.id).copied().unwrap_or_default();
+        }
+
+        let remaining = total - files.len();
+        Ok(Some((files, remaining)))
+    })
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+// ==================== Review Queues ====================
+
+#[utoipa::path(
+    get, path = "/api/review-queues", tag = "review",
+    responses((status = 200, description = "Queues, most recently reviewed first", body = Vec<ReviewQueueResponse>))
+)]
+pub async fn get_review_queues(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ReviewQueueResponse>>, AppError> {
+    let queues = spawn_db(state.db.clone(), |db| db.get_review_queues()).await?;
+    Ok(Json(queues.into_iter().map(Into::into).collect()))
+}
+
+#[utoipa::path(
+    post, path = "/api/review-queues", tag = "review",
+    request_body = CreateReviewQueueRequest,
+    responses(
+        (status = 201, body = ReviewQueueResponse),
+        (status = 400, description = "Empty name, unknown preset or orientation, or no files match", body = String),
+        (status = 409, description = "A queue already has the name", body = String),
+    )
+)]
+pub async fn create_review_queue(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateReviewQueueRequest>,
+) -> Result<(StatusCode, Json<ReviewQueueResponse>), AppError> {
+    let name = body.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("Review queue name cannot be empty".into()));
+    }
+    let orientation = body
+        .orientation
+        .as_deref()
+        .map(str::parse::<Orientation>)
+        .transpose()
+        .map_err(AppError::BadRequest)?;
+    let filter = ListOptions {
+        min_rating: body.rating,
+        unrated: body.unrated,
+        tag: body.tag,
+        dir: body.dir,
+        preset: body.preset,
+        orientation,
+        include_hidden: body.include_hidden,
+        ..Default::default()
+    };
+
+    let queue = spawn_db(state.db.clone(), move |db| {
+        if db.find_review_queue(&name)?.is_some() {
+            return Ok(Err(AppError::Conflict(format!("A review queue named '{}' already exists", name))));
+        }
+        if let Some(preset) = &filter.preset {
+            if db.get_filter_preset(preset)?.is_none() {
+                return Ok(Err(AppError::BadRequest("Unknown filter preset".into())));
+            }
+        }
+        if matching_files(db, &filter)?.is_empty() {
+            return Ok(Err(AppError::BadRequest("No files match the filter".into())));
+        }
+        cli::create_review_queue(db, &name, &filter).map(Ok)
+    })
+    .await??;
+
+    Ok((StatusCode::CREATED, Json(queue.into())))
+}
+
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReviewSessionParams {
+    /// How many files after the current one to return (default 10, max 100)
+    pub limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get, path = "/api/review-queues/{id}", tag = "review",
+    params(("id" = i64, Path, description = "Queue ID"), ReviewSessionParams),
+    responses(
+        (status = 200, body = ReviewSessionResponse),
+        (status = 404, description = "Unknown queue"),
+    )
+)]
+pub async fn get_review_session(
+    State(state): State<Arc<AppState>>,
+    Path(queue_id): Path<i64>,
+    Query(params): Query<ReviewSessionParams>,
+) -> Result<Json<ReviewSessionResponse>, AppError> {
+    let limit = params.limit.unwrap_or(10).clamp(1, 100);
+    let library_path = state.library_path.clone();
+    let session = spawn_db(state.db.clone(), move |db| review_session(db, &library_path, queue_id, limit)).await?;
+    session.map(Json).ok_or(AppError::NotFound)
+}
+
+#[utoipa::path(
+    put, path = "/api/review-queues/{id}/position", tag = "review",
+    params(("id" = i64, Path, description = "Queue ID")),
+    request_body = ReviewPositionRequest,
+    responses(
+        (status = 200, description = "The session from the new position", body = ReviewSessionResponse),
+        (status = 404, description = "Unknown queue, or the file isn't in it"),
+    )
+)]
+pub async fn set_review_position(
+    State(state): State<Arc<AppState>>,
+    Path(queue_id): Path<i64>,
+    Json(body): Json<ReviewPositionRequest>,
+) -> Result<Json<ReviewSessionResponse>, AppError> {
+    let library_path = state.library_path.clone();
+    let session = spawn_db(state.db.clone(), move |db| {
+        if !db.set_review_position(queue_id, body.file_id, chrono::Utc::now().timestamp())? {
+            return Ok(None);
+        }
+        review_session(db, &library_path, queue_id, 10)
+    })
+    .await?;
+    session.map(Json).ok_or(AppError::NotFound)
+}
+
+#[utoipa::path(
+    post, path = "/api/review-queues/{id}/decisions", tag = "review",
+    params(("id" = i64, Path, description = "Queue ID")),
+    request_body = ReviewDecisionRequest,
+    responses(
+        (status = 200, description = "The session from the file after it", body = ReviewSessionResponse),
+        (status = 400, description = "Unknown decision", body = String),
+        (status = 404, description = "Unknown queue, or the file isn't in it"),
+    )
+)]
+pub async fn record_review_decision(
+    State(state): State<Arc<AppState>>,
+    Path(queue_id): Path<i64>,
+    Json(body): Json<ReviewDecisionRequest>,
+) -> Result<Json<ReviewSessionResponse>, AppError> {
+    let decision = body.decision.parse::<ReviewDecision>().map_err(AppError::BadRequest)?;
+    let library_path = state.library_path.clone();
+    let session = spawn_db(state.db.clone(), move |db| {
+        if !db.record_review_decision(queue_id, body.file_id, decision, chrono::Utc::now().timestamp())? {
+            return Ok(None);
+        }
+        review_session(db, &library_path, queue_id, 10)
+    })
+    .await?;
+    session.map(Json).ok_or(AppError::NotFound)
+}
+
+#[utoipa::path(
+    delete, path = "/api/review-queues/{id}", tag = "review",
+    params(("id" = i64, Path, description = "Queue ID")),
+    responses(
+        (status = 204, description = "Deleted; ratings made while reviewing are kept"),
+        (status = 404, description = "Unknown queue"),
+    )
+)]
+pub async fn delete_review_queue(
+    State(state): State<Arc<AppState>>,
+    Path(queue_id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    let deleted = spawn_db(state.db.clone(), move |db| match db.get_review_queue(queue_id)? {
+        Some(queue) => db.delete_review_queue(&queue.name),
+        None => Ok(false),
+    })
+    .await?;
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::NotFound)
+    }
+}
+
+/// A queue's current file and up to `limit` after it, or None for an
+/// unknown queue
+fn review_session(
+    db: &Database,
+    library_path: &std::path::Path,
+    queue_id: i64,
+    limit: usize,
+) -> anyhow::Result<Option<ReviewSessionResponse>> {
+    let Some(queue) = db.get_review_queue(queue_id)? else {
+        return Ok(None);
+    };
+    let entries: Vec<_> = db
+        .get_review_queue_files(queue_id)?
+        .into_iter()
+        .skip(queue.position)
+        .take(limit + 1)
+        .collect();
+    let file_ids: Vec<i64> = entries.iter().map(|e| e.file.id).collect();
+    let all_file_tags = batch_get_file_tags(db.connection(), &file_ids)?;
+    let mut all_companions = batch_get_companions(db.connection(), &file_ids)?;
+    let durations = db.get_file_durations(&file_ids)?;
+    let versions = db.get_file_versions(&file_ids)?;
+
+    let mut files = entries.into_iter().map(|entry| {
+        let file = entry.file;
+        let mut companions = all_companions.remove(&file.id).unwrap_or_default();
+        if find_xmp_sidecar(&LibraryPath::file(&entry.dir_path, &file.filename).absolute(library_path)).is_some() {
+            companions.push("XMP".to_string());
+        }
+        ReviewFileResponse {
+            decision: entry.decision.map(|d| d.as_str().to_string()),
+            file: FileResponse {
+                tags: all_file_tags.get(&file.id).cloned().unwrap_or_default(),
+                companions,
+                duration: durations.get(&file.id).copied(),
+                version: versions.get(&file.id).copied().unwrap_or_default(),
+                id: file.id,
+                filename: file.filename,
+                directory_id: file.directory_id,
+                directory_path: entry.dir_path,
+                size: file.size,
+                rating: file.rating,
+                media_type: file.media_type,
+                width: file.width,
+                height: file.height,
+                stack_id: None,
+                stack_size: 0,
+            },
+        }
+    });
+    let file = files.next();
+    Ok(Some(ReviewSessionResponse { queue: queue.into(), file, upcoming: files.collect() }))
+}
+
+// ==================== Recent Files ====================
+
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RecentParams {
+    /// How many files to return (default 50, max 500)
+    pub limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get, path = "/api/recent/viewed", tag = "files",
+    params(RecentParams),
+    responses((status = 200, body = Vec<FileResponse>))
+)]
+pub async fn get_recently_viewed(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<RecentParams>,
+) -> Result<Json<Vec<FileResponse>>, AppError> {
+    recent_files(state, Activity::Viewed, params).await
+}
+
+#[utoipa::path(
+    get, path = "/api/recent/edited", tag = "files",
+    params(RecentParams),
+    responses((status = 200, body = Vec<FileResponse>))
+)]
+pub async fn get_recently_edited(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<RecentParams>,
+) -> Result<Json<Vec<FileResponse>>, AppError> {
+    recent_files(state, Activity::Edited, params).await
+}
+
+/// Most recently viewed or edited files, newest first
+async fn recent_files(
+    state: Arc<AppState>,
+    activity: Activity,
+    params: RecentParams,
+) -> Result<Json<Vec<FileResponse>>, AppError> {
+    let limit = params.limit.unwrap_or(50).min(500);
+    let db = state.db.clone();
+    let files = spawn_db(db, move |db| {
+        let recent = db.get_recent_files(activity, limit)?;
+        let file_ids: Vec<i64> = recent.iter().map(|(f, _)| f.id).collect();
+        let all_file_tags = batch_get_file_tags(db.connection(), &file_ids)?;
+        let mut all_companions = batch_get_companions(db.connection(), &file_ids)?;
+        let durations = db.get_file_durations(&file_ids)?;
+        let versions = db.get_file_versions(&file_ids)?;
+
+        Ok(recent
+            .into_iter()
+            .map(|(file, dir_path)| FileResponse {
+                tags: all_file_tags.get(&file.id).cloned().unwrap_or_default(),
+                companions: all_companions.remove(&file.id).unwrap_or_default(),
+                id: file.id,
+                filename: file.filename,
+                directory_id: file.directory_id,
+                directory_path: dir_path,
+                size: file.size,
+                rating: file.rating,
+                media_type: file.media_type,
+                width: file.width,
+                height: file.height,
+                duration: durations.get(&file.id).copied(),
+                version: versions.get(&file.id).copied().unwrap_or_default(),
+                stack_id: None,
+                stack_size: 0,
+            })
+            .collect())
+    })
+    .await?;
+
+    Ok(Json(files))
+}
+
 fn batch_get_file_tags(
     conn: &rusqlite::Connection,
     file_ids: &[i64],
@@ -443,6 +1376,68 @@ fn batch_get_file_tags(
     Ok(result)
 }
 
+/// Number of files in each of the given stacks
+fn batch_get_stack_sizes(
+    conn: &rusqlite::Connection,
+    stack_ids: &[i64],
+) -> Result<HashMap<i64, usize>, rusqlite::Error> {
+    if stack_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let placeholders: String = stack_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+    let query = format!(
+        "SELECT stack_id, COUNT(*) FROM files WHERE stack_id IN ({}) GROUP BY stack_id",
+        placeholders
+    );
+
+    let mut stmt = conn.prepare(&query)?;
+    let rows = stmt.query_map(
+        rusqlite::params_from_iter(stack_ids.iter()),
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, usize>(1)?)),
+    )?;
+
+    rows.collect()
+}
+
+/// Extensions of paired siblings (live photo video, RAW) keyed by primary file ID
+fn batch_get_companions(
+    conn: &rusqlite::Connection,
+    file_ids: &[i64],
+) -> Result<HashMap<i64, Vec<String>>, rusqlite::Error> {
+    if file_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let placeholders: String = file_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+    let query = format!(
+        "SELECT fp.primary_id, f.filename FROM file_pairs fp
+         JOIN files f ON fp.file_id = f.id
+         WHERE fp.primary_id IN ({})
+         ORDER BY fp.primary_id, f.filename",
+        placeholders
+    );
+
+    let mut stmt = conn.prepare(&query)?;
+    let mut result: HashMap<i64, Vec<String>> = HashMap::new();
+
+    let rows = stmt.query_map(
+        rusqlite::params_from_iter(file_ids.iter()),
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+    )?;
+
+    for row in rows {
+        let (primary_id, filename) = row?;
+        let ext = std::path::Path::new(&filename)
+            .extension()
+            .map(|e| e.to_string_lossy().to_uppercase())
+            .unwrap_or_default();
+        result.entry(primary_id).or_default().push(ext);
+    }
+
+    Ok(result)
+}
+
 fn batch_get_file_dirs(
     conn: &rusqlite::Connection,
     file_ids: &[i64],
@@ -478,8 +1473,267 @@ fn batch_get_file_dirs(
     Ok(result)
 }
 
+// ==================== File Edits ====================
+
+#[utoipa::path(
+    get, path = "/api/files/{id}", tag = "files",
+    params(("id" = i64, Path, description = "File ID")),
+    responses(
+        (status = 200, body = FileDetailResponse),
+        (status = 404, description = "Unknown file"),
+    )
+)]
+pub async fn get_file(
+    State(state): State<Arc<AppState>>,
+    Path(file_id): Path<i64>,
+) -> Result<Json<FileDetailResponse>, AppError> {
+    let library_path = state.library_path.clone();
+    let db = state.db.clone();
+    let detail = spawn_db(db, move |db| {
+        let Some((file, dir_path)) = db.get_file_with_path(file_id)? else {
+            return Ok(None);
+        };
+        let ids = [file_id];
+        let tags = db.get_file_tags(file_id)?;
+        let mut companions = batch_get_companions(db.connection(), &ids)?.remove(&file_id).unwrap_or_default();
+        if find_xmp_sidecar(&LibraryPath::file(&dir_path, &file.filename).absolute(&library_path)).is_some() {
+            companions.push("XMP".to_string());
+        }
+
+        let print_sizes = match (file.width, file.height) {
+            (Some(width), Some(height)) if print_size::is_printable(file.media_type.as_deref()) => print_size::PRINT_DPIS
+                .iter()
+                .map(|&dpi| {
+                    let (width_inches, height_inches) = print_size::print_inches(width, height, dpi);
+                    PrintSizeResponse { dpi, width_inches, height_inches }
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Ok(Some(FileDetailResponse {
+            file: FileResponse {
+                id: file.id,
+                filename: file.filename,
+                directory_id: file.directory_id,
+                directory_path: dir_path,
+                size: file.size,
+                rating: file.rating,
+                media_type: file.media_type,
+                width: file.width,
+                height: file.height,
+                duration: db.get_file_durations(&ids)?.get(&file_id).copied(),
+                tags,
+                version: db.get_file_version(file_id)?.unwrap_or_default(),
+                companions,
+                stack_id: None,
+                stack_size: 0,
+            },
+            print_sizes,
+        }))
+    })
+    .await?;
+
+    detail.map(Json).ok_or(AppError::NotFound)
+}
+
+#[utoipa::path(
+    patch, path = "/api/files/{id}", tag = "files",
+    params(("id" = i64, Path, description = "File ID")),
+    request_body = UpdateFileRequest,
+    responses(
+        (status = 200, body = FileMetaResponse),
+        (status = 400, description = "Rating out of range or empty tag name", body = String),
+        (status = 404, description = "Unknown file"),
+        (status = 409, description = "Changed by someone else since `version`", body = String),
+        (status = 428, description = "Neither If-Match nor `version` given", body = String),
+    )
+)]
+pub async fn update_file(
+    State(state): State<Arc<AppState>>,
+    Path(file_id): Path<i64>,
+    headers: HeaderMap,
+    Json(body): Json<UpdateFileRequest>,
+) -> Result<Json<FileMetaResponse>, AppError> {
+    let expected = expected_version(&headers, body.version)?;
+    if expected.is_none() && !headers.contains_key(header::IF_MATCH) {
+        return Err(AppError::PreconditionRequired(
+            "Send the file's version (If-Match header or \"version\") so edits made elsewhere aren't overwritten"
+                .into(),
+        ));
+    }
+    if let Some(Some(r)) = body.rating {
+        rating_scale(&state)?
+            .validate(r)
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    }
+    let add_tags: Vec<String> = body.add_tags.iter().map(|t| t.trim().to_lowercase()).collect();
+    let remove_tags: Vec<String> = body.remove_tags.iter().map(|t| t.trim().to_lowercase()).collect();
+    if add_tags.iter().chain(&remove_tags).any(|t| t.is_empty()) {
+        return Err(AppError::BadRequest("Tag name cannot be empty".into()));
+    }
+
+    let db = state.db.clone();
+    let meta = spawn_db(db, move |db| in_transaction(db, |db| {
+        let Some((file, _)) = db.get_file_with_path(file_id)? else {
+            return Ok(None);
+        };
+        if let Some(expected) = expected {
+            VersionConflict::check(expected, db.get_file_version(file_id)?.unwrap_or_default())?;
+        }
+        let changed = body.rating.is_some() || !add_tags.is_empty() || !remove_tags.is_empty();
+        let mut rating = file.rating;
+        if let Some(new_rating) = body.rating {
+            db.set_file_rating(file_id, new_rating)?;
+            rating = new_rating;
+        }
+        for tag in &add_tags {
+            db.add_file_tag(file_id, tag)?;
+        }
+        for tag in &remove_tags {
+            db.remove_file_tag(file_id, tag)?;
+        }
+        if changed {
+            db.record_file_activity(file_id, Activity::Edited, chrono::Utc::now().timestamp())?;
+        }
+        let tags = db.get_file_tags(file_id)?;
+        let version = db.get_file_version(file_id)?.unwrap_or_default();
+        Ok(Some(FileMetaResponse { id: file_id, rating, tags, version }))
+    }))
+    .await?;
+
+    meta.map(Json).ok_or(AppError::NotFound)
+}
+
+// ==================== File Attributes ====================
+
+/// Current attributes of a file, or None if the file doesn't exist
+fn file_attributes(db: &Database, file_id: i64) -> anyhow::Result<Option<FileAttributesResponse>> {
+    if db.get_file_with_path(file_id)?.is_none() {
+        return Ok(None);
+    }
+    let attributes = db.get_file_attributes(file_id)?.into_iter().collect();
+    Ok(Some(FileAttributesResponse { file_id, attributes }))
+}
+
+#[utoipa::path(
+    get, path = "/api/files/{id}/attributes", tag = "files",
+    params(("id" = i64, Path, description = "File ID")),
+    responses(
+        (status = 200, body = FileAttributesResponse),
+        (status = 404, description = "Unknown file"),
+    )
+)]
+pub async fn get_file_attributes(
+    State(state): State<Arc<AppState>>,
+    Path(file_id): Path<i64>,
+) -> Result<Json<FileAttributesResponse>, AppError> {
+    let db = state.db.clone();
+    let attributes = spawn_db(db, move |db| file_attributes(db, file_id)).await?;
+    attributes.map(Json).ok_or(AppError::NotFound)
+}
+
+#[utoipa::path(
+    put, path = "/api/files/{id}/attributes/{key}", tag = "files",
+    params(
+        ("id" = i64, Path, description = "File ID"),
+        ("key" = String, Path, description = "Attribute name"),
+    ),
+    request_body = SetAttributeRequest,
+    responses(
+        (status = 200, body = FileAttributesResponse),
+        (status = 400, description = "Empty attribute name", body = String),
+        (status = 404, description = "Unknown file"),
+    )
+)]
+pub async fn set_file_attribute(
+    State(state): State<Arc<AppState>>,
+    Path((file_id, key)): Path<(i64, String)>,
+    Json(body): Json<SetAttributeRequest>,
+) -> Result<Json<FileAttributesResponse>, AppError> {
+    let key = key.trim().to_string();
+    if key.is_empty() {
+        return Err(AppError::BadRequest("Attribute name cannot be empty".into()));
+    }
+
+    let db = state.db.clone();
+    let attributes = spawn_db(db, move |db| {
+        if db.get_file_with_path(file_id)?.is_none() {
+            return Ok(None);
+        }
+        db.set_file_attribute(file_id, &key, &body.value)?;
+        db.record_file_activity(file_id, Activity::Edited, chrono::Utc::now().timestamp())?;
+        file_attributes(db, file_id)
+    })
+    .await?;
+
+    attributes.map(Json).ok_or(AppError::NotFound)
+}
+
+#[utoipa::path(
+    delete, path = "/api/files/{id}/attributes/{key}", tag = "files",
+    params(
+        ("id" = i64, Path, description = "File ID"),
+        ("key" = String, Path, description = "Attribute to remove"),
+    ),
+    responses(
+        (status = 200, body = FileAttributesResponse),
+        (status = 404, description = "Unknown file"),
+    )
+)]
+pub async fn remove_file_attribute(
+    State(state): State<Arc<AppState>>,
+    Path((file_id, key)): Path<(i64, String)>,
+) -> Result<Json<FileAttributesResponse>, AppError> {
+    let db = state.db.clone();
+    let attributes = spawn_db(db, move |db| {
+        if db.remove_file_attribute(file_id, &key)? {
+            db.record_file_activity(file_id, Activity::Edited, chrono::Utc::now().timestamp())?;
+        }
+        file_attributes(db, file_id)
+    })
+    .await?;
+
+    attributes.map(Json).ok_or(AppError::NotFound)
+}
+
+#[utoipa::path(
+    get, path = "/api/files/{id}/exif", tag = "files",
+    params(("id" = i64, Path, description = "File ID")),
+    responses(
+        (status = 200, body = ExifResponse),
+        (status = 404, description = "Unknown file"),
+    )
+)]
+pub async fn get_file_exif(
+    State(state): State<Arc<AppState>>,
+    Path(file_id): Path<i64>,
+) -> Result<Json<ExifResponse>, AppError> {
+    let library_path = state.library_path.clone();
+    let db = state.db.clone();
+
+    let file_path = spawn_db(db, move |db| resolve_file_path(db, &library_path, file_id)).await?;
+    let Some(file_path) = file_path else {
+        return Err(AppError::NotFound);
+    };
+
+    // Reads only the header, but still disk I/O
+    let exif = tokio::task::spawn_blocking(move || crate::tui::exif::read_exif(&file_path))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(Json(ExifResponse::new(file_id, exif)))
+}
+
 // ==================== Thumbnail Serving ====================
 
+#[utoipa::path(
+    get, path = "/thumb/{file_id}", tag = "images",
+    params(("file_id" = i64, Path)),
+    responses(
+        (status = 200, description = "Cached 400px grid thumbnail", content_type = "image/jpeg", body = Vec<u8>),
+        (status = 404, description = "Unknown file or no thumbnail"),
+    )
+)]
 pub async fn serve_web_thumbnail(
     State(state): State<Arc<AppState>>,
     Path(file_id): Path<i64>,
@@ -494,10 +1748,19 @@ pub async fn serve_web_thumbnail(
         return Ok(StatusCode::NOT_FOUND.into_response());
     };
 
-    let thumb_path = thumbnails::get_preview_path_for_file(&file_path).map(|(path, _)| path);
-    serve_cached_image(thumb_path).await
+    let preview = preview_or_convert(file_path).await?;
+    metrics::global().observe_cache("thumb", matches!(preview, Some((_, true))));
+    serve_cached_image(preview.map(|(path, _)| path)).await
 }
 
+#[utoipa::path(
+    get, path = "/preview/{file_id}", tag = "images",
+    params(("file_id" = i64, Path)),
+    responses(
+        (status = 200, description = "Cached 1440p preview, or the original image", content_type = "image/jpeg", body = Vec<u8>),
+        (status = 404, description = "Unknown file or no preview"),
+    )
+)]
 pub async fn serve_preview(
     State(state): State<Arc<AppState>>,
     Path(file_id): Path<i64>,
@@ -505,28 +1768,129 @@ pub async fn serve_preview(
     let library_path = state.library_path.clone();
     let db = state.db.clone();
 
-    let file_path = spawn_db(db, move |db| resolve_file_path(db, &library_path, file_id))
-        .await?;
+    // Previews are only fetched by the lightbox, so each one is a view
+    let file_path = spawn_db(db, move |db| {
+        let path = resolve_file_path(db, &library_path, file_id)?;
+        if path.is_some() {
+            db.record_file_activity(file_id, Activity::Viewed, chrono::Utc::now().timestamp())?;
+        }
+        Ok(path)
+    })
+    .await?;
 
     let Some(file_path) = file_path else {
         return Ok(StatusCode::NOT_FOUND.into_response());
     };
 
-    let preview = thumbnails::get_preview_path_for_file(&file_path);
+    let preview = preview_or_convert(file_path).await?;
+    metrics::global().observe_cache("preview", matches!(preview, Some((_, true))));
     match preview {
         Some((path, _)) => serve_cached_image(Some(path)).await,
         None => Ok(StatusCode::NOT_FOUND.into_response()),
     }
 }
 
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ResizeParams {
+    /// Maximum width in pixels (1-4096)
+    pub w: Option<u32>,
+    /// Maximum height in pixels (1-4096)
+    pub h: Option<u32>,
+    /// `contain` (default) fits inside the box without enlarging; `cover`
+    /// fills it exactly, cropping around the center, and needs both sides
+    pub fit: Option<String>,
+}
+
+#[utoipa::path(
+    get, path = "/img/{file_id}", tag = "images",
+    params(("file_id" = i64, Path), ResizeParams),
+    responses(
+        (status = 200, description = "Image resized to the requested box, cached on disk", content_type = "image/jpeg", body = Vec<u8>),
+        (status = 400, description = "Missing or out-of-range size, or unknown fit"),
+        (status = 404, description = "Unknown or deleted file, or a video/document without a rendered thumbnail"),
+        (status = 500, description = "The image couldn't be decoded or the resized copy written"),
+    )
+)]
+pub async fn serve_resized_image(
+    State(state): State<Arc<AppState>>,
+    Path(file_id): Path<i64>,
+    Query(params): Query<ResizeParams>,
+) -> Result<Response, AppError> {
+    let fit = match params.fit.as_deref() {
+        Some(fit) => fit.parse().map_err(AppError::BadRequest)?,
+        None => thumbnails::ResizeFit::Contain,
+    };
+    let spec = thumbnails::ResizeSpec::new(params.w, params.h, fit).map_err(AppError::BadRequest)?;
+
+    let library_path = state.library_path.clone();
+    let db = state.db.clone();
+
+    let file_path = spawn_db(db, move |db| resolve_file_path(db, &library_path, file_id)).await?;
+    let Some(file_path) = file_path else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    if let Some(path) = thumbnails::get_cached_resized(&file_path, &spec) {
+        metrics::global().observe_cache("resized", true);
+        return serve_cached_image(Some(path)).await;
+    }
+    metrics::global().observe_cache("resized", false);
+
+    if !file_path.exists() {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+    let is_image = thumbnails::is_image_file(&file_path);
+    let resized = tokio::task::spawn_blocking(move || {
+        let _permit = workers::decode_permit();
+        thumbnails::generate_resized(&file_path, &spec)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?;
+    match resized {
+        Ok(path) => serve_cached_image(Some(path)).await,
+        // Videos and documents are resized from their rendered thumbnail,
+        // which may not exist yet
+        Err(_) if !is_image => Ok(StatusCode::NOT_FOUND.into_response()),
+        Err(e) => Err(AppError::Internal(format!("failed to resize file {}: {}", file_id, e.reason))),
+    }
+}
+
+#[utoipa::path(
+    get, path = "/dir-preview/{dir_id}", tag = "images",
+    params(("dir_id" = i64, Path)),
+    responses(
+        (status = 200, description = "Cached directory collage", content_type = "image/jpeg", body = Vec<u8>),
+        (status = 404, description = "No preview generated"),
+    )
+)]
 pub async fn serve_dir_preview(
     State(_state): State<Arc<AppState>>,
     Path(dir_id): Path<i64>,
 ) -> Result<Response, AppError> {
     let path = thumbnails::get_cached_dir_preview(dir_id);
+    metrics::global().observe_cache("dir_preview", path.is_some());
     serve_cached_image(path).await
 }
 
+/// Cached preview for a file; images the browser can't display (SVG, JPEG 2000,
+/// exotic TIFF) are converted to a thumbnail on first request instead
+async fn preview_or_convert(file_path: PathBuf) -> Result<Option<(PathBuf, bool)>, AppError> {
+    if let Some(preview) = thumbnails::get_preview_path_for_file(&file_path) {
+        return Ok(Some(preview));
+    }
+    if !thumbnails::is_image_file(&file_path) {
+        return Ok(None);
+    }
+    let thumb = tokio::task::spawn_blocking(move || {
+        let _permit = workers::decode_permit();
+        thumbnails::generate_image_thumbnail(&file_path)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(thumb.map(|path| (path, false)))
+}
+
 async fn serve_cached_image(path: Option<PathBuf>) -> Result<Response, AppError> {
     let Some(path) = path else {
         return Ok(StatusCode::NOT_FOUND.into_response());
@@ -548,22 +1912,160 @@ async fn serve_cached_image(path: Option<PathBuf>) -> Result<Response, AppError>
         .unwrap())
 }
 
+// ==================== Directory Preview Jobs ====================
+
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GeneratePreviewParams {
+    /// Also regenerate every subdirectory's preview (default false)
+    pub recursive: Option<bool>,
+}
+
+#[utoipa::path(
+    post, path = "/api/directories/{id}/preview/generate", tag = "directories",
+    params(("id" = i64, Path, description = "Directory ID"), GeneratePreviewParams),
+    responses(
+        (status = 202, description = "Job started; poll `/api/jobs/{id}`", body = JobResponse),
+        (status = 404, description = "Directory not found"),
+    )
+)]
+pub async fn generate_directory_preview(
+    State(state): State<Arc<AppState>>,
+    Path(dir_id): Path<i64>,
+    Query(params): Query<GeneratePreviewParams>,
+) -> Result<Response, AppError> {
+    let recursive = params.recursive.unwrap_or(false);
+    let dir_ids = spawn_db(state.db.clone(), move |db| {
+        let directories = db.get_all_directories()?;
+        if !directories.iter().any(|d| d.id == dir_id) {
+            return Ok(None);
+        }
+        let mut ids = vec![dir_id];
+        if recursive {
+            // Parents come first, so one pass picks up every level
+            let mut i = 0;
+            while i < ids.len() {
+                let parent = ids[i];
+                ids.extend(directories.iter().filter(|d| d.parent_id == Some(parent)).map(|d| d.id));
+                i += 1;
+            }
+        }
+        Ok(Some(ids))
+    })
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(start_preview_job(&state, dir_ids))
+}
+
+#[utoipa::path(
+    post, path = "/api/directories/preview/generate", tag = "directories",
+    request_body = GeneratePreviewsRequest,
+    responses((status = 202, description = "Job started; poll `/api/jobs/{id}`", body = JobResponse))
+)]
+pub async fn generate_directory_previews(
+    State(state): State<Arc<AppState>>,
+    body: Option<Json<GeneratePreviewsRequest>>,
+) -> Result<Response, AppError> {
+    let requested = body.map(|Json(b)| b).unwrap_or_default().directory_ids;
+    let dir_ids = spawn_db(state.db.clone(), move |db| {
+        let directories = db.get_all_directories()?;
+        Ok(match requested {
+            Some(ids) => {
+                let known: HashSet<i64> = directories.iter().map(|d| d.id).collect();
+                ids.into_iter().filter(|id| known.contains(id)).collect()
+            }
+            None => directories
+                .iter()
+                .filter(|d| !thumbnails::has_dir_preview(d.id))
+                .map(|d| d.id)
+                .collect::<Vec<_>>(),
+        })
+    })
+    .await?;
+
+    Ok(start_preview_job(&state, dir_ids))
+}
+
+#[utoipa::path(
+    get, path = "/api/jobs/{id}", tag = "directories",
+    params(("id" = u64, Path, description = "Job ID")),
+    responses(
+        (status = 200, body = JobResponse),
+        (status = 404, description = "No such job, or forgotten"),
+    )
+)]
+pub async fn get_job(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<u64>,
+) -> Result<Json<JobResponse>, AppError> {
+    let job = state.jobs.get(job_id).ok_or(AppError::NotFound)?;
+    Ok(Json(job.snapshot()))
+}
+
+/// How often `/api/jobs/{id}/events` reports progress
+const JOB_EVENT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+#[utoipa::path(
+    get, path = "/api/jobs/{id}/events", tag = "directories",
+    params(("id" = u64, Path, description = "Job ID")),
+    responses(
+        (status = 200, description = "Server-sent events, each a job status, every 250ms until the job is done", body = JobResponse, content_type = "text/event-stream"),
+        (status = 404, description = "No such job, or forgotten"),
+    )
+)]
+pub async fn get_job_events(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<u64>,
+) -> Result<Sse<impl futures_util::Stream<Item = Result<Event, axum::Error>>>, AppError> {
+    let job = state.jobs.get(job_id).ok_or(AppError::NotFound)?;
+    let events = futures_util::stream::unfold((Some(job), true), |(job, first)| async move {
+        let job = job?;
+        if !first {
+            tokio::time::sleep(JOB_EVENT_INTERVAL).await;
+        }
+        let status = job.snapshot();
+        let next = (!status.done).then_some(job);
+        Some((Event::default().json_data(status), (next, false)))
+    });
+    Ok(Sse::new(events))
+}
+
+/// Register a preview job over `dir_ids`, start it in the background and
+/// answer 202 with its initial status
+fn start_preview_job(state: &Arc<AppState>, dir_ids: Vec<i64>) -> Response {
+    let job = state.jobs.start(jobs::DIR_PREVIEW, String::new(), dir_ids.len());
+    let status = job.snapshot();
+    let db = state.db.clone();
+    let library_path = state.library_path.clone();
+    tokio::task::spawn_blocking(move || {
+        jobs::run_preview_job(&job, &db, library_path, &dir_ids);
+    });
+    (StatusCode::ACCEPTED, Json(status)).into_response()
+}
+
 // ==================== Original File Serving ====================
 
+#[utoipa::path(
+    get, path = "/original/{path}", tag = "images",
+    params(("path" = String, Path, description = "Library-relative file path (may contain `/`)")),
+    responses(
+        (status = 200, description = "Original file with its guessed content type", content_type = "application/octet-stream", body = Vec<u8>),
+        (status = 403, description = "Path escapes the library"),
+        (status = 404, description = "File not found"),
+    )
+)]
 pub async fn serve_original(
     State(state): State<Arc<AppState>>,
     Path(path): Path<String>,
 ) -> Result<Response, AppError> {
-    let requested = state.library_path.join(&path);
-
-    // Path traversal protection: canonicalize and verify prefix
-    let canonical = requested
-        .canonicalize()
-        .map_err(|_| AppError::NotFound)?;
-
-    if !canonical.starts_with(&state.library_path) {
-        return Ok(StatusCode::FORBIDDEN.into_response());
-    }
+    let canonical = match safe_path::resolve_existing(&state.library_path, &path) {
+        Ok(p) => p,
+        Err(PathError::NotFound) => return Ok(StatusCode::NOT_FOUND.into_response()),
+        Err(PathError::Invalid | PathError::Escape) => {
+            return Ok(StatusCode::FORBIDDEN.into_response())
+        }
+    };
 
     if !canonical.is_file() {
         return Ok(StatusCode::NOT_FOUND.into_response());
@@ -587,120 +2089,188 @@ pub async fn serve_original(
 
 // ==================== Duplicates ====================
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct DuplicatesParams {
+    /// `exact` (default) or `similar`
     #[serde(rename = "type")]
     pub match_type: Option<String>,
+    /// Max perceptual hash distance for `similar` (default 8)
     pub threshold: Option<u32>,
+    /// 1-based page number (default 1)
     pub page: Option<usize>,
+    /// Page size (default 50, max 200)
     pub per_page: Option<usize>,
+    /// Which copy to suggest keeping: `resolution`, `oldest`, `raw`,
+    /// `shortest-path` or `prefix:<directory>` (default from `[duplicates] keep`)
+    pub keep: Option<String>,
+    /// Only look for duplicates among files with this tag
+    pub tag: Option<String>,
+    /// Only look for duplicates among files rated at least this
+    pub rating: Option<f64>,
+    /// Only look for duplicates among unrated files
+    pub unrated: Option<bool>,
+}
+
+/// The files a duplicates request is limited to
+fn duplicate_scope(tag: Option<String>, rating: Option<f64>, unrated: Option<bool>) -> Result<DuplicateScope, AppError> {
+    let unrated = unrated.unwrap_or(false);
+    if unrated && rating.is_some() {
+        return Err(AppError::BadRequest("rating and unrated can't be combined".into()));
+    }
+    Ok(DuplicateScope { tag, min_rating: rating, unrated })
 }
 
+/// The keep policy a duplicates request asked for, or the library's
+fn keep_policy(state: &AppState, keep: Option<&str>) -> Result<KeepPolicy, AppError> {
+    match keep {
+        Some(keep) => keep.parse().map_err(AppError::BadRequest),
+        None => Config::load(&state.library_path)
+            .map(|config| config.duplicates.keep)
+            .map_err(|e| AppError::Internal(e.to_string())),
+    }
+}
+
+#[utoipa::path(
+    get, path = "/api/duplicates/summary", tag = "duplicates",
+    params(DuplicatesParams),
+    responses(
+        (status = 200, body = DuplicatesSummary),
+        (status = 202, description = "Similar files are being grouped; follow the job, then ask again", body = JobResponse),
+    )
+)]
 pub async fn get_duplicates_summary(
     State(state): State<Arc<AppState>>,
     Query(params): Query<DuplicatesParams>,
-) -> Result<Json<DuplicatesSummary>, AppError> {
+) -> Result<Response, AppError> {
     let threshold = params.threshold.unwrap_or(8);
+    let policy = keep_policy(&state, params.keep.as_deref())?;
+    let scope = duplicate_scope(params.tag, params.rating, params.unrated)?;
+    if let Some(job) = similar_job(&state, &scope, threshold).await? {
+        return Ok(job);
+    }
     let db = state.db.clone();
+    let similarity = state.similarity.clone();
 
     let summary = spawn_db(db, move |db| {
-        let exact_groups = db.find_duplicates_with_paths()?;
+        let exact_groups = db.find_reported_duplicates_in(&scope)?;
         let exact_files: usize = exact_groups.iter().map(|g| g.files.len()).sum();
+        let similar_groups = similar_groups(db, &similarity, &scope, &exact_groups, threshold)?;
 
-        let hashes_raw = db.get_all_perceptual_hashes()?;
-        let hashes_u64: Vec<(i64, u64)> = hashes_raw
-            .iter()
-            .map(|(id, h)| (*id, *h as u64))
-            .collect();
-        let similar_groups_raw = perceptual_hash::group_by_similarity(&hashes_u64, threshold);
-
-        // Exclude file IDs that are in exact duplicate groups
-        let exact_file_ids: std::collections::HashSet<i64> = exact_groups
-            .iter()
-            .flat_map(|g| g.files.iter().map(|(f, _)| f.id))
-            .collect();
+        let similar_files: usize = similar_groups.iter().map(|g| g.len()).sum();
 
-        let similar_groups: Vec<&Vec<i64>> = similar_groups_raw
+        // Sizes and dimensions decide which copy is kept
+        let mut files_by_id: HashMap<i64, (crate::db::File, String)> = if similar_groups.is_empty() {
+            HashMap::new()
+        } else {
+            db.get_all_files_with_paths()?.into_iter().map(|(f, dir)| (f.id, (f, dir))).collect()
+        };
+        let similar_with_files: Vec<Vec<(crate::db::File, String)>> = similar_groups
             .iter()
-            .filter(|group| {
-                // Keep group if it has 2+ files NOT in exact groups
-                let non_exact: Vec<&i64> = group
-                    .iter()
-                    .filter(|id| !exact_file_ids.contains(id))
-                    .collect();
-                non_exact.len() >= 2
-            })
+            .map(|ids| ids.iter().filter_map(|id| files_by_id.remove(id)).collect())
             .collect();
 
-        let similar_files: usize = similar_groups
+        let exact_reclaimable_bytes: i64 = exact_groups.iter().map(|g| reclaimable_bytes(&g.files, &policy)).sum();
+        let similar_reclaimable_bytes: i64 = similar_with_files
             .iter()
-            .map(|g| {
-                g.iter()
-                    .filter(|id| !exact_file_ids.contains(id))
-                    .count()
-            })
+            .map(|files| reclaimable_bytes(files, &policy))
             .sum();
+        let by_directory = reclaimable_by_directory(
+            exact_groups
+                .iter()
+                .map(|g| g.files.as_slice())
+                .chain(similar_with_files.iter().map(|files| files.as_slice())),
+            &policy,
+        );
 
         Ok(DuplicatesSummary {
             exact_groups: exact_groups.len(),
             exact_files,
             similar_groups: similar_groups.len(),
             similar_files,
+            exact_reclaimable_bytes,
+            similar_reclaimable_bytes,
+            reclaimable_bytes: exact_reclaimable_bytes + similar_reclaimable_bytes,
+            by_directory: by_directory
+                .into_iter()
+                .map(|d| DirectorySavingsResponse { directory: d.directory, bytes: d.bytes })
+                .collect(),
         })
     })
     .await?;
 
-    Ok(Json(summary))
+    Ok(Json(summary).into_response())
 }
 
+#[utoipa::path(
+    get, path = "/api/duplicates", tag = "duplicates",
+    params(DuplicatesParams),
+    responses(
+        (status = 200, body = DuplicatesResponse),
+        (status = 202, description = "Similar files are being grouped; follow the job, then ask again", body = JobResponse),
+    )
+)]
 pub async fn get_duplicates(
     State(state): State<Arc<AppState>>,
     Query(params): Query<DuplicatesParams>,
-) -> Result<Json<DuplicatesResponse>, AppError> {
+) -> Result<Response, AppError> {
     let match_type = params.match_type.unwrap_or_else(|| "exact".to_string());
     let threshold = params.threshold.unwrap_or(8);
     let page = params.page.unwrap_or(1).max(1);
     let per_page = params.per_page.unwrap_or(50).min(200);
+    let policy = keep_policy(&state, params.keep.as_deref())?;
+    let scope = duplicate_scope(params.tag, params.rating, params.unrated)?;
+    if match_type == "similar" {
+        if let Some(job) = similar_job(&state, &scope, threshold).await? {
+            return Ok(job);
+        }
+    }
     let db = state.db.clone();
+    let similarity = state.similarity.clone();
+    let super_groups = state.super_groups.clone();
 
     let response = spawn_db(db, move |db| {
         let conn = db.connection();
 
         match match_type.as_str() {
-            "exact" => build_exact_response(db, conn, page, per_page),
-            "similar" => build_similar_response(db, conn, threshold, page, per_page),
+            "exact" => build_exact_response(db, conn, &super_groups, &scope, page, per_page, &policy),
+            "similar" => build_similar_response(
+                db,
+                conn,
+                &similarity,
+                &super_groups,
+                &scope,
+                threshold,
+                page,
+                per_page,
+                &policy,
+            ),
             _ => Err(anyhow::anyhow!("Invalid type: must be 'exact' or 'similar'")),
         }
     })
     .await?;
 
-    Ok(Json(response))
+    Ok(Json(response).into_response())
 }
 
 fn build_exact_response(
     db: &Database,
     conn: &rusqlite::Connection,
+    super_groups: &SuperGroupCache,
+    scope: &DuplicateScope,
     page: usize,
     per_page: usize,
+    policy: &KeepPolicy,
 ) -> anyhow::Result<DuplicatesResponse> {
-    let exact_groups = db.find_duplicates_with_paths()?;
+    let exact_groups = db.find_reported_duplicates_in(scope)?;
     let total_groups = exact_groups.len();
 
-    // Compute folder super-groups from ALL groups before pagination
-    let all_group_folders: Vec<(usize, Vec<String>)> = exact_groups
-        .iter()
-        .enumerate()
-        .map(|(i, g)| {
-            let mut dirs: Vec<String> = g.files.iter().map(|(_, dir)| dir.clone()).collect();
-            dirs.sort();
-            dirs.dedup();
-            (i, dirs)
-        })
-        .collect();
-    let folder_super_groups = compute_folder_super_groups(&all_group_folders);
+    // Super-groups come from ALL groups, not just this page
+    let all_super_groups = exact_super_groups(super_groups, &exact_groups, policy)?;
+    let start = (page - 1) * per_page;
+    let folder_super_groups = super_groups_on_page(&all_super_groups, start, per_page);
 
     // Paginate
-    let start = (page - 1) * per_page;
     let paged_groups: Vec<_> = exact_groups
         .into_iter()
         .enumerate()
@@ -726,6 +2296,7 @@ fn build_exact_response(
                 filename: f.filename.clone(),
                 directory_path: dir_path.clone(),
                 size: f.size,
+                mtime: f.mtime,
                 width: f.width,
                 height: f.height,
                 rating: f.rating,
@@ -734,7 +2305,7 @@ fn build_exact_response(
             })
             .collect();
 
-        let suggested_keep_id = suggest_keep_id(&files);
+        let suggested_keep_id = suggest_keep_id(&files, policy);
 
         groups.push(DuplicateGroupResponse {
             group_index: *group_index,
@@ -743,6 +2314,7 @@ fn build_exact_response(
             max_distance: None,
             files,
             suggested_keep_id,
+            distances: None,
         });
     }
 
@@ -755,62 +2327,28 @@ fn build_exact_response(
     })
 }
 
+#[allow(clippy::too_many_arguments)]
 fn build_similar_response(
     db: &Database,
     conn: &rusqlite::Connection,
+    similarity: &SimilarityCache,
+    super_groups: &SuperGroupCache,
+    scope: &DuplicateScope,
     threshold: u32,
     page: usize,
     per_page: usize,
+    policy: &KeepPolicy,
 ) -> anyhow::Result<DuplicatesResponse> {
-    // Get exact duplicate file IDs to exclude
-    let exact_groups = db.find_duplicates_with_paths()?;
-    let exact_file_ids: std::collections::HashSet<i64> = exact_groups
-        .iter()
-        .flat_map(|g| g.files.iter().map(|(f, _)| f.id))
-        .collect();
-
-    let hashes_raw = db.get_all_perceptual_hashes()?;
-    let hashes_u64: Vec<(i64, u64)> = hashes_raw
-        .iter()
-        .map(|(id, h)| (*id, *h as u64))
-        .collect();
-    let similar_groups_raw = perceptual_hash::group_by_similarity(&hashes_u64, threshold);
-
-    // Filter: keep groups with 2+ non-exact files
-    let filtered_groups: Vec<Vec<i64>> = similar_groups_raw
-        .into_iter()
-        .map(|group| {
-            group
-                .into_iter()
-                .filter(|id| !exact_file_ids.contains(id))
-                .collect::<Vec<_>>()
-        })
-        .filter(|group| group.len() >= 2)
-        .collect();
-
+    let exact_groups = db.find_reported_duplicates_in(scope)?;
+    let filtered_groups = similar_groups(db, similarity, scope, &exact_groups, threshold)?;
     let total_groups = filtered_groups.len();
 
-    // Compute folder super-groups from ALL groups before pagination
-    let all_similar_ids: Vec<i64> = filtered_groups.iter().flat_map(|g| g.iter().copied()).collect();
-    let file_dir_map = batch_get_file_dirs(conn, &all_similar_ids)?;
-
-    let all_group_folders: Vec<(usize, Vec<String>)> = filtered_groups
-        .iter()
-        .enumerate()
-        .map(|(i, fids)| {
-            let mut dirs: Vec<String> = fids
-                .iter()
-                .filter_map(|id| file_dir_map.get(id).cloned())
-                .collect();
-            dirs.sort();
-            dirs.dedup();
-            (i, dirs)
-        })
-        .collect();
-    let folder_super_groups = compute_folder_super_groups(&all_group_folders);
+    // Super-groups come from ALL groups, not just this page
+    let all_super_groups = similar_super_groups(db, super_groups, threshold, &filtered_groups, policy)?;
+    let start = (page - 1) * per_page;
+    let folder_super_groups = super_groups_on_page(&all_super_groups, start, per_page);
 
     // Paginate
-    let start = (page - 1) * per_page;
     let paged_groups: Vec<_> = filtered_groups
         .into_iter()
         .enumerate()
@@ -818,9 +2356,6 @@ fn build_similar_response(
         .take(per_page)
         .collect();
 
-    // Build hash lookup for distance computation
-    let hash_map: HashMap<i64, u64> = hashes_u64.iter().copied().collect();
-
     // Batch fetch all file data
     let all_file_ids: Vec<i64> = paged_groups
         .iter()
@@ -830,15 +2365,22 @@ fn build_similar_response(
 
     let mut groups = Vec::new();
 
+    // Perceptual hashes of this page's files, for distances
+    let mut hash_map: HashMap<i64, u64> = HashMap::new();
+
     for (group_index, file_ids) in &paged_groups {
         let mut files = Vec::new();
         for &file_id in file_ids {
             if let Some((f, dir_path)) = db.get_file_with_path(file_id)? {
+                if let Some(hash) = f.perceptual_hash {
+                    hash_map.insert(f.id, hash as u64);
+                }
                 files.push(DuplicateFileResponse {
                     id: f.id,
                     filename: f.filename,
                     directory_path: dir_path,
                     size: f.size,
+                    mtime: f.mtime,
                     width: f.width,
                     height: f.height,
                     rating: f.rating,
@@ -852,20 +2394,22 @@ fn build_similar_response(
             continue;
         }
 
-        // Compute max Hamming distance within group
-        let mut max_distance = 0u32;
-        for (i, &a) in file_ids.iter().enumerate() {
-            for &b in &file_ids[i + 1..] {
-                let ha = hash_map.get(&a).copied().unwrap_or(0);
-                let hb = hash_map.get(&b).copied().unwrap_or(0);
-                let d = perceptual_hash::hamming_distance(ha, hb);
-                if d > max_distance {
-                    max_distance = d;
-                }
+        // Hamming distance between every pair in the group
+        let mut distances = Vec::new();
+        for (i, a) in files.iter().enumerate() {
+            for b in &files[i + 1..] {
+                let ha = hash_map.get(&a.id).copied().unwrap_or(0);
+                let hb = hash_map.get(&b.id).copied().unwrap_or(0);
+                distances.push(PairDistance {
+                    a: a.id,
+                    b: b.id,
+                    distance: perceptual_hash::hamming_distance(ha, hb),
+                });
             }
         }
+        let max_distance = distances.iter().map(|d| d.distance).max().unwrap_or(0);
 
-        let suggested_keep_id = suggest_keep_id(&files);
+        let suggested_keep_id = suggest_keep_id(&files, policy);
 
         groups.push(DuplicateGroupResponse {
             group_index: *group_index,
@@ -874,6 +2418,7 @@ fn build_similar_response(
             max_distance: Some(max_distance),
             files,
             suggested_keep_id,
+            distances: Some(distances),
         });
     }
 
@@ -886,48 +2431,321 @@ fn build_similar_response(
     })
 }
 
-/// Pick the best file to keep: highest resolution (width×height), fallback to largest size.
-fn suggest_keep_id(files: &[DuplicateFileResponse]) -> i64 {
-    files
+/// The file the keep policy would keep
+fn suggest_keep_id(files: &[DuplicateFileResponse], policy: &KeepPolicy) -> i64 {
+    let paths: Vec<String> = files
+        .iter()
+        .map(|f| LibraryPath::file(&f.directory_path, &f.filename).into_string())
+        .collect();
+    let candidates: Vec<KeepCandidate> = files
+        .iter()
+        .zip(&paths)
+        .map(|(f, path)| KeepCandidate {
+            path,
+            size: f.size,
+            mtime: f.mtime,
+            width: f.width,
+            height: f.height,
+        })
+        .collect();
+    files.get(choose_keep(&candidates, policy)).map(|f| f.id).unwrap_or(0)
+}
+
+/// When grouping the similar files in `scope` at `threshold` would hold up
+/// the request (many hashes, nothing cached), start the background job that
+/// does it, or join the one already running, and answer 202 with its status
+async fn similar_job(
+    state: &Arc<AppState>,
+    scope: &DuplicateScope,
+    threshold: u32,
+) -> Result<Option<Response>, AppError> {
+    let similarity = state.similarity.clone();
+    let hashes_scope = scope.clone();
+    let quick = spawn_db(state.db.clone(), move |db| {
+        let hashes: Vec<(i64, u64)> = db
+            .get_perceptual_hashes_in(&hashes_scope)?
+            .into_iter()
+            .map(|(id, h)| (id, h as u64))
+            .collect();
+        Ok(similarity.is_quick(&hashes, threshold))
+    })
+    .await?;
+    if quick {
+        return Ok(None);
+    }
+
+    let (job, started) = state
+        .jobs
+        .join_or_start(jobs::SIMILAR_DUPLICATES, format!("{} {:?}", threshold, scope));
+    if started {
+        let (job, db, similarity, scope) = (job.clone(), state.db.clone(), state.similarity.clone(), scope.clone());
+        tokio::task::spawn_blocking(move || jobs::run_similar_job(&job, &db, &similarity, &scope, threshold));
+    }
+    Ok(Some((StatusCode::ACCEPTED, Json(job.snapshot())).into_response()))
+}
+
+/// Groups of similar files in `scope` at `threshold`, leaving out files in
+/// `exact_groups` and pairs marked "keep both"; only groups of 2+ remain
+fn similar_groups(
+    db: &Database,
+    similarity: &SimilarityCache,
+    scope: &DuplicateScope,
+    exact_groups: &[DuplicateGroup],
+    threshold: u32,
+) -> anyhow::Result<Vec<Vec<i64>>> {
+    let hashes: Vec<(i64, u64)> = db
+        .get_perceptual_hashes_in(scope)?
+        .into_iter()
+        .map(|(id, h)| (id, h as u64))
+        .collect();
+    let exact_file_ids: HashSet<i64> = exact_groups
+        .iter()
+        .flat_map(|g| g.files.iter().map(|(f, _)| f.id))
+        .collect();
+    let ignored = db.get_ignored_duplicate_pairs()?;
+    Ok(similarity
+        .groups(&hashes, threshold)
         .iter()
-        .max_by_key(|f| {
-            let pixels = f
-                .width
-                .unwrap_or(0)
-                .saturating_mul(f.height.unwrap_or(0)) as i64;
-            (pixels, f.size)
+        .map(|group| {
+            let mut group: Vec<i64> = group
+                .iter()
+                .copied()
+                .filter(|id| !exact_file_ids.contains(id))
+                .collect();
+            ignored.retain_duplicates(&mut group, |&id| id);
+            group
         })
-        .map(|f| f.id)
-        .unwrap_or(0)
+        .filter(|group| group.len() >= 2)
+        .collect())
+}
+
+fn exact_super_groups(
+    cache: &SuperGroupCache,
+    groups: &[DuplicateGroup],
+    policy: &KeepPolicy,
+) -> anyhow::Result<Arc<Vec<FolderSuperGroup>>> {
+    let ids = groups.iter().map(|g| g.files.iter().map(|(f, _)| f.id).collect()).collect();
+    cache.get_or_compute(("exact".to_string(), 0, policy.to_string()), ids, || {
+        Ok(compute_folder_super_groups(groups.iter().map(|g| g.files.as_slice()), policy))
+    })
+}
+
+fn similar_super_groups(
+    db: &Database,
+    cache: &SuperGroupCache,
+    threshold: u32,
+    groups: &[Vec<i64>],
+    policy: &KeepPolicy,
+) -> anyhow::Result<Arc<Vec<FolderSuperGroup>>> {
+    cache.get_or_compute(("similar".to_string(), threshold, policy.to_string()), groups.to_vec(), || {
+        let mut files_by_id: HashMap<i64, (crate::db::File, String)> = if groups.is_empty() {
+            HashMap::new()
+        } else {
+            db.get_all_files_with_paths()?.into_iter().map(|(f, dir)| (f.id, (f, dir))).collect()
+        };
+        let group_files: Vec<Vec<(crate::db::File, String)>> = groups
+            .iter()
+            .map(|ids| ids.iter().filter_map(|id| files_by_id.remove(id)).collect())
+            .collect();
+        Ok(compute_folder_super_groups(group_files.iter().map(|files| files.as_slice()), policy))
+    })
 }
 
-/// Compute folder super-groups: pairs of folders that appear together in 2+ groups.
-fn compute_folder_super_groups(
-    all_group_folders: &[(usize, Vec<String>)],
+/// Folder super-groups: pairs of folders that hold the copies of 2+ groups
+/// between them, most reclaimable bytes first
+fn compute_folder_super_groups<'a>(
+    groups: impl Iterator<Item = &'a [(crate::db::File, String)]>,
+    policy: &KeepPolicy,
 ) -> Vec<FolderSuperGroup> {
-    let mut pair_map: HashMap<(String, String), Vec<usize>> = HashMap::new();
-
-    for (group_index, dirs) in all_group_folders {
-        if dirs.len() == 2 {
-            let pair = if dirs[0] <= dirs[1] {
-                (dirs[0].clone(), dirs[1].clone())
-            } else {
-                (dirs[1].clone(), dirs[0].clone())
-            };
-            pair_map.entry(pair).or_default().push(*group_index);
+    let mut pair_map: HashMap<(String, String), (Vec<usize>, i64)> = HashMap::new();
+
+    for (group_index, files) in groups.enumerate() {
+        let mut dirs: Vec<&String> = files.iter().map(|(_, dir)| dir).collect();
+        dirs.sort();
+        dirs.dedup();
+        if let [a, b] = dirs[..] {
+            let entry = pair_map.entry((a.clone(), b.clone())).or_default();
+            entry.0.push(group_index);
+            entry.1 += reclaimable_bytes(files, policy);
         }
     }
 
-    pair_map
+    let mut super_groups: Vec<FolderSuperGroup> = pair_map
         .into_iter()
-        .filter(|(_, indices)| indices.len() >= 2)
-        .map(|((a, b), indices)| FolderSuperGroup {
+        .filter(|(_, (indices, _))| indices.len() >= 2)
+        .map(|((a, b), (group_indices, reclaimable_bytes))| FolderSuperGroup {
             folders: vec![a, b],
-            group_indices: indices,
+            group_indices,
+            reclaimable_bytes,
         })
+        .collect();
+    super_groups.sort_by(|a, b| b.reclaimable_bytes.cmp(&a.reclaimable_bytes).then_with(|| a.folders.cmp(&b.folders)));
+    super_groups
+}
+
+/// The super-groups with a group among `per_page` groups from `start`
+fn super_groups_on_page(super_groups: &[FolderSuperGroup], start: usize, per_page: usize) -> Vec<FolderSuperGroup> {
+    let page = start..start + per_page;
+    super_groups
+        .iter()
+        .filter(|sg| sg.group_indices.iter().any(|i| page.contains(i)))
+        .cloned()
         .collect()
 }
 
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FolderSuperGroupParams {
+    /// `exact` (default) or `similar`
+    #[serde(rename = "type")]
+    pub match_type: Option<String>,
+    /// Max perceptual hash distance for `similar` (default 8)
+    pub threshold: Option<u32>,
+    /// Keep policy the savings are counted with (default from `[duplicates] keep`)
+    pub keep: Option<String>,
+    /// Only look for duplicates among files with this tag
+    pub tag: Option<String>,
+    /// Only look for duplicates among files rated at least this
+    pub rating: Option<f64>,
+    /// Only look for duplicates among unrated files
+    pub unrated: Option<bool>,
+    /// Only pairs with a folder at or below this path
+    pub prefix: Option<String>,
+    /// `savings` (default, most bytes first), `groups` (most groups first)
+    /// or `folders` (by path)
+    pub sort: Option<String>,
+    /// 1-based page number (default 1)
+    pub page: Option<usize>,
+    /// Page size (default 50, max 200)
+    pub per_page: Option<usize>,
+}
+
+#[utoipa::path(
+    get, path = "/api/duplicates/folders", tag = "duplicates",
+    params(FolderSuperGroupParams),
+    responses(
+        (status = 200, body = FolderSuperGroupsResponse),
+        (status = 202, description = "Similar files are being grouped; follow the job, then ask again", body = JobResponse),
+        (status = 400, description = "Unknown type, sort or keep policy", body = String),
+    )
+)]
+pub async fn get_folder_super_groups(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<FolderSuperGroupParams>,
+) -> Result<Response, AppError> {
+    let match_type = params.match_type.unwrap_or_else(|| "exact".to_string());
+    if match_type != "exact" && match_type != "similar" {
+        return Err(AppError::BadRequest("type must be 'exact' or 'similar'".into()));
+    }
+    let sort = params.sort.unwrap_or_else(|| "savings".to_string());
+    if !["savings", "groups", "folders"].contains(&sort.as_str()) {
+        return Err(AppError::BadRequest("sort must be 'savings', 'groups' or 'folders'".into()));
+    }
+    let threshold = params.threshold.unwrap_or(8);
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(50).clamp(1, 200);
+    let prefix = params.prefix.unwrap_or_default().trim_matches('/').to_string();
+    let policy = keep_policy(&state, params.keep.as_deref())?;
+    let scope = duplicate_scope(params.tag, params.rating, params.unrated)?;
+    if match_type == "similar" {
+        if let Some(job) = similar_job(&state, &scope, threshold).await? {
+            return Ok(job);
+        }
+    }
+    let db = state.db.clone();
+    let similarity = state.similarity.clone();
+    let cache = state.super_groups.clone();
+
+    let all = spawn_db(db, move |db| {
+        let exact_groups = db.find_reported_duplicates_in(&scope)?;
+        if match_type == "exact" {
+            exact_super_groups(&cache, &exact_groups, &policy)
+        } else {
+            let groups = similar_groups(db, &similarity, &scope, &exact_groups, threshold)?;
+            similar_super_groups(db, &cache, threshold, &groups, &policy)
+        }
+    })
+    .await?;
+
+    let under_prefix = |folder: &String| {
+        prefix.is_empty() || folder == &prefix || folder.starts_with(&format!("{}/", prefix))
+    };
+    let mut matching: Vec<&FolderSuperGroup> = all
+        .iter()
+        .filter(|sg| sg.folders.iter().any(under_prefix))
+        .collect();
+    match sort.as_str() {
+        "groups" => matching.sort_by(|a, b| {
+            b.group_indices
+                .len()
+                .cmp(&a.group_indices.len())
+                .then_with(|| b.reclaimable_bytes.cmp(&a.reclaimable_bytes))
+        }),
+        "folders" => matching.sort_by(|a, b| a.folders.cmp(&b.folders)),
+        // Already by savings
+        _ => {}
+    }
+
+    Ok(Json(FolderSuperGroupsResponse {
+        total: matching.len(),
+        super_groups: matching
+            .into_iter()
+            .skip((page - 1) * per_page)
+            .take(per_page)
+            .cloned()
+            .collect(),
+        page,
+        per_page,
+    })
+    .into_response())
+}
+
+#[utoipa::path(
+    post, path = "/api/duplicates/ignore", tag = "duplicates",
+    request_body = IgnoreDuplicatesRequest,
+    responses(
+        (status = 200, description = "The files are no longer reported as duplicates of each other", body = IgnoreDuplicatesResponse),
+        (status = 400, description = "Fewer than two files")
+    )
+)]
+pub async fn ignore_duplicates(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<IgnoreDuplicatesRequest>,
+) -> Result<Json<IgnoreDuplicatesResponse>, AppError> {
+    if body.file_ids.len() < 2 {
+        return Err(AppError::BadRequest("file_ids needs at least two files".to_string()));
+    }
+    let pairs = spawn_db(state.db.clone(), move |db| {
+        db.ignore_duplicates(&body.file_ids, chrono::Utc::now().timestamp())
+    })
+    .await?;
+    Ok(Json(IgnoreDuplicatesResponse { pairs }))
+}
+
+#[utoipa::path(
+    delete, path = "/api/duplicates/ignore", tag = "duplicates",
+    request_body = IgnoreDuplicatesRequest,
+    responses(
+        (status = 200, description = "The files are reported as duplicates again", body = IgnoreDuplicatesResponse),
+        (status = 400, description = "Fewer than two files")
+    )
+)]
+pub async fn unignore_duplicates(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<IgnoreDuplicatesRequest>,
+) -> Result<Json<IgnoreDuplicatesResponse>, AppError> {
+    if body.file_ids.len() < 2 {
+        return Err(AppError::BadRequest("file_ids needs at least two files".to_string()));
+    }
+    let pairs = spawn_db(state.db.clone(), move |db| db.unignore_duplicates(&body.file_ids)).await?;
+    Ok(Json(IgnoreDuplicatesResponse { pairs }))
+}
+
+#[utoipa::path(
+    post, path = "/api/duplicates/trash", tag = "duplicates",
+    request_body = TrashFilesRequest,
+    responses((status = 200, description = "Files moved to the library trash", body = TrashFilesResponse))
+)]
 pub async fn trash_files(
     State(state): State<Arc<AppState>>,
     Json(body): Json<TrashFilesRequest>,
@@ -968,11 +2786,7 @@ async fn execute_trash(
             let mut paths = Vec::new();
             for file_id in &file_ids {
                 if let Some((file, dir_path)) = db.get_file_with_path(*file_id)? {
-                    let full_path = if dir_path.is_empty() {
-                        library_path.join(&file.filename)
-                    } else {
-                        library_path.join(&dir_path).join(&file.filename)
-                    };
+                    let full_path = LibraryPath::file(&dir_path, &file.filename).absolute(&library_path);
                     paths.push((*file_id, full_path, dir_path, file.filename));
                 }
             }
@@ -1001,11 +2815,7 @@ async fn execute_trash(
                         let web_thumb_path = thumbnails::get_web_thumbnail_path(full_path);
 
                         // Compute trash destination
-                        let trash_dir = if dir_path.is_empty() {
-                            trash_root.clone()
-                        } else {
-                            trash_root.join(dir_path)
-                        };
+                        let trash_dir = LibraryPath::new(dir_path).absolute(&trash_root);
                         let mut trash_path = trash_dir.join(filename);
 
                         // Handle name conflicts
@@ -1089,6 +2899,11 @@ async fn execute_trash(
     Ok((trashed, errors))
 }
 
+#[utoipa::path(
+    post, path = "/api/duplicates/trash-folder-rule", tag = "duplicates",
+    request_body = TrashFolderRuleRequest,
+    responses((status = 200, body = TrashFolderRuleResponse))
+)]
 pub async fn trash_folder_rule(
     State(state): State<Arc<AppState>>,
     Json(body): Json<TrashFolderRuleRequest>,
@@ -1098,6 +2913,7 @@ pub async fn trash_folder_rule(
     let trash_folder = body.trash_folder.clone();
     let match_type = body.match_type.clone();
     let threshold = body.threshold.unwrap_or(8);
+    let similarity = state.similarity.clone();
 
     // Collect file IDs to trash from ALL matching groups
     let (file_ids_to_trash, groups_resolved) = spawn_db(db.clone(), move |db| {
@@ -1105,7 +2921,7 @@ pub async fn trash_folder_rule(
 
         match match_type.as_str() {
             "exact" => {
-                let exact_groups = db.find_duplicates_with_paths()?;
+                let exact_groups = db.find_reported_duplicates_with_paths()?;
                 let mut to_trash = Vec::new();
                 let mut resolved = 0usize;
 
@@ -1127,7 +2943,7 @@ pub async fn trash_folder_rule(
             }
             "similar" => {
                 // Get exact duplicate file IDs to exclude
-                let exact_groups = db.find_duplicates_with_paths()?;
+                let exact_groups = db.find_reported_duplicates_with_paths()?;
                 let exact_file_ids: std::collections::HashSet<i64> = exact_groups
                     .iter()
                     .flat_map(|g| g.files.iter().map(|(f, _)| f.id))
@@ -1138,17 +2954,20 @@ pub async fn trash_folder_rule(
                     .iter()
                     .map(|(id, h)| (*id, *h as u64))
                     .collect();
-                let similar_groups_raw =
-                    perceptual_hash::group_by_similarity(&hashes_u64, threshold);
+                let similar_groups_raw = similarity.groups(&hashes_u64, threshold);
 
                 // Filter: keep groups with 2+ non-exact files
+                let ignored = db.get_ignored_duplicate_pairs()?;
                 let filtered_groups: Vec<Vec<i64>> = similar_groups_raw
-                    .into_iter()
+                    .iter()
                     .map(|group| {
-                        group
-                            .into_iter()
+                        let mut group: Vec<i64> = group
+                            .iter()
+                            .copied()
                             .filter(|id| !exact_file_ids.contains(id))
-                            .collect::<Vec<_>>()
+                            .collect();
+                        ignored.retain_duplicates(&mut group, |&id| id);
+                        group
                     })
                     .filter(|group| group.len() >= 2)
                     .collect();
@@ -1200,6 +3019,82 @@ pub async fn trash_folder_rule(
     }))
 }
 
+// ==================== Disk Usage ====================
+
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UsageParams {
+    /// Library-relative directory (default: library root)
+    pub path: Option<String>,
+    /// Levels of children to include (default 2, max 5)
+    pub depth: Option<usize>,
+    /// Largest children kept per node (default 20, max 200)
+    pub limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get, path = "/api/usage", tag = "directories",
+    params(UsageParams),
+    responses(
+        (status = 200, description = "Recursive size aggregates for a treemap", body = UsageNodeResponse),
+        (status = 404, description = "Directory not found")
+    )
+)]
+pub async fn get_usage(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<UsageParams>,
+) -> Result<Json<UsageNodeResponse>, AppError> {
+    let db = state.db.clone();
+    let root = params.path.unwrap_or_default().trim_matches('/').to_string();
+    let depth = params.depth.unwrap_or(2).min(5);
+    let limit = params.limit.unwrap_or(20).clamp(1, 200);
+
+    let tree = spawn_db(db, move |db| db.get_usage_tree(&root, depth, limit)).await?;
+    tree.map(|node| Json(node.into())).ok_or(AppError::NotFound)
+}
+
+#[utoipa::path(
+    get, path = "/api/stats", tag = "directories",
+    responses((status = 200, description = "Library totals, without scanning the files table", body = StatsResponse))
+)]
+pub async fn get_stats(State(state): State<Arc<AppState>>) -> Result<Json<StatsResponse>, AppError> {
+    let summary = spawn_db(state.db.clone(), |db| db.library_summary()).await?;
+    Ok(Json(summary.into()))
+}
+
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HeatmapParams {
+    /// Calendar year (default: this year)
+    pub year: Option<i32>,
+}
+
+#[utoipa::path(
+    get, path = "/api/stats/heatmap", tag = "directories",
+    params(HeatmapParams),
+    responses((status = 200, description = "Photos per day of the year, by EXIF capture date", body = HeatmapResponse))
+)]
+pub async fn get_stats_heatmap(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HeatmapParams>,
+) -> Result<Json<HeatmapResponse>, AppError> {
+    let year = params.year.unwrap_or_else(|| chrono::Local::now().year());
+    let (days, years) = spawn_db(state.db.clone(), move |db| {
+        Ok((db.get_capture_days(year)?, db.get_capture_years()?))
+    })
+    .await?;
+    Ok(Json(HeatmapResponse {
+        year,
+        total: days.iter().map(|d| d.count).sum(),
+        max: days.iter().map(|d| d.count).max().unwrap_or(0),
+        days: days
+            .into_iter()
+            .map(|d| HeatmapDayResponse { date: d.date.format("%Y-%m-%d").to_string(), count: d.count })
+            .collect(),
+        years,
+    }))
+}
+
 // ==================== Embedded Assets ====================
 
 pub async fn serve_embedded_asset(
@@ -1208,6 +3103,10 @@ pub async fn serve_embedded_asset(
     let path = req.uri().path().trim_start_matches('/');
     let path = if path.is_empty() { "index.html" } else { path };
 
+    if path == "index.html" {
+        return Ok(index_html());
+    }
+
     match Assets::get(path) {
         Some(content) => {
             let content_type = mime_guess::from_path(path)
@@ -1222,15 +3121,24 @@ pub async fn serve_embedded_asset(
         }
         None => {
             // SPA fallback: serve index.html for unknown routes
-            match Assets::get("index.html") {
-                Some(content) => Ok(Response::builder()
-                    .status(StatusCode::OK)
-                    .header(header::CONTENT_TYPE, "text/html")
-                    .body(Body::from(content.data.to_vec()))
-                    .unwrap()),
-                None => Ok(StatusCode::NOT_FOUND.into_response()),
-            }
+            Ok(index_html())
+        }
+    }
+}
+
+/// index.html with the asset hash filled in, which the page compares with
+/// `/api/version` to notice the server was upgraded under it
+fn index_html() -> Response {
+    match Assets::get("index.html") {
+        Some(content) => {
+            let html = String::from_utf8_lossy(&content.data).replace("{{ASSET_HASH}}", ASSET_HASH);
+            Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "text/html")
+                .body(Body::from(html))
+                .unwrap()
         }
+        None => StatusCode::NOT_FOUND.into_response(),
     }
 }
 
@@ -1251,13 +3159,7 @@ fn resolve_file_path(db: &Database, library_path: &std::path::Path, file_id: i64
         return Ok(None);
     };
 
-    let path = if dir_path.is_empty() {
-        library_path.join(&filename)
-    } else {
-        library_path.join(&dir_path).join(&filename)
-    };
-
-    Ok(Some(path))
+    Ok(Some(LibraryPath::file(&dir_path, &filename).absolute(library_path)))
 }
 
 async fn spawn_db<F, T>(db: Arc<Mutex<Database>>, f: F) -> Result<T, AppError>
@@ -1266,18 +3168,69 @@ where
     T: Send + 'static,
 {
     tokio::task::spawn_blocking(move || {
+        let metrics = metrics::global();
+        let start = std::time::Instant::now();
         let db = db.lock().map_err(|_| AppError::Internal("Database lock poisoned".into()))?;
-        f(&db).map_err(|e| AppError::Internal(e.to_string()))
+        metrics.db_lock_wait.observe(start.elapsed());
+
+        let start = std::time::Instant::now();
+        let result = f(&db).map_err(|e| match e.downcast_ref::<VersionConflict>() {
+            Some(conflict) => AppError::Conflict(conflict.to_string()),
+            None => AppError::Internal(e.to_string()),
+        });
+        metrics.db_query.observe(start.elapsed());
+        result
     })
     .await
     .map_err(|e| AppError::Internal(e.to_string()))?
 }
 
+/// The version a write is based on, from an `If-Match` header (`"3"` or
+/// `3`; `*` matches any) or else the request body
+fn expected_version(headers: &HeaderMap, body_version: Option<i64>) -> Result<Option<i64>, AppError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(body_version);
+    };
+    let value = value
+        .to_str()
+        .map_err(|_| AppError::BadRequest("Invalid If-Match header".into()))?
+        .trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    value
+        .trim_start_matches("W/")
+        .trim_matches('"')
+        .parse()
+        .map(Some)
+        .map_err(|_| AppError::BadRequest(format!("If-Match must be a version number, got {}", value)))
+}
+
+/// Run `f` as one transaction, rolled back if it fails, so a version check
+/// and the write it guards can't be split by another process's write
+fn in_transaction<T>(db: &Database, f: impl FnOnce(&Database) -> anyhow::Result<T>) -> anyhow::Result<T> {
+    db.begin_transaction()?;
+    match f(db) {
+        Ok(value) => {
+            db.commit()?;
+            Ok(value)
+        }
+        Err(e) => {
+            let _ = db.rollback();
+            Err(e)
+        }
+    }
+}
+
 // ==================== Error Type ====================
 
 pub enum AppError {
     NotFound,
     BadRequest(String),
+    /// Changed by someone else since the client read it
+    Conflict(String),
+    /// A write that must say which version it was based on didn't
+    PreconditionRequired(String),
     Internal(String),
 }
 
@@ -1288,6 +3241,10 @@ impl IntoResponse for AppError {
             AppError::BadRequest(msg) => {
                 (StatusCode::BAD_REQUEST, msg).into_response()
             }
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg).into_response(),
+            AppError::PreconditionRequired(msg) => {
+                (StatusCode::PRECONDITION_REQUIRED, msg).into_response()
+            }
             AppError::Internal(msg) => {
                 eprintln!("Internal error: {}", msg);
                 StatusCode::INTERNAL_SERVER_ERROR.into_response()
@@ -0,0 +1,225 @@
+//! Background jobs started from the web API. A request registers a job and
+//! returns its id straight away; the work runs on a blocking thread and
+//! clients poll `/api/jobs/{id}` or follow `/api/jobs/{id}/events` for
+//! progress.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use rayon::prelude::*;
+
+use crate::config::Config;
+use crate::db::{Database, DuplicateScope};
+use crate::thumbnails::{
+    collect_preview_images_standalone, generate_dir_preview_from_paths, TempPreviewState,
+};
+use crate::workers;
+
+use super::models::JobResponse;
+use super::SimilarityCache;
+
+/// Finished jobs kept for polling; older ones are forgotten
+const MAX_JOBS: usize = 64;
+
+/// Directory previews, counting directories
+pub const DIR_PREVIEW: &str = "dir_preview";
+/// Similar-duplicate groups, counting images compared
+pub const SIMILAR_DUPLICATES: &str = "similar_duplicates";
+
+/// Progress of one background job
+pub struct Job {
+    pub id: u64,
+    kind: &'static str,
+    /// What the job was started for, so a request for the same thing joins
+    /// it instead of starting another
+    key: String,
+    total: AtomicUsize,
+    completed: AtomicUsize,
+    generated: AtomicUsize,
+    done: AtomicBool,
+    error: Mutex<Option<String>>,
+}
+
+impl Job {
+    pub fn snapshot(&self) -> JobResponse {
+        JobResponse {
+            id: self.id,
+            kind: self.kind.to_string(),
+            total: self.total.load(Ordering::Relaxed),
+            completed: self.completed.load(Ordering::Relaxed),
+            generated: self.generated.load(Ordering::Relaxed),
+            done: self.done.load(Ordering::Relaxed),
+            error: self.error.lock().ok().and_then(|e| e.clone()),
+        }
+    }
+}
+
+/// Jobs started by this server, newest last
+#[derive(Default)]
+pub struct JobRegistry {
+    next_id: AtomicU64,
+    jobs: Mutex<VecDeque<Arc<Job>>>,
+}
+
+impl JobRegistry {
+    /// Register a job of `kind` over `total` items, dropping the oldest
+    /// finished job once more than [`MAX_JOBS`] are kept
+    pub fn start(&self, kind: &'static str, key: String, total: usize) -> Arc<Job> {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        self.push(&mut jobs, kind, key, total)
+    }
+
+    /// The unfinished job of `kind` started for `key`, or a new one, and
+    /// whether it's new (and so needs running)
+    pub fn join_or_start(&self, kind: &'static str, key: String) -> (Arc<Job>, bool) {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        let running = jobs
+            .iter()
+            .find(|j| j.kind == kind && j.key == key && !j.done.load(Ordering::Relaxed))
+            .cloned();
+        match running {
+            Some(job) => (job, false),
+            None => (self.push(&mut jobs, kind, key, 0), true),
+        }
+    }
+
+    fn push(&self, jobs: &mut VecDeque<Arc<Job>>, kind: &'static str, key: String, total: usize) -> Arc<Job> {
+        let job = Arc::new(Job {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed) + 1,
+            kind,
+            key,
+            total: AtomicUsize::new(total),
+            completed: AtomicUsize::new(0),
+            generated: AtomicUsize::new(0),
+            done: AtomicBool::new(false),
+            error: Mutex::new(None),
+        });
+        if jobs.len() >= MAX_JOBS {
+            if let Some(i) = jobs.iter().position(|j| j.done.load(Ordering::Relaxed)) {
+                jobs.remove(i);
+            }
+        }
+        jobs.push_back(job.clone());
+        job
+    }
+
+    pub fn get(&self, id: u64) -> Option<Arc<Job>> {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.iter().find(|j| j.id == id).cloned()
+    }
+}
+
+/// Generate the composite previews of `dir_ids`, the same way the TUI does:
+/// sample images while holding the database, then decode in parallel
+/// without it. Blocks; run on a blocking thread.
+pub fn run_preview_job(
+    job: &Job,
+    db: &Mutex<Database>,
+    library_path: PathBuf,
+    dir_ids: &[i64],
+) {
+    finish(job, generate_previews(job, db, library_path, dir_ids));
+}
+
+/// Record how a job ended
+fn finish(job: &Job, result: Result<()>) {
+    if let Err(e) = result {
+        if let Ok(mut error) = job.error.lock() {
+            *error = Some(format!("{:#}", e));
+        }
+    }
+    job.done.store(true, Ordering::Relaxed);
+}
+
+fn generate_previews(
+    job: &Job,
+    db: &Mutex<Database>,
+    library_path: PathBuf,
+    dir_ids: &[i64],
+) -> Result<()> {
+    let preview_data: Vec<(i64, Vec<PathBuf>)> = {
+        let db = db.lock().map_err(|_| anyhow::anyhow!("Database lock poisoned"))?;
+        let directories = db.get_all_directories()?;
+        let state = TempPreviewState {
+            cover: Config::load(&library_path)?.previews.cover,
+            library_path,
+            db: &db,
+            directories: &directories,
+        };
+        dir_ids
+            .iter()
+            .filter_map(|id| state.directories.iter().find(|d| d.id == *id))
+            .map(|dir| (dir.id, collect_preview_images_standalone(&state, dir)))
+            .collect()
+    };
+    // Directories deleted since the request count as done
+    job.completed.fetch_add(dir_ids.len() - preview_data.len(), Ordering::Relaxed);
+
+    workers::pool(0).install(|| {
+        preview_data.par_iter().for_each(|(dir_id, images)| {
+            if generate_dir_preview_from_paths(*dir_id, images).is_some() {
+                job.generated.fetch_add(1, Ordering::Relaxed);
+            }
+            job.completed.fetch_add(1, Ordering::Relaxed);
+        });
+    });
+    Ok(())
+}
+
+/// Group the perceptual hashes in `scope` at `threshold` into `similarity`,
+/// where the duplicate requests that started the job find them. Progress
+/// counts images compared; `generated` is the groups found. Blocks; run on a
+/// blocking thread.
+pub fn run_similar_job(
+    job: &Job,
+    db: &Mutex<Database>,
+    similarity: &SimilarityCache,
+    scope: &DuplicateScope,
+    threshold: u32,
+) {
+    let result = (|| {
+        let hashes: Vec<(i64, u64)> = {
+            let db = db.lock().map_err(|_| anyhow::anyhow!("Database lock poisoned"))?;
+            db.get_perceptual_hashes_in(scope)?
+                .into_iter()
+                .map(|(id, h)| (id, h as u64))
+                .collect()
+        };
+        job.total.store(hashes.len(), Ordering::Relaxed);
+        let groups = similarity.groups_counting(&hashes, threshold, &job.completed);
+        job.generated.store(groups.len(), Ordering::Relaxed);
+        Ok(())
+    })();
+    finish(job, result);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_forgets_oldest_finished_job() {
+        let registry = JobRegistry::default();
+        let start = |total| registry.start(DIR_PREVIEW, String::new(), total);
+        let first = start(1);
+        first.done.store(true, Ordering::Relaxed);
+        let running = start(1);
+        for _ in 2..MAX_JOBS {
+            start(0).done.store(true, Ordering::Relaxed);
+        }
+        assert!(registry.get(first.id).is_some());
+
+        let last = start(0);
+        assert!(registry.get(first.id).is_none());
+        assert!(registry.get(running.id).is_some());
+        assert_eq!(registry.get(last.id).unwrap().snapshot().id, last.id);
+
+        let (joined, started) = registry.join_or_start(DIR_PREVIEW, String::new());
+        assert_eq!((joined.id, started), (running.id, false));
+        let (similar, started) = registry.join_or_start(SIMILAR_DUPLICATES, "8".to_string());
+        assert!(started && similar.id > last.id);
+    }
+}
@@ -0,0 +1,435 @@
+//! Prometheus metrics for `/metrics` (text exposition format 0.0.4).
+//!
+//! A single process-wide registry collects request counts/latencies, DB lock
+//! and query timings, and thumbnail cache hits. Library size and daemon job
+//! gauges are computed at scrape time.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::daemon::{DaemonSnapshot, JobStatus};
+use crate::db::Database;
+
+/// Latency buckets in seconds (upper bounds)
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// The process-wide metrics registry
+pub fn global() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::default)
+}
+
+/// Cumulative histogram with fixed [`BUCKETS`]
+#[derive(Default)]
+pub struct Histogram {
+    counts: [AtomicU64; BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    pub fn observe(&self, d: Duration) {
+        let secs = d.as_secs_f64();
+        for (bound, count) in BUCKETS.iter().zip(&self.counts) {
+            if secs <= *bound {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(d.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        for (bound, count) in BUCKETS.iter().zip(&self.counts) {
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{}{}le=\"{}\"}} {}",
+                name,
+                labels,
+                sep,
+                bound,
+                count.load(Ordering::Relaxed)
+            );
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}{}le=\"+Inf\"}} {}",
+            name, labels, sep, count
+        );
+        let braces = |l: &str| {
+            if l.is_empty() {
+                String::new()
+            } else {
+                format!("{{{}}}", l)
+            }
+        };
+        let _ = writeln!(
+            out,
+            "{}_sum{} {}",
+            name,
+            braces(labels),
+            self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+        );
+        let _ = writeln!(out, "{}_count{} {}", name, braces(labels), count);
+    }
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    /// (route, method, status) -> count
+    requests: Mutex<BTreeMap<(String, String, u16), u64>>,
+    /// route -> latency
+    request_duration: Mutex<BTreeMap<String, Histogram>>,
+    /// (kind, hit) -> count, kind is "thumb" / "preview" / "dir_preview"
+    cache_lookups: Mutex<BTreeMap<(&'static str, bool), u64>>,
+    pub db_lock_wait: Histogram,
+    pub db_query: Histogram,
+}
+
+impl Metrics {
+    pub fn observe_request(&self, route: &str, method: &str, status: u16, elapsed: Duration) {
+        if let Ok(mut requests) = self.requests.lock() {
+            *requests
+                .entry((route.to_string(), method.to_string(), status))
+                .or_insert(0) += 1;
+        }
+        if let Ok(mut durations) = self.request_duration.lock() {
+            durations
+                .entry(route.to_string())
+                .or_default()
+                .observe(elapsed);
+        }
+    }
+
+    /// Record a thumbnail cache lookup: `hit` = served a pre-generated image
+    pub fn observe_cache(&self, kind: &'static str, hit: bool) {
+        if let Ok(mut lookups) = self.cache_lookups.lock() {
+            *lookups.entry((kind, hit)).or_insert(0) += 1;
+        }
+    }
+
+    pub fn render(&self, library: &LibraryStats, daemon: Option<&DaemonSnapshot>) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# HELP picman_build_info Build information");
+        let _ = writeln!(out, "# TYPE picman_build_info gauge");
+        let _ = writeln!(
+            out,
+            "picman_build_info{{version=\"{}\"}} 1",
+            env!("CARGO_PKG_VERSION")
+        );
+
+        let _ = writeln!(
+            out,
+            "# HELP picman_http_requests_total HTTP requests by route, method, and status"
+        );
+        let _ = writeln!(out, "# TYPE picman_http_requests_total counter");
+        if let Ok(requests) = self.requests.lock() {
+            for ((route, method, status), count) in requests.iter() {
+                let _ = writeln!(
+                    out,
+                    "picman_http_requests_total{{route=\"{}\",method=\"{}\",status=\"{}\"}} {}",
+                    escape(route),
+                    escape(method),
+                    status,
+                    count
+                );
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP picman_http_request_duration_seconds HTTP request latency by route"
+        );
+        let _ = writeln!(out, "# TYPE picman_http_request_duration_seconds histogram");
+        if let Ok(durations) = self.request_duration.lock() {
+            for (route, histogram) in durations.iter() {
+                histogram.render(
+                    &mut out,
+                    "picman_http_request_duration_seconds",
+                    &format!("route=\"{}\"", escape(route)),
+                );
+            }
+        }
+
+        let _ = writeln!(out, "# HELP picman_thumbnail_cache_lookups_total Thumbnail cache lookups by kind and result");
+        let _ = writeln!(out, "# TYPE picman_thumbnail_cache_lookups_total counter");
+        if let Ok(lookups) = self.cache_lookups.lock() {
+            for ((kind, hit), count) in lookups.iter() {
+                let _ = writeln!(
+                    out,
+                    "picman_thumbnail_cache_lookups_total{{kind=\"{}\",result=\"{}\"}} {}",
+                    kind,
+                    if *hit { "hit" } else { "miss" },
+                    count
+                );
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP picman_db_lock_wait_seconds Time spent waiting for the database lock"
+        );
+        let _ = writeln!(out, "# TYPE picman_db_lock_wait_seconds histogram");
+        self.db_lock_wait
+            .render(&mut out, "picman_db_lock_wait_seconds", "");
+
+        let _ = writeln!(
+            out,
+            "# HELP picman_db_query_duration_seconds Time spent running database work"
+        );
+        let _ = writeln!(out, "# TYPE picman_db_query_duration_seconds histogram");
+        self.db_query
+            .render(&mut out, "picman_db_query_duration_seconds", "");
+
+        for (name, help, value) in [
+            (
+                "picman_library_files",
+                "Files in the library",
+                library.files,
+            ),
+            (
+                "picman_library_directories",
+                "Directories in the library",
+                library.directories,
+            ),
+            (
+                "picman_library_bytes",
+                "Total size of library files",
+                library.bytes,
+            ),
+            (
+                "picman_library_files_without_hash",
+                "Files not yet hashed",
+                library.files_without_hash,
+            ),
+        ] {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} gauge", name);
+            let _ = writeln!(out, "{} {}", name, value);
+        }
+
+        if let Some(daemon) = daemon {
+            render_jobs(&mut out, daemon);
+        }
+
+        out
+    }
+}
+
+/// Library size gauges, read at scrape time from the trigger-maintained
+/// `library_stats` row (see `Database::library_summary`)
+pub struct LibraryStats {
+    pub files: i64,
+    pub directories: i64,
+    pub bytes: i64,
+    pub files_without_hash: i64,
+}
+
+impl LibraryStats {
+    pub fn query(db: &Database) -> anyhow::Result<Self> {
+        let summary = db.library_summary()?;
+        Ok(Self {
+            files: summary.files,
+            directories: summary.directories,
+            bytes: summary.bytes,
+            files_without_hash: summary.without_hash,
+        })
+    }
+}
+
+fn render_jobs(out: &mut String, daemon: &DaemonSnapshot) {
+    let mut jobs: Vec<(&str, &JobStatus)> = vec![
+        ("watcher", &daemon.watcher),
+        ("thumbnails", &daemon.thumbnails),
+        ("maintenance", &daemon.maintenance),
+    ];
+    jobs.extend(
+        daemon
+            .tasks
+            .iter()
+            .map(|(name, status)| (name.as_str(), status)),
+    );
+
+    let _ = writeln!(
+        out,
+        "# HELP picman_daemon_uptime_seconds Seconds since the daemon started"
+    );
+    let _ = writeln!(out, "# TYPE picman_daemon_uptime_seconds gauge");
+    let _ = writeln!(out, "picman_daemon_uptime_seconds {}", daemon.uptime_secs);
+
+    type Field = fn(&JobStatus) -> Option<f64>;
+    let fields: [(&str, &str, &str, Field); 6] = [
+        (
+            "picman_job_running",
+            "gauge",
+            "1 while the job is running",
+            |s| Some(if s.running { 1.0 } else { 0.0 }),
+        ),
+        (
+            "picman_job_runs_total",
+            "counter",
+            "Completed job runs",
+            |s| Some(s.runs as f64),
+        ),
+        (
+            "picman_job_last_success",
+            "gauge",
+            "1 if the last run succeeded",
+            |s| {
+                s.last_run
+                    .map(|_| if s.last_error.is_none() { 1.0 } else { 0.0 })
+            },
+        ),
+        (
+            "picman_job_last_run_timestamp_seconds",
+            "gauge",
+            "When the last run finished",
+            |s| s.last_run.map(|t| t as f64),
+        ),
+        (
+            "picman_job_last_duration_seconds",
+            "gauge",
+            "Duration of the last run",
+            |s| s.last_duration_ms.map(|ms| ms as f64 / 1000.0),
+        ),
+        (
+            "picman_job_next_run_timestamp_seconds",
+            "gauge",
+            "When the job is next scheduled",
+            |s| s.next_run.map(|t| t as f64),
+        ),
+    ];
+
+    for (name, kind, help, field) in fields {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        for (job, status) in &jobs {
+            if let Some(value) = field(status) {
+                let _ = writeln!(out, "{}{{job=\"{}\"}} {}", name, escape(job), value);
+            }
+        }
+    }
+}
+
+/// Middleware recording request count and latency per matched route.
+/// Installed with `route_layer`, so unmatched (asset fallback) requests are not counted.
+pub async fn track_requests(req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().clone();
+
+    let start = Instant::now();
+    let response = next.run(req).await;
+    global().observe_request(
+        &route,
+        method.as_str(),
+        response.status().as_u16(),
+        start.elapsed(),
+    );
+
+    response
+}
+
+/// Escape a label value per the exposition format
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_library() -> LibraryStats {
+        LibraryStats {
+            files: 0,
+            directories: 0,
+            bytes: 0,
+            files_without_hash: 0,
+        }
+    }
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let h = Histogram::default();
+        h.observe(Duration::from_millis(3));
+        h.observe(Duration::from_millis(70));
+        h.observe(Duration::from_secs(30));
+
+        let mut out = String::new();
+        h.render(&mut out, "t", "route=\"/x\"");
+        assert!(out.contains("t_bucket{route=\"/x\",le=\"0.005\"} 1\n"));
+        assert!(out.contains("t_bucket{route=\"/x\",le=\"0.1\"} 2\n"));
+        assert!(out.contains("t_bucket{route=\"/x\",le=\"10\"} 2\n"));
+        assert!(out.contains("t_bucket{route=\"/x\",le=\"+Inf\"} 3\n"));
+        assert!(out.contains("t_count{route=\"/x\"} 3\n"));
+        assert!(out.contains("t_sum{route=\"/x\"} 30.073\n"));
+    }
+
+    #[test]
+    fn test_render_requests_and_cache() {
+        let m = Metrics::default();
+        m.observe_request("/api/tags", "GET", 200, Duration::from_millis(2));
+        m.observe_request("/api/tags", "GET", 200, Duration::from_millis(4));
+        m.observe_cache("thumb", true);
+        m.observe_cache("thumb", false);
+        m.observe_cache("thumb", false);
+
+        let out = m.render(&empty_library(), None);
+        assert!(out.contains(
+            "picman_http_requests_total{route=\"/api/tags\",method=\"GET\",status=\"200\"} 2\n"
+        ));
+        assert!(out
+            .contains("picman_thumbnail_cache_lookups_total{kind=\"thumb\",result=\"miss\"} 2\n"));
+        assert!(out.contains("picman_library_files 0\n"));
+        assert!(!out.contains("picman_job_running"));
+    }
+
+    #[test]
+    fn test_render_daemon_jobs() {
+        let status = crate::daemon::DaemonStatus::new();
+        let out = global().render(&empty_library(), Some(&status.snapshot()));
+        assert!(out.contains("picman_job_running{job=\"watcher\"} 0\n"));
+        assert!(out.contains("picman_job_runs_total{job=\"maintenance\"} 0\n"));
+        // No run yet: no last-success sample
+        assert!(!out.contains("picman_job_last_success{"));
+    }
+
+    #[test]
+    fn test_library_stats_query() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("a", None, None).unwrap();
+        db.insert_file(dir, "x.jpg", 100, 0, None).unwrap();
+        db.insert_file(dir, "y.jpg", 50, 0, None).unwrap();
+
+        let stats = LibraryStats::query(&db).unwrap();
+        assert_eq!(stats.files, 2);
+        assert_eq!(stats.directories, 1);
+        assert_eq!(stats.bytes, 150);
+        assert_eq!(stats.files_without_hash, 2);
+    }
+
+    #[test]
+    fn test_escape_label_values() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -1,15 +1,33 @@
 mod handlers;
+mod jobs;
+mod metrics;
 mod models;
+mod openapi;
+pub(crate) mod pidfile;
+pub mod safe_path;
 
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::Result;
+use axum::http::{header, HeaderMap, StatusCode, Uri};
+use axum::response::{IntoResponse, Redirect};
 use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
 use rust_embed::Embed;
 use tokio::net::TcpListener;
+use tokio::sync::watch;
 
+use crate::daemon::DaemonStatus;
 use crate::db::Database;
+use crate::perceptual_hash::{group_by_similarity, SimilarityIndex, MAX_INDEXED_THRESHOLD};
+
+use jobs::JobRegistry;
+use models::FolderSuperGroup;
 
 #[derive(Embed)]
 #[folder = "src/serve/assets/"]
@@ -18,9 +36,171 @@ struct Assets;
 pub struct AppState {
     pub db: Arc<Mutex<Database>>,
     pub library_path: PathBuf,
+    /// Background job status when running under `picman daemon`
+    pub daemon: Option<Arc<DaemonStatus>>,
+    /// Close perceptual hash pairs, kept between similar-duplicate requests
+    pub similarity: Arc<SimilarityCache>,
+    /// Folder super-groups of the duplicate listings, kept between pages
+    pub super_groups: Arc<SuperGroupCache>,
+    /// Background jobs started through the API, e.g. directory previews
+    pub jobs: JobRegistry,
+}
+
+impl AppState {
+    pub fn new(db: Database, library_path: PathBuf) -> Self {
+        Self {
+            db: Arc::new(Mutex::new(db)),
+            library_path,
+            daemon: None,
+            similarity: Arc::default(),
+            super_groups: Arc::default(),
+            jobs: JobRegistry::default(),
+        }
+    }
+}
+
+/// Hash lists up to this long are grouped within the request; longer ones
+/// in a background job the client follows
+const INLINE_SIMILARITY_HASHES: usize = 5_000;
+
+/// What [`SimilarityCache`] holds, all computed from `hashes`
+#[derive(Default)]
+struct SimilarityState {
+    hashes: Vec<(i64, u64)>,
+    index: Option<Arc<SimilarityIndex>>,
+    groups: HashMap<u32, Arc<Vec<Vec<i64>>>>,
+}
+
+/// The last [`SimilarityIndex`] built and the groups found per threshold, so
+/// moving the similarity threshold in the duplicates view doesn't compare
+/// every pair of hashes again. Everything is dropped once a perceptual hash
+/// changes (or the scope does).
+pub struct SimilarityCache {
+    state: Mutex<SimilarityState>,
+    /// Hash lists longer than this are left to [`jobs::run_similar_job`]
+    inline_limit: usize,
+}
+
+impl Default for SimilarityCache {
+    fn default() -> Self {
+        Self::with_inline_limit(INLINE_SIMILARITY_HASHES)
+    }
+}
+
+impl SimilarityCache {
+    pub fn with_inline_limit(inline_limit: usize) -> Self {
+        Self { state: Mutex::default(), inline_limit }
+    }
+
+    /// Whether grouping `hashes` at `threshold` is quick enough to do within
+    /// a request: few hashes, or the groups are already known
+    pub fn is_quick(&self, hashes: &[(i64, u64)], threshold: u32) -> bool {
+        hashes.len() <= self.inline_limit || self.cached(hashes, threshold).is_some()
+    }
+
+    fn cached(&self, hashes: &[(i64, u64)], threshold: u32) -> Option<Arc<Vec<Vec<i64>>>> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.hashes != hashes {
+            return None;
+        }
+        state.groups.get(&threshold).cloned()
+    }
+
+    /// Groups of similar files at `threshold`
+    pub fn groups(&self, hashes: &[(i64, u64)], threshold: u32) -> Arc<Vec<Vec<i64>>> {
+        self.groups_counting(hashes, threshold, &AtomicUsize::new(0))
+    }
+
+    /// Like [`SimilarityCache::groups`], adding one to `compared` per hash
+    /// compared when the index has to be built. The index is rebuilt when
+    /// any perceptual hash changed since it was built; thresholds wider than
+    /// it covers compare all pairs directly. The lock isn't held while
+    /// comparing, so requests answered from the cache don't wait.
+    pub fn groups_counting(&self, hashes: &[(i64, u64)], threshold: u32, compared: &AtomicUsize) -> Arc<Vec<Vec<i64>>> {
+        if let Some(groups) = self.cached(hashes, threshold) {
+            return groups;
+        }
+        let (groups, index) = if threshold > MAX_INDEXED_THRESHOLD {
+            (group_by_similarity(hashes, threshold), None)
+        } else {
+            let cached = {
+                let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                state.index.clone().filter(|_| state.hashes == hashes)
+            };
+            let index = cached.unwrap_or_else(|| Arc::new(SimilarityIndex::build_counting(hashes.to_vec(), compared)));
+            (index.groups(threshold), Some(index))
+        };
+        compared.store(hashes.len(), Ordering::Relaxed);
+
+        let groups = Arc::new(groups);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.hashes != hashes {
+            *state = SimilarityState { hashes: hashes.to_vec(), ..Default::default() };
+        }
+        if index.is_some() {
+            state.index = index;
+        }
+        state.groups.insert(threshold, groups.clone());
+        groups
+    }
 }
 
-pub fn run_serve(library_path: &std::path::Path, port: u16) -> Result<()> {
+/// Which listing super-groups were computed for: match type, similarity
+/// threshold (0 for exact) and keep policy
+type SuperGroupKey = (String, u32, String);
+
+/// Super-groups and the groups (file IDs) they were computed from
+type SuperGroupEntry = (Vec<Vec<i64>>, Arc<Vec<FolderSuperGroup>>);
+
+/// Folder super-groups per duplicate listing, reused until its groups
+/// change, so paging through tens of thousands of groups doesn't pair up
+/// folders again for each page
+#[derive(Default)]
+pub struct SuperGroupCache(Mutex<HashMap<SuperGroupKey, SuperGroupEntry>>);
+
+impl SuperGroupCache {
+    pub fn get_or_compute(
+        &self,
+        key: SuperGroupKey,
+        groups: Vec<Vec<i64>>,
+        compute: impl FnOnce() -> Result<Vec<FolderSuperGroup>>,
+    ) -> Result<Arc<Vec<FolderSuperGroup>>> {
+        if let Some((cached_groups, super_groups)) = self.0.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            if *cached_groups == groups {
+                return Ok(super_groups.clone());
+            }
+        }
+        let super_groups = Arc::new(compute()?);
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, (groups, super_groups.clone()));
+        Ok(super_groups)
+    }
+}
+
+/// Options for `picman serve`
+pub struct ServeOptions {
+    pub port: u16,
+    pub tls: Option<TlsOptions>,
+    /// PID file location (defaults to `<library>/.picman-serve.pid`)
+    pub pid_file: Option<PathBuf>,
+    /// Job status to report from `/api/health` (set by `picman daemon`)
+    pub daemon: Option<Arc<DaemonStatus>>,
+}
+
+/// HTTPS configuration. When `redirect_port` is set, a plain HTTP listener
+/// on that port redirects every request to the HTTPS port.
+pub struct TlsOptions {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    pub redirect_port: Option<u16>,
+}
+
+/// How long in-flight requests get to finish after a shutdown signal
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+pub fn run_serve(library_path: &std::path::Path, options: ServeOptions) -> Result<()> {
     let db_path = library_path.join(".picman.db");
     let db = Database::open(&db_path)?;
 
@@ -28,27 +208,177 @@ pub fn run_serve(library_path: &std::path::Path, port: u16) -> Result<()> {
         .canonicalize()
         .map_err(|e| anyhow::anyhow!("Failed to resolve library path: {}", e))?;
 
-    let state = Arc::new(AppState {
-        db: Arc::new(Mutex::new(db)),
-        library_path,
-    });
+    let pid_path = options
+        .pid_file
+        .clone()
+        .unwrap_or_else(|| pidfile::default_pid_path(&library_path));
+    let _pid_file = pidfile::PidFile::create(&pid_path)?;
+
+    let mut state = AppState::new(db, library_path);
+    state.daemon = options.daemon;
+    let state = Arc::new(state);
 
+    let port = options.port;
     let rt = tokio::runtime::Runtime::new()?;
-    rt.block_on(async move {
-        let app = build_router(state);
+    rt.block_on({
+        let state = state.clone();
+        async move {
+            let app = build_router(state);
+
+            // Fan a single Ctrl+C / SIGTERM out to every listener
+            let (shutdown_tx, shutdown_rx) = watch::channel(false);
+            tokio::spawn(async move {
+                shutdown_signal().await;
+                println!("Shutting down, waiting for in-flight requests...");
+                let _ = shutdown_tx.send(true);
+            });
+
+            match options.tls {
+                None => {
+                    let addr = format!("0.0.0.0:{}", port);
+                    println!("Serving library on http://localhost:{}", port);
+                    println!("  Also available on http://0.0.0.0:{}", port);
+                    println!("Press Ctrl+C to stop.");
+
+                    let listener = TcpListener::bind(&addr).await?;
+                    let serve = axum::serve(listener, app)
+                        .with_graceful_shutdown(wait_for_shutdown(shutdown_rx.clone()));
+                    // Bound the drain: long-running requests must not hold shutdown forever
+                    tokio::select! {
+                        result = serve => result?,
+                        _ = async {
+                            wait_for_shutdown(shutdown_rx).await;
+                            tokio::time::sleep(SHUTDOWN_GRACE).await;
+                        } => eprintln!("Grace period elapsed, dropping remaining connections"),
+                    }
+                }
+                Some(tls) => {
+                    let config = RustlsConfig::from_pem_file(&tls.cert, &tls.key)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Failed to load TLS certificate/key: {}", e))?;
+
+                    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+                    println!("Serving library on https://localhost:{}", port);
+                    println!("  Also available on https://0.0.0.0:{}", port);
+
+                    if let Some(redirect_port) = tls.redirect_port {
+                        let redirect_addr = format!("0.0.0.0:{}", redirect_port);
+                        let listener = TcpListener::bind(&redirect_addr).await?;
+                        println!(
+                            "  Redirecting http://0.0.0.0:{} to HTTPS",
+                            redirect_port
+                        );
+                        let rx = shutdown_rx.clone();
+                        tokio::spawn(async move {
+                            let result = axum::serve(listener, build_redirect_router(port))
+                                .with_graceful_shutdown(wait_for_shutdown(rx))
+                                .await;
+                            if let Err(e) = result {
+                                eprintln!("HTTP redirect listener failed: {}", e);
+                            }
+                        });
+                    }
+                    println!("Press Ctrl+C to stop.");
+
+                    let handle = axum_server::Handle::new();
+                    tokio::spawn({
+                        let handle = handle.clone();
+                        async move {
+                            wait_for_shutdown(shutdown_rx).await;
+                            handle.graceful_shutdown(Some(SHUTDOWN_GRACE));
+                        }
+                    });
+
+                    axum_server::bind_rustls(addr, config)
+                        .handle(handle)
+                        .serve(app.into_make_service())
+                        .await?;
+                }
+            }
+
+            Ok::<(), anyhow::Error>(())
+        }
+    })?;
 
-        let addr = format!("0.0.0.0:{}", port);
-        println!("Serving library on http://localhost:{}", port);
-        println!("  Also available on http://0.0.0.0:{}", port);
-        println!("Press Ctrl+C to stop.");
+    // Stop any blocking DB tasks that are still queued, then make sure
+    // no write is mid-flight before the connection is closed.
+    rt.shutdown_timeout(SHUTDOWN_GRACE);
+    drop(
+        state
+            .db
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Database lock poisoned"))?,
+    );
+    println!("Server stopped.");
 
-        let listener = TcpListener::bind(&addr).await?;
-        axum::serve(listener, app).await?;
+    Ok(())
+}
 
-        Ok::<(), anyhow::Error>(())
-    })?;
+/// Stop a server started with `picman serve` by signalling the PID in its pid file.
+pub fn stop_serve(library_path: &std::path::Path, pid_file: Option<&std::path::Path>) -> Result<u32> {
+    let pid_path = match pid_file {
+        Some(p) => p.to_path_buf(),
+        None => pidfile::default_pid_path(&library_path.canonicalize()?),
+    };
+    pidfile::stop(&pid_path, SHUTDOWN_GRACE * 2)
+}
 
-    Ok(())
+/// Resolves when the process receives Ctrl+C or (on Unix) SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Resolves once the shutdown flag has been raised
+async fn wait_for_shutdown(mut rx: watch::Receiver<bool>) {
+    let _ = rx.wait_for(|stop| *stop).await;
+}
+
+/// Router for the plain HTTP listener: redirects everything to HTTPS.
+fn build_redirect_router(https_port: u16) -> Router {
+    Router::new().fallback(move |headers: HeaderMap, uri: Uri| async move {
+        let Some(host) = headers.get(header::HOST).and_then(|h| h.to_str().ok()) else {
+            return StatusCode::BAD_REQUEST.into_response();
+        };
+        let path = uri.path_and_query().map(|pq| pq.as_str()).unwrap_or("/");
+        Redirect::permanent(&https_redirect_url(host, https_port, path)).into_response()
+    })
+}
+
+/// Build the HTTPS URL for a request that arrived on the HTTP listener,
+/// replacing whatever port the Host header carried with the HTTPS port.
+fn https_redirect_url(host: &str, https_port: u16, path_and_query: &str) -> String {
+    let hostname = if let Some(end) = host.strip_prefix('[').and_then(|h| h.find(']')) {
+        // IPv6 literal: keep the brackets, drop any port after them
+        &host[..end + 2]
+    } else {
+        host.split(':').next().unwrap_or(host)
+    };
+
+    if https_port == 443 {
+        format!("https://{}{}", hostname, path_and_query)
+    } else {
+        format!("https://{}:{}{}", hostname, https_port, path_and_query)
+    }
 }
 
 fn build_router(state: Arc<AppState>) -> Router {
@@ -56,12 +386,20 @@ fn build_router(state: Arc<AppState>) -> Router {
 
     Router::new()
         .route("/api/health", get(handlers::health))
+        .route("/metrics", get(handlers::get_metrics))
+        .route("/api/settings", get(handlers::get_settings))
+        .route("/api/version", get(handlers::get_version))
         .route("/api/directories", get(handlers::get_directories))
         .route(
             "/api/duplicates/summary",
             get(handlers::get_duplicates_summary),
         )
         .route("/api/duplicates", get(handlers::get_duplicates))
+        .route("/api/duplicates/folders", get(handlers::get_folder_super_groups))
+        .route(
+            "/api/duplicates/ignore",
+            post(handlers::ignore_duplicates).delete(handlers::unignore_duplicates),
+        )
         .route("/api/duplicates/trash", post(handlers::trash_files))
         .route(
             "/api/duplicates/trash-folder-rule",
@@ -83,12 +421,55 @@ fn build_router(state: Arc<AppState>) -> Router {
             "/api/directories/{id}/tags/{tag_name}",
             delete(handlers::remove_directory_tag),
         )
+        .route(
+            "/api/directories/{id}/preview/generate",
+            post(handlers::generate_directory_preview),
+        )
+        .route(
+            "/api/directories/preview/generate",
+            post(handlers::generate_directory_previews),
+        )
+        .route("/api/jobs/{id}", get(handlers::get_job))
+        .route("/api/jobs/{id}/events", get(handlers::get_job_events))
         .route("/api/tags", get(handlers::get_tags))
+        .route(
+            "/api/tags/aliases",
+            get(handlers::get_tag_aliases).post(handlers::add_tag_alias),
+        )
+        .route(
+            "/api/tags/aliases/{alias}",
+            delete(handlers::remove_tag_alias),
+        )
+        .route("/api/usage", get(handlers::get_usage))
+        .route("/api/stats", get(handlers::get_stats))
+        .route("/api/stats/heatmap", get(handlers::get_stats_heatmap))
         .route("/api/files", get(handlers::get_filtered_files))
+        .route("/api/review-queues", get(handlers::get_review_queues).post(handlers::create_review_queue))
+        .route("/api/review-queues/{id}", get(handlers::get_review_session).delete(handlers::delete_review_queue))
+        .route("/api/review-queues/{id}/position", put(handlers::set_review_position))
+        .route("/api/review-queues/{id}/decisions", post(handlers::record_review_decision))
+        .route("/api/files/next", get(handlers::get_next_file))
+        .route("/api/files/next/batch", get(handlers::get_next_files))
+        .route("/api/recent/viewed", get(handlers::get_recently_viewed))
+        .route("/api/recent/edited", get(handlers::get_recently_edited))
+        .route("/api/files/{id}", get(handlers::get_file).patch(handlers::update_file))
+        .route(
+            "/api/files/{id}/attributes",
+            get(handlers::get_file_attributes),
+        )
+        .route("/api/files/{id}/exif", get(handlers::get_file_exif))
+        .route(
+            "/api/files/{id}/attributes/{key}",
+            put(handlers::set_file_attribute).delete(handlers::remove_file_attribute),
+        )
         .route("/thumb/{file_id}", get(handlers::serve_web_thumbnail))
+        .route("/img/{file_id}", get(handlers::serve_resized_image))
         .route("/preview/{file_id}", get(handlers::serve_preview))
         .route("/dir-preview/{dir_id}", get(handlers::serve_dir_preview))
         .route("/original/{*path}", get(handlers::serve_original))
+        .route("/api/openapi.json", get(openapi::openapi_json))
+        .route("/api/docs", get(openapi::swagger_ui))
+        .route_layer(axum::middleware::from_fn(metrics::track_requests))
         .fallback(get(handlers::serve_embedded_asset))
         .with_state(state)
 }
@@ -97,15 +478,12 @@ fn build_router(state: Arc<AppState>) -> Router {
 mod tests {
     use super::*;
     use axum::body::Body;
-    use axum::http::{Request, StatusCode};
+    use axum::http::Request;
     use tower::ServiceExt;
 
     fn test_state() -> Arc<AppState> {
         let db = Database::open_in_memory().unwrap();
-        Arc::new(AppState {
-            db: Arc::new(Mutex::new(db)),
-            library_path: PathBuf::from("/tmp/test-library"),
-        })
+        Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library")))
     }
 
     #[tokio::test]
@@ -125,87 +503,1202 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_directories_endpoint_empty_db() {
-        let app = build_router(test_state());
+    async fn test_metrics_endpoint() {
+        let app = build_router(test_state());
+
+        // Generate some traffic first so the request counter has a sample
+        let _ = app
+            .clone()
+            .oneshot(Request::builder().uri("/api/tags").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(Request::builder().uri("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain; version=0.0.4"));
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("picman_http_requests_total{route=\"/api/tags\",method=\"GET\",status=\"200\"}"));
+        assert!(text.contains("picman_library_files 0"));
+        assert!(text.contains("picman_db_query_duration_seconds_count"));
+    }
+
+    #[tokio::test]
+    async fn test_openapi_spec_covers_routes() {
+        let app = build_router(test_state());
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/openapi.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let spec: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let paths = spec["paths"].as_object().unwrap();
+
+        for (path, method) in [
+            ("/api/health", "get"),
+            ("/api/settings", "get"),
+            ("/api/version", "get"),
+            ("/metrics", "get"),
+            ("/api/directories", "get"),
+            ("/api/directories/{id}/files", "get"),
+            ("/api/directories/{id}/rating", "put"),
+            ("/api/directories/{id}/tags", "post"),
+            ("/api/directories/{id}/tags/{tag_name}", "delete"),
+            ("/api/directories/{id}/preview/generate", "post"),
+            ("/api/directories/preview/generate", "post"),
+            ("/api/jobs/{id}", "get"),
+            ("/api/jobs/{id}/events", "get"),
+            ("/api/tags", "get"),
+            ("/api/tags/aliases", "get"),
+            ("/api/tags/aliases", "post"),
+            ("/api/tags/aliases/{alias}", "delete"),
+            ("/api/usage", "get"),
+            ("/api/stats", "get"),
+            ("/api/stats/heatmap", "get"),
+            ("/api/files", "get"),
+            ("/api/review-queues", "get"),
+            ("/api/review-queues", "post"),
+            ("/api/review-queues/{id}", "get"),
+            ("/api/review-queues/{id}", "delete"),
+            ("/api/review-queues/{id}/position", "put"),
+            ("/api/review-queues/{id}/decisions", "post"),
+            ("/api/files/next", "get"),
+            ("/api/files/next/batch", "get"),
+            ("/api/recent/viewed", "get"),
+            ("/api/recent/edited", "get"),
+            ("/api/files/{id}", "get"),
+            ("/api/files/{id}", "patch"),
+            ("/api/files/{id}/exif", "get"),
+            ("/api/duplicates/summary", "get"),
+            ("/api/duplicates", "get"),
+            ("/api/duplicates/folders", "get"),
+            ("/api/duplicates/ignore", "post"),
+            ("/api/duplicates/ignore", "delete"),
+            ("/api/duplicates/trash", "post"),
+            ("/api/duplicates/trash-folder-rule", "post"),
+            ("/thumb/{file_id}", "get"),
+            ("/img/{file_id}", "get"),
+            ("/preview/{file_id}", "get"),
+            ("/dir-preview/{dir_id}", "get"),
+            ("/original/{path}", "get"),
+        ] {
+            assert!(
+                paths.get(path).and_then(|p| p.get(method)).is_some(),
+                "{} {} missing from OpenAPI spec",
+                method,
+                path
+            );
+        }
+        assert!(spec["components"]["schemas"]["PaginatedFiles"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_swagger_ui_page() {
+        let app = build_router(test_state());
+        let response = app
+            .oneshot(Request::builder().uri("/api/docs").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("/api/openapi.json"));
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_daemon_jobs() {
+        let mut state = AppState::new(
+            Database::open_in_memory().unwrap(),
+            PathBuf::from("/tmp/test-library"),
+        );
+        state.daemon = Some(Arc::new(DaemonStatus::new()));
+        let app = build_router(Arc::new(state));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/health")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["status"], "ok");
+        assert_eq!(json["daemon"]["watcher"]["runs"], 0);
+        assert!(json["daemon"]["maintenance"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_filtered_files_by_media_type() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("scans", None, None).unwrap();
+        db.insert_file(dir, "photo.jpg", 100, 0, Some("image")).unwrap();
+        db.insert_file(dir, "invoice.pdf", 100, 0, Some("document")).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/files?media_type=document")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["total"], 1);
+        assert_eq!(json["files"][0]["filename"], "invoice.pdf");
+        assert_eq!(json["files"][0]["media_type"], "document");
+    }
+
+    #[tokio::test]
+    async fn test_filtered_files_by_camera() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let fuji = db.insert_file(dir, "fuji.jpg", 100, 0, Some("image")).unwrap();
+        let phone = db.insert_file(dir, "phone.jpg", 100, 0, Some("image")).unwrap();
+        db.set_file_gear(fuji, Some("Fujifilm X-T3"), Some("XF35mmF1.4 R")).unwrap();
+        db.set_file_gear(phone, Some("iPhone 13"), None).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/files?camera=Fujifilm%20X-T3")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["total"], 1);
+        assert_eq!(json["files"][0]["filename"], "fuji.jpg");
+    }
+
+    #[tokio::test]
+    async fn test_filtered_files_by_exposure() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("night", None, None).unwrap();
+        let noisy = db.insert_file(dir, "noisy.jpg", 100, 0, Some("image")).unwrap();
+        let blurry = db.insert_file(dir, "blurry.jpg", 100, 0, Some("image")).unwrap();
+        db.set_file_exposure(noisy, Some(6400), Some(1.4), Some(1.0 / 250.0)).unwrap();
+        db.set_file_exposure(blurry, Some(200), Some(8.0), Some(1.0 / 15.0)).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/files?min_shutter=1/60")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["total"], 1);
+        assert_eq!(json["files"][0]["filename"], "blurry.jpg");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/files?min_shutter=slow")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_filtered_files_by_orientation() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        db.insert_file_with_dimensions(dir, "wide.jpg", 100, 0, Some("image"), Some(6000), Some(4000)).unwrap();
+        db.insert_file_with_dimensions(dir, "tall.jpg", 100, 0, Some("image"), Some(4000), Some(6000)).unwrap();
+        db.insert_file_with_dimensions(dir, "pano.jpg", 100, 0, Some("image"), Some(12000), Some(3000)).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/files?orientation=landscape")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["total"], 1);
+        assert_eq!(json["files"][0]["filename"], "wide.jpg");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/files?orientation=sideways")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_file_print_sizes() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let big = db.insert_file_with_dimensions(dir, "big.jpg", 100, 0, Some("image"), Some(6000), Some(4000)).unwrap();
+        db.insert_file_with_dimensions(dir, "small.jpg", 100, 0, Some("image"), Some(2000), Some(1500)).unwrap();
+        db.insert_file_with_dimensions(dir, "clip.mp4", 100, 0, Some("video"), Some(3840), Some(2160)).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let get = |uri: &str| {
+            let app = app.clone();
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            async move { app.oneshot(request).await.unwrap() }
+        };
+
+        let response = get(&format!("/api/files/{}", big)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["filename"], "big.jpg");
+        assert_eq!(json["print_sizes"][0]["dpi"], 300);
+        assert_eq!(json["print_sizes"][0]["width_inches"], 20.0);
+        assert_eq!(json["print_sizes"][2]["dpi"], 150);
+        assert_eq!(json["print_sizes"][2]["height_inches"], 4000.0 / 150.0);
+        assert_eq!(get("/api/files/9999").await.status(), StatusCode::NOT_FOUND);
+
+        // 8x10 at 300 dpi needs 2400x3000; the video is big enough but not printable
+        let json = body_json(get("/api/files?print_size=8x10").await).await;
+        assert_eq!(json["total"], 1);
+        assert_eq!(json["files"][0]["filename"], "big.jpg");
+        let json = body_json(get("/api/files?print_size=8x10&print_dpi=150").await).await;
+        assert_eq!(json["total"], 2);
+        assert_eq!(get("/api/files?print_size=huge").await.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_filtered_files_by_preset() {
+        use crate::tui::dialogs::{FilterCriteria, RatingFilter};
+
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let keep = db.insert_file(dir, "keep.jpg", 100, 0, Some("image")).unwrap();
+        let low = db.insert_file(dir, "low.jpg", 100, 0, Some("image")).unwrap();
+        db.set_file_rating(keep, Some(5.0)).unwrap();
+        db.set_file_rating(low, Some(2.0)).unwrap();
+        db.add_file_tag(keep, "beach").unwrap();
+        db.add_file_tag(low, "beach").unwrap();
+        db.save_filter_preset(
+            "best beach",
+            &FilterCriteria {
+                rating: RatingFilter::MinRating(4.0),
+                tags: vec!["beach".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/files?preset=best%20beach")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["total"], 1);
+        assert_eq!(json["files"][0]["filename"], "keep.jpg");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/files?preset=missing")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_next_unrated_file_in_review_order() {
+        let db = Database::open_in_memory().unwrap();
+        let a = db.insert_directory("a", None, None).unwrap();
+        let b = db.insert_directory("b", None, None).unwrap();
+        let first = db.insert_file(b, "1.jpg", 100, 0, Some("image")).unwrap();
+        let rated = db.insert_file(a, "2.jpg", 100, 0, Some("image")).unwrap();
+        let start = db.insert_file(a, "1.jpg", 100, 0, Some("image")).unwrap();
+        db.set_file_rating(rated, Some(3.0)).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let get = |uri: String| {
+            let app = app.clone();
+            async move {
+                app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // Ordered by path then filename, skipping the rated file
+        let json = body_json(get("/api/files/next".to_string()).await).await;
+        assert_eq!(json["file"]["id"], start);
+        assert_eq!(json["remaining"], 1);
+
+        let json = body_json(get(format!("/api/files/next?after={}", start)).await).await;
+        assert_eq!(json["file"]["id"], first);
+        assert_eq!(json["remaining"], 0);
+
+        let json = body_json(get(format!("/api/files/next?after={}", first)).await).await;
+        assert!(json["file"].is_null());
+
+        // The batch variant prefetches several, and `all` includes rated files
+        let json = body_json(get("/api/files/next/batch?filter=all&limit=2".to_string()).await).await;
+        let ids: Vec<i64> = json["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(ids, vec![start, rated]);
+        assert_eq!(json["remaining"], 1);
+
+        assert_eq!(get("/api/files/next?after=999".to_string()).await.status(), StatusCode::NOT_FOUND);
+        assert_eq!(get("/api/files/next?filter=bogus".to_string()).await.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_next_file_leaves_out_hidden_directories() {
+        let db = Database::open_in_memory().unwrap();
+        let a = db.insert_directory("a", None, None).unwrap();
+        let b = db.insert_directory("b", None, None).unwrap();
+        let below = db.insert_directory("b/raw", Some(b), None).unwrap();
+        let shown = db.insert_file(a, "1.jpg", 100, 0, Some("image")).unwrap();
+        let hidden = db.insert_file(b, "1.jpg", 100, 0, Some("image")).unwrap();
+        db.insert_file(below, "1.jpg", 100, 0, Some("image")).unwrap();
+        db.hide_directory(b, 0).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let get = |uri: &str| {
+            let app = app.clone();
+            let uri = uri.to_string();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                body_json(response).await
+            }
+        };
+
+        let json = get("/api/files/next/batch").await;
+        let ids: Vec<i64> = json["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(ids, vec![shown]);
+        assert_eq!(json["remaining"], 0);
+
+        let json = get(&format!("/api/files/next?after={}&include_hidden=true", shown)).await;
+        assert_eq!(json["file"]["id"], hidden);
+        assert_eq!(json["remaining"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_review_queue_session() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("2024", None, None).unwrap();
+        let other = db.insert_directory("2023", None, None).unwrap();
+        let a = db.insert_file(dir, "a.jpg", 100, 0, Some("image")).unwrap();
+        let b = db.insert_file(dir, "b.jpg", 100, 0, Some("image")).unwrap();
+        let c = db.insert_file(dir, "c.jpg", 100, 0, Some("image")).unwrap();
+        let rated = db.insert_file(dir, "d.jpg", 100, 0, Some("image")).unwrap();
+        db.insert_file(other, "old.jpg", 100, 0, Some("image")).unwrap();
+        db.set_file_rating(rated, Some(3.0)).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+        let send = |method: &str, uri: String, body: &str| {
+            let app = app.clone();
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            async move { app.oneshot(request).await.unwrap() }
+        };
+
+        let create = r#"{"name": "2024 unrated", "unrated": true, "dir": "2024"}"#;
+        let response = send("POST", "/api/review-queues".to_string(), create).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let json = body_json(response).await;
+        assert_eq!(json["total"], 3);
+        assert_eq!(json["filter"], "unrated, in 2024/");
+        let id = json["id"].as_i64().unwrap();
+        assert_eq!(send("POST", "/api/review-queues".to_string(), create).await.status(), StatusCode::CONFLICT);
+        let none = r#"{"name": "none", "tag": "missing"}"#;
+        assert_eq!(send("POST", "/api/review-queues".to_string(), none).await.status(), StatusCode::BAD_REQUEST);
+
+        let json = body_json(send("GET", format!("/api/review-queues/{}?limit=1", id), "").await).await;
+        assert_eq!(json["file"]["id"], a);
+        assert_eq!(json["upcoming"].as_array().unwrap().len(), 1);
+
+        // Deciding moves on; the session answers with the next file
+        let decide = |file: i64, decision: &str| format!(r#"{{"file_id": {}, "decision": "{}"}}"#, file, decision);
+        let uri = format!("/api/review-queues/{}/decisions", id);
+        let json = body_json(send("POST", uri.clone(), &decide(a, "rated")).await).await;
+        assert_eq!(json["file"]["id"], b);
+        let json = body_json(send("POST", uri.clone(), &decide(b, "skipped")).await).await;
+        assert_eq!(json["file"]["id"], c);
+        assert_eq!(json["queue"]["decided"], 2);
+        assert_eq!(json["queue"]["position"], 2);
+        assert_eq!(send("POST", uri.clone(), &decide(c, "maybe")).await.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(send("POST", uri.clone(), &decide(rated, "rated")).await.status(), StatusCode::NOT_FOUND);
+
+        // Another session resumes there, and can go back
+        let json = body_json(send("GET", "/api/review-queues".to_string(), "").await).await;
+        assert_eq!(json[0]["position"], 2);
+        let position = format!("/api/review-queues/{}/position", id);
+        let json = body_json(send("PUT", position, &format!(r#"{{"file_id": {}}}"#, a)).await).await;
+        assert_eq!(json["file"]["id"], a);
+        assert_eq!(json["file"]["decision"], "rated");
+
+        let json = body_json(send("POST", uri, &decide(c, "rated")).await).await;
+        assert!(json["file"].is_null());
+        assert_eq!(json["queue"]["finished"], true);
+
+        let queue = format!("/api/review-queues/{}", id);
+        assert_eq!(send("DELETE", queue.clone(), "").await.status(), StatusCode::NO_CONTENT);
+        assert_eq!(send("GET", queue, "").await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_resized_image_checks_size_before_lookup() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let file = db.insert_file(dir, "a.jpg", 100, 0, Some("image")).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let cases = [
+            (format!("/img/{}", file), StatusCode::BAD_REQUEST),
+            (format!("/img/{}?w=0", file), StatusCode::BAD_REQUEST),
+            (format!("/img/{}?w=5000", file), StatusCode::BAD_REQUEST),
+            (format!("/img/{}?w=400&fit=stretch", file), StatusCode::BAD_REQUEST),
+            (format!("/img/{}?w=400&fit=cover", file), StatusCode::BAD_REQUEST),
+            ("/img/999?w=400".to_string(), StatusCode::NOT_FOUND),
+            // Known to the database but missing on disk
+            (format!("/img/{}?w=400&h=300&fit=cover", file), StatusCode::NOT_FOUND),
+        ];
+        for (uri, expected) in cases {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resized_image_fails_loudly_on_undecodable_file() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join("trip")).unwrap();
+        std::fs::write(temp.path().join("trip/broken.jpg"), "not a jpeg").unwrap();
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let file = db.insert_file(dir, "broken.jpg", 10, 0, Some("image")).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, temp.path().to_path_buf())));
+
+        let response = app
+            .oneshot(Request::builder().uri(format!("/img/{}?w=400", file)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_recent_files_after_preview_and_edit() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let viewed = db.insert_file(dir, "viewed.jpg", 100, 0, Some("image")).unwrap();
+        let edited = db.insert_file(dir, "edited.jpg", 100, 0, Some("image")).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        // The preview itself is missing, but the request still counts as a view
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/preview/{}", viewed))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/api/files/{}/attributes/client", edited))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"value": "Hongdan"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        for (uri, filename) in [("/api/recent/viewed", "viewed.jpg"), ("/api/recent/edited", "edited.jpg")] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let json = body_json(response).await;
+            assert_eq!(json.as_array().unwrap().len(), 1, "{}", uri);
+            assert_eq!(json[0]["filename"], filename);
+            assert_eq!(json[0]["directory_path"], "trip");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_directory_files_fold_paired_siblings() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("iphone", None, None).unwrap();
+        db.insert_file(dir, "IMG_1.HEIC", 100, 0, Some("image")).unwrap();
+        db.insert_file(dir, "IMG_1.MOV", 100, 0, Some("video")).unwrap();
+        let still = db.insert_file(dir, "IMG_2.HEIC", 100, 0, Some("image")).unwrap();
+        db.rebuild_directory_pairs(dir).unwrap();
+        db.set_file_duration(still, Some(2.5)).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/directories/{}/files", dir))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["total"], 2);
+        assert_eq!(json["files"][0]["filename"], "IMG_1.HEIC");
+        assert_eq!(json["files"][0]["companions"], serde_json::json!(["MOV"]));
+        assert_eq!(json["files"][1]["companions"], serde_json::json!([]));
+        assert!(json["files"][0]["duration"].is_null());
+        assert_eq!(json["files"][1]["duration"], 2.5);
+    }
+
+    #[tokio::test]
+    async fn test_directory_files_collapse_stacks() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("burst", None, None).unwrap();
+        let a = db.insert_file(dir, "a.jpg", 100, 0, Some("image")).unwrap();
+        let b = db.insert_file(dir, "b.jpg", 100, 0, Some("image")).unwrap();
+        db.insert_file(dir, "c.jpg", 100, 0, Some("image")).unwrap();
+        let stack = db.create_stack(&[a, b]).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/directories/{}/files", dir))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let json = body_json(response).await;
+        assert_eq!(json["total"], 2);
+        assert_eq!(json["files"][0]["filename"], "a.jpg");
+        assert_eq!(json["files"][0]["stack_size"], 2);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/directories/{}/files?expand_stacks={}", dir, stack))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let json = body_json(response).await;
+        assert_eq!(json["total"], 3);
+        assert_eq!(json["files"][1]["stack_id"], stack);
+        assert_eq!(json["files"][1]["stack_size"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_directories_endpoint_empty_db() {
+        let app = build_router(test_state());
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/directories")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_directories_pinned_filter() {
+        let db = Database::open_in_memory().unwrap();
+        db.insert_directory("photos", None, None).unwrap();
+        let videos = db.insert_directory("videos", None, None).unwrap();
+        db.pin_directory(videos, 100).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/directories?pinned=true")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["path"], "videos");
+        assert_eq!(json[0]["pinned"], true);
+    }
+
+    #[tokio::test]
+    async fn test_directories_leave_out_hidden() {
+        let db = Database::open_in_memory().unwrap();
+        db.insert_directory("photos", None, None).unwrap();
+        let exports = db.insert_directory("exports", None, None).unwrap();
+        db.insert_directory("exports/2019", Some(exports), None).unwrap();
+        db.hide_directory(exports, 100).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/api/directories").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let json = body_json(response).await;
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["path"], "photos");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/directories?include_hidden=true")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let json = body_json(response).await;
+        assert_eq!(json.as_array().unwrap().len(), 3);
+        let exports = json.as_array().unwrap().iter().find(|d| d["path"] == "exports").unwrap();
+        assert_eq!(exports["hidden"], true);
+    }
+
+    #[tokio::test]
+    async fn test_tags_endpoint_empty_db() {
+        let app = build_router(test_state());
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/tags")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_directory_files_not_found() {
+        let app = build_router(test_state());
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/directories/999/files")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // Should return 200 with empty list (directory doesn't exist but query still works)
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_embedded_asset_fallback() {
+        let app = build_router(test_state());
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // Should return 200 if index.html exists, 404 otherwise
+        let status = response.status();
+        assert!(status == StatusCode::OK || status == StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_version_matches_served_page() {
+        let app = build_router(test_state());
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/api/version").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        let hash = json["asset_hash"].as_str().unwrap().to_string();
+        assert_eq!(hash.len(), 16);
+
+        // Every route that falls back to index.html carries the same hash
+        let response = app
+            .oneshot(Request::builder().uri("/some/route").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let html = String::from_utf8_lossy(&body);
+        assert!(html.contains(&format!("content=\"{}\"", hash)));
+        assert!(!html.contains("{{ASSET_HASH}}"));
+    }
+
+    #[tokio::test]
+    async fn test_original_rejects_traversal() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(tmp_dir.path().join("photo.jpg"), b"jpg").unwrap();
+
+        let state = Arc::new(AppState::new(
+            Database::open_in_memory().unwrap(),
+            tmp_dir.path().to_path_buf(),
+        ));
+
+        let app = build_router(state.clone());
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/original/..%2F..%2Fetc%2Fpasswd")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let app = build_router(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/original/photo.jpg")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_https_redirect_url() {
+        assert_eq!(
+            https_redirect_url("photos.local:8080", 8443, "/api/tags?x=1"),
+            "https://photos.local:8443/api/tags?x=1"
+        );
+        assert_eq!(
+            https_redirect_url("photos.local", 443, "/"),
+            "https://photos.local/"
+        );
+        assert_eq!(
+            https_redirect_url("[::1]:8080", 8443, "/"),
+            "https://[::1]:8443/"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_redirect_router() {
+        let app = build_redirect_router(8443);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/directories/5?page=2")
+                    .header("host", "nas:8080")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            response.headers()["location"],
+            "https://nas:8443/directories/5?page=2"
+        );
+    }
+
+    fn test_state_with_dir() -> (Arc<AppState>, i64) {
+        let db = Database::open_in_memory().unwrap();
+        let dir_id = db.insert_directory("photos/vacation", None, None).unwrap();
+        let state = Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library")));
+        (state, dir_id)
+    }
+
+    async fn body_json(response: axum::http::Response<Body>) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_usage_endpoint() {
+        let db = Database::open_in_memory().unwrap();
+        let trips = db.insert_directory("trips", None, None).unwrap();
+        let rome = db.insert_directory("trips/rome", Some(trips), None).unwrap();
+        db.insert_file(trips, "map.jpg", 100, 0, Some("image")).unwrap();
+        db.insert_file(rome, "clip.mp4", 1_000, 0, Some("video")).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/api/usage?path=trips&depth=1").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["size"], 1_100);
+        assert_eq!(json["children"][0]["path"], "trips/rome");
+        assert_eq!(json["children"][0]["is_dir"], true);
+        assert_eq!(json["children"][1]["name"], "map.jpg");
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/usage?path=nope").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_stats_endpoint() {
+        let db = Database::open_in_memory().unwrap();
+        let trips = db.insert_directory("trips", None, None).unwrap();
+        let map = db.insert_file(trips, "map.jpg", 100, 0, Some("image")).unwrap();
+        db.insert_file(trips, "clip.mp4", 1_000, 0, Some("video")).unwrap();
+        db.set_file_rating(map, Some(4.0)).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/stats").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!((json["directories"].as_i64(), json["files"].as_i64()), (Some(1), Some(2)));
+        assert_eq!((json["bytes"].as_i64(), json["rated"].as_i64()), (Some(1_100), Some(1)));
+        assert_eq!((json["images"].as_i64(), json["videos"].as_i64()), (Some(1), Some(1)));
+        assert!(json["last_sync"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_stats_heatmap_endpoint() {
+        let db = Database::open_in_memory().unwrap();
+        let trips = db.insert_directory("trips", None, None).unwrap();
+        let day = |y, m, d| chrono::NaiveDate::from_ymd_opt(y, m, d);
+        for (name, date) in [("a.jpg", day(2024, 5, 4)), ("b.jpg", day(2024, 5, 4)), ("c.jpg", day(2023, 1, 2))] {
+            let id = db.insert_file(trips, name, 100, 0, Some("image")).unwrap();
+            db.set_file_capture_date(id, date).unwrap();
+        }
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/stats/heatmap?year=2024").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["days"], serde_json::json!([{ "date": "2024-05-04", "count": 2 }]));
+        assert_eq!((json["total"].as_u64(), json["max"].as_u64()), (Some(2), Some(2)));
+        assert_eq!(json["years"], serde_json::json!([2023, 2024]));
+    }
+
+    #[tokio::test]
+    async fn test_tag_aliases_roundtrip() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("trip", None, None).unwrap();
+        let file = db.insert_file(dir, "a.jpg", 100, 0, Some("image")).unwrap();
+        db.add_file_tag(file, "nyc").unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+        let request = |method: &str, uri: &str, body: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(request("POST", "/api/tags/aliases", r#"{"alias": "NYC", "tag": "new york"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json[0]["alias"], "nyc");
+        assert_eq!(json[0]["tag"], "new york");
+
+        // The existing tag was merged, and filtering by the alias still works
+        let response = app.clone().oneshot(request("GET", "/api/files?tag=nyc", "")).await.unwrap();
+        let json = body_json(response).await;
+        assert_eq!(json["files"][0]["tags"][0], "new york");
+
+        let response = app
+            .clone()
+            .oneshot(request("POST", "/api/tags/aliases", r#"{"alias": "new york", "tag": "nyc"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app.clone().oneshot(request("DELETE", "/api/tags/aliases/nyc", "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(body_json(response).await.as_array().unwrap().is_empty());
+        let response = app.oneshot(request("DELETE", "/api/tags/aliases/nyc", "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_file_attributes_roundtrip() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("shoot", None, None).unwrap();
+        let file = db.insert_file(dir, "a.jpg", 100, 0, Some("image")).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/api/directories")
-                    .body(Body::empty())
+                    .method("PUT")
+                    .uri(format!("/api/files/{}/attributes/client", file))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"value": "Acme"}"#))
                     .unwrap(),
             )
             .await
             .unwrap();
-
         assert_eq!(response.status(), StatusCode::OK);
-    }
+        let json = body_json(response).await;
+        assert_eq!(json["attributes"]["client"], "Acme");
 
-    #[tokio::test]
-    async fn test_tags_endpoint_empty_db() {
-        let app = build_router(test_state());
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/api/tags")
+                    .method("DELETE")
+                    .uri(format!("/api/files/{}/attributes/client", file))
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
-
         assert_eq!(response.status(), StatusCode::OK);
-    }
+        let json = body_json(response).await;
+        assert_eq!(json["attributes"], serde_json::json!({}));
 
-    #[tokio::test]
-    async fn test_directory_files_not_found() {
-        let app = build_router(test_state());
         let response = app
             .oneshot(
                 Request::builder()
-                    .uri("/api/directories/999/files")
+                    .uri("/api/files/9999/attributes")
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
 
-        // Should return 200 with empty list (directory doesn't exist but query still works)
+    #[tokio::test]
+    async fn test_update_file_inline() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("shoot", None, None).unwrap();
+        let file = db.insert_file(dir, "a.jpg", 100, 0, Some("image")).unwrap();
+        db.set_file_rating(file, Some(3.0)).unwrap();
+        db.add_file_tag(file, "draft").unwrap();
+        let version = db.get_file_version(file).unwrap().unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let patch = |uri: String, if_match: &str, body: String| {
+            let mut request = Request::builder()
+                .method("PATCH")
+                .uri(uri)
+                .header("content-type", "application/json");
+            if !if_match.is_empty() {
+                request = request.header("if-match", if_match);
+            }
+            request.body(Body::from(body)).unwrap()
+        };
+
+        // Leaving the rating out keeps it
+        let response = app
+            .clone()
+            .oneshot(patch(
+                format!("/api/files/{}", file),
+                "",
+                format!(r#"{{"add_tags": [" Beach "], "remove_tags": ["draft"], "version": {}}}"#, version),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["rating"], 3.0);
+        assert_eq!(json["tags"], serde_json::json!(["beach"]));
+        let edited = json["version"].as_i64().unwrap();
+        assert!(edited > version);
+
+        // null clears it
+        let response = app
+            .clone()
+            .oneshot(patch(format!("/api/files/{}", file), &format!("\"{}\"", edited), r#"{"rating": null}"#.into()))
+            .await
+            .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
+        assert!(body_json(response).await["rating"].is_null());
+
+        // Based on a version someone else has since changed
+        let response = app
+            .clone()
+            .oneshot(patch(format!("/api/files/{}", file), &edited.to_string(), r#"{"rating": 5}"#.into()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = app
+            .clone()
+            .oneshot(patch(format!("/api/files/{}", file), "", r#"{"rating": 5}"#.into()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+
+        let response = app
+            .clone()
+            .oneshot(patch(format!("/api/files/{}", file), "*", r#"{"rating": 12}"#.into()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .oneshot(patch("/api/files/9999".to_string(), "*", r#"{"rating": 4}"#.into()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
-    async fn test_embedded_asset_fallback() {
-        let app = build_router(test_state());
+    async fn test_file_exif() {
+        use exif::experimental::Writer;
+        use exif::{Field, In, Tag, Value};
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("shoot")).unwrap();
+        let lens = Field {
+            tag: Tag::LensModel,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![b"XF35mmF1.4 R".to_vec()]),
+        };
+        let metering = Field { tag: Tag::MeteringMode, ifd_num: In::PRIMARY, value: Value::Short(vec![3]) };
+        let mut writer = Writer::new();
+        writer.push_field(&lens);
+        writer.push_field(&metering);
+        let mut tiff = std::io::Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        std::fs::write(tmp.path().join("shoot/a.tif"), tiff.into_inner()).unwrap();
+
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("shoot", None, None).unwrap();
+        let file = db.insert_file(dir, "a.tif", 100, 0, Some("image")).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, tmp.path().to_path_buf())));
+
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/")
+                    .uri(format!("/api/files/{}/exif", file))
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["lens"], "XF35mmF1.4 R");
+        assert_eq!(json["metering_mode"], "spot");
+        assert!(json["camera_make"].is_null());
+        assert_eq!(json["tags"].as_array().unwrap().len(), 2);
+        assert_eq!(json["tags"][0]["ifd"], "primary");
 
-        // Should return 200 if index.html exists, 404 otherwise
-        let status = response.status();
-        assert!(status == StatusCode::OK || status == StatusCode::NOT_FOUND);
-    }
-
-    fn test_state_with_dir() -> (Arc<AppState>, i64) {
-        let db = Database::open_in_memory().unwrap();
-        let dir_id = db.insert_directory("photos/vacation", None, None).unwrap();
-        let state = Arc::new(AppState {
-            db: Arc::new(Mutex::new(db)),
-            library_path: PathBuf::from("/tmp/test-library"),
-        });
-        (state, dir_id)
-    }
-
-    async fn body_json(response: axum::http::Response<Body>) -> serde_json::Value {
-        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        let response = app
+            .oneshot(Request::builder().uri("/api/files/9999/exif").body(Body::empty()).unwrap())
             .await
             .unwrap();
-        serde_json::from_slice(&bytes).unwrap()
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
@@ -226,7 +1719,32 @@ mod tests {
 
         assert_eq!(response.status(), StatusCode::OK);
         let json = body_json(response).await;
-        assert_eq!(json["rating"], 3);
+        assert_eq!(json["rating"], 3.0);
+    }
+
+    #[tokio::test]
+    async fn test_directory_edit_with_stale_version_conflicts() {
+        let (state, dir_id) = test_state_with_dir();
+        let app = build_router(state);
+        let put = |if_match: &str| {
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/api/directories/{}/rating", dir_id))
+                .header("content-type", "application/json")
+                .header("if-match", if_match)
+                .body(Body::from(r#"{"rating": 3}"#))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(put("0")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["version"], 1);
+
+        let response = app.clone().oneshot(put("0")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = app.oneshot(put("not-a-version")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
     #[tokio::test]
@@ -235,7 +1753,7 @@ mod tests {
         // First set a rating
         {
             let db = state.db.lock().unwrap();
-            db.set_directory_rating(dir_id, Some(4)).unwrap();
+            db.set_directory_rating(dir_id, Some(4.0)).unwrap();
         }
         let app = build_router(state);
         let response = app
@@ -274,6 +1792,57 @@ mod tests {
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[tokio::test]
+    async fn test_ten_point_library_accepts_higher_ratings() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join(".picman.toml"), "[ratings]\nscale = 10\n").unwrap();
+        let db = Database::open_in_memory().unwrap();
+        let dir_id = db.insert_directory("photos", None, None).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, tmp.path().to_path_buf())));
+
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/api/settings").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(body_json(response).await["rating_scale"], 10);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/api/directories/{}/rating", dir_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"rating": 8}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["rating"], 8.0);
+    }
+
+    #[tokio::test]
+    async fn test_directory_rating_in_half_steps() {
+        let (state, dir_id) = test_state_with_dir();
+        let app = build_router(state);
+        for (body, status) in [(r#"{"rating": 3.5}"#, StatusCode::OK), (r#"{"rating": 3.25}"#, StatusCode::BAD_REQUEST)] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("PUT")
+                        .uri(format!("/api/directories/{}/rating", dir_id))
+                        .header("content-type", "application/json")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status, "{}", body);
+        }
+    }
+
     #[tokio::test]
     async fn test_add_directory_tag() {
         let (state, dir_id) = test_state_with_dir();
@@ -297,6 +1866,73 @@ mod tests {
         assert_eq!(tags[0], "travel"); // should be lowercased
     }
 
+    #[tokio::test]
+    async fn test_generate_directory_preview_job() {
+        let db = Database::open_in_memory().unwrap();
+        let parent = db.insert_directory("trips", None, None).unwrap();
+        let child = db.insert_directory("trips/rome", Some(parent), None).unwrap();
+        db.insert_directory("trips/rome/day1", Some(child), None).unwrap();
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+
+        let post = |uri: String, body: String| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(post(format!("/api/directories/{}/preview/generate?recursive=true", child), String::new()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let job = body_json(response).await;
+        assert_eq!(job["kind"], "dir_preview");
+        assert_eq!(job["total"], 2);
+
+        // No images anywhere, so the job finishes without writing previews
+        let status = loop {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(format!("/api/jobs/{}", job["id"])).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let status = body_json(response).await;
+            if status["done"] == true {
+                break status;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(status["completed"], 2);
+        assert_eq!(status["generated"], 0);
+
+        let body = format!(r#"{{"directory_ids": [{}, 999]}}"#, parent);
+        let response = app
+            .clone()
+            .oneshot(post("/api/directories/preview/generate".to_string(), body))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(body_json(response).await["total"], 1);
+
+        let response = app
+            .clone()
+            .oneshot(post("/api/directories/999/preview/generate".to_string(), String::new()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/jobs/999").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
     // ==================== Duplicates Tests ====================
 
     fn test_state_with_duplicates() -> Arc<AppState> {
@@ -330,10 +1966,7 @@ mod tests {
             .unwrap();
         db.set_file_hash(f5, "unique123").unwrap();
 
-        Arc::new(AppState {
-            db: Arc::new(Mutex::new(db)),
-            library_path: PathBuf::from("/tmp/test-library"),
-        })
+        Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library")))
     }
 
     #[tokio::test]
@@ -354,6 +1987,12 @@ mod tests {
         let json = body_json(response).await;
         assert_eq!(json["exact_groups"], 2);
         assert_eq!(json["exact_files"], 4);
+        // One copy of each pair is reclaimable, all from one top-level folder
+        assert_eq!(json["exact_reclaimable_bytes"], 7200000);
+        assert_eq!(json["reclaimable_bytes"], 7200000);
+        let by_directory = json["by_directory"].as_array().unwrap();
+        assert_eq!(by_directory.len(), 1);
+        assert_eq!(by_directory[0]["bytes"], 7200000);
     }
 
     #[tokio::test]
@@ -376,6 +2015,8 @@ mod tests {
         assert_eq!(json["exact_files"], 0);
         assert_eq!(json["similar_groups"], 0);
         assert_eq!(json["similar_files"], 0);
+        assert_eq!(json["reclaimable_bytes"], 0);
+        assert!(json["by_directory"].as_array().unwrap().is_empty());
     }
 
     #[tokio::test]
@@ -429,10 +2070,7 @@ mod tests {
             db.set_file_hash(f1, "samehash").unwrap();
             db.set_file_hash(f2, "samehash").unwrap();
 
-            Arc::new(AppState {
-                db: Arc::new(Mutex::new(db)),
-                library_path: PathBuf::from("/tmp/test-library"),
-            })
+            Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library")))
         };
 
         let app = build_router(state);
@@ -460,6 +2098,155 @@ mod tests {
         assert_eq!(suggested_id, high_res_file["id"].as_i64().unwrap());
     }
 
+    #[tokio::test]
+    async fn test_get_duplicates_keep_policy_param() {
+        let state = test_state_with_duplicates();
+
+        let response = build_router(state.clone())
+            .oneshot(
+                Request::builder()
+                    .uri("/api/duplicates?type=exact&keep=prefix:backup")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        for group in json["groups"].as_array().unwrap() {
+            let suggested_id = group["suggested_keep_id"].as_i64().unwrap();
+            let kept = group["files"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|f| f["id"].as_i64() == Some(suggested_id))
+                .unwrap();
+            assert_eq!(kept["directory_path"], "backup/2024");
+        }
+
+        // The savings now come out of photos/ instead
+        let response = build_router(state.clone())
+            .oneshot(
+                Request::builder()
+                    .uri("/api/duplicates/summary?keep=prefix:backup")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let json = body_json(response).await;
+        assert_eq!(json["by_directory"][0]["directory"], "photos");
+
+        let response = build_router(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/api/duplicates?keep=newest")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_get_similar_duplicates_threshold_and_distances() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("photos", None, None).unwrap();
+        for (name, hash) in [("a.jpg", 0b0000), ("b.jpg", 0b0011), ("c.jpg", 0b1111_1111)] {
+            let id = db.insert_file(dir, name, 100, 0, Some("image")).unwrap();
+            db.set_perceptual_hash(id, hash).unwrap();
+        }
+        let state = Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library")));
+
+        let get = |threshold: u32| {
+            let state = state.clone();
+            async move {
+                let response = build_router(state)
+                    .oneshot(
+                        Request::builder()
+                            .uri(format!("/api/duplicates?type=similar&threshold={}", threshold))
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                body_json(response).await
+            }
+        };
+
+        let json = get(6).await;
+        assert_eq!(json["total_groups"], 1);
+        let group = &json["groups"][0];
+        assert_eq!(group["files"].as_array().unwrap().len(), 3);
+        assert_eq!(group["max_distance"], 8);
+        let mut distances: Vec<i64> = group["distances"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| d["distance"].as_i64().unwrap())
+            .collect();
+        distances.sort();
+        assert_eq!(distances, vec![2, 6, 8]);
+
+        // A tighter threshold regroups from the same cached index
+        let index = state.similarity.state.lock().unwrap().index.clone().unwrap();
+        let json = get(2).await;
+        assert_eq!(json["groups"][0]["files"].as_array().unwrap().len(), 2);
+        assert_eq!(json["groups"][0]["distances"].as_array().unwrap().len(), 1);
+        assert!(Arc::ptr_eq(&index, state.similarity.state.lock().unwrap().index.as_ref().unwrap()));
+        assert_eq!(get(1).await["total_groups"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_similar_duplicates_run_as_a_job() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = db.insert_directory("photos", None, None).unwrap();
+        for (name, hash) in [("a.jpg", 0b0000), ("b.jpg", 0b0011), ("c.jpg", 0b1111_1111)] {
+            let id = db.insert_file(dir, name, 100, 0, Some("image")).unwrap();
+            db.set_perceptual_hash(id, hash).unwrap();
+        }
+        let mut state = AppState::new(db, PathBuf::from("/tmp/test-library"));
+        state.similarity = Arc::new(SimilarityCache::with_inline_limit(0));
+        let state = Arc::new(state);
+        let get = |uri: &str| {
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            build_router(state.clone()).oneshot(request)
+        };
+
+        // Nothing cached, so the request hands back a job to follow
+        let response = get("/api/duplicates?type=similar&threshold=2").await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let job = body_json(response).await;
+        assert_eq!(job["kind"], "similar_duplicates");
+
+        let response = get(&format!("/api/jobs/{}/events", job["id"])).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let last = String::from_utf8(bytes.to_vec()).unwrap();
+        let last: serde_json::Value =
+            serde_json::from_str(last.lines().filter_map(|l| l.strip_prefix("data: ")).next_back().unwrap()).unwrap();
+        assert_eq!((last["done"].as_bool(), last["total"].as_u64()), (Some(true), Some(3)));
+        assert_eq!((last["completed"].as_u64(), last["generated"].as_u64()), (Some(3), Some(1)));
+
+        // Then the groups at that threshold come from the cache
+        let response = get("/api/duplicates?type=similar&threshold=2").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["total_groups"], 1);
+        let response = get("/api/duplicates/folders?type=similar&threshold=2").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // A new hash makes the cached groups stale
+        let d = state.db.lock().unwrap().insert_file(dir, "d.jpg", 100, 0, Some("image")).unwrap();
+        state.db.lock().unwrap().set_perceptual_hash(d, 0b0001).unwrap();
+        let response = get("/api/duplicates?type=similar&threshold=2").await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_ne!(body_json(response).await["id"], job["id"]);
+
+        let response = get("/api/jobs/999/events").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn test_get_duplicates_folder_super_groups() {
         // Two groups share the same pair of folders → should create a super-group
@@ -487,6 +2274,37 @@ mod tests {
         assert_eq!(group_indices.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_get_duplicates_scoped_to_tag_or_rating() {
+        let state = test_state_with_duplicates();
+        {
+            let db = state.db.lock().unwrap();
+            for name in ["photos/vacation/beach.jpg", "backup/2024/beach_copy.jpg"] {
+                let file = db.get_file_by_path(name).unwrap().unwrap();
+                db.add_file_tag(file.id, "keepers").unwrap();
+            }
+            let sunset = db.get_file_by_path("photos/vacation/sunset.jpg").unwrap().unwrap();
+            db.set_file_rating(sunset.id, Some(5.0)).unwrap();
+        }
+        let app = build_router(state);
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let json = body_json(app.clone().oneshot(get("/api/duplicates?tag=keepers")).await.unwrap()).await;
+        assert_eq!(json["total_groups"], 1);
+        assert_eq!(json["groups"][0]["hash"], "aabbccdd");
+
+        // One sunset copy is rated, so only the beach pair is unrated
+        let json = body_json(app.clone().oneshot(get("/api/duplicates?unrated=true")).await.unwrap()).await;
+        assert_eq!(json["total_groups"], 1);
+        assert_eq!(json["groups"][0]["hash"], "aabbccdd");
+
+        let json = body_json(app.clone().oneshot(get("/api/duplicates/summary?rating=4")).await.unwrap()).await;
+        assert_eq!(json["exact_groups"], 0);
+
+        let response = app.oneshot(get("/api/duplicates?rating=4&unrated=true")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[tokio::test]
     async fn test_get_duplicates_pagination() {
         let state = test_state_with_duplicates();
@@ -510,6 +2328,63 @@ mod tests {
         assert_eq!(json["groups"].as_array().unwrap().len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_ignore_duplicates_hides_group() {
+        let state = test_state_with_duplicates();
+        let request = |method: &str, uri: &str, body: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = build_router(state.clone())
+            .oneshot(request("GET", "/api/duplicates?type=exact", ""))
+            .await
+            .unwrap();
+        let json = body_json(response).await;
+        assert_eq!(json["total_groups"], 2);
+        let ids: Vec<i64> = json["groups"][0]["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["id"].as_i64().unwrap())
+            .collect();
+
+        let body = format!(r#"{{"file_ids": {:?}}}"#, ids);
+        let response = build_router(state.clone())
+            .oneshot(request("POST", "/api/duplicates/ignore", &body))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["pairs"], 1);
+
+        let response = build_router(state.clone())
+            .oneshot(request("GET", "/api/duplicates/summary", ""))
+            .await
+            .unwrap();
+        assert_eq!(body_json(response).await["exact_groups"], 1);
+
+        let response = build_router(state.clone())
+            .oneshot(request("DELETE", "/api/duplicates/ignore", &body))
+            .await
+            .unwrap();
+        assert_eq!(body_json(response).await["pairs"], 1);
+        let response = build_router(state.clone())
+            .oneshot(request("GET", "/api/duplicates/summary", ""))
+            .await
+            .unwrap();
+        assert_eq!(body_json(response).await["exact_groups"], 2);
+
+        let response = build_router(state)
+            .oneshot(request("POST", "/api/duplicates/ignore", r#"{"file_ids": [1]}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[tokio::test]
     async fn test_trash_files_empty() {
         let state = test_state();
@@ -548,10 +2423,7 @@ mod tests {
             .insert_file(dir_id, "dupe.jpg", 15, 100, Some("image"))
             .unwrap();
 
-        let state = Arc::new(AppState {
-            db: Arc::new(Mutex::new(db)),
-            library_path: library_path.clone(),
-        });
+        let state = Arc::new(AppState::new(db, library_path.clone()));
 
         let app = build_router(state.clone());
         let response = app
@@ -609,10 +2481,7 @@ mod tests {
             file_ids.push(fid);
         }
 
-        let state = Arc::new(AppState {
-            db: Arc::new(Mutex::new(db)),
-            library_path: library_path.clone(),
-        });
+        let state = Arc::new(AppState::new(db, library_path.clone()));
 
         let ids_json: Vec<String> = file_ids.iter().map(|id| id.to_string()).collect();
         let body = format!(r#"{{"file_ids": [{}]}}"#, ids_json.join(","));
@@ -715,10 +2584,7 @@ mod tests {
             trash_ids.push(ft);
         }
 
-        let state = Arc::new(AppState {
-            db: Arc::new(Mutex::new(db)),
-            library_path: library_path.clone(),
-        });
+        let state = Arc::new(AppState::new(db, library_path.clone()));
 
         let app = build_router(state.clone());
         let response = app
@@ -784,10 +2650,7 @@ mod tests {
             db.set_file_hash(f2, &format!("hash_{}", i)).unwrap();
         }
 
-        let state = Arc::new(AppState {
-            db: Arc::new(Mutex::new(db)),
-            library_path: PathBuf::from("/tmp/test-library"),
-        });
+        let state = Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library")));
 
         // Request only page 1 with per_page=1 (so only 1 group on this page)
         let app = build_router(state);
@@ -815,4 +2678,50 @@ mod tests {
             "Super-group should include all 3 groups, not just the page subset"
         );
     }
+
+    #[tokio::test]
+    async fn test_folder_super_groups_sorted_filtered_and_paged() {
+        let db = Database::open_in_memory().unwrap();
+        let photos = db.insert_directory("photos", None, None).unwrap();
+        let backup = db.insert_directory("backup/2024", None, None).unwrap();
+        let old = db.insert_directory("old", None, None).unwrap();
+        // photos + backup/2024: two small groups; photos + old: two large ones
+        for (i, (other, size)) in [(backup, 100), (backup, 100), (old, 5000), (old, 5000)].into_iter().enumerate() {
+            let hash = format!("hash_{}", i);
+            let a = db.insert_file(photos, &format!("a{}.jpg", i), size, 0, Some("image")).unwrap();
+            let b = db.insert_file(other, &format!("b{}.jpg", i), size, 0, Some("image")).unwrap();
+            db.set_file_hash(a, &hash).unwrap();
+            db.set_file_hash(b, &hash).unwrap();
+        }
+        let app = build_router(Arc::new(AppState::new(db, PathBuf::from("/tmp/test-library"))));
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let json = body_json(app.clone().oneshot(get("/api/duplicates/folders")).await.unwrap()).await;
+        assert_eq!(json["total"], 2);
+        let super_groups = json["super_groups"].as_array().unwrap();
+        assert_eq!(super_groups[0]["folders"], serde_json::json!(["old", "photos"]));
+        assert_eq!(super_groups[0]["reclaimable_bytes"], 10000);
+        assert_eq!(super_groups[1]["reclaimable_bytes"], 200);
+
+        let json = body_json(app.clone().oneshot(get("/api/duplicates/folders?prefix=backup/")).await.unwrap()).await;
+        assert_eq!(json["total"], 1);
+        assert_eq!(json["super_groups"][0]["folders"], serde_json::json!(["backup/2024", "photos"]));
+
+        let json = body_json(
+            app.clone()
+                .oneshot(get("/api/duplicates/folders?sort=folders&per_page=1&page=2"))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(json["total"], 2);
+        assert_eq!(json["super_groups"][0]["folders"], serde_json::json!(["old", "photos"]));
+
+        // A listing page only carries the super-groups of its own groups
+        let json = body_json(app.clone().oneshot(get("/api/duplicates?per_page=2")).await.unwrap()).await;
+        assert_eq!(json["folder_super_groups"].as_array().unwrap().len(), 1);
+
+        let response = app.oneshot(get("/api/duplicates/folders?sort=size")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
 }
@@ -1,42 +1,116 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct HealthResponse {
+    /// `ok`, or `degraded` when a background job's last run failed
     pub status: String,
+    /// Background job status (only under `picman daemon` or with a maintenance schedule)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub daemon: Option<crate::daemon::DaemonSnapshot>,
+}
+
+/// Library settings the web UI needs
+#[derive(Serialize, ToSchema)]
+pub struct SettingsResponse {
+    /// Highest rating: 5, or 10 for libraries rating on a 1-10 scale
+    pub rating_scale: i32,
 }
 
-#[derive(Serialize)]
+/// Server build, so a page loaded from an older build can tell it's stale
+#[derive(Serialize, ToSchema)]
+pub struct VersionResponse {
+    /// picman crate version
+    pub version: String,
+    /// Fingerprint of the embedded web UI assets
+    pub asset_hash: String,
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct DirectoryResponse {
     pub id: i64,
     pub path: String,
     pub parent_id: Option<i64>,
-    pub rating: Option<i32>,
+    pub rating: Option<f64>,
     pub tags: Vec<String>,
     pub file_count: usize,
+    /// Pinned to the top of the TUI tree
+    pub pinned: bool,
+    /// Marked hidden (only listed with `include_hidden`)
+    pub hidden: bool,
+    /// Bumped by every rating or tag change; send it back as `If-Match`
+    pub version: i64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct FileResponse {
     pub id: i64,
     pub filename: String,
     pub directory_id: i64,
     pub directory_path: String,
     pub size: i64,
-    pub rating: Option<i32>,
+    pub rating: Option<f64>,
     pub media_type: Option<String>,
     pub width: Option<i32>,
     pub height: Option<i32>,
+    /// Video length in seconds, once a sync has read it with ffprobe
+    pub duration: Option<f64>,
     pub tags: Vec<String>,
+    /// Bumped by every rating or tag change; send it back with edits
+    pub version: i64,
+    /// Extensions of paired siblings shown under this entry (`MOV` for a live photo, `NEF`, `XMP`)
+    pub companions: Vec<String>,
+    /// Manual stack this file belongs to (only reported by directory listings)
+    pub stack_id: Option<i64>,
+    /// Files in the collapsed stack this cell stands for (0 when not a collapsed stack)
+    pub stack_size: usize,
+}
+
+/// The largest print a file makes at one resolution
+#[derive(Serialize, ToSchema)]
+pub struct PrintSizeResponse {
+    pub dpi: u32,
+    pub width_inches: f64,
+    pub height_inches: f64,
 }
 
-#[derive(Serialize)]
+/// One file, with the print sizes its dimensions allow
+#[derive(Serialize, ToSchema)]
+pub struct FileDetailResponse {
+    #[serde(flatten)]
+    pub file: FileResponse,
+    /// At 300, 240 and 150 dpi; empty for videos and files without dimensions
+    pub print_sizes: Vec<PrintSizeResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct TagResponse {
     pub name: String,
     pub file_count: i64,
     pub directory_count: i64,
 }
 
-#[derive(Serialize)]
+/// Another name for a tag, used in its place when tagging and filtering
+#[derive(Serialize, ToSchema)]
+pub struct TagAliasResponse {
+    pub alias: String,
+    /// The tag the alias stands for
+    pub tag: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AddTagAliasRequest {
+    /// The other name, e.g. "nyc" (stored lowercased)
+    pub alias: String,
+    /// The tag it stands for, e.g. "new york"; files and directories
+    /// already tagged with the alias are retagged
+    pub tag: String,
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct PaginatedFiles {
     pub files: Vec<FileResponse>,
     pub total: usize,
@@ -44,38 +118,263 @@ pub struct PaginatedFiles {
     pub per_page: usize,
 }
 
-#[derive(Deserialize)]
+/// The next file to review and how many remain after it
+#[derive(Serialize, ToSchema)]
+pub struct NextFileResponse {
+    /// None when the review is done
+    pub file: Option<FileResponse>,
+    pub remaining: usize,
+}
+
+/// The next files to review (for prefetching) and how many remain after them
+#[derive(Serialize, ToSchema)]
+pub struct NextFilesResponse {
+    pub files: Vec<FileResponse>,
+    pub remaining: usize,
+}
+
+/// A review queue (`picman review`): the files a filter matched, in review
+/// order, and how far the review has got
+#[derive(Serialize, ToSchema)]
+pub struct ReviewQueueResponse {
+    pub id: i64,
+    pub name: String,
+    /// The filter it was built from, e.g. `unrated, in 2024/`
+    pub filter: String,
+    /// Index of the file the review is at (`total` once past the last)
+    pub position: usize,
+    pub total: usize,
+    /// Files rated or skipped so far
+    pub decided: usize,
+    /// Every file rated or skipped
+    pub finished: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl From<crate::db::ReviewQueue> for ReviewQueueResponse {
+    fn from(queue: crate::db::ReviewQueue) -> Self {
+        Self {
+            finished: queue.is_finished(),
+            id: queue.id,
+            name: queue.name,
+            filter: queue.filter,
+            position: queue.position,
+            total: queue.total,
+            decided: queue.decided,
+            created_at: queue.created_at,
+            updated_at: queue.updated_at,
+        }
+    }
+}
+
+/// A file in a review queue
+#[derive(Serialize, ToSchema)]
+pub struct ReviewFileResponse {
+    #[serde(flatten)]
+    pub file: FileResponse,
+    /// `rated`, `skipped`, or null if not reviewed yet
+    pub decision: Option<String>,
+}
+
+/// Where a review stands: the file it's at and the ones after it
+#[derive(Serialize, ToSchema)]
+pub struct ReviewSessionResponse {
+    pub queue: ReviewQueueResponse,
+    /// The file the review is at; null once past the last
+    pub file: Option<ReviewFileResponse>,
+    /// Up to `limit` files after it, for prefetching
+    pub upcoming: Vec<ReviewFileResponse>,
+}
+
+/// Build a review queue from a filter; fields left out don't filter
+#[derive(Deserialize, ToSchema)]
+pub struct CreateReviewQueueRequest {
+    pub name: String,
+    /// Minimum rating, on the library's rating scale
+    pub rating: Option<f64>,
+    /// Only files without a rating
+    #[serde(default)]
+    pub unrated: bool,
+    pub tag: Option<String>,
+    /// Only files in this directory and below, e.g. `2024`
+    pub dir: Option<String>,
+    /// A filter preset saved in the TUI filter dialog
+    pub preset: Option<String>,
+    /// `landscape`, `portrait`, `square`, `panorama` or `tall`
+    pub orientation: Option<String>,
+    /// Also include files in hidden directories
+    #[serde(default)]
+    pub include_hidden: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ReviewPositionRequest {
+    /// The file to continue the review from
+    pub file_id: i64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ReviewDecisionRequest {
+    pub file_id: i64,
+    /// `rated` (rate it with `PATCH /api/files/{id}` first) or `skipped`;
+    /// skipping a file already rated keeps it rated
+    pub decision: String,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct SetRatingRequest {
-    pub rating: Option<i32>,
+    /// 1 to the library's rating scale (5 or 10) in half steps, or null to clear
+    #[schema(minimum = 1, maximum = 10)]
+    pub rating: Option<f64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct AddTagRequest {
+    /// Tag name (stored lowercased)
     pub tag: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+pub struct FileAttributesResponse {
+    pub file_id: i64,
+    /// Custom key/value metadata, sorted by key
+    pub attributes: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SetAttributeRequest {
+    pub value: String,
+}
+
+/// EXIF metadata read from the original file; fields the file doesn't have
+/// are null
+#[derive(Serialize, ToSchema)]
+pub struct ExifResponse {
+    pub file_id: i64,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens: Option<String>,
+    /// E.g. `f/2.8`
+    pub aperture: Option<String>,
+    /// E.g. `1/250 s`
+    pub shutter_speed: Option<String>,
+    /// E.g. `ISO 400`
+    pub iso: Option<String>,
+    /// E.g. `35 mm`
+    pub focal_length: Option<String>,
+    pub exposure_program: Option<String>,
+    pub metering_mode: Option<String>,
+    pub flash: Option<String>,
+    pub exposure_bias: Option<String>,
+    /// Capture time as stored, e.g. `2024-03-15 10:20:30`
+    pub date_taken: Option<String>,
+    pub gps_lat: Option<f64>,
+    pub gps_lon: Option<f64>,
+    /// Meters above sea level
+    pub gps_altitude: Option<f64>,
+    /// Every EXIF field in the file, long values cut short
+    pub tags: Vec<ExifTagResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExifTagResponse {
+    /// `primary`, or `thumbnail` for the embedded preview's fields
+    pub ifd: String,
+    pub name: String,
+    pub value: String,
+}
+
+impl ExifResponse {
+    pub fn new(file_id: i64, exif: crate::tui::exif::ExifInfo) -> Self {
+        Self {
+            file_id,
+            camera_make: exif.camera_make,
+            camera_model: exif.camera_model,
+            lens: exif.lens,
+            aperture: exif.aperture,
+            shutter_speed: exif.shutter_speed,
+            iso: exif.iso,
+            focal_length: exif.focal_length,
+            exposure_program: exif.exposure_program,
+            metering_mode: exif.metering_mode,
+            flash: exif.flash,
+            exposure_bias: exif.exposure_bias,
+            date_taken: exif.date_taken,
+            gps_lat: exif.gps_lat,
+            gps_lon: exif.gps_lon,
+            gps_altitude: exif.gps_altitude,
+            tags: exif
+                .tags
+                .into_iter()
+                .map(|tag| ExifTagResponse {
+                    ifd: tag.ifd.to_string(),
+                    name: tag.name,
+                    value: tag.value,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct DirectoryMetaResponse {
-    pub rating: Option<i32>,
+    pub rating: Option<f64>,
+    pub tags: Vec<String>,
+    pub version: i64,
+}
+
+/// Inline edit of one file from the grid; fields left out are unchanged
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateFileRequest {
+    /// New rating, or null to clear it
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<f64>, minimum = 1, maximum = 10)]
+    pub rating: Option<Option<f64>>,
+    /// Tags to add (stored lowercased)
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    #[serde(default)]
+    pub remove_tags: Vec<String>,
+    /// Version the edit is based on, if not sent as `If-Match`
+    pub version: Option<i64>,
+}
+
+/// Tells a field sent as null (`Some(None)`) from one left out (`None`)
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FileMetaResponse {
+    pub id: i64,
+    pub rating: Option<f64>,
     pub tags: Vec<String>,
+    pub version: i64,
 }
 
 // ==================== Duplicates ====================
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct DuplicateFileResponse {
     pub id: i64,
     pub filename: String,
     pub directory_path: String,
     pub size: i64,
+    /// Modification time (unix seconds), used by the `oldest` keep policy
+    pub mtime: i64,
     pub width: Option<i32>,
     pub height: Option<i32>,
-    pub rating: Option<i32>,
+    pub rating: Option<f64>,
     pub media_type: Option<String>,
     pub tags: Vec<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct DuplicateGroupResponse {
     pub group_index: usize,
     pub match_type: String,
@@ -83,59 +382,231 @@ pub struct DuplicateGroupResponse {
     pub max_distance: Option<u32>,
     pub files: Vec<DuplicateFileResponse>,
     pub suggested_keep_id: i64,
+    /// Similar groups: the distance between every pair of files, so a client
+    /// can split the group at a tighter threshold without asking again
+    pub distances: Option<Vec<PairDistance>>,
 }
 
-#[derive(Serialize)]
+/// Hamming distance between the perceptual hashes of two files
+#[derive(Serialize, ToSchema)]
+pub struct PairDistance {
+    pub a: i64,
+    pub b: i64,
+    pub distance: u32,
+}
+
+/// Two folders that hold copies of each other in 2+ duplicate groups
+#[derive(Clone, Serialize, ToSchema)]
 pub struct FolderSuperGroup {
     pub folders: Vec<String>,
+    /// Indices of those groups in the whole listing, not just one page
     pub group_indices: Vec<usize>,
+    /// Bytes freed by keeping one copy in each of those groups
+    pub reclaimable_bytes: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FolderSuperGroupsResponse {
+    pub super_groups: Vec<FolderSuperGroup>,
+    /// Super-groups matching the prefix, across all pages
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct DuplicatesResponse {
     pub groups: Vec<DuplicateGroupResponse>,
     pub total_groups: usize,
     pub page: usize,
     pub per_page: usize,
+    /// Super-groups with a group on this page (all of their groups listed);
+    /// `/api/duplicates/folders` pages through every one
     pub folder_super_groups: Vec<FolderSuperGroup>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct DuplicatesSummary {
     pub exact_groups: usize,
     pub exact_files: usize,
     pub similar_groups: usize,
     pub similar_files: usize,
+    /// Bytes freed by keeping one copy per exact group
+    pub exact_reclaimable_bytes: i64,
+    /// Bytes freed by keeping the highest resolution image per similar group
+    pub similar_reclaimable_bytes: i64,
+    pub reclaimable_bytes: i64,
+    /// Reclaimable bytes per top-level directory, largest first
+    pub by_directory: Vec<DirectorySavingsResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DirectorySavingsResponse {
+    /// First path component, or "(root)" for files at the library root
+    pub directory: String,
+    pub bytes: i64,
+}
+
+/// Files to mark as not duplicates of each other (or to unmark)
+#[derive(Deserialize, ToSchema)]
+pub struct IgnoreDuplicatesRequest {
+    /// At least two; every pair among them is (un)marked
+    pub file_ids: Vec<i64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, ToSchema)]
+pub struct IgnoreDuplicatesResponse {
+    /// File pairs whose mark changed
+    pub pairs: usize,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct TrashFilesRequest {
     pub file_ids: Vec<i64>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct TrashFilesResponse {
     pub trashed: usize,
     pub errors: Vec<TrashErrorResponse>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct TrashErrorResponse {
     pub file_id: i64,
     pub error: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct TrashFolderRuleRequest {
+    /// `exact` or `similar`
     pub match_type: String,
     pub threshold: Option<u32>,
     pub keep_folder: String,
     pub trash_folder: String,
 }
 
-#[derive(Serialize)]
+/// Library totals, kept up to date by the database on every write
+#[derive(Serialize, ToSchema)]
+pub struct StatsResponse {
+    pub directories: i64,
+    pub files: i64,
+    pub bytes: i64,
+    pub images: i64,
+    pub videos: i64,
+    pub documents: i64,
+    pub rated: i64,
+    pub tagged: i64,
+    /// Files `sync --hash` hasn't hashed yet
+    pub without_hash: i64,
+    /// Images whose dimensions haven't been read yet
+    pub without_dimensions: i64,
+    /// Files thumbnail generation gave up on
+    pub thumbnail_failures: i64,
+    /// Unix timestamp (seconds) of the last finished sync
+    pub last_sync: Option<i64>,
+}
+
+impl From<crate::db::LibrarySummary> for StatsResponse {
+    fn from(summary: crate::db::LibrarySummary) -> Self {
+        Self {
+            directories: summary.directories,
+            files: summary.files,
+            bytes: summary.bytes,
+            images: summary.images,
+            videos: summary.videos,
+            documents: summary.documents,
+            rated: summary.rated,
+            tagged: summary.tagged,
+            without_hash: summary.without_hash,
+            without_dimensions: summary.without_dimensions,
+            thumbnail_failures: summary.thumbnail_failures,
+            last_sync: summary.last_sync,
+        }
+    }
+}
+
+/// Images captured on one day
+#[derive(Serialize, ToSchema)]
+pub struct HeatmapDayResponse {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub count: usize,
+}
+
+/// Photos per day of a year, by EXIF capture date, for a calendar heatmap
+#[derive(Serialize, ToSchema)]
+pub struct HeatmapResponse {
+    pub year: i32,
+    /// Only days with photos, in date order
+    pub days: Vec<HeatmapDayResponse>,
+    pub total: usize,
+    /// Photos on the busiest day, to scale the shading
+    pub max: usize,
+    /// Every year with a capture date, oldest first
+    pub years: Vec<i32>,
+}
+
+/// A node of the disk usage treemap: a directory (sizes include everything
+/// below it) or a file
+#[derive(Serialize, ToSchema)]
+pub struct UsageNodeResponse {
+    pub name: String,
+    pub path: String,
+    /// Directory or file ID
+    pub id: Option<i64>,
+    pub is_dir: bool,
+    pub size: i64,
+    pub file_count: i64,
+    /// Largest first; empty below the requested depth
+    #[schema(no_recursion)]
+    pub children: Vec<UsageNodeResponse>,
+    /// Bytes in children left out by `limit`
+    pub other_size: i64,
+}
+
+impl From<crate::db::UsageNode> for UsageNodeResponse {
+    fn from(node: crate::db::UsageNode) -> Self {
+        Self {
+            name: node.name,
+            path: node.path,
+            id: node.id,
+            is_dir: node.is_dir,
+            size: node.size,
+            file_count: node.file_count,
+            children: node.children.into_iter().map(Into::into).collect(),
+            other_size: node.other_size,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct TrashFolderRuleResponse {
     pub trashed: usize,
     pub groups_resolved: usize,
     pub errors: Vec<TrashErrorResponse>,
 }
+
+#[derive(Deserialize, Default, ToSchema)]
+pub struct GeneratePreviewsRequest {
+    /// Directories to (re)generate; omitted means every directory without a preview
+    #[serde(default)]
+    pub directory_ids: Option<Vec<i64>>,
+}
+
+/// Status of a background job started through the API
+#[derive(Serialize, ToSchema)]
+pub struct JobResponse {
+    pub id: u64,
+    /// What the job does (`dir_preview`, `similar_duplicates`)
+    pub kind: String,
+    /// Directories to process, or images to compare
+    pub total: usize,
+    pub completed: usize,
+    /// Directories a preview was written for (empty ones get none), or
+    /// similar groups found
+    pub generated: usize,
+    pub done: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
@@ -0,0 +1,164 @@
+//! OpenAPI description of the HTTP API, served at `/api/openapi.json`,
+//! with a Swagger UI page at `/api/docs`.
+//!
+//! Every route in `build_router` (except the SPA fallback and the docs
+//! routes themselves) must be listed in `paths(...)` below; a test in
+//! `mod.rs` checks this.
+
+use axum::http::header;
+use axum::response::{Html, IntoResponse, Response};
+use utoipa::OpenApi;
+
+use super::handlers;
+use super::models::*;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "picman",
+        description = "Browse, rate, tag, and de-duplicate a picman photo library."
+    ),
+    paths(
+        handlers::health,
+        handlers::get_metrics,
+        handlers::get_settings,
+        handlers::get_version,
+        handlers::get_directories,
+        handlers::get_directory_files,
+        handlers::set_directory_rating,
+        handlers::add_directory_tag,
+        handlers::remove_directory_tag,
+        handlers::generate_directory_preview,
+        handlers::generate_directory_previews,
+        handlers::get_job,
+        handlers::get_job_events,
+        handlers::get_tags,
+        handlers::get_tag_aliases,
+        handlers::add_tag_alias,
+        handlers::remove_tag_alias,
+        handlers::get_usage,
+        handlers::get_stats,
+        handlers::get_stats_heatmap,
+        handlers::get_filtered_files,
+        handlers::get_next_file,
+        handlers::get_next_files,
+        handlers::get_review_queues,
+        handlers::create_review_queue,
+        handlers::get_review_session,
+        handlers::set_review_position,
+        handlers::record_review_decision,
+        handlers::delete_review_queue,
+        handlers::get_recently_viewed,
+        handlers::get_recently_edited,
+        handlers::get_file_attributes,
+        handlers::get_file,
+        handlers::update_file,
+        handlers::set_file_attribute,
+        handlers::remove_file_attribute,
+        handlers::get_file_exif,
+        handlers::get_duplicates_summary,
+        handlers::get_duplicates,
+        handlers::get_folder_super_groups,
+        handlers::ignore_duplicates,
+        handlers::unignore_duplicates,
+        handlers::trash_files,
+        handlers::trash_folder_rule,
+        handlers::serve_web_thumbnail,
+        handlers::serve_resized_image,
+        handlers::serve_preview,
+        handlers::serve_dir_preview,
+        handlers::serve_original,
+    ),
+    components(schemas(
+        HealthResponse,
+        SettingsResponse,
+        VersionResponse,
+        DirectoryResponse,
+        FileResponse,
+        TagResponse,
+        TagAliasResponse,
+        AddTagAliasRequest,
+        PaginatedFiles,
+        NextFileResponse,
+        NextFilesResponse,
+        ReviewQueueResponse,
+        ReviewFileResponse,
+        ReviewSessionResponse,
+        CreateReviewQueueRequest,
+        ReviewPositionRequest,
+        ReviewDecisionRequest,
+        SetRatingRequest,
+        AddTagRequest,
+        DirectoryMetaResponse,
+        GeneratePreviewsRequest,
+        JobResponse,
+        UpdateFileRequest,
+        FileMetaResponse,
+        FileDetailResponse,
+        PrintSizeResponse,
+        FileAttributesResponse,
+        SetAttributeRequest,
+        ExifResponse,
+        ExifTagResponse,
+        DuplicateFileResponse,
+        DuplicateGroupResponse,
+        PairDistance,
+        FolderSuperGroup,
+        FolderSuperGroupsResponse,
+        DuplicatesResponse,
+        DuplicatesSummary,
+        DirectorySavingsResponse,
+        IgnoreDuplicatesRequest,
+        IgnoreDuplicatesResponse,
+        TrashFilesRequest,
+        TrashFilesResponse,
+        TrashErrorResponse,
+        TrashFolderRuleRequest,
+        TrashFolderRuleResponse,
+        UsageNodeResponse,
+        StatsResponse,
+        HeatmapDayResponse,
+        HeatmapResponse,
+    )),
+    tags(
+        (name = "health", description = "Liveness and monitoring"),
+        (name = "directories", description = "Directory tree, ratings, and tags"),
+        (name = "files", description = "File queries and custom attributes"),
+        (name = "tags", description = "Tag listing"),
+        (name = "review", description = "Review queues resumed across sessions"),
+        (name = "duplicates", description = "Duplicate detection and cleanup"),
+        (name = "images", description = "Thumbnails, previews, and original files"),
+    )
+)]
+pub struct ApiDoc;
+
+pub async fn openapi_json() -> Response {
+    (
+        [(header::CONTENT_TYPE, "application/json")],
+        ApiDoc::openapi().to_pretty_json().unwrap_or_default(),
+    )
+        .into_response()
+}
+
+/// Swagger UI page. The UI bundle is loaded from a CDN so it doesn't bloat
+/// the binary; the spec itself is always served locally.
+pub async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_HTML)
+}
+
+const SWAGGER_HTML: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>picman API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
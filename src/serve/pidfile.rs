@@ -0,0 +1,191 @@
+//! PID file handling for service-style management of `picman serve`.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+
+use crate::process::is_running;
+#[cfg(unix)]
+use crate::process::signal_target;
+
+/// Default PID file location inside the library (next to `.picman.db`)
+pub fn default_pid_path(library_path: &Path) -> PathBuf {
+    library_path.join(".picman-serve.pid")
+}
+
+/// A PID file owned by the running server. Removed on drop.
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Write the current process ID to `path`.
+    /// Fails if the file already names a live process; stale files are replaced.
+    pub fn create(path: &Path) -> Result<Self> {
+        if let Some(pid) = read_pid(path)? {
+            if is_running(pid) {
+                bail!(
+                    "Server already running (pid {}, pid file {})",
+                    pid,
+                    path.display()
+                );
+            }
+        }
+
+        std::fs::write(path, format!("{}\n", std::process::id()))
+            .with_context(|| format!("Failed to write pid file {}", path.display()))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Only remove the file if it still names us
+        if read_pid(&self.path).ok().flatten() == Some(std::process::id()) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Read the PID stored in `path`, or None if the file doesn't exist
+pub fn read_pid(path: &Path) -> Result<Option<u32>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => {
+            let pid = contents
+                .trim()
+                .parse::<u32>()
+                .with_context(|| format!("Malformed pid file {}", path.display()))?;
+            // 0 would signal our own process group and anything past i32::MAX
+            // wraps negative (-1 signals every process we can reach)
+            if pid == 0 || pid > i32::MAX as u32 {
+                bail!("Invalid pid {} in pid file {}", pid, path.display());
+            }
+            Ok(Some(pid))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read pid file {}", path.display())),
+    }
+}
+
+/// Ask the server named in the PID file to shut down gracefully (SIGTERM)
+/// and wait up to `timeout` for it to exit. Returns the stopped PID.
+#[cfg(unix)]
+pub fn stop(path: &Path, timeout: Duration) -> Result<u32> {
+    let Some(pid) = read_pid(path)? else {
+        bail!("No pid file at {} — is the server running?", path.display());
+    };
+
+    let Some(target) = signal_target(pid).filter(|_| is_running(pid)) else {
+        let _ = std::fs::remove_file(path);
+        bail!("Server (pid {}) is not running; removed stale pid file", pid);
+    };
+
+    let ret = unsafe { libc::kill(target, libc::SIGTERM) };
+    if ret != 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("Failed to signal pid {}", pid));
+    }
+
+    let start = Instant::now();
+    while is_running(pid) {
+        if start.elapsed() > timeout {
+            bail!("Server (pid {}) did not exit within {:?}", pid, timeout);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    Ok(pid)
+}
+
+#[cfg(not(unix))]
+pub fn stop(_path: &Path, _timeout: Duration) -> Result<u32> {
+    bail!("`serve --stop` is only supported on Unix")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_create_writes_and_drop_removes() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("serve.pid");
+
+        {
+            let _pid_file = PidFile::create(&path).unwrap();
+            assert_eq!(read_pid(&path).unwrap(), Some(std::process::id()));
+        }
+
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_create_refuses_live_pid() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("serve.pid");
+
+        let _first = PidFile::create(&path).unwrap();
+        assert!(PidFile::create(&path).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_replaces_stale_pid() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("serve.pid");
+
+        // A child that has exited and been reaped leaves a dead PID behind
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead_pid = child.id();
+        child.wait().unwrap();
+        std::fs::write(&path, format!("{}\n", dead_pid)).unwrap();
+
+        let _pid_file = PidFile::create(&path).unwrap();
+        assert_eq!(read_pid(&path).unwrap(), Some(std::process::id()));
+    }
+
+    #[test]
+    fn test_read_pid_missing_file() {
+        let tmp = TempDir::new().unwrap();
+        assert_eq!(read_pid(&tmp.path().join("nope.pid")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_read_pid_malformed() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("bad.pid");
+        std::fs::write(&path, "not a pid").unwrap();
+        assert!(read_pid(&path).is_err());
+    }
+
+    #[test]
+    fn test_read_pid_rejects_group_pids() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("bad.pid");
+        for contents in ["0", "2147483648", "4294967295"] {
+            std::fs::write(&path, contents).unwrap();
+            assert!(read_pid(&path).is_err(), "{}", contents);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stop_terminates_process() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("serve.pid");
+
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        std::fs::write(&path, format!("{}\n", child.id())).unwrap();
+
+        // Reap the child in the background so it doesn't linger as a zombie
+        let waiter = std::thread::spawn(move || child.wait());
+        let pid = stop(&path, Duration::from_secs(5)).unwrap();
+        assert!(pid > 0);
+        waiter.join().unwrap().unwrap();
+    }
+}
@@ -0,0 +1,270 @@
+//! Library-relative path resolution for the web server.
+//!
+//! Every handler that turns a client-supplied path into a filesystem path must
+//! go through this module so that requests can never reach outside the library
+//! root, whether via `..` components, absolute paths, or symlinks pointing
+//! elsewhere.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Why a requested path was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// Path is malformed (absolute, contains `..`, NUL bytes, etc.)
+    Invalid,
+    /// Path resolves (via symlinks) to a location outside the library
+    Escape,
+    /// Path does not exist
+    NotFound,
+}
+
+/// Validate the lexical shape of a client-supplied relative path.
+/// Only plain components are allowed; `.` segments are dropped.
+fn sanitize(requested: &str) -> Result<PathBuf, PathError> {
+    if requested.contains('\0') || requested.contains('\\') {
+        return Err(PathError::Invalid);
+    }
+
+    let mut clean = PathBuf::new();
+    for component in Path::new(requested).components() {
+        match component {
+            Component::Normal(part) => clean.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(PathError::Invalid);
+            }
+        }
+    }
+
+    if clean.as_os_str().is_empty() {
+        return Err(PathError::Invalid);
+    }
+
+    Ok(clean)
+}
+
+fn canonical_root(library_root: &Path) -> Result<PathBuf, PathError> {
+    library_root.canonicalize().map_err(|_| PathError::NotFound)
+}
+
+/// Resolve an existing library-relative path to its canonical absolute path,
+/// guaranteeing the result lies inside `library_root`.
+pub fn resolve_existing(library_root: &Path, requested: &str) -> Result<PathBuf, PathError> {
+    let relative = sanitize(requested)?;
+    let root = canonical_root(library_root)?;
+
+    let canonical = root
+        .join(&relative)
+        .canonicalize()
+        .map_err(|_| PathError::NotFound)?;
+
+    if !canonical.starts_with(&root) {
+        return Err(PathError::Escape);
+    }
+
+    Ok(canonical)
+}
+
+/// Resolve a library-relative path that may not exist yet (e.g. an upload
+/// target). The parent directory must exist and lie inside `library_root`,
+/// and the final component must not be a symlink leading outside it.
+pub fn resolve_new(library_root: &Path, requested: &str) -> Result<PathBuf, PathError> {
+    let relative = sanitize(requested)?;
+    let root = canonical_root(library_root)?;
+
+    let file_name = relative.file_name().ok_or(PathError::Invalid)?.to_owned();
+    let parent = match relative.parent() {
+        Some(p) if !p.as_os_str().is_empty() => root
+            .join(p)
+            .canonicalize()
+            .map_err(|_| PathError::NotFound)?,
+        _ => root.clone(),
+    };
+
+    if !parent.starts_with(&root) {
+        return Err(PathError::Escape);
+    }
+
+    let target = parent.join(file_name);
+    if target.symlink_metadata().is_ok() {
+        // Exists already: apply the same rules as for existing paths
+        let canonical = target.canonicalize().map_err(|_| PathError::Escape)?;
+        if !canonical.starts_with(&root) {
+            return Err(PathError::Escape);
+        }
+    }
+
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup() -> TempDir {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("photos/vacation")).unwrap();
+        fs::write(tmp.path().join("photos/vacation/beach.jpg"), b"jpg").unwrap();
+        tmp
+    }
+
+    #[test]
+    fn test_resolves_plain_relative_path() {
+        let tmp = setup();
+        let resolved = resolve_existing(tmp.path(), "photos/vacation/beach.jpg").unwrap();
+        assert_eq!(
+            resolved,
+            tmp.path()
+                .canonicalize()
+                .unwrap()
+                .join("photos/vacation/beach.jpg")
+        );
+    }
+
+    #[test]
+    fn test_ignores_current_dir_segments() {
+        let tmp = setup();
+        assert!(resolve_existing(tmp.path(), "./photos/./vacation/beach.jpg").is_ok());
+    }
+
+    #[test]
+    fn test_rejects_parent_dir_traversal() {
+        let tmp = setup();
+        assert_eq!(
+            resolve_existing(tmp.path(), "../etc/passwd"),
+            Err(PathError::Invalid)
+        );
+        assert_eq!(
+            resolve_existing(tmp.path(), "photos/../../etc/passwd"),
+            Err(PathError::Invalid)
+        );
+        // Even traversal that would land back inside the library is rejected
+        assert_eq!(
+            resolve_existing(tmp.path(), "photos/../photos/vacation/beach.jpg"),
+            Err(PathError::Invalid)
+        );
+    }
+
+    #[test]
+    fn test_rejects_absolute_paths() {
+        let tmp = setup();
+        assert_eq!(
+            resolve_existing(tmp.path(), "/etc/passwd"),
+            Err(PathError::Invalid)
+        );
+    }
+
+    #[test]
+    fn test_rejects_nul_and_backslash() {
+        let tmp = setup();
+        assert_eq!(
+            resolve_existing(tmp.path(), "photos\0/beach.jpg"),
+            Err(PathError::Invalid)
+        );
+        assert_eq!(
+            resolve_existing(tmp.path(), "..\\..\\etc\\passwd"),
+            Err(PathError::Invalid)
+        );
+    }
+
+    #[test]
+    fn test_rejects_empty_path() {
+        let tmp = setup();
+        assert_eq!(resolve_existing(tmp.path(), ""), Err(PathError::Invalid));
+        assert_eq!(resolve_existing(tmp.path(), "."), Err(PathError::Invalid));
+    }
+
+    #[test]
+    fn test_missing_file_is_not_found() {
+        let tmp = setup();
+        assert_eq!(
+            resolve_existing(tmp.path(), "photos/missing.jpg"),
+            Err(PathError::NotFound)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rejects_symlink_escape() {
+        let tmp = setup();
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("secret.txt"), b"secret").unwrap();
+
+        std::os::unix::fs::symlink(outside.path(), tmp.path().join("photos/link")).unwrap();
+        std::os::unix::fs::symlink(
+            outside.path().join("secret.txt"),
+            tmp.path().join("photos/secret.txt"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolve_existing(tmp.path(), "photos/link/secret.txt"),
+            Err(PathError::Escape)
+        );
+        assert_eq!(
+            resolve_existing(tmp.path(), "photos/secret.txt"),
+            Err(PathError::Escape)
+        );
+        assert_eq!(
+            resolve_new(tmp.path(), "photos/link/upload.jpg"),
+            Err(PathError::Escape)
+        );
+        assert_eq!(
+            resolve_new(tmp.path(), "photos/secret.txt"),
+            Err(PathError::Escape)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_allows_symlink_within_library() {
+        let tmp = setup();
+        std::os::unix::fs::symlink(
+            tmp.path().join("photos/vacation"),
+            tmp.path().join("photos/alias"),
+        )
+        .unwrap();
+
+        assert!(resolve_existing(tmp.path(), "photos/alias/beach.jpg").is_ok());
+    }
+
+    #[test]
+    fn test_resolve_new_in_existing_directory() {
+        let tmp = setup();
+        let target = resolve_new(tmp.path(), "photos/vacation/new.jpg").unwrap();
+        assert_eq!(
+            target,
+            tmp.path()
+                .canonicalize()
+                .unwrap()
+                .join("photos/vacation/new.jpg")
+        );
+    }
+
+    #[test]
+    fn test_resolve_new_at_library_root() {
+        let tmp = setup();
+        let target = resolve_new(tmp.path(), "new.jpg").unwrap();
+        assert_eq!(target, tmp.path().canonicalize().unwrap().join("new.jpg"));
+    }
+
+    #[test]
+    fn test_resolve_new_missing_parent() {
+        let tmp = setup();
+        assert_eq!(
+            resolve_new(tmp.path(), "nope/new.jpg"),
+            Err(PathError::NotFound)
+        );
+    }
+
+    #[test]
+    fn test_resolve_new_rejects_traversal() {
+        let tmp = setup();
+        assert_eq!(
+            resolve_new(tmp.path(), "../outside.jpg"),
+            Err(PathError::Invalid)
+        );
+    }
+}
@@ -1,13 +1,32 @@
 //! Word suggestion extraction for rename dialog.
 //!
-//! Extracts meaningful words from directory paths and file tags
-//! to suggest rename options.
+//! Extracts meaningful words from directory paths, file tags, and EXIF
+//! metadata to suggest rename options, ranked by frequency across the subtree.
 
 use std::collections::{HashMap, HashSet};
 
+/// EXIF facts about one file that can become rename suggestions
+#[derive(Debug, Clone, Default)]
+pub struct ExifHints {
+    pub camera_model: Option<String>,
+    /// Capture year and month (1-12)
+    pub captured: Option<(i32, u32)>,
+}
+
 /// Extract meaningful words from a directory path for rename suggestions.
 /// Words from file tags are weighted higher (counted 3x).
 pub fn extract_suggested_words(path: &str, file_tags: &[String]) -> Vec<String> {
+    extract_suggested_words_with_exif(path, file_tags, &[])
+}
+
+/// Like [`extract_suggested_words`], also proposing camera models and capture
+/// dates ("2024", "2024-03") from the EXIF of files in the subtree. Each file
+/// counts once per token, so the most common camera and dates rank first.
+pub fn extract_suggested_words_with_exif(
+    path: &str,
+    file_tags: &[String],
+    exif: &[ExifHints],
+) -> Vec<String> {
     let mut word_counts: HashMap<String, usize> = HashMap::new();
 
     // Delimiters for splitting
@@ -50,6 +69,19 @@ pub fn extract_suggested_words(path: &str, file_tags: &[String]) -> Vec<String>
         *word_counts.entry(tag.clone()).or_insert(0) += 3;
     }
 
+    // Add EXIF tokens, one count per file
+    for hints in exif {
+        if let Some(model) = hints.camera_model.as_deref().map(str::trim) {
+            if !model.is_empty() {
+                *word_counts.entry(model.to_string()).or_insert(0) += 1;
+            }
+        }
+        if let Some((year, month)) = hints.captured {
+            *word_counts.entry(year.to_string()).or_insert(0) += 1;
+            *word_counts.entry(format!("{}-{:02}", year, month)).or_insert(0) += 1;
+        }
+    }
+
     // Sort by frequency, then alphabetically
     let mut words: Vec<(String, usize)> = word_counts.into_iter().collect();
     words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
@@ -161,4 +193,60 @@ mod tests {
         assert!(result.contains(&"Photoshoot".to_string()));
         assert!(result.contains(&"Album".to_string()));
     }
+
+    fn hints(model: Option<&str>, captured: Option<(i32, u32)>) -> ExifHints {
+        ExifHints {
+            camera_model: model.map(str::to_string),
+            captured,
+        }
+    }
+
+    #[test]
+    fn test_exif_tokens_ranked_by_frequency() {
+        let exif = vec![
+            hints(Some("ILCE-7M3"), Some((2024, 3))),
+            hints(Some("ILCE-7M3"), Some((2024, 3))),
+            hints(Some("iPhone 15 Pro"), Some((2024, 4))),
+        ];
+        let result = extract_suggested_words_with_exif("trip", &[], &exif);
+
+        assert_eq!(result[..3], ["2024", "2024-03", "ILCE-7M3"]);
+        assert!(result.contains(&"iPhone 15 Pro".to_string()));
+        assert!(result.contains(&"2024-04".to_string()));
+        assert!(result.contains(&"trip".to_string()));
+    }
+
+    #[test]
+    fn test_exif_year_survives_numeric_filter() {
+        // Bare years are dropped from paths but kept when they come from EXIF
+        let result = extract_suggested_words_with_exif("2023", &[], &[hints(None, Some((2023, 12)))]);
+        assert_eq!(result, vec!["2023", "2023-12"]);
+    }
+
+    #[test]
+    fn test_ranking_is_stable_across_input_order() {
+        let exif = vec![
+            hints(Some("X100V"), Some((2022, 7))),
+            hints(Some("GR III"), Some((2023, 1))),
+            hints(None, None),
+            hints(Some("  "), Some((2022, 7))),
+        ];
+        let tags = vec!["street".to_string(), "night".to_string()];
+        let expected = extract_suggested_words_with_exif("city/walks", &tags, &exif);
+
+        let mut reversed_exif = exif.clone();
+        reversed_exif.reverse();
+        let reversed_tags: Vec<String> = tags.iter().rev().cloned().collect();
+        for _ in 0..10 {
+            assert_eq!(
+                extract_suggested_words_with_exif("walks/city", &reversed_tags, &reversed_exif),
+                expected
+            );
+        }
+        // Ties are broken alphabetically
+        assert_eq!(
+            expected,
+            vec!["night", "street", "2022", "2022-07", "2023", "2023-01", "GR III", "X100V", "city", "walks"]
+        );
+    }
 }
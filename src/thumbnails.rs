@@ -1,11 +1,18 @@
 use image::{DynamicImage, GenericImageView, RgbImage};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{self, AtomicU64};
 use std::sync::{Mutex, OnceLock};
 
-use crate::db::{Database, Directory};
+use crate::color::open_srgb;
+use crate::db::{Database, Directory, File};
+use crate::filenames::normalize;
+use crate::screenshot::{is_likely_screenshot, SCREENSHOT_TAG};
 
 // ==================== Media Type Detection ====================
 
@@ -19,6 +26,7 @@ pub fn is_image_file(path: &Path) -> bool {
     matches!(
         extension.as_str(),
         "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "tiff" | "tif"
+            | "svg" | "jp2" | "j2k" | "jpf" | "jpx"
     )
 }
 
@@ -35,6 +43,25 @@ pub fn is_video_file(path: &Path) -> bool {
     )
 }
 
+/// PDFs and office documents; thumbnails show the first page
+pub fn is_document_file(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    matches!(
+        extension.as_str(),
+        "pdf" | "doc" | "docx" | "odt" | "rtf" | "ppt" | "pptx" | "odp"
+    )
+}
+
+/// Whether picman can generate a thumbnail for the file (image, video, or document)
+pub fn is_thumbnailable_file(path: &Path) -> bool {
+    is_image_file(path) || is_video_file(path) || is_document_file(path)
+}
+
 // ==================== EXIF Orientation ====================
 
 /// Read EXIF orientation and apply rotation/flip to image
@@ -63,6 +90,158 @@ pub fn apply_exif_orientation(path: &Path, img: DynamicImage) -> DynamicImage {
     }
 }
 
+// ==================== Image Decoding ====================
+
+/// Decoded by the `image` crate (multi-page TIFFs yield their first page)
+fn is_natively_decodable(path: &Path) -> bool {
+    matches!(
+        lowercase_extension(path).as_str(),
+        "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "tiff" | "tif"
+    )
+}
+
+/// Shown as-is by the TUI and browsers when no thumbnail exists. Everything
+/// else (TIFF, SVG, JPEG 2000) is always displayed through a thumbnail.
+pub fn is_directly_displayable(path: &Path) -> bool {
+    matches!(
+        lowercase_extension(path).as_str(),
+        "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp"
+    )
+}
+
+fn lowercase_extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+/// How images of a given format get decoded on this machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageDecoder {
+    /// The `image` crate
+    Native,
+    /// An external converter found on PATH
+    External(&'static str),
+    /// Neither; the hint names what to install
+    Unavailable(&'static str),
+}
+
+/// External converters to try for a format, in order of preference
+fn converters_for(extension: &str) -> (&'static [&'static str], &'static str) {
+    match extension {
+        "svg" => (&["rsvg-convert", "magick", "convert"], "librsvg (rsvg-convert) or ImageMagick"),
+        "jp2" | "j2k" | "jpf" | "jpx" => (&["opj_decompress", "magick", "convert"], "OpenJPEG (opj_decompress) or ImageMagick"),
+        // Fallback for TIFF/BMP variants the image crate can't read
+        _ => (&["magick", "convert"], "ImageMagick"),
+    }
+}
+
+/// Pick the decoder for an image file based on its extension and the tools installed
+pub fn image_decoder(path: &Path) -> ImageDecoder {
+    if is_natively_decodable(path) {
+        return ImageDecoder::Native;
+    }
+    match find_converter(path) {
+        Ok(tool) => ImageDecoder::External(tool),
+        Err(hint) => ImageDecoder::Unavailable(hint),
+    }
+}
+
+/// The first installed converter for the file's format, or an install hint
+fn find_converter(path: &Path) -> Result<&'static str, &'static str> {
+    let (tools, hint) = converters_for(&lowercase_extension(path));
+    tools.iter().copied().find(|tool| tool_available(tool)).ok_or(hint)
+}
+
+/// Whether `name` is an executable on PATH (cached per process)
+pub(crate) fn tool_available(name: &'static str) -> bool {
+    static TOOLS: OnceLock<Mutex<HashMap<&'static str, bool>>> = OnceLock::new();
+    let mut tools = match TOOLS.get_or_init(|| Mutex::new(HashMap::new())).lock() {
+        Ok(tools) => tools,
+        Err(_) => return false,
+    };
+    *tools.entry(name).or_insert_with(|| {
+        std::env::var_os("PATH").is_some_and(|paths| {
+            std::env::split_paths(&paths).any(|dir| dir.join(name).is_file())
+        })
+    })
+}
+
+/// Decode an image, falling back to an external converter for formats (or
+/// format variants) the `image` crate can't read. Pixels come back in sRGB;
+/// an embedded ICC profile is applied (see [`crate::color`]).
+pub fn open_image(path: &Path) -> Result<DynamicImage, ThumbnailError> {
+    let native_error = if is_natively_decodable(path) {
+        match open_srgb(path) {
+            Ok(img) => return Ok(img),
+            Err(e) => Some(ThumbnailError::from_image(e)),
+        }
+    } else {
+        None
+    };
+
+    match find_converter(path) {
+        Ok(tool) => convert_with(tool, path),
+        Err(hint) => Err(native_error.unwrap_or_else(|| {
+            ThumbnailError::transient(format!("no decoder installed (install {})", hint))
+        })),
+    }
+}
+
+/// Temporary files written so far by this process, for [`unique_temp_suffix`]
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// `<pid>-<n>`, different for every temporary file any process writes, so
+/// concurrent conversions and writes never share one
+fn unique_temp_suffix() -> String {
+    format!("{}-{}", std::process::id(), TEMP_COUNTER.fetch_add(1, atomic::Ordering::Relaxed))
+}
+
+/// Convert `path` to a temporary PNG with an external tool and decode that
+fn convert_with(tool: &str, path: &Path) -> Result<DynamicImage, ThumbnailError> {
+    let output = std::env::temp_dir().join(format!("picman-convert-{}.png", unique_temp_suffix()));
+
+    let mut command = Command::new(tool);
+    match tool {
+        "rsvg-convert" => {
+            command
+                .args(["--format", "png", "--keep-aspect-ratio", "--height"])
+                .arg(THUMBNAIL_MAX_HEIGHT.to_string())
+                .arg("--output")
+                .arg(&output)
+                .arg(path);
+        }
+        "opj_decompress" => {
+            command.arg("-i").arg(path).arg("-o").arg(&output);
+        }
+        // ImageMagick: `[0]` selects the first page/frame
+        _ => {
+            let mut input = path.as_os_str().to_owned();
+            input.push("[0]");
+            command.arg(input).arg(&output);
+        }
+    }
+
+    let status = command
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_err(|e| ThumbnailError::transient(format!("could not run {}: {}", tool, e)));
+
+    let result = match status {
+        // Converters keep the source's profile in the PNG they write
+        Ok(status) if status.success() && output.exists() => {
+            open_srgb(&output).map_err(ThumbnailError::from_image)
+        }
+        Ok(_) => Err(ThumbnailError::permanent(format!("{} could not convert the image", tool))),
+        Err(e) => Err(e),
+    };
+
+    let _ = std::fs::remove_file(&output);
+    result
+}
+
 // ==================== Thumbnail Cache ====================
 
 // In-memory cache mapping original path -> thumbnail path
@@ -98,6 +277,18 @@ pub fn get_web_thumbnail_dir() -> Option<PathBuf> {
 
 // ==================== Thumbnail Path Computation ====================
 
+/// Hash a path in its composed (NFC) spelling, so a file reached through a
+/// decomposed name from the scan and a composed one from the database maps
+/// to the same thumbnail. Paths already composed hash as they always have.
+fn hash_path(path: &Path, hasher: &mut impl std::hash::Hasher) {
+    use std::hash::Hash;
+
+    match path.to_str() {
+        Some(s) => Path::new(normalize(s).as_ref()).hash(hasher),
+        None => path.hash(hasher),
+    }
+}
+
 /// Generate a thumbnail path for an image based on its path hash
 pub(crate) fn get_thumbnail_path(original_path: &Path) -> Option<PathBuf> {
     use std::collections::hash_map::DefaultHasher;
@@ -105,7 +296,7 @@ pub(crate) fn get_thumbnail_path(original_path: &Path) -> Option<PathBuf> {
 
     let cache_dir = get_thumbnail_dir()?;
     let mut hasher = DefaultHasher::new();
-    original_path.hash(&mut hasher);
+    hash_path(original_path, &mut hasher);
 
     // Include mtime in hash so thumbnails regenerate when file changes
     let mtime = std::fs::metadata(original_path).ok()?.modified().ok()?;
@@ -122,7 +313,7 @@ pub fn compute_thumbnail_path(original_path: &Path, mtime: std::time::SystemTime
 
     let cache_dir = get_thumbnail_dir()?;
     let mut hasher = DefaultHasher::new();
-    original_path.hash(&mut hasher);
+    hash_path(original_path, &mut hasher);
     mtime.hash(&mut hasher);
 
     Some(cache_dir.join(format!("{:016x}.jpg", hasher.finish())))
@@ -140,14 +331,28 @@ pub(crate) fn get_video_thumbnail_path(video_path: &Path) -> Option<PathBuf> {
         .map(|p| p.with_file_name(format!("vid_{}", p.file_name().unwrap().to_string_lossy())))
 }
 
+/// Compute document thumbnail path for a given path and mtime
+pub fn compute_document_thumbnail_path(original_path: &Path, mtime: std::time::SystemTime) -> Option<PathBuf> {
+    compute_thumbnail_path(original_path, mtime)
+        .map(|p| p.with_file_name(format!("doc_{}", p.file_name().unwrap().to_string_lossy())))
+}
+
+/// Get document thumbnail path with "doc_" prefix
+pub(crate) fn get_document_thumbnail_path(document_path: &Path) -> Option<PathBuf> {
+    get_thumbnail_path(document_path)
+        .map(|p| p.with_file_name(format!("doc_{}", p.file_name().unwrap().to_string_lossy())))
+}
+
 // ==================== Thumbnail Existence Checks ====================
 
-/// Check if a thumbnail exists for a file (image or video)
+/// Check if a thumbnail exists for a file (image, video, or document)
 pub fn has_thumbnail(path: &Path) -> bool {
     if is_image_file(path) {
         get_thumbnail_path(path).map(|p| p.exists()).unwrap_or(false)
     } else if is_video_file(path) {
         get_video_thumbnail_path(path).map(|p| p.exists()).unwrap_or(false)
+    } else if is_document_file(path) {
+        get_document_thumbnail_path(path).map(|p| p.exists()).unwrap_or(false)
     } else {
         false
     }
@@ -175,22 +380,26 @@ fn get_cached_image_thumbnail(image_path: &Path) -> Option<PathBuf> {
     None
 }
 
-/// Get cached thumbnail for a video file (does NOT generate)
-fn get_cached_video_thumbnail(video_path: &Path) -> Option<PathBuf> {
+/// Get cached thumbnail for a video or document (does NOT generate).
+/// `thumb_path_for` is `get_video_thumbnail_path` or `get_document_thumbnail_path`.
+fn get_cached_rendered_thumbnail(
+    path: &Path,
+    thumb_path_for: fn(&Path) -> Option<PathBuf>,
+) -> Option<PathBuf> {
     let mut cache = get_thumbnail_cache().lock().ok()?;
 
     // Check in-memory cache first
-    if let Some(thumb_path) = cache.get(video_path) {
+    if let Some(thumb_path) = cache.get(path) {
         if thumb_path.exists() {
             return Some(thumb_path.clone());
         }
     }
 
-    let thumb_path = get_video_thumbnail_path(video_path)?;
+    let thumb_path = thumb_path_for(path)?;
 
     // If thumbnail exists on disk, use it
     if thumb_path.exists() {
-        cache.insert(video_path.to_path_buf(), thumb_path.clone());
+        cache.insert(path.to_path_buf(), thumb_path.clone());
         return Some(thumb_path);
     }
 
@@ -198,17 +407,22 @@ fn get_cached_video_thumbnail(video_path: &Path) -> Option<PathBuf> {
 }
 
 /// Get the preview path for a file (thumbnail or original) and whether it's a thumbnail.
-/// For images: returns cached thumbnail if exists, otherwise original
-/// For videos: returns cached thumbnail if exists, otherwise None
+/// For images: returns cached thumbnail if exists, otherwise original (None for
+/// TIFF/SVG/JPEG 2000, which need a generated thumbnail)
+/// For videos and documents: returns cached thumbnail if exists, otherwise None
 pub fn get_preview_path_for_file(file_path: &Path) -> Option<(PathBuf, bool)> {
     if is_image_file(file_path) {
         match get_cached_image_thumbnail(file_path) {
             Some(thumb) => Some((thumb, true)),
-            None => Some((file_path.to_path_buf(), false)), // Fall back to original image
+            // Fall back to original image, unless it needs conversion first
+            None if is_directly_displayable(file_path) => Some((file_path.to_path_buf(), false)),
+            None => None,
         }
     } else if is_video_file(file_path) {
         // For videos, only use cached thumbnail (don't generate during preload)
-        get_cached_video_thumbnail(file_path).map(|thumb| (thumb, true))
+        get_cached_rendered_thumbnail(file_path, get_video_thumbnail_path).map(|thumb| (thumb, true))
+    } else if is_document_file(file_path) {
+        get_cached_rendered_thumbnail(file_path, get_document_thumbnail_path).map(|thumb| (thumb, true))
     } else {
         None
     }
@@ -221,7 +435,8 @@ pub fn generate_image_thumbnail(image_path: &Path) -> Option<PathBuf> {
     let thumb_path = get_thumbnail_path(image_path)?;
 
     // Generate thumbnail: load, apply EXIF, resize, save
-    let img = image::open(image_path).ok()?;
+    crate::workers::throttle_read(image_path);
+    let img = open_image(image_path).ok()?;
     let img = apply_exif_orientation(image_path, img);
 
     // Resize to max height, preserving aspect ratio
@@ -280,43 +495,190 @@ pub fn generate_video_thumbnail(video_path: &Path) -> Option<PathBuf> {
     }
 }
 
+/// A video's length in seconds, read with ffprobe. `Ok(None)` when ffprobe
+/// can't make sense of the file; an error when ffprobe isn't installed.
+pub fn probe_video_duration(video_path: &Path) -> std::io::Result<Option<f64>> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(video_path)
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|d| d.is_finite() && *d >= 0.0))
+}
+
+/// Generate thumbnail for a document by rendering its first page
+pub fn generate_document_thumbnail(document_path: &Path) -> Option<PathBuf> {
+    let thumb_path = get_document_thumbnail_path(document_path)?;
+
+    render_document_page(document_path, &thumb_path, PageFit::Height(THUMBNAIL_MAX_HEIGHT)).ok()?;
+
+    if let Ok(mut cache) = get_thumbnail_cache().lock() {
+        cache.insert(document_path.to_path_buf(), thumb_path.clone());
+    }
+    Some(thumb_path)
+}
+
+/// Target size for a rendered document page (aspect ratio is preserved)
+#[derive(Clone, Copy)]
+enum PageFit {
+    Width(u32),
+    Height(u32),
+}
+
+/// Render the first page of a document to `output` (JPEG) using poppler's
+/// `pdftoppm`. Office documents are converted to PDF with LibreOffice first.
+fn render_document_page(document: &Path, output: &Path, fit: PageFit) -> Result<(), ThumbnailError> {
+    let is_pdf = document
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("pdf"));
+
+    crate::workers::throttle_read(document);
+
+    if is_pdf {
+        return render_pdf_page(document, output, fit);
+    }
+
+    // soffice names its output after the input's stem, so give each
+    // conversion its own directory
+    let work_dir = std::env::temp_dir().join(format!(
+        "picman-doc-{}-{}",
+        std::process::id(),
+        output.file_stem().and_then(|s| s.to_str()).unwrap_or("page")
+    ));
+    std::fs::create_dir_all(&work_dir).map_err(|e| ThumbnailError::transient(e.to_string()))?;
+
+    let result = (|| {
+        let status = Command::new("soffice")
+            .args(["--headless", "--convert-to", "pdf", "--outdir"])
+            .arg(&work_dir)
+            .arg(document)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .map_err(|e| ThumbnailError::transient(format!("could not run soffice: {}", e)))?;
+
+        let stem = document.file_stem().unwrap_or_default();
+        let pdf = work_dir.join(stem).with_extension("pdf");
+        if !status.success() || !pdf.exists() {
+            return Err(ThumbnailError::permanent("soffice could not convert the document"));
+        }
+        render_pdf_page(&pdf, output, fit)
+    })();
+
+    let _ = std::fs::remove_dir_all(&work_dir);
+    result
+}
+
+fn render_pdf_page(pdf: &Path, output: &Path, fit: PageFit) -> Result<(), ThumbnailError> {
+    let (scale_x, scale_y) = match fit {
+        PageFit::Width(w) => (w.to_string(), "-1".to_string()),
+        PageFit::Height(h) => ("-1".to_string(), h.to_string()),
+    };
+
+    // pdftoppm appends ".jpg" to the output prefix
+    let status = Command::new("pdftoppm")
+        .args(["-f", "1", "-l", "1", "-singlefile", "-jpeg"])
+        .args(["-scale-to-x", &scale_x, "-scale-to-y", &scale_y])
+        .arg(pdf)
+        .arg(output.with_extension(""))
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_err(|e| ThumbnailError::transient(format!("could not run pdftoppm: {}", e)))?;
+
+    if status.success() && output.exists() {
+        Ok(())
+    } else {
+        Err(ThumbnailError::permanent("pdftoppm could not render the first page"))
+    }
+}
+
 // ==================== Web Thumbnail (Grid) Generation ====================
 
 /// Generate a web thumbnail path for an image based on its path hash.
 /// Canonicalizes the path before hashing so that relative and absolute paths
 /// produce the same hash (e.g. `./photo.jpg` and `/home/user/lib/photo.jpg`).
 pub fn get_web_thumbnail_path(original_path: &Path) -> Option<PathBuf> {
+    let cache_dir = get_web_thumbnail_dir()?;
+    let key = web_cache_key(original_path)?;
+    Some(cache_dir.join(format!("{:016x}.jpg", key)))
+}
+
+/// Hash of a file's canonical path and mtime, shared by its web thumbnail and
+/// its resized versions
+fn web_cache_key(original_path: &Path) -> Option<u64> {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
 
-    let cache_dir = get_web_thumbnail_dir()?;
     let canonical = original_path.canonicalize().ok()?;
     let mut hasher = DefaultHasher::new();
-    canonical.hash(&mut hasher);
+    hash_path(&canonical, &mut hasher);
 
     let mtime = std::fs::metadata(original_path).ok()?.modified().ok()?;
     mtime.hash(&mut hasher);
 
-    Some(cache_dir.join(format!("{:016x}.jpg", hasher.finish())))
+    Some(hasher.finish())
 }
 
 /// Check if a web thumbnail exists for a file
 pub fn has_web_thumbnail(path: &Path) -> bool {
     if is_image_file(path) {
         get_web_thumbnail_path(path).map(|p| p.exists()).unwrap_or(false)
-    } else if is_video_file(path) {
-        // Video web thumbnails use same path (extracted frame is already small)
+    } else if is_video_file(path) || is_document_file(path) {
+        // Video and document web thumbnails use same path (rendered frame is already small)
         get_web_thumbnail_path(path).map(|p| p.exists()).unwrap_or(false)
     } else {
         false
     }
 }
 
+/// Why a web thumbnail could not be generated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThumbnailError {
+    pub reason: String,
+    /// Retrying won't help until the file changes (corrupt or unsupported
+    /// content), as opposed to IO problems or a missing ffmpeg
+    pub permanent: bool,
+}
+
+impl ThumbnailError {
+    fn permanent(reason: impl Into<String>) -> Self {
+        Self { reason: reason.into(), permanent: true }
+    }
+
+    fn transient(reason: impl Into<String>) -> Self {
+        Self { reason: reason.into(), permanent: false }
+    }
+
+    fn from_image(e: image::ImageError) -> Self {
+        match e {
+            image::ImageError::IoError(e) => Self::transient(e.to_string()),
+            other => Self::permanent(other.to_string()),
+        }
+    }
+}
+
 /// Generate a small (400px wide) web thumbnail for grid display
 pub fn generate_web_thumbnail(image_path: &Path) -> Option<PathBuf> {
-    let thumb_path = get_web_thumbnail_path(image_path)?;
+    try_generate_web_thumbnail(image_path).ok()
+}
+
+/// Like [`generate_web_thumbnail`], but reports why generation failed
+pub fn try_generate_web_thumbnail(image_path: &Path) -> Result<PathBuf, ThumbnailError> {
+    let thumb_path = get_web_thumbnail_path(image_path)
+        .ok_or_else(|| ThumbnailError::transient("file or cache directory not accessible"))?;
 
-    let img = image::open(image_path).ok()?;
+    crate::workers::throttle_read(image_path);
+    let img = open_image(image_path)?;
     let img = apply_exif_orientation(image_path, img);
 
     // Resize to max width, preserving aspect ratio
@@ -330,38 +692,226 @@ pub fn generate_web_thumbnail(image_path: &Path) -> Option<PathBuf> {
         img
     };
 
-    let mut output = std::fs::File::create(&thumb_path).ok()?;
+    let mut output = std::fs::File::create(&thumb_path)
+        .map_err(|e| ThumbnailError::transient(e.to_string()))?;
     let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut output, WEB_THUMBNAIL_QUALITY);
-    img.to_rgb8().write_with_encoder(encoder).ok()?;
+    img.to_rgb8()
+        .write_with_encoder(encoder)
+        .map_err(ThumbnailError::from_image)?;
 
-    Some(thumb_path)
+    Ok(thumb_path)
 }
 
 /// Generate a small web thumbnail from a video file using ffmpeg
 pub fn generate_web_video_thumbnail(video_path: &Path) -> Option<PathBuf> {
-    let thumb_path = get_web_thumbnail_path(video_path)?;
+    try_generate_web_video_thumbnail(video_path).ok()
+}
+
+/// Like [`generate_web_video_thumbnail`], but reports why generation failed
+pub fn try_generate_web_video_thumbnail(video_path: &Path) -> Result<PathBuf, ThumbnailError> {
+    let thumb_path = get_web_thumbnail_path(video_path)
+        .ok_or_else(|| ThumbnailError::transient("file or cache directory not accessible"))?;
+    let (Some(input), Some(output)) = (video_path.to_str(), thumb_path.to_str()) else {
+        return Err(ThumbnailError::permanent("path is not valid UTF-8"));
+    };
 
     let scale_filter = format!("scale={}:-1", WEB_THUMBNAIL_MAX_WIDTH);
     let status = Command::new("ffmpeg")
         .args([
             "-y",
-            "-i", video_path.to_str()?,
+            "-i", input,
             "-ss", "00:00:01",
             "-vframes", "1",
             "-vf", &scale_filter,
             "-q:v", "5",
-            thumb_path.to_str()?,
+            output,
         ])
         .stdout(std::process::Stdio::null())
         .stderr(std::process::Stdio::null())
         .status()
-        .ok()?;
+        .map_err(|e| ThumbnailError::transient(format!("could not run ffmpeg: {}", e)))?;
 
     if status.success() && thumb_path.exists() {
-        Some(thumb_path)
+        Ok(thumb_path)
     } else {
-        None
+        Err(ThumbnailError::permanent("ffmpeg could not extract a frame"))
+    }
+}
+
+/// Generate a small web thumbnail from the first page of a document
+pub fn try_generate_web_document_thumbnail(document_path: &Path) -> Result<PathBuf, ThumbnailError> {
+    let thumb_path = get_web_thumbnail_path(document_path)
+        .ok_or_else(|| ThumbnailError::transient("file or cache directory not accessible"))?;
+
+    render_document_page(document_path, &thumb_path, PageFit::Width(WEB_THUMBNAIL_MAX_WIDTH))?;
+    Ok(thumb_path)
+}
+
+// ==================== Resized Images (web /img) ====================
+
+/// Largest side `/img` will produce
+pub const MAX_RESIZE_DIMENSION: u32 = 4096;
+const RESIZED_QUALITY: u8 = 82;
+
+/// Sides `/img` actually renders; requests are rounded up to the next one,
+/// so a client trying every size can't fill the cache or keep the CPU busy
+const RESIZE_BUCKETS: [u32; 11] = [
+    100, 200, 300, 400, 600, 800, 1200, 1600, 2400, 3200, MAX_RESIZE_DIMENSION,
+];
+
+/// How a resized image fills the requested box
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeFit {
+    /// Fit inside the box, keeping the aspect ratio (never enlarged)
+    Contain,
+    /// Fill the box exactly, cropping the overflow around the center
+    Cover,
+}
+
+impl ResizeFit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResizeFit::Contain => "contain",
+            ResizeFit::Cover => "cover",
+        }
+    }
+}
+
+impl std::str::FromStr for ResizeFit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "contain" => Ok(ResizeFit::Contain),
+            "cover" => Ok(ResizeFit::Cover),
+            other => Err(format!("unknown fit '{}' (expected contain or cover)", other)),
+        }
+    }
+}
+
+/// A requested size; a missing side is unconstrained
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResizeSpec {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fit: ResizeFit,
+}
+
+impl ResizeSpec {
+    /// Check a request: at least one side, both for `cover`, each within
+    /// 1..=[`MAX_RESIZE_DIMENSION`]. Each side is rounded up to a size bucket.
+    pub fn new(width: Option<u32>, height: Option<u32>, fit: ResizeFit) -> Result<Self, String> {
+        if width.is_none() && height.is_none() {
+            return Err("w or h is required".to_string());
+        }
+        if fit == ResizeFit::Cover && (width.is_none() || height.is_none()) {
+            return Err("fit=cover needs both w and h".to_string());
+        }
+        for side in [width, height].into_iter().flatten() {
+            if side == 0 || side > MAX_RESIZE_DIMENSION {
+                return Err(format!("sizes must be between 1 and {}", MAX_RESIZE_DIMENSION));
+            }
+        }
+        let bucket = |side: u32| RESIZE_BUCKETS.into_iter().find(|&b| b >= side).unwrap_or(MAX_RESIZE_DIMENSION);
+        Ok(Self { width: width.map(bucket), height: height.map(bucket), fit })
+    }
+
+    fn bounds(&self) -> (u32, u32) {
+        (self.width.unwrap_or(u32::MAX), self.height.unwrap_or(u32::MAX))
+    }
+
+    /// Whether an image of this size has enough pixels to produce the box
+    fn covered_by(&self, width: u32, height: u32) -> bool {
+        self.width.is_none_or(|w| width >= w) && self.height.is_none_or(|h| height >= h)
+    }
+
+    fn apply(&self, img: DynamicImage) -> DynamicImage {
+        let (w, h) = self.bounds();
+        let filter = image::imageops::FilterType::Lanczos3;
+        match self.fit {
+            ResizeFit::Cover => img.resize_to_fill(w, h, filter),
+            ResizeFit::Contain if img.width() > w || img.height() > h => img.resize(w, h, filter),
+            ResizeFit::Contain => img,
+        }
+    }
+}
+
+/// Get the resized image cache directory (~/.cache/picman/resized)
+pub fn get_resized_dir() -> Option<PathBuf> {
+    let home = std::env::var("HOME").ok()?;
+    let cache_dir = PathBuf::from(home).join(".cache/picman/resized");
+    std::fs::create_dir_all(&cache_dir).ok()?;
+    Some(cache_dir)
+}
+
+/// Cache path for one size of a file. The name starts with the file's web
+/// thumbnail key, so every size of a file is orphaned together when it
+/// changes.
+pub fn get_resized_path(original_path: &Path, spec: &ResizeSpec) -> Option<PathBuf> {
+    let cache_dir = get_resized_dir()?;
+    let key = web_cache_key(original_path)?;
+    Some(cache_dir.join(format!(
+        "{:016x}_{}x{}_{}.jpg",
+        key,
+        spec.width.unwrap_or(0),
+        spec.height.unwrap_or(0),
+        spec.fit.as_str()
+    )))
+}
+
+/// Cached resized version of a file, or None if it hasn't been generated
+pub fn get_cached_resized(original_path: &Path, spec: &ResizeSpec) -> Option<PathBuf> {
+    get_resized_path(original_path, spec).filter(|p| p.exists())
+}
+
+/// Generate (or regenerate) a resized version of an image, video, or document
+pub fn generate_resized(original_path: &Path, spec: &ResizeSpec) -> Result<PathBuf, ThumbnailError> {
+    let out_path = get_resized_path(original_path, spec)
+        .ok_or_else(|| ThumbnailError::transient("file or cache directory not accessible"))?;
+
+    let img = spec.apply(resize_source(original_path, spec)?);
+
+    // Written beside the final name under a name of its own, so concurrent
+    // requests for the same size never rename half a file into place
+    let tmp_path = out_path.with_extension(format!("jpg.{}.tmp", unique_temp_suffix()));
+    let mut output = std::fs::File::create(&tmp_path)
+        .map_err(|e| ThumbnailError::transient(e.to_string()))?;
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut output, RESIZED_QUALITY);
+    if let Err(e) = img.to_rgb8().write_with_encoder(encoder) {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(ThumbnailError::from_image(e));
     }
+    std::fs::rename(&tmp_path, &out_path).map_err(|e| ThumbnailError::transient(e.to_string()))?;
+
+    Ok(out_path)
+}
+
+/// The image to resize from. The cached 1440p thumbnail is used when it's big
+/// enough, which saves decoding a RAW or a large JPEG again; videos and
+/// documents only have their rendered thumbnail.
+fn resize_source(path: &Path, spec: &ResizeSpec) -> Result<DynamicImage, ThumbnailError> {
+    let cached = if is_image_file(path) {
+        get_cached_image_thumbnail(path)
+    } else if is_video_file(path) {
+        get_cached_rendered_thumbnail(path, get_video_thumbnail_path)
+    } else if is_document_file(path) {
+        get_cached_rendered_thumbnail(path, get_document_thumbnail_path)
+    } else {
+        return Err(ThumbnailError::permanent("not an image, video, or document"));
+    };
+
+    if let Some(thumb) = cached.and_then(|p| image::open(p).ok()) {
+        if !is_image_file(path) || spec.covered_by(thumb.width(), thumb.height()) {
+            return Ok(thumb);
+        }
+    }
+    if !is_image_file(path) {
+        return Err(ThumbnailError::transient("no thumbnail rendered yet"));
+    }
+
+    crate::workers::throttle_read(path);
+    let img = open_image(path)?;
+    Ok(apply_exif_orientation(path, img))
 }
 
 // ==================== Directory Preview Generation ====================
@@ -387,7 +937,7 @@ fn calc_grid_layout(n: usize) -> (usize, usize) {
 }
 
 /// Get the directory preview cache directory (~/.cache/picman/dir_previews)
-fn get_dir_preview_dir() -> Option<PathBuf> {
+pub(crate) fn get_dir_preview_dir() -> Option<PathBuf> {
     let home = std::env::var("HOME").ok()?;
     let cache_dir = PathBuf::from(home).join(".cache/picman/dir_previews");
     std::fs::create_dir_all(&cache_dir).ok()?;
@@ -415,15 +965,26 @@ pub fn has_dir_preview(dir_id: i64) -> bool {
     get_cached_dir_preview(dir_id).is_some()
 }
 
-/// Load an image, applying EXIF and using cached thumbnail if available
+/// Load an image, applying EXIF and using cached thumbnail if available.
+/// Videos contribute their poster frame, extracted with ffmpeg if it isn't
+/// cached yet.
 fn load_image_for_composite(path: &Path) -> Option<DynamicImage> {
+    if is_video_file(path) {
+        let poster = get_video_thumbnail_path(path)
+            .filter(|p| p.exists())
+            .or_else(|| generate_video_thumbnail(path))?;
+        return image::open(&poster).ok();
+    }
+
     // Prefer cached thumbnail
     if let Some(thumb_path) = get_thumbnail_path(path).filter(|p| p.exists()) {
+        crate::workers::throttle_read(&thumb_path);
         return image::open(&thumb_path).ok();
     }
 
     // Fall back to original with EXIF applied
-    let img = image::open(path).ok()?;
+    crate::workers::throttle_read(path);
+    let img = open_image(path).ok()?;
     Some(apply_exif_orientation(path, img))
 }
 
@@ -510,103 +1071,258 @@ pub fn generate_dir_preview_from_paths(dir_id: i64, images: &[PathBuf]) -> Optio
 
 // ==================== Standalone Preview Image Collection ====================
 
-/// Minimal state needed for background directory preview generation
-pub struct TempPreviewState {
-    pub library_path: PathBuf,
-    pub db: Database,
-    pub directories: Vec<Directory>,
+/// Which images make up a directory's preview collage (`[previews] cover`
+/// in the config). Screenshots and rejected files are left out under every
+/// order unless a directory holds nothing else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewCover {
+    /// Highest rated first, then the most recent; unrated after rated
+    #[default]
+    Rating,
+    /// Most recently modified first
+    Recent,
+    /// A fixed shuffle per directory
+    Random,
 }
 
-/// Collect preview images using TempPreviewState (requires DB access)
-pub fn collect_preview_images_standalone(state: &TempPreviewState, dir: &Directory) -> Vec<PathBuf> {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
+/// Files tagged this are never picked for a directory preview
+pub const REJECTED_TAG: &str = "rejected";
 
-    let mut images = Vec::new();
+/// Minimal state needed for background directory preview generation
+pub struct TempPreviewState<'a> {
+    pub library_path: PathBuf,
+    pub db: &'a Database,
+    pub directories: &'a [Directory],
+    pub cover: PreviewCover,
+}
 
+/// Collect up to 12 image paths for a directory preview: the best image
+/// below each of up to 12 subdirectories, or without subdirectories the
+/// best 12 images in the directory itself. Video-only directories use their
+/// videos, whose poster frames go into the collage.
+pub fn collect_preview_images_standalone(state: &TempPreviewState<'_>, dir: &Directory) -> Vec<PathBuf> {
     let child_dirs: Vec<&Directory> = state
         .directories
         .iter()
         .filter(|d| d.parent_id == Some(dir.id))
         .collect();
 
-    if !child_dirs.is_empty() {
-        let mut hasher = DefaultHasher::new();
-        dir.id.hash(&mut hasher);
-        let seed = hasher.finish();
-
-        let mut selected_dirs: Vec<_> = child_dirs.iter().enumerate().collect();
-        selected_dirs.sort_by_key(|(i, _)| {
-            let mut h = DefaultHasher::new();
-            seed.hash(&mut h);
-            i.hash(&mut h);
-            h.finish()
-        });
-
-        for (_, subdir) in selected_dirs.into_iter().take(DIR_PREVIEW_MAX_IMAGES) {
-            if let Some(img) = find_image_in_dir_recursive_standalone(state, subdir.id) {
-                images.push(img);
-            }
-        }
-    } else {
-        if let Ok(files) = state.db.get_files_in_directory(dir.id) {
-            let image_files: Vec<_> = files
-                .into_iter()
-                .filter(|f| {
-                    let path = get_file_path_standalone(state, dir, &f.filename);
-                    is_image_file(&path)
-                })
-                .collect();
-
-            let mut hasher = DefaultHasher::new();
-            dir.id.hash(&mut hasher);
-            let seed = hasher.finish();
-
-            let mut indexed: Vec<_> = image_files.iter().enumerate().collect();
-            indexed.sort_by_key(|(i, _)| {
-                let mut h = DefaultHasher::new();
-                seed.hash(&mut h);
-                i.hash(&mut h);
-                h.finish()
-            });
-
-            for (_, file) in indexed.into_iter().take(DIR_PREVIEW_MAX_IMAGES) {
-                images.push(get_file_path_standalone(state, dir, &file.filename));
-            }
-        }
+    if child_dirs.is_empty() {
+        return cover_files(state, dir)
+            .into_iter()
+            .take(DIR_PREVIEW_MAX_IMAGES)
+            .map(|f| get_file_path_standalone(state, dir, &f.filename))
+            .collect();
     }
 
-    images
+    let mut covers: Vec<(File, PathBuf)> = child_dirs
+        .into_iter()
+        .filter_map(|child| find_image_in_dir_recursive_standalone(state, child.id))
+        .collect();
+    covers.sort_by(|(a, _), (b, _)| cover_order(a, b, state.cover, dir.id));
+    covers
+        .into_iter()
+        .take(DIR_PREVIEW_MAX_IMAGES)
+        .map(|(_, path)| path)
+        .collect()
 }
 
-fn find_image_in_dir_recursive_standalone(state: &TempPreviewState, dir_id: i64) -> Option<PathBuf> {
+/// The best cover image in a directory, or failing that in the first of its
+/// subdirectories (recursively) that has one
+fn find_image_in_dir_recursive_standalone(state: &TempPreviewState<'_>, dir_id: i64) -> Option<(File, PathBuf)> {
     let dir = state.directories.iter().find(|d| d.id == dir_id)?;
 
-    if let Ok(files) = state.db.get_files_in_directory(dir_id) {
-        for file in &files {
-            let path = get_file_path_standalone(state, dir, &file.filename);
-            if is_image_file(&path) {
-                return Some(path);
-            }
-        }
+    if let Some(file) = cover_files(state, dir).into_iter().next() {
+        let path = get_file_path_standalone(state, dir, &file.filename);
+        return Some((file, path));
     }
 
-    let child_dirs: Vec<i64> = state
+    state
         .directories
         .iter()
         .filter(|d| d.parent_id == Some(dir_id))
-        .map(|d| d.id)
-        .collect();
+        .find_map(|child| find_image_in_dir_recursive_standalone(state, child.id))
+}
 
-    for child_id in child_dirs {
-        if let Some(img) = find_image_in_dir_recursive_standalone(state, child_id) {
-            return Some(img);
-        }
+/// Images directly in `dir`, best cover first, or its videos when it has
+/// no images. Screenshots and rejected files are dropped unless that would
+/// leave none.
+fn cover_files(state: &TempPreviewState<'_>, dir: &Directory) -> Vec<File> {
+    let Ok(files) = state.db.get_files_in_directory(dir.id) else {
+        return Vec::new();
+    };
+    let (images, videos): (Vec<File>, Vec<File>) = files
+        .into_iter()
+        .filter(|f| is_image_file(Path::new(&f.filename)) || is_video_file(Path::new(&f.filename)))
+        .partition(|f| is_image_file(Path::new(&f.filename)));
+    let images = if images.is_empty() { videos } else { images };
+    let tags = state.db.get_file_tags_for_directory(dir.id).unwrap_or_default();
+
+    let (mut kept, skipped): (Vec<File>, Vec<File>) = images.into_iter().partition(|f| {
+        let file_tags = tags.get(&f.id).map(Vec::as_slice).unwrap_or_default();
+        !is_unwanted_cover(f, file_tags)
+    });
+    if kept.is_empty() {
+        kept = skipped;
     }
+    kept.sort_by(|a, b| cover_order(a, b, state.cover, dir.id));
+    kept
+}
 
-    None
+fn is_unwanted_cover(file: &File, tags: &[String]) -> bool {
+    tags.iter().any(|t| t == REJECTED_TAG || t == SCREENSHOT_TAG)
+        || is_likely_screenshot(&file.filename, file.width, file.height, file.camera.as_deref())
 }
 
-fn get_file_path_standalone(state: &TempPreviewState, dir: &Directory, filename: &str) -> PathBuf {
+/// Best cover first. `seed` (the directory id) fixes the random shuffle, so
+/// regenerating a preview picks the same images.
+fn cover_order(a: &File, b: &File, cover: PreviewCover, seed: i64) -> Ordering {
+    let newest = || b.mtime.cmp(&a.mtime);
+    let order = match cover {
+        PreviewCover::Rating => {
+            let rating = |f: &File| f.rating.unwrap_or(f64::NEG_INFINITY);
+            rating(b).total_cmp(&rating(a)).then_with(newest)
+        }
+        PreviewCover::Recent => newest(),
+        PreviewCover::Random => {
+            let shuffle = |f: &File| {
+                let mut h = DefaultHasher::new();
+                seed.hash(&mut h);
+                f.id.hash(&mut h);
+                h.finish()
+            };
+            shuffle(a).cmp(&shuffle(b))
+        }
+    };
+    order.then(a.id.cmp(&b.id))
+}
+
+fn get_file_path_standalone(state: &TempPreviewState<'_>, dir: &Directory, filename: &str) -> PathBuf {
     dir.file_path(&state.library_path, filename)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resize_spec_validation_and_fit() {
+        assert!(ResizeSpec::new(None, None, ResizeFit::Contain).is_err());
+        assert!(ResizeSpec::new(Some(0), None, ResizeFit::Contain).is_err());
+        assert!(ResizeSpec::new(None, Some(4097), ResizeFit::Contain).is_err());
+        assert!(ResizeSpec::new(Some(300), None, ResizeFit::Cover).is_err());
+        assert_eq!("cover".parse::<ResizeFit>(), Ok(ResizeFit::Cover));
+        assert!("stretch".parse::<ResizeFit>().is_err());
+
+        // Sizes are rounded up to a bucket
+        let spec = ResizeSpec::new(Some(201), Some(4000), ResizeFit::Cover).unwrap();
+        assert_eq!((spec.width, spec.height), (Some(300), Some(4096)));
+        let spec = ResizeSpec::new(None, Some(1), ResizeFit::Contain).unwrap();
+        assert_eq!((spec.width, spec.height), (None, Some(100)));
+
+        let img = || DynamicImage::new_rgb8(800, 400);
+
+        let contain = ResizeSpec::new(Some(200), Some(200), ResizeFit::Contain).unwrap();
+        assert_eq!(contain.apply(img()).dimensions(), (200, 100));
+
+        let cover = ResizeSpec::new(Some(200), Some(200), ResizeFit::Cover).unwrap();
+        assert_eq!(cover.apply(img()).dimensions(), (200, 200));
+
+        // Never enlarged
+        let wide = ResizeSpec::new(Some(2000), None, ResizeFit::Contain).unwrap();
+        assert_eq!(wide.apply(img()).dimensions(), (800, 400));
+        assert!(!wide.covered_by(800, 400));
+        assert!(contain.covered_by(800, 400));
+    }
+
+    #[test]
+    fn test_covers_prefer_rated_recent_images_over_screenshots() {
+        let db = Database::open_in_memory().unwrap();
+        let dir_id = db.insert_directory("trip", None, None).unwrap();
+        let old_best = db.insert_file(dir_id, "old.jpg", 1, 100, Some("image")).unwrap();
+        let new_good = db.insert_file(dir_id, "new.jpg", 1, 300, Some("image")).unwrap();
+        db.insert_file(dir_id, "unrated.jpg", 1, 400, Some("image")).unwrap();
+        let rejected = db.insert_file(dir_id, "blurry.jpg", 1, 500, Some("image")).unwrap();
+        db.insert_file(dir_id, "Screenshot 2024-05-01.png", 1, 600, Some("image")).unwrap();
+        db.insert_file(dir_id, "clip.mp4", 1, 700, Some("video")).unwrap();
+        db.set_file_rating(old_best, Some(5.0)).unwrap();
+        db.set_file_rating(new_good, Some(4.0)).unwrap();
+        db.set_file_rating(rejected, Some(5.0)).unwrap();
+        db.add_file_tag(rejected, REJECTED_TAG).unwrap();
+
+        let directories = db.get_all_directories().unwrap();
+        let mut state = TempPreviewState {
+            library_path: PathBuf::from("/library"),
+            db: &db,
+            directories: &directories,
+            cover: PreviewCover::Rating,
+        };
+        let names = |state: &TempPreviewState<'_>| -> Vec<String> {
+            collect_preview_images_standalone(state, &directories[0])
+                .iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+        assert_eq!(names(&state), ["old.jpg", "new.jpg", "unrated.jpg"]);
+
+        state.cover = PreviewCover::Recent;
+        assert_eq!(names(&state), ["unrated.jpg", "new.jpg", "old.jpg"]);
+    }
+
+    #[test]
+    fn test_video_only_directories_use_their_videos() {
+        let db = Database::open_in_memory().unwrap();
+        let parent = db.insert_directory("2024", None, None).unwrap();
+        let clips = db.insert_directory("2024/clips", Some(parent), None).unwrap();
+        let mixed = db.insert_directory("2024/mixed", Some(parent), None).unwrap();
+        db.insert_file(clips, "a.mp4", 1, 100, Some("video")).unwrap();
+        db.insert_file(clips, "b.MOV", 1, 200, Some("video")).unwrap();
+        db.insert_file(mixed, "c.mp4", 1, 300, Some("video")).unwrap();
+        db.insert_file(mixed, "d.jpg", 1, 50, Some("image")).unwrap();
+
+        let directories = db.get_all_directories().unwrap();
+        let state = TempPreviewState {
+            library_path: PathBuf::from("/library"),
+            db: &db,
+            directories: &directories,
+            cover: PreviewCover::Recent,
+        };
+        let dir = |id: i64| directories.iter().find(|d| d.id == id).unwrap();
+        assert_eq!(
+            collect_preview_images_standalone(&state, dir(clips)),
+            [PathBuf::from("/library/2024/clips/b.MOV"), PathBuf::from("/library/2024/clips/a.mp4")]
+        );
+        // Images win over videos in a mixed directory
+        assert_eq!(
+            collect_preview_images_standalone(&state, dir(mixed)),
+            [PathBuf::from("/library/2024/mixed/d.jpg")]
+        );
+        assert_eq!(collect_preview_images_standalone(&state, dir(parent)).len(), 2);
+    }
+
+    #[test]
+    fn test_covers_fall_back_to_screenshots_and_pick_from_subdirectories() {
+        let db = Database::open_in_memory().unwrap();
+        let parent = db.insert_directory("phone", None, None).unwrap();
+        let shots = db.insert_directory("phone/shots", Some(parent), None).unwrap();
+        let camera = db.insert_directory("phone/camera", Some(parent), None).unwrap();
+        db.insert_file(shots, "Screenshot_1.png", 1, 100, Some("image")).unwrap();
+        let best = db.insert_file(camera, "IMG_2.jpg", 1, 50, Some("image")).unwrap();
+        db.insert_file(camera, "IMG_1.jpg", 1, 200, Some("image")).unwrap();
+        db.set_file_rating(best, Some(3.0)).unwrap();
+
+        let directories = db.get_all_directories().unwrap();
+        let state = TempPreviewState {
+            library_path: PathBuf::from("/library"),
+            db: &db,
+            directories: &directories,
+            cover: PreviewCover::Rating,
+        };
+        let parent_dir = directories.iter().find(|d| d.id == parent).unwrap();
+        assert_eq!(
+            collect_preview_images_standalone(&state, parent_dir),
+            [PathBuf::from("/library/phone/camera/IMG_2.jpg"), PathBuf::from("/library/phone/shots/Screenshot_1.png")]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use crossterm::{
+    cursor::Show,
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -7,15 +8,24 @@ use crossterm::{
 
 use super::mouse::{self, MouseState};
 use ratatui::prelude::*;
-use std::io::stdout;
+use std::backtrace::Backtrace;
+use std::io::{stdout, Stdout};
+use std::ops::{Deref, DerefMut};
 use std::path::Path;
-use std::time::Duration;
-use tracing::{debug, info, instrument};
-
-use crate::cli::{run_init, run_sync_incremental};
-use crate::db::Database;
-
-use super::state::{AppState, Focus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::cli::{SyncLocked, SyncStats};
+use crate::db::{check_database, Database};
+use crate::logging::log_file_path;
+use crate::notify::{JobKind, Notifier};
+use crate::rating::RatingScale;
+
+use super::onboarding::{run_onboarding, set_up_library};
+use super::startup_sync::run_startup_sync;
+use super::state::{AppState, Focus, PreviewZoom};
 use super::ui::render;
 
 /// Run the TUI application
@@ -25,81 +35,183 @@ pub fn run_tui(library_path: &Path, skip_sync: bool) -> Result<()> {
     let mut status_parts = Vec::new();
 
     info!("starting TUI");
+    if !library_path.is_dir() {
+        anyhow::bail!("Library path does not exist: {}", library_path.display());
+    }
 
-    // Auto-init if no database exists
+    // First run: ask how to set up the library instead of scanning blindly
+    let mut just_created = false;
     if !db_path.exists() {
-        info!("no database found, initializing");
-        let stats = run_init(library_path)?;
-        info!(dirs = stats.directories, files = stats.files, "init complete");
-        status_parts.push(format!(
-            "Init: {} dirs, {} files",
-            stats.directories, stats.files
-        ));
+        info!("no database found, starting setup");
+        let Some(choices) = run_onboarding(library_path)? else {
+            info!("setup cancelled");
+            return Ok(());
+        };
+        status_parts.extend(set_up_library(library_path, &choices)?);
+        just_created = true;
     }
 
-    // Incremental sync on startup (unless --skip-sync)
-    if skip_sync {
-        info!("skipping sync (--skip-sync)");
-    } else {
-        info!("syncing database with filesystem (incremental)");
-        let sync_stats = run_sync_incremental(library_path)?;
-        let sync_changes = sync_stats.directories_added
-            + sync_stats.directories_removed
-            + sync_stats.files_added
-            + sync_stats.files_removed
-            + sync_stats.files_modified;
-        info!(
-            dirs_added = sync_stats.directories_added,
-            dirs_removed = sync_stats.directories_removed,
-            files_added = sync_stats.files_added,
-            files_removed = sync_stats.files_removed,
-            files_modified = sync_stats.files_modified,
-            "sync complete"
-        );
-        if sync_changes > 0 {
-            status_parts.push(format!(
-                "Sync: +{} -{} files",
-                sync_stats.files_added,
-                sync_stats.files_removed
-            ));
+    // Fail (or restore the backup) while errors can still be printed plainly
+    check_database(&db_path)?;
+
+    let mut terminal = TerminalGuard::new()?;
+    let result = (|| -> Result<()> {
+        // Incremental sync on startup (unless --skip-sync)
+        if skip_sync {
+            info!("skipping sync (--skip-sync)");
+        } else if just_created {
+            debug!("library was just set up, skipping sync");
+        } else {
+            info!("syncing database with filesystem (incremental)");
+            let started = Instant::now();
+            let sync_stats = match run_startup_sync(&mut terminal, library_path) {
+                Ok(Some(stats)) => {
+                    Notifier::for_library(library_path).job_finished(
+                        JobKind::Sync,
+                        started.elapsed(),
+                        &format!("+{} -{} ~{} files", stats.files_added, stats.files_removed, stats.files_modified),
+                    );
+                    stats
+                }
+                Ok(None) => {
+                    status_parts.push("Sync skipped".to_string());
+                    SyncStats::default()
+                }
+                // Browse what's there while the other sync finishes
+                Err(e) if e.is::<SyncLocked>() => {
+                    warn!("{:#}", e);
+                    status_parts.push("Sync skipped: another sync is running".to_string());
+                    SyncStats::default()
+                }
+                Err(e) => return Err(e),
+            };
+            let sync_changes = sync_stats.directories_added
+                + sync_stats.directories_removed
+                + sync_stats.files_added
+                + sync_stats.files_removed
+                + sync_stats.files_modified;
+            info!(
+                dirs_added = sync_stats.directories_added,
+                dirs_removed = sync_stats.directories_removed,
+                files_added = sync_stats.files_added,
+                files_removed = sync_stats.files_removed,
+                files_modified = sync_stats.files_modified,
+                "sync complete"
+            );
+            if sync_changes > 0 {
+                status_parts.push(format!(
+                    "Sync: +{} -{} files",
+                    sync_stats.files_added,
+                    sync_stats.files_removed
+                ));
+            }
         }
+
+        debug!("opening database");
+        let db = Database::open(&db_path)?;
+
+        // Initialize state
+        debug!("loading directory tree");
+        let mut state = AppState::new(library_path.to_path_buf(), db)?;
+        info!(dirs = state.tree.directories.len(), "loaded directory tree");
+
+        // Show startup status
+        if !status_parts.is_empty() {
+            state.status_message = Some(status_parts.join(" | "));
+        }
+
+        // Main loop
+        run_app(&mut terminal, &mut state)
+    })();
+
+    drop(terminal);
+    result
+}
+
+/// True while the terminal is in raw mode on the alternate screen, so the
+/// panic hook and [`TerminalGuard`] restore it exactly once
+static TERMINAL_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// The TUI's terminal: raw mode on the alternate screen, restored when
+/// dropped so an error anywhere in the TUI leaves the shell usable. The
+/// first guard also installs a panic hook that restores it before the
+/// panic is reported.
+pub(super) struct TerminalGuard {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl TerminalGuard {
+    pub(super) fn new() -> Result<Self> {
+        install_panic_hook();
+        debug!("setting up terminal");
+        enable_raw_mode()?;
+        TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+        // From here a failure drops nothing yet, so restore by hand
+        let terminal = (|| -> Result<_> {
+            let mut stdout = stdout();
+            execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+            let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+            terminal.clear()?;
+            Ok(terminal)
+        })()
+        .inspect_err(|_| restore_terminal())?;
+        Ok(Self { terminal })
     }
+}
 
-    debug!("opening database");
-    let db = Database::open(&db_path)?;
+impl Deref for TerminalGuard {
+    type Target = Terminal<CrosstermBackend<Stdout>>;
 
-    // Initialize state
-    debug!("loading directory tree");
-    let mut state = AppState::new(library_path.to_path_buf(), db)?;
-    info!(dirs = state.tree.directories.len(), "loaded directory tree");
+    fn deref(&self) -> &Self::Target {
+        &self.terminal
+    }
+}
 
-    // Show startup status
-    if !status_parts.is_empty() {
-        state.status_message = Some(status_parts.join(" | "));
+impl DerefMut for TerminalGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.terminal
     }
+}
 
-    // Setup terminal
-    debug!("setting up terminal");
-    enable_raw_mode()?;
-    let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-    terminal.clear()?;
-
-    // Main loop
-    let result = run_app(&mut terminal, &mut state);
-
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
 
-    result
+/// Leave raw mode and the alternate screen and show the cursor, if the TUI
+/// still holds the terminal. Errors are logged: there's nothing better to
+/// do with them on the way out.
+fn restore_terminal() {
+    if !TERMINAL_ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let result = disable_raw_mode().and_then(|_| execute!(stdout(), LeaveAlternateScreen, DisableMouseCapture, Show));
+    if let Err(e) = result {
+        warn!(error = %e, "failed to restore terminal");
+    }
+}
+
+/// Restore the terminal before the default hook prints the panic, which
+/// would otherwise land on the alternate screen and vanish, and write the
+/// report with a backtrace to the log file
+fn install_panic_hook() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            error!(
+                panic = %info,
+                backtrace = %Backtrace::force_capture(),
+                "picman panicked"
+            );
+            if let Some(log) = log_file_path() {
+                eprintln!("picman crashed; the report is in {}", log.display());
+            }
+            default_hook(info);
+        }));
+    });
 }
 
 fn run_app(
@@ -120,6 +232,14 @@ fn run_app(
 
         // Poll for completed preview loads and insert into cache
         state.poll_preview_results();
+        if let Err(e) = state.poll_dashboard() {
+            state.show_dashboard = false;
+            state.show_error(e);
+        }
+        // Bookkeeping again: a failed check is retried on the next tick
+        if let Err(e) = state.poll_metadata_changes() {
+            warn!(error = format!("{:#}", e), "failed to check for metadata changes");
+        }
 
         // Force full terminal repaint after closing overlays — image protocol
         // content (kitty/sixel) gets destroyed by overlays and ratatui's diff
@@ -131,17 +251,29 @@ fn run_app(
 
         terminal.draw(|frame| render(frame, state))?;
 
+        // A preview drawn outside rapid navigation counts as viewing the file
+        // Bookkeeping, not something the user asked for: log it, don't pop up
+        if !state.skip_preview {
+            if let Err(e) = state.record_file_view() {
+                warn!(error = format!("{:#}", e), "failed to record file view");
+            }
+        }
+        if let Err(e) = state.save_thumbnail_presence() {
+            warn!(error = format!("{:#}", e), "failed to save thumbnail check");
+        }
+
         // Clear skip_preview AFTER rendering so it takes effect this frame.
         // Event handling below may set it again for the next frame.
         state.clear_skip_preview();
 
         // Use shorter timeout when we're waiting for async work:
-        // - Background operations (thumbnails, hashing): 100ms for progress updates
+        // - Background operations (thumbnails, hashing) or the dashboard's
+        //   thumbnail count: 100ms for progress updates
         // - Pending preview: 5ms so the worker result is picked up promptly
         // - Idle: 1 second to save CPU
         let preview_ready = match state.focus {
             Focus::FileList => match state.selected_file_path() {
-                Some(sel) => state.preview_cache.borrow().has_protocol(&sel),
+                Some(sel) => state.preview_cache.borrow().has_protocol(&sel, state.preview_zoom),
                 None => true,
             },
             Focus::DirectoryTree => {
@@ -149,7 +281,9 @@ fn run_app(
             }
         };
 
-        let timeout = if state.background_progress.is_some() {
+        let dashboard_counting = state.show_dashboard
+            && state.dashboard.as_ref().is_some_and(|d| d.is_counting());
+        let timeout = if state.tasks.is_busy() || dashboard_counting {
             Duration::from_millis(100)
         } else if !preview_ready {
             Duration::from_millis(5)
@@ -159,44 +293,50 @@ fn run_app(
 
         // Wait for event with timeout
         if event::poll(timeout)? {
-            match event::read()? {
-                Event::Key(key) if key.kind == KeyEventKind::Press => {
-                    match handle_key(key.code, state)? {
-                        KeyAction::Quit => return Ok(()),
-                        KeyAction::Cancelling => cancelling = true,
-                        KeyAction::Continue => {}
-                    }
-                }
-                Event::Mouse(mouse_event) => {
-                    mouse::handle_mouse(mouse_event, state, &mut mouse_state)?;
-                }
-                _ => {}
+            match handle_event(event::read()?, state, &mut mouse_state) {
+                KeyAction::Quit => return Ok(()),
+                KeyAction::Cancelling => cancelling = true,
+                KeyAction::Continue => {}
             }
         }
 
         // Drain all pending events to avoid lag during rapid navigation
         while event::poll(Duration::ZERO)? {
-            match event::read()? {
-                Event::Key(key) if key.kind == KeyEventKind::Press => {
-                    match handle_key(key.code, state)? {
-                        KeyAction::Quit => return Ok(()),
-                        KeyAction::Cancelling => cancelling = true,
-                        KeyAction::Continue => {}
-                    }
-                }
-                Event::Mouse(mouse_event) => {
-                    mouse::handle_mouse(mouse_event, state, &mut mouse_state)?;
-                }
-                _ => {}
+            match handle_event(event::read()?, state, &mut mouse_state) {
+                KeyAction::Quit => return Ok(()),
+                KeyAction::Cancelling => cancelling = true,
+                KeyAction::Continue => {}
             }
         }
 
         // After draining all keypresses, process deferred updates
-        state.load_files_if_dirty()?;
+        if let Err(e) = state.load_files_if_dirty() {
+            // Don't retry (and report it again) every frame; moving reloads
+            state.files_dirty = false;
+            state.show_error(e);
+        }
         state.refresh_exif_cache();
     }
 }
 
+/// Dispatch a terminal event. A failed action is shown in the error popup
+/// and the app keeps running; only terminal IO errors end it.
+fn handle_event(event: Event, state: &mut AppState, mouse_state: &mut MouseState) -> KeyAction {
+    let result = match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => handle_key(key.code, state),
+        // Clicks would act on the panes under the popup
+        Event::Mouse(_) if state.error_popup.is_some() => Ok(KeyAction::Continue),
+        Event::Mouse(mouse_event) => {
+            mouse::handle_mouse(mouse_event, state, mouse_state).map(|_| KeyAction::Continue)
+        }
+        _ => Ok(KeyAction::Continue),
+    };
+    result.unwrap_or_else(|e| {
+        state.show_error(e);
+        KeyAction::Continue
+    })
+}
+
 enum KeyAction {
     Quit,
     Continue,
@@ -209,10 +349,33 @@ enum FilterAction {
     AutoApply,
     Apply,
     Clear,
+    SavePreset(String),
+}
+
+/// Rating a key stands for: digits rate directly (1-9, as far as the scale
+/// goes) and the home row `asdfg` gives one to five stars on either scale,
+/// so `g` is the top rating on a 10-point library too
+fn rating_for_key(scale: RatingScale, key: char) -> Option<f64> {
+    if let Some(digit) = key.to_digit(10) {
+        let rating = digit as i32;
+        return (rating >= 1 && rating <= scale.max()).then_some(rating as f64);
+    }
+    let stars = "asdfg".find(key)? as f64 + 1.0;
+    Some(RatingScale::Five.convert(stars, scale))
 }
 
 /// Handle a key press. Returns KeyAction indicating what to do next.
 fn handle_key(code: KeyCode, state: &mut AppState) -> Result<KeyAction> {
+    // An error popup sits above everything and takes every key until closed
+    if let Some(ref mut popup) = state.error_popup {
+        match code {
+            KeyCode::Char('d') => popup.toggle_details(),
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => state.close_error(),
+            _ => {}
+        }
+        return Ok(KeyAction::Continue);
+    }
+
     // Handle filter dialog if active
     if state.filter_dialog.is_some() {
         use super::dialogs::FilterDialogFocus;
@@ -223,148 +386,166 @@ fn handle_key(code: KeyCode, state: &mut AppState) -> Result<KeyAction> {
             let focus = dialog.focus;
             let tag_editing = focus == FilterDialogFocus::Tag && dialog.tag_editing;
 
-            match code {
-                KeyCode::Esc => {
-                    if tag_editing {
-                        dialog.tag_editing = false;
-                    } else {
-                        action = FilterAction::Apply;
+            if let Some(ref mut name) = dialog.preset_name {
+                // Typing a name for the preset being saved
+                match code {
+                    KeyCode::Esc => dialog.preset_name = None,
+                    KeyCode::Enter => {
+                        action = FilterAction::SavePreset(name.clone());
+                        dialog.preset_name = None;
                     }
-                }
-                KeyCode::Char('m') => {
-                    if tag_editing {
-                        dialog.char_input('m');
-                    } else {
-                        action = FilterAction::Apply;
+                    KeyCode::Backspace => {
+                        name.pop();
                     }
+                    KeyCode::Char(c) => name.push(c),
+                    _ => {}
                 }
-                KeyCode::Tab => dialog.cycle_focus_down(),
-                KeyCode::BackTab => dialog.cycle_focus_up(),
-                KeyCode::Up => {
-                    if focus == FilterDialogFocus::Tag {
-                        dialog.navigate_up();
-                    } else {
-                        dialog.cycle_focus_up();
+            } else {
+                match code {
+                    KeyCode::F(n @ 1..=9) if dialog.load_preset(n as usize - 1) => {
+                        action = FilterAction::AutoApply;
                     }
-                }
-                KeyCode::Down => {
-                    if focus == FilterDialogFocus::Tag {
-                        dialog.navigate_down();
-                    } else {
-                        dialog.cycle_focus_down();
+                    KeyCode::Esc => {
+                        if tag_editing {
+                            dialog.tag_editing = false;
+                        } else {
+                            action = FilterAction::Apply;
+                        }
                     }
-                }
-                KeyCode::Left => {
-                    dialog.navigate_rating_left();
-                    action = FilterAction::AutoApply;
-                }
-                KeyCode::Right => {
-                    dialog.navigate_rating_right();
-                    action = FilterAction::AutoApply;
-                }
-                KeyCode::Enter | KeyCode::Char(' ') => {
-                    match focus {
-                        FilterDialogFocus::VideoOnly => {
-                            dialog.toggle_video();
-                            action = FilterAction::AutoApply;
+                    KeyCode::Char('m') => {
+                        if tag_editing {
+                            dialog.char_input('m');
+                        } else {
+                            action = FilterAction::Apply;
+                        }
+                    }
+                    KeyCode::Tab => dialog.cycle_focus_down(),
+                    KeyCode::BackTab => dialog.cycle_focus_up(),
+                    KeyCode::Up => {
+                        if focus == FilterDialogFocus::Tag {
+                            dialog.navigate_up();
+                        } else {
+                            dialog.cycle_focus_up();
+                        }
+                    }
+                    KeyCode::Down => {
+                        if focus == FilterDialogFocus::Tag {
+                            dialog.navigate_down();
+                        } else {
+                            dialog.cycle_focus_down();
                         }
-                        FilterDialogFocus::Tag => {
-                            if tag_editing {
-                                if !dialog.filtered_tags.is_empty() {
+                    }
+                    KeyCode::Left => {
+                        dialog.navigate_rating_left();
+                        action = FilterAction::AutoApply;
+                    }
+                    KeyCode::Right => {
+                        dialog.navigate_rating_right();
+                        action = FilterAction::AutoApply;
+                    }
+                    KeyCode::Enter | KeyCode::Char(' ') => {
+                        match focus {
+                            FilterDialogFocus::Media => {
+                                dialog.cycle_media();
+                                action = FilterAction::AutoApply;
+                            }
+                            FilterDialogFocus::Tag => {
+                                if tag_editing {
+                                    if !dialog.filtered_tags.is_empty() {
+                                        dialog.add_tag();
+                                        action = FilterAction::AutoApply;
+                                    } else {
+                                        dialog.tag_editing = false;
+                                    }
+                                } else if dialog.tag_input_selected {
+                                    dialog.tag_editing = true;
+                                } else {
                                     dialog.add_tag();
                                     action = FilterAction::AutoApply;
-                                } else {
-                                    dialog.tag_editing = false;
                                 }
-                            } else if dialog.tag_input_selected {
-                                dialog.tag_editing = true;
-                            } else {
-                                dialog.add_tag();
-                                action = FilterAction::AutoApply;
                             }
+                            FilterDialogFocus::Rating => {}
                         }
-                        FilterDialogFocus::Rating => {}
                     }
-                }
-                KeyCode::Backspace => {
-                    if tag_editing {
-                        if dialog.tag_input.is_empty() {
-                            dialog.tag_editing = false;
+                    KeyCode::Backspace => {
+                        if tag_editing {
+                            if dialog.tag_input.is_empty() {
+                                dialog.tag_editing = false;
+                            } else {
+                                dialog.backspace();
+                                action = FilterAction::AutoApply;
+                            }
                         } else {
                             dialog.backspace();
                             action = FilterAction::AutoApply;
                         }
-                    } else {
-                        dialog.backspace();
-                        action = FilterAction::AutoApply;
                     }
-                }
-                KeyCode::Char(c) => {
-                    if tag_editing {
-                        dialog.char_input(c);
-                    } else {
-                        match c {
-                            'i' => {
-                                if focus == FilterDialogFocus::Tag && dialog.tag_input_selected {
-                                    dialog.tag_editing = true;
+                    KeyCode::Char(c) => {
+                        if tag_editing {
+                            dialog.char_input(c);
+                        } else {
+                            match c {
+                                'i' => {
+                                    if focus == FilterDialogFocus::Tag && dialog.tag_input_selected {
+                                        dialog.tag_editing = true;
+                                    }
                                 }
-                            }
-                            'j' => {
-                                if focus == FilterDialogFocus::Tag {
-                                    dialog.navigate_down();
-                                } else {
-                                    dialog.cycle_focus_down();
+                                'j' => {
+                                    if focus == FilterDialogFocus::Tag {
+                                        dialog.navigate_down();
+                                    } else {
+                                        dialog.cycle_focus_down();
+                                    }
                                 }
-                            }
-                            'k' => {
-                                if focus == FilterDialogFocus::Tag {
-                                    dialog.navigate_up();
-                                } else {
-                                    dialog.cycle_focus_up();
+                                'k' => {
+                                    if focus == FilterDialogFocus::Tag {
+                                        dialog.navigate_up();
+                                    } else {
+                                        dialog.cycle_focus_up();
+                                    }
                                 }
+                                'h' => {
+                                    dialog.navigate_rating_left();
+                                    action = FilterAction::AutoApply;
+                                }
+                                'l' => {
+                                    dialog.navigate_rating_right();
+                                    action = FilterAction::AutoApply;
+                                }
+                                '0' => action = FilterAction::Clear,
+                                '1'..='9' | 'a' | 's' | 'd' | 'f' | 'g' => {
+                                    if let Some(rating) = rating_for_key(dialog.rating_scale, c) {
+                                        dialog.set_rating(rating);
+                                        action = FilterAction::AutoApply;
+                                    }
+                                }
+                                'v' => {
+                                    dialog.cycle_media();
+                                    action = FilterAction::AutoApply;
+                                }
+                                'c' => {
+                                    dialog.cycle_camera();
+                                    action = FilterAction::AutoApply;
+                                }
+                                'n' => {
+                                    dialog.cycle_lens();
+                                    action = FilterAction::AutoApply;
+                                }
+                                'o' => {
+                                    dialog.cycle_orientation();
+                                    action = FilterAction::AutoApply;
+                                }
+                                'u' => {
+                                    dialog.set_unrated();
+                                    action = FilterAction::AutoApply;
+                                }
+                                'w' => dialog.preset_name = Some(String::new()),
+                                _ => {}
                             }
-                            'h' => {
-                                dialog.navigate_rating_left();
-                                action = FilterAction::AutoApply;
-                            }
-                            'l' => {
-                                dialog.navigate_rating_right();
-                                action = FilterAction::AutoApply;
-                            }
-                            '0' => action = FilterAction::Clear,
-                            '1' | 'a' => {
-                                dialog.set_rating(1);
-                                action = FilterAction::AutoApply;
-                            }
-                            '2' | 's' => {
-                                dialog.set_rating(2);
-                                action = FilterAction::AutoApply;
-                            }
-                            '3' | 'd' => {
-                                dialog.set_rating(3);
-                                action = FilterAction::AutoApply;
-                            }
-                            '4' | 'f' => {
-                                dialog.set_rating(4);
-                                action = FilterAction::AutoApply;
-                            }
-                            '5' | 'g' => {
-                                dialog.set_rating(5);
-                                action = FilterAction::AutoApply;
-                            }
-                            'v' => {
-                                dialog.toggle_video();
-                                action = FilterAction::AutoApply;
-                            }
-                            'u' => {
-                                dialog.set_unrated();
-                                action = FilterAction::AutoApply;
-                            }
-                            _ => {}
                         }
                     }
+                    _ => {}
                 }
-                _ => {}
             }
         }
 
@@ -373,6 +554,7 @@ fn handle_key(code: KeyCode, state: &mut AppState) -> Result<KeyAction> {
             FilterAction::AutoApply => state.auto_apply_filter()?,
             FilterAction::Apply => state.apply_filter()?,
             FilterAction::Clear => state.clear_filter()?,
+            FilterAction::SavePreset(name) => state.save_filter_preset(&name)?,
             FilterAction::None => {}
         }
         return Ok(KeyAction::Continue);
@@ -382,6 +564,7 @@ fn handle_key(code: KeyCode, state: &mut AppState) -> Result<KeyAction> {
     if state.tag_input.is_some() {
         let mut do_toggle = false;
         let mut do_close = false;
+        let mut toggle_recent = None;
 
         // First pass: mutate the dialog directly
         if let Some(ref mut input) = state.tag_input {
@@ -423,6 +606,9 @@ fn handle_key(code: KeyCode, state: &mut AppState) -> Result<KeyAction> {
                     KeyCode::Char('i') if input.input_selected => {
                         input.editing = true;
                     }
+                    KeyCode::Char(c @ '1'..='9') => {
+                        toggle_recent = c.to_digit(10).map(|d| d as usize);
+                    }
                     _ => {}
                 }
             }
@@ -431,6 +617,8 @@ fn handle_key(code: KeyCode, state: &mut AppState) -> Result<KeyAction> {
         // Second pass: actions that need &mut AppState
         if do_toggle {
             state.toggle_tag()?;
+        } else if let Some(key) = toggle_recent {
+            state.toggle_recent_tag(key)?;
         } else if do_close {
             state.close_tag_input();
         }
@@ -459,6 +647,21 @@ fn handle_key(code: KeyCode, state: &mut AppState) -> Result<KeyAction> {
         return Ok(KeyAction::Continue);
     }
 
+    // Handle goto prompt if active
+    if let Some(ref mut goto) = state.goto {
+        match code {
+            KeyCode::Esc => state.close_goto(),
+            KeyCode::Enter => state.goto_selected(),
+            KeyCode::Tab => goto.complete(),
+            KeyCode::Up => goto.move_up(),
+            KeyCode::Down => goto.move_down(),
+            KeyCode::Backspace => goto.pop_char(),
+            KeyCode::Char(c) => goto.push_char(c),
+            _ => {}
+        }
+        return Ok(KeyAction::Continue);
+    }
+
     // Handle help overlay — eat all keys except ? and Esc which close it
     if state.show_help {
         match code {
@@ -471,13 +674,33 @@ fn handle_key(code: KeyCode, state: &mut AppState) -> Result<KeyAction> {
         return Ok(KeyAction::Continue);
     }
 
+    // Handle dashboard — eat all keys except D and Esc which close it
+    if state.show_dashboard {
+        if matches!(code, KeyCode::Char('D') | KeyCode::Esc) {
+            state.toggle_dashboard()?;
+        }
+        return Ok(KeyAction::Continue);
+    }
+
     // Handle operations menu
     if let Some(ref mut menu) = state.operations_menu {
         match code {
             KeyCode::Esc | KeyCode::Char('o') => state.close_operations_menu(),
             KeyCode::Up | KeyCode::Char('k') => menu.move_up(),
             KeyCode::Down | KeyCode::Char('j') => menu.move_down(),
-            KeyCode::Enter => state.operations_menu_select(),
+            KeyCode::Tab | KeyCode::BackTab => menu.switch_tab(),
+            KeyCode::Enter if menu.tab == crate::tui::state::OperationsTab::History => {
+                if let Some(record) = menu.selected_record().cloned() {
+                    state.rerun_operation(&record);
+                }
+            }
+            KeyCode::Enter => state.operations_menu_select()?,
+            // Repeat the most recent operation on this directory
+            KeyCode::Char('.') => {
+                if let Some(record) = menu.history.first().cloned() {
+                    state.rerun_operation(&record);
+                }
+            }
             KeyCode::Char('1') => {
                 state.close_operations_menu();
                 state.run_operation(crate::tui::state::OperationType::Thumbnails);
@@ -498,6 +721,56 @@ fn handle_key(code: KeyCode, state: &mut AppState) -> Result<KeyAction> {
                 state.close_operations_menu();
                 state.run_operation(crate::tui::state::OperationType::DirPreviewRecursive);
             }
+            KeyCode::Char('6') => {
+                state.close_operations_menu();
+                state.propagate_rating(crate::cli::Propagation::Down)?;
+            }
+            KeyCode::Char('7') => {
+                state.close_operations_menu();
+                state.propagate_rating(crate::cli::Propagation::Up(crate::db::RatingAggregate::Max))?;
+            }
+            KeyCode::Char('8') => {
+                state.close_operations_menu();
+                state.propagate_rating(crate::cli::Propagation::Up(crate::db::RatingAggregate::Average))?;
+            }
+            KeyCode::Char('9') => {
+                state.close_operations_menu();
+                state.materialize_tags()?;
+            }
+            KeyCode::Char('0') => {
+                state.close_operations_menu();
+                state.untag_recursively()?;
+            }
+            KeyCode::Char('r') => {
+                state.close_operations_menu();
+                state.rescan_directory()?;
+            }
+            _ => {}
+        }
+        return Ok(KeyAction::Continue);
+    }
+
+    // Handle disk usage view
+    if let Some(ref mut view) = state.usage_view {
+        match code {
+            KeyCode::Esc | KeyCode::Char('u') | KeyCode::Char('q') => state.close_usage_view(),
+            KeyCode::Up | KeyCode::Char('k') => view.move_up(),
+            KeyCode::Down | KeyCode::Char('j') => view.move_down(),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => state.usage_view_open_selected()?,
+            KeyCode::Left | KeyCode::Backspace | KeyCode::Char('h') => state.usage_view_parent()?,
+            KeyCode::Char('g') => state.usage_view_go_to_selected()?,
+            _ => {}
+        }
+        return Ok(KeyAction::Continue);
+    }
+
+    // Handle the task list (expanded status area)
+    if state.tasks.expanded {
+        match code {
+            KeyCode::Esc | KeyCode::Char('b') => state.tasks.toggle_expanded(),
+            KeyCode::Up | KeyCode::Char('k') => state.tasks.move_up(),
+            KeyCode::Down | KeyCode::Char('j') => state.tasks.move_down(),
+            KeyCode::Char('x') => state.cancel_selected_task(),
             _ => {}
         }
         return Ok(KeyAction::Continue);
@@ -530,6 +803,15 @@ fn handle_key(code: KeyCode, state: &mut AppState) -> Result<KeyAction> {
     // Clear status message on any key
     state.clear_status_message();
 
+    // Second key of `e<level>`
+    if state.expand_level_pending {
+        state.expand_level_pending = false;
+        if let KeyCode::Char(c @ '1'..='9') = code {
+            state.expand_to_depth(c as usize - '0' as usize);
+        }
+        return Ok(KeyAction::Continue);
+    }
+
     // Normal key handling
     match code {
         KeyCode::Char('q') => {
@@ -545,35 +827,50 @@ fn handle_key(code: KeyCode, state: &mut AppState) -> Result<KeyAction> {
         KeyCode::Char('l') | KeyCode::Right => state.move_right(),
         KeyCode::Tab => state.toggle_focus(),
         KeyCode::Enter => state.select()?,
-        KeyCode::Char('1') | KeyCode::Char('a') => state.set_rating(Some(1))?,
-        KeyCode::Char('2') | KeyCode::Char('s') => state.set_rating(Some(2))?,
-        KeyCode::Char('3') | KeyCode::Char('d') => state.set_rating(Some(3))?,
-        KeyCode::Char('4') | KeyCode::Char('f') => state.set_rating(Some(4))?,
-        KeyCode::Char('5') | KeyCode::Char('g') => state.set_rating(Some(5))?,
-        KeyCode::Char('0') => state.set_rating(None)?,
+        KeyCode::Char(c @ ('1'..='9' | 'a' | 's' | 'd' | 'f' | 'g')) => {
+            if let Some(rating) = rating_for_key(state.rating_scale, c) {
+                state.rate_selected(Some(rating))?;
+            }
+        }
+        KeyCode::Char('0') => state.rate_selected(None)?,
+        KeyCode::Char('[') => state.nudge_selected(-0.5)?,
+        KeyCode::Char(']') => state.nudge_selected(0.5)?,
+        KeyCode::Char('c') => state.toggle_cull_mode(),
+        KeyCode::Char('Z') => state.zoom_in_preview(),
+        KeyCode::Char('W') => state.set_preview_zoom(PreviewZoom::Fit),
+        KeyCode::Char('=') => state.set_preview_zoom(PreviewZoom::Actual),
         KeyCode::Char('t') => state.open_tag_input()?,
         KeyCode::Char('r') => state.open_rename_dialog()?,
+        KeyCode::Char('P') => state.toggle_pin()?,
+        KeyCode::Char('H') => state.toggle_hidden()?,
+        KeyCode::Char('.') => state.toggle_show_hidden(),
+        KeyCode::Char(' ') => state.toggle_mark()?,
+        KeyCode::Char('S') => state.stack_marked_files()?,
+        KeyCode::Char('z') => state.toggle_stack_expanded()?,
         KeyCode::Char('o') => state.open_operations_menu(),
+        KeyCode::Char('u') => state.open_usage_view()?,
+        KeyCode::Char('b') => state.tasks.toggle_expanded(),
+        KeyCode::F(5) => state.run_operation(crate::tui::state::OperationType::Sync),
         KeyCode::Char('m') => state.open_filter_dialog()?,
         KeyCode::Char('?') => state.toggle_help(),
+        KeyCode::Char('D') => state.toggle_dashboard()?,
         KeyCode::Char('/') => state.search.activate(),
+        KeyCode::Char(':') => state.open_goto(),
+        KeyCode::Char('-') => state.collapse_all(),
+        KeyCode::Char('+') => state.expand_all(),
+        KeyCode::Char('O') => state.cycle_tree_sort()?,
+        KeyCode::Char('e') => {
+            state.expand_level_pending = true;
+            state.status_message = Some("Expand tree to level: 1-9".to_string());
+        }
         KeyCode::Char('i') => {
             state.details_expanded = !state.details_expanded;
-            if state.details_expanded {
-                // Read EXIF for current file if we don't have it cached
-                if let Some(path) = state.selected_file_path() {
-                    let needs_read = state
-                        .cached_exif
-                        .as_ref()
-                        .map(|(p, _)| p != &path)
-                        .unwrap_or(true);
-                    if needs_read {
-                        let info = crate::tui::exif::read_exif(&path);
-                        state.cached_exif = Some((path, info));
-                    }
-                }
-            }
+            // Read EXIF for current file if we don't have it cached
+            state.refresh_exif_cache();
         }
+        KeyCode::Char('I') => state.toggle_exif_dump(),
+        KeyCode::Char('J') => state.scroll_exif_dump(1),
+        KeyCode::Char('K') => state.scroll_exif_dump(-1),
         _ => {}
     }
     Ok(KeyAction::Continue)
@@ -2,36 +2,57 @@
 
 use ratatui::prelude::*;
 
+use crate::rating::RatingScale;
+
 // Semantic colors
 pub const RATING_COLOR: Color = Color::Yellow;
 pub const TAG_COLOR: Color = Color::Blue;
 pub const VIDEO_INDICATOR: Color = Color::Magenta;
+pub const DOCUMENT_INDICATOR: Color = Color::LightRed;
 pub const IMAGE_INDICATOR: Color = Color::Green;
+pub const PAIR_INDICATOR: Color = Color::Cyan;
+pub const STACK_INDICATOR: Color = Color::LightBlue;
+pub const ASPECT_INDICATOR: Color = Color::LightGreen;
+pub const MARK_COLOR: Color = Color::LightMagenta;
+pub const PIN_COLOR: Color = Color::LightYellow;
 pub const FOCUS_COLOR: Color = Color::Cyan;
 pub const UNFOCUS_COLOR: Color = Color::DarkGray;
 pub const HEADER_COLOR: Color = Color::White;
 pub const HELP_TEXT: Color = Color::DarkGray;
 pub const SUCCESS_COLOR: Color = Color::Green;
 pub const WARNING_COLOR: Color = Color::Yellow;
+pub const ERROR_COLOR: Color = Color::Red;
 pub const STATUS_BAR_BG: Color = Color::DarkGray;
 pub const STATUS_BAR_FG: Color = Color::White;
 
-/// Format a rating as filled stars only (e.g., "★★★").
-pub fn format_rating(rating: Option<i32>) -> String {
-    match rating {
-        Some(r) => "★".repeat(r as usize),
-        None => "unrated".to_string(),
+/// Format a rating as filled stars with a trailing half star (e.g., "★★★½"),
+/// or out of ten on a 10-point library (e.g., "★ 7.5/10") where a row of
+/// stars gets too long.
+pub fn format_rating(rating: Option<f64>, scale: RatingScale) -> String {
+    match (rating, scale) {
+        (Some(r), RatingScale::Five) => format_stars(r),
+        (Some(r), RatingScale::Ten) => format!("★ {}/{}", r, scale.max()),
+        (None, _) => "unrated".to_string(),
+    }
+}
+
+/// Whole stars plus "½" for a half step (e.g., "★★½" for 2.5)
+pub fn format_stars(rating: f64) -> String {
+    let mut stars = "★".repeat(rating.trunc() as usize);
+    if rating.fract() >= 0.5 {
+        stars.push('½');
     }
+    stars
 }
 
-/// Format a rating compactly (e.g., "★4" or empty for unrated).
-pub fn format_rating_compact(rating: Option<i32>) -> String {
+/// Format a rating compactly (e.g., "★4", "★3.5" or empty for unrated).
+pub fn format_rating_compact(rating: Option<f64>) -> String {
     rating.map(|r| format!("★{}", r)).unwrap_or_default()
 }
 
 /// Create a styled span for a rating (yellow stars).
-pub fn styled_rating(rating: Option<i32>) -> Span<'static> {
-    let text = format_rating(rating);
+pub fn styled_rating(rating: Option<f64>, scale: RatingScale) -> Span<'static> {
+    let text = format_rating(rating, scale);
     Span::styled(text, Style::default().fg(RATING_COLOR))
 }
 
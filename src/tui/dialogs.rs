@@ -1,31 +1,197 @@
+use crate::rating::RatingScale;
+
 /// Rating filter options
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub enum RatingFilter {
     #[default]
     Any,              // No rating filter
     Unrated,          // Only unrated items
-    MinRating(i32),   // Minimum rating (1-5)
+    MinRating(f64),   // Minimum rating (1 to the library's rating scale)
+}
+
+/// Media type filter, cycled with `v` in the filter dialog
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MediaFilter {
+    #[default]
+    Any,
+    Video,
+    Document,
+}
+
+impl MediaFilter {
+    /// The `files.media_type` value this filter requires, if any
+    pub fn media_type(self) -> Option<&'static str> {
+        match self {
+            MediaFilter::Any => None,
+            MediaFilter::Video => Some("video"),
+            MediaFilter::Document => Some("document"),
+        }
+    }
+
+    /// Inverse of `media_type`; unknown values are `None`
+    pub fn from_media_type(media_type: Option<&str>) -> Option<Self> {
+        match media_type {
+            None => Some(MediaFilter::Any),
+            Some("video") => Some(MediaFilter::Video),
+            Some("document") => Some(MediaFilter::Document),
+            Some(_) => None,
+        }
+    }
+
+    /// Any → Video → Document → Any
+    pub fn next(self) -> Self {
+        match self {
+            MediaFilter::Any => MediaFilter::Video,
+            MediaFilter::Video => MediaFilter::Document,
+            MediaFilter::Document => MediaFilter::Any,
+        }
+    }
+}
+
+/// Shape of a file, worked out from its stored width and height (already
+/// corrected for EXIF rotation). The classes don't overlap: a 3:1 pano is
+/// a panorama, not also landscape. Cycled with `o` in the filter dialog.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    Landscape,
+    Portrait,
+    /// Sides within 5% of each other
+    Square,
+    /// At least twice as wide as tall, e.g. a stitched panorama
+    Panorama,
+    /// At least twice as tall as wide, e.g. a vertical panorama
+    Tall,
+}
+
+impl Orientation {
+    pub const ALL: [Orientation; 5] = [
+        Orientation::Landscape,
+        Orientation::Portrait,
+        Orientation::Square,
+        Orientation::Panorama,
+        Orientation::Tall,
+    ];
+
+    /// The class of a `width` x `height` file; `None` without dimensions
+    pub fn of(width: Option<i32>, height: Option<i32>) -> Option<Self> {
+        let (w, h) = (width? as i64, height? as i64);
+        if w <= 0 || h <= 0 {
+            return None;
+        }
+        let (long, short) = (w.max(h), w.min(h));
+        Some(if long * 20 <= short * 21 {
+            Orientation::Square
+        } else if w >= 2 * h {
+            Orientation::Panorama
+        } else if h >= 2 * w {
+            Orientation::Tall
+        } else if w > h {
+            Orientation::Landscape
+        } else {
+            Orientation::Portrait
+        })
+    }
+
+    /// SQL condition on `f.width` / `f.height` matching exactly the files
+    /// [`Orientation::of`] puts in this class
+    pub fn sql_condition(self) -> &'static str {
+        match self {
+            Orientation::Landscape => {
+                "(f.height > 0 AND f.width * 20 > f.height * 21 AND f.width < 2 * f.height)"
+            }
+            Orientation::Portrait => {
+                "(f.width > 0 AND f.height * 20 > f.width * 21 AND f.height < 2 * f.width)"
+            }
+            Orientation::Square => {
+                "(f.width > 0 AND f.height > 0 AND MAX(f.width, f.height) * 20 <= MIN(f.width, f.height) * 21)"
+            }
+            Orientation::Panorama => "(f.height > 0 AND f.width >= 2 * f.height)",
+            Orientation::Tall => "(f.width > 0 AND f.height >= 2 * f.width)",
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Orientation::Landscape => "landscape",
+            Orientation::Portrait => "portrait",
+            Orientation::Square => "square",
+            Orientation::Panorama => "panorama",
+            Orientation::Tall => "tall",
+        }
+    }
+
+    /// Aspect ratio class: landscape and portrait are both "standard"
+    pub fn aspect_ratio(self) -> &'static str {
+        match self {
+            Orientation::Landscape | Orientation::Portrait => "standard",
+            other => other.as_str(),
+        }
+    }
+
+    /// File list badge for shapes worth spotting at a glance; standard
+    /// frames get none
+    pub fn badge(self) -> Option<&'static str> {
+        match self {
+            Orientation::Landscape | Orientation::Portrait => None,
+            Orientation::Square => Some("sq"),
+            Orientation::Panorama => Some("pano"),
+            Orientation::Tall => Some("tall"),
+        }
+    }
+
+    /// Any → Landscape → Portrait → Square → Panorama → Tall → Any
+    pub fn next(current: Option<Self>) -> Option<Self> {
+        match current {
+            None => Some(Orientation::Landscape),
+            Some(o) => Self::ALL.iter().skip_while(|x| **x != o).nth(1).copied(),
+        }
+    }
+}
+
+impl std::str::FromStr for Orientation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|o| o.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("unknown orientation '{}' (expected landscape, portrait, square, panorama or tall)", s))
+    }
 }
 
 /// Active filter criteria for filtering directories and files
-#[derive(Clone, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct FilterCriteria {
     pub rating: RatingFilter,
     pub tags: Vec<String>,        // Empty = any tags, multiple = AND logic
-    pub video_only: bool,         // If true, only show video files
+    pub media: MediaFilter,       // Any, or only videos / documents
+    pub camera: Option<String>,   // Exact camera name from EXIF (make + model)
+    pub lens: Option<String>,     // Exact lens name from EXIF
+    pub orientation: Option<Orientation>, // From width/height, not tags
 }
 
 impl FilterCriteria {
     pub fn is_active(&self) -> bool {
-        self.rating != RatingFilter::Any || !self.tags.is_empty() || self.video_only
+        self.rating != RatingFilter::Any
+            || !self.tags.is_empty()
+            || self.has_file_only_criteria()
+    }
+
+    /// Whether any criterion only files can meet (media type, camera, lens,
+    /// orientation). Directory ratings and tags can't stand in for these.
+    pub fn has_file_only_criteria(&self) -> bool {
+        self.media != MediaFilter::Any
+            || self.camera.is_some()
+            || self.lens.is_some()
+            || self.orientation.is_some()
     }
 
     /// Check whether a single file passes this filter.
     ///
     /// `file_tags` are tags on the file itself; `dir_tags` are inherited from
     /// the directory and its ancestors. When `ancestor_matches` is true the
-    /// directory already satisfies rating+tag criteria, so only `video_only`
-    /// is enforced.
+    /// directory already satisfies rating+tag criteria, so only `media`,
+    /// `camera`, `lens` and `orientation` are enforced.
     pub fn matches_file(
         &self,
         file: &crate::db::File,
@@ -33,8 +199,19 @@ impl FilterCriteria {
         dir_tags: &[String],
         ancestor_matches: bool,
     ) -> bool {
-        // video_only always applies, even when ancestor matches
-        if self.video_only && file.media_type.as_deref() != Some("video") {
+        // media and gear always apply, even when ancestor matches
+        if let Some(media_type) = self.media.media_type() {
+            if file.media_type.as_deref() != Some(media_type) {
+                return false;
+            }
+        }
+        if self.camera.is_some() && file.camera != self.camera {
+            return false;
+        }
+        if self.lens.is_some() && file.lens != self.lens {
+            return false;
+        }
+        if self.orientation.is_some() && Orientation::of(file.width, file.height) != self.orientation {
             return false;
         }
 
@@ -76,7 +253,7 @@ impl FilterCriteria {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FilterDialogFocus {
     Rating,
-    VideoOnly,
+    Media,
     Tag,
 }
 
@@ -223,9 +400,18 @@ pub struct FilterDialogState {
     pub tag_list_index: usize,
     pub tag_scroll_offset: usize,      // Scroll offset for tag list
     pub focus: FilterDialogFocus,
-    pub video_only: bool,              // Filter to show only videos
+    pub media: MediaFilter,            // Filter to show only videos / documents
+    pub cameras: Vec<String>,          // Cameras in the library, cycled with `c`
+    pub camera: Option<String>,
+    pub lenses: Vec<String>,           // Lenses in the library, cycled with `n`
+    pub lens: Option<String>,
+    pub orientation: Option<Orientation>, // Cycled with `o`
     pub tag_input_selected: bool,      // True when the input line is the selected item
     pub tag_editing: bool,             // True when actively typing in tag input
+    pub presets: Vec<crate::db::FilterPreset>, // Saved presets, activated with F1-F9
+    pub preset_name: Option<String>,   // Name being typed while saving a preset
+    pub rating_scale: RatingScale,     // Highest minimum rating offered
+    pub aliases: Vec<(String, String)>, // (alias, canonical), so typing an alias finds its tag
 }
 
 impl FilterDialogState {
@@ -240,19 +426,45 @@ impl FilterDialogState {
             tag_list_index: 0,
             tag_scroll_offset: 0,
             focus: FilterDialogFocus::Rating,
-            video_only: current_filter.video_only,
+            media: current_filter.media,
+            cameras: Vec::new(),
+            camera: current_filter.camera.clone(),
+            lenses: Vec::new(),
+            lens: current_filter.lens.clone(),
+            orientation: current_filter.orientation,
             tag_input_selected: true,
             tag_editing: false,
+            presets: Vec::new(),
+            preset_name: None,
+            rating_scale: RatingScale::default(),
+            aliases: Vec::new(),
         }
     }
 
+    /// Replace the dialog's criteria with a saved preset's (F1 is index 0).
+    /// Returns false if there is no such preset.
+    pub fn load_preset(&mut self, index: usize) -> bool {
+        let Some(preset) = self.presets.get(index) else {
+            return false;
+        };
+        self.rating_filter = preset.criteria.rating;
+        self.selected_tags = preset.criteria.tags.clone();
+        self.media = preset.criteria.media;
+        self.camera = preset.criteria.camera.clone();
+        self.lens = preset.criteria.lens.clone();
+        self.orientation = preset.criteria.orientation;
+        self.tag_editing = false;
+        self.update_tag_filter();
+        true
+    }
+
     pub fn update_tag_filter(&mut self) {
         let query = self.tag_input.to_lowercase();
         self.filtered_tags = self
             .all_tags
             .iter()
             .filter(|tag| {
-                tag.to_lowercase().contains(&query)
+                tag_matches(tag, &query, &self.aliases)
                     && !self.selected_tags.contains(tag)
             })
             .cloned()
@@ -325,7 +537,10 @@ impl FilterDialogState {
         FilterCriteria {
             rating: self.rating_filter,
             tags: self.selected_tags.clone(),
-            video_only: self.video_only,
+            media: self.media,
+            camera: self.camera.clone(),
+            lens: self.lens.clone(),
+            orientation: self.orientation,
         }
     }
 
@@ -350,28 +565,28 @@ impl FilterDialogState {
         self.update_tag_filter();
     }
 
-    /// Navigate up across sections (Tag list → VideoOnly → Rating)
+    /// Navigate up across sections (Tag list → Media → Rating)
     pub fn navigate_up(&mut self) {
         match self.focus {
             FilterDialogFocus::Tag => {
                 if !self.move_tag_list_up() {
-                    self.focus = FilterDialogFocus::VideoOnly;
+                    self.focus = FilterDialogFocus::Media;
                 }
             }
-            FilterDialogFocus::VideoOnly => {
+            FilterDialogFocus::Media => {
                 self.focus = FilterDialogFocus::Rating;
             }
             FilterDialogFocus::Rating => {}
         }
     }
 
-    /// Navigate down across sections (Rating → VideoOnly → Tag list)
+    /// Navigate down across sections (Rating → Media → Tag list)
     pub fn navigate_down(&mut self) {
         match self.focus {
             FilterDialogFocus::Rating => {
-                self.focus = FilterDialogFocus::VideoOnly;
+                self.focus = FilterDialogFocus::Media;
             }
-            FilterDialogFocus::VideoOnly => {
+            FilterDialogFocus::Media => {
                 self.focus = FilterDialogFocus::Tag;
             }
             FilterDialogFocus::Tag => {
@@ -380,29 +595,30 @@ impl FilterDialogState {
         }
     }
 
-    /// Cycle rating left (Any ← Unrated ← 1 ← 2 ← 3 ← 4 ← 5)
+    /// Cycle rating left (Any ← Unrated ← 1 ← 2 ← ... ← scale max)
     pub fn navigate_rating_left(&mut self) {
         if self.focus != FilterDialogFocus::Rating {
             return;
         }
         self.rating_filter = match self.rating_filter {
-            RatingFilter::Any => RatingFilter::MinRating(5),
+            RatingFilter::Any => RatingFilter::MinRating(self.rating_scale.max() as f64),
             RatingFilter::Unrated => RatingFilter::Any,
-            RatingFilter::MinRating(1) => RatingFilter::Unrated,
-            RatingFilter::MinRating(n) => RatingFilter::MinRating(n - 1),
+            RatingFilter::MinRating(n) if n <= 1.0 => RatingFilter::Unrated,
+            // A half-star minimum snaps to the whole rating below it
+            RatingFilter::MinRating(n) => RatingFilter::MinRating(n.ceil() - 1.0),
         };
     }
 
-    /// Cycle rating right (Any → Unrated → 1 → 2 → 3 → 4 → 5)
+    /// Cycle rating right (Any → Unrated → 1 → 2 → ... → scale max)
     pub fn navigate_rating_right(&mut self) {
         if self.focus != FilterDialogFocus::Rating {
             return;
         }
         self.rating_filter = match self.rating_filter {
             RatingFilter::Any => RatingFilter::Unrated,
-            RatingFilter::Unrated => RatingFilter::MinRating(1),
-            RatingFilter::MinRating(5) => RatingFilter::Any,
-            RatingFilter::MinRating(n) => RatingFilter::MinRating(n + 1),
+            RatingFilter::Unrated => RatingFilter::MinRating(1.0),
+            RatingFilter::MinRating(n) if n >= self.rating_scale.max() as f64 => RatingFilter::Any,
+            RatingFilter::MinRating(n) => RatingFilter::MinRating(n.floor() + 1.0),
         };
     }
 
@@ -410,8 +626,8 @@ impl FilterDialogState {
     pub fn cycle_focus_down(&mut self) {
         self.tag_editing = false;
         self.focus = match self.focus {
-            FilterDialogFocus::Rating => FilterDialogFocus::VideoOnly,
-            FilterDialogFocus::VideoOnly => FilterDialogFocus::Tag,
+            FilterDialogFocus::Rating => FilterDialogFocus::Media,
+            FilterDialogFocus::Media => FilterDialogFocus::Tag,
             FilterDialogFocus::Tag => FilterDialogFocus::Rating,
         };
         if self.focus == FilterDialogFocus::Tag {
@@ -424,24 +640,39 @@ impl FilterDialogState {
         self.tag_editing = false;
         self.focus = match self.focus {
             FilterDialogFocus::Rating => FilterDialogFocus::Tag,
-            FilterDialogFocus::VideoOnly => FilterDialogFocus::Rating,
-            FilterDialogFocus::Tag => FilterDialogFocus::VideoOnly,
+            FilterDialogFocus::Media => FilterDialogFocus::Rating,
+            FilterDialogFocus::Tag => FilterDialogFocus::Media,
         };
         if self.focus == FilterDialogFocus::Tag {
             self.tag_input_selected = true;
         }
     }
 
-    /// Set a specific minimum rating (1-5). Only works when Rating section focused.
-    pub fn set_rating(&mut self, rating: i32) {
+    /// Set a specific minimum rating (1 to the scale max). Only works when Rating section focused.
+    pub fn set_rating(&mut self, rating: f64) {
         if self.focus == FilterDialogFocus::Rating {
             self.rating_filter = RatingFilter::MinRating(rating);
         }
     }
 
-    /// Toggle video-only filter
-    pub fn toggle_video(&mut self) {
-        self.video_only = !self.video_only;
+    /// Cycle the media type filter (any → videos → documents)
+    pub fn cycle_media(&mut self) {
+        self.media = self.media.next();
+    }
+
+    /// Cycle the camera filter through the library's cameras, then back to any
+    pub fn cycle_camera(&mut self) {
+        self.camera = next_option(&self.cameras, self.camera.as_deref());
+    }
+
+    /// Cycle the lens filter through the library's lenses, then back to any
+    pub fn cycle_lens(&mut self) {
+        self.lens = next_option(&self.lenses, self.lens.as_deref());
+    }
+
+    /// Cycle the orientation filter through each class, then back to any
+    pub fn cycle_orientation(&mut self) {
+        self.orientation = Orientation::next(self.orientation);
     }
 
     /// Set the unrated filter. Only works when Rating section focused.
@@ -466,12 +697,30 @@ impl FilterDialogState {
     }
 }
 
+/// The option after `current` (None = any), wrapping back to None after the
+/// last. A value no longer in `options` starts over from the first.
+fn next_option(options: &[String], current: Option<&str>) -> Option<String> {
+    let next = match current {
+        None => 0,
+        Some(current) => options.iter().position(|o| o == current).map_or(0, |i| i + 1),
+    };
+    options.get(next).cloned()
+}
+
 /// Sort tags so prefix matches come before substring-only matches.
 /// Preserves alphabetical order within each group.
 fn sort_prefix_first(tags: &mut [String], query: &str) {
     tags.sort_by_key(|tag| !tag.to_lowercase().starts_with(query));
 }
 
+/// Whether `tag`, or one of its aliases, contains the lowercased `query`
+fn tag_matches(tag: &str, query: &str, aliases: &[(String, String)]) -> bool {
+    tag.to_lowercase().contains(query)
+        || aliases
+            .iter()
+            .any(|(alias, canonical)| canonical == tag && alias.to_lowercase().contains(query))
+}
+
 /// State for the tag input popup
 pub struct TagInputState {
     pub input: String,
@@ -484,6 +733,11 @@ pub struct TagInputState {
     pub editing: bool,
     /// Tags currently applied to the selected item (for toggle display)
     pub current_tags: Vec<String>,
+    /// The user's most recently applied tags, toggled with `1`-`9` while
+    /// browsing. Fixed while the popup is open so the keys don't shift.
+    pub recent_tags: Vec<String>,
+    /// Tag aliases as (alias, canonical), so typing an alias finds its tag
+    pub aliases: Vec<(String, String)>,
 }
 
 impl TagInputState {
@@ -501,6 +755,8 @@ impl TagInputState {
             input_selected: true,
             editing: true,
             current_tags,
+            recent_tags: Vec::new(),
+            aliases: Vec::new(),
         }
     }
 
@@ -514,7 +770,7 @@ impl TagInputState {
         self.filtered_tags = self
             .all_tags
             .iter()
-            .filter(|tag| tag.to_lowercase().contains(&query))
+            .filter(|tag| tag_matches(tag, &query, &self.aliases))
             .cloned()
             .collect();
         sort_prefix_first(&mut self.filtered_tags, &query);
@@ -664,18 +920,183 @@ impl SearchState {
     }
 }
 
+/// State for the goto prompt (`:`): jump to a directory by typing its path
+pub struct GotoState {
+    pub input: String,
+    /// Completion candidates for the input as (directory ID, path)
+    pub matches: Vec<(i64, String)>,
+    pub selected: usize,
+    /// Every directory in the library, captured when the prompt opens
+    directories: Vec<(i64, String)>,
+}
+
+impl GotoState {
+    pub fn new(mut directories: Vec<(i64, String)>) -> Self {
+        directories.retain(|(_, path)| !path.is_empty());
+        directories.sort_by(|a, b| a.1.cmp(&b.1));
+        let mut state = Self {
+            input: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            directories,
+        };
+        state.update_matches();
+        state
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.input.push(c);
+        self.update_matches();
+    }
+
+    pub fn pop_char(&mut self) {
+        self.input.pop();
+        self.update_matches();
+    }
+
+    pub fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.selected + 1 < self.matches.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Complete the input to the selected candidate, with a trailing slash
+    /// when it has subdirectories so Tab keeps descending
+    pub fn complete(&mut self) {
+        let Some((_, path)) = self.matches.get(self.selected) else {
+            return;
+        };
+        let prefix = format!("{}/", path);
+        let has_children = self.directories.iter().any(|(_, p)| p.starts_with(&prefix));
+        self.input = if has_children { prefix } else { path.clone() };
+        self.update_matches();
+    }
+
+    /// The directory to go to: an exact path match, else the selected candidate
+    pub fn target(&self) -> Option<i64> {
+        let typed = self.input.trim_end_matches('/').to_lowercase();
+        self.directories
+            .iter()
+            .find(|(_, path)| path.to_lowercase() == typed)
+            .or_else(|| self.matches.get(self.selected))
+            .map(|(id, _)| *id)
+    }
+
+    /// Candidates are the entries one level below the typed path, like
+    /// shell completion. If nothing completes, fall back to directories
+    /// whose path contains the input anywhere.
+    fn update_matches(&mut self) {
+        let query = self.input.to_lowercase();
+        let level_start = query.rfind('/').map(|i| i + 1).unwrap_or(0);
+
+        let mut matches: Vec<(i64, String)> = self
+            .directories
+            .iter()
+            .filter(|(_, path)| {
+                let path = path.to_lowercase();
+                path.starts_with(&query) && !path[level_start..].contains('/')
+            })
+            .cloned()
+            .collect();
+        if matches.is_empty() {
+            matches = self
+                .directories
+                .iter()
+                .filter(|(_, path)| path.to_lowercase().contains(&query))
+                .cloned()
+                .collect();
+        }
+
+        self.matches = matches;
+        self.selected = 0;
+    }
+}
+
+/// A failed TUI action, reported in a popup instead of ending the app
+pub struct ErrorPopupState {
+    /// What went wrong, from the outermost error context
+    pub message: String,
+    /// The causes under it, then the backtrace when one was captured
+    /// (`RUST_BACKTRACE=1`)
+    pub details: Vec<String>,
+    /// Whether the details are shown (`d`)
+    pub show_details: bool,
+}
+
+impl ErrorPopupState {
+    pub fn new(error: &anyhow::Error) -> Self {
+        let mut details: Vec<String> = error
+            .chain()
+            .skip(1)
+            .map(|cause| format!("Caused by: {}", cause))
+            .collect();
+        let backtrace = error.backtrace();
+        if backtrace.status() == std::backtrace::BacktraceStatus::Captured {
+            details.push(String::new());
+            details.extend(backtrace.to_string().lines().map(String::from));
+        }
+        Self {
+            message: error.to_string(),
+            details,
+            show_details: false,
+        }
+    }
+
+    pub fn toggle_details(&mut self) {
+        self.show_details = !self.show_details;
+    }
+}
+
+/// What the rating propagation items would do, shown in the menu as a dry run
+#[derive(Default)]
+pub struct RatingPreview {
+    pub directory_rating: Option<f64>,
+    pub unrated_files: usize,
+    pub rated_files: usize,
+    pub max: Option<f64>,
+    pub average: Option<f64>,
+}
+
+/// Tabs of the operations menu
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OperationsTab {
+    #[default]
+    Actions,
+    /// Recently finished operations, to run again on this directory
+    History,
+}
+
 /// State for operations menu popup
+#[derive(Default)]
 pub struct OperationsMenuState {
     pub directory_path: String,
     pub file_count: usize,
+    pub rating_preview: RatingPreview,
+    /// The directory's own tags, removed by "Untag recursively"
+    pub directory_tags: Vec<String>,
     pub selected: usize,
+    pub tab: OperationsTab,
+    /// Recent operations, newest first
+    pub history: Vec<crate::db::OperationRecord>,
+    pub history_selected: usize,
 }
 
 impl OperationsMenuState {
-    const ITEM_COUNT: usize = 5;
+    const ITEM_COUNT: usize = 11;
 
     /// Move selection up (wraps to bottom)
     pub fn move_up(&mut self) {
+        if self.tab == OperationsTab::History {
+            let count = self.history.len();
+            if count > 0 {
+                self.history_selected = (self.history_selected + count - 1) % count;
+            }
+            return;
+        }
         if self.selected > 0 {
             self.selected -= 1;
         } else {
@@ -685,12 +1106,148 @@ impl OperationsMenuState {
 
     /// Move selection down (wraps to top)
     pub fn move_down(&mut self) {
+        if self.tab == OperationsTab::History {
+            let count = self.history.len();
+            if count > 0 {
+                self.history_selected = (self.history_selected + 1) % count;
+            }
+            return;
+        }
         if self.selected < Self::ITEM_COUNT - 1 {
             self.selected += 1;
         } else {
             self.selected = 0;
         }
     }
+
+    pub fn switch_tab(&mut self) {
+        self.tab = match self.tab {
+            OperationsTab::Actions => OperationsTab::History,
+            OperationsTab::History => OperationsTab::Actions,
+        };
+    }
+
+    /// The history entry under the cursor
+    pub fn selected_record(&self) -> Option<&crate::db::OperationRecord> {
+        self.history.get(self.history_selected)
+    }
+}
+
+/// State for the disk usage view: one directory broken down into its
+/// largest subdirectories and files
+pub struct UsageViewState {
+    pub node: crate::db::UsageNode,
+    pub selected: usize,
+}
+
+impl UsageViewState {
+    /// Move selection up (wraps to bottom)
+    pub fn move_up(&mut self) {
+        let count = self.node.children.len();
+        if count > 0 {
+            self.selected = (self.selected + count - 1) % count;
+        }
+    }
+
+    /// Move selection down (wraps to top)
+    pub fn move_down(&mut self) {
+        let count = self.node.children.len();
+        if count > 0 {
+            self.selected = (self.selected + 1) % count;
+        }
+    }
+
+    pub fn selected_entry(&self) -> Option<&crate::db::UsageNode> {
+        self.node.children.get(self.selected)
+    }
+}
+
+/// Rows of the first-run setup dialog, top to bottom
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnboardingField {
+    Scan,
+    Hash,
+    Thumbnails,
+    Exclude,
+    Start,
+}
+
+/// State for the first-run setup dialog, shown when the TUI is opened on a
+/// directory without a database
+pub struct OnboardingState {
+    pub scan: bool,
+    pub hash: bool,
+    pub thumbnails: bool,
+    /// Comma-separated exclude patterns, as typed
+    pub exclude: String,
+    pub selected: OnboardingField,
+}
+
+impl Default for OnboardingState {
+    fn default() -> Self {
+        Self {
+            scan: true,
+            hash: false,
+            thumbnails: false,
+            exclude: String::new(),
+            selected: OnboardingField::Scan,
+        }
+    }
+}
+
+impl OnboardingState {
+    const FIELDS: [OnboardingField; 5] = [
+        OnboardingField::Scan,
+        OnboardingField::Hash,
+        OnboardingField::Thumbnails,
+        OnboardingField::Exclude,
+        OnboardingField::Start,
+    ];
+
+    fn selected_index(&self) -> usize {
+        Self::FIELDS.iter().position(|f| *f == self.selected).unwrap_or(0)
+    }
+
+    /// Move selection up (wraps to bottom)
+    pub fn move_up(&mut self) {
+        let index = self.selected_index();
+        self.selected = Self::FIELDS[(index + Self::FIELDS.len() - 1) % Self::FIELDS.len()];
+    }
+
+    /// Move selection down (wraps to top)
+    pub fn move_down(&mut self) {
+        self.selected = Self::FIELDS[(self.selected_index() + 1) % Self::FIELDS.len()];
+    }
+
+    /// Flip the selected checkbox. Hashes and thumbnails need a scan.
+    pub fn toggle(&mut self) {
+        match self.selected {
+            OnboardingField::Scan => self.scan = !self.scan,
+            OnboardingField::Hash if self.scan => self.hash = !self.hash,
+            OnboardingField::Thumbnails if self.scan => self.thumbnails = !self.thumbnails,
+            _ => {}
+        }
+    }
+
+    /// Whether hashes will be computed (only with a scan)
+    pub fn hash_enabled(&self) -> bool {
+        self.scan && self.hash
+    }
+
+    /// Whether thumbnails will be generated (only with a scan)
+    pub fn thumbnails_enabled(&self) -> bool {
+        self.scan && self.thumbnails
+    }
+
+    /// The typed exclude patterns, split on commas
+    pub fn exclude_patterns(&self) -> Vec<String> {
+        self.exclude
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(String::from)
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -825,7 +1382,7 @@ mod tests {
         assert!(!filter.is_active());
 
         let filter = FilterCriteria {
-            rating: RatingFilter::MinRating(3),
+            rating: RatingFilter::MinRating(3.0),
             ..Default::default()
         };
         assert!(filter.is_active());
@@ -837,7 +1394,7 @@ mod tests {
         assert!(filter.is_active());
 
         let filter = FilterCriteria {
-            video_only: true,
+            media: MediaFilter::Video,
             ..Default::default()
         };
         assert!(filter.is_active());
@@ -859,6 +1416,29 @@ mod tests {
         assert_eq!(dialog.filtered_tags, vec!["portrait"]);
     }
 
+    #[test]
+    fn test_filter_dialog_load_preset() {
+        let all_tags = vec!["landscape".to_string(), "portrait".to_string()];
+        let mut dialog = FilterDialogState::new(all_tags, &FilterCriteria::default());
+        let criteria = FilterCriteria {
+            rating: RatingFilter::MinRating(4.0),
+            tags: vec!["portrait".to_string()],
+            media: MediaFilter::Video,
+            camera: Some("Fujifilm X-T3".to_string()),
+            lens: None,
+            orientation: Some(Orientation::Portrait),
+        };
+        dialog.presets = vec![crate::db::FilterPreset {
+            name: "best".to_string(),
+            criteria: criteria.clone(),
+        }];
+
+        assert!(!dialog.load_preset(1));
+        assert!(dialog.load_preset(0));
+        assert_eq!(dialog.to_criteria(), criteria);
+        assert_eq!(dialog.filtered_tags, vec!["landscape"]);
+    }
+
     #[test]
     fn test_filter_dialog_excludes_selected_tags() {
         let all_tags = vec![
@@ -879,14 +1459,14 @@ mod tests {
     fn test_filter_dialog_to_criteria() {
         let all_tags = vec!["landscape".to_string()];
         let mut dialog = FilterDialogState::new(all_tags, &FilterCriteria::default());
-        dialog.rating_filter = RatingFilter::MinRating(3);
+        dialog.rating_filter = RatingFilter::MinRating(3.0);
         dialog.selected_tags = vec!["landscape".to_string()];
-        dialog.video_only = true;
+        dialog.media = MediaFilter::Document;
 
         let criteria = dialog.to_criteria();
-        assert_eq!(criteria.rating, RatingFilter::MinRating(3));
+        assert_eq!(criteria.rating, RatingFilter::MinRating(3.0));
         assert_eq!(criteria.tags, vec!["landscape"]);
-        assert!(criteria.video_only);
+        assert_eq!(criteria.media, MediaFilter::Document);
     }
 
     // ==================== TagInputState Tests ====================
@@ -915,6 +1495,15 @@ mod tests {
         assert_eq!(state.filtered_tags, vec!["screenshot", "landscape"]);
     }
 
+    #[test]
+    fn test_tag_input_filter_finds_tags_by_alias() {
+        let mut state = TagInputState::new(vec!["new york".to_string(), "sunset".to_string()]);
+        state.aliases = vec![("nyc".to_string(), "new york".to_string())];
+        state.input = "NY".to_string();
+        state.update_filter();
+        assert_eq!(state.filtered_tags, vec!["new york"]);
+    }
+
     #[test]
     fn test_filter_dialog_prefers_prefix_matches() {
         let all_tags = vec![
@@ -1088,7 +1677,7 @@ mod tests {
         dialog.focus = FilterDialogFocus::Tag;
         dialog.tag_input_selected = true; // at top of tag section
         dialog.navigate_up();
-        assert_eq!(dialog.focus, FilterDialogFocus::VideoOnly);
+        assert_eq!(dialog.focus, FilterDialogFocus::Media);
     }
 
     #[test]
@@ -1105,7 +1694,7 @@ mod tests {
     #[test]
     fn test_filter_dialog_navigate_up_video_to_rating() {
         let mut dialog = make_filter_dialog();
-        dialog.focus = FilterDialogFocus::VideoOnly;
+        dialog.focus = FilterDialogFocus::Media;
         dialog.navigate_up();
         assert_eq!(dialog.focus, FilterDialogFocus::Rating);
     }
@@ -1123,13 +1712,13 @@ mod tests {
         let mut dialog = make_filter_dialog();
         dialog.focus = FilterDialogFocus::Rating;
         dialog.navigate_down();
-        assert_eq!(dialog.focus, FilterDialogFocus::VideoOnly);
+        assert_eq!(dialog.focus, FilterDialogFocus::Media);
     }
 
     #[test]
     fn test_filter_dialog_navigate_down_from_video() {
         let mut dialog = make_filter_dialog();
-        dialog.focus = FilterDialogFocus::VideoOnly;
+        dialog.focus = FilterDialogFocus::Media;
         dialog.navigate_down();
         assert_eq!(dialog.focus, FilterDialogFocus::Tag);
     }
@@ -1152,14 +1741,14 @@ mod tests {
 
         // Any -> 5
         dialog.navigate_rating_left();
-        assert_eq!(dialog.rating_filter, RatingFilter::MinRating(5));
+        assert_eq!(dialog.rating_filter, RatingFilter::MinRating(5.0));
 
         // 5 -> 4
         dialog.navigate_rating_left();
-        assert_eq!(dialog.rating_filter, RatingFilter::MinRating(4));
+        assert_eq!(dialog.rating_filter, RatingFilter::MinRating(4.0));
 
         // ... all the way to 1 -> Unrated -> Any
-        dialog.rating_filter = RatingFilter::MinRating(1);
+        dialog.rating_filter = RatingFilter::MinRating(1.0);
         dialog.navigate_rating_left();
         assert_eq!(dialog.rating_filter, RatingFilter::Unrated);
         dialog.navigate_rating_left();
@@ -1184,10 +1773,16 @@ mod tests {
         dialog.navigate_rating_right();
         assert_eq!(dialog.rating_filter, RatingFilter::Unrated);
         dialog.navigate_rating_right();
-        assert_eq!(dialog.rating_filter, RatingFilter::MinRating(1));
-        dialog.rating_filter = RatingFilter::MinRating(5);
+        assert_eq!(dialog.rating_filter, RatingFilter::MinRating(1.0));
+        dialog.rating_filter = RatingFilter::MinRating(5.0);
         dialog.navigate_rating_right();
         assert_eq!(dialog.rating_filter, RatingFilter::Any);
+
+        // A 10-point library goes on past 5
+        dialog.rating_scale = RatingScale::Ten;
+        dialog.rating_filter = RatingFilter::MinRating(5.0);
+        dialog.navigate_rating_right();
+        assert_eq!(dialog.rating_filter, RatingFilter::MinRating(6.0));
     }
 
     #[test]
@@ -1197,7 +1792,7 @@ mod tests {
         dialog.tag_editing = true;
 
         dialog.cycle_focus_down();
-        assert_eq!(dialog.focus, FilterDialogFocus::VideoOnly);
+        assert_eq!(dialog.focus, FilterDialogFocus::Media);
         assert!(!dialog.tag_editing); // editing cleared on focus change
 
         dialog.cycle_focus_down();
@@ -1220,7 +1815,7 @@ mod tests {
         assert!(dialog.tag_input_selected);
 
         dialog.cycle_focus_up();
-        assert_eq!(dialog.focus, FilterDialogFocus::VideoOnly);
+        assert_eq!(dialog.focus, FilterDialogFocus::Media);
 
         dialog.cycle_focus_up();
         assert_eq!(dialog.focus, FilterDialogFocus::Rating);
@@ -1230,26 +1825,28 @@ mod tests {
     fn test_filter_dialog_set_rating() {
         let mut dialog = make_filter_dialog();
         dialog.focus = FilterDialogFocus::Rating;
-        dialog.set_rating(3);
-        assert_eq!(dialog.rating_filter, RatingFilter::MinRating(3));
+        dialog.set_rating(3.0);
+        assert_eq!(dialog.rating_filter, RatingFilter::MinRating(3.0));
     }
 
     #[test]
     fn test_filter_dialog_set_rating_ignored_on_other_focus() {
         let mut dialog = make_filter_dialog();
         dialog.focus = FilterDialogFocus::Tag;
-        dialog.set_rating(3);
+        dialog.set_rating(3.0);
         assert_eq!(dialog.rating_filter, RatingFilter::Any); // unchanged
     }
 
     #[test]
-    fn test_filter_dialog_toggle_video() {
+    fn test_filter_dialog_cycle_media() {
         let mut dialog = make_filter_dialog();
-        assert!(!dialog.video_only);
-        dialog.toggle_video();
-        assert!(dialog.video_only);
-        dialog.toggle_video();
-        assert!(!dialog.video_only);
+        assert_eq!(dialog.media, MediaFilter::Any);
+        dialog.cycle_media();
+        assert_eq!(dialog.media, MediaFilter::Video);
+        dialog.cycle_media();
+        assert_eq!(dialog.media, MediaFilter::Document);
+        dialog.cycle_media();
+        assert_eq!(dialog.media, MediaFilter::Any);
     }
 
     #[test]
@@ -1322,14 +1919,46 @@ mod tests {
         assert_eq!(state.filtered_tags, vec!["portrait", "landscape"]);
     }
 
+    // ==================== OnboardingState Tests ====================
+
+    #[test]
+    fn test_onboarding_navigation_wraps() {
+        let mut state = OnboardingState::default();
+        state.move_up();
+        assert_eq!(state.selected, OnboardingField::Start);
+        state.move_down();
+        state.move_down();
+        assert_eq!(state.selected, OnboardingField::Hash);
+    }
+
+    #[test]
+    fn test_onboarding_options_need_scan() {
+        let mut state = OnboardingState { selected: OnboardingField::Hash, ..Default::default() };
+        state.toggle();
+        assert!(state.hash_enabled());
+
+        state.selected = OnboardingField::Scan;
+        state.toggle();
+        assert!(!state.hash_enabled());
+        state.selected = OnboardingField::Thumbnails;
+        state.toggle();
+        assert!(!state.thumbnails && !state.thumbnails_enabled());
+    }
+
+    #[test]
+    fn test_onboarding_exclude_patterns() {
+        let state = OnboardingState { exclude: " @eaDir, *.tmp ,,".to_string(), ..Default::default() };
+        assert_eq!(state.exclude_patterns(), vec!["@eaDir", "*.tmp"]);
+        assert!(OnboardingState::default().exclude_patterns().is_empty());
+    }
+
     // ==================== OperationsMenuState Tests ====================
 
     #[test]
     fn test_operations_menu_move_down() {
         let mut menu = OperationsMenuState {
-            directory_path: String::new(),
-            file_count: 0,
             selected: 0,
+            ..Default::default()
         };
         menu.move_down();
         assert_eq!(menu.selected, 1);
@@ -1340,9 +1969,8 @@ mod tests {
     #[test]
     fn test_operations_menu_move_down_wraps() {
         let mut menu = OperationsMenuState {
-            directory_path: String::new(),
-            file_count: 0,
-            selected: 4,
+            selected: 10,
+            ..Default::default()
         };
         menu.move_down();
         assert_eq!(menu.selected, 0);
@@ -1351,9 +1979,8 @@ mod tests {
     #[test]
     fn test_operations_menu_move_up() {
         let mut menu = OperationsMenuState {
-            directory_path: String::new(),
-            file_count: 0,
             selected: 3,
+            ..Default::default()
         };
         menu.move_up();
         assert_eq!(menu.selected, 2);
@@ -1362,19 +1989,46 @@ mod tests {
     #[test]
     fn test_operations_menu_move_up_wraps() {
         let mut menu = OperationsMenuState {
-            directory_path: String::new(),
-            file_count: 0,
             selected: 0,
+            ..Default::default()
         };
         menu.move_up();
-        assert_eq!(menu.selected, 4);
+        assert_eq!(menu.selected, 10);
+    }
+
+    #[test]
+    fn test_operations_menu_history_tab() {
+        let record = |operation: &str| crate::db::OperationRecord {
+            operation: operation.to_string(),
+            directory: String::new(),
+            finished_at: 0,
+            duration_ms: 0,
+            items: 1,
+            cancelled: false,
+        };
+        let mut menu = OperationsMenuState {
+            selected: 2,
+            history: vec![record("hash"), record("thumbnails")],
+            ..Default::default()
+        };
+
+        menu.switch_tab();
+        assert_eq!(menu.tab, OperationsTab::History);
+        assert_eq!(menu.selected_record().unwrap().operation, "hash");
+        menu.move_up();
+        assert_eq!(menu.selected_record().unwrap().operation, "thumbnails");
+        menu.move_down();
+        assert_eq!(menu.history_selected, 0);
+        // The actions cursor is untouched
+        menu.switch_tab();
+        assert_eq!(menu.selected, 2);
     }
 
     // ==================== FilterCriteria::matches_file Tests ====================
 
     use crate::db::File;
 
-    fn make_file(media_type: Option<&str>, rating: Option<i32>) -> File {
+    fn make_file(media_type: Option<&str>, rating: Option<f64>) -> File {
         File {
             id: 1,
             directory_id: 1,
@@ -1387,22 +2041,24 @@ mod tests {
             width: None,
             height: None,
             perceptual_hash: None,
+            camera: None,
+            lens: None,
         }
     }
 
     #[test]
     fn test_matches_file_no_filter_passes_all() {
         let filter = FilterCriteria::default();
-        let file = make_file(Some("image"), Some(3));
+        let file = make_file(Some("image"), Some(3.0));
         assert!(filter.matches_file(&file, &[], &[], false));
     }
 
     #[test]
     fn test_matches_file_ancestor_match_bypasses_rating_and_tag_filters() {
         let filter = FilterCriteria {
-            rating: RatingFilter::MinRating(5),
+            rating: RatingFilter::MinRating(5.0),
             tags: vec!["rare".to_string()],
-            video_only: false,
+            ..Default::default()
         };
         let file = make_file(Some("image"), None);
         // ancestor_matches=true should bypass rating and tag checks
@@ -1410,17 +2066,116 @@ mod tests {
     }
 
     #[test]
-    fn test_matches_file_video_only_applies_even_with_ancestor_match() {
+    fn test_matches_file_media_filter_applies_even_with_ancestor_match() {
         let filter = FilterCriteria {
-            rating: RatingFilter::Any,
-            tags: vec![],
-            video_only: true,
+            media: MediaFilter::Video,
+            ..Default::default()
         };
         let image_file = make_file(Some("image"), None);
         let video_file = make_file(Some("video"), None);
-        // video_only should always apply, even with ancestor match
+        // media filter should always apply, even with ancestor match
         assert!(!filter.matches_file(&image_file, &[], &[], true));
         assert!(filter.matches_file(&video_file, &[], &[], true));
+
+        let filter = FilterCriteria {
+            media: MediaFilter::Document,
+            ..Default::default()
+        };
+        assert!(!filter.matches_file(&video_file, &[], &[], true));
+        assert!(filter.matches_file(&make_file(Some("document"), None), &[], &[], true));
+    }
+
+    #[test]
+    fn test_matches_file_gear_filter_applies_even_with_ancestor_match() {
+        let filter = FilterCriteria {
+            camera: Some("Fujifilm X-T3".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.is_active());
+        let mut file = make_file(Some("image"), None);
+        assert!(!filter.matches_file(&file, &[], &[], true));
+        file.camera = Some("Fujifilm X-T3".to_string());
+        assert!(filter.matches_file(&file, &[], &[], true));
+
+        let filter = FilterCriteria {
+            lens: Some("XF35mmF1.4 R".to_string()),
+            ..filter
+        };
+        assert!(!filter.matches_file(&file, &[], &[], false));
+        file.lens = Some("XF35mmF1.4 R".to_string());
+        assert!(filter.matches_file(&file, &[], &[], false));
+    }
+
+    #[test]
+    fn test_orientation_classes() {
+        let of = |w, h| Orientation::of(Some(w), Some(h));
+        assert_eq!(of(6000, 4000), Some(Orientation::Landscape));
+        assert_eq!(of(4000, 6000), Some(Orientation::Portrait));
+        assert_eq!(of(1080, 1080), Some(Orientation::Square));
+        assert_eq!(of(1040, 1000), Some(Orientation::Square), "within 5%");
+        assert_eq!(of(1100, 1000), Some(Orientation::Landscape));
+        assert_eq!(of(12000, 3000), Some(Orientation::Panorama));
+        assert_eq!(of(2000, 4000), Some(Orientation::Tall));
+        assert_eq!(of(12000, 6000), Some(Orientation::Panorama), "exactly 2:1");
+        assert_eq!(Orientation::of(None, Some(100)), None);
+        assert_eq!(of(0, 0), None);
+
+        assert_eq!(Orientation::Portrait.aspect_ratio(), "standard");
+        assert_eq!(Orientation::Landscape.badge(), None);
+        assert_eq!(Orientation::Panorama.badge(), Some("pano"));
+
+        assert_eq!("Square".parse::<Orientation>(), Ok(Orientation::Square));
+        assert!("diagonal".parse::<Orientation>().is_err());
+    }
+
+    #[test]
+    fn test_matches_file_orientation_uses_dimensions() {
+        let filter = FilterCriteria {
+            orientation: Some(Orientation::Portrait),
+            ..Default::default()
+        };
+        assert!(filter.has_file_only_criteria());
+        let mut file = make_file(Some("image"), None);
+        // Files without dimensions have no orientation, whatever they're tagged
+        assert!(!filter.matches_file(&file, &["portrait".to_string()], &[], true));
+        (file.width, file.height) = (Some(3000), Some(4000));
+        assert!(filter.matches_file(&file, &[], &[], true));
+        (file.width, file.height) = (Some(4000), Some(3000));
+        assert!(!filter.matches_file(&file, &[], &[], false));
+    }
+
+    #[test]
+    fn test_filter_dialog_cycle_orientation() {
+        let mut dialog = FilterDialogState::new(vec![], &FilterCriteria::default());
+        let mut seen = Vec::new();
+        for _ in 0..6 {
+            dialog.cycle_orientation();
+            seen.push(dialog.orientation);
+        }
+        assert_eq!(
+            seen,
+            vec![
+                Some(Orientation::Landscape),
+                Some(Orientation::Portrait),
+                Some(Orientation::Square),
+                Some(Orientation::Panorama),
+                Some(Orientation::Tall),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_dialog_cycle_camera() {
+        let mut dialog = FilterDialogState::new(vec![], &FilterCriteria::default());
+        dialog.cameras = vec!["Canon EOS R5".to_string(), "iPhone 13".to_string()];
+
+        dialog.cycle_camera();
+        assert_eq!(dialog.camera.as_deref(), Some("Canon EOS R5"));
+        dialog.cycle_camera();
+        assert_eq!(dialog.camera.as_deref(), Some("iPhone 13"));
+        dialog.cycle_camera();
+        assert_eq!(dialog.camera, None);
     }
 
     #[test]
@@ -1430,19 +2185,19 @@ mod tests {
             ..Default::default()
         };
         assert!(filter.matches_file(&make_file(Some("image"), None), &[], &[], false));
-        assert!(!filter.matches_file(&make_file(Some("image"), Some(3)), &[], &[], false));
+        assert!(!filter.matches_file(&make_file(Some("image"), Some(3.0)), &[], &[], false));
     }
 
     #[test]
     fn test_matches_file_rating_min() {
         let filter = FilterCriteria {
-            rating: RatingFilter::MinRating(3),
+            rating: RatingFilter::MinRating(3.0),
             ..Default::default()
         };
         assert!(!filter.matches_file(&make_file(Some("image"), None), &[], &[], false));
-        assert!(!filter.matches_file(&make_file(Some("image"), Some(2)), &[], &[], false));
-        assert!(filter.matches_file(&make_file(Some("image"), Some(3)), &[], &[], false));
-        assert!(filter.matches_file(&make_file(Some("image"), Some(5)), &[], &[], false));
+        assert!(!filter.matches_file(&make_file(Some("image"), Some(2.0)), &[], &[], false));
+        assert!(filter.matches_file(&make_file(Some("image"), Some(3.0)), &[], &[], false));
+        assert!(filter.matches_file(&make_file(Some("image"), Some(5.0)), &[], &[], false));
     }
 
     #[test]
@@ -1529,4 +2284,74 @@ mod tests {
         // Browse mode (empty input): cursor position preserved (clamped)
         assert_eq!(input.selected_index, 1);
     }
+
+    // ==================== GotoState Tests ====================
+
+    fn goto_directories() -> Vec<(i64, String)> {
+        vec![
+            (1, "photos".to_string()),
+            (2, "photos/vacation".to_string()),
+            (3, "photos/vacation/beach".to_string()),
+            (4, "videos".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_goto_completes_one_level_at_a_time() {
+        let mut goto = GotoState::new(goto_directories());
+        let paths = |g: &GotoState| g.matches.iter().map(|(_, p)| p.clone()).collect::<Vec<_>>();
+        assert_eq!(paths(&goto), vec!["photos", "videos"]);
+
+        goto.push_char('P');
+        assert_eq!(paths(&goto), vec!["photos"]);
+
+        // Tab descends into directories that have children
+        goto.complete();
+        assert_eq!(goto.input, "photos/");
+        assert_eq!(paths(&goto), vec!["photos/vacation"]);
+
+        goto.complete();
+        goto.complete();
+        assert_eq!(goto.input, "photos/vacation/beach");
+        assert_eq!(goto.target(), Some(3));
+    }
+
+    #[test]
+    fn test_goto_falls_back_to_substring_match() {
+        let mut goto = GotoState::new(goto_directories());
+        for c in "beach".chars() {
+            goto.push_char(c);
+        }
+        assert_eq!(goto.matches, vec![(3, "photos/vacation/beach".to_string())]);
+        assert_eq!(goto.target(), Some(3));
+    }
+
+    #[test]
+    fn test_goto_target_prefers_exact_path() {
+        let mut goto = GotoState::new(goto_directories());
+        for c in "photos/".chars() {
+            goto.push_char(c);
+        }
+        // Candidates are the children, but the typed path itself exists
+        assert_eq!(goto.matches[0].0, 2);
+        assert_eq!(goto.target(), Some(1));
+
+        goto.input = "nowhere".to_string();
+        goto.update_matches();
+        assert_eq!(goto.target(), None);
+    }
+
+    // ==================== ErrorPopupState Tests ====================
+
+    #[test]
+    fn test_error_popup_splits_message_from_causes() {
+        let error = anyhow::anyhow!("disk full").context("Failed to rename IMG_1.jpg");
+        let mut popup = ErrorPopupState::new(&error);
+        assert_eq!(popup.message, "Failed to rename IMG_1.jpg");
+        assert_eq!(popup.details[0], "Caused by: disk full");
+        assert!(!popup.show_details);
+
+        popup.toggle_details();
+        assert!(popup.show_details);
+    }
 }
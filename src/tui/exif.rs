@@ -10,10 +10,41 @@ pub struct ExifInfo {
     pub shutter_speed: Option<String>,
     pub iso: Option<String>,
     pub focal_length: Option<String>,
+    /// ISO, aperture (f-number) and exposure time in seconds as numbers,
+    /// for storing and range filtering
+    pub iso_speed: Option<u32>,
+    pub f_number: Option<f64>,
+    pub exposure_time: Option<f64>,
+    /// E.g. "aperture priority", "manual"
+    pub exposure_program: Option<String>,
+    /// E.g. "pattern", "spot"
+    pub metering_mode: Option<String>,
+    /// Whether and how the flash fired, e.g. "fired, auto mode"
+    pub flash: Option<String>,
+    pub exposure_bias: Option<String>,
+    /// Capture time as "YYYY-MM-DD HH:MM:SS"
+    pub date_taken: Option<String>,
     pub gps_lat: Option<f64>,
     pub gps_lon: Option<f64>,
+    /// Meters above sea level (negative below)
+    pub gps_altitude: Option<f64>,
+    /// Every field in the file, in file order, for the full dump
+    pub tags: Vec<ExifTag>,
 }
 
+/// One EXIF field as stored, for the full tag dump
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExifTag {
+    /// "primary" for the image itself, "thumbnail" for the embedded preview
+    pub ifd: &'static str,
+    pub name: String,
+    pub value: String,
+}
+
+/// Longest value shown in the dump; maker notes and embedded blobs run to
+/// kilobytes of hex
+const MAX_TAG_VALUE_LEN: usize = 120;
+
 impl ExifInfo {
     pub fn has_any(&self) -> bool {
         self.camera_make.is_some()
@@ -23,8 +54,24 @@ impl ExifInfo {
             || self.shutter_speed.is_some()
             || self.iso.is_some()
             || self.focal_length.is_some()
+            || self.exposure_program.is_some()
+            || self.metering_mode.is_some()
+            || self.flash.is_some()
+            || self.date_taken.is_some()
             || self.gps_lat.is_some()
     }
+
+    /// Make and model as one name, e.g. "Fujifilm X-T3". Models that
+    /// already start with the brand ("Canon EOS R5", "NIKON D750" from
+    /// "NIKON CORPORATION") are used as they are.
+    pub fn camera(&self) -> Option<String> {
+        let first_word = |s: &str| s.split_whitespace().next().unwrap_or("").to_lowercase();
+        match (self.camera_make.as_deref(), self.camera_model.as_deref()) {
+            (Some(make), Some(model)) if first_word(make) == first_word(model) => Some(model.to_string()),
+            (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+            (make, model) => make.or(model).map(str::to_string),
+        }
+    }
 }
 
 /// Read EXIF metadata from an image file (header-only, fast)
@@ -56,8 +103,39 @@ pub fn read_exif(path: &Path) -> ExifInfo {
         }
     };
 
+    let iso_speed = [exif::Tag::PhotographicSensitivity, exif::Tag::ISOSpeed]
+        .into_iter()
+        .find_map(|tag| exif.get_field(tag, exif::In::PRIMARY)?.value.get_uint(0))
+        .filter(|&iso| iso > 0);
+    let positive = |v: f64| v.is_finite() && v > 0.0;
+
     let gps_lat = parse_gps_coord(&exif, exif::Tag::GPSLatitude, exif::Tag::GPSLatitudeRef);
     let gps_lon = parse_gps_coord(&exif, exif::Tag::GPSLongitude, exif::Tag::GPSLongitudeRef);
+    let gps_altitude = get_rational(exif::Tag::GPSAltitude).map(|alt| {
+        // Ref 1 means below sea level
+        let below = exif
+            .get_field(exif::Tag::GPSAltitudeRef, exif::In::PRIMARY)
+            .and_then(|f| f.value.get_uint(0))
+            == Some(1);
+        if below { -alt } else { alt }
+    });
+
+    let tags = exif
+        .fields()
+        .map(|field| {
+            let mut value = field.display_value().with_unit(&exif).to_string();
+            if value.len() > MAX_TAG_VALUE_LEN {
+                let end = (0..=MAX_TAG_VALUE_LEN).rev().find(|&i| value.is_char_boundary(i)).unwrap_or(0);
+                value.truncate(end);
+                value.push('…');
+            }
+            ExifTag {
+                ifd: if field.ifd_num == exif::In::THUMBNAIL { "thumbnail" } else { "primary" },
+                name: field.tag.to_string(),
+                value: value.trim_matches('"').to_string(),
+            }
+        })
+        .collect();
 
     ExifInfo {
         camera_make: get_str(exif::Tag::Make),
@@ -69,11 +147,38 @@ pub fn read_exif(path: &Path) -> ExifInfo {
             .or_else(|| get_str(exif::Tag::ISOSpeed))
             .map(|s| format!("ISO {}", s)),
         focal_length: get_rational(exif::Tag::FocalLength).map(|f| format!("{:.0} mm", f)),
+        iso_speed,
+        f_number: get_rational(exif::Tag::FNumber).filter(|&f| positive(f)),
+        exposure_time: get_rational(exif::Tag::ExposureTime).filter(|&t| positive(t)),
+        exposure_program: get_str(exif::Tag::ExposureProgram),
+        metering_mode: get_str(exif::Tag::MeteringMode),
+        flash: get_str(exif::Tag::Flash),
+        exposure_bias: get_str(exif::Tag::ExposureBiasValue),
+        date_taken: get_str(exif::Tag::DateTimeOriginal).or_else(|| get_str(exif::Tag::DateTime)),
         gps_lat,
         gps_lon,
+        gps_altitude,
+        tags,
     }
 }
 
+/// Year and month from an EXIF date ("2024-03-15 10:20:30" or "2024:03:15 10:20:30")
+pub fn parse_year_month(date: &str) -> Option<(i32, u32)> {
+    let year = date.get(0..4)?.parse().ok()?;
+    let month = date.get(5..7)?.parse().ok()?;
+    if !(1..=12).contains(&month) {
+        return None;
+    }
+    Some((year, month))
+}
+
+/// Calendar day from an EXIF date, like [`parse_year_month`]
+pub fn parse_capture_date(date: &str) -> Option<chrono::NaiveDate> {
+    let (year, month) = parse_year_month(date)?;
+    let day = date.get(8..10)?.parse().ok()?;
+    chrono::NaiveDate::from_ymd_opt(year, month, day)
+}
+
 /// Parse GPS coordinate from EXIF (degrees, minutes, seconds + reference direction)
 fn parse_gps_coord(
     exif: &exif::Exif,
@@ -122,6 +227,71 @@ mod tests {
         assert!(!info.has_any());
     }
 
+    #[test]
+    fn test_read_exif_exposure_gps_and_full_dump() {
+        use exif::experimental::Writer;
+        use exif::{Field, In, Rational, Tag, Value};
+
+        let field = |tag, value| Field { tag, ifd_num: In::PRIMARY, value };
+        let ascii = |s: &str| Value::Ascii(vec![s.as_bytes().to_vec()]);
+        let rational = |n, d| Rational { num: n, denom: d };
+        let fields = [
+            field(Tag::Make, ascii("Fujifilm")),
+            field(Tag::LensModel, ascii("XF35mmF1.4 R")),
+            field(Tag::ExposureProgram, Value::Short(vec![3])),
+            field(Tag::MeteringMode, Value::Short(vec![5])),
+            field(Tag::Flash, Value::Short(vec![0x10])),
+            field(Tag::PhotographicSensitivity, Value::Short(vec![3200])),
+            field(Tag::FNumber, Value::Rational(vec![rational(14, 10)])),
+            field(Tag::ExposureTime, Value::Rational(vec![rational(1, 30)])),
+            field(Tag::GPSLatitudeRef, ascii("S")),
+            field(Tag::GPSLatitude, Value::Rational(vec![rational(33, 1), rational(52, 1), rational(0, 1)])),
+            field(Tag::GPSAltitudeRef, Value::Byte(vec![0])),
+            field(Tag::GPSAltitude, Value::Rational(vec![rational(1250, 10)])),
+            field(Tag::MakerNote, Value::Undefined(vec![0xab; 500], 0)),
+        ];
+        let mut writer = Writer::new();
+        for f in &fields {
+            writer.push_field(f);
+        }
+        let mut tiff = std::io::Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("photo.tif");
+        std::fs::write(&path, tiff.into_inner()).unwrap();
+
+        let info = read_exif(&path);
+        assert_eq!(info.lens.as_deref(), Some("XF35mmF1.4 R"));
+        assert_eq!(info.exposure_program.as_deref(), Some("aperture priority"));
+        assert_eq!(info.metering_mode.as_deref(), Some("pattern"));
+        assert!(info.flash.as_deref().unwrap().starts_with("not fired"));
+        assert_eq!(info.iso_speed, Some(3200));
+        assert_eq!(info.f_number, Some(1.4));
+        assert_eq!(info.exposure_time, Some(1.0 / 30.0));
+        assert!((info.gps_lat.unwrap() + 33.8667).abs() < 1e-3);
+        assert_eq!(info.gps_altitude, Some(125.0));
+
+        // The dump has every field, with blobs cut short
+        let make = info.tags.iter().find(|t| t.name == "Make").unwrap();
+        assert_eq!((make.ifd, make.value.as_str()), ("primary", "Fujifilm"));
+        let note = info.tags.iter().find(|t| t.name == "MakerNote").unwrap();
+        assert!(note.value.chars().count() <= MAX_TAG_VALUE_LEN + 1);
+        assert_eq!(info.tags.len(), fields.len());
+    }
+
+    #[test]
+    fn test_parse_dates() {
+        assert_eq!(parse_year_month("2024-03-15 10:20:30"), Some((2024, 3)));
+        assert_eq!(parse_year_month("2019:11:02 08:00:00"), Some((2019, 11)));
+        assert_eq!(parse_year_month("0000:00:00 00:00:00"), None);
+        assert_eq!(parse_year_month("soon"), None);
+
+        let day = |y, m, d| chrono::NaiveDate::from_ymd_opt(y, m, d);
+        assert_eq!(parse_capture_date("2019:11:02 08:00:00"), day(2019, 11, 2));
+        assert_eq!(parse_capture_date("2024-02-30 10:20:30"), None);
+        assert_eq!(parse_capture_date("2024-03"), None);
+    }
+
     #[test]
     fn test_exif_info_has_any() {
         let empty = ExifInfo::default();
@@ -133,4 +303,21 @@ mod tests {
         };
         assert!(with_make.has_any());
     }
+
+    #[test]
+    fn test_camera_name_drops_repeated_brand() {
+        let camera = |make: Option<&str>, model: Option<&str>| {
+            ExifInfo {
+                camera_make: make.map(str::to_string),
+                camera_model: model.map(str::to_string),
+                ..Default::default()
+            }
+            .camera()
+        };
+        assert_eq!(camera(Some("FUJIFILM"), Some("X-T3")).as_deref(), Some("FUJIFILM X-T3"));
+        assert_eq!(camera(Some("Canon"), Some("Canon EOS R5")).as_deref(), Some("Canon EOS R5"));
+        assert_eq!(camera(Some("NIKON CORPORATION"), Some("NIKON D750")).as_deref(), Some("NIKON D750"));
+        assert_eq!(camera(None, Some("iPhone 13")).as_deref(), Some("iPhone 13"));
+        assert_eq!(camera(None, None), None);
+    }
 }
@@ -3,10 +3,12 @@ pub mod colors;
 pub mod dialogs;
 pub mod exif;
 mod mouse;
+mod onboarding;
 mod operations;
 pub mod preview_cache;
 pub mod preview_loader;
 pub mod state;
+mod startup_sync;
 mod ui;
 pub mod widgets;
 
@@ -72,7 +72,9 @@ fn handle_left_click(
 ) -> Result<()> {
     let is_double = mouse_state.record_click(col, row);
 
-    if let Some(index) = tree_item_at(row, col, state) {
+    if let Some(dir_id) = breadcrumb_target_at(row, col, state) {
+        state.go_to_directory(dir_id);
+    } else if let Some(index) = tree_item_at(row, col, state) {
         state.focus = Focus::DirectoryTree;
         state.select_tree_index(index);
         if is_double {
@@ -163,14 +165,22 @@ fn tree_item_at(row: u16, col: u16, state: &AppState) -> Option<usize> {
     let scroll_offset = state.tree.list_state.offset();
     let index = scroll_offset + row_in_list;
 
-    let visible_count = state.get_visible_directories().len();
-    if index < visible_count {
+    if index < state.tree_row_count() {
         Some(index)
     } else {
         None
     }
 }
 
+/// The directory of the breadcrumb segment at a click position, if any
+fn breadcrumb_target_at(row: u16, col: u16, state: &AppState) -> Option<i64> {
+    state
+        .breadcrumb_targets
+        .iter()
+        .find(|(area, _)| contains(*area, col, row))
+        .map(|(_, id)| *id)
+}
+
 /// Map a click position to a file list item index, or None if outside the data rows.
 ///
 /// The file list uses `Table` with `Borders::ALL` + a header row + 1-row `bottom_margin`.
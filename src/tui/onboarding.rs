@@ -0,0 +1,148 @@
+//! First-run setup: when the TUI is opened on a directory without a
+//! database, ask how to set the library up before scanning anything.
+
+use std::path::Path;
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use tracing::info;
+
+use crate::cli::{configure_excludes, run_generate_thumbnails, run_init, run_sync};
+use crate::db::Database;
+use crate::vfs::open_library_fs;
+
+use super::app::TerminalGuard;
+use super::state::{OnboardingField, OnboardingState};
+use super::widgets::render_onboarding;
+
+/// Show the setup dialog. Returns the choices, or `None` if the user quit.
+pub fn run_onboarding(library_path: &Path) -> Result<Option<OnboardingState>> {
+    let library = library_path
+        .canonicalize()
+        .unwrap_or_else(|_| library_path.to_path_buf())
+        .display()
+        .to_string();
+
+    let mut terminal = TerminalGuard::new()?;
+    let mut state = OnboardingState::default();
+    let result = (|| -> Result<Option<OnboardingState>> {
+        loop {
+            terminal.draw(|frame| render_onboarding(frame, frame.area(), &library, &state))?;
+
+            let Event::Key(key) = event::read()? else { continue };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if handle_key(key.code, &mut state) {
+                return Ok(match key.code {
+                    KeyCode::Enter => Some(std::mem::take(&mut state)),
+                    _ => None,
+                });
+            }
+        }
+    })();
+    drop(terminal);
+    result
+}
+
+/// Apply a key to the dialog; true when it closes (Enter starts, Esc quits)
+fn handle_key(code: KeyCode, state: &mut OnboardingState) -> bool {
+    let typing = state.selected == OnboardingField::Exclude;
+    match code {
+        KeyCode::Enter | KeyCode::Esc => return true,
+        KeyCode::Up | KeyCode::BackTab => state.move_up(),
+        KeyCode::Down | KeyCode::Tab => state.move_down(),
+        KeyCode::Backspace if typing => {
+            state.exclude.pop();
+        }
+        KeyCode::Char(c) if typing => state.exclude.push(c),
+        KeyCode::Char('k') => state.move_up(),
+        KeyCode::Char('j') => state.move_down(),
+        KeyCode::Char(' ') => state.toggle(),
+        _ => {}
+    }
+    false
+}
+
+/// Create the library as chosen. Runs outside the TUI so the usual
+/// progress bars show. Returns notes for the status bar.
+pub fn set_up_library(library_path: &Path, choices: &OnboardingState) -> Result<Vec<String>> {
+    let mut notes = Vec::new();
+    configure_excludes(library_path, &choices.exclude_patterns())?;
+
+    if !choices.scan {
+        Database::open(&library_path.join(".picman.db"))?;
+        notes.push("Library created without scanning; run 'picman sync' to scan it".to_string());
+        return Ok(notes);
+    }
+
+    println!("Scanning {}...", library_path.display());
+    let stats = run_init(library_path)?;
+    info!(dirs = stats.directories, files = stats.files, "init complete");
+    println!("Found {} files in {} directories", stats.files, stats.directories);
+    notes.push(format!("Init: {} dirs, {} files", stats.directories, stats.files));
+
+    if choices.hash_enabled() {
+        if open_library_fs(library_path)?.is_remote() {
+            notes.push("Hashing is not available for remote libraries".to_string());
+        } else {
+            println!("Computing file hashes...");
+            let sync = run_sync(library_path, true, false, false)?;
+            notes.push(format!("Hashed {} files", sync.files_hashed));
+        }
+    }
+
+    if choices.thumbnails_enabled() {
+        println!("Generating thumbnails...");
+        let thumbnails = run_generate_thumbnails(library_path)?;
+        notes.push(format!("{} thumbnails", thumbnails.generated));
+    }
+
+    Ok(notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_handle_key_types_only_in_exclude_field() {
+        let mut state = OnboardingState::default();
+        assert!(!handle_key(KeyCode::Char('j'), &mut state));
+        assert_eq!(state.selected, OnboardingField::Hash);
+        assert!(!handle_key(KeyCode::Char(' '), &mut state));
+        assert!(state.hash);
+
+        state.selected = OnboardingField::Exclude;
+        for c in "@eaDir j".chars() {
+            handle_key(KeyCode::Char(c), &mut state);
+        }
+        handle_key(KeyCode::Backspace, &mut state);
+        assert_eq!(state.exclude, "@eaDir ");
+        assert_eq!(state.selected, OnboardingField::Exclude);
+        assert!(handle_key(KeyCode::Enter, &mut state));
+    }
+
+    #[test]
+    fn test_set_up_library_applies_choices() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("trip/@eaDir")).unwrap();
+        fs::write(root.join("trip/a.jpg"), "data").unwrap();
+        fs::write(root.join("trip/@eaDir/a.jpg"), "data").unwrap();
+
+        let choices = OnboardingState {
+            hash: true,
+            exclude: "@eaDir".to_string(),
+            ..Default::default()
+        };
+        let notes = set_up_library(root, &choices).unwrap();
+        assert_eq!(notes, vec!["Init: 1 dirs, 1 files", "Hashed 1 files"]);
+
+        let db = Database::open(&root.join(".picman.db")).unwrap();
+        assert!(db.get_directory_by_path("trip/@eaDir").unwrap().is_none());
+        assert!(db.get_files_needing_hash(Default::default()).unwrap().is_empty());
+    }
+}
@@ -1,25 +1,60 @@
+use std::collections::VecDeque;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::db::Directory;
+use anyhow::{anyhow, Result};
+
+use crate::cli::{run_sync_incremental_with_progress, run_sync_subdir, Propagation, SyncStats};
+use crate::config::Config;
+use crate::db::{Directory, OperationRecord, RatingAggregate};
+use crate::notify::JobKind;
 use crate::scanner::detect_orientation;
+use crate::vfs::ScanProgress;
+use crate::library_path::LibraryPath;
 
-use super::dialogs::OperationsMenuState;
+use super::dialogs::{OperationsMenuState, RatingPreview};
 use super::state::AppState;
 
 /// Types of background operations
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OperationType {
     Thumbnails,
     Orientation,
     Hash,
     DirPreview,
     DirPreviewRecursive,
+    /// Incremental sync of the whole library (`F5`)
+    Sync,
 }
 
 impl OperationType {
+    const ALL: [OperationType; 6] = [
+        OperationType::Thumbnails,
+        OperationType::Orientation,
+        OperationType::Hash,
+        OperationType::DirPreview,
+        OperationType::DirPreviewRecursive,
+        OperationType::Sync,
+    ];
+
+    /// Name stored in the operation history
+    pub fn name(&self) -> &'static str {
+        match self {
+            OperationType::Thumbnails => "thumbnails",
+            OperationType::Orientation => "orientation",
+            OperationType::Hash => "hash",
+            OperationType::DirPreview => "dir_preview",
+            OperationType::DirPreviewRecursive => "dir_preview_recursive",
+            OperationType::Sync => "sync",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|op| op.name() == name)
+    }
+
     pub fn label(&self) -> &'static str {
         match self {
             OperationType::Thumbnails => "Generating thumbnails",
@@ -27,6 +62,7 @@ impl OperationType {
             OperationType::Hash => "Computing hashes",
             OperationType::DirPreview => "Generating dir preview",
             OperationType::DirPreviewRecursive => "Generating dir previews",
+            OperationType::Sync => "Syncing library",
         }
     }
 
@@ -37,20 +73,257 @@ impl OperationType {
             OperationType::Hash => "files hashed",
             OperationType::DirPreview => "dir preview generated",
             OperationType::DirPreviewRecursive => "dir previews generated",
+            OperationType::Sync => "directories scanned",
+        }
+    }
+
+    /// Which `[notifications]` setting covers this operation
+    fn job_kind(&self) -> Option<JobKind> {
+        match self {
+            OperationType::Thumbnails | OperationType::DirPreviewRecursive => Some(JobKind::Thumbnails),
+            OperationType::Hash => Some(JobKind::Hashing),
+            OperationType::Sync => Some(JobKind::Sync),
+            OperationType::Orientation | OperationType::DirPreview => None,
+        }
+    }
+}
+
+/// How far back a running task's rate looks. Long enough to smooth over
+/// a few slow files, short enough to follow a change of pace (videos after
+/// photos, a busy disk).
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(30);
+
+/// Least time between two throughput samples
+const THROUGHPUT_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Completed counts sampled over the last [`THROUGHPUT_WINDOW`], for a
+/// task's rate and time remaining
+#[derive(Debug, Default)]
+pub struct Throughput {
+    samples: VecDeque<(Instant, usize)>,
+}
+
+impl Throughput {
+    /// Note `completed` items at `at`
+    pub fn record(&mut self, at: Instant, completed: usize) {
+        if self
+            .samples
+            .back()
+            .is_some_and(|(last, _)| at.saturating_duration_since(*last) < THROUGHPUT_SAMPLE_INTERVAL)
+        {
+            return;
         }
+        self.samples.push_back((at, completed));
+        // Keep one sample at or beyond the window's start
+        while self.samples.len() > 2 && at.saturating_duration_since(self.samples[1].0) >= THROUGHPUT_WINDOW {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Items per second over the window, once it spans a second
+    pub fn rate(&self) -> Option<f64> {
+        let (first, last) = (self.samples.front()?, self.samples.back()?);
+        let secs = last.0.saturating_duration_since(first.0).as_secs_f64();
+        (secs >= 1.0).then(|| last.1.saturating_sub(first.1) as f64 / secs)
+    }
+
+    /// Time to finish `remaining` more items at the current rate
+    pub fn eta(&self, remaining: usize) -> Option<Duration> {
+        let rate = self.rate().filter(|&r| r > 0.0)?;
+        Some(Duration::from_secs_f64(remaining as f64 / rate))
     }
 }
 
 /// Progress tracker for background operations
 pub struct BackgroundProgress {
     pub operation: OperationType,
+    /// Directory the operation runs on (with everything below it)
+    pub directory: String,
     pub total: usize,
     pub completed: Arc<AtomicUsize>,
     pub done: Arc<AtomicBool>,
     pub cancelled: Arc<AtomicBool>,
     pub start_time: Instant,
+    /// Sampled from `completed` on each tick
+    pub throughput: Throughput,
 }
 
+impl BackgroundProgress {
+    fn new(operation: OperationType, directory: &str, total: usize) -> Self {
+        Self {
+            operation,
+            directory: directory.to_string(),
+            total,
+            completed: Arc::new(AtomicUsize::new(0)),
+            done: Arc::new(AtomicBool::new(false)),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            start_time: Instant::now(),
+            throughput: Throughput::default(),
+        }
+    }
+}
+
+/// A background operation that has ended, kept for the task list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinishedTask {
+    pub operation: OperationType,
+    pub directory: String,
+    pub completed: usize,
+    pub cancelled: bool,
+    pub elapsed: Duration,
+}
+
+impl FinishedTask {
+    fn to_record(&self) -> OperationRecord {
+        OperationRecord {
+            operation: self.operation.name().to_string(),
+            directory: self.directory.clone(),
+            finished_at: chrono::Utc::now().timestamp(),
+            duration_ms: self.elapsed.as_millis() as i64,
+            items: self.completed as i64,
+            cancelled: self.cancelled,
+        }
+    }
+
+    /// Completion notice, e.g. "120 files hashed"
+    pub fn summary(&self) -> String {
+        if self.cancelled {
+            format!("Cancelled - {} {}", self.completed, self.operation.done_label())
+        } else {
+            format!("{} {}", self.completed, self.operation.done_label())
+        }
+    }
+}
+
+/// How many finished tasks the task list remembers
+const FINISHED_TASKS_KEPT: usize = 5;
+
+/// Background operations. Different kinds run side by side (thumbnails
+/// while hashing); a second request for a kind that's already running
+/// waits in the queue, since both would work through the same files.
+#[derive(Default)]
+pub struct TaskManager {
+    pub running: Vec<BackgroundProgress>,
+    pub queue: VecDeque<OperationType>,
+    /// Most recently finished last
+    pub finished: VecDeque<FinishedTask>,
+    /// Whether the status area is expanded into the task list
+    pub expanded: bool,
+    /// Selected row in the task list: running tasks, then queued ones
+    pub selected: usize,
+    /// Outcome of the last background sync, left by its thread
+    sync_result: Arc<Mutex<Option<Result<SyncStats>>>>,
+}
+
+impl TaskManager {
+    pub fn is_busy(&self) -> bool {
+        !self.running.is_empty()
+    }
+
+    pub fn is_running(&self, operation: OperationType) -> bool {
+        self.running.iter().any(|task| task.operation == operation)
+    }
+
+    /// Rows in the task list that can be selected
+    pub fn row_count(&self) -> usize {
+        self.running.len() + self.queue.len()
+    }
+
+    pub fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.selected + 1 < self.row_count() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn toggle_expanded(&mut self) {
+        self.expanded = !self.expanded;
+        self.selected = 0;
+    }
+
+    /// Cancel the selected running task, or drop the selected queued one
+    pub fn cancel_selected(&mut self) -> Option<OperationType> {
+        let operation = if let Some(task) = self.running.get(self.selected) {
+            task.cancelled.store(true, Ordering::Relaxed);
+            task.operation
+        } else {
+            self.queue.remove(self.selected - self.running.len())?
+        };
+        self.selected = self.selected.min(self.row_count().saturating_sub(1));
+        Some(operation)
+    }
+
+    /// Cancel every running task and forget the queue
+    pub fn cancel_all(&mut self) {
+        self.queue.clear();
+        for task in &self.running {
+            task.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Sample each running task's progress for its rate
+    fn sample(&mut self, now: Instant) {
+        for task in &mut self.running {
+            task.throughput.record(now, task.completed.load(Ordering::Relaxed));
+        }
+    }
+
+    /// Take the tasks whose threads have finished, remembering them
+    fn reap(&mut self) -> Vec<FinishedTask> {
+        let mut reaped = Vec::new();
+        self.running.retain(|task| {
+            if !task.done.load(Ordering::Relaxed) {
+                return true;
+            }
+            reaped.push(FinishedTask {
+                operation: task.operation,
+                directory: task.directory.clone(),
+                completed: task.completed.load(Ordering::Relaxed),
+                cancelled: task.cancelled.load(Ordering::Relaxed),
+                elapsed: task.start_time.elapsed(),
+            });
+            false
+        });
+        for task in &reaped {
+            self.finished.push_back(task.clone());
+            if self.finished.len() > FINISHED_TASKS_KEPT {
+                self.finished.pop_front();
+            }
+        }
+        self.selected = self.selected.min(self.row_count().saturating_sub(1));
+        reaped
+    }
+
+    /// Take the queued operations whose kind is no longer running
+    fn take_startable(&mut self) -> Vec<OperationType> {
+        let mut startable: Vec<OperationType> = Vec::new();
+        self.queue.retain(|&operation| {
+            if self.running.iter().any(|task| task.operation == operation) || startable.contains(&operation) {
+                return true;
+            }
+            startable.push(operation);
+            false
+        });
+        startable
+    }
+
+    /// Height of the status area: one line, or the expanded task list
+    pub fn status_height(&self) -> u16 {
+        if !self.expanded {
+            return 1;
+        }
+        // Header, then one line per task, queued operation and notice
+        let rows = 1 + self.row_count().max(1) + self.finished.len();
+        rows.min(12) as u16
+    }
+}
+
+/// How many past operations the menu's history tab lists
+const HISTORY_SHOWN: usize = 50;
+
 /// Background operation and operations menu methods on AppState
 impl AppState {
     // ==================== Operations Menu Methods ====================
@@ -72,7 +345,10 @@ impl AppState {
             self.operations_menu = Some(OperationsMenuState {
                 directory_path: dir.path.clone(),
                 file_count,
-                selected: 0,
+                rating_preview: self.rating_preview(&dir),
+                directory_tags: self.db.get_directory_tags(dir.id).unwrap_or_default(),
+                history: self.db.get_operation_history(HISTORY_SHOWN).unwrap_or_default(),
+                ..Default::default()
             });
         }
     }
@@ -93,36 +369,183 @@ impl AppState {
         }
     }
 
+    /// Dry run of the rating propagation items for a directory
+    fn rating_preview(&self, dir: &Directory) -> RatingPreview {
+        let (max, rated_files) = self
+            .db
+            .aggregate_subtree_rating(&dir.path, RatingAggregate::Max)
+            .unwrap_or_default();
+        let (average, _) = self
+            .db
+            .aggregate_subtree_rating(&dir.path, RatingAggregate::Average)
+            .unwrap_or_default();
+
+        RatingPreview {
+            directory_rating: dir.rating,
+            unrated_files: self.db.count_unrated_subtree_files(&dir.path).unwrap_or(0),
+            rated_files,
+            max,
+            average,
+        }
+    }
+
     /// Execute the selected operation from menu
-    pub fn operations_menu_select(&mut self) {
-        let operation = if let Some(ref menu) = self.operations_menu {
-            match menu.selected {
-                0 => OperationType::Thumbnails,
-                1 => OperationType::Orientation,
-                2 => OperationType::Hash,
-                3 => OperationType::DirPreview,
-                4 => OperationType::DirPreviewRecursive,
-                _ => return,
-            }
-        } else {
-            return;
+    pub fn operations_menu_select(&mut self) -> Result<()> {
+        let Some(selected) = self.operations_menu.as_ref().map(|menu| menu.selected) else {
+            return Ok(());
         };
 
         self.operations_menu = None;
-        self.run_operation(operation);
+        match selected {
+            0 => self.run_operation(OperationType::Thumbnails),
+            1 => self.run_operation(OperationType::Orientation),
+            2 => self.run_operation(OperationType::Hash),
+            3 => self.run_operation(OperationType::DirPreview),
+            4 => self.run_operation(OperationType::DirPreviewRecursive),
+            5 => self.propagate_rating(Propagation::Down)?,
+            6 => self.propagate_rating(Propagation::Up(RatingAggregate::Max))?,
+            7 => self.propagate_rating(Propagation::Up(RatingAggregate::Average))?,
+            8 => self.materialize_tags()?,
+            9 => self.untag_recursively()?,
+            10 => self.rescan_directory()?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Sync the selected directory and everything below it with the disk,
+    /// e.g. after copying new photos into it
+    pub fn rescan_directory(&mut self) -> Result<()> {
+        let Some(dir) = self.get_selected_directory().cloned() else {
+            return Ok(());
+        };
+        if dir.path.is_empty() {
+            self.status_message = Some("Select a directory to rescan; the whole library syncs at startup".to_string());
+            return Ok(());
+        }
+
+        let stats = run_sync_subdir(&self.library_path, &dir.path, false, false, false)?;
+        self.reload_after_sync(dir.id)?;
+
+        self.status_message = Some(format!(
+            "Rescanned {}: +{} -{} directories, +{} -{} ~{} files",
+            dir.path,
+            stats.directories_added,
+            stats.directories_removed,
+            stats.files_added,
+            stats.files_removed,
+            stats.files_modified
+        ));
+        Ok(())
+    }
+
+    /// Reload the tree and file list after a sync, keeping the expanded
+    /// directories, the cursor on `dir_id` and the selected file
+    fn reload_after_sync(&mut self, dir_id: i64) -> Result<()> {
+        self.reload_directories()?;
+        self.update_matching_directories()?;
+        self.select_directory_in_tree(dir_id);
+        *self.missing_preview_cache.borrow_mut() = None;
+        self.invalidate_dashboard();
+        let selected_id = self.file_list.selected_file().map(|f| f.file.id);
+        self.reload_files_selecting(selected_id)
+    }
+
+    /// Copy tags inherited from directories onto the files below the selected directory
+    pub fn materialize_tags(&mut self) -> Result<()> {
+        let Some(dir) = self.get_selected_directory().cloned() else {
+            return Ok(());
+        };
+
+        let added = self.db.materialize_inherited_tags(&dir.path)?;
+        let selected_id = self.file_list.selected_file().map(|f| f.file.id);
+        self.reload_files_selecting(selected_id)?;
+        self.status_message = Some(format!("Added {} inherited tags to files", added));
+        Ok(())
+    }
+
+    /// Remove the selected directory's tags from it and everything below it
+    pub fn untag_recursively(&mut self) -> Result<()> {
+        let Some(dir) = self.get_selected_directory().cloned() else {
+            return Ok(());
+        };
+
+        let tags = self.db.get_directory_tags(dir.id)?;
+        if tags.is_empty() {
+            self.status_message = Some("Directory has no tags to remove".to_string());
+            return Ok(());
+        }
+
+        let (mut files, mut dirs) = (0, 0);
+        for tag in &tags {
+            let (f, d) = self.db.untag_subtree(&dir.path, tag)?;
+            files += f;
+            dirs += d;
+        }
+        self.snapshot.refresh_directories(&self.db)?;
+        let selected_id = self.file_list.selected_file().map(|f| f.file.id);
+        self.reload_files_selecting(selected_id)?;
+        self.status_message = Some(format!(
+            "Removed {} from {} files and {} directories",
+            tags.join(", "),
+            files,
+            dirs
+        ));
+        Ok(())
+    }
+
+    /// Push the selected directory's rating down to its unrated files, or
+    /// derive its rating from the files below it
+    pub fn propagate_rating(&mut self, propagation: Propagation) -> Result<()> {
+        let Some(dir) = self.get_selected_directory().cloned() else {
+            return Ok(());
+        };
+        let dir_name = if dir.path.is_empty() { "." } else { dir.path.as_str() };
+
+        match propagation {
+            Propagation::Down => {
+                let Some(rating) = dir.rating else {
+                    self.status_message = Some(format!("{} has no rating to propagate", dir_name));
+                    return Ok(());
+                };
+                let updated = self.db.rate_unrated_subtree_files(&dir.path, rating)?;
+                let selected_id = self.file_list.selected_file().map(|f| f.file.id);
+                self.reload_files_selecting(selected_id)?;
+                self.status_message = Some(format!(
+                    "Rated {} unrated files with {} stars",
+                    updated, rating
+                ));
+            }
+            Propagation::Up(aggregate) => {
+                let (derived, rated_files) = self.db.aggregate_subtree_rating(&dir.path, aggregate)?;
+                let Some(rating) = derived else {
+                    self.status_message = Some(format!("No rated files under {}", dir_name));
+                    return Ok(());
+                };
+                self.db.set_directory_rating(dir.id, Some(rating))?;
+                if let Some(d) = self.tree.directories.iter_mut().find(|d| d.id == dir.id) {
+                    d.rating = Some(rating);
+                }
+                self.status_message = Some(format!(
+                    "Rated {} {} stars (from {} rated files)",
+                    dir_name, rating, rated_files
+                ));
+            }
+        }
+        Ok(())
     }
 
     // ==================== Background Operation Execution ====================
 
     /// Run a background operation on current directory and all subdirectories
     pub fn run_operation(&mut self, operation: OperationType) {
-        // If an operation is already running, queue this one
-        if self.background_progress.is_some() {
-            self.operation_queue.push_back(operation);
+        // The same kind of operation is already running: queue this one
+        if self.tasks.is_running(operation) {
+            self.tasks.queue.push_back(operation);
             self.status_message = Some(format!(
                 "Queued {} ({} in queue)",
                 operation.label(),
-                self.operation_queue.len()
+                self.tasks.queue.len()
             ));
             return;
         }
@@ -132,6 +555,10 @@ impl AppState {
             self.run_dir_preview_operation(operation);
             return;
         }
+        if operation == OperationType::Sync {
+            self.run_sync_operation();
+            return;
+        }
 
         // Collect files that need processing (skips already-processed ones)
         let file_data = self.collect_files_for_operation(operation);
@@ -141,58 +568,63 @@ impl AppState {
         }
 
         // Set up progress tracking
-        let completed = Arc::new(AtomicUsize::new(0));
-        let done = Arc::new(AtomicBool::new(false));
-        let cancelled = Arc::new(AtomicBool::new(false));
-
-        self.background_progress = Some(BackgroundProgress {
-            operation,
-            total: file_data.len(),
-            completed: Arc::clone(&completed),
-            done: Arc::clone(&done),
-            cancelled: Arc::clone(&cancelled),
-            start_time: Instant::now(),
-        });
+        let directory = self.get_selected_directory().map(|d| d.path.clone()).unwrap_or_default();
+        let progress = BackgroundProgress::new(operation, &directory, file_data.len());
+        let completed = Arc::clone(&progress.completed);
+        let done = Arc::clone(&progress.done);
+        let cancelled = Arc::clone(&progress.cancelled);
+        self.tasks.running.push(progress);
 
         let db_path = self.library_path.join(".picman.db");
+        let hash_algorithm = Config::load(&self.library_path)
+            .map(|c| c.hashing.algorithm)
+            .unwrap_or_default();
 
         // Spawn background thread for parallel processing
         std::thread::spawn(move || {
             use rayon::prelude::*;
 
-            match operation {
-                OperationType::Thumbnails => {
-                    use crate::thumbnails::{generate_image_thumbnail, generate_video_thumbnail, is_image_file, is_video_file};
+            // Sized by --jobs (default: one thread per core)
+            crate::workers::pool(0).install(|| {
+                match operation {
+                    OperationType::Thumbnails => {
+                        use crate::thumbnails::{
+                            generate_document_thumbnail, generate_image_thumbnail, generate_video_thumbnail,
+                            is_document_file, is_image_file, is_video_file,
+                        };
 
-                    file_data.par_iter().for_each(|(_, path)| {
-                        if cancelled.load(Ordering::Relaxed) {
-                            return;
-                        }
-                        if is_image_file(path) {
-                            generate_image_thumbnail(path);
-                        } else if is_video_file(path) {
-                            generate_video_thumbnail(path);
-                        }
-                        completed.fetch_add(1, Ordering::Relaxed);
-                    });
-                }
-                OperationType::Orientation => {
-                    parallel_compute_serial_write(
-                        &file_data, &cancelled, &completed, &db_path,
-                        |path| detect_orientation(path),
-                        |db, file_id, tag| { let _ = db.add_file_tag(file_id, tag); },
-                    );
-                }
-                OperationType::Hash => {
-                    use crate::hash::compute_file_hash;
-                    parallel_compute_serial_write(
-                        &file_data, &cancelled, &completed, &db_path,
-                        |path| compute_file_hash(path).ok(),
-                        |db, file_id, hash| { let _ = db.set_file_hash(file_id, &hash); },
-                    );
+                        file_data.par_iter().for_each(|(_, path)| {
+                            if cancelled.load(Ordering::Relaxed) {
+                                return;
+                            }
+                            if is_image_file(path) {
+                                generate_image_thumbnail(path);
+                            } else if is_video_file(path) {
+                                generate_video_thumbnail(path);
+                            } else if is_document_file(path) {
+                                generate_document_thumbnail(path);
+                            }
+                            completed.fetch_add(1, Ordering::Relaxed);
+                        });
+                    }
+                    OperationType::Orientation => {
+                        parallel_compute_serial_write(
+                            &file_data, &cancelled, &completed, &db_path,
+                            |path| detect_orientation(path),
+                            |db, file_id, tag| { let _ = db.add_file_tag(file_id, tag); },
+                        );
+                    }
+                    OperationType::Hash => {
+                        use crate::hash::compute_file_hash_with;
+                        parallel_compute_serial_write(
+                            &file_data, &cancelled, &completed, &db_path,
+                            |path| compute_file_hash_with(path, hash_algorithm, |_| {}).ok(),
+                            |db, file_id, hash| { let _ = db.set_file_hash_with(file_id, &hash, hash_algorithm); },
+                        );
+                    }
+                    OperationType::DirPreview | OperationType::DirPreviewRecursive | OperationType::Sync => {}
                 }
-                OperationType::DirPreview | OperationType::DirPreviewRecursive => {}
-            }
+            });
 
             done.store(true, Ordering::Relaxed);
         });
@@ -204,7 +636,7 @@ impl AppState {
     /// and filters based on what the operation needs (e.g., skipping files
     /// that already have thumbnails, hashes, or orientation tags).
     fn collect_files_for_operation(&self, operation: OperationType) -> Vec<(i64, PathBuf)> {
-        use crate::thumbnails::{has_thumbnail, is_image_file, is_video_file};
+        use crate::thumbnails::{has_thumbnail, is_image_file, is_thumbnailable_file};
 
         let selected_dir = match self.get_selected_directory() {
             Some(d) => d.clone(),
@@ -226,15 +658,11 @@ impl AppState {
                 .unwrap_or_default();
 
             for file in files {
-                let path = if dir_path.is_empty() {
-                    self.library_path.join(&file.filename)
-                } else {
-                    self.library_path.join(&dir_path).join(&file.filename)
-                };
+                let path = LibraryPath::file(&dir_path, &file.filename).absolute(&self.library_path);
 
                 let include = match operation {
                     OperationType::Thumbnails => {
-                        (is_image_file(&path) || is_video_file(&path)) && !has_thumbnail(&path)
+                        is_thumbnailable_file(&path) && !has_thumbnail(&path)
                     }
                     OperationType::Orientation => {
                         if !is_image_file(&path) {
@@ -246,7 +674,7 @@ impl AppState {
                         }
                     }
                     OperationType::Hash => file.hash.is_none(),
-                    OperationType::DirPreview | OperationType::DirPreviewRecursive => false,
+                    OperationType::DirPreview | OperationType::DirPreviewRecursive | OperationType::Sync => false,
                 };
 
                 if include {
@@ -274,7 +702,7 @@ impl AppState {
         // Collect directories to process
         let dir_data: Vec<Directory> = if operation == OperationType::DirPreview {
             // Single directory only
-            vec![selected_dir]
+            vec![selected_dir.clone()]
         } else {
             // Recursive: selected + all descendants
             let mut dir_ids = vec![selected_dir.id];
@@ -294,7 +722,15 @@ impl AppState {
 
         // For single directory, run synchronously (fast enough)
         if operation == OperationType::DirPreview {
+            let start = Instant::now();
             generate_dir_preview(self, &dir_data[0]);
+            self.record_finished(&FinishedTask {
+                operation,
+                directory: selected_dir.path.clone(),
+                completed: 1,
+                cancelled: false,
+                elapsed: start.elapsed(),
+            });
             // Clear cache to reload
             self.dir_preview_cache.borrow_mut().clear();
             self.status_message = Some("Dir preview generated".to_string());
@@ -302,22 +738,16 @@ impl AppState {
         }
 
         // For recursive, run in background with progress
-        let completed = Arc::new(AtomicUsize::new(0));
-        let done = Arc::new(AtomicBool::new(false));
-        let cancelled = Arc::new(AtomicBool::new(false));
-
-        self.background_progress = Some(BackgroundProgress {
-            operation,
-            total,
-            completed: Arc::clone(&completed),
-            done: Arc::clone(&done),
-            cancelled: Arc::clone(&cancelled),
-            start_time: Instant::now(),
-        });
+        let progress = BackgroundProgress::new(operation, &selected_dir.path, total);
+        let completed = Arc::clone(&progress.completed);
+        let done = Arc::clone(&progress.done);
+        let cancelled = Arc::clone(&progress.cancelled);
+        self.tasks.running.push(progress);
 
         let db_path = self.library_path.join(".picman.db");
         let library_path = self.library_path.clone();
         let all_directories = self.tree.directories.clone();
+        let cover = self.preview_cover;
 
         std::thread::spawn(move || {
             use rayon::prelude::*;
@@ -333,8 +763,9 @@ impl AppState {
 
             let temp_state = TempPreviewState {
                 library_path,
-                db,
-                directories: all_directories,
+                db: &db,
+                directories: &all_directories,
+                cover,
             };
 
             // Step 1: Collect all image paths (sequential - needs DB)
@@ -347,73 +778,153 @@ impl AppState {
                 .collect();
 
             // Step 2: Generate previews in parallel (no DB needed)
-            preview_data.par_iter().for_each(|(dir_id, images)| {
-                if cancelled.load(Ordering::Relaxed) {
-                    return;
-                }
-                generate_dir_preview_from_paths(*dir_id, images);
-                completed.fetch_add(1, Ordering::Relaxed);
+            crate::workers::pool(0).install(|| {
+                preview_data.par_iter().for_each(|(dir_id, images)| {
+                    if cancelled.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    generate_dir_preview_from_paths(*dir_id, images);
+                    completed.fetch_add(1, Ordering::Relaxed);
+                });
             });
 
             done.store(true, Ordering::Relaxed);
         });
     }
 
+    /// Sync the whole library with the disk in the background, e.g. after
+    /// copying new files in. Progress counts directories listed against
+    /// those already known; the tree and file list reload when it ends.
+    fn run_sync_operation(&mut self) {
+        let progress = BackgroundProgress::new(OperationType::Sync, "", self.tree.directories.len());
+        let completed = Arc::clone(&progress.completed);
+        let done = Arc::clone(&progress.done);
+        let cancelled = Arc::clone(&progress.cancelled);
+        self.tasks.running.push(progress);
+
+        let result = Arc::clone(&self.tasks.sync_result);
+        let library_path = self.library_path.clone();
+
+        std::thread::spawn(move || {
+            let scan = Arc::new(ScanProgress::default());
+            let sync = {
+                let scan = scan.clone();
+                std::thread::spawn(move || run_sync_incremental_with_progress(&library_path, scan))
+            };
+            while !sync.is_finished() {
+                if cancelled.load(Ordering::Relaxed) {
+                    scan.cancel();
+                }
+                completed.store(scan.dirs(), Ordering::Relaxed);
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            completed.store(scan.dirs(), Ordering::Relaxed);
+            let outcome = sync.join().unwrap_or_else(|_| Err(anyhow!("Sync panicked")));
+            *result.lock().unwrap_or_else(|e| e.into_inner()) = Some(outcome);
+            done.store(true, Ordering::Relaxed);
+        });
+    }
+
+    /// Pick up a finished background sync: reload what it changed, in place,
+    /// and describe it for the status bar. A sync cancelled before it
+    /// changed anything is left to the task's own summary.
+    fn finish_sync(&mut self, cancelled: bool) -> Option<String> {
+        let outcome = self.tasks.sync_result.lock().unwrap_or_else(|e| e.into_inner()).take()?;
+        let stats = match outcome {
+            Ok(stats) => stats,
+            Err(_) if cancelled => return None,
+            Err(e) => return Some(format!("Sync failed: {:#}", e)),
+        };
+        let dir_id = self.get_selected_directory().map(|d| d.id);
+        if let Err(e) = dir_id.map_or(Ok(()), |id| self.reload_after_sync(id)) {
+            return Some(format!("Synced, but reloading failed: {:#}", e));
+        }
+        Some(format!(
+            "Synced: +{} -{} directories, +{} -{} ~{} files",
+            stats.directories_added,
+            stats.directories_removed,
+            stats.files_added,
+            stats.files_removed,
+            stats.files_modified
+        ))
+    }
+
     // ==================== Background Progress Management ====================
 
-    /// Cancel any running background operation
+    /// Cancel all background operations, e.g. before quitting
     pub fn cancel_background_operation(&mut self) {
-        if let Some(ref progress) = self.background_progress {
-            progress.cancelled.store(true, Ordering::Relaxed);
-        }
+        self.tasks.cancel_all();
     }
 
     /// Check if a background operation is running
     pub fn has_background_operation(&self) -> bool {
-        self.background_progress.is_some()
+        self.tasks.is_busy()
     }
 
-    /// Check and update background operation progress
+    /// Collect finished background operations, report them, and start
+    /// queued operations that can now run
     pub fn update_background_progress(&mut self) {
-        if let Some(ref progress) = self.background_progress {
-            if progress.done.load(Ordering::Relaxed) {
-                let completed = progress.completed.load(Ordering::Relaxed);
-                let was_cancelled = progress.cancelled.load(Ordering::Relaxed);
-                let queue_len = self.operation_queue.len();
-
-                if was_cancelled {
-                    // Clear queue on cancel
-                    self.operation_queue.clear();
-                    self.status_message = Some(format!(
-                        "Cancelled - {} {}",
-                        completed,
-                        progress.operation.done_label()
-                    ));
-                } else if queue_len > 0 {
-                    self.status_message = Some(format!(
-                        "{} {} ({} more queued)",
-                        completed,
-                        progress.operation.done_label(),
-                        queue_len
-                    ));
-                } else {
-                    self.status_message = Some(format!(
-                        "{} {}",
-                        completed,
-                        progress.operation.done_label()
-                    ));
-                }
-                self.background_progress = None;
-                // Clear preview caches to reload
-                self.preview_cache.borrow_mut().clear();
-                *self.missing_preview_cache.borrow_mut() = None;
-
-                // Start next queued operation if any
-                if let Some(next_op) = self.operation_queue.pop_front() {
-                    self.run_operation(next_op);
-                }
+        self.tasks.sample(Instant::now());
+        let finished = self.tasks.reap();
+        if finished.is_empty() {
+            return;
+        }
+
+        for task in &finished {
+            self.record_finished(task);
+            if let Some(kind) = task.operation.job_kind().filter(|_| !task.cancelled) {
+                self.notifier.job_finished(kind, task.elapsed, &task.summary());
             }
         }
+
+        let mut notices: Vec<String> = finished
+            .iter()
+            .map(|task| match task.operation {
+                OperationType::Sync => self.finish_sync(task.cancelled).unwrap_or_else(|| task.summary()),
+                _ => task.summary(),
+            })
+            .collect();
+        if !self.tasks.queue.is_empty() {
+            notices.push(format!("{} more queued", self.tasks.queue.len()));
+        }
+        self.status_message = Some(notices.join(" | "));
+
+        // Clear preview caches to reload
+        self.preview_cache.borrow_mut().clear();
+        *self.missing_preview_cache.borrow_mut() = None;
+        self.invalidate_dashboard();
+
+        for operation in self.tasks.take_startable() {
+            self.run_operation(operation);
+        }
+    }
+
+    /// Add a finished operation to the history shown in the operations menu
+    fn record_finished(&self, task: &FinishedTask) {
+        if let Err(e) = self.db.record_operation(&task.to_record()) {
+            tracing::warn!(error = %e, "failed to record operation history");
+        }
+    }
+
+    /// Run an operation from the history again, on the selected directory
+    pub fn rerun_operation(&mut self, record: &OperationRecord) {
+        self.close_operations_menu();
+        match OperationType::from_name(&record.operation) {
+            Some(operation) => self.run_operation(operation),
+            None => self.status_message = Some(format!("Unknown operation '{}'", record.operation)),
+        }
+    }
+
+    /// Cancel the operation selected in the task list
+    pub fn cancel_selected_task(&mut self) {
+        let was_running = self.tasks.selected < self.tasks.running.len();
+        if let Some(operation) = self.tasks.cancel_selected() {
+            self.status_message = Some(if was_running {
+                format!("Cancelling {}", operation.label().to_lowercase())
+            } else {
+                format!("Removed {} from the queue", operation.label().to_lowercase())
+            });
+        }
     }
 }
 
@@ -453,3 +964,134 @@ fn parallel_compute_serial_write<T: Send>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finish(tasks: &TaskManager, operation: OperationType, completed: usize) {
+        let task = tasks.running.iter().find(|t| t.operation == operation).unwrap();
+        task.completed.store(completed, Ordering::Relaxed);
+        task.done.store(true, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn test_tasks_queue_only_behind_same_kind() {
+        let mut tasks = TaskManager::default();
+        tasks.running.push(BackgroundProgress::new(OperationType::Thumbnails, "", 10));
+        tasks.running.push(BackgroundProgress::new(OperationType::Hash, "", 10));
+        tasks.queue.extend([OperationType::Hash, OperationType::Hash, OperationType::Orientation]);
+
+        // Orientation isn't running, so it can start; both hashes wait
+        assert_eq!(tasks.take_startable(), vec![OperationType::Orientation]);
+        assert_eq!(tasks.queue.len(), 2);
+
+        finish(&tasks, OperationType::Hash, 7);
+        let finished = tasks.reap();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].summary(), "7 files hashed");
+        assert!(tasks.is_running(OperationType::Thumbnails));
+        // Only one of the queued hashes starts now
+        assert_eq!(tasks.take_startable(), vec![OperationType::Hash]);
+        assert_eq!(tasks.queue, [OperationType::Hash]);
+    }
+
+    #[test]
+    fn test_tasks_cancel_selected() {
+        let mut tasks = TaskManager::default();
+        tasks.running.push(BackgroundProgress::new(OperationType::Thumbnails, "", 10));
+        tasks.queue.extend([OperationType::Thumbnails, OperationType::Hash]);
+
+        // A queued row is dropped from the queue
+        tasks.move_down();
+        tasks.move_down();
+        tasks.move_down();
+        assert_eq!(tasks.selected, 2);
+        assert_eq!(tasks.cancel_selected(), Some(OperationType::Hash));
+        assert_eq!(tasks.selected, 1);
+
+        // A running row is flagged; it's reaped once its thread stops
+        tasks.move_up();
+        assert_eq!(tasks.cancel_selected(), Some(OperationType::Thumbnails));
+        assert!(tasks.running[0].cancelled.load(Ordering::Relaxed));
+        finish(&tasks, OperationType::Thumbnails, 3);
+        tasks.reap();
+        assert_eq!(tasks.finished[0].summary(), "Cancelled - 3 thumbnails generated");
+        assert!(!tasks.is_busy());
+        assert_eq!(tasks.queue, [OperationType::Thumbnails]);
+    }
+
+    #[test]
+    fn test_throughput_follows_recent_pace() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut throughput = Throughput::default();
+        throughput.record(start, 0);
+        assert_eq!(throughput.rate(), None);
+
+        // 10 items/s for a minute, then 2 items/s
+        for sec in 1..=60 {
+            throughput.record(at(sec), sec as usize * 10);
+        }
+        assert_eq!(throughput.rate(), Some(10.0));
+        assert_eq!(throughput.eta(100), Some(Duration::from_secs(10)));
+        for sec in 61..=120 {
+            throughput.record(at(sec), 600 + (sec as usize - 60) * 2);
+        }
+        assert_eq!(throughput.rate(), Some(2.0));
+        assert!(throughput.samples.len() <= 32);
+
+        // Samples closer together than the interval are skipped
+        let before = throughput.samples.len();
+        throughput.record(at(120) + Duration::from_millis(100), 721);
+        assert_eq!(throughput.samples.len(), before);
+
+        // Stalled: no rate to estimate from
+        for sec in 121..=160 {
+            throughput.record(at(sec), 720);
+        }
+        assert_eq!(throughput.eta(10), None);
+    }
+
+    #[test]
+    fn test_background_sync_reloads_in_place() {
+        use crate::tui::state::test_helpers::create_test_app_state;
+
+        let (mut state, temp) = create_test_app_state();
+        let photos = state.db.get_directory_by_path("photos").unwrap().unwrap();
+        state.select_directory_in_tree(photos.id);
+        state.file_list.selected_index = 1;
+        std::fs::write(temp.path().join("photos/img3.jpg"), "data").unwrap();
+
+        state.run_operation(OperationType::Sync);
+        let started = Instant::now();
+        while state.has_background_operation() {
+            assert!(started.elapsed() < Duration::from_secs(30), "sync didn't finish");
+            std::thread::sleep(Duration::from_millis(20));
+            state.update_background_progress();
+        }
+
+        let status = state.status_message.clone().unwrap();
+        assert!(status.starts_with("Synced: +0 -0 directories, +1"), "{}", status);
+        assert_eq!(state.get_selected_directory().unwrap().id, photos.id);
+        let names: Vec<&str> = state.file_list.files.iter().map(|f| f.file.filename.as_str()).collect();
+        assert!(names.contains(&"img3.jpg"));
+        assert_eq!(state.file_list.selected_file().unwrap().file.filename, "img2.jpg");
+    }
+
+    #[test]
+    fn test_tasks_keep_recent_finished() {
+        let mut tasks = TaskManager::default();
+        for completed in 0..FINISHED_TASKS_KEPT + 2 {
+            tasks.running.push(BackgroundProgress::new(OperationType::Hash, "", 10));
+            finish(&tasks, OperationType::Hash, completed);
+            tasks.reap();
+        }
+        assert_eq!(tasks.finished.len(), FINISHED_TASKS_KEPT);
+        assert_eq!(tasks.finished.back().unwrap().completed, FINISHED_TASKS_KEPT + 1);
+
+        assert_eq!(tasks.status_height(), 1);
+        tasks.toggle_expanded();
+        assert_eq!(tasks.status_height(), (2 + FINISHED_TASKS_KEPT) as u16);
+    }
+}
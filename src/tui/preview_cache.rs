@@ -5,95 +5,200 @@ use std::sync::Arc;
 use image::DynamicImage;
 use ratatui_image::protocol::StatefulProtocol;
 
+/// How much of the image the file preview shows. Every tier is decoded and
+/// cached on its own, so zooming in loads more of the original's detail
+/// instead of enlarging the fitted preview.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PreviewZoom {
+    /// Whole image fitted to the pane (`W`)
+    #[default]
+    Fit,
+    /// Center of the image at 2× the fitted scale
+    Zoom2,
+    /// Center of the image at 4× the fitted scale
+    Zoom4,
+    /// Center of the original, one image pixel per screen pixel (`=`)
+    Actual,
+}
+
+impl PreviewZoom {
+    /// Next tier in (`Z`); 1:1 is as far as it goes
+    pub fn zoom_in(self) -> Self {
+        match self {
+            Self::Fit => Self::Zoom2,
+            Self::Zoom2 => Self::Zoom4,
+            Self::Zoom4 | Self::Actual => Self::Actual,
+        }
+    }
+
+    /// Shown in the preview title; None when fitted
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            Self::Fit => None,
+            Self::Zoom2 => Some("2×"),
+            Self::Zoom4 => Some("4×"),
+            Self::Actual => Some("1:1"),
+        }
+    }
+}
+
+/// Cache key: the same file at another zoom tier is a separate entry
+type CacheKey = (PathBuf, PreviewZoom);
+
+/// Size assumed for an entry before any are cached: a fitted 1920×1440
+/// RGB preview with its protocol
+const TYPICAL_ENTRY_BYTES: usize = 1920 * 1440 * 3 * 3;
+
+/// Approximate memory held by a cache entry. A protocol keeps its own copy
+/// of the image plus the encoded output, counted as twice the image again.
+fn entry_bytes(image: &DynamicImage, has_protocol: bool) -> usize {
+    let image_bytes = image.as_bytes().len();
+    if has_protocol {
+        image_bytes * 3
+    } else {
+        image_bytes
+    }
+}
+
 /// Cached image preview state — stores the decoded image and optionally a
 /// render-ready protocol. When the protocol is present, rendering is instant
 /// (no round-trip to the worker thread).
 pub struct PreviewCache {
     pub path: PathBuf,
+    pub zoom: PreviewZoom,
     pub image: Arc<DynamicImage>,
     pub protocol: Option<Box<dyn StatefulProtocol>>,
 }
 
 impl PreviewCache {
-    pub fn new(path: PathBuf, image: Arc<DynamicImage>, protocol: Option<Box<dyn StatefulProtocol>>) -> Self {
-        Self { path, image, protocol }
+    pub fn new(
+        path: PathBuf,
+        zoom: PreviewZoom,
+        image: Arc<DynamicImage>,
+        protocol: Option<Box<dyn StatefulProtocol>>,
+    ) -> Self {
+        Self { path, zoom, image, protocol }
     }
 }
 
-/// LRU cache for decoded image previews
-/// Holds up to `max_size` entries, evicting least-recently-used when full.
-/// Designed for ~200 decoded images (~1GB memory).
+/// LRU cache for decoded image previews, keyed by (path, zoom tier)
+/// Holds entries up to a memory budget of `max_bytes`, evicting
+/// least-recently-used when over it, so a folder of 45MP images and one of
+/// phone snaps both fit what the machine can spare. The newest entry is
+/// always kept, even if it alone is over budget.
 pub struct LruPreviewCache {
-    /// Map of path -> cached preview
-    entries: HashMap<PathBuf, PreviewCache>,
+    /// Map of (path, zoom) -> cached preview
+    entries: HashMap<CacheKey, PreviewCache>,
     /// Access order: most recently used at back, least at front
-    access_order: VecDeque<PathBuf>,
-    /// Maximum number of entries
-    max_size: usize,
+    access_order: VecDeque<CacheKey>,
+    /// Approximate bytes held by all entries
+    bytes: usize,
+    /// Memory budget in bytes
+    max_bytes: usize,
 }
 
 impl LruPreviewCache {
-    pub fn new(max_size: usize) -> Self {
+    pub fn new(max_bytes: usize) -> Self {
         Self {
             entries: HashMap::new(),
             access_order: VecDeque::new(),
-            max_size,
+            bytes: 0,
+            max_bytes,
         }
     }
 
-    /// Insert a preview into the cache, evicting oldest if over capacity
-    pub fn insert(&mut self, path: PathBuf, image: Arc<DynamicImage>, protocol: Option<Box<dyn StatefulProtocol>>) {
-        // If already in cache, remove from access order (will re-add at end)
-        if self.entries.contains_key(&path) {
-            self.access_order.retain(|p| p != &path);
+    /// Evict least-recently-used entries until `incoming` more bytes fit
+    fn make_room(&mut self, incoming: usize) {
+        while self.bytes + incoming > self.max_bytes {
+            let Some(oldest) = self.access_order.pop_front() else {
+                break;
+            };
+            self.remove_entry(&oldest);
+        }
+    }
+
+    fn remove_entry(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.bytes -= entry_bytes(&entry.image, entry.protocol.is_some());
         }
+    }
 
-        // Evict if at capacity
-        while self.entries.len() >= self.max_size && !self.access_order.is_empty() {
-            if let Some(oldest) = self.access_order.pop_front() {
-                self.entries.remove(&oldest);
-            }
+    /// Insert a preview into the cache, evicting oldest if over capacity
+    pub fn insert(
+        &mut self,
+        path: PathBuf,
+        zoom: PreviewZoom,
+        image: Arc<DynamicImage>,
+        protocol: Option<Box<dyn StatefulProtocol>>,
+    ) {
+        let key = (path, zoom);
+        // If already in cache, drop the old entry (will re-add at end)
+        if self.entries.contains_key(&key) {
+            self.access_order.retain(|k| k != &key);
+            self.remove_entry(&key);
         }
 
+        // Evict until the new entry fits the budget
+        let size = entry_bytes(&image, protocol.is_some());
+        self.make_room(size);
+
         // Insert new entry
-        self.entries.insert(path.clone(), PreviewCache::new(path.clone(), image, protocol));
-        self.access_order.push_back(path);
+        self.entries.insert(key.clone(), PreviewCache::new(key.0.clone(), zoom, image, protocol));
+        self.access_order.push_back(key);
+        self.bytes += size;
     }
 
     /// Store a protocol on an existing cache entry (used when the worker
     /// creates the protocol after the image was already cached).
-    pub fn set_protocol(&mut self, path: &Path, protocol: Box<dyn StatefulProtocol>) {
-        if let Some(entry) = self.entries.get_mut(path) {
-            entry.protocol = Some(protocol);
-        }
+    pub fn set_protocol(&mut self, path: &Path, zoom: PreviewZoom, protocol: Box<dyn StatefulProtocol>) {
+        let key = (path.to_path_buf(), zoom);
+        let Some(entry) = self.entries.get_mut(&key) else {
+            return;
+        };
+        let before = entry_bytes(&entry.image, entry.protocol.is_some());
+        entry.protocol = Some(protocol);
+        let after = entry_bytes(&entry.image, true);
+        self.bytes = self.bytes - before + after;
+
+        // Evict others to stay in budget, keeping this entry where it was
+        let Some(position) = self.access_order.iter().position(|k| k == &key) else {
+            return;
+        };
+        self.access_order.remove(position);
+        let others = self.access_order.len();
+        self.make_room(0);
+        let evicted = others - self.access_order.len();
+        self.access_order.insert(position.saturating_sub(evicted), key);
     }
 
     /// Get a cached preview without updating access order (read-only peek)
-    pub fn get(&self, path: &Path) -> Option<&PreviewCache> {
-        self.entries.get(path)
+    pub fn get(&self, path: &Path, zoom: PreviewZoom) -> Option<&PreviewCache> {
+        self.entries.get(&(path.to_path_buf(), zoom))
     }
 
     /// Get a cached preview, updating access order
-    pub fn get_mut(&mut self, path: &Path) -> Option<&mut PreviewCache> {
-        if self.entries.contains_key(path) {
+    pub fn get_mut(&mut self, path: &Path, zoom: PreviewZoom) -> Option<&mut PreviewCache> {
+        let key = (path.to_path_buf(), zoom);
+        if self.entries.contains_key(&key) {
             // Update access order: move to back (most recent)
-            self.access_order.retain(|p| p.as_path() != path);
-            self.access_order.push_back(path.to_path_buf());
-            self.entries.get_mut(path)
+            self.access_order.retain(|k| k != &key);
+            self.access_order.push_back(key.clone());
+            self.entries.get_mut(&key)
         } else {
             None
         }
     }
 
-    /// Check if cache contains a path
-    pub fn contains(&self, path: &Path) -> bool {
-        self.entries.contains_key(path)
+    /// Check if cache contains a path at this zoom tier
+    pub fn contains(&self, path: &Path, zoom: PreviewZoom) -> bool {
+        self.entries.contains_key(&(path.to_path_buf(), zoom))
     }
 
     /// Clear all entries
     pub fn clear(&mut self) {
         self.entries.clear();
         self.access_order.clear();
+        self.bytes = 0;
     }
 
     /// Get number of entries
@@ -106,21 +211,33 @@ impl LruPreviewCache {
         self.entries.is_empty()
     }
 
-    /// Maximum number of entries this cache can hold
-    pub fn max_size(&self) -> usize {
-        self.max_size
+    /// Approximate bytes held by the cached previews
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Roughly how many entries fit the budget, judging by the average size
+    /// of those cached so far. Bounds preloading, whose images aren't
+    /// decoded yet.
+    pub fn estimated_capacity(&self) -> usize {
+        let average = if self.entries.is_empty() {
+            TYPICAL_ENTRY_BYTES
+        } else {
+            self.bytes / self.entries.len()
+        };
+        (self.max_bytes / average.max(1)).max(1)
     }
 
     /// Check if a cache entry has a ready-to-render protocol
-    pub fn has_protocol(&self, path: &Path) -> bool {
+    pub fn has_protocol(&self, path: &Path, zoom: PreviewZoom) -> bool {
         self.entries
-            .get(path)
+            .get(&(path.to_path_buf(), zoom))
             .is_some_and(|e| e.protocol.is_some())
     }
 
     /// Get the most recently accessed entry (for showing stale preview during rapid scroll)
     pub fn get_last_accessed_mut(&mut self) -> Option<&mut PreviewCache> {
-        self.access_order.back().and_then(|path| self.entries.get_mut(path))
+        self.access_order.back().and_then(|key| self.entries.get_mut(key))
     }
 }
 
@@ -134,64 +251,139 @@ mod tests {
 
     #[test]
     fn test_lru_preview_cache_basic() {
-        let cache = LruPreviewCache::new(3);
+        let cache = LruPreviewCache::new(9);
         assert_eq!(cache.len(), 0);
         assert!(cache.is_empty());
-        assert!(!cache.contains(&PathBuf::from("test.jpg")));
+        assert!(!cache.contains(&PathBuf::from("test.jpg"), PreviewZoom::Fit));
     }
 
     #[test]
     fn test_lru_eviction() {
-        let mut cache = LruPreviewCache::new(3);
+        // Room for three 1×1 RGB images (3 bytes each)
+        let mut cache = LruPreviewCache::new(9);
 
-        cache.insert(PathBuf::from("a.jpg"), mock_image(), None);
-        cache.insert(PathBuf::from("b.jpg"), mock_image(), None);
-        cache.insert(PathBuf::from("c.jpg"), mock_image(), None);
+        cache.insert(PathBuf::from("a.jpg"), PreviewZoom::Fit, mock_image(), None);
+        cache.insert(PathBuf::from("b.jpg"), PreviewZoom::Fit, mock_image(), None);
+        cache.insert(PathBuf::from("c.jpg"), PreviewZoom::Fit, mock_image(), None);
         assert_eq!(cache.len(), 3);
 
         // Inserting a 4th should evict the oldest ("a.jpg")
-        cache.insert(PathBuf::from("d.jpg"), mock_image(), None);
+        cache.insert(PathBuf::from("d.jpg"), PreviewZoom::Fit, mock_image(), None);
         assert_eq!(cache.len(), 3);
-        assert!(!cache.contains(&PathBuf::from("a.jpg")));
-        assert!(cache.contains(&PathBuf::from("b.jpg")));
-        assert!(cache.contains(&PathBuf::from("c.jpg")));
-        assert!(cache.contains(&PathBuf::from("d.jpg")));
+        assert!(!cache.contains(&PathBuf::from("a.jpg"), PreviewZoom::Fit));
+        assert!(cache.contains(&PathBuf::from("b.jpg"), PreviewZoom::Fit));
+        assert!(cache.contains(&PathBuf::from("c.jpg"), PreviewZoom::Fit));
+        assert!(cache.contains(&PathBuf::from("d.jpg"), PreviewZoom::Fit));
     }
 
     #[test]
     fn test_lru_access_order() {
-        let mut cache = LruPreviewCache::new(3);
+        let mut cache = LruPreviewCache::new(9);
 
-        cache.insert(PathBuf::from("a.jpg"), mock_image(), None);
-        cache.insert(PathBuf::from("b.jpg"), mock_image(), None);
-        cache.insert(PathBuf::from("c.jpg"), mock_image(), None);
+        cache.insert(PathBuf::from("a.jpg"), PreviewZoom::Fit, mock_image(), None);
+        cache.insert(PathBuf::from("b.jpg"), PreviewZoom::Fit, mock_image(), None);
+        cache.insert(PathBuf::from("c.jpg"), PreviewZoom::Fit, mock_image(), None);
 
         // Access "a.jpg" — makes it most-recently-used
-        cache.get_mut(Path::new("a.jpg"));
+        cache.get_mut(Path::new("a.jpg"), PreviewZoom::Fit);
 
         // Insert a 4th — should evict "b.jpg" (the least recently used)
-        cache.insert(PathBuf::from("d.jpg"), mock_image(), None);
+        cache.insert(PathBuf::from("d.jpg"), PreviewZoom::Fit, mock_image(), None);
         assert_eq!(cache.len(), 3);
-        assert!(cache.contains(&PathBuf::from("a.jpg")));
-        assert!(!cache.contains(&PathBuf::from("b.jpg")));
-        assert!(cache.contains(&PathBuf::from("c.jpg")));
-        assert!(cache.contains(&PathBuf::from("d.jpg")));
+        assert!(cache.contains(&PathBuf::from("a.jpg"), PreviewZoom::Fit));
+        assert!(!cache.contains(&PathBuf::from("b.jpg"), PreviewZoom::Fit));
+        assert!(cache.contains(&PathBuf::from("c.jpg"), PreviewZoom::Fit));
+        assert!(cache.contains(&PathBuf::from("d.jpg"), PreviewZoom::Fit));
     }
 
     #[test]
     fn test_get_last_accessed() {
-        let mut cache = LruPreviewCache::new(3);
+        let mut cache = LruPreviewCache::new(9);
 
-        cache.insert(PathBuf::from("a.jpg"), mock_image(), None);
-        cache.insert(PathBuf::from("b.jpg"), mock_image(), None);
+        cache.insert(PathBuf::from("a.jpg"), PreviewZoom::Fit, mock_image(), None);
+        cache.insert(PathBuf::from("b.jpg"), PreviewZoom::Fit, mock_image(), None);
 
         // Last inserted is "b.jpg"
         let last = cache.get_last_accessed_mut().unwrap();
         assert_eq!(last.path, PathBuf::from("b.jpg"));
 
         // Access "a.jpg" — now it becomes the last accessed
-        cache.get_mut(Path::new("a.jpg"));
+        cache.get_mut(Path::new("a.jpg"), PreviewZoom::Fit);
         let last = cache.get_last_accessed_mut().unwrap();
         assert_eq!(last.path, PathBuf::from("a.jpg"));
     }
+
+    #[test]
+    fn test_zoom_tiers_are_cached_separately() {
+        let mut cache = LruPreviewCache::new(100);
+        let path = PathBuf::from("a.jpg");
+
+        cache.insert(path.clone(), PreviewZoom::Fit, mock_image(), None);
+        assert!(cache.contains(&path, PreviewZoom::Fit));
+        assert!(!cache.contains(&path, PreviewZoom::Zoom2));
+
+        cache.insert(path.clone(), PreviewZoom::Zoom2, Arc::new(DynamicImage::new_rgb8(2, 2)), None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&path, PreviewZoom::Fit).unwrap().image.width(), 1);
+        assert_eq!(cache.get(&path, PreviewZoom::Zoom2).unwrap().image.width(), 2);
+        assert_eq!(cache.get_last_accessed_mut().unwrap().zoom, PreviewZoom::Zoom2);
+    }
+
+    #[test]
+    fn test_evicts_by_memory_not_count() {
+        // 100×100 RGB is 30 000 bytes; the budget fits three of them
+        let mut cache = LruPreviewCache::new(90_000);
+        let large = || Arc::new(DynamicImage::new_rgb8(100, 100));
+
+        for name in ["a.jpg", "b.jpg", "c.jpg"] {
+            cache.insert(PathBuf::from(name), PreviewZoom::Fit, large(), None);
+        }
+        assert_eq!((cache.len(), cache.bytes()), (3, 90_000));
+        assert_eq!(cache.estimated_capacity(), 3);
+
+        // One twice the size pushes out the two oldest
+        cache.insert(PathBuf::from("big.jpg"), PreviewZoom::Fit, Arc::new(DynamicImage::new_rgb8(200, 100)), None);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains(Path::new("c.jpg"), PreviewZoom::Fit));
+        assert_eq!(cache.bytes(), 90_000);
+
+        // Small ones fit many more
+        cache.clear();
+        for i in 0..100 {
+            cache.insert(PathBuf::from(format!("{}.jpg", i)), PreviewZoom::Fit, mock_image(), None);
+        }
+        assert_eq!(cache.len(), 100);
+        assert_eq!(cache.bytes(), 300);
+    }
+
+    #[test]
+    fn test_replacing_entry_keeps_byte_count() {
+        let mut cache = LruPreviewCache::new(1_000_000);
+        let path = PathBuf::from("a.jpg");
+        cache.insert(path.clone(), PreviewZoom::Fit, Arc::new(DynamicImage::new_rgb8(10, 10)), None);
+        cache.insert(path.clone(), PreviewZoom::Fit, Arc::new(DynamicImage::new_rgb8(20, 10)), None);
+        assert_eq!((cache.len(), cache.bytes()), (1, 600));
+    }
+
+    #[test]
+    fn test_oversized_entry_is_still_kept() {
+        let mut cache = LruPreviewCache::new(100);
+        cache.insert(PathBuf::from("a.jpg"), PreviewZoom::Fit, mock_image(), None);
+        cache.insert(PathBuf::from("huge.jpg"), PreviewZoom::Fit, Arc::new(DynamicImage::new_rgb8(100, 100)), None);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains(Path::new("huge.jpg"), PreviewZoom::Fit));
+        assert_eq!(cache.estimated_capacity(), 1);
+    }
+
+    #[test]
+    fn test_zoom_in_stops_at_actual_size() {
+        let mut zoom = PreviewZoom::Fit;
+        let mut labels = Vec::new();
+        for _ in 0..4 {
+            zoom = zoom.zoom_in();
+            labels.push(zoom.label().unwrap());
+        }
+        assert_eq!(labels, vec!["2×", "4×", "1:1", "1:1"]);
+        assert_eq!(PreviewZoom::Fit.label(), None);
+    }
 }
@@ -2,25 +2,32 @@ use image::DynamicImage;
 use ratatui::layout::Rect;
 use ratatui_image::protocol::StatefulProtocol;
 use ratatui_image::{FilterType, Resize};
+use std::collections::VecDeque;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicI64, AtomicU32, AtomicU64, Ordering};
 use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 use std::sync::Arc;
 use std::thread;
+use tracing::{debug, info, warn};
 
 use crate::thumbnails::{
-    apply_exif_orientation, generate_video_thumbnail, get_preview_path_for_file, is_image_file,
-    is_video_file,
+    apply_exif_orientation, generate_document_thumbnail, generate_image_thumbnail,
+    generate_video_thumbnail, get_preview_path_for_file, is_directly_displayable,
+    is_document_file, is_image_file, is_video_file, open_image,
 };
-use super::widgets::create_protocol;
+use crate::vfs::LibraryFs;
+use super::preview_cache::PreviewZoom;
+use super::widgets::{create_protocol, picker_font_size};
 
 /// Request to load an image in the background.
 ///
 /// `preview_path: None` → worker resolves thumbnail path via `get_preview_path_for_file`
 /// (moves the stat() calls off the UI thread).
 /// `preview_path: Some(...)` → worker loads directly from that path (used for dir previews).
+/// `zoom` beyond `Fit` makes the worker decode the original, not the thumbnail.
 pub struct LoadRequest {
     pub path: PathBuf,
+    pub zoom: PreviewZoom,
     pub preview_path: Option<PathBuf>,
     pub is_dir_preview: bool,
     pub dir_id: i64,
@@ -31,11 +38,25 @@ pub struct LoadRequest {
 /// (or neither, on decode failure).
 pub struct LoadResult {
     pub path: PathBuf,
+    pub zoom: PreviewZoom,
     pub image: Option<Arc<DynamicImage>>,
     pub protocol: Option<Box<dyn StatefulProtocol>>,
     pub is_dir_preview: bool,
 }
 
+/// What the worker did with its requests, logged when the loader is dropped
+#[derive(Debug, Default)]
+struct LoaderStats {
+    /// Images decoded and sent back
+    decoded: AtomicU64,
+    /// Requests dropped as stale before any work was done
+    dropped_queued: AtomicU64,
+    /// Requests dropped as stale after their path was resolved or decoded
+    dropped_late: AtomicU64,
+    /// Requests answered by the work done for another request of the same preview
+    coalesced: AtomicU64,
+}
+
 fn pack_area(width: u16, height: u16) -> u32 {
     (width as u32) << 16 | height as u32
 }
@@ -48,7 +69,10 @@ fn unpack_area(packed: u32) -> (u16, u16) {
 ///
 /// Key features:
 /// - Never blocks the UI thread on `image::open()` or `resize_encode()`
-/// - Skips stale loads from previous directories or navigation positions
+/// - Skips stale loads from previous directories or navigation positions,
+///   checking again after each expensive step (resolve, decode)
+/// - Coalesces requests for the same preview: an image decoded for a request
+///   that went stale answers a newer request for it instead of being thrown away
 /// - Processes requests sequentially in a dedicated thread
 /// - Always creates a render protocol after decoding (stored in cache for instant render)
 pub struct PreviewLoader {
@@ -62,18 +86,16 @@ pub struct PreviewLoader {
     /// Bumped when the selection moves to an uncached file, causing the worker
     /// to skip loads queued for the previous position.
     load_generation: Arc<AtomicU64>,
-    /// Paths that are currently being loaded (to avoid duplicate requests)
-    pending: std::collections::HashSet<PathBuf>,
-}
-
-impl Default for PreviewLoader {
-    fn default() -> Self {
-        Self::new()
-    }
+    /// (path, zoom) pairs currently being loaded (to avoid duplicate requests)
+    pending: std::collections::HashSet<(PathBuf, PreviewZoom)>,
+    stats: Arc<LoaderStats>,
 }
 
 impl PreviewLoader {
-    pub fn new() -> Self {
+    /// Create a loader reading originals through `fs`, which fetches them
+    /// on demand for remote libraries. `resolution` caps the width of
+    /// decoded previews (`[tui] preview_resolution`).
+    pub fn new(fs: Arc<dyn LibraryFs>, resolution: u32) -> Self {
         let (load_tx, load_rx) = channel::<LoadRequest>();
         let (result_tx, result_rx) = channel::<LoadResult>();
         let current_dir_id = Arc::new(AtomicI64::new(-1));
@@ -82,10 +104,23 @@ impl PreviewLoader {
         let dir_id_clone = Arc::clone(&current_dir_id);
         let area_clone = Arc::clone(&preview_area);
         let gen_clone = Arc::clone(&load_generation);
+        let stats = Arc::new(LoaderStats::default());
+        let stats_clone = Arc::clone(&stats);
 
         // Spawn worker thread
         thread::spawn(move || {
-            worker_loop(load_rx, result_tx, dir_id_clone, area_clone, gen_clone);
+            Worker {
+                load_rx,
+                result_tx,
+                fs,
+                bounds: decode_bounds(resolution),
+                current_dir_id: dir_id_clone,
+                preview_area: area_clone,
+                load_generation: gen_clone,
+                stats: stats_clone,
+                backlog: VecDeque::new(),
+            }
+            .run();
         });
 
         Self {
@@ -95,6 +130,7 @@ impl PreviewLoader {
             preview_area,
             load_generation,
             pending: std::collections::HashSet::new(),
+            stats,
         }
     }
 
@@ -111,6 +147,7 @@ impl PreviewLoader {
             preview_area: Arc::new(AtomicU32::new(0)),
             load_generation: Arc::new(AtomicU64::new(0)),
             pending: std::collections::HashSet::new(),
+            stats: Arc::default(),
         }
     }
 
@@ -140,8 +177,8 @@ impl PreviewLoader {
     }
 
     /// Queue a file preview. Worker resolves thumbnail path — zero stat() on UI thread.
-    pub fn queue_file_load(&mut self, path: PathBuf, dir_id: i64) -> bool {
-        self.queue_load_inner(path, None, false, dir_id)
+    pub fn queue_file_load(&mut self, path: PathBuf, zoom: PreviewZoom, dir_id: i64) -> bool {
+        self.queue_load_inner(path, zoom, None, false, dir_id)
     }
 
     /// Queue a directory composite preview with an explicit disk path.
@@ -151,23 +188,26 @@ impl PreviewLoader {
         preview_path: PathBuf,
         dir_id: i64,
     ) -> bool {
-        self.queue_load_inner(cache_key, Some(preview_path), true, dir_id)
+        self.queue_load_inner(cache_key, PreviewZoom::Fit, Some(preview_path), true, dir_id)
     }
 
     fn queue_load_inner(
         &mut self,
         path: PathBuf,
+        zoom: PreviewZoom,
         preview_path: Option<PathBuf>,
         is_dir_preview: bool,
         dir_id: i64,
     ) -> bool {
-        if self.pending.contains(&path) {
+        let key = (path, zoom);
+        if self.pending.contains(&key) {
             return false;
         }
 
         let generation = self.load_generation.load(Ordering::Relaxed);
         let request = LoadRequest {
-            path: path.clone(),
+            path: key.0.clone(),
+            zoom,
             preview_path,
             is_dir_preview,
             dir_id,
@@ -175,16 +215,16 @@ impl PreviewLoader {
         };
 
         if self.load_tx.send(request).is_ok() {
-            self.pending.insert(path);
+            self.pending.insert(key);
             true
         } else {
             false
         }
     }
 
-    /// Check if a path is currently being loaded
-    pub fn is_pending(&self, path: &Path) -> bool {
-        self.pending.contains(path)
+    /// Check if a path is currently being loaded at this zoom tier
+    pub fn is_pending(&self, path: &Path, zoom: PreviewZoom) -> bool {
+        self.pending.contains(&(path.to_path_buf(), zoom))
     }
 
     /// Poll for completed image loads. Returns all available results.
@@ -193,7 +233,7 @@ impl PreviewLoader {
         loop {
             match self.result_rx.try_recv() {
                 Ok(result) => {
-                    self.pending.remove(&result.path);
+                    self.pending.remove(&(result.path.clone(), result.zoom));
                     results.push(result);
                 }
                 Err(TryRecvError::Empty) => break,
@@ -204,119 +244,255 @@ impl PreviewLoader {
     }
 }
 
-/// Worker thread that processes load requests.
-/// Blocks on recv() — no busy-polling needed since there's only one channel.
-fn worker_loop(
+impl Drop for PreviewLoader {
+    fn drop(&mut self) {
+        let stats = &self.stats;
+        info!(
+            decoded = stats.decoded.load(Ordering::Relaxed),
+            dropped_queued = stats.dropped_queued.load(Ordering::Relaxed),
+            dropped_late = stats.dropped_late.load(Ordering::Relaxed),
+            coalesced = stats.coalesced.load(Ordering::Relaxed),
+            "preview loader stats"
+        );
+    }
+}
+
+/// The worker thread's side of a [`PreviewLoader`]
+struct Worker {
     load_rx: Receiver<LoadRequest>,
     result_tx: Sender<LoadResult>,
+    fs: Arc<dyn LibraryFs>,
+    bounds: (u32, u32),
     current_dir_id: Arc<AtomicI64>,
     preview_area: Arc<AtomicU32>,
     load_generation: Arc<AtomicU64>,
-) {
-    while let Ok(req) = load_rx.recv() {
-        handle_load(req, &result_tx, &current_dir_id, &load_generation, &preview_area);
+    stats: Arc<LoaderStats>,
+    /// Requests taken off the channel while looking for duplicates, not yet handled
+    backlog: VecDeque<LoadRequest>,
+}
+
+/// How far a request got before it went stale
+#[derive(Debug, Clone, Copy)]
+enum Stage {
+    Queued,
+    Resolved,
+    Decoded,
+}
+
+impl Worker {
+    /// Process requests until the loader is dropped.
+    /// Blocks on recv() — no busy-polling needed since there's only one channel.
+    fn run(mut self) {
+        loop {
+            let request = match self.backlog.pop_front() {
+                Some(request) => request,
+                None => match self.load_rx.recv() {
+                    Ok(request) => request,
+                    Err(_) => break,
+                },
+            };
+            self.handle_load(request);
+        }
+    }
+
+    /// The UI's current (directory, generation)
+    fn current(&self) -> (i64, u64) {
+        (
+            self.current_dir_id.load(Ordering::Relaxed),
+            self.load_generation.load(Ordering::Relaxed),
+        )
+    }
+
+    /// The request to finish the work done so far for: `request` itself while
+    /// it's current, else a current request for the same preview queued since
+    /// (the UI re-queues a file after moving away and back). None cancels the job.
+    fn still_wanted(&mut self, request: LoadRequest, stage: Stage) -> Option<LoadRequest> {
+        let current = self.current();
+        if !is_stale(&request, current) {
+            return Some(request);
+        }
+
+        self.backlog.extend(self.load_rx.try_iter());
+        let newer = self
+            .backlog
+            .iter()
+            .position(|queued| same_preview(queued, &request) && !is_stale(queued, current));
+        if let Some(newer) = newer.and_then(|i| self.backlog.remove(i)) {
+            self.stats.coalesced.fetch_add(1, Ordering::Relaxed);
+            return Some(newer);
+        }
+
+        let dropped = match stage {
+            Stage::Queued => &self.stats.dropped_queued,
+            Stage::Resolved | Stage::Decoded => &self.stats.dropped_late,
+        };
+        dropped.fetch_add(1, Ordering::Relaxed);
+        debug!(path = %request.path.display(), ?stage, "dropped stale preview load");
+        None
+    }
+
+    /// Send a result, answering any other current requests for the same
+    /// preview with it too
+    fn send(&mut self, request: &LoadRequest, result: LoadResult) {
+        let current = self.current();
+        self.backlog.extend(self.load_rx.try_iter());
+        let before = self.backlog.len();
+        self.backlog
+            .retain(|queued| !same_preview(queued, request) || is_stale(queued, current));
+        let duplicates = before - self.backlog.len();
+        self.stats.coalesced.fetch_add(duplicates as u64, Ordering::Relaxed);
+        let _ = self.result_tx.send(result);
+    }
+
+    /// Handle a load-from-disk request: decode image and create a render protocol.
+    /// Stale requests (wrong directory or generation) are dropped before each
+    /// expensive step.
+    fn handle_load(&mut self, request: LoadRequest) {
+        // Skip stale requests — pending was already cleared by the caller
+        let Some(request) = self.still_wanted(request, Stage::Queued) else {
+            return;
+        };
+
+        // Resolve the preview path: explicit for dir previews, worker-resolved for files
+        let (load_path, is_thumbnail) = match &request.preview_path {
+            Some(p) => (p.clone(), true), // dir composites have no EXIF, treat as thumbnail
+            None => match resolve_preview_path(&request.path, request.zoom, self.fs.as_ref()) {
+                Some(result) => result,
+                None => {
+                    // No preview available (e.g., image file with no original and no thumbnail)
+                    let result = LoadResult {
+                        path: request.path.clone(),
+                        zoom: request.zoom,
+                        image: None,
+                        protocol: None,
+                        is_dir_preview: request.is_dir_preview,
+                    };
+                    self.send(&request, result);
+                    return;
+                }
+            },
+        };
+
+        // Resolving may have fetched an original or generated a thumbnail
+        let Some(request) = self.still_wanted(request, Stage::Resolved) else {
+            return;
+        };
+
+        // Load and decode the image
+        let image = load_image(&load_path, is_thumbnail);
+
+        // Re-check after decode — directory or generation may have changed
+        let Some(request) = self.still_wanted(request, Stage::Decoded) else {
+            return;
+        };
+
+        let pane_pixels = pane_pixels(&self.preview_area);
+        let arc_image = image.map(|img| Arc::new(frame_for_zoom(img, request.zoom, self.bounds, pane_pixels)));
+
+        // Always create a protocol so the cache entry is render-ready
+        let protocol = arc_image
+            .as_ref()
+            .and_then(|img| make_pre_encoded_protocol(img, &self.preview_area));
+
+        if arc_image.is_some() {
+            self.stats.decoded.fetch_add(1, Ordering::Relaxed);
+        }
+        let result = LoadResult {
+            path: request.path.clone(),
+            zoom: request.zoom,
+            image: arc_image,
+            protocol,
+            is_dir_preview: request.is_dir_preview,
+        };
+        self.send(&request, result);
     }
 }
 
-/// Maximum cached image dimensions (pixels). Images larger than this are
-/// downscaled before caching to keep protocol creation fast.
+/// Whether the UI has moved on since `request` was queued, given its current
+/// (directory, generation). Dir previews skip the generation check
+/// (infrequent, always worth processing).
+fn is_stale(request: &LoadRequest, (dir_id, generation): (i64, u64)) -> bool {
+    request.dir_id != dir_id || (!request.is_dir_preview && request.generation != generation)
+}
+
+/// Whether two requests ask for the same preview
+fn same_preview(a: &LoadRequest, b: &LoadRequest) -> bool {
+    a.path == b.path && a.zoom == b.zoom && a.is_dir_preview == b.is_dir_preview
+}
+
+/// Default width cap for decoded previews (pixels). Images larger than the
+/// cap are downscaled before caching to keep protocol creation fast.
 /// 1920×1440 covers any realistic terminal preview area with headroom.
-const MAX_CACHE_WIDTH: u32 = 1920;
-const MAX_CACHE_HEIGHT: u32 = 1440;
+pub const DEFAULT_PREVIEW_RESOLUTION: u32 = 1920;
+
+/// Maximum cached image dimensions for a width cap, at 4:3
+fn decode_bounds(resolution: u32) -> (u32, u32) {
+    (resolution, resolution * 3 / 4)
+}
 
 /// Downscale an image if it exceeds the maximum cache dimensions.
 /// Thumbnails and small images pass through unchanged.
-fn downscale_for_cache(image: DynamicImage) -> DynamicImage {
-    if image.width() > MAX_CACHE_WIDTH || image.height() > MAX_CACHE_HEIGHT {
-        image.resize(
-            MAX_CACHE_WIDTH,
-            MAX_CACHE_HEIGHT,
-            image::imageops::FilterType::Triangle,
-        )
+fn downscale_for_cache(image: DynamicImage, (max_width, max_height): (u32, u32)) -> DynamicImage {
+    if image.width() > max_width || image.height() > max_height {
+        image.resize(max_width, max_height, image::imageops::FilterType::Triangle)
     } else {
         image
     }
 }
 
-/// Resolve preview path for a file: try cached thumbnail, then video thumbnail generation.
-/// Returns (load_path, is_thumbnail). Called on the worker thread — all stat() happens here.
-fn resolve_preview_path(path: &Path) -> Option<(PathBuf, bool)> {
+/// Cut out the part of the image a zoom tier shows and bring it within
+/// `bounds`. Zoomed tiers keep the center; 1:1 keeps as many pixels as the
+/// pane has (`pane_pixels`, falling back to `bounds` when unknown), which the
+/// protocol then draws without scaling.
+fn frame_for_zoom(
+    image: DynamicImage,
+    zoom: PreviewZoom,
+    bounds: (u32, u32),
+    pane_pixels: Option<(u32, u32)>,
+) -> DynamicImage {
+    let center_crop = |image: DynamicImage, width: u32, height: u32| {
+        let (width, height) = (width.clamp(1, image.width()), height.clamp(1, image.height()));
+        image.crop_imm((image.width() - width) / 2, (image.height() - height) / 2, width, height)
+    };
+    match zoom {
+        PreviewZoom::Fit => downscale_for_cache(image, bounds),
+        PreviewZoom::Zoom2 | PreviewZoom::Zoom4 => {
+            let factor = if zoom == PreviewZoom::Zoom2 { 2 } else { 4 };
+            let (width, height) = (image.width() / factor, image.height() / factor);
+            downscale_for_cache(center_crop(image, width, height), bounds)
+        }
+        PreviewZoom::Actual => {
+            let (width, height) = pane_pixels.unwrap_or(bounds);
+            center_crop(image, width, height)
+        }
+    }
+}
+
+/// Resolve preview path for a file: try cached thumbnail, then thumbnail generation for
+/// videos, documents, and images that need conversion (TIFF, SVG, JPEG 2000).
+/// Zoomed previews of displayable images skip the thumbnail for the original's detail.
+/// Returns (load_path, is_thumbnail). Called on the worker thread — all stat() happens here,
+/// as does fetching the original of a remote library.
+fn resolve_preview_path(path: &Path, zoom: PreviewZoom, fs: &dyn LibraryFs) -> Option<(PathBuf, bool)> {
+    if let Err(e) = fs.ensure_local(path) {
+        warn!(path = %path.display(), error = %e, "failed to fetch original");
+    }
+    if zoom != PreviewZoom::Fit && is_directly_displayable(path) && path.exists() {
+        return Some((path.to_path_buf(), false));
+    }
     get_preview_path_for_file(path).or_else(|| {
         if is_video_file(path) {
             generate_video_thumbnail(path).map(|thumb| (thumb, true))
+        } else if is_document_file(path) {
+            generate_document_thumbnail(path).map(|thumb| (thumb, true))
+        } else if is_image_file(path) {
+            generate_image_thumbnail(path).map(|thumb| (thumb, true))
         } else {
             None
         }
     })
 }
 
-/// Handle a load-from-disk request: decode image and create a render protocol.
-/// Stale requests (wrong directory or generation) are silently dropped.
-/// Dir-preview requests skip generation staleness (infrequent, always worth processing).
-fn handle_load(
-    request: LoadRequest,
-    result_tx: &Sender<LoadResult>,
-    current_dir_id: &Arc<AtomicI64>,
-    load_generation: &Arc<AtomicU64>,
-    preview_area: &Arc<AtomicU32>,
-) {
-    // Skip stale requests — pending was already cleared by the caller
-    if request.dir_id != current_dir_id.load(Ordering::Relaxed) {
-        return;
-    }
-    // Dir previews skip generation check (infrequent, always worth processing)
-    if !request.is_dir_preview
-        && request.generation != load_generation.load(Ordering::Relaxed)
-    {
-        return;
-    }
-
-    // Resolve the preview path: explicit for dir previews, worker-resolved for files
-    let (load_path, is_thumbnail) = match request.preview_path {
-        Some(p) => (p, true), // dir composites have no EXIF, treat as thumbnail
-        None => match resolve_preview_path(&request.path) {
-            Some(result) => result,
-            None => {
-                // No preview available (e.g., image file with no original and no thumbnail)
-                let _ = result_tx.send(LoadResult {
-                    path: request.path,
-                    image: None,
-                    protocol: None,
-                    is_dir_preview: request.is_dir_preview,
-                });
-                return;
-            }
-        },
-    };
-
-    // Load and decode the image
-    let image = load_image(&load_path, is_thumbnail);
-
-    // Re-check after decode — directory or generation may have changed
-    if request.dir_id != current_dir_id.load(Ordering::Relaxed) {
-        return;
-    }
-    if !request.is_dir_preview
-        && request.generation != load_generation.load(Ordering::Relaxed)
-    {
-        return;
-    }
-
-    let arc_image = image.map(|img| Arc::new(downscale_for_cache(img)));
-
-    // Always create a protocol so the cache entry is render-ready
-    let protocol = arc_image
-        .as_ref()
-        .and_then(|img| make_pre_encoded_protocol(img, preview_area));
-
-    let _ = result_tx.send(LoadResult {
-        path: request.path,
-        image: arc_image,
-        protocol,
-        is_dir_preview: request.is_dir_preview,
-    });
-}
-
 /// Create a protocol from an image and pre-encode at the current preview area size
 fn make_pre_encoded_protocol(
     image: &DynamicImage,
@@ -339,9 +515,21 @@ fn make_pre_encoded_protocol(
     Some(proto)
 }
 
+/// Size of the preview pane in screen pixels, if both the pane and the
+/// terminal's cell size are known
+fn pane_pixels(preview_area: &Arc<AtomicU32>) -> Option<(u32, u32)> {
+    let packed = preview_area.load(Ordering::Relaxed);
+    if packed == 0 {
+        return None;
+    }
+    let (cell_width, cell_height) = picker_font_size()?;
+    let (w, h) = unpack_area(packed);
+    Some((w as u32 * cell_width as u32, h as u32 * cell_height as u32))
+}
+
 /// Load an image, applying EXIF orientation if needed
 fn load_image(preview_path: &Path, is_thumbnail: bool) -> Option<DynamicImage> {
-    let img = image::open(preview_path).ok()?;
+    let img = open_image(preview_path).ok()?;
 
     // Apply EXIF orientation only for original files (thumbnails have it baked in)
     Some(if is_thumbnail || !is_image_file(preview_path) {
@@ -369,15 +557,107 @@ mod tests {
         (loader, load_rx, result_tx)
     }
 
+    /// A worker on injected channels, in directory 1 at generation 0,
+    /// returning the request sender and result receiver
+    fn test_worker() -> (Worker, Sender<LoadRequest>, Receiver<LoadResult>) {
+        let (load_tx, load_rx) = channel::<LoadRequest>();
+        let (result_tx, result_rx) = channel::<LoadResult>();
+        let worker = Worker {
+            load_rx,
+            result_tx,
+            fs: Arc::new(crate::vfs::LocalFs::new(PathBuf::from("/photos"))),
+            bounds: decode_bounds(DEFAULT_PREVIEW_RESOLUTION),
+            current_dir_id: Arc::new(AtomicI64::new(1)),
+            preview_area: Arc::new(AtomicU32::new(0)),
+            load_generation: Arc::new(AtomicU64::new(0)),
+            stats: Arc::default(),
+            backlog: VecDeque::new(),
+        };
+        (worker, load_tx, result_rx)
+    }
+
+    fn request(path: &str, generation: u64, is_dir_preview: bool) -> LoadRequest {
+        LoadRequest {
+            path: PathBuf::from(path),
+            zoom: PreviewZoom::Fit,
+            preview_path: None,
+            is_dir_preview,
+            dir_id: 1,
+            generation,
+        }
+    }
+
+    #[test]
+    fn test_worker_cancels_stale_requests() {
+        let (mut worker, _load_tx, _result_rx) = test_worker();
+
+        assert!(worker.still_wanted(request("/photos/a.jpg", 0, false), Stage::Queued).is_some());
+
+        worker.load_generation.store(1, Ordering::Relaxed);
+        assert!(worker.still_wanted(request("/photos/a.jpg", 0, false), Stage::Queued).is_none());
+        assert!(worker.still_wanted(request("/photos/b.jpg", 0, false), Stage::Decoded).is_none());
+        // Dir previews outlive generation bumps, but not directory changes
+        assert!(worker.still_wanted(request("\0dir/7", 0, true), Stage::Queued).is_some());
+        worker.current_dir_id.store(2, Ordering::Relaxed);
+        assert!(worker.still_wanted(request("\0dir/7", 0, true), Stage::Queued).is_none());
+
+        assert_eq!(worker.stats.dropped_queued.load(Ordering::Relaxed), 2);
+        assert_eq!(worker.stats.dropped_late.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_worker_hands_stale_decode_to_requeued_request() {
+        let (mut worker, load_tx, _result_rx) = test_worker();
+
+        // The user moved away while a.jpg decoded, then came back to it
+        worker.load_generation.store(2, Ordering::Relaxed);
+        load_tx.send(request("/photos/b.jpg", 1, false)).unwrap();
+        load_tx.send(request("/photos/a.jpg", 2, false)).unwrap();
+        load_tx.send(request("/photos/c.jpg", 2, false)).unwrap();
+
+        let adopted = worker.still_wanted(request("/photos/a.jpg", 0, false), Stage::Decoded).unwrap();
+        assert_eq!((adopted.path, adopted.generation), (PathBuf::from("/photos/a.jpg"), 2));
+        assert_eq!(worker.stats.coalesced.load(Ordering::Relaxed), 1);
+        assert_eq!(worker.stats.dropped_late.load(Ordering::Relaxed), 0);
+
+        // The rest stay queued in order
+        let queued: Vec<_> = worker.backlog.iter().map(|r| r.path.clone()).collect();
+        assert_eq!(queued, vec![PathBuf::from("/photos/b.jpg"), PathBuf::from("/photos/c.jpg")]);
+    }
+
+    #[test]
+    fn test_worker_answers_duplicate_requests_once() {
+        let (mut worker, load_tx, result_rx) = test_worker();
+
+        // Dir previews can be queued twice across a generation bump
+        let dir = request("\0dir/7", 0, true);
+        load_tx.send(request("\0dir/7", 1, true)).unwrap();
+        load_tx.send(request("/photos/a.jpg", 0, false)).unwrap();
+
+        let result = LoadResult {
+            path: dir.path.clone(),
+            zoom: dir.zoom,
+            image: None,
+            protocol: None,
+            is_dir_preview: true,
+        };
+        worker.send(&dir, result);
+
+        assert_eq!(result_rx.try_iter().count(), 1);
+        assert_eq!(worker.backlog.len(), 1);
+        assert_eq!(worker.backlog[0].path, PathBuf::from("/photos/a.jpg"));
+        assert_eq!(worker.stats.coalesced.load(Ordering::Relaxed), 1);
+    }
+
     #[test]
     fn test_queue_file_load_tracks_pending() {
         let (mut loader, _load_rx, _result_tx) = test_loader();
 
         let path = PathBuf::from("/photos/img001.jpg");
-        let queued = loader.queue_file_load(path.clone(), 1);
+        let queued = loader.queue_file_load(path.clone(), PreviewZoom::Fit, 1);
 
         assert!(queued);
-        assert!(loader.is_pending(&path));
+        assert!(loader.is_pending(&path, PreviewZoom::Fit));
     }
 
     #[test]
@@ -385,8 +665,8 @@ mod tests {
         let (mut loader, _load_rx, _result_tx) = test_loader();
 
         let path = PathBuf::from("/photos/img001.jpg");
-        assert!(loader.queue_file_load(path.clone(), 1));
-        assert!(!loader.queue_file_load(path.clone(), 1));
+        assert!(loader.queue_file_load(path.clone(), PreviewZoom::Fit, 1));
+        assert!(!loader.queue_file_load(path.clone(), PreviewZoom::Fit, 1));
     }
 
     #[test]
@@ -394,7 +674,7 @@ mod tests {
         let (mut loader, load_rx, _result_tx) = test_loader();
 
         let path = PathBuf::from("/photos/img001.jpg");
-        loader.queue_file_load(path.clone(), 1);
+        loader.queue_file_load(path.clone(), PreviewZoom::Fit, 1);
 
         let req = load_rx.try_recv().unwrap();
         assert_eq!(req.path, path);
@@ -421,11 +701,11 @@ mod tests {
         let (mut loader, _load_rx, _result_tx) = test_loader();
 
         let path = PathBuf::from("/photos/img001.jpg");
-        loader.queue_file_load(path.clone(), 1);
-        assert!(loader.is_pending(&path));
+        loader.queue_file_load(path.clone(), PreviewZoom::Fit, 1);
+        assert!(loader.is_pending(&path, PreviewZoom::Fit));
 
         loader.set_current_dir(2);
-        assert!(!loader.is_pending(&path));
+        assert!(!loader.is_pending(&path, PreviewZoom::Fit));
     }
 
     #[test]
@@ -433,13 +713,14 @@ mod tests {
         let (mut loader, _load_rx, result_tx) = test_loader();
 
         let path = PathBuf::from("/photos/img001.jpg");
-        loader.queue_file_load(path.clone(), 1);
-        assert!(loader.is_pending(&path));
+        loader.queue_file_load(path.clone(), PreviewZoom::Fit, 1);
+        assert!(loader.is_pending(&path, PreviewZoom::Fit));
 
         // Simulate the worker sending back a result
         result_tx
             .send(LoadResult {
                 path: path.clone(),
+                zoom: PreviewZoom::Fit,
                 image: None,
                 protocol: None,
                 is_dir_preview: false,
@@ -448,7 +729,7 @@ mod tests {
 
         let results = loader.poll_results();
         assert_eq!(results.len(), 1);
-        assert!(!loader.is_pending(&path));
+        assert!(!loader.is_pending(&path, PreviewZoom::Fit));
     }
 
     #[test]
@@ -461,6 +742,7 @@ mod tests {
         result_tx
             .send(LoadResult {
                 path: path.clone(),
+                zoom: PreviewZoom::Fit,
                 image: None,
                 protocol: None,
                 is_dir_preview: true,
@@ -496,7 +778,7 @@ mod tests {
         let (mut loader, load_rx, _result_tx) = test_loader();
 
         let path = PathBuf::from("/photos/img001.jpg");
-        loader.queue_file_load(path.clone(), 1);
+        loader.queue_file_load(path.clone(), PreviewZoom::Fit, 1);
 
         // Should appear on load channel with current generation
         let req = load_rx.try_recv().unwrap();
@@ -507,9 +789,10 @@ mod tests {
     #[test]
     fn test_downscale_for_cache_shrinks_large_images() {
         let large = DynamicImage::new_rgb8(4000, 3000);
-        let result = downscale_for_cache(large);
-        assert!(result.width() <= MAX_CACHE_WIDTH);
-        assert!(result.height() <= MAX_CACHE_HEIGHT);
+        let bounds = decode_bounds(DEFAULT_PREVIEW_RESOLUTION);
+        let result = downscale_for_cache(large, bounds);
+        assert!(result.width() <= bounds.0);
+        assert!(result.height() <= bounds.1);
         // Aspect ratio preserved: 4000×3000 → 1920×1440
         assert_eq!(result.width(), 1920);
         assert_eq!(result.height(), 1440);
@@ -518,7 +801,7 @@ mod tests {
     #[test]
     fn test_downscale_for_cache_preserves_small_images() {
         let small = DynamicImage::new_rgb8(200, 150);
-        let result = downscale_for_cache(small);
+        let result = downscale_for_cache(small, decode_bounds(DEFAULT_PREVIEW_RESOLUTION));
         assert_eq!(result.width(), 200);
         assert_eq!(result.height(), 150);
     }
@@ -528,13 +811,13 @@ mod tests {
         let (mut loader, _load_rx, _result_tx) = test_loader();
 
         let path = PathBuf::from("/photos/img001.jpg");
-        loader.queue_file_load(path.clone(), 1);
-        assert!(loader.is_pending(&path));
+        loader.queue_file_load(path.clone(), PreviewZoom::Fit, 1);
+        assert!(loader.is_pending(&path, PreviewZoom::Fit));
         assert_eq!(loader.pending_count(), 1);
 
         loader.bump_load_generation();
 
-        assert!(!loader.is_pending(&path));
+        assert!(!loader.is_pending(&path, PreviewZoom::Fit));
         assert_eq!(loader.pending_count(), 0);
     }
 
@@ -543,13 +826,13 @@ mod tests {
         let (mut loader, load_rx, _result_tx) = test_loader();
 
         let path = PathBuf::from("/photos/img001.jpg");
-        assert!(loader.queue_file_load(path.clone(), 1));
+        assert!(loader.queue_file_load(path.clone(), PreviewZoom::Fit, 1));
         // Duplicate blocked
-        assert!(!loader.queue_file_load(path.clone(), 1));
+        assert!(!loader.queue_file_load(path.clone(), PreviewZoom::Fit, 1));
 
         // Bump clears pending — same file can be re-queued with new generation
         loader.bump_load_generation();
-        assert!(loader.queue_file_load(path.clone(), 1));
+        assert!(loader.queue_file_load(path.clone(), PreviewZoom::Fit, 1));
 
         // First request has generation 0, second has generation 1
         let req1 = load_rx.try_recv().unwrap();
@@ -557,4 +840,46 @@ mod tests {
         let req2 = load_rx.try_recv().unwrap();
         assert_eq!(req2.generation, 1);
     }
+
+    #[test]
+    fn test_zoom_tiers_queue_separately() {
+        let (mut loader, load_rx, _result_tx) = test_loader();
+
+        let path = PathBuf::from("/photos/img001.jpg");
+        assert!(loader.queue_file_load(path.clone(), PreviewZoom::Fit, 1));
+        assert!(loader.queue_file_load(path.clone(), PreviewZoom::Zoom2, 1));
+        assert!(!loader.queue_file_load(path.clone(), PreviewZoom::Zoom2, 1));
+        assert!(!loader.is_pending(&path, PreviewZoom::Actual));
+
+        assert_eq!(load_rx.try_recv().unwrap().zoom, PreviewZoom::Fit);
+        assert_eq!(load_rx.try_recv().unwrap().zoom, PreviewZoom::Zoom2);
+    }
+
+    #[test]
+    fn test_frame_for_zoom_crops_center_at_full_detail() {
+        let bounds = decode_bounds(800);
+        let original = || DynamicImage::new_rgb8(4000, 3000);
+
+        let fit = frame_for_zoom(original(), PreviewZoom::Fit, bounds, None);
+        assert_eq!((fit.width(), fit.height()), (800, 600));
+
+        // 2× shows the middle 2000×1500, still within the decode bounds
+        let zoom2 = frame_for_zoom(original(), PreviewZoom::Zoom2, bounds, None);
+        assert_eq!((zoom2.width(), zoom2.height()), (800, 600));
+
+        // 4× of a 1000×750 crop isn't scaled at all
+        let zoom4 = frame_for_zoom(original(), PreviewZoom::Zoom4, decode_bounds(1920), None);
+        assert_eq!((zoom4.width(), zoom4.height()), (1000, 750));
+
+        // 1:1 takes exactly the pane's pixels, never more than the image has
+        let actual = frame_for_zoom(original(), PreviewZoom::Actual, bounds, Some((1200, 700)));
+        assert_eq!((actual.width(), actual.height()), (1200, 700));
+        let small = frame_for_zoom(
+            DynamicImage::new_rgb8(300, 200),
+            PreviewZoom::Actual,
+            bounds,
+            Some((1200, 700)),
+        );
+        assert_eq!((small.width(), small.height()), (300, 200));
+    }
 }
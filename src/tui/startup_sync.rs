@@ -0,0 +1,59 @@
+//! The startup sync runs on a background thread behind a progress screen,
+//! so a slow library (e.g. on a NAS) doesn't look like a hang and the scan
+//! can be skipped.
+
+use std::io::Stdout;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::prelude::*;
+use tracing::info;
+
+use crate::cli::{run_sync_incremental_with_progress, SyncStats};
+use crate::vfs::ScanProgress;
+
+use super::widgets::render_sync_progress;
+
+/// Run an incremental sync while showing its progress. Returns `None` if
+/// the user skipped it; the sync is then cancelled, and stops without
+/// changes unless it had already started writing.
+pub fn run_startup_sync(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    library_path: &Path,
+) -> Result<Option<SyncStats>> {
+    let library = library_path.display().to_string();
+    let progress = Arc::new(ScanProgress::default());
+    let handle = {
+        let progress = progress.clone();
+        let library_path = library_path.to_path_buf();
+        thread::spawn(move || run_sync_incremental_with_progress(&library_path, progress))
+    };
+
+    let started = Instant::now();
+    loop {
+        if handle.is_finished() {
+            let stats = handle.join().map_err(|_| anyhow!("Startup sync panicked"))??;
+            return Ok(Some(stats));
+        }
+
+        terminal.draw(|frame| {
+            render_sync_progress(frame, frame.area(), &library, &progress, started.elapsed())
+        })?;
+
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else { continue };
+        if key.kind == KeyEventKind::Press && matches!(key.code, KeyCode::Esc | KeyCode::Char('s')) {
+            info!(dirs = progress.dirs(), files = progress.files(), "startup sync skipped");
+            // Not joined: a remote listing can't be interrupted, and the
+            // thread exits on its own once it sees the cancellation
+            progress.cancel();
+            return Ok(None);
+        }
+    }
+}
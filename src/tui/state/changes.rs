@@ -0,0 +1,93 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use super::AppState;
+
+/// How often to look for ratings and tags changed by another process
+/// (the CLI, the web UI, the daemon)
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+impl AppState {
+    /// Pick up ratings and tags another process changed since the last
+    /// check, updating just those rows in the tree, the file list and the
+    /// snapshot. The
+    /// check itself is one index lookup; nothing is reread when nothing
+    /// changed.
+    pub fn poll_metadata_changes(&mut self) -> Result<()> {
+        if self.metadata_checked.elapsed() < POLL_INTERVAL {
+            return Ok(());
+        }
+        self.metadata_checked = Instant::now();
+        if self.db.metadata_version()? == self.metadata_version {
+            return Ok(());
+        }
+
+        let changes = self.db.get_metadata_changes_since(self.metadata_version)?;
+        self.metadata_version = changes.version;
+        if changes.overflowed {
+            // Too much changed to say what: reload everything
+            self.reload_directories()?;
+            let selected_id = self.file_list.selected_file().map(|f| f.file.id);
+            return self.reload_files_selecting(selected_id);
+        }
+
+        for dir in self.tree.directories.iter_mut().filter(|d| changes.directories.contains(&d.id)) {
+            if let Some(current) = self.db.get_directory(dir.id)? {
+                dir.rating = current.rating;
+            }
+            self.snapshot.set_directory_tags(dir.id, self.db.get_directory_tags(dir.id)?);
+        }
+        for &file_id in &changes.files {
+            let listed = self.file_list.files.iter().any(|f| f.file.id == file_id);
+            if !listed && !self.snapshot.has_file(file_id) {
+                continue;
+            }
+            let Some((current, _)) = self.db.get_file_with_path(file_id)? else {
+                continue;
+            };
+            let tags = self.db.get_file_tags(file_id)?;
+            if let Some(entry) = self.file_list.files.iter_mut().find(|f| f.file.id == file_id) {
+                entry.file.rating = current.rating;
+                entry.tags = tags.clone();
+            }
+            self.snapshot.update_file(file_id, current.rating, tags);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::create_test_app_state;
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_changes_from_another_process_show_up() {
+        let (mut state, temp) = create_test_app_state();
+        let photos = state.db.get_directory_by_path("photos").unwrap().unwrap();
+        state.select_directory_in_tree(photos.id);
+        state.file_list.selected_index = 1;
+        let img1 = state.file_list.files[0].file.id;
+
+        // Like the CLI or the web server, through its own connection
+        let other = Database::open(&temp.path().join(".picman.db")).unwrap();
+        other.set_file_rating(img1, Some(4.0)).unwrap();
+        other.add_file_tag(img1, "keeper").unwrap();
+        other.set_directory_rating(photos.id, Some(2.0)).unwrap();
+
+        // Not looked at again within the interval
+        state.poll_metadata_changes().unwrap();
+        assert_eq!(state.file_list.files[0].file.rating, None);
+
+        state.metadata_checked -= POLL_INTERVAL;
+        state.poll_metadata_changes().unwrap();
+        assert_eq!(state.file_list.files[0].file.rating, Some(4.0));
+        assert_eq!(state.file_list.files[0].tags, vec!["keeper"]);
+        let dir = state.tree.directories.iter().find(|d| d.id == photos.id).unwrap();
+        assert_eq!(dir.rating, Some(2.0));
+        // Updated in place: the cursor stays put
+        assert_eq!(state.file_list.selected_index, 1);
+    }
+}
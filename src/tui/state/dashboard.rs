@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use anyhow::Result;
+use rayon::prelude::*;
+
+use crate::db::LibrarySummary;
+use crate::thumbnails::{has_thumbnail, is_thumbnailable_file};
+use crate::library_path::LibraryPath;
+
+use super::AppState;
+
+/// Library summary shown by the dashboard (`D`). The totals come from one
+/// aggregated query, rerun each time the dashboard opens; the thumbnail
+/// count needs a stat per file, so it's kept until something changes the
+/// library (an operation finishing, a rescan).
+pub struct DashboardState {
+    pub summary: LibrarySummary,
+    /// Thumbnailable files without a cached thumbnail; None while counting
+    pub missing_thumbnails: Option<usize>,
+    /// Result of the background thumbnail count, which stats every original
+    counting: Option<Receiver<usize>>,
+}
+
+impl DashboardState {
+    pub fn is_counting(&self) -> bool {
+        self.counting.is_some()
+    }
+}
+
+impl AppState {
+    pub fn toggle_dashboard(&mut self) -> Result<()> {
+        self.show_dashboard = !self.show_dashboard;
+        if !self.show_dashboard {
+            self.force_redraw = true;
+            return Ok(());
+        }
+        if let Some(dashboard) = self.dashboard.as_mut() {
+            dashboard.summary = self.db.library_summary()?;
+        }
+        self.poll_dashboard()
+    }
+
+    /// Drop the cached summary and thumbnail count; they're rebuilt the
+    /// next time the dashboard shows
+    pub fn invalidate_dashboard(&mut self) {
+        self.dashboard = None;
+    }
+
+    /// Build the summary if the dashboard is shown without one, and pick up
+    /// the thumbnail count once the background thread has it
+    pub fn poll_dashboard(&mut self) -> Result<()> {
+        if self.show_dashboard && self.dashboard.is_none() {
+            self.dashboard = Some(self.build_dashboard()?);
+        }
+        if let Some(dashboard) = self.dashboard.as_mut() {
+            if let Some(count) = dashboard.counting.as_ref().and_then(|rx| rx.try_recv().ok()) {
+                dashboard.missing_thumbnails = Some(count);
+                dashboard.counting = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn build_dashboard(&self) -> Result<DashboardState> {
+        let summary = self.db.library_summary()?;
+
+        let paths: Vec<PathBuf> = self
+            .db
+            .get_all_files_with_paths()?
+            .into_iter()
+            .map(|(file, dir_path)| LibraryPath::file(&dir_path, &file.filename).absolute(&self.library_path))
+            .filter(|path| is_thumbnailable_file(path))
+            .collect();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let missing = paths.par_iter().filter(|path| !has_thumbnail(path)).count();
+            let _ = tx.send(missing);
+        });
+
+        Ok(DashboardState {
+            summary,
+            missing_thumbnails: None,
+            counting: Some(rx),
+        })
+    }
+}
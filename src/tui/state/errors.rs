@@ -0,0 +1,34 @@
+use tracing::error;
+
+use super::{AppState, ErrorPopupState};
+
+impl AppState {
+    /// Report a failed action: log it and show it in the error popup,
+    /// replacing any error still on screen
+    pub fn show_error(&mut self, e: anyhow::Error) {
+        error!(error = format!("{:#}", e), "TUI action failed");
+        self.error_popup = Some(ErrorPopupState::new(&e));
+    }
+
+    pub fn close_error(&mut self) {
+        self.error_popup = None;
+        self.force_redraw = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::create_test_app_state;
+
+    #[test]
+    fn test_show_error_replaces_and_close_redraws() {
+        let (mut state, _temp) = create_test_app_state();
+        state.show_error(anyhow::anyhow!("first"));
+        state.show_error(anyhow::anyhow!("second"));
+        assert_eq!(state.error_popup.as_ref().unwrap().message, "second");
+
+        state.close_error();
+        assert!(state.error_popup.is_none());
+        assert!(state.force_redraw);
+    }
+}
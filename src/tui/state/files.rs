@@ -1,11 +1,19 @@
-use std::process::Command;
+use std::collections::HashMap;
+use std::path::Path;
 
 use anyhow::Result;
 
+use crate::cli::open_in_viewer;
+use crate::db::Activity;
+use crate::scanner::find_xmp_sidecar;
+use crate::library_path::LibraryPath;
+
 use super::{AppState, FileWithTags, RatingFilter};
 
 impl AppState {
-    /// Load files for the currently selected directory, applying current filter
+    /// Load files for the currently selected directory, applying current
+    /// filter. Directories are listed from the snapshot, which reads the
+    /// database only when the selection enters another top-level directory.
     pub(super) fn load_files_for_selected_directory(&mut self) -> Result<()> {
         self.file_list.files.clear();
         self.file_list.selected_index = 0;
@@ -13,37 +21,89 @@ impl AppState {
 
         let selected_dir = self.get_selected_directory().cloned();
         if let Some(dir) = selected_dir {
+            // Marks and expanded stacks only make sense within one directory
+            if self.current_dir_id != Some(dir.id) {
+                self.file_list.marked.clear();
+                self.file_list.expanded_stacks.clear();
+            }
+
             // Update current directory ID and notify preview loader
             self.current_dir_id = Some(dir.id);
             self.preview_loader.borrow_mut().set_current_dir(dir.id);
-            let files = self.db.get_files_in_directory(dir.id)?;
+            let branch_id = self.tree.ancestor_ids(dir.id).last().unwrap_or(dir.id);
+            if let Some(branch) = self.tree.directories.iter().find(|d| d.id == branch_id) {
+                self.snapshot.load_branch(&self.db, branch)?;
+            }
 
             // Check if this directory or any ancestor matches the full filter criteria
             // If so, show all files without checking individual file filters
-            let ancestor_matches_filter = self.directory_or_ancestor_matches_filter(dir.id)?;
+            let ancestor_matches_filter = self.directory_or_ancestor_matches_filter(dir.id);
 
             // Get tags from this directory and all ancestors (for tag filter inheritance)
             let dir_tags = if !self.filter.tags.is_empty() {
-                self.get_directory_and_ancestor_tags(dir.id)?
+                self.get_directory_and_ancestor_tags(dir.id)
             } else {
                 vec![]
             };
 
-            // Batch fetch all tags for files in this directory (single query instead of N)
-            let all_file_tags = self.db.get_file_tags_for_directory(dir.id)?;
+            let listing = self.snapshot.listing(dir.id);
+
+            // Paired siblings (live photo video, RAW) are folded into their primary
+            let mut companions: HashMap<i64, Vec<String>> = HashMap::new();
+            for file in listing.files {
+                if let Some(primary_id) = listing.pairs.get(&file.id) {
+                    companions.entry(*primary_id).or_default().push(extension_label(&file.filename));
+                }
+            }
+            let dir_path = LibraryPath::new(&dir.path).absolute(&self.library_path);
+
+            // Collapsed stacks show only their first visible file, with a count
+            let mut stack_rows: HashMap<i64, usize> = HashMap::new();
+
+            for file in listing.files {
+                if listing.pairs.contains_key(&file.id) {
+                    continue;
+                }
 
-            for file in files {
-                let tags = all_file_tags
+                let tags = listing
+                    .file_tags
                     .get(&file.id)
                     .cloned()
                     .unwrap_or_default();
 
-                if !self.filter.matches_file(&file, &tags, &dir_tags, ancestor_matches_filter) {
+                if !self.filter.matches_file(file, &tags, &dir_tags, ancestor_matches_filter) {
                     continue;
                 }
 
-                self.file_list.files.push(FileWithTags { file, tags });
+                let mut companions = companions.remove(&file.id).unwrap_or_default();
+                if find_xmp_sidecar(&dir_path.join(&file.filename)).is_some() {
+                    companions.push("XMP".to_string());
+                }
+
+                let name_collisions = listing.name_collisions.get(&file.id).cloned().unwrap_or_default();
+
+                let stack_id = listing.stacks.get(&file.id).copied();
+                let mut stack_size = 0;
+                if let Some(id) = stack_id.filter(|id| !self.file_list.expanded_stacks.contains(id)) {
+                    if let Some(&row) = stack_rows.get(&id) {
+                        self.file_list.files[row].stack_size += 1;
+                        continue;
+                    }
+                    stack_rows.insert(id, self.file_list.files.len());
+                    stack_size = 1;
+                }
+
+                self.file_list.files.push(FileWithTags {
+                    file: file.clone(),
+                    tags,
+                    companions,
+                    stack_id,
+                    stack_size,
+                    name_collisions,
+                });
             }
+        } else if let Some(folder) = self.selected_virtual_folder() {
+            self.load_virtual_folder_files(folder)?;
         }
 
         Ok(())
@@ -67,30 +127,19 @@ impl AppState {
 
     /// Open the selected file with the default system viewer
     pub fn open_selected_file(&self) -> Result<()> {
+        if let Some(file_with_tags) = self.file_list.selected_file() {
+            let now = chrono::Utc::now().timestamp();
+            self.db.record_file_activity(file_with_tags.file.id, Activity::Viewed, now)?;
+        }
         if let Some(path) = self.selected_file_path() {
-            #[cfg(target_os = "linux")]
-            {
-                Command::new("xdg-open")
-                    .arg(&path)
-                    .stdout(std::process::Stdio::null())
-                    .stderr(std::process::Stdio::null())
-                    .spawn()?;
-            }
-            #[cfg(target_os = "macos")]
-            {
-                Command::new("open")
-                    .arg(&path)
-                    .stdout(std::process::Stdio::null())
-                    .stderr(std::process::Stdio::null())
-                    .spawn()?;
-            }
+            open_in_viewer(&path)?;
         }
         Ok(())
     }
 
     /// Check if a directory or any of its ancestors matches the full filter criteria
     /// (both rating and tags, when both are active)
-    fn directory_or_ancestor_matches_filter(&self, dir_id: i64) -> Result<bool> {
+    fn directory_or_ancestor_matches_filter(&self, dir_id: i64) -> bool {
         let ids = std::iter::once(dir_id).chain(self.tree.ancestor_ids(dir_id));
 
         for id in ids {
@@ -109,29 +158,35 @@ impl AppState {
             let dir_matches_tags = if self.filter.tags.is_empty() {
                 true
             } else {
-                let dir_tags = self.db.get_directory_tags(id)?;
+                let dir_tags = self.snapshot.directory_tags(id);
                 self.filter.tags.iter().all(|t| dir_tags.contains(t))
             };
 
             if dir_matches_rating && dir_matches_tags {
-                return Ok(true);
+                return true;
             }
         }
 
-        Ok(false)
+        false
     }
 
     /// Get all tags from a directory and all its ancestors
-    fn get_directory_and_ancestor_tags(&self, dir_id: i64) -> Result<Vec<String>> {
-        let mut all_tags = Vec::new();
-        for id in std::iter::once(dir_id).chain(self.tree.ancestor_ids(dir_id)) {
-            let tags = self.db.get_directory_tags(id)?;
-            all_tags.extend(tags);
-        }
-        Ok(all_tags)
+    fn get_directory_and_ancestor_tags(&self, dir_id: i64) -> Vec<String> {
+        std::iter::once(dir_id)
+            .chain(self.tree.ancestor_ids(dir_id))
+            .flat_map(|id| self.snapshot.directory_tags(id).iter().cloned())
+            .collect()
     }
 }
 
+/// Uppercase extension used to label a companion file ("IMG_1.mov" -> "MOV")
+fn extension_label(filename: &str) -> String {
+    Path::new(filename)
+        .extension()
+        .map(|e| e.to_string_lossy().to_uppercase())
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
@@ -1,12 +1,34 @@
 use anyhow::Result;
 
-use super::{AppState, FilterCriteria, FilterDialogState, RatingFilter};
+use crate::db::GearField;
+
+use super::{AppState, FilterCriteria, FilterDialogState, MediaFilter, RatingFilter};
 
 impl AppState {
     /// Open the filter dialog
     pub fn open_filter_dialog(&mut self) -> Result<()> {
         let all_tags = self.db.get_all_tags()?;
-        self.filter_dialog = Some(FilterDialogState::new(all_tags, &self.filter));
+        let mut dialog = FilterDialogState::new(all_tags, &self.filter);
+        dialog.presets = self.db.get_filter_presets()?;
+        dialog.cameras = self.db.get_gear_names(GearField::Camera)?;
+        dialog.lenses = self.db.get_gear_names(GearField::Lens)?;
+        dialog.rating_scale = self.rating_scale;
+        dialog.aliases = self.db.get_tag_aliases()?;
+        self.filter_dialog = Some(dialog);
+        Ok(())
+    }
+
+    /// Save the dialog's current criteria as a named preset
+    pub fn save_filter_preset(&mut self, name: &str) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Ok(());
+        }
+        if let Some(ref mut dialog) = self.filter_dialog {
+            self.db.save_filter_preset(name, &dialog.to_criteria())?;
+            dialog.presets = self.db.get_filter_presets()?;
+            self.status_message = Some(format!("Saved filter preset '{}'", name));
+        }
         Ok(())
     }
 
@@ -31,12 +53,19 @@ impl AppState {
 
     /// Clear the entire filter
     pub fn clear_filter(&mut self) -> Result<()> {
+        let virtual_folder = self.selected_virtual_folder();
         self.filter = FilterCriteria::default();
         self.matching_dir_ids.clear();
+        if let Some(folder) = virtual_folder {
+            self.select_virtual_folder(folder);
+        }
         if let Some(ref mut dialog) = self.filter_dialog {
             dialog.rating_filter = RatingFilter::Any;
             dialog.selected_tags.clear();
-            dialog.video_only = false;
+            dialog.media = MediaFilter::Any;
+            dialog.camera = None;
+            dialog.lens = None;
+            dialog.orientation = None;
             dialog.update_tag_filter();
         }
         self.force_redraw = true;
@@ -46,7 +75,8 @@ impl AppState {
     }
 
     /// Update the set of matching directory IDs based on current filter
-    pub(super) fn update_matching_directories(&mut self) -> Result<()> {
+    pub(crate) fn update_matching_directories(&mut self) -> Result<()> {
+        let virtual_folder = self.selected_virtual_folder();
         if self.filter.is_active() {
             // Store the currently selected directory ID before updating
             let current_dir_id = self.get_selected_directory().map(|d| d.id);
@@ -54,7 +84,10 @@ impl AppState {
             self.matching_dir_ids = self.db.get_directories_with_matching_files(
                 self.filter.rating,
                 &self.filter.tags,
-                self.filter.video_only,
+                self.filter.media,
+                self.filter.camera.as_deref(),
+                self.filter.lens.as_deref(),
+                self.filter.orientation,
             )?;
 
             // Reset selection if current directory is not visible
@@ -68,6 +101,9 @@ impl AppState {
         } else {
             self.matching_dir_ids.clear();
         }
+        if let Some(folder) = virtual_folder {
+            self.select_virtual_folder(folder);
+        }
         Ok(())
     }
 
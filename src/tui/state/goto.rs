@@ -0,0 +1,115 @@
+use super::{AppState, Focus, GotoState};
+
+impl AppState {
+    /// Open the goto prompt with every directory as a completion candidate
+    pub fn open_goto(&mut self) {
+        let directories = self
+            .tree
+            .directories
+            .iter()
+            .map(|d| (d.id, d.path.clone()))
+            .collect();
+        self.goto = Some(GotoState::new(directories));
+    }
+
+    pub fn close_goto(&mut self) {
+        self.goto = None;
+    }
+
+    /// Go to the directory the prompt points at and close it
+    pub fn goto_selected(&mut self) {
+        let Some(target) = self.goto.as_ref().and_then(|g| g.target()) else {
+            self.status_message = Some("No such directory".to_string());
+            return;
+        };
+        self.close_goto();
+        self.go_to_directory(target);
+    }
+
+    /// Select a directory in the tree and focus it, expanding its ancestors
+    pub fn go_to_directory(&mut self, dir_id: i64) {
+        self.focus = Focus::DirectoryTree;
+        if !self.select_directory_in_tree(dir_id) {
+            self.status_message = Some(if self.tree.is_hidden(dir_id) {
+                "Directory is hidden; press . to show hidden directories".to_string()
+            } else {
+                "Directory is hidden by the current filter".to_string()
+            });
+        }
+    }
+
+    /// Path segments for the breadcrumb above the file list as (label,
+    /// directory to go to). The first segment is the library itself.
+    pub fn breadcrumb(&self) -> Vec<(String, Option<i64>)> {
+        let library = self
+            .library_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.library_path.display().to_string());
+        let mut segments = vec![(library, None)];
+
+        if let Some(folder) = self.selected_virtual_folder() {
+            segments.push((self.virtual_folder_name(folder), None));
+            return segments;
+        }
+        let Some(dir) = self.get_selected_directory() else {
+            return segments;
+        };
+
+        let mut chain: Vec<i64> = self.tree.ancestor_ids(dir.id).collect();
+        chain.reverse();
+        chain.push(dir.id);
+        for id in chain {
+            if let Some(d) = self.tree.directories.iter().find(|d| d.id == id) {
+                let name = d.path.rsplit('/').next().unwrap_or(&d.path);
+                segments.push((name.to_string(), Some(d.id)));
+            }
+        }
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn test_goto_selects_directory_and_breadcrumb_follows() {
+        let (mut state, _tempdir) = create_test_app_state();
+        let photos = state.db.get_directory_by_path("photos").unwrap().unwrap();
+        let beach = state.db.insert_directory("photos/beach", Some(photos.id), None).unwrap();
+        state.tree.directories = state.db.get_all_directories().unwrap();
+        state.focus = Focus::FileList;
+
+        state.open_goto();
+        for c in "beach".chars() {
+            state.goto.as_mut().unwrap().push_char(c);
+        }
+        state.goto_selected();
+
+        assert!(state.goto.is_none());
+        assert_eq!(state.focus, Focus::DirectoryTree);
+        assert!(state.tree.expanded.contains(&photos.id));
+        assert_eq!(state.get_selected_directory().unwrap().id, beach);
+
+        let crumbs = state.breadcrumb();
+        let labels: Vec<&str> = crumbs.iter().skip(1).map(|(l, _)| l.as_str()).collect();
+        assert_eq!(labels, vec!["photos", "beach"]);
+        assert_eq!(crumbs[0].1, None);
+        assert_eq!(crumbs[1].1, Some(photos.id));
+    }
+
+    #[test]
+    fn test_goto_unknown_path_keeps_prompt_open() {
+        let (mut state, _tempdir) = create_test_app_state();
+        state.open_goto();
+        for c in "nowhere".chars() {
+            state.goto.as_mut().unwrap().push_char(c);
+        }
+        state.goto_selected();
+
+        assert!(state.goto.is_some());
+        assert_eq!(state.status_message.as_deref(), Some("No such directory"));
+    }
+}
@@ -0,0 +1,73 @@
+use anyhow::Result;
+
+use super::{AppState, Focus};
+
+impl AppState {
+    /// Hide the selected directory (and everything below it) from the tree,
+    /// or unhide it if it already is
+    pub fn toggle_hidden(&mut self) -> Result<()> {
+        if self.focus != Focus::DirectoryTree {
+            return Ok(());
+        }
+        let Some(dir) = self.get_selected_directory().cloned() else {
+            return Ok(());
+        };
+        let name = if dir.path.is_empty() { "." } else { dir.path.as_str() };
+
+        if self.tree.hidden.contains(&dir.id) {
+            self.db.unhide_directory(dir.id)?;
+            self.status_message = Some(format!("Unhid {}", name));
+        } else {
+            self.db.hide_directory(dir.id, chrono::Utc::now().timestamp())?;
+            self.status_message = Some(if self.tree.show_hidden {
+                format!("Hid {}", name)
+            } else {
+                format!("Hid {} (press . to show hidden directories)", name)
+            });
+        }
+        let hidden = !self.tree.hidden.remove(&dir.id);
+        self.reshape_tree(|tree, _| {
+            if hidden {
+                tree.hidden.insert(dir.id);
+            }
+            true
+        });
+        Ok(())
+    }
+
+    /// Show or leave out hidden directories
+    pub fn toggle_show_hidden(&mut self) {
+        let show = !self.tree.show_hidden;
+        self.reshape_tree(|tree, _| {
+            tree.show_hidden = show;
+            true
+        });
+        self.status_message = Some(
+            if show { "Showing hidden directories" } else { "Leaving out hidden directories" }.to_string(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::*;
+
+    #[test]
+    fn test_toggle_hidden_and_show_hidden() {
+        let (mut state, _tempdir) = create_test_app_state();
+        let photos = state.db.get_directory_by_path("photos").unwrap().unwrap();
+        assert_eq!(state.get_selected_directory().unwrap().id, photos.id);
+
+        state.toggle_hidden().unwrap();
+        assert!(state.db.get_hidden_directory_ids().unwrap().contains(&photos.id));
+        let paths: Vec<&str> = state.get_visible_directories().iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, vec!["videos"]);
+
+        // Shown again (dimmed) on request, where it can be unhidden
+        state.toggle_show_hidden();
+        assert!(state.select_directory_in_tree(photos.id));
+        state.toggle_hidden().unwrap();
+        assert!(state.db.get_hidden_directory_ids().unwrap().is_empty());
+        assert!(state.tree.hidden.is_empty());
+    }
+}
@@ -1,29 +1,54 @@
+mod changes;
+mod dashboard;
+mod errors;
 mod files;
 mod filter;
+mod goto;
+mod hidden;
 mod navigation;
+mod pins;
 mod preview;
+mod recent;
 mod rename;
+mod review;
 mod search;
+mod snapshot;
+mod stacks;
 mod tags;
+mod tree_sort;
+mod usage;
 
 use std::cell::RefCell;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::Result;
 use ratatui::layout::Rect;
 use ratatui::widgets::{ListState, TableState};
 
-use crate::db::{Database, Directory, File};
+use tracing::warn;
+
+use crate::config::{Config, TuiConfig};
+use crate::db::{Activity, Database, Directory, DirectoryTotals, File, ReviewQueue};
 use crate::tui::preview_loader::PreviewLoader;
+use crate::notify::Notifier;
+use crate::rating::RatingScale;
+use crate::thumbnails::PreviewCover;
+use crate::vfs::{open_library_fs, LibraryFs};
 
 // Re-export dialog types so existing `use crate::tui::state::X` paths keep working
 pub use super::dialogs::{
-    FilterCriteria, FilterDialogFocus, FilterDialogState, OperationsMenuState, RatingFilter,
-    RenameDialogState, SearchState, TagInputState,
+    ErrorPopupState, FilterCriteria, FilterDialogFocus, FilterDialogState, GotoState, MediaFilter,
+    OnboardingField, OnboardingState, OperationsMenuState, OperationsTab, RatingFilter, RenameDialogState,
+    SearchState, TagInputState, UsageViewState,
 };
-pub use super::operations::{BackgroundProgress, OperationType};
-pub use super::preview_cache::LruPreviewCache;
+pub use super::operations::{BackgroundProgress, FinishedTask, OperationType, TaskManager};
+pub use super::preview_cache::{LruPreviewCache, PreviewZoom};
+pub use dashboard::DashboardState;
+pub use recent::{ViewRecorder, VirtualFolder};
+pub use snapshot::Snapshot;
 
 /// Which pane has focus
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,6 +62,54 @@ pub enum Focus {
 pub struct FileWithTags {
     pub file: File,
     pub tags: Vec<String>,
+    /// Extensions of paired siblings shown under this entry (MOV, NEF, XMP)
+    pub companions: Vec<String>,
+    /// Manual stack this file belongs to
+    pub stack_id: Option<i64>,
+    /// Number of files this row stands for while its stack is collapsed (0 otherwise)
+    pub stack_size: usize,
+    /// Siblings whose names clash with this one on case-insensitive filesystems
+    pub name_collisions: Vec<String>,
+}
+
+/// Order of sibling directories in the tree (`[tui] tree_sort` in the config)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TreeSort {
+    #[default]
+    Name,
+    /// Highest rated first, unrated last
+    Rating,
+    /// Newest file anywhere below first
+    Newest,
+    /// Largest total size first
+    Size,
+}
+
+impl TreeSort {
+    pub fn name(self) -> &'static str {
+        match self {
+            TreeSort::Name => "name",
+            TreeSort::Rating => "rating",
+            TreeSort::Newest => "newest",
+            TreeSort::Size => "size",
+        }
+    }
+
+    /// The mode `O` switches to
+    pub fn next(self) -> Self {
+        match self {
+            TreeSort::Name => TreeSort::Rating,
+            TreeSort::Rating => TreeSort::Newest,
+            TreeSort::Newest => TreeSort::Size,
+            TreeSort::Size => TreeSort::Name,
+        }
+    }
+
+    /// Whether ordering needs `Database::get_directory_totals`
+    pub fn needs_totals(self) -> bool {
+        matches!(self, TreeSort::Newest | TreeSort::Size)
+    }
 }
 
 /// State for the directory tree
@@ -45,6 +118,13 @@ pub struct TreeState {
     pub selected_index: usize,
     pub expanded: HashSet<i64>,
     pub list_state: ListState,
+    /// Pinned directory IDs, shown above the tree in pin order
+    pub pinned: Vec<i64>,
+    pub sort: TreeSort,
+    /// Directories marked hidden; they and everything below them are left
+    /// out of the tree unless `show_hidden`
+    pub hidden: HashSet<i64>,
+    pub show_hidden: bool,
 }
 
 impl TreeState {
@@ -54,6 +134,34 @@ impl TreeState {
             selected_index: 0,
             expanded: HashSet::new(),
             list_state: ListState::default().with_selected(Some(0)),
+            pinned: Vec::new(),
+            sort: TreeSort::default(),
+            hidden: HashSet::new(),
+            show_hidden: false,
+        }
+    }
+
+    /// Order `directories` by the sort mode. Rows are built by walking each
+    /// parent's children in vector order, so any total order over all
+    /// directories also orders every set of siblings. Ties go by path.
+    pub fn sort_directories(&mut self, totals: &HashMap<i64, DirectoryTotals>) {
+        let total = |d: &Directory| totals.get(&d.id).copied().unwrap_or_default();
+        match self.sort {
+            TreeSort::Name => self.directories.sort_by(|a, b| a.path.cmp(&b.path)),
+            TreeSort::Rating => self
+                .directories
+                .sort_by(|a, b| {
+                    b.rating
+                        .partial_cmp(&a.rating)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                        .then_with(|| a.path.cmp(&b.path))
+                }),
+            TreeSort::Newest => self
+                .directories
+                .sort_by(|a, b| total(b).newest.cmp(&total(a).newest).then_with(|| a.path.cmp(&b.path))),
+            TreeSort::Size => self
+                .directories
+                .sort_by(|a, b| total(b).size.cmp(&total(a).size).then_with(|| a.path.cmp(&b.path))),
         }
     }
 
@@ -66,7 +174,7 @@ impl TreeState {
 
     fn collect_visible<'a>(&'a self, parent_id: Option<i64>, visible: &mut Vec<&'a Directory>) {
         for dir in &self.directories {
-            if dir.parent_id == parent_id {
+            if dir.parent_id == parent_id && self.is_shown(dir.id) {
                 visible.push(dir);
                 if self.expanded.contains(&dir.id) {
                     self.collect_visible(Some(dir.id), visible);
@@ -77,7 +185,23 @@ impl TreeState {
 
     /// Check if a directory has children
     pub fn has_children(&self, dir_id: i64) -> bool {
-        self.directories.iter().any(|d| d.parent_id == Some(dir_id))
+        self.directories
+            .iter()
+            .any(|d| d.parent_id == Some(dir_id) && self.is_shown(d.id))
+    }
+
+    /// Whether a directory's row may be shown: it isn't marked hidden, or
+    /// hidden directories are being shown. Rows below a hidden directory
+    /// are never reached, since the walk doesn't descend into it.
+    fn is_shown(&self, dir_id: i64) -> bool {
+        self.show_hidden || !self.hidden.contains(&dir_id)
+    }
+
+    /// Whether a directory is hidden, or inside a hidden directory
+    pub fn is_hidden(&self, dir_id: i64) -> bool {
+        std::iter::once(dir_id)
+            .chain(self.ancestor_ids(dir_id))
+            .any(|id| self.hidden.contains(&id))
     }
 
     /// Get depth of a directory in the tree
@@ -119,7 +243,7 @@ impl TreeState {
         visible: &mut Vec<&'a Directory>,
     ) {
         for dir in &self.directories {
-            if dir.parent_id == parent_id && matching_ids.contains(&dir.id) {
+            if dir.parent_id == parent_id && matching_ids.contains(&dir.id) && self.is_shown(dir.id) {
                 visible.push(dir);
                 if self.expanded.contains(&dir.id) {
                     self.collect_visible_filtered(Some(dir.id), matching_ids, visible);
@@ -135,7 +259,72 @@ impl TreeState {
         }
         self.directories
             .iter()
-            .any(|d| d.parent_id == Some(dir_id) && matching_ids.contains(&d.id))
+            .any(|d| d.parent_id == Some(dir_id) && matching_ids.contains(&d.id) && self.is_shown(d.id))
+    }
+
+    /// Collapse every directory
+    pub fn collapse_all(&mut self) {
+        self.expanded.clear();
+    }
+
+    /// Expand exactly the directories above `depth`, so that many levels
+    /// below the top are shown. With a filter, only matching directories
+    /// are expanded.
+    pub fn expand_to_depth(&mut self, depth: usize, matching_ids: &HashSet<i64>) {
+        let levels = self.expandable_by_depth(matching_ids);
+        self.expanded = levels.into_iter().take(depth).flatten().collect();
+    }
+
+    /// Expand level by level until everything is open or the next level would
+    /// show more than `max_rows` directories. With a filter, only matching
+    /// directories are expanded. Returns false if it stopped early.
+    pub fn expand_all(&mut self, max_rows: usize, matching_ids: &HashSet<i64>) -> bool {
+        let is_shown = |id: i64| matching_ids.is_empty() || matching_ids.contains(&id);
+        let mut child_counts: HashMap<i64, usize> = HashMap::new();
+        let mut rows = 0;
+        for dir in self.directories.iter().filter(|d| is_shown(d.id)) {
+            match dir.parent_id {
+                Some(pid) => *child_counts.entry(pid).or_default() += 1,
+                None => rows += 1,
+            }
+        }
+
+        self.expanded.clear();
+        for level in self.expandable_by_depth(matching_ids) {
+            let added: usize = level.iter().map(|id| child_counts[id]).sum();
+            if rows + added > max_rows {
+                return false;
+            }
+            rows += added;
+            self.expanded.extend(level);
+        }
+        true
+    }
+
+    /// IDs of directories that have (matching) children, grouped by depth
+    fn expandable_by_depth(&self, matching_ids: &HashSet<i64>) -> Vec<Vec<i64>> {
+        let is_shown = |id: i64| matching_ids.is_empty() || matching_ids.contains(&id);
+        let parents: HashMap<i64, Option<i64>> =
+            self.directories.iter().map(|d| (d.id, d.parent_id)).collect();
+        let with_children: HashSet<i64> = self
+            .directories
+            .iter()
+            .filter(|d| is_shown(d.id))
+            .filter_map(|d| d.parent_id)
+            .collect();
+
+        let mut levels: Vec<Vec<i64>> = Vec::new();
+        for dir in &self.directories {
+            if !is_shown(dir.id) || !with_children.contains(&dir.id) {
+                continue;
+            }
+            let depth = std::iter::successors(dir.parent_id, |pid| parents.get(pid).copied().flatten()).count();
+            if levels.len() <= depth {
+                levels.resize(depth + 1, Vec::new());
+            }
+            levels[depth].push(dir.id);
+        }
+        levels
     }
 }
 
@@ -144,6 +333,10 @@ pub struct FileListState {
     pub files: Vec<FileWithTags>,
     pub selected_index: usize,
     pub table_state: TableState,
+    /// File IDs marked (Space) for stacking
+    pub marked: HashSet<i64>,
+    /// Stacks currently shown expanded (z)
+    pub expanded_stacks: HashSet<i64>,
 }
 
 impl FileListState {
@@ -152,6 +345,8 @@ impl FileListState {
             files: Vec::new(),
             selected_index: 0,
             table_state: TableState::default().with_selected(Some(0)),
+            marked: HashSet::new(),
+            expanded_stacks: HashSet::new(),
         }
     }
 
@@ -161,17 +356,20 @@ impl FileListState {
     }
 }
 
-/// Default LRU cache size for file previews.
-const DEFAULT_PREVIEW_CACHE_SIZE: usize = 50;
-
-/// Default LRU cache size for directory composite previews.
-/// These are larger images (~12 thumbnails composited) so we keep fewer.
-const DEFAULT_DIR_PREVIEW_CACHE_SIZE: usize = 20;
+/// Split of `[tui] preview_memory_mb` between the preview caches: file
+/// previews get four fifths, directory composites (~12 thumbnails each,
+/// looked at less often) the rest.
+fn preview_cache_budgets(memory_mb: u32) -> (usize, usize) {
+    let total = memory_mb as usize * 1024 * 1024;
+    (total / 5 * 4, total / 5)
+}
 
 /// Main application state
 pub struct AppState {
     pub library_path: PathBuf,
     pub db: Database,
+    /// What the tree, file list and details panel draw from
+    pub snapshot: Snapshot,
     pub focus: Focus,
     pub tree: TreeState,
     pub file_list: FileListState,
@@ -181,23 +379,31 @@ pub struct AppState {
     pub tag_input: Option<TagInputState>,
     pub filter_dialog: Option<FilterDialogState>,
     pub rename_dialog: Option<RenameDialogState>,
+    /// Goto prompt (`:`)
+    pub goto: Option<GotoState>,
+    /// Last failed action, shown over everything until dismissed
+    pub error_popup: Option<ErrorPopupState>,
     pub filter: FilterCriteria,
     /// Directory IDs that match the current filter (includes ancestors for tree structure)
     pub matching_dir_ids: HashSet<i64>,
     /// Operations menu popup
     pub operations_menu: Option<OperationsMenuState>,
+    /// Disk usage view popup
+    pub usage_view: Option<UsageViewState>,
     /// Status message to show temporarily
     pub status_message: Option<String>,
-    /// Background operation progress
-    pub background_progress: Option<BackgroundProgress>,
-    /// Queue of pending operations (executed sequentially)
-    pub operation_queue: VecDeque<OperationType>,
+    /// Running, queued and recently finished background operations
+    pub tasks: TaskManager,
+    /// Desktop notifications for long operations
+    pub notifier: Notifier,
     /// Cache for missing preview check: (dir_id, is_missing)
     pub missing_preview_cache: RefCell<Option<(i64, bool)>>,
     /// True when file list needs to be reloaded (deferred loading for smooth scrolling)
     pub files_dirty: bool,
     /// Skip loading new previews during rapid navigation (show cached instead)
     pub skip_preview: bool,
+    /// Library filesystem (local, or remote with originals fetched on demand)
+    pub fs: Arc<dyn LibraryFs>,
     /// Background image loader - decodes images off the main thread
     pub preview_loader: RefCell<PreviewLoader>,
     /// Current directory ID for tracking stale preview loads
@@ -209,56 +415,135 @@ pub struct AppState {
     /// Layout rects saved each frame for mouse hit-testing
     pub tree_area: Rect,
     pub file_list_area: Rect,
-    /// Path of the file whose protocol is currently being rendered.
-    /// The actual protocol lives in the preview_cache entry. This path is used
+    /// Clickable breadcrumb segments from the last frame, as (area, directory ID)
+    pub breadcrumb_targets: Vec<(Rect, i64)>,
+    /// Path and zoom tier of the file whose protocol is currently being rendered.
+    /// The actual protocol lives in the preview_cache entry. This key is used
     /// as a fallback reference during rapid navigation (skip_preview) so we know
     /// which cached protocol to keep showing.
-    pub render_protocol: RefCell<Option<PathBuf>>,
+    pub render_protocol: RefCell<Option<(PathBuf, PreviewZoom)>>,
     /// Incremental search state (/ key)
     pub search: SearchState,
     /// Whether the details panel is expanded (toggled with `i`)
     pub details_expanded: bool,
+    /// Whether the expanded details panel lists every EXIF tag instead of
+    /// the summary (toggled with `I`)
+    pub exif_dump: bool,
+    /// First line of the EXIF dump shown (`J`/`K`)
+    pub exif_scroll: u16,
     /// Cached EXIF data for the current file (avoids re-reading on every frame)
     pub cached_exif: Option<(PathBuf, super::exif::ExifInfo)>,
+    /// `e` was pressed; the next digit expands the tree to that level
+    pub expand_level_pending: bool,
+    /// Last file recorded as viewed, so lingering on it doesn't re-record
+    pub viewed_file_id: Option<i64>,
+    /// Writes file views off the UI thread
+    pub view_recorder: ViewRecorder,
+    /// The library's rating scale (`[ratings] scale` in the config)
+    pub rating_scale: RatingScale,
+    /// Which images directory previews show (`[previews] cover`)
+    pub preview_cover: PreviewCover,
+    /// Cull mode (`c`): rating a file moves on to the next one
+    pub cull_mode: bool,
+    /// Whether the library dashboard is shown (toggled with `D`)
+    pub show_dashboard: bool,
+    /// Cached dashboard summary, built the first time it's shown
+    pub dashboard: Option<DashboardState>,
+    /// Zoom tier of the file preview (`Z`, `W`, `=`); kept while moving
+    /// between files so a series can be checked at the same detail
+    pub preview_zoom: PreviewZoom,
+    /// Review queues listed as virtual folders, by name
+    pub review_queues: Vec<ReviewQueue>,
+    /// Ratings and tags as of this `metadata_version`, and when it was
+    /// last checked for changes made by other processes
+    metadata_version: i64,
+    metadata_checked: Instant,
 }
 
 impl AppState {
     pub fn new(library_path: PathBuf, db: Database) -> Result<Self> {
         let directories = db.get_all_directories()?;
-        let tree = TreeState::new(directories);
+        let mut tree = TreeState::new(directories);
+        tree.pinned = db.get_pinned_directory_ids()?;
+        tree.hidden = db.get_hidden_directory_ids()?;
+        let (tui_config, rating_scale, preview_cover) = match Config::load(&library_path) {
+            Ok(config) => (config.tui, config.ratings.scale, config.previews.cover),
+            Err(e) => {
+                warn!(error = %format!("{:#}", e), "using the default TUI settings and rating scale");
+                (TuiConfig::default(), RatingScale::default(), PreviewCover::default())
+            }
+        };
+        tree.sort = tui_config.tree_sort;
+        if tree.sort.needs_totals() {
+            tree.sort_directories(&db.get_directory_totals()?);
+        } else {
+            tree.sort_directories(&HashMap::new());
+        }
+        let fs = open_library_fs(&library_path)?;
+        let notifier = Notifier::for_library(&library_path);
+
+        let (file_cache_bytes, dir_cache_bytes) = preview_cache_budgets(tui_config.preview_memory_mb);
+        let metadata_version = db.metadata_version()?;
+        let snapshot = Snapshot::load(&db)?;
+        let db_path = library_path.join(".picman.db");
 
         let mut state = Self {
             library_path,
             db,
+            snapshot,
             focus: Focus::DirectoryTree,
             tree,
             file_list: FileListState::new(),
             show_help: false,
-            preview_cache: RefCell::new(LruPreviewCache::new(DEFAULT_PREVIEW_CACHE_SIZE)),
-            dir_preview_cache: RefCell::new(LruPreviewCache::new(DEFAULT_DIR_PREVIEW_CACHE_SIZE)),
+            preview_cache: RefCell::new(LruPreviewCache::new(file_cache_bytes)),
+            dir_preview_cache: RefCell::new(LruPreviewCache::new(dir_cache_bytes)),
             tag_input: None,
             filter_dialog: None,
             rename_dialog: None,
+            goto: None,
+            error_popup: None,
             filter: FilterCriteria::default(),
             matching_dir_ids: HashSet::new(),
             operations_menu: None,
+            usage_view: None,
             status_message: None,
-            background_progress: None,
-            operation_queue: VecDeque::new(),
+            tasks: TaskManager::default(),
+            notifier,
             missing_preview_cache: RefCell::new(None),
             files_dirty: false,
             skip_preview: false,
-            preview_loader: RefCell::new(PreviewLoader::new()),
+            preview_loader: RefCell::new(PreviewLoader::new(
+                Arc::clone(&fs),
+                tui_config.preview_resolution,
+            )),
+            fs,
             current_dir_id: None,
             force_redraw: false,
             tree_area: Rect::default(),
             file_list_area: Rect::default(),
+            breadcrumb_targets: Vec::new(),
             render_protocol: RefCell::new(None),
             search: SearchState::new(),
             details_expanded: false,
+            exif_dump: false,
+            exif_scroll: 0,
             cached_exif: None,
+            expand_level_pending: false,
+            viewed_file_id: None,
+            view_recorder: ViewRecorder::new(db_path),
+            rating_scale,
+            preview_cover,
+            cull_mode: false,
+            show_dashboard: false,
+            dashboard: None,
+            preview_zoom: PreviewZoom::default(),
+            review_queues: Vec::new(),
+            metadata_version,
+            metadata_checked: Instant::now(),
         };
 
+        state.reload_review_queues()?;
+
         // Load files for initial selection
         state.load_files_for_selected_directory()?;
 
@@ -279,21 +564,34 @@ impl AppState {
         dirs
     }
 
-    /// Get the currently selected directory considering the filter
+    /// Get the currently selected directory considering the filter.
+    /// Rows start with the pinned directories, then the tree.
     pub fn get_selected_directory(&self) -> Option<&Directory> {
+        let pinned = self.pinned_directories();
+        if let Some(dir) = pinned.get(self.tree.selected_index) {
+            return Some(dir);
+        }
         self.get_visible_directories()
-            .get(self.tree.selected_index)
+            .get(self.tree.selected_index - pinned.len())
             .copied()
     }
 
     /// Get the full path to the currently selected file
     pub fn selected_file_path(&self) -> Option<PathBuf> {
         let file_with_tags = self.file_list.selected_file()?;
-        let dir = self.get_selected_directory()?;
+        let dir = self.file_directory(&file_with_tags.file)?;
         Some(dir.file_path(&self.library_path, &file_with_tags.file.filename))
     }
 
-    pub fn set_rating(&mut self, rating: Option<i32>) -> Result<()> {
+    /// The directory a listed file is in: the selected one, or for a
+    /// virtual folder, wherever the file lives
+    pub fn file_directory(&self, file: &File) -> Option<&Directory> {
+        self.get_selected_directory()
+            .filter(|d| d.id == file.directory_id)
+            .or_else(|| self.tree.directories.iter().find(|d| d.id == file.directory_id))
+    }
+
+    pub fn set_rating(&mut self, rating: Option<f64>) -> Result<()> {
         match self.focus {
             Focus::DirectoryTree => {
                 if let Some(dir) = self.get_selected_directory() {
@@ -309,14 +607,85 @@ impl AppState {
                 if let Some(file_with_tags) =
                     self.file_list.files.get_mut(self.file_list.selected_index)
                 {
-                    self.db.set_file_rating(file_with_tags.file.id, rating)?;
+                    let file_id = file_with_tags.file.id;
+                    self.db.set_file_rating(file_id, rating)?;
+                    self.db.record_file_activity(file_id, Activity::Edited, chrono::Utc::now().timestamp())?;
                     file_with_tags.file.rating = rating;
+                    self.snapshot.set_file_rating(file_id, rating);
+                    self.record_review_rating(file_id)?;
                 }
             }
         }
         Ok(())
     }
 
+    /// Raise or lower the selected rating by `delta` (half a star with `[`
+    /// and `]`). Raising an unrated item gives it 1; lowering past 1 clears
+    /// the rating.
+    pub fn nudge_rating(&mut self, delta: f64) -> Result<()> {
+        let current = match self.focus {
+            Focus::DirectoryTree => match self.get_selected_directory() {
+                Some(dir) => dir.rating,
+                None => return Ok(()),
+            },
+            Focus::FileList => match self.file_list.files.get(self.file_list.selected_index) {
+                Some(file_with_tags) => file_with_tags.file.rating,
+                None => return Ok(()),
+            },
+        };
+        let rating = match current {
+            None if delta > 0.0 => Some(1.0),
+            None => return Ok(()),
+            Some(r) if r + delta < 1.0 => None,
+            Some(r) => Some((r + delta).min(self.rating_scale.max() as f64)),
+        };
+        if rating != current {
+            self.set_rating(rating)?;
+        }
+        Ok(())
+    }
+
+    /// Set the selected rating (None clears it); in cull mode a rated file
+    /// is followed by the next one in the list
+    pub fn rate_selected(&mut self, rating: Option<f64>) -> Result<()> {
+        self.set_rating(rating)?;
+        if self.cull_mode && self.focus == Focus::FileList {
+            self.cull_advance();
+        }
+        Ok(())
+    }
+
+    /// Like `nudge_rating`, advancing afterwards in cull mode
+    pub fn nudge_selected(&mut self, delta: f64) -> Result<()> {
+        self.nudge_rating(delta)?;
+        if self.cull_mode && self.focus == Focus::FileList {
+            self.cull_advance();
+        }
+        Ok(())
+    }
+
+    /// Move to the next file without wrapping, so a session ends on the
+    /// last file instead of starting over at the first
+    fn cull_advance(&mut self) {
+        let next = self.file_list.selected_index + 1;
+        if next >= self.file_list.files.len() {
+            self.status_message = Some("Cull: last file".to_string());
+            return;
+        }
+        self.file_list.selected_index = next;
+        self.file_list.table_state.select(Some(next));
+        self.preload_cull_files();
+    }
+
+    pub fn toggle_cull_mode(&mut self) {
+        self.cull_mode = !self.cull_mode;
+        self.status_message = Some(if self.cull_mode {
+            "Cull mode on: rating a file moves to the next".to_string()
+        } else {
+            "Cull mode off".to_string()
+        });
+    }
+
     pub fn toggle_help(&mut self) {
         self.show_help = !self.show_help;
     }
@@ -338,7 +707,7 @@ pub(super) mod test_helpers {
             crate::db::Directory { id: 1, path: "photos".to_string(), parent_id: None, rating: None, mtime: Some(0) },
             crate::db::Directory { id: 2, path: "photos/vacation".to_string(), parent_id: Some(1), rating: None, mtime: Some(0) },
             crate::db::Directory { id: 3, path: "photos/vacation/beach".to_string(), parent_id: Some(2), rating: None, mtime: Some(0) },
-            crate::db::Directory { id: 4, path: "videos".to_string(), parent_id: None, rating: Some(5), mtime: Some(0) },
+            crate::db::Directory { id: 4, path: "videos".to_string(), parent_id: None, rating: Some(5.0), mtime: Some(0) },
         ]
     }
 
@@ -460,6 +829,90 @@ mod tests {
         assert_eq!(visible.len(), 1); // Only photos visible (beach is under vacation which isn't in filter)
     }
 
+    #[test]
+    fn test_tree_hidden_directories_leave_out_subtree() {
+        let mut tree = TreeState::new(create_test_directories());
+        tree.expanded.extend([1, 2]);
+        tree.hidden.insert(2);
+
+        let paths: Vec<&str> = tree.visible_directories().iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, vec!["photos", "videos"]);
+        assert!(!tree.has_children(1));
+        assert!(tree.is_hidden(3));
+        assert!(!tree.is_hidden(1));
+
+        tree.show_hidden = true;
+        assert_eq!(tree.visible_directories().len(), 4);
+        assert!(tree.has_children(1));
+    }
+
+    #[test]
+    fn test_tree_sort_orders_siblings() {
+        let mut tree = TreeState::new(create_test_directories());
+        tree.expanded.extend([1, 2]);
+        let paths = |tree: &TreeState| tree.visible_directories().iter().map(|d| d.path.clone()).collect::<Vec<_>>();
+
+        // videos is rated, photos isn't
+        tree.sort = TreeSort::Rating;
+        tree.sort_directories(&HashMap::new());
+        assert_eq!(paths(&tree), vec!["videos", "photos", "photos/vacation", "photos/vacation/beach"]);
+
+        let totals = HashMap::from([
+            (1, DirectoryTotals { files: 1, size: 10, newest: Some(5) }),
+            (4, DirectoryTotals { files: 2, size: 20, newest: Some(1) }),
+        ]);
+        tree.sort = TreeSort::Newest;
+        tree.sort_directories(&totals);
+        assert_eq!(paths(&tree)[0], "photos");
+        tree.sort = TreeSort::Size;
+        tree.sort_directories(&totals);
+        assert_eq!(paths(&tree)[0], "videos");
+
+        tree.sort = TreeSort::Name;
+        tree.sort_directories(&totals);
+        assert_eq!(paths(&tree), vec!["photos", "photos/vacation", "photos/vacation/beach", "videos"]);
+    }
+
+    #[test]
+    fn test_tree_expand_to_depth_and_collapse_all() {
+        let mut tree = TreeState::new(create_test_directories());
+
+        tree.expand_to_depth(1, &HashSet::new());
+        assert_eq!(tree.expanded, HashSet::from([1]));
+        assert_eq!(tree.visible_directories().len(), 3);
+
+        tree.expand_to_depth(5, &HashSet::new());
+        assert_eq!(tree.expanded, HashSet::from([1, 2]));
+        assert_eq!(tree.visible_directories().len(), 4);
+
+        tree.collapse_all();
+        assert!(tree.expanded.is_empty());
+    }
+
+    #[test]
+    fn test_tree_expand_all_is_bounded() {
+        let mut tree = TreeState::new(create_test_directories());
+
+        assert!(tree.expand_all(100, &HashSet::new()));
+        assert_eq!(tree.expanded, HashSet::from([1, 2]));
+
+        // Two roots plus vacation fit, beach would make four
+        assert!(!tree.expand_all(3, &HashSet::new()));
+        assert_eq!(tree.expanded, HashSet::from([1]));
+    }
+
+    #[test]
+    fn test_tree_expand_all_matching_only() {
+        let mut dirs = create_test_directories();
+        dirs.push(crate::db::Directory { id: 5, path: "videos/2024".to_string(), parent_id: Some(4), rating: None, mtime: Some(0) });
+        let mut tree = TreeState::new(dirs);
+
+        // Filter matches beach (and its ancestors), not videos/2024
+        let matching: HashSet<i64> = [1, 2, 3].into();
+        assert!(tree.expand_all(100, &matching));
+        assert_eq!(tree.expanded, HashSet::from([1, 2]));
+    }
+
     // ==================== Rating Tests ====================
 
     #[test]
@@ -467,10 +920,10 @@ mod tests {
         let (mut state, _tempdir) = create_test_app_state();
         state.focus = Focus::DirectoryTree;
 
-        state.set_rating(Some(4)).unwrap();
+        state.set_rating(Some(4.0)).unwrap();
 
         let dir = state.get_selected_directory().unwrap();
-        assert_eq!(dir.rating, Some(4));
+        assert_eq!(dir.rating, Some(4.0));
     }
 
     #[test]
@@ -481,9 +934,81 @@ mod tests {
         // First ensure files are loaded
         assert!(!state.file_list.files.is_empty());
 
-        state.set_rating(Some(3)).unwrap();
+        state.set_rating(Some(3.0)).unwrap();
 
         let file = state.file_list.selected_file().unwrap();
-        assert_eq!(file.file.rating, Some(3));
+        assert_eq!(file.file.rating, Some(3.0));
+    }
+
+    #[test]
+    fn test_nudge_rating_in_half_steps() {
+        let (mut state, _tempdir) = create_test_app_state();
+        state.focus = Focus::FileList;
+        let rating = |state: &AppState| state.file_list.selected_file().unwrap().file.rating;
+
+        state.nudge_rating(0.5).unwrap();
+        assert_eq!(rating(&state), Some(1.0));
+        state.nudge_rating(0.5).unwrap();
+        assert_eq!(rating(&state), Some(1.5));
+
+        state.set_rating(Some(5.0)).unwrap();
+        state.nudge_rating(0.5).unwrap();
+        assert_eq!(rating(&state), Some(5.0), "capped at the scale");
+
+        state.set_rating(Some(1.0)).unwrap();
+        state.nudge_rating(-0.5).unwrap();
+        assert_eq!(rating(&state), None);
+    }
+
+    #[test]
+    fn test_dashboard_counts_library_and_pending_work() {
+        let (mut state, _tempdir) = create_test_app_state();
+
+        state.toggle_dashboard().unwrap();
+        assert!(state.show_dashboard);
+        let summary = &state.dashboard.as_ref().unwrap().summary;
+        assert_eq!((summary.files, summary.images, summary.videos), (3, 2, 1));
+        assert_eq!(summary.without_hash, 3);
+
+        // No thumbnails were generated, so all three are missing
+        let start = std::time::Instant::now();
+        while state.dashboard.as_ref().unwrap().missing_thumbnails.is_none() {
+            assert!(start.elapsed() < std::time::Duration::from_secs(10));
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            state.poll_dashboard().unwrap();
+        }
+        assert_eq!(state.dashboard.as_ref().unwrap().missing_thumbnails, Some(3));
+
+        // Reopening keeps the count but refreshes the totals
+        state.toggle_dashboard().unwrap();
+        state.focus = Focus::FileList;
+        state.set_rating(Some(4.0)).unwrap();
+        state.toggle_dashboard().unwrap();
+        let dashboard = state.dashboard.as_ref().unwrap();
+        assert_eq!(dashboard.summary.rated, 1);
+        assert_eq!(dashboard.missing_thumbnails, Some(3));
+    }
+
+    #[test]
+    fn test_cull_mode_rates_and_advances() {
+        let (mut state, _tempdir) = create_test_app_state();
+        state.focus = Focus::FileList;
+        let ratings = |state: &AppState| -> Vec<Option<f64>> {
+            state.file_list.files.iter().map(|f| f.file.rating).collect()
+        };
+
+        // Without cull mode the selection stays put
+        state.rate_selected(Some(2.0)).unwrap();
+        assert_eq!(state.file_list.selected_index, 0);
+
+        state.toggle_cull_mode();
+        state.rate_selected(Some(4.0)).unwrap();
+        assert_eq!(state.file_list.selected_index, 1);
+        state.nudge_selected(0.5).unwrap();
+        assert_eq!(ratings(&state), vec![Some(4.0), Some(1.0)]);
+
+        // The last file stays selected rather than wrapping to the first
+        assert_eq!(state.file_list.selected_index, 1);
+        assert_eq!(state.status_message.as_deref(), Some("Cull: last file"));
     }
 }
@@ -1,6 +1,11 @@
+use std::collections::HashSet;
+
 use anyhow::Result;
 
-use super::{AppState, Focus};
+use super::{AppState, Focus, TreeState, VirtualFolder};
+
+/// Most directory rows expand-all opens; it stops at the last level that fits
+const EXPAND_ALL_MAX_ROWS: usize = 2000;
 
 /// Navigation direction for wrapping list movement
 #[derive(Clone, Copy)]
@@ -33,7 +38,7 @@ impl AppState {
     fn navigate(&mut self, direction: Direction) -> Result<()> {
         match self.focus {
             Focus::DirectoryTree => {
-                let count = self.get_visible_directories().len();
+                let count = self.tree_row_count();
                 if count > 0 {
                     self.tree.selected_index = wrap_index(self.tree.selected_index, count, direction);
                     self.tree.list_state.select(Some(self.tree.selected_index));
@@ -55,8 +60,7 @@ impl AppState {
     /// Select a specific index in the directory tree (for mouse clicks).
     /// No-op if index is out of bounds.
     pub fn select_tree_index(&mut self, index: usize) {
-        let visible_count = self.get_visible_directories().len();
-        if index < visible_count {
+        if index < self.tree_row_count() {
             self.tree.selected_index = index;
             self.tree.list_state.select(Some(index));
             self.files_dirty = true;
@@ -76,17 +80,16 @@ impl AppState {
     pub fn move_left(&mut self) {
         match self.focus {
             Focus::DirectoryTree => {
-                // Collapse current directory or move to parent
+                // Collapse current directory or move to parent (pinned rows have neither)
+                if self.selected_pinned_row() {
+                    return;
+                }
                 if let Some(dir) = self.get_selected_directory().cloned() {
                     if self.tree.expanded.contains(&dir.id) {
                         self.tree.expanded.remove(&dir.id);
                     } else if let Some(parent_id) = dir.parent_id {
                         // Move to parent
-                        let visible = self.get_visible_directories();
-                        if let Some(pos) = visible.iter().position(|d| d.id == parent_id) {
-                            self.tree.selected_index = pos;
-                            self.tree.list_state.select(Some(pos));
-                        }
+                        self.select_directory_in_tree(parent_id);
                     }
                 }
             }
@@ -101,13 +104,19 @@ impl AppState {
         match self.focus {
             Focus::DirectoryTree => {
                 // Expand current directory or switch to file list
-                if let Some(dir) = self.get_selected_directory().cloned() {
+                if self.selected_pinned_row() {
+                    if !self.file_list.files.is_empty() {
+                        self.focus = Focus::FileList;
+                    }
+                } else if let Some(dir) = self.get_selected_directory().cloned() {
                     let has_children = self.tree.has_visible_children(dir.id, &self.matching_dir_ids);
                     if has_children && !self.tree.expanded.contains(&dir.id) {
                         self.tree.expanded.insert(dir.id);
                     } else if !self.file_list.files.is_empty() {
                         self.focus = Focus::FileList;
                     }
+                } else if self.selected_virtual_folder().is_some() && !self.file_list.files.is_empty() {
+                    self.focus = Focus::FileList;
                 }
             }
             Focus::FileList => {
@@ -116,6 +125,63 @@ impl AppState {
         }
     }
 
+    /// Collapse the whole tree
+    pub fn collapse_all(&mut self) {
+        self.reshape_tree(|tree, _| {
+            tree.collapse_all();
+            true
+        });
+    }
+
+    /// Expand the whole tree (only matching directories while filtering),
+    /// up to `EXPAND_ALL_MAX_ROWS` rows
+    pub fn expand_all(&mut self) {
+        let complete = self.reshape_tree(|tree, matching| tree.expand_all(EXPAND_ALL_MAX_ROWS, matching));
+        if !complete {
+            self.status_message = Some(format!(
+                "Expanded as far as fits in {} rows; press e and a level to go deeper",
+                EXPAND_ALL_MAX_ROWS
+            ));
+        } else if !self.matching_dir_ids.is_empty() {
+            self.status_message = Some("Expanded all matching directories".to_string());
+        }
+    }
+
+    /// Show `depth` levels below the top of the tree
+    pub fn expand_to_depth(&mut self, depth: usize) {
+        self.reshape_tree(|tree, matching| {
+            tree.expand_to_depth(depth, matching);
+            true
+        });
+    }
+
+    /// Change which directories are expanded, keeping the selection on the
+    /// same row, or on its nearest ancestor if that got collapsed away
+    pub(super) fn reshape_tree(&mut self, reshape: impl FnOnce(&mut TreeState, &HashSet<i64>) -> bool) -> bool {
+        let pinned_row = self.selected_pinned_row();
+        let virtual_folder = self.selected_virtual_folder();
+        let selected = self.get_selected_directory().map(|d| d.id);
+
+        let result = reshape(&mut self.tree, &self.matching_dir_ids);
+
+        if pinned_row {
+            // Pinned rows don't move
+        } else if let Some(folder) = virtual_folder {
+            self.select_virtual_folder(folder);
+        } else if let Some(dir_id) = selected {
+            let visible: Vec<i64> = self.get_visible_directories().iter().map(|d| d.id).collect();
+            let position = std::iter::once(dir_id)
+                .chain(self.tree.ancestor_ids(dir_id))
+                .find_map(|id| visible.iter().position(|&v| v == id));
+            match position {
+                Some(position) => self.select_tree_index(self.pinned_directories().len() + position),
+                // Gone with no visible ancestor: stay on the same row
+                None => self.select_tree_index(self.tree.selected_index.min(self.tree_row_count() - 1)),
+            }
+        }
+        result
+    }
+
     pub fn toggle_focus(&mut self) {
         self.focus = match self.focus {
             Focus::DirectoryTree => Focus::FileList,
@@ -126,6 +192,16 @@ impl AppState {
     pub fn select(&mut self) -> Result<()> {
         match self.focus {
             Focus::DirectoryTree => {
+                // On a pinned row, jump to the directory in the tree first
+                if self.selected_pinned_row() {
+                    let dir_id = self.get_selected_directory().map(|d| d.id);
+                    if let Some(dir_id) = dir_id {
+                        if !self.select_directory_in_tree(dir_id) {
+                            self.status_message = Some("Directory is hidden by the current filter".to_string());
+                            return Ok(());
+                        }
+                    }
+                }
                 if let Some(dir) = self.get_selected_directory().cloned() {
                     let has_children = self.tree.has_visible_children(dir.id, &self.matching_dir_ids);
                     if has_children {
@@ -146,6 +222,16 @@ impl AppState {
                             self.focus = Focus::FileList;
                         }
                     }
+                } else if let Some(folder) = self.selected_virtual_folder() {
+                    self.load_files_for_selected_directory()?;
+                    if self.file_list.files.is_empty() {
+                        self.status_message = Some(format!("{}: nothing yet", self.virtual_folder_name(folder)));
+                    } else {
+                        self.focus = Focus::FileList;
+                        if let VirtualFolder::Review(id) = folder {
+                            self.status_message = self.review_resume_message(id);
+                        }
+                    }
                 }
             }
             Focus::FileList => {
@@ -162,13 +248,32 @@ mod tests {
     use super::super::test_helpers::*;
     use super::super::Focus;
 
+    #[test]
+    fn test_collapse_all_selects_nearest_visible_ancestor() {
+        let (mut state, _tempdir) = create_test_app_state();
+        let photos = state.db.get_directory_by_path("photos").unwrap().unwrap();
+        let beach = state.db.insert_directory("photos/beach", Some(photos.id), None).unwrap();
+        state.tree.directories = state.db.get_all_directories().unwrap();
+
+        state.expand_all();
+        assert!(state.select_directory_in_tree(beach));
+        assert_eq!(state.tree.selected_index, 1);
+
+        state.collapse_all();
+        assert_eq!(state.get_selected_directory().unwrap().id, photos.id);
+
+        state.expand_to_depth(1);
+        assert_eq!(state.get_selected_directory().unwrap().id, photos.id);
+        assert_eq!(state.get_visible_directories().len(), 3);
+    }
+
     #[test]
     fn test_app_state_move_down_wraps() {
         let (mut state, _tempdir) = create_test_app_state();
         state.focus = Focus::DirectoryTree;
 
-        // Move to last directory
-        state.tree.selected_index = state.get_visible_directories().len() - 1;
+        // Move to the last row (the virtual folders follow the directories)
+        state.tree.selected_index = state.tree_row_count() - 1;
 
         // Move down should wrap to top
         state.move_down().unwrap();
@@ -183,8 +288,7 @@ mod tests {
 
         // Move up should wrap to bottom
         state.move_up().unwrap();
-        let visible_count = state.get_visible_directories().len();
-        assert_eq!(state.tree.selected_index, visible_count - 1);
+        assert_eq!(state.tree.selected_index, state.tree_row_count() - 1);
     }
 
     #[test]
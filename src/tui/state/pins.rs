@@ -0,0 +1,94 @@
+use anyhow::Result;
+
+use crate::db::Directory;
+
+use super::{AppState, Focus};
+
+impl AppState {
+    /// Pinned directories, shown first in the tree regardless of expansion
+    /// or filter (but not while hidden)
+    pub fn pinned_directories(&self) -> Vec<&Directory> {
+        self.tree
+            .pinned
+            .iter()
+            .filter(|&&id| self.tree.show_hidden || !self.tree.is_hidden(id))
+            .filter_map(|id| self.tree.directories.iter().find(|d| d.id == *id))
+            .collect()
+    }
+
+    /// Whether the tree selection is on one of the pinned rows
+    pub fn selected_pinned_row(&self) -> bool {
+        self.tree.selected_index < self.pinned_directories().len()
+    }
+
+    /// Pin the selected directory, or unpin it if it already is
+    pub fn toggle_pin(&mut self) -> Result<()> {
+        if self.focus != Focus::DirectoryTree {
+            return Ok(());
+        }
+        let Some(dir) = self.get_selected_directory().cloned() else {
+            return Ok(());
+        };
+        let name = if dir.path.is_empty() { "." } else { dir.path.as_str() };
+
+        if self.tree.pinned.contains(&dir.id) {
+            self.db.unpin_directory(dir.id)?;
+            self.tree.pinned.retain(|&id| id != dir.id);
+            self.status_message = Some(format!("Unpinned {}", name));
+        } else {
+            self.db.pin_directory(dir.id, chrono::Utc::now().timestamp())?;
+            self.tree.pinned.push(dir.id);
+            self.status_message = Some(format!("Pinned {}", name));
+        }
+        // The pinned rows above changed, so find the directory again
+        self.select_directory_in_tree(dir.id);
+        Ok(())
+    }
+
+    /// Select a directory in the tree below the pinned rows, expanding its
+    /// ancestors. Returns false if the filter hides it.
+    pub fn select_directory_in_tree(&mut self, dir_id: i64) -> bool {
+        let ancestors: Vec<i64> = self.tree.ancestor_ids(dir_id).collect();
+        self.tree.expanded.extend(ancestors);
+
+        let Some(position) = self.get_visible_directories().iter().position(|d| d.id == dir_id) else {
+            return false;
+        };
+        self.select_tree_index(self.pinned_directories().len() + position);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::*;
+
+    #[test]
+    fn test_pinned_rows_come_first() {
+        let (mut state, _tempdir) = create_test_app_state();
+        let videos = state.db.get_directory_by_path("videos").unwrap().unwrap();
+
+        // Select "videos" (second root) and pin it
+        state.tree.selected_index = 1;
+        state.toggle_pin().unwrap();
+        assert_eq!(state.tree.pinned, vec![videos.id]);
+        assert_eq!(state.db.get_pinned_directory_ids().unwrap(), vec![videos.id]);
+
+        // The tree moved down a row; the selection followed "videos"
+        assert_eq!(state.tree.selected_index, 2);
+        assert_eq!(state.get_selected_directory().unwrap().path, "videos");
+        assert!(!state.selected_pinned_row());
+
+        // Row 0 is the pinned copy
+        state.tree.selected_index = 0;
+        assert!(state.selected_pinned_row());
+        assert_eq!(state.get_selected_directory().unwrap().id, videos.id);
+        assert_eq!(state.tree_row_count(), 1 + 2 + 2);
+
+        // Unpinning from the pinned row lands on the directory in the tree
+        state.toggle_pin().unwrap();
+        assert!(state.tree.pinned.is_empty());
+        assert_eq!(state.tree.selected_index, 1);
+        assert_eq!(state.get_selected_directory().unwrap().path, "videos");
+    }
+}
@@ -1,4 +1,4 @@
-use super::AppState;
+use super::{AppState, PreviewZoom};
 
 impl AppState {
     /// Poll for completed background preview loads and insert into cache.
@@ -16,9 +16,9 @@ impl AppState {
             };
 
             if let Some(image) = result.image {
-                cache.insert(result.path.clone(), image, result.protocol);
+                cache.insert(result.path.clone(), result.zoom, image, result.protocol);
             } else if let Some(protocol) = result.protocol {
-                cache.set_protocol(&result.path, protocol);
+                cache.set_protocol(&result.path, result.zoom, protocol);
             }
         }
 
@@ -39,18 +39,13 @@ impl AppState {
     /// No disk I/O happens here — extension checks are pure string ops, and
     /// thumbnail path resolution is deferred to the worker thread.
     fn preload_directory_files(&self) {
-        use crate::thumbnails::{is_image_file, is_video_file};
+        use crate::thumbnails::is_thumbnailable_file;
 
         let dir_id = match self.current_dir_id {
             Some(id) => id,
             None => return,
         };
 
-        let dir = match self.get_selected_directory() {
-            Some(d) => d.clone(),
-            None => return,
-        };
-
         let mut loader = self.preview_loader.borrow_mut();
         let cache = self.preview_cache.borrow();
         let total = self.file_list.files.len();
@@ -59,17 +54,20 @@ impl AppState {
         }
 
         let selected_idx = self.file_list.selected_index;
-        let max_pending = cache.max_size();
+        let max_pending = cache.estimated_capacity();
+        let zoom = self.preview_zoom;
 
         // If selected file isn't cached, bump generation to invalidate stale
         // preloads from a previous position — the worker skips them instantly.
-        let selected_file = &self.file_list.files[selected_idx];
-        let selected_path = dir.file_path(&self.library_path, &selected_file.file.filename);
-
-        if !cache.contains(&selected_path) && !loader.is_pending(&selected_path) {
-            loader.bump_load_generation();
+        if let Some(path) = self.selected_file_path() {
+            if !cache.contains(&path, zoom) && !loader.is_pending(&path, zoom) {
+                loader.bump_load_generation();
+            }
         }
 
+        // A virtual folder's files are spread over many directories
+        let selected_dir = self.get_selected_directory();
+
         // Queue files starting from selected index, wrapping around.
         // Selected file is always first → processed before neighbors.
         // Limit total (cached + pending) to cache size to avoid an eviction cycle
@@ -80,20 +78,76 @@ impl AppState {
                 break;
             }
 
-            let file_with_tags = &self.file_list.files[idx];
-            let file_path = dir.file_path(&self.library_path, &file_with_tags.file.filename);
+            let file = &self.file_list.files[idx].file;
+            let Some(dir) = selected_dir.or_else(|| self.file_directory(file)) else {
+                continue;
+            };
+            let file_path = dir.file_path(&self.library_path, &file.filename);
 
             // Extension-only filter — no stat() calls
-            if !is_image_file(&file_path) && !is_video_file(&file_path) {
+            if !is_thumbnailable_file(&file_path) {
+                continue;
+            }
+
+            if cache.contains(&file_path, zoom) || loader.is_pending(&file_path, zoom) {
                 continue;
             }
 
-            if cache.contains(&file_path) || loader.is_pending(&file_path) {
+            loader.queue_file_load(file_path, zoom, dir_id);
+        }
+    }
+
+    /// Queue the selected file and the one after it, selected first. Cull
+    /// mode calls this right after advancing so the next photo is already
+    /// decoding while the current one is being rated.
+    pub(super) fn preload_cull_files(&self) {
+        let Some(dir_id) = self.current_dir_id else {
+            return;
+        };
+        let selected_idx = self.file_list.selected_index;
+        let selected_dir = self.get_selected_directory();
+        let zoom = self.preview_zoom;
+
+        let mut loader = self.preview_loader.borrow_mut();
+        let cache = self.preview_cache.borrow();
+        if let Some(path) = self.selected_file_path() {
+            if !cache.contains(&path, zoom) && !loader.is_pending(&path, zoom) {
+                loader.bump_load_generation();
+            }
+        }
+        for idx in selected_idx..(selected_idx + 2).min(self.file_list.files.len()) {
+            let file = &self.file_list.files[idx].file;
+            let Some(dir) = selected_dir.or_else(|| self.file_directory(file)) else {
+                continue;
+            };
+            let file_path = dir.file_path(&self.library_path, &file.filename);
+            if !crate::thumbnails::is_thumbnailable_file(&file_path)
+                || cache.contains(&file_path, zoom)
+                || loader.is_pending(&file_path, zoom)
+            {
                 continue;
             }
+            loader.queue_file_load(file_path, zoom, dir_id);
+        }
+    }
 
-            loader.queue_file_load(file_path, dir_id);
+    /// Switch the file preview's zoom tier. The new tier is decoded on the
+    /// worker; the current image stays up until it's ready.
+    pub fn set_preview_zoom(&mut self, zoom: PreviewZoom) {
+        if zoom == self.preview_zoom {
+            return;
         }
+        self.preview_zoom = zoom;
+        // A smaller image doesn't overwrite all of the larger one's cells
+        self.force_redraw = true;
+        self.status_message = Some(match zoom.label() {
+            Some(label) => format!("Preview zoom: {}", label),
+            None => "Preview zoom: fit".to_string(),
+        });
+    }
+
+    pub fn zoom_in_preview(&mut self) {
+        self.set_preview_zoom(self.preview_zoom.zoom_in());
     }
 
     /// Update EXIF cache if details are expanded and selection changed
@@ -112,6 +166,30 @@ impl AppState {
         if needs_read {
             let info = super::super::exif::read_exif(&path);
             self.cached_exif = Some((path, info));
+            self.exif_scroll = 0;
+        }
+    }
+
+    /// Switch the expanded details panel between the EXIF summary and the
+    /// full tag dump, expanding the panel if needed
+    pub fn toggle_exif_dump(&mut self) {
+        if self.details_expanded {
+            self.exif_dump = !self.exif_dump;
+        } else {
+            self.details_expanded = true;
+            self.exif_dump = true;
+        }
+        self.exif_scroll = 0;
+        self.refresh_exif_cache();
+    }
+
+    /// Scroll the EXIF dump by `delta` lines, stopping at the last tag
+    pub fn scroll_exif_dump(&mut self, delta: i32) {
+        if !(self.details_expanded && self.exif_dump) {
+            return;
         }
+        let tags = self.cached_exif.as_ref().map_or(0, |(_, info)| info.tags.len());
+        let max = tags.saturating_sub(1) as i32;
+        self.exif_scroll = (self.exif_scroll as i32 + delta).clamp(0, max.max(0)) as u16;
     }
 }
@@ -0,0 +1,247 @@
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+use anyhow::Result;
+use tracing::warn;
+
+use crate::db::{Activity, Database};
+
+use super::{AppState, FileWithTags, Focus};
+
+/// How many files a virtual folder lists
+const RECENT_SHOWN: usize = 100;
+
+/// Virtual folders listed below the directory tree, gathering files from
+/// anywhere in the library
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtualFolder {
+    RecentlyViewed,
+    RecentlyEdited,
+    /// A review queue built with `picman review create`, by ID
+    Review(i64),
+}
+
+impl VirtualFolder {
+    /// The folders always listed; review queues follow them
+    pub const FIXED: [VirtualFolder; 2] = [VirtualFolder::RecentlyViewed, VirtualFolder::RecentlyEdited];
+
+    fn activity(self) -> Option<Activity> {
+        match self {
+            VirtualFolder::RecentlyViewed => Some(Activity::Viewed),
+            VirtualFolder::RecentlyEdited => Some(Activity::Edited),
+            VirtualFolder::Review(_) => None,
+        }
+    }
+
+    /// Stands in for a directory ID in the preview loader; real IDs are
+    /// positive and -1 means "no directory"
+    pub fn preview_dir_id(self) -> i64 {
+        match self {
+            VirtualFolder::RecentlyViewed => -2,
+            VirtualFolder::RecentlyEdited => -3,
+            VirtualFolder::Review(id) => -3 - id,
+        }
+    }
+}
+
+/// Records files as viewed on its own thread and database connection, so
+/// drawing never waits behind another process's write lock (a sync)
+pub struct ViewRecorder {
+    tx: Option<Sender<(i64, i64)>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl ViewRecorder {
+    pub fn new(db_path: PathBuf) -> Self {
+        let (tx, rx) = mpsc::channel::<(i64, i64)>();
+        let worker = thread::spawn(move || {
+            let mut db = None;
+            for (file_id, viewed_at) in rx {
+                if db.is_none() {
+                    match Database::open(&db_path) {
+                        Ok(opened) => db = Some(opened),
+                        Err(e) => {
+                            warn!(error = format!("{:#}", e), "failed to open database to record file views");
+                            return;
+                        }
+                    }
+                }
+                if let Some(db) = &db {
+                    if let Err(e) = db.record_file_activity(file_id, Activity::Viewed, viewed_at) {
+                        warn!(error = format!("{:#}", e), file_id, "failed to record file view");
+                    }
+                }
+            }
+        });
+        Self {
+            tx: Some(tx),
+            worker: Some(worker),
+        }
+    }
+
+    /// Queue a view of `file_id` at `viewed_at` (Unix seconds)
+    pub fn record(&self, file_id: i64, viewed_at: i64) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send((file_id, viewed_at));
+        }
+    }
+
+    /// Write out the queued views and stop the thread; later views are
+    /// dropped
+    pub fn finish(&mut self) {
+        self.tx = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for ViewRecorder {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+impl AppState {
+    /// Virtual folders in tree order: the fixed ones, then review queues
+    /// by name
+    pub fn virtual_folders(&self) -> Vec<VirtualFolder> {
+        VirtualFolder::FIXED
+            .into_iter()
+            .chain(self.review_queues.iter().map(|q| VirtualFolder::Review(q.id)))
+            .collect()
+    }
+
+    pub fn virtual_folder_name(&self, folder: VirtualFolder) -> String {
+        match folder {
+            VirtualFolder::RecentlyViewed => "Recently viewed".to_string(),
+            VirtualFolder::RecentlyEdited => "Recently edited".to_string(),
+            VirtualFolder::Review(id) => match self.review_queues.iter().find(|q| q.id == id) {
+                Some(queue) => format!("Review: {}", queue.name),
+                None => "Review".to_string(),
+            },
+        }
+    }
+
+    /// Rows in the directory tree: pinned directories, visible directories,
+    /// then the virtual folders
+    pub fn tree_row_count(&self) -> usize {
+        self.pinned_directories().len() + self.get_visible_directories().len() + self.virtual_folders().len()
+    }
+
+    /// The virtual folder selected in the tree, if any
+    pub fn selected_virtual_folder(&self) -> Option<VirtualFolder> {
+        let index = self
+            .tree
+            .selected_index
+            .checked_sub(self.pinned_directories().len() + self.get_visible_directories().len())?;
+        self.virtual_folders().get(index).copied()
+    }
+
+    /// Select a virtual folder, e.g. to keep it selected while the number
+    /// of visible directories above it changes
+    pub(super) fn select_virtual_folder(&mut self, folder: VirtualFolder) {
+        let position = self.virtual_folders().iter().position(|&f| f == folder).unwrap_or_default();
+        self.tree.selected_index = self.pinned_directories().len() + self.get_visible_directories().len() + position;
+        self.tree.list_state.select(Some(self.tree.selected_index));
+    }
+
+    /// Fill the file list with a virtual folder's files: recent files
+    /// newest first, a review queue in review order.
+    /// The filter doesn't apply here.
+    pub(super) fn load_virtual_folder_files(&mut self, folder: VirtualFolder) -> Result<()> {
+        let dir_id = folder.preview_dir_id();
+        if self.current_dir_id != Some(dir_id) {
+            self.file_list.marked.clear();
+            self.file_list.expanded_stacks.clear();
+        }
+        self.current_dir_id = Some(dir_id);
+        self.preview_loader.borrow_mut().set_current_dir(dir_id);
+
+        let Some(activity) = folder.activity() else {
+            if let VirtualFolder::Review(id) = folder {
+                self.load_review_queue_files(id)?;
+            }
+            return Ok(());
+        };
+        let recent = self.db.get_recent_files(activity, RECENT_SHOWN)?;
+        let ids: Vec<i64> = recent.iter().map(|(file, _)| file.id).collect();
+        let mut tags = self.db.get_file_tags_for_files(&ids)?;
+        for (file, _) in recent {
+            let tags = tags.remove(&file.id).unwrap_or_default();
+            self.file_list.files.push(FileWithTags {
+                file,
+                tags,
+                companions: Vec::new(),
+                stack_id: None,
+                stack_size: 0,
+                name_collisions: Vec::new(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Note the selected file as viewed once its preview is on screen;
+    /// moving back and forth over the same file counts once
+    pub fn record_file_view(&mut self) -> Result<()> {
+        if self.focus != Focus::FileList {
+            return Ok(());
+        }
+        let Some(file_id) = self.file_list.selected_file().map(|f| f.file.id) else {
+            return Ok(());
+        };
+        if self.viewed_file_id != Some(file_id) {
+            let previous = self.viewed_file_id.replace(file_id);
+            self.view_recorder.record(file_id, chrono::Utc::now().timestamp());
+            self.record_review_progress(previous, file_id)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn test_virtual_folder_lists_recent_files() {
+        let (mut state, _tempdir) = create_test_app_state();
+        let img = state.db.get_file_by_path("photos/img1.jpg").unwrap().unwrap();
+        let vid = state.db.get_file_by_path("videos/vid1.mp4").unwrap().unwrap();
+        state.db.record_file_activity(img.id, Activity::Viewed, 100).unwrap();
+        state.db.record_file_activity(vid.id, Activity::Viewed, 200).unwrap();
+
+        // Directly after the two root directories
+        state.tree.selected_index = 2;
+        assert_eq!(state.selected_virtual_folder(), Some(VirtualFolder::RecentlyViewed));
+        assert!(state.get_selected_directory().is_none());
+
+        state.load_files_for_selected_directory().unwrap();
+        let names: Vec<&str> = state.file_list.files.iter().map(|f| f.file.filename.as_str()).collect();
+        assert_eq!(names, vec!["vid1.mp4", "img1.jpg"]);
+        // Paths resolve through each file's own directory
+        assert!(state.selected_file_path().unwrap().ends_with("videos/vid1.mp4"));
+
+        state.tree.selected_index = 3;
+        state.load_files_for_selected_directory().unwrap();
+        assert!(state.file_list.files.is_empty());
+    }
+
+    #[test]
+    fn test_record_file_view_once_per_file() {
+        let (mut state, _tempdir) = create_test_app_state();
+        state.load_files_for_selected_directory().unwrap();
+        state.focus = Focus::FileList;
+
+        state.record_file_view().unwrap();
+        state.record_file_view().unwrap();
+        state.move_down().unwrap();
+        state.record_file_view().unwrap();
+        state.view_recorder.finish();
+
+        let viewed = state.db.get_recent_files(Activity::Viewed, 10).unwrap();
+        assert_eq!(viewed.len(), 2);
+    }
+}
@@ -1,15 +1,26 @@
 use anyhow::Result;
 
-use crate::suggestions::extract_suggested_words;
+use crate::suggestions::{extract_suggested_words_with_exif, ExifHints};
+use crate::thumbnails::is_image_file;
+use crate::tui::exif::{parse_year_month, read_exif};
+use crate::library_path::LibraryPath;
 
 use super::{AppState, Focus, RenameDialogState};
 
+/// Most images whose EXIF is read for rename suggestions; keeps the dialog
+/// responsive on large subtrees while still reflecting the common values
+const EXIF_SAMPLE_LIMIT: usize = 200;
+
 impl AppState {
     /// Open the rename dialog for the selected directory
     pub fn open_rename_dialog(&mut self) -> Result<()> {
         if self.focus != Focus::DirectoryTree {
             return Ok(());
         }
+        if self.fs.is_remote() {
+            self.status_message = Some("Renaming is not supported for remote libraries".to_string());
+            return Ok(());
+        }
 
         if let Some(dir) = self.get_selected_directory().cloned() {
             // Collect all descendant directory IDs
@@ -25,19 +36,31 @@ impl AppState {
                 }
             }
 
-            // Collect tags from files in all these directories
+            // Collect tags and EXIF hints from files in all these directories
             let mut file_tags: Vec<String> = Vec::new();
+            let mut exif_hints: Vec<ExifHints> = Vec::new();
             for did in &dir_ids {
+                let Some(d) = self.tree.directories.iter().find(|d| d.id == *did) else {
+                    continue;
+                };
                 if let Ok(files) = self.db.get_files_in_directory(*did) {
                     for f in &files {
                         if let Ok(tags) = self.db.get_file_tags(f.id) {
                             file_tags.extend(tags);
                         }
+                        let path = d.file_path(&self.library_path, &f.filename);
+                        if exif_hints.len() < EXIF_SAMPLE_LIMIT && is_image_file(&path) {
+                            let exif = read_exif(&path);
+                            exif_hints.push(ExifHints {
+                                camera_model: exif.camera_model,
+                                captured: exif.date_taken.as_deref().and_then(parse_year_month),
+                            });
+                        }
                     }
                 }
             }
 
-            let suggested_words = extract_suggested_words(&all_paths, &file_tags);
+            let suggested_words = extract_suggested_words_with_exif(&all_paths, &file_tags, &exif_hints);
             self.rename_dialog = Some(RenameDialogState::new(dir.id, dir.path.clone(), suggested_words));
         }
         Ok(())
@@ -63,11 +86,11 @@ impl AppState {
         };
 
         // Build new path: replace the last component
-        let new_path = if let Some(parent) = old_path.rsplit_once('/') {
-            format!("{}/{}", parent.0, new_name)
-        } else {
-            new_name.clone()
-        };
+        let new_path = LibraryPath::new(&old_path)
+            .parent()
+            .unwrap_or_default()
+            .join(&new_name)
+            .into_string();
 
         // Don't rename if nothing changed
         if old_path == new_path {
@@ -98,6 +121,7 @@ impl AppState {
                 dir.path = dir.path.replacen(&old_path, &new_path, 1);
             }
         }
+        self.sort_tree()?;
 
         self.status_message = Some(format!("Renamed to '{}'", new_name));
         self.rename_dialog = None;
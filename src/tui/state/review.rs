@@ -0,0 +1,145 @@
+use anyhow::Result;
+
+use crate::db::ReviewDecision;
+
+use super::{AppState, FileWithTags, VirtualFolder};
+
+impl AppState {
+    /// Reload the review queues listed in the tree, sorted by name so
+    /// reviewing one doesn't move the rows
+    pub(super) fn reload_review_queues(&mut self) -> Result<()> {
+        let mut queues = self.db.get_review_queues()?;
+        queues.sort_by_key(|q| q.name.to_lowercase());
+        self.review_queues = queues;
+        Ok(())
+    }
+
+    /// The review queue listed in the file list, if any
+    fn listed_review_queue(&self) -> Option<i64> {
+        match self.selected_virtual_folder()? {
+            VirtualFolder::Review(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Fill the file list with a queue's files in review order, selecting
+    /// the file the review got to
+    pub(super) fn load_review_queue_files(&mut self, id: i64) -> Result<()> {
+        self.reload_review_queues()?;
+        for entry in self.db.get_review_queue_files(id)? {
+            let tags = self.db.get_file_tags(entry.file.id)?;
+            self.file_list.files.push(FileWithTags {
+                file: entry.file,
+                tags,
+                companions: Vec::new(),
+                stack_id: None,
+                stack_size: 0,
+                name_collisions: Vec::new(),
+            });
+        }
+        let position = self.review_queues.iter().find(|q| q.id == id).map(|q| q.position);
+        if let Some(position) = position.filter(|_| !self.file_list.files.is_empty()) {
+            let index = position.min(self.file_list.files.len() - 1);
+            self.file_list.selected_index = index;
+            self.file_list.table_state.select(Some(index));
+        }
+        Ok(())
+    }
+
+    /// `Review 2024: 120 of 400 reviewed, at 121`
+    pub(super) fn review_resume_message(&self, id: i64) -> Option<String> {
+        let queue = self.review_queues.iter().find(|q| q.id == id)?;
+        Some(if queue.is_finished() {
+            format!("Review {}: all {} reviewed", queue.name, queue.total)
+        } else {
+            format!(
+                "Review {}: {} of {} reviewed, at {}",
+                queue.name,
+                queue.decided,
+                queue.total,
+                (queue.position + 1).min(queue.total)
+            )
+        })
+    }
+
+    /// Save how far a review got as the selection moves from `previous` to
+    /// `current`; moving on from a file without rating it skips it
+    pub(super) fn record_review_progress(&mut self, previous: Option<i64>, current: i64) -> Result<()> {
+        let Some(id) = self.listed_review_queue() else {
+            return Ok(());
+        };
+        let now = chrono::Utc::now().timestamp();
+        let index = |file_id: i64| self.file_list.files.iter().position(|f| f.file.id == file_id);
+        if let (Some(previous), Some(current_index)) = (previous, index(current)) {
+            if index(previous).is_some_and(|i| i < current_index) {
+                self.db.record_review_decision(id, previous, ReviewDecision::Skipped, now)?;
+            }
+        }
+        self.db.set_review_position(id, current, now)?;
+        self.reload_review_queues()
+    }
+
+    /// Note a file rated while reviewing a queue
+    pub(super) fn record_review_rating(&mut self, file_id: i64) -> Result<()> {
+        let Some(id) = self.listed_review_queue() else {
+            return Ok(());
+        };
+        self.db
+            .record_review_decision(id, file_id, ReviewDecision::Rated, chrono::Utc::now().timestamp())?;
+        self.reload_review_queues()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::*;
+    use super::super::Focus;
+    use super::*;
+
+    #[test]
+    fn test_review_queue_resumes_where_it_stopped() {
+        let (mut state, _tempdir) = create_test_app_state();
+        let ids: Vec<i64> = ["photos/img1.jpg", "photos/img2.jpg", "videos/vid1.mp4"]
+            .iter()
+            .map(|path| state.db.get_file_by_path(path).unwrap().unwrap().id)
+            .collect();
+        let queue = state.db.create_review_queue("June", "all files", &ids, 100).unwrap();
+        state.reload_review_queues().unwrap();
+
+        // After the two root directories and the two recent folders
+        state.tree.selected_index = 4;
+        assert_eq!(state.selected_virtual_folder(), Some(VirtualFolder::Review(queue)));
+        assert_eq!(state.virtual_folder_name(VirtualFolder::Review(queue)), "Review: June");
+        state.load_files_for_selected_directory().unwrap();
+        assert_eq!(state.file_list.files.len(), 3);
+        state.focus = Focus::FileList;
+
+        // Rate the first, pass over the second
+        state.record_file_view().unwrap();
+        state.rate_selected(Some(4.0)).unwrap();
+        state.move_down().unwrap();
+        state.record_file_view().unwrap();
+        state.move_down().unwrap();
+        state.record_file_view().unwrap();
+
+        let saved = state.db.get_review_queue(queue).unwrap().unwrap();
+        assert_eq!((saved.position, saved.decided), (2, 2));
+        let decisions: Vec<_> = state
+            .db
+            .get_review_queue_files(queue)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.decision)
+            .collect();
+        assert_eq!(decisions, [Some(ReviewDecision::Rated), Some(ReviewDecision::Skipped), None]);
+
+        // Coming back to the queue picks up at the third file
+        state.focus = Focus::DirectoryTree;
+        state.tree.selected_index = 0;
+        state.load_files_for_selected_directory().unwrap();
+        state.tree.selected_index = 4;
+        state.load_files_for_selected_directory().unwrap();
+        assert_eq!(state.file_list.selected_index, 2);
+        assert_eq!(state.review_resume_message(queue).unwrap(), "Review June: 2 of 3 reviewed, at 3");
+    }
+}
@@ -0,0 +1,259 @@
+//! Read-only copy of the rows the TUI draws from, so moving around,
+//! filtering and rendering never query SQLite: tags, totals and thumbnail
+//! checks of every directory, plus the files (with their tags, pairs,
+//! stacks and name collisions) of the top-level directory the selection
+//! is in.
+//!
+//! It is reloaded rather than patched when the database changes under it:
+//! `reload_directories` (after sync and other operations) refreshes all of
+//! it, `reload_files_selecting` (after bulk tag, rating and stack writes)
+//! the files, and moving into another top-level directory loads that one.
+//! Ratings and tags set in the TUI, or another process's changes found by
+//! `poll_metadata_changes`, are written through.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::db::{Database, Directory, DirectoryTotals, File};
+
+use super::AppState;
+
+#[derive(Default)]
+pub struct Snapshot {
+    /// Each directory's own tags
+    directory_tags: HashMap<i64, Vec<String>>,
+    /// File count, size and newest file of each directory and everything below it
+    totals: HashMap<i64, DirectoryTotals>,
+    /// Directories found to have all their thumbnails, with their mtime then
+    thumbnail_presence: RefCell<HashMap<i64, Option<i64>>>,
+    /// Presence found while drawing, saved after the frame
+    unsaved_presence: RefCell<Vec<(i64, Option<i64>)>>,
+    /// ID and path of the top-level directory whose files are loaded
+    /// (None: load on next use)
+    branch: Option<(i64, String)>,
+    /// Directory ID -> its files, by name
+    files: HashMap<i64, Vec<File>>,
+    file_tags: HashMap<i64, Vec<String>>,
+    /// Companion file ID -> primary file ID
+    pairs: HashMap<i64, i64>,
+    /// File ID -> stack ID
+    stacks: HashMap<i64, i64>,
+    name_collisions: HashMap<i64, Vec<String>>,
+}
+
+/// What the file list shows for one directory, borrowed from the snapshot
+pub struct DirectoryListing<'a> {
+    pub files: &'a [File],
+    pub file_tags: &'a HashMap<i64, Vec<String>>,
+    pub pairs: &'a HashMap<i64, i64>,
+    pub stacks: &'a HashMap<i64, i64>,
+    pub name_collisions: &'a HashMap<i64, Vec<String>>,
+}
+
+impl Snapshot {
+    /// Load the directory-wide parts; files are loaded per branch on use
+    pub fn load(db: &Database) -> Result<Self> {
+        let mut snapshot = Self::default();
+        snapshot.refresh_directories(db)?;
+        Ok(snapshot)
+    }
+
+    /// Reread the tags, totals and thumbnail checks of every directory, and
+    /// drop the loaded files
+    pub fn refresh_directories(&mut self, db: &Database) -> Result<()> {
+        self.directory_tags = db.get_all_directory_tags()?;
+        self.totals = db.get_directory_totals()?;
+        *self.thumbnail_presence.borrow_mut() = db.get_all_thumbnail_presence()?;
+        self.invalidate_files();
+        Ok(())
+    }
+
+    /// Drop the loaded files, so they're read again on next use
+    pub fn invalidate_files(&mut self) {
+        self.branch = None;
+        self.files.clear();
+        self.file_tags.clear();
+        self.pairs.clear();
+        self.stacks.clear();
+        self.name_collisions.clear();
+    }
+
+    /// Load the files of `root` and everything below it, unless they're
+    /// what is loaded already. `root` is a top-level directory.
+    pub fn load_branch(&mut self, db: &Database, root: &Directory) -> Result<()> {
+        if self.branch.as_ref().is_some_and(|(id, _)| *id == root.id) {
+            return Ok(());
+        }
+        self.invalidate_files();
+        for file in db.get_files_in_branch(&root.path)? {
+            self.files.entry(file.directory_id).or_default().push(file);
+        }
+        self.file_tags = db.get_file_tags_in_branch(&root.path)?;
+        self.pairs = db.get_branch_pairs(&root.path)?;
+        self.stacks = db.get_branch_stacks(&root.path)?;
+        self.name_collisions = db.get_branch_name_collisions(&root.path)?;
+        self.branch = Some((root.id, root.path.clone()));
+        Ok(())
+    }
+
+    /// The files of a directory in the loaded branch (none for any other)
+    pub fn listing(&self, dir_id: i64) -> DirectoryListing<'_> {
+        DirectoryListing {
+            files: self.files.get(&dir_id).map(Vec::as_slice).unwrap_or_default(),
+            file_tags: &self.file_tags,
+            pairs: &self.pairs,
+            stacks: &self.stacks,
+            name_collisions: &self.name_collisions,
+        }
+    }
+
+    /// Whether the directory's files are loaded
+    pub fn has_files_of(&self, dir: &Directory) -> bool {
+        match &self.branch {
+            Some((_, root)) if root.is_empty() => dir.path.is_empty(),
+            Some((_, root)) => dir.path == *root || dir.path.starts_with(&format!("{}/", root)),
+            None => false,
+        }
+    }
+
+    pub fn directory_tags(&self, dir_id: i64) -> &[String] {
+        self.directory_tags.get(&dir_id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn totals(&self, dir_id: i64) -> DirectoryTotals {
+        self.totals.get(&dir_id).copied().unwrap_or_default()
+    }
+
+    /// Whether the directory had all its thumbnails when last checked at this `mtime`
+    pub fn has_thumbnail_presence(&self, dir_id: i64, mtime: Option<i64>) -> bool {
+        self.thumbnail_presence.borrow().get(&dir_id) == Some(&mtime)
+    }
+
+    /// Remember a directory found to have all its thumbnails; saved to the
+    /// database by [`AppState::save_thumbnail_presence`]
+    pub fn record_thumbnail_presence(&self, dir_id: i64, mtime: Option<i64>) {
+        self.thumbnail_presence.borrow_mut().insert(dir_id, mtime);
+        self.unsaved_presence.borrow_mut().push((dir_id, mtime));
+    }
+
+    /// Whether the file is in the loaded branch
+    pub fn has_file(&self, file_id: i64) -> bool {
+        self.files.values().flatten().any(|f| f.id == file_id)
+    }
+
+    /// Record a file's new rating and tags, e.g. changed by another process
+    pub fn update_file(&mut self, file_id: i64, rating: Option<f64>, tags: Vec<String>) {
+        self.set_file_rating(file_id, rating);
+        self.file_tags.insert(file_id, tags);
+    }
+
+    /// Record a rating set on a file, which the database gives its paired
+    /// siblings too
+    pub fn set_file_rating(&mut self, file_id: i64, rating: Option<f64>) {
+        let group = self.pair_group(file_id);
+        for file in self.files.values_mut().flatten().filter(|f| group.contains(&f.id)) {
+            file.rating = rating;
+        }
+    }
+
+    /// Record a tag added to (or removed from) a file and its paired siblings
+    pub fn set_file_tag(&mut self, file_id: i64, tag: &str, applied: bool) {
+        for id in self.pair_group(file_id) {
+            let tags = self.file_tags.entry(id).or_default();
+            tags.retain(|t| t != tag);
+            if applied {
+                tags.push(tag.to_string());
+                tags.sort();
+            }
+        }
+    }
+
+    pub fn set_directory_tags(&mut self, dir_id: i64, tags: Vec<String>) {
+        self.directory_tags.insert(dir_id, tags);
+    }
+
+    /// The file, its primary and the primary's other companions
+    fn pair_group(&self, file_id: i64) -> Vec<i64> {
+        let primary = self.pairs.get(&file_id).copied().unwrap_or(file_id);
+        std::iter::once(primary)
+            .chain(self.pairs.iter().filter(|(_, p)| **p == primary).map(|(id, _)| *id))
+            .collect()
+    }
+}
+
+impl AppState {
+    /// Save the thumbnail checks the details panel made while drawing
+    pub fn save_thumbnail_presence(&self) -> Result<()> {
+        let unsaved = std::mem::take(&mut *self.snapshot.unsaved_presence.borrow_mut());
+        for (dir_id, mtime) in unsaved {
+            self.db.set_thumbnail_presence(dir_id, mtime)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::create_test_app_state;
+    use super::super::Focus;
+
+    #[test]
+    fn test_moving_around_reads_the_snapshot() {
+        let (mut state, _temp) = create_test_app_state();
+        let photos = state.db.get_directory_by_path("photos").unwrap().unwrap();
+        state.select_directory_in_tree(photos.id);
+        assert_eq!(state.file_list.files.len(), 2);
+        let img1 = state.file_list.files[0].file.id;
+
+        // A write the TUI didn't make isn't seen until the snapshot is refreshed
+        state.db.add_directory_tag(photos.id, "trip").unwrap();
+        state.db.set_file_rating(img1, Some(3.0)).unwrap();
+        state.load_files_for_selected_directory().unwrap();
+        assert_eq!(state.file_list.files[0].file.rating, None);
+        assert!(state.snapshot.directory_tags(photos.id).is_empty());
+
+        state.reload_directories().unwrap();
+        state.load_files_for_selected_directory().unwrap();
+        assert_eq!(state.file_list.files[0].file.rating, Some(3.0));
+        assert_eq!(state.snapshot.directory_tags(photos.id), ["trip"]);
+        assert_eq!(state.snapshot.totals(photos.id).files, 2);
+    }
+
+    #[test]
+    fn test_tui_writes_go_through_to_the_snapshot() {
+        let (mut state, _temp) = create_test_app_state();
+        let photos = state.db.get_directory_by_path("photos").unwrap().unwrap();
+        state.select_directory_in_tree(photos.id);
+        state.focus = Focus::FileList;
+        let img1 = state.file_list.files[0].file.id;
+
+        state.set_rating(Some(4.0)).unwrap();
+        state.open_tag_input().unwrap();
+        if let Some(ref mut input) = state.tag_input {
+            input.input = "keeper".to_string();
+            input.update_filter();
+        }
+        state.toggle_tag().unwrap();
+
+        // Leaving the directory and coming back rebuilds the list from the snapshot
+        state.load_files_for_selected_directory().unwrap();
+        assert_eq!(state.file_list.files[0].file.id, img1);
+        assert_eq!(state.file_list.files[0].file.rating, Some(4.0));
+        assert_eq!(state.file_list.files[0].tags, ["keeper"]);
+    }
+
+    #[test]
+    fn test_thumbnail_presence_is_saved_after_drawing() {
+        let (state, _temp) = create_test_app_state();
+        let photos = state.db.get_directory_by_path("photos").unwrap().unwrap();
+
+        state.snapshot.record_thumbnail_presence(photos.id, photos.mtime);
+        assert!(state.snapshot.has_thumbnail_presence(photos.id, photos.mtime));
+        assert!(!state.db.has_thumbnail_presence(photos.id, photos.mtime).unwrap());
+
+        state.save_thumbnail_presence().unwrap();
+        assert!(state.db.has_thumbnail_presence(photos.id, photos.mtime).unwrap());
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+
+use super::{AppState, Focus};
+
+impl AppState {
+    /// Mark or unmark the selected file for stacking, then move down
+    pub fn toggle_mark(&mut self) -> Result<()> {
+        if self.focus != Focus::FileList {
+            return Ok(());
+        }
+        let Some(file_id) = self.file_list.selected_file().map(|f| f.file.id) else {
+            return Ok(());
+        };
+        if !self.file_list.marked.remove(&file_id) {
+            self.file_list.marked.insert(file_id);
+        }
+        self.move_down()
+    }
+
+    /// Stack the marked files; with nothing marked, unstack the selected stack
+    pub fn stack_marked_files(&mut self) -> Result<()> {
+        if self.focus != Focus::FileList {
+            return Ok(());
+        }
+        if self.selected_virtual_folder().is_some() {
+            self.status_message = Some("Stacks can only be made within a directory".to_string());
+            return Ok(());
+        }
+        let selected_id = self.file_list.selected_file().map(|f| f.file.id);
+
+        if self.file_list.marked.is_empty() {
+            let Some(stack_id) = self.file_list.selected_file().and_then(|f| f.stack_id) else {
+                self.status_message = Some("Mark files with Space, then press S to stack them".to_string());
+                return Ok(());
+            };
+            self.db.dissolve_stack(stack_id)?;
+            self.file_list.expanded_stacks.remove(&stack_id);
+            self.status_message = Some("Stack removed".to_string());
+        } else {
+            // Collapsed rows stand for their whole stack
+            let mut file_ids: Vec<i64> = self.file_list.marked.drain().collect();
+            let marked_stacks: Vec<i64> = self
+                .file_list
+                .files
+                .iter()
+                .filter(|f| f.stack_size > 1 && file_ids.contains(&f.file.id))
+                .filter_map(|f| f.stack_id)
+                .collect();
+            if !marked_stacks.is_empty() {
+                let stacks = self.db.get_directory_stacks(self.current_dir_id.unwrap_or_default())?;
+                for (file_id, stack_id) in stacks {
+                    if marked_stacks.contains(&stack_id) && !file_ids.contains(&file_id) {
+                        file_ids.push(file_id);
+                    }
+                }
+            }
+
+            if file_ids.len() < 2 {
+                self.status_message = Some("A stack needs at least two files".to_string());
+                return Ok(());
+            }
+            self.db.create_stack(&file_ids)?;
+            self.status_message = Some(format!("Stacked {} files", file_ids.len()));
+        }
+
+        self.reload_files_selecting(selected_id)
+    }
+
+    /// Expand or collapse the stack under the cursor
+    pub fn toggle_stack_expanded(&mut self) -> Result<()> {
+        if self.focus != Focus::FileList {
+            return Ok(());
+        }
+        let Some(file) = self.file_list.selected_file() else {
+            return Ok(());
+        };
+        let Some(stack_id) = file.stack_id else {
+            return Ok(());
+        };
+        let selected_id = file.file.id;
+
+        if !self.file_list.expanded_stacks.remove(&stack_id) {
+            self.file_list.expanded_stacks.insert(stack_id);
+        }
+        self.relist_files_selecting(Some(selected_id))
+    }
+
+    /// Reread the files from the database after a write, keeping the cursor
+    /// on the given file (or the row that now stands for it) when it is
+    /// still visible
+    pub(crate) fn reload_files_selecting(&mut self, file_id: Option<i64>) -> Result<()> {
+        self.snapshot.invalidate_files();
+        self.relist_files_selecting(file_id)
+    }
+
+    /// Rebuild the file list from the snapshot, keeping the cursor like
+    /// [`AppState::reload_files_selecting`]
+    fn relist_files_selecting(&mut self, file_id: Option<i64>) -> Result<()> {
+        let stack_id = file_id.and_then(|id| {
+            self.file_list.files.iter().find(|f| f.file.id == id).and_then(|f| f.stack_id)
+        });
+        self.load_files_for_selected_directory()?;
+
+        let index = self
+            .file_list
+            .files
+            .iter()
+            .position(|f| Some(f.file.id) == file_id)
+            .or_else(|| {
+                let stack_id = stack_id?;
+                self.file_list.files.iter().position(|f| f.stack_id == Some(stack_id))
+            });
+        if let Some(index) = index {
+            self.file_list.selected_index = index;
+            self.file_list.table_state.select(Some(index));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::create_test_app_state;
+    use super::*;
+
+    #[test]
+    fn test_stack_collapse_and_expand() {
+        let (mut state, _temp) = create_test_app_state();
+        state.focus = Focus::FileList;
+        state.load_files_for_selected_directory().unwrap();
+        assert_eq!(state.file_list.files.len(), 2);
+
+        // Mark both photos and stack them into one row
+        state.toggle_mark().unwrap();
+        state.toggle_mark().unwrap();
+        state.stack_marked_files().unwrap();
+        assert!(state.file_list.marked.is_empty());
+        assert_eq!(state.file_list.files.len(), 1);
+        assert_eq!(state.file_list.files[0].file.filename, "img1.jpg");
+        assert_eq!(state.file_list.files[0].stack_size, 2);
+
+        state.toggle_stack_expanded().unwrap();
+        assert_eq!(state.file_list.files.len(), 2);
+        assert!(state.file_list.files.iter().all(|f| f.stack_id.is_some() && f.stack_size == 0));
+
+        // S with nothing marked dissolves the stack under the cursor
+        state.stack_marked_files().unwrap();
+        assert_eq!(state.file_list.files.len(), 2);
+        assert!(state.file_list.files.iter().all(|f| f.stack_id.is_none()));
+    }
+}
@@ -1,7 +1,20 @@
 use anyhow::Result;
 
+use crate::db::Activity;
+
 use super::{AppState, Focus, TagInputState};
 
+/// Recent tags offered in the tag popup, one per number key
+const RECENT_TAG_KEYS: usize = 9;
+
+/// Whose recent tags to show. Libraries on a shared drive are tagged by
+/// several people, each with their own habits.
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
 impl AppState {
     /// Open the tag input popup with current item's tags for toggle display
     pub fn open_tag_input(&mut self) -> Result<()> {
@@ -21,7 +34,10 @@ impl AppState {
                 }
             }
         };
-        self.tag_input = Some(TagInputState::new_with_current(all_tags, current_tags));
+        let mut input = TagInputState::new_with_current(all_tags, current_tags);
+        input.recent_tags = self.db.get_recent_tags(&current_user(), RECENT_TAG_KEYS)?;
+        input.aliases = self.db.get_tag_aliases()?;
+        self.tag_input = Some(input);
         Ok(())
     }
 
@@ -48,7 +64,24 @@ impl AppState {
             self.force_redraw = true;
             return Ok(());
         }
+        self.toggle_named_tag(tag)
+    }
+
+    /// Toggle the recent tag on number key `key` (1-based)
+    pub fn toggle_recent_tag(&mut self, key: usize) -> Result<()> {
+        let tag = self
+            .tag_input
+            .as_ref()
+            .and_then(|input| input.recent_tags.get(key.checked_sub(1)?).cloned());
+        match tag {
+            Some(tag) => self.toggle_named_tag(tag),
+            None => Ok(()),
+        }
+    }
 
+    fn toggle_named_tag(&mut self, tag: String) -> Result<()> {
+        // Typed aliases apply, and show up as, their canonical tag
+        let tag = self.db.canonical_tag(&tag)?;
         let is_applied = self
             .tag_input
             .as_ref()
@@ -63,6 +96,7 @@ impl AppState {
                     } else {
                         self.db.add_directory_tag(dir_id, &tag)?;
                     }
+                    self.snapshot.set_directory_tags(dir_id, self.db.get_directory_tags(dir_id)?);
                 }
             }
             Focus::FileList => {
@@ -79,10 +113,16 @@ impl AppState {
                             file_with_tags.tags.sort();
                         }
                     }
+                    self.db.record_file_activity(file_with_tags.file.id, Activity::Edited, chrono::Utc::now().timestamp())?;
+                    self.snapshot.set_file_tag(file_with_tags.file.id, &tag, !is_applied);
                 }
             }
         }
 
+        if !is_applied {
+            self.db.record_tag_use(&current_user(), &tag, chrono::Utc::now().timestamp())?;
+        }
+
         // Update popup state to reflect the toggle
         if let Some(ref mut input) = self.tag_input {
             input.apply_toggle(&tag, is_applied);
@@ -246,4 +286,35 @@ mod tests {
         // The tag at that index should still exist in the list
         assert_eq!(tag_input.filtered_tags[1], tag_at_1);
     }
+
+    #[test]
+    fn test_recent_tags_toggle_by_number() {
+        let (mut state, _tempdir) = create_test_app_state();
+        state.focus = Focus::FileList;
+        let file_id = state.file_list.files[0].file.id;
+
+        // Applying tags makes them recent on the next open
+        for tag in ["beach", "family"] {
+            state.open_tag_input().unwrap();
+            if let Some(ref mut input) = state.tag_input {
+                input.input = tag.to_string();
+                input.update_filter();
+            }
+            state.toggle_tag().unwrap();
+        }
+        // Both were applied within the same second; make "family" the newest
+        state.db.record_tag_use(&super::current_user(), "family", i64::MAX).unwrap();
+        state.open_tag_input().unwrap();
+        assert_eq!(state.tag_input.as_ref().unwrap().recent_tags, vec!["family", "beach"]);
+
+        // 2 removes "beach", pressing it again puts it back
+        state.toggle_recent_tag(2).unwrap();
+        assert_eq!(state.db.get_file_tags(file_id).unwrap(), vec!["family"]);
+        state.toggle_recent_tag(2).unwrap();
+        assert!(state.tag_input.as_ref().unwrap().is_applied("beach"));
+
+        // Keys without a recent tag do nothing
+        state.toggle_recent_tag(9).unwrap();
+        assert_eq!(state.file_list.files[0].tags, vec!["beach", "family"]);
+    }
 }
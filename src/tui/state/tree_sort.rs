@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::config::set_config_value;
+use crate::db::DirectoryTotals;
+
+use super::AppState;
+
+impl AppState {
+    /// Switch to the next tree sort mode and save it in the library config
+    pub fn cycle_tree_sort(&mut self) -> Result<()> {
+        self.tree.sort = self.tree.sort.next();
+        self.sort_tree()?;
+
+        let name = self.tree.sort.name();
+        let saved = set_config_value(&self.library_path, "tui", "tree_sort", toml::Value::String(name.to_string()));
+        self.status_message = Some(match saved {
+            Ok(()) => format!("Tree sorted by {}", name),
+            Err(e) => format!("Tree sorted by {} (not saved: {:#})", name, e),
+        });
+        Ok(())
+    }
+
+    /// Re-apply the sort mode, e.g. after directories were renamed,
+    /// keeping the selection on the same directory
+    pub fn sort_tree(&mut self) -> Result<()> {
+        let totals = self.tree_sort_totals()?;
+        self.reshape_tree(|tree, _| {
+            tree.sort_directories(&totals);
+            true
+        });
+        Ok(())
+    }
+
+    /// Reload the directories from the database in the current sort order
+    pub fn reload_directories(&mut self) -> Result<()> {
+        let directories = self.db.get_all_directories()?;
+        self.snapshot.refresh_directories(&self.db)?;
+        let totals = self.tree_sort_totals()?;
+        self.reshape_tree(|tree, _| {
+            tree.directories = directories;
+            tree.sort_directories(&totals);
+            true
+        });
+        Ok(())
+    }
+
+    /// Aggregate stats, if the sort mode needs them
+    fn tree_sort_totals(&self) -> Result<HashMap<i64, DirectoryTotals>> {
+        if self.tree.sort.needs_totals() {
+            self.db.get_directory_totals()
+        } else {
+            Ok(HashMap::new())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::*;
+    use super::super::TreeSort;
+    use crate::config::Config;
+
+    #[test]
+    fn test_cycle_tree_sort_keeps_selection_and_saves() {
+        let (mut state, _tempdir) = create_test_app_state();
+        // photos holds two files, videos one
+        state.tree.selected_index = 1;
+        assert_eq!(state.get_selected_directory().unwrap().path, "videos");
+
+        state.cycle_tree_sort().unwrap();
+        state.cycle_tree_sort().unwrap();
+        state.cycle_tree_sort().unwrap();
+        assert_eq!(state.tree.sort, TreeSort::Size);
+        assert_eq!(state.get_visible_directories()[0].path, "photos");
+        assert_eq!(state.get_selected_directory().unwrap().path, "videos");
+        assert_eq!(state.tree.selected_index, 1);
+
+        let config = Config::load(&state.library_path).unwrap();
+        assert_eq!(config.tui.tree_sort, TreeSort::Size);
+    }
+}
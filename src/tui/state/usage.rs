@@ -0,0 +1,122 @@
+use anyhow::Result;
+
+use super::{AppState, Focus, UsageViewState};
+use crate::library_path::LibraryPath;
+
+/// Largest entries listed per directory in the usage view
+const USAGE_VIEW_LIMIT: usize = 100;
+
+impl AppState {
+    /// Open the disk usage view for the selected directory
+    pub fn open_usage_view(&mut self) -> Result<()> {
+        let path = self.get_selected_directory().map(|d| d.path.clone()).unwrap_or_default();
+        self.show_usage(&path, None)
+    }
+
+    /// Close the disk usage view
+    pub fn close_usage_view(&mut self) {
+        self.usage_view = None;
+        self.force_redraw = true;
+    }
+
+    /// Break down the selected entry, if it is a directory
+    pub fn usage_view_open_selected(&mut self) -> Result<()> {
+        let Some(path) = self
+            .usage_view
+            .as_ref()
+            .and_then(|view| view.selected_entry())
+            .filter(|entry| entry.is_dir)
+            .map(|entry| entry.path.clone())
+        else {
+            return Ok(());
+        };
+        self.show_usage(&path, None)
+    }
+
+    /// Go up to the parent directory, keeping the current one selected
+    pub fn usage_view_parent(&mut self) -> Result<()> {
+        let Some(path) = self.usage_view.as_ref().map(|view| view.node.path.clone()) else {
+            return Ok(());
+        };
+        let Some(parent) = LibraryPath::new(&path).parent() else {
+            return Ok(());
+        };
+        self.show_usage(parent.as_str(), Some(&path))
+    }
+
+    /// Close the view and select the chosen entry in the tree (or file list)
+    pub fn usage_view_go_to_selected(&mut self) -> Result<()> {
+        let Some(entry) = self.usage_view.as_ref().and_then(|view| view.selected_entry()).cloned() else {
+            return Ok(());
+        };
+        self.close_usage_view();
+
+        let dir_path = if entry.is_dir {
+            entry.path.as_str()
+        } else {
+            entry.path.rsplit_once('/').map_or("", |(parent, _)| parent)
+        };
+        let Some(dir_id) = self.tree.directories.iter().find(|d| d.path == dir_path).map(|d| d.id) else {
+            return Ok(());
+        };
+        if !self.select_directory_in_tree(dir_id) {
+            self.status_message = Some("Directory is hidden by the current filter".to_string());
+            return Ok(());
+        }
+        self.focus = Focus::DirectoryTree;
+        self.load_files_if_dirty()?;
+
+        if let (false, Some(file_id)) = (entry.is_dir, entry.id) {
+            if let Some(index) = self.file_list.files.iter().position(|f| f.file.id == file_id) {
+                self.focus = Focus::FileList;
+                self.select_file_index(index);
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the breakdown of `path`, selecting the entry at `select` if given
+    fn show_usage(&mut self, path: &str, select: Option<&str>) -> Result<()> {
+        let Some(node) = self.db.get_usage_tree(path, 1, USAGE_VIEW_LIMIT)? else {
+            return Ok(());
+        };
+        let selected = select
+            .and_then(|select| node.children.iter().position(|c| c.path == select))
+            .unwrap_or(0);
+        self.usage_view = Some(UsageViewState { node, selected });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::create_test_app_state;
+    use super::*;
+
+    #[test]
+    fn test_usage_view_navigation() {
+        let (mut state, _temp) = create_test_app_state();
+        state.show_usage("", None).unwrap();
+
+        let view = state.usage_view.as_ref().unwrap();
+        assert_eq!(view.node.size, 12);
+        assert_eq!(view.selected_entry().unwrap().path, "photos");
+
+        state.usage_view_open_selected().unwrap();
+        assert_eq!(state.usage_view.as_ref().unwrap().node.path, "photos");
+        state.usage_view.as_mut().unwrap().move_down();
+        assert_eq!(state.usage_view.as_ref().unwrap().selected_entry().unwrap().name, "img2.jpg");
+
+        // Going to a file selects it in the file list
+        state.usage_view_go_to_selected().unwrap();
+        assert!(state.usage_view.is_none());
+        assert_eq!(state.focus, Focus::FileList);
+        assert_eq!(state.file_list.selected_file().unwrap().file.filename, "img2.jpg");
+
+        state.show_usage("photos", None).unwrap();
+        state.usage_view_parent().unwrap();
+        let view = state.usage_view.as_ref().unwrap();
+        assert_eq!(view.node.path, "");
+        assert_eq!(view.selected_entry().unwrap().path, "photos");
+    }
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Local};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     prelude::*,
@@ -5,20 +6,22 @@ use ratatui::{
 };
 
 use super::colors::{FOCUS_COLOR, HEADER_COLOR, HELP_TEXT};
-use super::state::{AppState, Focus};
+use super::state::{AppState, Focus, OperationType, OperationsTab};
 use super::widgets::{
-    render_details_panel, render_directory_tree, render_file_list, render_filter_dialog,
-    render_preview, render_rename_dialog, render_status_bar, render_tag_popup,
+    render_breadcrumb, render_dashboard, render_details_panel, render_directory_tree, render_error_popup,
+    render_file_list, render_filter_dialog, render_goto_prompt, render_preview,
+    render_rename_dialog, render_status_bar, render_tag_popup, render_usage_view,
 };
 
 /// Main render function
 pub fn render(frame: &mut Frame, state: &mut AppState) {
     let size = frame.area();
 
-    // Main layout: status bar at bottom, content above
+    // Main layout: status bar at bottom (taller while the task list is
+    // expanded), content above
     let main_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .constraints([Constraint::Min(0), Constraint::Length(state.tasks.status_height())])
         .split(size);
 
     let content_area = main_chunks[0];
@@ -58,7 +61,14 @@ pub fn render(frame: &mut Frame, state: &mut AppState) {
         .split(tree_files_area);
 
     let tree_area = tree_files_chunks[0];
-    let file_list_area = tree_files_chunks[1];
+
+    // Breadcrumb line above the file list
+    let file_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(tree_files_chunks[1]);
+    let breadcrumb_area = file_chunks[0];
+    let file_list_area = file_chunks[1];
 
     // Save layout rects for mouse hit-testing
     state.tree_area = tree_area;
@@ -66,6 +76,7 @@ pub fn render(frame: &mut Frame, state: &mut AppState) {
 
     // Render widgets
     render_directory_tree(frame, tree_area, state);
+    render_breadcrumb(frame, breadcrumb_area, state);
     render_file_list(frame, file_list_area, state);
     render_details_panel(frame, details_area, state);
     render_preview(frame, preview_area, state);
@@ -73,10 +84,14 @@ pub fn render(frame: &mut Frame, state: &mut AppState) {
 
     // Dim background when any modal is active
     let has_modal = state.show_help
+        || state.show_dashboard
         || state.tag_input.is_some()
         || state.filter_dialog.is_some()
         || state.rename_dialog.is_some()
-        || state.operations_menu.is_some();
+        || state.goto.is_some()
+        || state.operations_menu.is_some()
+        || state.usage_view.is_some()
+        || state.error_popup.is_some();
 
     if has_modal {
         // Skip the inner preview area — kitty image placeholders corrupt when restyled
@@ -106,6 +121,10 @@ pub fn render(frame: &mut Frame, state: &mut AppState) {
         render_help_overlay(frame, size);
     }
 
+    if state.show_dashboard {
+        render_dashboard(frame, size, state.dashboard.as_ref());
+    }
+
     // Render tag input popup if active
     if let Some(ref tag_input) = state.tag_input {
         render_tag_popup(frame, size, tag_input);
@@ -121,61 +140,118 @@ pub fn render(frame: &mut Frame, state: &mut AppState) {
         render_rename_dialog(frame, size, rename_dialog);
     }
 
+    // Render goto prompt if active
+    if let Some(ref goto) = state.goto {
+        render_goto_prompt(frame, size, goto);
+    }
+
     // Render operations menu if active
     if let Some(ref menu) = state.operations_menu {
         render_operations_menu(frame, size, menu);
     }
+
+    // Render disk usage view if active
+    if let Some(ref view) = state.usage_view {
+        render_usage_view(frame, size, view);
+    }
+
+    // Render error popup last, above whatever dialog failed
+    if let Some(ref popup) = state.error_popup {
+        render_error_popup(frame, size, popup);
+    }
 }
 
 fn render_operations_menu(frame: &mut Frame, area: Rect, menu: &super::state::OperationsMenuState) {
     let dir_name = if menu.directory_path.is_empty() { "." } else { &menu.directory_path };
 
+    // Rating items describe what they would change (dry run)
+    let preview = &menu.rating_preview;
+    let push_down = match preview.directory_rating {
+        Some(r) => format!("{} unrated files get {}★", preview.unrated_files, r),
+        None => "Directory has no rating".to_string(),
+    };
+    let derive = |rating: Option<f64>| match rating {
+        Some(r) => format!("Set directory to {}★ ({} rated)", r, preview.rated_files),
+        None => "No rated files".to_string(),
+    };
+
+    let untag = if menu.directory_tags.is_empty() {
+        "Directory has no tags".to_string()
+    } else {
+        format!("Remove {} from everything below", menu.directory_tags.join(", "))
+    };
+
     let options = [
-        ("1", "Thumbnails",              "Generate preview thumbnails"),
-        ("2", "Orientation",             "Tag landscape/portrait"),
-        ("3", "Hash",                    "Compute file hashes"),
-        ("4", "Dir preview",             "Current directory only"),
-        ("5", "Dir preview (recursive)", "Include subdirectories"),
+        ("1", "Thumbnails",              "Generate preview thumbnails".to_string()),
+        ("2", "Orientation",             "Tag landscape/portrait".to_string()),
+        ("3", "Hash",                    "Compute file hashes".to_string()),
+        ("4", "Dir preview",             "Current directory only".to_string()),
+        ("5", "Dir preview (recursive)", "Include subdirectories".to_string()),
+        ("6", "Rating to files",         push_down),
+        ("7", "Rating from files (max)", derive(preview.max)),
+        ("8", "Rating from files (avg)", derive(preview.average)),
+        ("9", "Tags to files",           "Copy inherited directory tags onto files".to_string()),
+        ("0", "Untag recursively",       untag),
+        ("r", "Rescan",                  "Sync this directory with the disk".to_string()),
     ];
 
+    let tab_style = |tab: OperationsTab| {
+        if menu.tab == tab {
+            Style::default().fg(HEADER_COLOR).add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+        } else {
+            Style::default().fg(HELP_TEXT)
+        }
+    };
+
     let mut lines: Vec<Line> = vec![
         Line::from(vec![
             Span::styled(" Directory: ", Style::default().fg(HELP_TEXT)),
             Span::styled(dir_name, Style::default().fg(HEADER_COLOR)),
             Span::styled(format!(" ({} files)", menu.file_count), Style::default().fg(HELP_TEXT)),
         ]),
+        Line::from(vec![
+            Span::raw(" "),
+            Span::styled("Actions", tab_style(OperationsTab::Actions)),
+            Span::styled(" │ ", Style::default().fg(HELP_TEXT)),
+            Span::styled(format!("History ({})", menu.history.len()), tab_style(OperationsTab::History)),
+        ]),
         Line::from(""),
     ];
 
-    for (i, (key, name, desc)) in options.iter().enumerate() {
-        let is_selected = i == menu.selected;
-        if is_selected {
-            lines.push(Line::from(vec![
-                Span::styled(" ▸ ", Style::default().fg(FOCUS_COLOR)),
-                Span::styled(
-                    format!("[{}] {}", key, name),
-                    Style::default().bg(FOCUS_COLOR).fg(Color::Black),
-                ),
-                Span::styled(format!("  {}", desc), Style::default().fg(Color::White)),
-            ]));
-        } else {
-            lines.push(Line::from(vec![
-                Span::raw("   "),
-                Span::styled(format!("[{}]", key), Style::default().fg(FOCUS_COLOR)),
-                Span::styled(format!(" {}", name), Style::default().fg(Color::White)),
-                Span::styled(format!("  {}", desc), Style::default().fg(HELP_TEXT)),
-            ]));
+    if menu.tab == OperationsTab::History {
+        render_operation_history(&mut lines, menu);
+    } else {
+        for (i, (key, name, desc)) in options.iter().enumerate() {
+            let is_selected = i == menu.selected;
+            if is_selected {
+                lines.push(Line::from(vec![
+                    Span::styled(" ▸ ", Style::default().fg(FOCUS_COLOR)),
+                    Span::styled(
+                        format!("[{}] {}", key, name),
+                        Style::default().bg(FOCUS_COLOR).fg(Color::Black),
+                    ),
+                    Span::styled(format!("  {}", desc), Style::default().fg(Color::White)),
+                ]));
+            } else {
+                lines.push(Line::from(vec![
+                    Span::raw("   "),
+                    Span::styled(format!("[{}]", key), Style::default().fg(FOCUS_COLOR)),
+                    Span::styled(format!(" {}", name), Style::default().fg(Color::White)),
+                    Span::styled(format!("  {}", desc), Style::default().fg(HELP_TEXT)),
+                ]));
+            }
         }
     }
 
     lines.push(Line::from(""));
-    lines.push(Line::from(Span::styled(
-        " Enter: select  o/Esc: cancel",
-        Style::default().fg(HELP_TEXT),
-    )));
+    let hint = match menu.tab {
+        OperationsTab::Actions => " Enter: select  Tab: history  .: repeat last  o/Esc: cancel",
+        OperationsTab::History => " Enter: re-run on this directory  Tab: actions  o/Esc: cancel",
+    };
+    lines.push(Line::from(Span::styled(hint, Style::default().fg(HELP_TEXT))));
 
-    let width = 60;
-    let height = 11;
+    let width = 72;
+    let height = 18;
     let x = (area.width.saturating_sub(width)) / 2;
     let y = (area.height.saturating_sub(height)) / 2;
 
@@ -193,6 +269,48 @@ fn render_operations_menu(frame: &mut Frame, area: Rect, menu: &super::state::Op
     frame.render_widget(dialog, dialog_area);
 }
 
+/// Rows of the operations menu's history tab, scrolled to keep the
+/// selection visible
+fn render_operation_history(lines: &mut Vec<Line>, menu: &super::state::OperationsMenuState) {
+    const VISIBLE_ROWS: usize = 11;
+
+    if menu.history.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "   No operations yet",
+            Style::default().fg(HELP_TEXT),
+        )));
+        return;
+    }
+
+    let offset = menu.history_selected.saturating_sub(VISIBLE_ROWS - 1);
+    for (i, record) in menu.history.iter().enumerate().skip(offset).take(VISIBLE_ROWS) {
+        let label = OperationType::from_name(&record.operation)
+            .map(|op| op.label().to_string())
+            .unwrap_or_else(|| record.operation.clone());
+        let dir = if record.directory.is_empty() { "." } else { record.directory.as_str() };
+        let when = DateTime::from_timestamp(record.finished_at, 0)
+            .map(|t| t.with_timezone(&Local).format("%b %d %H:%M").to_string())
+            .unwrap_or_default();
+        let secs = record.duration_ms / 1000;
+        let mut detail = format!("{} items  {}:{:02}  {}", record.items, secs / 60, secs % 60, when);
+        if record.cancelled {
+            detail.push_str("  cancelled");
+        }
+
+        let (marker, label_style) = if i == menu.history_selected {
+            (" ▸ ", Style::default().bg(FOCUS_COLOR).fg(Color::Black))
+        } else {
+            ("   ", Style::default().fg(Color::White))
+        };
+        lines.push(Line::from(vec![
+            Span::styled(marker, Style::default().fg(FOCUS_COLOR)),
+            Span::styled(label, label_style),
+            Span::styled(format!(" {}", dir), Style::default().fg(HEADER_COLOR)),
+            Span::styled(format!("  {}", detail), Style::default().fg(HELP_TEXT)),
+        ]));
+    }
+}
+
 fn render_help_overlay(frame: &mut Frame, area: Rect) {
     fn key_line(key: &str, desc: &str, key_width: usize) -> Line<'static> {
         Line::from(vec![
@@ -219,17 +337,35 @@ fn render_help_overlay(frame: &mut Frame, area: Rect) {
         key_line("h/←", "Collapse/Left pane", 10),
         key_line("l/→", "Expand/Right pane", 10),
         key_line("Tab", "Switch focus", 10),
+        key_line("-/+", "Collapse/expand all", 10),
+        key_line("e 1-9", "Expand tree to level", 10),
+        key_line("O", "Sort tree (name/rating/newest/size)", 10),
         Line::from(""),
         Line::from(Span::styled("  Actions:", section)),
         key_line("Enter", "Open file / Select dir", 10),
-        key_line("1-5/asdfg", "Set rating", 10),
+        key_line("1-9/asdfg", "Set rating", 10),
         key_line("0", "Clear rating", 10),
+        key_line("[ / ]", "Rating down/up half a star", 10),
+        key_line("c", "Cull mode (rate moves to next)", 10),
+        key_line("Z / W / =", "Zoom preview in / fit / 1:1", 10),
         key_line("t", "Add tag", 10),
         key_line("r", "Rename directory", 10),
+        key_line("P", "Pin/unpin directory", 10),
+        key_line("H", "Hide/unhide directory", 10),
+        key_line(".", "Show hidden directories", 10),
+        key_line("Space", "Mark file for stacking", 10),
+        key_line("S", "Stack marked / unstack", 10),
+        key_line("z", "Expand/collapse stack", 10),
         key_line("o", "Operations menu", 10),
+        key_line("u", "Disk usage", 10),
+        key_line("b", "Background tasks", 10),
+        key_line("F5", "Sync library in background", 10),
         key_line("m", "Filter", 10),
         key_line("i", "Toggle details", 10),
+        key_line("I / J K", "All EXIF tags / scroll", 10),
         key_line("/", "Search", 10),
+        key_line(":", "Go to directory", 10),
+        key_line("D", "Library dashboard", 10),
         key_line("?", "Toggle help", 10),
         key_line("q", "Quit", 10),
         Line::from(""),
@@ -240,7 +376,7 @@ fn render_help_overlay(frame: &mut Frame, area: Rect) {
     ];
 
     let help_width = 60;
-    let help_height = 29;
+    let help_height = 47;
     let x = (area.width.saturating_sub(help_width)) / 2;
     let y = (area.height.saturating_sub(help_height)) / 2;
 
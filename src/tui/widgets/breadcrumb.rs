@@ -0,0 +1,59 @@
+use ratatui::{layout::Rect, prelude::*, widgets::Paragraph};
+
+use crate::tui::colors::{FOCUS_COLOR, HEADER_COLOR, HELP_TEXT};
+use crate::tui::state::AppState;
+
+const SEPARATOR: &str = " › ";
+const ELLIPSIS: &str = "…";
+
+/// Path of the current directory above the file list. Ancestor segments are
+/// clickable; their positions are saved for mouse hit-testing.
+pub fn render_breadcrumb(frame: &mut Frame, area: Rect, state: &mut AppState) {
+    let segments = state.breadcrumb();
+    let last = segments.len() - 1;
+    let width = |label: &str| Span::raw(label).width() as u16;
+
+    // Drop leading segments until the rest fits, always keeping the last one
+    let mut first = 0;
+    let total = |from: usize| -> u16 {
+        let labels: u16 = segments[from..].iter().map(|(l, _)| width(l)).sum();
+        let separators = (segments.len() - from - 1) as u16 * width(SEPARATOR);
+        let elided = if from > 0 { width(ELLIPSIS) + width(SEPARATOR) } else { 0 };
+        1 + labels + separators + elided
+    };
+    while first < last && total(first) > area.width {
+        first += 1;
+    }
+
+    let separator_style = Style::default().fg(HELP_TEXT);
+    let mut spans = vec![Span::raw(" ")];
+    let mut x = area.x + 1;
+    if first > 0 {
+        spans.push(Span::styled(ELLIPSIS, separator_style));
+        spans.push(Span::styled(SEPARATOR, separator_style));
+        x += width(ELLIPSIS) + width(SEPARATOR);
+    }
+
+    state.breadcrumb_targets.clear();
+    for (i, (label, dir_id)) in segments.iter().enumerate().skip(first) {
+        if i > first {
+            spans.push(Span::styled(SEPARATOR, separator_style));
+            x += width(SEPARATOR);
+        }
+        let style = if i == last {
+            Style::default().fg(HEADER_COLOR).add_modifier(Modifier::BOLD)
+        } else if dir_id.is_some() {
+            Style::default().fg(FOCUS_COLOR)
+        } else {
+            separator_style
+        };
+        let label_width = width(label).min((area.x + area.width).saturating_sub(x));
+        if let Some(id) = dir_id.filter(|_| i != last) {
+            state.breadcrumb_targets.push((Rect::new(x, area.y, label_width, 1), id));
+        }
+        spans.push(Span::styled(label.clone(), style));
+        x += label_width;
+    }
+
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Local};
+use ratatui::{
+    layout::Rect,
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::tui::colors::{HEADER_COLOR, HELP_TEXT, SUCCESS_COLOR, WARNING_COLOR};
+use crate::tui::state::DashboardState;
+
+pub fn render_dashboard(frame: &mut Frame, area: Rect, dashboard: Option<&DashboardState>) {
+    let popup_width = 50.min(area.width);
+    let popup_height = 20.min(area.height);
+    let x = (area.width.saturating_sub(popup_width)) / 2;
+    let y = (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(x, y, popup_width, popup_height);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Library ")
+        .title_style(Style::default().fg(HEADER_COLOR).add_modifier(Modifier::BOLD));
+
+    let Some(dashboard) = dashboard else {
+        frame.render_widget(Paragraph::new(" Loading...").block(block), popup_area);
+        return;
+    };
+    let summary = &dashboard.summary;
+
+    let section = Style::default().fg(HEADER_COLOR).add_modifier(Modifier::BOLD);
+    let row = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("   {:<22}", label), Style::default().fg(HELP_TEXT)),
+            Span::raw(value),
+        ])
+    };
+    // Outstanding work stands out until it's done
+    let pending = |label: &str, count: Option<i64>| {
+        let (text, color) = match count {
+            None => ("counting...".to_string(), HELP_TEXT),
+            Some(0) => ("none".to_string(), SUCCESS_COLOR),
+            Some(n) => (n.to_string(), WARNING_COLOR),
+        };
+        Line::from(vec![
+            Span::styled(format!("   {:<22}", label), Style::default().fg(HELP_TEXT)),
+            Span::styled(text, Style::default().fg(color)),
+        ])
+    };
+    let share = |count: i64| {
+        if summary.files == 0 {
+            count.to_string()
+        } else {
+            format!("{} ({}%)", count, count * 100 / summary.files)
+        }
+    };
+
+    let last_sync = summary
+        .last_sync
+        .and_then(|t| DateTime::from_timestamp(t, 0))
+        .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "never".to_string());
+
+    let lines = vec![
+        Line::from(""),
+        Line::from(Span::styled("  Totals", section)),
+        row("Directories", summary.directories.to_string()),
+        row("Files", format!("{} ({})", summary.files, format_size(summary.bytes))),
+        row(
+            "Images/videos/docs",
+            format!("{} / {} / {}", summary.images, summary.videos, summary.documents),
+        ),
+        row("Rated", share(summary.rated)),
+        row("Tagged", share(summary.tagged)),
+        Line::from(""),
+        Line::from(Span::styled("  Pending work", section)),
+        pending("Without hash", Some(summary.without_hash)),
+        pending("Without dimensions", Some(summary.without_dimensions)),
+        pending("Without thumbnail", dashboard.missing_thumbnails.map(|n| n as i64)),
+        pending("Thumbnail failures", Some(summary.thumbnail_failures)),
+        Line::from(""),
+        row("Last sync", last_sync),
+        Line::from(""),
+        Line::from(Span::styled("  D/Esc: close", Style::default().fg(HELP_TEXT))),
+    ];
+
+    frame.render_widget(Paragraph::new(lines).block(block), popup_area);
+}
+
+fn format_size(bytes: i64) -> String {
+    const KB: i64 = 1024;
+    const MB: i64 = KB * 1024;
+    const GB: i64 = MB * 1024;
+
+    if bytes >= GB {
+        format!("{:.1} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.1} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.1} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
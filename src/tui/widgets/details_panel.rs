@@ -7,14 +7,19 @@ use ratatui::{
 };
 
 use crate::tui::colors::{
-    format_rating, HEADER_COLOR, HELP_TEXT, RATING_COLOR, SUCCESS_COLOR, TAG_COLOR, WARNING_COLOR,
+    format_rating, ASPECT_INDICATOR, HEADER_COLOR, HELP_TEXT, PAIR_INDICATOR, RATING_COLOR, SUCCESS_COLOR, TAG_COLOR,
+    WARNING_COLOR,
 };
+use crate::tui::dialogs::Orientation;
 use crate::tui::state::{AppState, Focus};
 
-use crate::thumbnails::{has_dir_preview, has_thumbnail, is_image_file, is_video_file};
+use crate::thumbnails::{has_dir_preview, has_thumbnail, is_thumbnailable_file};
+use crate::library_path::LibraryPath;
+use crate::print_size::{describe_inches, is_printable, print_inches, DEFAULT_PRINT_DPI, PRINT_DPIS};
 
 pub fn render_details_panel(frame: &mut Frame, area: Rect, state: &AppState) {
     let content = match (&state.focus, state.details_expanded) {
+        (Focus::FileList, true) if state.exif_dump => render_exif_dump(state),
         (Focus::FileList, true) => render_file_details_expanded(state),
         (Focus::FileList, false) => render_file_details(state),
         (Focus::DirectoryTree, _) => render_directory_details(state),
@@ -38,17 +43,21 @@ fn render_file_details(state: &AppState) -> Text<'static> {
     };
 
     let file = &file_with_tags.file;
-    let dir = state.get_selected_directory();
+    let dir = state.file_directory(file);
 
     // Build full path
     let full_path = match dir {
-        Some(d) if !d.path.is_empty() => format!("{}/{}", d.path, file.filename),
-        _ => file.filename.clone(),
+        Some(d) => LibraryPath::file(&d.path, &file.filename).into_string(),
+        None => file.filename.clone(),
     };
 
     // Format size with optional dimensions
     let size = format_size(file.size);
     let size_dims = match (file.width, file.height) {
+        (Some(w), Some(h)) if is_printable(file.media_type.as_deref()) => {
+            let (pw, ph) = print_inches(w, h, DEFAULT_PRINT_DPI);
+            format!("{}  {}×{}  Prints {:.1}×{:.1} in at {} dpi", size, w, h, pw, ph, DEFAULT_PRINT_DPI)
+        }
         (Some(w), Some(h)) => format!("{}  {}×{}", size, w, h),
         _ => size,
     };
@@ -69,7 +78,7 @@ fn render_file_details(state: &AppState) -> Text<'static> {
     // Line 3: rating
     let line3 = Line::from(vec![
         Span::raw("Rating: "),
-        Span::styled(format_rating(file.rating), Style::default().fg(RATING_COLOR)),
+        Span::styled(format_rating(file.rating, state.rating_scale), Style::default().fg(RATING_COLOR)),
     ]);
 
     // Line 4: timestamps
@@ -101,12 +110,12 @@ fn render_file_details_expanded(state: &AppState) -> Text<'static> {
     };
 
     let file = &file_with_tags.file;
-    let dir = state.get_selected_directory();
+    let dir = state.file_directory(file);
 
     // Build full path
     let full_path = match dir {
-        Some(d) if !d.path.is_empty() => format!("{}/{}", d.path, file.filename),
-        _ => file.filename.clone(),
+        Some(d) => LibraryPath::file(&d.path, &file.filename).into_string(),
+        None => file.filename.clone(),
     };
 
     let (modified, created) = if let Some(fs_path) = state.selected_file_path() {
@@ -127,7 +136,21 @@ fn render_file_details_expanded(state: &AppState) -> Text<'static> {
 
     // Dimensions
     if let (Some(w), Some(h)) = (file.width, file.height) {
-        lines.push(Line::from(format!("  {}×{}", w, h)));
+        match Orientation::of(file.width, file.height) {
+            Some(shape) => lines.push(Line::from(vec![
+                Span::raw(format!("  {}×{}  ", w, h)),
+                Span::styled(shape.aspect_ratio(), Style::default().fg(ASPECT_INDICATOR)),
+            ])),
+            None => lines.push(Line::from(format!("  {}×{}", w, h))),
+        }
+        let dpis = if is_printable(file.media_type.as_deref()) { &PRINT_DPIS[..] } else { &[] };
+        for &dpi in dpis {
+            let (pw, ph) = print_inches(w, h, dpi);
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {} dpi  ", dpi), Style::default().fg(HELP_TEXT)),
+                Span::raw(describe_inches(pw, ph)),
+            ]));
+        }
     }
 
     // Size (formatted + exact bytes)
@@ -141,7 +164,7 @@ fn render_file_details_expanded(state: &AppState) -> Text<'static> {
     // Rating
     lines.push(Line::from(vec![
         Span::raw("  Rating: "),
-        Span::styled(format_rating(file.rating), Style::default().fg(RATING_COLOR)),
+        Span::styled(format_rating(file.rating, state.rating_scale), Style::default().fg(RATING_COLOR)),
     ]));
 
     // Timestamps
@@ -176,6 +199,22 @@ fn render_file_details_expanded(state: &AppState) -> Text<'static> {
         },
     ]));
 
+    // Paired siblings
+    if !file_with_tags.companions.is_empty() {
+        lines.push(Line::from(vec![
+            Span::raw("  Paired: "),
+            Span::styled(file_with_tags.companions.join(", "), Style::default().fg(PAIR_INDICATOR)),
+        ]));
+    }
+
+    // Case-insensitive name clashes
+    if !file_with_tags.name_collisions.is_empty() {
+        lines.push(Line::from(vec![
+            Span::raw("  Name clash: "),
+            Span::styled(file_with_tags.name_collisions.join(", "), Style::default().fg(WARNING_COLOR)),
+        ]));
+    }
+
     // Tags
     let mut tag_spans: Vec<Span> = vec![Span::raw("  Tags: ")];
     if file_with_tags.tags.is_empty() {
@@ -205,14 +244,12 @@ fn render_file_details_expanded(state: &AppState) -> Text<'static> {
             lines.push(Line::from(""));
             lines.push(Line::from(Span::styled("EXIF", section)));
 
-            if let Some(ref make) = exif.camera_make {
-                if let Some(ref model) = exif.camera_model {
-                    lines.push(Line::from(format!("  {} {}", make, model)));
-                } else {
-                    lines.push(Line::from(format!("  {}", make)));
-                }
-            } else if let Some(ref model) = exif.camera_model {
-                lines.push(Line::from(format!("  {}", model)));
+            if let Some(camera) = exif.camera() {
+                lines.push(Line::from(format!("  {}", camera)));
+            }
+
+            if let Some(ref date) = exif.date_taken {
+                lines.push(Line::from(format!("  Taken: {}", date)));
             }
 
             if let Some(ref lens) = exif.lens {
@@ -234,15 +271,61 @@ fn render_file_details_expanded(state: &AppState) -> Text<'static> {
                 lines.push(Line::from(format!("  {}", exposure_parts.join("  "))));
             }
 
+            // How the exposure was made: program, bias, metering, flash
+            let mode_parts: Vec<String> = [
+                exif.exposure_program.clone(),
+                exif.exposure_bias.clone(),
+                exif.metering_mode.as_ref().map(|m| format!("{} metering", m)),
+                exif.flash.as_ref().map(|f| format!("flash {}", f)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            if !mode_parts.is_empty() {
+                lines.push(Line::from(format!("  {}", mode_parts.join(", "))));
+            }
+
             if let (Some(lat), Some(lon)) = (exif.gps_lat, exif.gps_lon) {
-                lines.push(Line::from(format!("  GPS: {:.6}, {:.6}", lat, lon)));
+                let altitude = exif.gps_altitude.map(|a| format!("  {:.0} m", a)).unwrap_or_default();
+                lines.push(Line::from(format!("  GPS: {:.6}, {:.6}{}", lat, lon, altitude)));
             }
+
+            lines.push(Line::from(Span::styled(
+                format!("  I: all {} tags", exif.tags.len()),
+                Style::default().fg(HELP_TEXT),
+            )));
         }
     }
 
     Text::from(lines)
 }
 
+/// Every EXIF tag of the selected file, from `exif_scroll` on
+fn render_exif_dump(state: &AppState) -> Text<'static> {
+    let section = Style::default().fg(HEADER_COLOR).add_modifier(Modifier::BOLD);
+    let current = state.selected_file_path();
+    let tags = match &state.cached_exif {
+        Some((path, exif)) if current.as_ref() == Some(path) => &exif.tags,
+        _ => return Text::raw("No file selected"),
+    };
+
+    let mut lines = vec![Line::from(vec![
+        Span::styled(format!("EXIF tags ({})", tags.len()), section),
+        Span::styled("  I: summary  J/K: scroll", Style::default().fg(HELP_TEXT)),
+    ])];
+    if tags.is_empty() {
+        lines.push(Line::from(Span::styled("  none", Style::default().fg(HELP_TEXT))));
+    }
+    for tag in tags.iter().skip(state.exif_scroll as usize) {
+        let prefix = if tag.ifd == "thumbnail" { "thumb " } else { "" };
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {}{}: ", prefix, tag.name), Style::default().fg(HELP_TEXT)),
+            Span::raw(tag.value.clone()),
+        ]));
+    }
+    Text::from(lines)
+}
+
 fn render_directory_details(state: &AppState) -> Text<'static> {
     let Some(dir) = state.get_selected_directory() else {
         return Text::raw("No directory selected");
@@ -258,14 +341,10 @@ fn render_directory_details(state: &AppState) -> Text<'static> {
     // Count subdirs from in-memory tree (recursive)
     let subdir_count = count_subdirs_recursive(&state.tree.directories, dir.id);
 
-    // Get file count and size from DB (recursive)
-    let (file_count, total_size) = state.db.get_directory_stats(dir.id).unwrap_or((0, 0));
-
-    // Get directory tags (query on demand)
-    let tags = match state.db.get_directory_tags(dir.id) {
-        Ok(t) => t,
-        Err(_) => vec![],
-    };
+    // File count and size (recursive) and tags, from the snapshot
+    let totals = state.snapshot.totals(dir.id);
+    let (file_count, total_size) = (totals.files, totals.size);
+    let tags = state.snapshot.directory_tags(dir.id);
 
     // Line 1: path
     let line1 = Line::from(path);
@@ -273,7 +352,7 @@ fn render_directory_details(state: &AppState) -> Text<'static> {
     // Line 2: rating
     let line2 = Line::from(vec![
         Span::raw("Rating: "),
-        Span::styled(format_rating(dir.rating), Style::default().fg(RATING_COLOR)),
+        Span::styled(format_rating(dir.rating, state.rating_scale), Style::default().fg(RATING_COLOR)),
     ]);
 
     // Line 3: stats
@@ -317,7 +396,9 @@ fn render_directory_details(state: &AppState) -> Text<'static> {
     Text::from(lines)
 }
 
-/// Check if a directory has any media files missing thumbnails (cached)
+/// Check if a directory has any media files missing thumbnails (cached).
+/// A directory found complete is remembered in the DB until its mtime
+/// changes, so it isn't stat'ed again in later sessions.
 fn check_dir_missing_thumbnails(state: &AppState, dir: &crate::db::Directory) -> bool {
     // Check cache first
     if let Some((cached_id, cached_result)) = *state.missing_preview_cache.borrow() {
@@ -326,25 +407,30 @@ fn check_dir_missing_thumbnails(state: &AppState, dir: &crate::db::Directory) ->
         }
     }
 
-    // Compute and cache
-    let result = compute_missing_thumbnails(state, dir);
+    let result = if state.snapshot.has_thumbnail_presence(dir.id, dir.mtime) {
+        false
+    } else if !state.snapshot.has_files_of(dir) {
+        // Files not loaded yet (deferred during rapid navigation): check next frame
+        return false;
+    } else {
+        let missing = compute_missing_thumbnails(state, dir);
+        if !missing {
+            state.snapshot.record_thumbnail_presence(dir.id, dir.mtime);
+        }
+        missing
+    };
     *state.missing_preview_cache.borrow_mut() = Some((dir.id, result));
     result
 }
 
 /// Actually check if directory has missing thumbnails
 fn compute_missing_thumbnails(state: &AppState, dir: &crate::db::Directory) -> bool {
-    let files = match state.db.get_files_in_directory(dir.id) {
-        Ok(f) => f,
-        Err(_) => return false,
-    };
-
     let dir_path = dir.full_path(&state.library_path);
 
     // Check first media file only (quick check)
-    for file in &files {
+    for file in state.snapshot.listing(dir.id).files {
         let path = dir_path.join(&file.filename);
-        if is_image_file(&path) || is_video_file(&path) {
+        if is_thumbnailable_file(&path) {
             return !has_thumbnail(&path);
         }
     }
@@ -1,11 +1,13 @@
+use std::collections::HashSet;
+
 use ratatui::{
     layout::Rect,
     prelude::*,
     widgets::{Block, Borders, List, ListItem},
 };
 
-use crate::tui::colors::{FOCUS_COLOR, HELP_TEXT, UNFOCUS_COLOR};
-use crate::tui::state::{AppState, Focus};
+use crate::tui::colors::{FOCUS_COLOR, HELP_TEXT, PIN_COLOR, UNFOCUS_COLOR};
+use crate::tui::state::{AppState, Focus, VirtualFolder};
 
 use crate::db::Directory;
 use crate::tui::state::TreeState;
@@ -18,8 +20,6 @@ use crate::tui::state::TreeState;
 /// This is a pure function: it only reads directory metadata (depth, parent_id)
 /// and produces strings, making it easy to test without a full AppState.
 fn compute_tree_prefixes(visible_dirs: &[&Directory], tree: &TreeState) -> Vec<String> {
-    use std::collections::HashSet;
-
     let n = visible_dirs.len();
     if n == 0 {
         return vec![];
@@ -80,7 +80,31 @@ pub fn render_directory_tree(frame: &mut Frame, area: Rect, state: &mut AppState
     let visible_dirs = state.get_search_visible_directories();
     let tree_prefixes = compute_tree_prefixes(&visible_dirs, &state.tree);
 
-    let items: Vec<ListItem> = visible_dirs
+    // Rows shown only because hidden directories are being shown. Parents
+    // come before their children, so one pass finds whole subtrees.
+    let mut hidden_rows: HashSet<i64> = HashSet::new();
+    if state.tree.show_hidden {
+        for dir in &visible_dirs {
+            if state.tree.hidden.contains(&dir.id) || dir.parent_id.is_some_and(|p| hidden_rows.contains(&p)) {
+                hidden_rows.insert(dir.id);
+            }
+        }
+    }
+
+    // Pinned directories come first, by full path, matching `get_selected_directory`
+    let mut items: Vec<ListItem> = state
+        .pinned_directories()
+        .iter()
+        .map(|dir| {
+            let path = if dir.path.is_empty() { "." } else { dir.path.as_str() };
+            ListItem::new(Line::from(vec![
+                Span::styled("⚑  ", Style::default().fg(PIN_COLOR)),
+                Span::raw(path.to_string()),
+            ]))
+        })
+        .collect();
+
+    let tree_items: Vec<ListItem> = visible_dirs
         .iter()
         .enumerate()
         .map(|(i, dir)| {
@@ -108,11 +132,30 @@ pub fn render_directory_tree(frame: &mut Frame, area: Rect, state: &mut AppState
                 Style::default().fg(HELP_TEXT),
             ));
 
-            spans.push(Span::raw(display_name.to_string()));
+            if hidden_rows.contains(&dir.id) {
+                spans.push(Span::styled(display_name.to_string(), Style::default().fg(HELP_TEXT)));
+            } else {
+                spans.push(Span::raw(display_name.to_string()));
+            }
 
             ListItem::new(Line::from(spans))
         })
         .collect();
+    items.extend(tree_items);
+
+    // Virtual folders come after the tree, matching `selected_virtual_folder`
+    items.extend(state.virtual_folders().into_iter().map(|folder| {
+        let mut spans = vec![
+            Span::styled(if matches!(folder, VirtualFolder::Review(_)) { "▸  " } else { "◷  " }, Style::default().fg(HELP_TEXT)),
+            Span::styled(state.virtual_folder_name(folder), Style::default().add_modifier(Modifier::ITALIC)),
+        ];
+        if let VirtualFolder::Review(id) = folder {
+            if let Some(queue) = state.review_queues.iter().find(|q| q.id == id) {
+                spans.push(Span::styled(format!(" {}/{}", queue.decided, queue.total), Style::default().fg(HELP_TEXT)));
+            }
+        }
+        ListItem::new(Line::from(spans))
+    }));
 
     let border_style = if is_focused {
         Style::default().fg(FOCUS_COLOR)
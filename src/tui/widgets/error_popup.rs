@@ -0,0 +1,62 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+use crate::tui::colors::{ERROR_COLOR, HELP_TEXT};
+use crate::tui::state::ErrorPopupState;
+
+pub fn render_error_popup(frame: &mut Frame, area: Rect, popup: &ErrorPopupState) {
+    let popup_width = 70.min(area.width.saturating_sub(4));
+    let text_width = popup_width.saturating_sub(4).max(1) as usize;
+    // Rough wrapped height of the message, so short errors get a small box
+    let message_height = (popup.message.chars().count() / text_width + 1) as u16;
+    let details_height = if popup.show_details { popup.details.len() as u16 + 1 } else { 0 };
+    let popup_height = (message_height + details_height + 4).min(area.height.saturating_sub(2));
+    let x = (area.width.saturating_sub(popup_width)) / 2;
+    let y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = Rect::new(x, y, popup_width, popup_height);
+
+    // Clear the area behind the popup
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ERROR_COLOR))
+        .title(" Error ")
+        .title_style(Style::default().fg(ERROR_COLOR).add_modifier(Modifier::BOLD));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),    // Message and details
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner);
+
+    let mut lines = vec![Line::from(format!(" {}", popup.message))];
+    if popup.show_details {
+        lines.push(Line::default());
+        lines.extend(
+            popup
+                .details
+                .iter()
+                .map(|d| Line::styled(format!(" {}", d), Style::default().fg(HELP_TEXT))),
+        );
+    }
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), chunks[0]);
+
+    let help = if popup.details.is_empty() {
+        " Esc:Close"
+    } else if popup.show_details {
+        " d:Hide details  Esc:Close"
+    } else {
+        " d:Details  Esc:Close"
+    };
+    frame.render_widget(Paragraph::new(help).style(Style::default().fg(HELP_TEXT)), chunks[1]);
+}
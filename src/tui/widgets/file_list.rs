@@ -4,52 +4,87 @@ use ratatui::{
     widgets::{Block, Borders, Cell, Row, Table},
 };
 
+use crate::rating::RatingScale;
 use crate::thumbnails::has_thumbnail;
-use crate::tui::colors::{FOCUS_COLOR, HEADER_COLOR, HELP_TEXT, UNFOCUS_COLOR, VIDEO_INDICATOR};
+use crate::tui::colors::{
+    format_rating_compact, format_stars, ASPECT_INDICATOR, DOCUMENT_INDICATOR, FOCUS_COLOR, HEADER_COLOR, HELP_TEXT,
+    MARK_COLOR, PAIR_INDICATOR, RATING_COLOR, STACK_INDICATOR, UNFOCUS_COLOR, VIDEO_INDICATOR,
+    WARNING_COLOR,
+};
+use crate::tui::dialogs::Orientation;
 use crate::tui::state::{AppState, Focus};
 
 pub fn render_file_list(frame: &mut Frame, area: Rect, state: &mut AppState) {
     let is_focused = state.focus == Focus::FileList;
 
-    // Build directory path for thumbnail checks
-    let dir_path = state.get_selected_directory().map(|d| {
-        if d.path.is_empty() {
-            state.library_path.clone()
-        } else {
-            state.library_path.join(&d.path)
-        }
-    });
-
     // Get search-filtered file indices
     let visible_indices = state.visible_file_indices();
 
-    let rows: Vec<Row> = visible_indices
+    // Thumbnail checks; virtual folders list files from all over the library
+    let selected_dir = state.get_selected_directory();
+    let has_thumbs: Vec<bool> = visible_indices
         .iter()
         .map(|&idx| {
+            let file = &state.file_list.files[idx].file;
+            selected_dir
+                .or_else(|| state.file_directory(file))
+                .map(|d| has_thumbnail(&d.file_path(&state.library_path, &file.filename)))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    let rows: Vec<Row> = visible_indices
+        .iter()
+        .zip(&has_thumbs)
+        .map(|(&idx, &has_thumb)| {
             let file_with_tags = &state.file_list.files[idx];
             let file = &file_with_tags.file;
 
-            // Format filename with video indicator
-            let is_video = file
-                .media_type
-                .as_deref()
-                .map(|t| t.starts_with("video/"))
-                .unwrap_or(false);
-            let name_cell = if is_video {
-                Cell::from(Line::from(vec![
-                    Span::styled("[V] ", Style::default().fg(VIDEO_INDICATOR)),
-                    Span::raw(&file.filename),
-                ]))
-            } else {
-                Cell::from(file.filename.as_str())
+            // Format filename with video/document indicator
+            let indicator = match file.media_type.as_deref() {
+                Some("video") => Some(("[V] ", VIDEO_INDICATOR)),
+                Some("document") => Some(("[D] ", DOCUMENT_INDICATOR)),
+                _ => None,
+            };
+            let mut name_spans = Vec::new();
+            if state.file_list.marked.contains(&file.id) {
+                name_spans.push(Span::styled("● ", Style::default().fg(MARK_COLOR)));
+            }
+            if file_with_tags.stack_size > 1 {
+                name_spans.push(Span::styled(
+                    format!("[{}] ", file_with_tags.stack_size),
+                    Style::default().fg(STACK_INDICATOR).add_modifier(Modifier::BOLD),
+                ));
+            } else if file_with_tags.stack_id.is_some() {
+                name_spans.push(Span::styled("│ ", Style::default().fg(STACK_INDICATOR)));
+            }
+            if let Some((icon, color)) = indicator {
+                name_spans.push(Span::styled(icon, Style::default().fg(color)));
+            }
+            name_spans.push(Span::raw(&file.filename));
+            // Paired siblings folded into this entry
+            for ext in &file_with_tags.companions {
+                name_spans.push(Span::styled(format!(" +{}", ext), Style::default().fg(PAIR_INDICATOR)));
+            }
+            // Panoramas, tall strips and squares stand out from standard frames
+            if let Some(badge) = Orientation::of(file.width, file.height).and_then(Orientation::badge) {
+                name_spans.push(Span::styled(format!(" {}", badge), Style::default().fg(ASPECT_INDICATOR)));
+            }
+            // Name clashes with a sibling on case-insensitive filesystems
+            if !file_with_tags.name_collisions.is_empty() {
+                name_spans.push(Span::styled(" ⚠", Style::default().fg(WARNING_COLOR)));
+            }
+            let name_cell = Cell::from(Line::from(name_spans));
+
+            // Stars (with half stars) fit the column on the 5-point scale only
+            let rating = match state.rating_scale {
+                RatingScale::Five => file.rating.map(format_stars).unwrap_or_default(),
+                RatingScale::Ten => format_rating_compact(file.rating),
             };
+            let rating_cell = Cell::from(Span::styled(rating, Style::default().fg(RATING_COLOR)));
 
             // Format file size with thumbnail indicator
             let size = format_size(file.size);
-            let has_thumb = dir_path
-                .as_ref()
-                .map(|dp| has_thumbnail(&dp.join(&file.filename)))
-                .unwrap_or(false);
             let size_cell = if has_thumb {
                 Cell::from(Line::from(vec![
                     Span::raw(size),
@@ -59,11 +94,11 @@ pub fn render_file_list(frame: &mut Frame, area: Rect, state: &mut AppState) {
                 Cell::from(size)
             };
 
-            Row::new(vec![name_cell, size_cell])
+            Row::new(vec![name_cell, rating_cell, size_cell])
         })
         .collect();
 
-    let header = Row::new(vec!["Name", "Size"])
+    let header = Row::new(vec!["Name", "Rating", "Size"])
         .style(
             Style::default()
                 .fg(HEADER_COLOR)
@@ -73,6 +108,7 @@ pub fn render_file_list(frame: &mut Frame, area: Rect, state: &mut AppState) {
 
     let widths = [
         Constraint::Min(0),
+        Constraint::Length(6),
         Constraint::Length(10),
     ];
 
@@ -4,12 +4,13 @@ use ratatui::{
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
 };
 
+use crate::rating::RatingScale;
 use crate::tui::colors::{FOCUS_COLOR, HEADER_COLOR, HELP_TEXT, RATING_COLOR, TAG_COLOR, UNFOCUS_COLOR};
-use crate::tui::state::{FilterDialogFocus, FilterDialogState, RatingFilter};
+use crate::tui::state::{FilterDialogFocus, FilterDialogState, MediaFilter, RatingFilter};
 
 pub fn render_filter_dialog(frame: &mut Frame, area: Rect, dialog: &FilterDialogState) {
     let popup_width = 60;
-    let popup_height = 20;
+    let popup_height = 26;
     let x = (area.width.saturating_sub(popup_width)) / 2;
     let y = (area.height.saturating_sub(popup_height)) / 2;
 
@@ -36,6 +37,8 @@ pub fn render_filter_dialog(frame: &mut Frame, area: Rect, dialog: &FilterDialog
         .constraints([
             Constraint::Length(3), // Rating section
             Constraint::Length(3), // Media section
+            Constraint::Length(3), // Gear section
+            Constraint::Length(3), // Presets section
             Constraint::Min(8),    // Tags section
             Constraint::Length(1), // Help text
         ])
@@ -47,11 +50,17 @@ pub fn render_filter_dialog(frame: &mut Frame, area: Rect, dialog: &FilterDialog
     // Media section
     render_media_section(frame, chunks[1], dialog);
 
+    // Gear section
+    render_gear_section(frame, chunks[2], dialog);
+
+    // Presets section
+    render_presets_section(frame, chunks[3], dialog);
+
     // Tags section
-    render_tags_section(frame, chunks[2], dialog);
+    render_tags_section(frame, chunks[4], dialog);
 
     // Help text
-    render_help_text(frame, chunks[3]);
+    render_help_text(frame, chunks[5]);
 }
 
 fn render_rating_section(frame: &mut Frame, area: Rect, dialog: &FilterDialogState) {
@@ -91,16 +100,21 @@ fn render_rating_section(frame: &mut Frame, area: Rect, dialog: &FilterDialogSta
         Span::raw("  "),
     ];
 
-    // Star ratings 1-5
-    for i in 1..=5 {
-        let stars = "★".repeat(i);
-        let style = if dialog.rating_filter == RatingFilter::MinRating(i as i32) {
+    // Minimum ratings: stars on the 5-point scale, numbers on the 10-point
+    // one where ten rows of stars wouldn't fit
+    let max = dialog.rating_scale.max();
+    for i in 1..=max {
+        let label = match dialog.rating_scale {
+            RatingScale::Five => "★".repeat(i as usize),
+            RatingScale::Ten => i.to_string(),
+        };
+        let style = if dialog.rating_filter == RatingFilter::MinRating(i as f64) {
             Style::default().bg(FOCUS_COLOR).fg(Color::Black)
         } else {
             Style::default().fg(RATING_COLOR)
         };
-        spans.push(Span::styled(stars, style));
-        if i < 5 {
+        spans.push(Span::styled(label, style));
+        if i < max {
             spans.push(Span::raw("  "));
         }
     }
@@ -111,7 +125,7 @@ fn render_rating_section(frame: &mut Frame, area: Rect, dialog: &FilterDialogSta
 }
 
 fn render_media_section(frame: &mut Frame, area: Rect, dialog: &FilterDialogState) {
-    let is_focused = dialog.focus == FilterDialogFocus::VideoOnly;
+    let is_focused = dialog.focus == FilterDialogFocus::Media;
 
     let border_style = if is_focused {
         Style::default().fg(FOCUS_COLOR)
@@ -128,16 +142,112 @@ fn render_media_section(frame: &mut Frame, area: Rect, dialog: &FilterDialogStat
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    let checkbox = if dialog.video_only { "[x]" } else { "[ ]" };
-    let style = if is_focused {
+    let options = [
+        (MediaFilter::Any, "Any"),
+        (MediaFilter::Video, "Videos"),
+        (MediaFilter::Document, "Documents"),
+    ];
+    let mut spans = vec![Span::raw(" ")];
+    for (i, (media, label)) in options.iter().enumerate() {
+        if *media == dialog.media {
+            let style = if is_focused {
+                Style::default().fg(FOCUS_COLOR)
+            } else {
+                Style::default()
+            };
+            spans.push(Span::styled(format!("[{}]", label), style.add_modifier(Modifier::BOLD)));
+        } else {
+            spans.push(Span::styled(format!(" {} ", label), Style::default().fg(HELP_TEXT)));
+        }
+        if i < options.len() - 1 {
+            spans.push(Span::raw(" "));
+        }
+    }
+
+    // Orientation is worked out from dimensions, so it sits with media type
+    spans.push(Span::raw("   "));
+    spans.push(Span::styled("o", Style::default().fg(FOCUS_COLOR)));
+    spans.push(Span::raw(" "));
+    spans.push(match dialog.orientation {
+        Some(orientation) => Span::styled(
+            orientation.as_str(),
+            Style::default().fg(FOCUS_COLOR).add_modifier(Modifier::BOLD),
+        ),
+        None => Span::styled("any shape", Style::default().fg(HELP_TEXT)),
+    });
+
+    let paragraph = Paragraph::new(Line::from(spans));
+    frame.render_widget(paragraph, inner);
+}
+
+fn render_gear_section(frame: &mut Frame, area: Rect, dialog: &FilterDialogState) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(UNFOCUS_COLOR))
+        .title(" Camera / Lens ")
+        .title_style(Style::default().fg(HEADER_COLOR));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let value = |name: &Option<String>| match name {
+        Some(name) => Span::styled(name.clone(), Style::default().fg(FOCUS_COLOR).add_modifier(Modifier::BOLD)),
+        None => Span::styled("Any", Style::default().fg(HELP_TEXT)),
+    };
+    let line = Line::from(vec![
+        Span::styled(" c", Style::default().fg(FOCUS_COLOR)),
+        Span::raw(" "),
+        value(&dialog.camera),
+        Span::raw("  "),
+        Span::styled("n", Style::default().fg(FOCUS_COLOR)),
+        Span::raw(" "),
+        value(&dialog.lens),
+    ]);
+
+    frame.render_widget(Paragraph::new(line), inner);
+}
+
+fn render_presets_section(frame: &mut Frame, area: Rect, dialog: &FilterDialogState) {
+    let border_style = if dialog.preset_name.is_some() {
         Style::default().fg(FOCUS_COLOR)
     } else {
-        Style::default()
+        Style::default().fg(UNFOCUS_COLOR)
     };
 
-    let text = format!(" {} Video only", checkbox);
-    let paragraph = Paragraph::new(text).style(style);
-    frame.render_widget(paragraph, inner);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(" Presets ")
+        .title_style(Style::default().fg(HEADER_COLOR));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let line = if let Some(ref name) = dialog.preset_name {
+        Line::from(vec![
+            Span::raw(" Save as: "),
+            Span::styled(format!("{}_", name), Style::default().fg(FOCUS_COLOR)),
+            Span::styled("  Enter:Save Esc:Cancel", Style::default().fg(HELP_TEXT)),
+        ])
+    } else if dialog.presets.is_empty() {
+        Line::from(Span::styled(
+            " (none) w: save the current filter",
+            Style::default().fg(HELP_TEXT),
+        ))
+    } else {
+        // F1-F9 activate; later presets are only reachable from the CLI/API
+        let mut spans = vec![Span::raw(" ")];
+        for (i, preset) in dialog.presets.iter().take(9).enumerate() {
+            if i > 0 {
+                spans.push(Span::raw("  "));
+            }
+            spans.push(Span::styled(format!("F{}", i + 1), Style::default().fg(FOCUS_COLOR)));
+            spans.push(Span::raw(format!(" {}", preset.name)));
+        }
+        Line::from(spans)
+    };
+
+    frame.render_widget(Paragraph::new(line), inner);
 }
 
 fn render_tags_section(frame: &mut Frame, area: Rect, dialog: &FilterDialogState) {
@@ -297,7 +407,7 @@ fn render_autocomplete_list(frame: &mut Frame, area: Rect, dialog: &FilterDialog
 }
 
 fn render_help_text(frame: &mut Frame, area: Rect) {
-    let help = " hjkl/Arrows:Navigate  0:Clear  m/Esc:Close";
+    let help = " hjkl:Navigate  0:Clear  w:Save preset  m/Esc:Close";
     let paragraph = Paragraph::new(help).style(Style::default().fg(HELP_TEXT));
     frame.render_widget(paragraph, area);
 }
@@ -0,0 +1,71 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+};
+
+use crate::tui::colors::{FOCUS_COLOR, HEADER_COLOR, HELP_TEXT};
+use crate::tui::state::GotoState;
+
+pub fn render_goto_prompt(frame: &mut Frame, area: Rect, goto: &GotoState) {
+    let popup_width = 60;
+    let popup_height = 14;
+    let x = (area.width.saturating_sub(popup_width)) / 2;
+    let y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = Rect::new(x, y, popup_width, popup_height);
+
+    // Clear the area behind the popup
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Go to Directory ")
+        .title_style(
+            Style::default()
+                .fg(HEADER_COLOR)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Input line
+            Constraint::Min(0),    // Candidates
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner);
+
+    let input = Paragraph::new(format!(" :{}_", goto.input)).style(Style::default().fg(FOCUS_COLOR));
+    frame.render_widget(input, chunks[0]);
+
+    // Keep the selected candidate in view
+    let visible = chunks[1].height as usize;
+    let offset = (goto.selected + 1).saturating_sub(visible);
+    let items: Vec<ListItem> = if goto.matches.is_empty() {
+        vec![ListItem::new("   No matching directories").style(Style::default().fg(HELP_TEXT))]
+    } else {
+        goto.matches
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(visible)
+            .map(|(i, (_, path))| {
+                let style = if i == goto.selected {
+                    Style::default().bg(FOCUS_COLOR).fg(Color::Black)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(format!("   {}", path)).style(style)
+            })
+            .collect()
+    };
+    frame.render_widget(List::new(items), chunks[1]);
+
+    let help = Paragraph::new(" Enter:Go  Tab:Complete  Up/Down:Select  Esc:Cancel")
+        .style(Style::default().fg(HELP_TEXT));
+    frame.render_widget(help, chunks[2]);
+}
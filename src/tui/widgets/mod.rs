@@ -1,17 +1,31 @@
+mod breadcrumb;
+mod dashboard;
 mod details_panel;
 mod directory_tree;
+mod error_popup;
 mod file_list;
 mod filter_dialog;
+mod goto_prompt;
+mod onboarding;
 mod preview;
 mod rename_dialog;
 mod status_bar;
+mod sync_progress;
 mod tag_popup;
+mod usage_view;
 
+pub use breadcrumb::render_breadcrumb;
+pub use dashboard::render_dashboard;
 pub use details_panel::render_details_panel;
 pub use directory_tree::render_directory_tree;
+pub use error_popup::render_error_popup;
 pub use file_list::render_file_list;
 pub use filter_dialog::render_filter_dialog;
-pub use preview::{create_protocol, generate_dir_preview, render_preview};
+pub use goto_prompt::render_goto_prompt;
+pub use onboarding::render_onboarding;
+pub use preview::{create_protocol, generate_dir_preview, picker_font_size, render_preview};
 pub use rename_dialog::render_rename_dialog;
 pub use status_bar::render_status_bar;
+pub use sync_progress::render_sync_progress;
 pub use tag_popup::render_tag_popup;
+pub use usage_view::render_usage_view;
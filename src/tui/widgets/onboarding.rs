@@ -0,0 +1,84 @@
+use ratatui::{
+    layout::Rect,
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::tui::colors::{FOCUS_COLOR, HEADER_COLOR, HELP_TEXT};
+use crate::tui::state::{OnboardingField, OnboardingState};
+
+pub fn render_onboarding(frame: &mut Frame, area: Rect, library: &str, state: &OnboardingState) {
+    let width = 68.min(area.width);
+    let height = 16.min(area.height);
+    let x = (area.width.saturating_sub(width)) / 2;
+    let y = (area.height.saturating_sub(height)) / 2;
+
+    let dialog_area = Rect::new(x, y, width, height);
+
+    frame.render_widget(Clear, dialog_area);
+
+    let checkbox = |checked: bool| if checked { "[x]" } else { "[ ]" };
+    let needs_scan = |checked: bool| {
+        if state.scan { checkbox(checked) } else { "[-]" }
+    };
+    let rows = [
+        (OnboardingField::Scan, format!("{} Scan the directory now", checkbox(state.scan))),
+        (OnboardingField::Hash, format!("{} Compute file hashes (finds duplicates)", needs_scan(state.hash))),
+        (OnboardingField::Thumbnails, format!("{} Generate thumbnails", needs_scan(state.thumbnails))),
+        (OnboardingField::Exclude, format!("Exclude: {}", state.exclude)),
+        (OnboardingField::Start, "Start".to_string()),
+    ];
+
+    let mut lines: Vec<Line> = vec![
+        Line::from(vec![
+            Span::styled(" No picman library in ", Style::default().fg(HELP_TEXT)),
+            Span::styled(library, Style::default().fg(HEADER_COLOR)),
+            Span::styled(" yet.", Style::default().fg(HELP_TEXT)),
+        ]),
+        Line::from(Span::styled(" Choose how to set it up:", Style::default().fg(HELP_TEXT))),
+        Line::from(""),
+    ];
+
+    for (field, label) in rows {
+        if field == OnboardingField::Start {
+            lines.push(Line::from(""));
+        }
+        let is_selected = field == state.selected;
+        let mut spans = vec![
+            Span::styled(if is_selected { " ▸ " } else { "   " }, Style::default().fg(FOCUS_COLOR)),
+            Span::styled(
+                label,
+                if is_selected {
+                    Style::default().bg(FOCUS_COLOR).fg(Color::Black)
+                } else {
+                    Style::default().fg(Color::White)
+                },
+            ),
+        ];
+        if field == OnboardingField::Exclude {
+            if is_selected {
+                spans.push(Span::styled("█", Style::default().fg(FOCUS_COLOR)));
+            }
+            lines.push(Line::from(spans));
+            lines.push(Line::from(Span::styled(
+                "     comma-separated names, e.g. @eaDir, *.tmp",
+                Style::default().fg(HELP_TEXT),
+            )));
+            continue;
+        }
+        lines.push(Line::from(spans));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        " ↑↓/Tab: move  Space: toggle  Enter: start  Esc: quit",
+        Style::default().fg(HELP_TEXT),
+    )));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Welcome to picman ")
+        .title_style(Style::default().fg(HEADER_COLOR).add_modifier(Modifier::BOLD));
+
+    frame.render_widget(Paragraph::new(lines).block(block), dialog_area);
+}
@@ -10,10 +10,11 @@ use std::sync::{Mutex, OnceLock};
 
 use crate::db::Directory;
 use crate::thumbnails::{
-    self, generate_dir_preview_from_paths, get_cached_dir_preview, is_image_file,
+    self, collect_preview_images_standalone, generate_dir_preview_from_paths,
+    get_cached_dir_preview, TempPreviewState,
 };
 use crate::tui::colors::UNFOCUS_COLOR;
-use crate::tui::state::{AppState, Focus};
+use crate::tui::state::{AppState, Focus, PreviewZoom};
 
 // Global picker (created once, thread-safe)
 static PICKER: OnceLock<Mutex<Option<Picker>>> = OnceLock::new();
@@ -34,115 +35,24 @@ pub fn create_protocol(image: DynamicImage) -> Option<Box<dyn StatefulProtocol>>
     Some(picker.new_resize_protocol(image))
 }
 
-// ==================== AppState-dependent preview image collection ====================
-
-const DIR_PREVIEW_MAX_IMAGES: usize = 12;
-
-/// Collect up to 12 image paths for directory preview
-/// If dir has subdirs: 1 image from each of up to 12 subdirs (searching recursively)
-/// If no subdirs: up to 12 images from current directory
-fn collect_preview_images(state: &AppState, dir: &Directory) -> Vec<PathBuf> {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-
-    let mut images = Vec::new();
-
-    // Get child directories
-    let child_dirs: Vec<&Directory> = state
-        .tree
-        .directories
-        .iter()
-        .filter(|d| d.parent_id == Some(dir.id))
-        .collect();
-
-    if !child_dirs.is_empty() {
-        // Has subdirs: pick up to 12 subdirs and get 1 image from each
-        let mut hasher = DefaultHasher::new();
-        dir.id.hash(&mut hasher);
-        let seed = hasher.finish();
-
-        // Deterministically select up to 12 subdirs
-        let mut selected_dirs: Vec<_> = child_dirs.iter().enumerate().collect();
-        selected_dirs.sort_by_key(|(i, _)| {
-            let mut h = DefaultHasher::new();
-            seed.hash(&mut h);
-            i.hash(&mut h);
-            h.finish()
-        });
-
-        for (_, subdir) in selected_dirs.into_iter().take(DIR_PREVIEW_MAX_IMAGES) {
-            if let Some(img) = find_image_in_dir_recursive(state, subdir.id) {
-                images.push(img);
-            }
-        }
-    } else {
-        // No subdirs: pick up to 12 images from current directory
-        if let Ok(files) = state.db.get_files_in_directory(dir.id) {
-            let image_files: Vec<_> = files
-                .into_iter()
-                .filter(|f| {
-                    let path = get_file_path(state, dir, &f.filename);
-                    is_image_file(&path)
-                })
-                .collect();
-
-            // Deterministic selection using dir_id as seed
-            let mut hasher = DefaultHasher::new();
-            dir.id.hash(&mut hasher);
-            let seed = hasher.finish();
-
-            let mut indexed: Vec<_> = image_files.iter().enumerate().collect();
-            indexed.sort_by_key(|(i, _)| {
-                let mut h = DefaultHasher::new();
-                seed.hash(&mut h);
-                i.hash(&mut h);
-                h.finish()
-            });
-
-            for (_, file) in indexed.into_iter().take(DIR_PREVIEW_MAX_IMAGES) {
-                images.push(get_file_path(state, dir, &file.filename));
-            }
-        }
-    }
-
-    images
+/// Terminal cell size in pixels, if the terminal reports it
+pub fn picker_font_size() -> Option<(u16, u16)> {
+    let picker_guard = get_picker_mutex().lock().ok()?;
+    picker_guard.as_ref().map(|picker| picker.font_size)
 }
 
-/// Find first image in a directory tree (recursive search)
-fn find_image_in_dir_recursive(state: &AppState, dir_id: i64) -> Option<PathBuf> {
-    // First try to find an image directly in this directory
-    let dir = state.tree.directories.iter().find(|d| d.id == dir_id)?;
-
-    if let Ok(files) = state.db.get_files_in_directory(dir_id) {
-        for file in &files {
-            let path = get_file_path(state, dir, &file.filename);
-            if is_image_file(&path) {
-                return Some(path);
-            }
-        }
-    }
-
-    // No image found, search child directories
-    let child_dirs: Vec<i64> = state
-        .tree
-        .directories
-        .iter()
-        .filter(|d| d.parent_id == Some(dir_id))
-        .map(|d| d.id)
-        .collect();
-
-    for child_id in child_dirs {
-        if let Some(img) = find_image_in_dir_recursive(state, child_id) {
-            return Some(img);
-        }
-    }
-
-    None
-}
+// ==================== AppState-dependent preview image collection ====================
 
-/// Get full path to a file
-fn get_file_path(state: &AppState, dir: &Directory, filename: &str) -> PathBuf {
-    dir.file_path(&state.library_path, filename)
+/// Collect up to 12 image paths for directory preview, picked by the
+/// library's `[previews] cover` order
+fn collect_preview_images(state: &AppState, dir: &Directory) -> Vec<PathBuf> {
+    let temp_state = TempPreviewState {
+        library_path: state.library_path.clone(),
+        db: &state.db,
+        directories: &state.tree.directories,
+        cover: state.preview_cover,
+    };
+    collect_preview_images_standalone(&temp_state, dir)
 }
 
 /// Generate dynamic grid composite preview for a directory
@@ -187,7 +97,7 @@ fn render_directory_preview(frame: &mut Frame, area: Rect, state: &AppState) {
     // Cache hit — render directly from LRU
     {
         let mut cache = state.dir_preview_cache.borrow_mut();
-        if let Some(entry) = cache.get_mut(&key) {
+        if let Some(entry) = cache.get_mut(&key, PreviewZoom::Fit) {
             if let Some(ref mut protocol) = entry.protocol {
                 let image_widget =
                     StatefulImage::new(None).resize(Resize::Fit(Some(FilterType::Lanczos3)));
@@ -200,7 +110,7 @@ fn render_directory_preview(frame: &mut Frame, area: Rect, state: &AppState) {
     // Cache miss — check if already loading
     {
         let loader = state.preview_loader.borrow();
-        if loader.is_pending(&key) {
+        if loader.is_pending(&key, PreviewZoom::Fit) {
             let placeholder = Paragraph::new("Loading preview...");
             frame.render_widget(placeholder, inner);
             return;
@@ -231,10 +141,15 @@ fn render_directory_preview(frame: &mut Frame, area: Rect, state: &AppState) {
 
 /// Render file preview (single image/video thumbnail)
 fn render_file_preview(frame: &mut Frame, area: Rect, state: &AppState) {
+    let zoom = state.preview_zoom;
+    let title = match zoom.label() {
+        Some(label) => format!(" Preview [{}] ", label),
+        None => " Preview ".to_string(),
+    };
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(UNFOCUS_COLOR))
-        .title(" Preview ");
+        .title(title);
 
     // Get selected file path
     let file_path = match state.selected_file_path() {
@@ -249,7 +164,7 @@ fn render_file_preview(frame: &mut Frame, area: Rect, state: &AppState) {
     };
 
     // Early return for non-media files (extension check only — no disk I/O)
-    if !thumbnails::is_image_file(&file_path) && !thumbnails::is_video_file(&file_path) {
+    if !thumbnails::is_thumbnailable_file(&file_path) {
         let info = format!("File: {}", file_path.display());
         let placeholder = Paragraph::new(info)
             .block(block)
@@ -270,12 +185,12 @@ fn render_file_preview(frame: &mut Frame, area: Rect, state: &AppState) {
     // but don't queue new loads — the user is still scrolling.
     if state.skip_preview {
         let mut cache = state.preview_cache.borrow_mut();
-        if let Some(entry) = cache.get_mut(&file_path) {
+        if let Some(entry) = cache.get_mut(&file_path, zoom) {
             if let Some(ref mut protocol) = entry.protocol {
                 let image_widget =
                     StatefulImage::new(None).resize(Resize::Fit(Some(FilterType::Lanczos3)));
                 frame.render_stateful_widget(image_widget, inner, protocol);
-                *state.render_protocol.borrow_mut() = Some(file_path);
+                *state.render_protocol.borrow_mut() = Some((file_path, zoom));
                 return;
             }
         }
@@ -288,12 +203,12 @@ fn render_file_preview(frame: &mut Frame, area: Rect, state: &AppState) {
     // Try to render directly from cache — instant path for preloaded files
     {
         let mut cache = state.preview_cache.borrow_mut();
-        if let Some(entry) = cache.get_mut(&file_path) {
+        if let Some(entry) = cache.get_mut(&file_path, zoom) {
             if let Some(ref mut protocol) = entry.protocol {
                 let image_widget =
                     StatefulImage::new(None).resize(Resize::Fit(Some(FilterType::Lanczos3)));
                 frame.render_stateful_widget(image_widget, inner, protocol);
-                *state.render_protocol.borrow_mut() = Some(file_path);
+                *state.render_protocol.borrow_mut() = Some((file_path, zoom));
                 return;
             }
         }
@@ -302,9 +217,9 @@ fn render_file_preview(frame: &mut Frame, area: Rect, state: &AppState) {
     // Not in cache — queue for background loading (all disk I/O on worker thread)
     {
         let mut loader = state.preview_loader.borrow_mut();
-        if !loader.is_pending(&file_path) {
+        if !loader.is_pending(&file_path, zoom) {
             if let Some(dir_id) = state.current_dir_id {
-                loader.queue_file_load(file_path, dir_id);
+                loader.queue_file_load(file_path, zoom, dir_id);
             }
         }
     }
@@ -316,10 +231,10 @@ fn render_file_preview(frame: &mut Frame, area: Rect, state: &AppState) {
 /// Render the last successfully rendered protocol from cache (keeps previous image visible).
 /// Used during rapid navigation or while waiting for a new image to load.
 fn render_fallback_protocol(frame: &mut Frame, area: Rect, state: &AppState) {
-    let render_path = state.render_protocol.borrow().clone();
-    if let Some(ref path) = render_path {
+    let render_key = state.render_protocol.borrow().clone();
+    if let Some((ref path, zoom)) = render_key {
         let mut cache = state.preview_cache.borrow_mut();
-        if let Some(entry) = cache.get_mut(path) {
+        if let Some(entry) = cache.get_mut(path, zoom) {
             if let Some(ref mut protocol) = entry.protocol {
                 let image_widget =
                     StatefulImage::new(None).resize(Resize::Fit(Some(FilterType::Lanczos3)));
@@ -1,7 +1,7 @@
 use ratatui::{layout::Rect, prelude::*, widgets::Paragraph};
 
-use crate::tui::colors::{FOCUS_COLOR, RATING_COLOR, STATUS_BAR_BG, STATUS_BAR_FG, SUCCESS_COLOR, TAG_COLOR, VIDEO_INDICATOR, WARNING_COLOR};
-use crate::tui::state::{AppState, Focus, RatingFilter};
+use crate::tui::colors::{DOCUMENT_INDICATOR, FOCUS_COLOR, HELP_TEXT, RATING_COLOR, STATUS_BAR_BG, STATUS_BAR_FG, SUCCESS_COLOR, TAG_COLOR, VIDEO_INDICATOR, WARNING_COLOR};
+use crate::tui::state::{AppState, BackgroundProgress, Focus, MediaFilter, RatingFilter, TaskManager};
 
 /// Spinner frames for indeterminate progress
 const SPINNER_FRAMES: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
@@ -17,71 +17,142 @@ fn format_duration(secs: u64) -> String {
     }
 }
 
-pub fn render_status_bar(frame: &mut Frame, area: Rect, state: &AppState) {
+/// Items per second, with a decimal while it's slow
+fn format_rate(rate: f64) -> String {
+    if rate < 10.0 {
+        format!("{:.1}/s", rate)
+    } else {
+        format!("{:.0}/s", rate)
+    }
+}
+
+/// Spinner, label, bar, counts, elapsed time, rate and ETA for one operation
+fn task_spans(progress: &BackgroundProgress) -> Vec<Span<'static>> {
     use std::sync::atomic::Ordering;
 
-    // Show background operation progress if active
-    if let Some(ref progress) = state.background_progress {
-        let completed = progress.completed.load(Ordering::Relaxed);
-        let total = progress.total;
-        let elapsed = progress.start_time.elapsed();
-        let elapsed_secs = elapsed.as_secs();
+    let completed = progress.completed.load(Ordering::Relaxed);
+    let total = progress.total;
+    let elapsed = progress.start_time.elapsed();
+    let elapsed_secs = elapsed.as_secs();
 
-        let mut spans: Vec<Span> = Vec::new();
+    let mut spans: Vec<Span> = Vec::new();
 
-        // Spinner animation (based on elapsed time)
-        let spinner_idx = (elapsed.as_millis() / 80) as usize % SPINNER_FRAMES.len();
-        spans.push(Span::styled(
-            format!("{} ", SPINNER_FRAMES[spinner_idx]),
-            Style::default().fg(FOCUS_COLOR),
-        ));
+    // Spinner animation (based on elapsed time)
+    let spinner_idx = (elapsed.as_millis() / 80) as usize % SPINNER_FRAMES.len();
+    spans.push(Span::styled(
+        format!("{} ", SPINNER_FRAMES[spinner_idx]),
+        Style::default().fg(FOCUS_COLOR),
+    ));
 
-        // Operation label
-        spans.push(Span::raw(format!("{} ", progress.operation.label())));
+    // Operation label
+    spans.push(Span::raw(format!("{} ", progress.operation.label())));
 
-        // Visual progress bar
-        let bar_width = 20usize;
-        let filled = if total > 0 {
-            (completed * bar_width / total).min(bar_width)
-        } else {
-            0
-        };
-        let empty = bar_width - filled;
+    // Visual progress bar
+    let bar_width = 20usize;
+    let filled = if total > 0 {
+        (completed * bar_width / total).min(bar_width)
+    } else {
+        0
+    };
+    let empty = bar_width - filled;
+
+    spans.push(Span::styled("[", Style::default().fg(Color::Gray)));
+    spans.push(Span::styled(
+        "█".repeat(filled),
+        Style::default().fg(FOCUS_COLOR),
+    ));
+    spans.push(Span::styled(
+        "░".repeat(empty),
+        Style::default().fg(Color::Gray),
+    ));
+    spans.push(Span::styled("] ", Style::default().fg(Color::Gray)));
+
+    // Count and percentage
+    let pct = if total > 0 { completed * 100 / total } else { 0 };
+    spans.push(Span::raw(format!("{}/{} ({}%) ", completed, total, pct)));
+
+    // Elapsed time
+    spans.push(Span::styled(
+        format_duration(elapsed_secs),
+        Style::default().fg(WARNING_COLOR),
+    ));
+
+    if progress.cancelled.load(Ordering::Relaxed) {
+        spans.push(Span::styled(" | cancelling", Style::default().fg(WARNING_COLOR)));
+    } else if let Some(rate) = progress.throughput.rate().filter(|_| completed < total) {
+        // Rate and ETA over the last half minute, once there's that much to go on
+        spans.push(Span::raw(format!(" | {}", format_rate(rate))));
+        if let Some(eta) = progress.throughput.eta(total - completed) {
+            spans.push(Span::raw(" | ETA "));
+            spans.push(Span::styled(
+                format_duration(eta.as_secs()),
+                Style::default().fg(SUCCESS_COLOR),
+            ));
+        }
+    }
 
-        spans.push(Span::styled("[", Style::default().fg(Color::Gray)));
-        spans.push(Span::styled(
-            "█".repeat(filled),
-            Style::default().fg(FOCUS_COLOR),
-        ));
-        spans.push(Span::styled(
-            "░".repeat(empty),
-            Style::default().fg(Color::Gray),
-        ));
-        spans.push(Span::styled("] ", Style::default().fg(Color::Gray)));
+    spans
+}
 
-        // Count and percentage
-        let pct = if total > 0 { completed * 100 / total } else { 0 };
-        spans.push(Span::raw(format!("{}/{} ({}%) ", completed, total, pct)));
+/// The expanded status area: every running and queued operation, then
+/// the recently finished ones
+fn render_task_list(frame: &mut Frame, area: Rect, tasks: &TaskManager) {
+    let mut lines: Vec<Line> = vec![Line::from(vec![
+        Span::styled("Tasks", Style::default().add_modifier(Modifier::BOLD)),
+        Span::styled("  j/k:select  x:cancel  b/Esc:close", Style::default().fg(HELP_TEXT)),
+    ])];
+
+    let marker = |row: usize| if row == tasks.selected { "▸ " } else { "  " };
+    for (row, progress) in tasks.running.iter().enumerate() {
+        let mut spans = vec![Span::styled(marker(row), Style::default().fg(FOCUS_COLOR))];
+        spans.extend(task_spans(progress));
+        lines.push(Line::from(spans));
+    }
+    for (i, operation) in tasks.queue.iter().enumerate() {
+        let row = tasks.running.len() + i;
+        lines.push(Line::from(vec![
+            Span::styled(marker(row), Style::default().fg(FOCUS_COLOR)),
+            Span::styled(format!("queued: {}", operation.label()), Style::default().fg(Color::Magenta)),
+        ]));
+    }
+    if tasks.row_count() == 0 {
+        lines.push(Line::from(Span::styled("  No background operations", Style::default().fg(HELP_TEXT))));
+    }
 
-        // Elapsed time
-        spans.push(Span::styled(
-            format_duration(elapsed_secs),
-            Style::default().fg(WARNING_COLOR),
-        ));
+    for task in tasks.finished.iter().rev() {
+        let (mark, color) = if task.cancelled { ("✗", WARNING_COLOR) } else { ("✓", SUCCESS_COLOR) };
+        lines.push(Line::from(Span::styled(
+            format!("{} {} ({})", mark, task.summary(), format_duration(task.elapsed.as_secs())),
+            Style::default().fg(color),
+        )));
+    }
 
-        // ETA (only show if we have meaningful progress)
-        if completed > 0 && completed < total {
-            let rate = completed as f64 / elapsed_secs.max(1) as f64;
-            let remaining = (total - completed) as f64 / rate;
-            spans.push(Span::raw(" | ETA "));
+    let list = Paragraph::new(lines).style(Style::default().bg(STATUS_BAR_BG).fg(STATUS_BAR_FG));
+    frame.render_widget(list, area);
+}
+
+pub fn render_status_bar(frame: &mut Frame, area: Rect, state: &AppState) {
+    if state.tasks.expanded {
+        render_task_list(frame, area, &state.tasks);
+        return;
+    }
+
+    // Show background operation progress if active
+    if let Some(progress) = state.tasks.running.first() {
+        let mut spans = task_spans(progress);
+
+        // Other operations running alongside
+        let others = state.tasks.running.len() - 1;
+        if others > 0 {
+            spans.push(Span::raw(" | "));
             spans.push(Span::styled(
-                format_duration(remaining as u64),
-                Style::default().fg(SUCCESS_COLOR),
+                format!("+{} running", others),
+                Style::default().fg(FOCUS_COLOR),
             ));
         }
 
         // Queue count (if any)
-        let queue_len = state.operation_queue.len();
+        let queue_len = state.tasks.queue.len();
         if queue_len > 0 {
             spans.push(Span::raw(" | "));
             spans.push(Span::styled(
@@ -90,9 +161,9 @@ pub fn render_status_bar(frame: &mut Frame, area: Rect, state: &AppState) {
             ));
         }
 
-        // Cancel hint
+        // Task list and cancel hints
         spans.push(Span::styled(
-            " [q]",
+            " [b]tasks [q]",
             Style::default().fg(Color::Gray),
         ));
 
@@ -120,15 +191,34 @@ pub fn render_status_bar(frame: &mut Frame, area: Rect, state: &AppState) {
     };
     spans.push(Span::raw(format!("[{}]", focus_str)));
 
+    if state.cull_mode {
+        spans.push(Span::styled(" [Cull]", Style::default().fg(RATING_COLOR)));
+    }
+
     // Filter indicator with colors
     if state.filter.is_active() {
         spans.push(Span::raw(" [Filter: "));
 
         let mut first = true;
 
-        // Video filter (magenta)
-        if state.filter.video_only {
-            spans.push(Span::styled("video", Style::default().fg(VIDEO_INDICATOR)));
+        // Media filter (magenta for video, red for documents)
+        match state.filter.media {
+            MediaFilter::Any => {}
+            MediaFilter::Video => {
+                spans.push(Span::styled("video", Style::default().fg(VIDEO_INDICATOR)));
+                first = false;
+            }
+            MediaFilter::Document => {
+                spans.push(Span::styled("documents", Style::default().fg(DOCUMENT_INDICATOR)));
+                first = false;
+            }
+        }
+
+        if let Some(orientation) = state.filter.orientation {
+            if !first {
+                spans.push(Span::raw(" "));
+            }
+            spans.push(Span::raw(orientation.as_str()));
             first = false;
         }
 
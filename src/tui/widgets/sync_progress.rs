@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use ratatui::{
+    layout::Rect,
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::tui::colors::{FOCUS_COLOR, HEADER_COLOR, HELP_TEXT};
+use crate::vfs::ScanProgress;
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+pub fn render_sync_progress(
+    frame: &mut Frame,
+    area: Rect,
+    library: &str,
+    progress: &ScanProgress,
+    elapsed: Duration,
+) {
+    let width = 60.min(area.width);
+    let height = 9.min(area.height);
+    let x = (area.width.saturating_sub(width)) / 2;
+    let y = (area.height.saturating_sub(height)) / 2;
+
+    let dialog_area = Rect::new(x, y, width, height);
+
+    frame.render_widget(Clear, dialog_area);
+
+    let spinner = SPINNER[(elapsed.as_millis() / 100) as usize % SPINNER.len()];
+    let secs = elapsed.as_secs();
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(format!(" {} ", spinner), Style::default().fg(FOCUS_COLOR)),
+            Span::styled("Syncing ", Style::default().fg(HELP_TEXT)),
+            Span::styled(library, Style::default().fg(HEADER_COLOR)),
+        ]),
+        Line::from(""),
+        Line::from(format!("   Directories scanned: {}", progress.dirs())),
+        Line::from(format!("   Files found:         {}", progress.files())),
+        Line::from(Span::styled(
+            format!("   Elapsed:             {}:{:02}", secs / 60, secs % 60),
+            Style::default().fg(HELP_TEXT),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            " Esc/s: skip and browse the library as last synced",
+            Style::default().fg(HELP_TEXT),
+        )),
+    ];
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Startup sync ")
+        .title_style(Style::default().fg(HEADER_COLOR).add_modifier(Modifier::BOLD));
+
+    frame.render_widget(Paragraph::new(lines).block(block), dialog_area);
+}
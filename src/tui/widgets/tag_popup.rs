@@ -9,7 +9,7 @@ use crate::tui::state::TagInputState;
 
 pub fn render_tag_popup(frame: &mut Frame, area: Rect, tag_input: &TagInputState) {
     let popup_width = 60;
-    let popup_height = 12;
+    let popup_height = 13;
     let x = (area.width.saturating_sub(popup_width)) / 2;
     let y = (area.height.saturating_sub(popup_height)) / 2;
 
@@ -30,19 +30,42 @@ pub fn render_tag_popup(frame: &mut Frame, area: Rect, tag_input: &TagInputState
     let inner = block.inner(popup_area);
     frame.render_widget(block, popup_area);
 
-    // Split into input line, autocomplete list, and help text
+    // Split into input line, recent tags, autocomplete list, and help text
+    let recent_height = if tag_input.recent_tags.is_empty() { 0 } else { 1 };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(1), // Input line
-            Constraint::Min(0),   // Autocomplete list
-            Constraint::Length(1), // Help text
+            Constraint::Length(1),             // Input line
+            Constraint::Length(recent_height), // Recent tags
+            Constraint::Min(0),                // Autocomplete list
+            Constraint::Length(1),             // Help text
         ])
         .split(inner);
 
     render_input_line(frame, chunks[0], tag_input);
-    render_autocomplete(frame, chunks[1], tag_input);
-    render_help(frame, chunks[2], tag_input);
+    render_recent_tags(frame, chunks[1], tag_input);
+    render_autocomplete(frame, chunks[2], tag_input);
+    render_help(frame, chunks[3], tag_input);
+}
+
+/// Recent tags with the number key that toggles each; dimmed while typing,
+/// when the keys go to the input instead
+fn render_recent_tags(frame: &mut Frame, area: Rect, tag_input: &TagInputState) {
+    if area.height == 0 {
+        return;
+    }
+    let key_style = if tag_input.editing {
+        Style::default().fg(HELP_TEXT)
+    } else {
+        Style::default().bg(FOCUS_COLOR).fg(Color::Black)
+    };
+    let mut spans = vec![Span::raw(" ")];
+    for (i, tag) in tag_input.recent_tags.iter().enumerate() {
+        let check = if tag_input.is_applied(tag) { "✓" } else { "" };
+        spans.push(Span::styled(format!("{}", i + 1), key_style));
+        spans.push(Span::styled(format!("{}{}  ", check, tag), Style::default().fg(TAG_COLOR)));
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
 }
 
 fn render_input_line(frame: &mut Frame, area: Rect, tag_input: &TagInputState) {
@@ -119,7 +142,7 @@ fn render_help(frame: &mut Frame, area: Rect, tag_input: &TagInputState) {
     let text = if tag_input.editing {
         " Esc:Cancel  Enter:Toggle  ↑↓:Select"
     } else {
-        " i:Edit  j/k:Nav  Enter:Toggle  Esc:Close"
+        " i:Edit  j/k:Nav  Enter:Toggle  1-9:Recent  Esc:Close"
     };
     let paragraph = Paragraph::new(text).style(Style::default().fg(HELP_TEXT));
     frame.render_widget(paragraph, area);
@@ -0,0 +1,114 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+};
+
+use crate::tui::colors::{FOCUS_COLOR, HEADER_COLOR, HELP_TEXT};
+use crate::tui::state::UsageViewState;
+
+const BAR_WIDTH: usize = 24;
+
+pub fn render_usage_view(frame: &mut Frame, area: Rect, view: &UsageViewState) {
+    let popup_width = (area.width * 3 / 4).max(60).min(area.width);
+    let popup_height = (area.height * 3 / 4).max(12).min(area.height);
+    let x = (area.width.saturating_sub(popup_width)) / 2;
+    let y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = Rect::new(x, y, popup_width, popup_height);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Disk Usage ")
+        .title_style(Style::default().fg(HEADER_COLOR).add_modifier(Modifier::BOLD));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2), // Directory and total
+            Constraint::Min(1),    // Entries
+            Constraint::Length(1), // Archive hint
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner);
+
+    let node = &view.node;
+    let dir_name = if node.path.is_empty() { "." } else { &node.path };
+    let header = Paragraph::new(Line::from(vec![
+        Span::styled(" Directory: ", Style::default().fg(HELP_TEXT)),
+        Span::styled(dir_name, Style::default().fg(HEADER_COLOR)),
+        Span::styled(
+            format!("  {} in {} files", format_size(node.size), node.file_count),
+            Style::default().fg(HELP_TEXT),
+        ),
+    ]));
+    frame.render_widget(header, chunks[0]);
+
+    // Bars are relative to the whole directory, so they show its share
+    let total = node.size.max(1);
+    let mut items: Vec<ListItem> = node
+        .children
+        .iter()
+        .map(|entry| {
+            let filled = (entry.size as f64 / total as f64 * BAR_WIDTH as f64).round() as usize;
+            let filled = filled.clamp(usize::from(entry.size > 0), BAR_WIDTH);
+            let name = if entry.is_dir { format!("{}/", entry.name) } else { entry.name.clone() };
+            let name_style = if entry.is_dir {
+                Style::default().fg(HEADER_COLOR)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!(" {}", "█".repeat(filled)), Style::default().fg(FOCUS_COLOR)),
+                Span::styled("░".repeat(BAR_WIDTH - filled), Style::default().fg(HELP_TEXT)),
+                Span::raw(format!(" {:>9}  ", format_size(entry.size))),
+                Span::styled(name, name_style),
+            ]))
+        })
+        .collect();
+    if node.other_size > 0 {
+        items.push(ListItem::new(Span::styled(
+            format!(" {} in smaller entries", format_size(node.other_size)),
+            Style::default().fg(HELP_TEXT),
+        )));
+    }
+
+    let list = List::new(items)
+        .highlight_style(Style::default().bg(FOCUS_COLOR).fg(Color::Black));
+    let mut list_state = ListState::default();
+    if !node.children.is_empty() {
+        list_state.select(Some(view.selected));
+    }
+    frame.render_stateful_widget(list, chunks[1], &mut list_state);
+
+    let hint = match view.selected_entry() {
+        Some(entry) if entry.is_dir => format!(" picman archive <library> --to s3://… --dir \"{}\"", entry.path),
+        _ => String::new(),
+    };
+    frame.render_widget(Paragraph::new(hint).style(Style::default().fg(HELP_TEXT)), chunks[2]);
+
+    let help = Paragraph::new(" j/k: move  Enter/l: open  h: up  g: go to  u/Esc: close")
+        .style(Style::default().fg(HELP_TEXT));
+    frame.render_widget(help, chunks[3]);
+}
+
+fn format_size(bytes: i64) -> String {
+    const KB: i64 = 1024;
+    const MB: i64 = KB * 1024;
+    const GB: i64 = MB * 1024;
+
+    if bytes >= GB {
+        format!("{:.1} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.1} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.1} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
@@ -0,0 +1,1134 @@
+//! Filesystem access for a library, local or remote.
+//!
+//! A local library is read straight from disk. A remote library lives on a
+//! server reachable over SSH; the library path on this machine is a mirror
+//! that holds the database, config and any originals fetched so far. Sync
+//! lists the remote tree with `find`, and originals are downloaded into the
+//! mirror the first time something needs their bytes (previews, metadata).
+//!
+//! Remote access shells out to the system `ssh` client, so keys, agents and
+//! `~/.ssh/config` host aliases work as they do on the command line. The
+//! server needs a POSIX shell and GNU `find` (for `-printf`).
+
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use tracing::{debug, info, instrument, warn};
+use walkdir::{DirEntry, WalkDir};
+
+use crate::config::{Config, RemoteConfig};
+use crate::scanner::is_media_file;
+use crate::library_path::LibraryPath;
+
+/// A directory or media file in the library, relative to its root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsEntry {
+    pub relative_path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub mtime: i64,
+}
+
+/// Running counts of what a scan has found so far, readable from another
+/// thread while the scan runs. Setting `cancel` makes a local walk stop early;
+/// callers must then discard the partial results.
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    dirs: AtomicUsize,
+    files: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl ScanProgress {
+    pub fn dirs(&self) -> usize {
+        self.dirs.load(Ordering::Relaxed)
+    }
+
+    pub fn files(&self) -> usize {
+        self.files.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn record(&self, entry: &FsEntry) {
+        let counter = if entry.is_dir { &self.dirs } else { &self.files };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Read access to a library tree. Hidden entries (dot-prefixed), entries
+/// matching the `[scan] exclude` patterns and non-media files are never
+/// returned. Symlinks are skipped unless `[scan] follow_symlinks` is set,
+/// and dot-entries unless `[scan] dotfiles` is.
+pub trait LibraryFs: Send + Sync {
+    /// All directories below the root, parents before children
+    fn walk_directories(&self) -> Result<Vec<FsEntry>>;
+
+    /// All directories and media files below the root, parents before children
+    fn walk(&self) -> Result<Vec<FsEntry>>;
+
+    /// The directory `dir` (not the root) followed by all directories and
+    /// media files below it, parents before children. Empty if `dir` no
+    /// longer exists.
+    fn walk_subtree(&self, dir: &str) -> Result<Vec<FsEntry>>;
+
+    /// Media files directly inside the given directories ("" is the root).
+    /// Directories that no longer exist are skipped.
+    fn list_files(&self, dirs: &[&str]) -> Result<Vec<FsEntry>>;
+
+    /// Make sure `path` (an absolute path inside the library) is readable on
+    /// this machine, fetching it if necessary
+    fn ensure_local(&self, path: &Path) -> Result<()>;
+
+    /// Whether originals live elsewhere and are fetched on demand
+    fn is_remote(&self) -> bool {
+        false
+    }
+}
+
+/// Open the filesystem for a library, honouring its `[remote]` config
+pub fn open_library_fs(library_path: &Path) -> Result<Arc<dyn LibraryFs>> {
+    open_library_fs_with_progress(library_path, Arc::default())
+}
+
+/// Like [`open_library_fs`], counting every entry listed into `progress`
+pub fn open_library_fs_with_progress(
+    library_path: &Path,
+    progress: Arc<ScanProgress>,
+) -> Result<Arc<dyn LibraryFs>> {
+    let config = Config::load(library_path)?;
+    let exclude = config.scan.exclude;
+    let follow_symlinks = config.scan.follow_symlinks;
+    let dotfiles = config.scan.dotfiles;
+    match config.remote {
+        Some(remote) => Ok(Arc::new(
+            SshFs::new(library_path.to_path_buf(), &remote)?
+                .with_excludes(exclude)
+                .with_follow_symlinks(follow_symlinks)
+                .with_dotfiles(dotfiles)
+                .with_progress(progress),
+        )),
+        None => Ok(Arc::new(
+            LocalFs::new(library_path.to_path_buf())
+                .with_excludes(exclude)
+                .with_follow_symlinks(follow_symlinks)
+                .with_dotfiles(dotfiles)
+                .with_progress(progress),
+        )),
+    }
+}
+
+/// Whether a file or directory name matches one of the exclude patterns
+pub fn is_excluded(patterns: &[String], name: &str) -> bool {
+    patterns.iter().any(|pattern| wildcard_match(pattern, name))
+}
+
+/// Whether any component of a relative path is excluded
+fn is_excluded_path(patterns: &[String], relative_path: &str) -> bool {
+    !patterns.is_empty() && relative_path.split('/').any(|name| is_excluded(patterns, name))
+}
+
+/// Whether a dot-prefixed name is left out: all of them unless `dotfiles`,
+/// and picman's own (`.picman-trash`, `.a.jpg.picman-restore`) always
+pub fn is_skipped_dotfile(name: &str, dotfiles: bool) -> bool {
+    name.starts_with('.') && (!dotfiles || name.starts_with(".picman") || name.contains(".picman-"))
+}
+
+/// Whether any component of a relative path is a skipped dotfile
+fn is_dotfile_path(relative_path: &str, dotfiles: bool) -> bool {
+    relative_path.split('/').any(|name| is_skipped_dotfile(name, dotfiles))
+}
+
+/// Match `name` against a pattern where `*` is any run of characters and
+/// `?` any single character
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, tried)) => {
+                    // Let the `*` swallow one more character
+                    p = star + 1;
+                    n = tried + 1;
+                    backtrack = Some((star, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// ==================== Local ====================
+
+/// A library on a locally mounted filesystem
+pub struct LocalFs {
+    root: PathBuf,
+    exclude: Vec<String>,
+    follow_symlinks: bool,
+    dotfiles: bool,
+    progress: Arc<ScanProgress>,
+}
+
+impl LocalFs {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            exclude: Vec::new(),
+            follow_symlinks: false,
+            dotfiles: false,
+            progress: Arc::default(),
+        }
+    }
+
+    /// Skip files and directories whose names match these patterns
+    pub fn with_excludes(mut self, exclude: Vec<String>) -> Self {
+        self.exclude = exclude;
+        self
+    }
+
+    /// Follow symlinked directories and files instead of skipping them.
+    /// Each directory is entered once (by device and inode), however many
+    /// links lead to it, so a link back up the tree can't loop.
+    pub fn with_follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Index dot-prefixed files and directories too
+    pub fn with_dotfiles(mut self, dotfiles: bool) -> Self {
+        self.dotfiles = dotfiles;
+        self
+    }
+
+    /// Count entries into `progress` as they are found
+    pub fn with_progress(mut self, progress: Arc<ScanProgress>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Walk from `start`; `min_depth` 1 leaves out `start` itself
+    fn walk_filtered(&self, start: &Path, min_depth: usize, include_files: bool) -> Vec<FsEntry> {
+        let mut entries = Vec::new();
+        // Directories entered so far, when following symlinks. A subtree
+        // walk counts the directories above it too, which a full walk has
+        // already entered.
+        let mut visited: HashSet<(u64, u64)> = start
+            .ancestors()
+            .take_while(|dir| dir.starts_with(&self.root))
+            .filter_map(dir_identity)
+            .collect();
+        let mut walker = WalkDir::new(start).min_depth(min_depth).follow_links(self.follow_symlinks);
+        if self.follow_symlinks {
+            // Real directories before links to them, so a directory is
+            // recorded at its own path when it is also linked from a sibling
+            walker = walker.sort_by(|a, b| {
+                (a.path_is_symlink(), a.file_name()).cmp(&(b.path_is_symlink(), b.file_name()))
+            });
+        }
+        for entry in walker.into_iter().filter_entry(|e| {
+            !(is_hidden(e) && is_skipped_dotfile(&e.file_name().to_string_lossy(), self.dotfiles))
+                && (e.depth() == 0 || !is_excluded(&self.exclude, &e.file_name().to_string_lossy()))
+                && (e.depth() == 0
+                    || !self.follow_symlinks
+                    || !e.file_type().is_dir()
+                    || dir_identity(e.path()).is_none_or(|id| visited.insert(id)))
+        }) {
+            if self.progress.is_cancelled() {
+                break;
+            }
+            let Ok(entry) = entry else { continue };
+            let is_dir = entry.file_type().is_dir();
+            let wanted = is_dir
+                || (include_files && entry.file_type().is_file() && is_media_file(entry.path()));
+            if !wanted {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else { continue };
+            let Some(relative) = LibraryPath::strip_library(&self.root, entry.path()) else { continue };
+            let entry = FsEntry {
+                relative_path: relative.into_string(),
+                is_dir,
+                size: if is_dir { 0 } else { metadata.len() },
+                mtime: mtime_secs(&metadata),
+            };
+            self.progress.record(&entry);
+            entries.push(entry);
+        }
+        entries
+    }
+}
+
+impl LibraryFs for LocalFs {
+    fn walk_directories(&self) -> Result<Vec<FsEntry>> {
+        Ok(self.walk_filtered(&self.root, 1, false))
+    }
+
+    fn walk(&self) -> Result<Vec<FsEntry>> {
+        Ok(self.walk_filtered(&self.root, 1, true))
+    }
+
+    fn walk_subtree(&self, dir: &str) -> Result<Vec<FsEntry>> {
+        let start = self.root.join(dir);
+        if !start.is_dir()
+            || is_excluded_path(&self.exclude, dir)
+            || is_dotfile_path(dir, self.dotfiles)
+            || (!self.follow_symlinks && start.is_symlink())
+        {
+            return Ok(Vec::new());
+        }
+        Ok(self.walk_filtered(&start, 0, true))
+    }
+
+    fn list_files(&self, dirs: &[&str]) -> Result<Vec<FsEntry>> {
+        let mut files = Vec::new();
+        for dir in dirs {
+            if self.progress.is_cancelled() {
+                break;
+            }
+            let Ok(entries) = std::fs::read_dir(self.root.join(dir)) else {
+                continue;
+            };
+            for entry in entries.filter_map(|e| e.ok()) {
+                let filename = entry.file_name().to_string_lossy().to_string();
+                if is_skipped_dotfile(&filename, self.dotfiles) || is_excluded(&self.exclude, &filename) {
+                    continue; // Skip hidden and excluded files
+                }
+                let path = entry.path();
+                // DirEntry::metadata() describes a symlink itself
+                let metadata = if self.follow_symlinks { std::fs::metadata(&path) } else { entry.metadata() };
+                let Ok(metadata) = metadata else { continue };
+                if !metadata.is_file() || !is_media_file(&path) {
+                    continue;
+                }
+                let entry = FsEntry {
+                    relative_path: join_relative(dir, &filename),
+                    is_dir: false,
+                    size: metadata.len(),
+                    mtime: mtime_secs(&metadata),
+                };
+                self.progress.record(&entry);
+                files.push(entry);
+            }
+        }
+        Ok(files)
+    }
+
+    fn ensure_local(&self, _path: &Path) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub(crate) fn is_hidden(entry: &DirEntry) -> bool {
+    if entry.depth() == 0 {
+        return false; // Never filter the root
+    }
+    entry
+        .file_name()
+        .to_str()
+        .map(|s| s.starts_with('.'))
+        .unwrap_or(false)
+}
+
+/// Device and inode of a directory, which tell two paths to it apart from
+/// two directories
+#[cfg(unix)]
+fn dir_identity(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Elsewhere walkdir's own check still stops links back to an ancestor
+#[cfg(not(unix))]
+fn dir_identity(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+pub(crate) fn mtime_secs(metadata: &std::fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn join_relative(dir: &str, name: &str) -> String {
+    LibraryPath::file(dir, name).into_string()
+}
+
+// ==================== SSH ====================
+
+/// Remote library location parsed from `ssh://[user@]host[:port]/path`
+/// (`sftp://` is accepted as a synonym)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshTarget {
+    /// `user@host` or `host`, as passed to ssh
+    pub destination: String,
+    pub port: Option<u16>,
+    /// Absolute path of the library on the server
+    pub root: String,
+}
+
+impl SshTarget {
+    pub fn parse(url: &str) -> Result<Self> {
+        let Some(rest) = url
+            .strip_prefix("ssh://")
+            .or_else(|| url.strip_prefix("sftp://"))
+        else {
+            bail!("Expected ssh://[user@]host[:port]/path, got '{}'", url);
+        };
+        let Some((authority, path)) = rest.split_once('/') else {
+            bail!("Remote URL '{}' has no library path", url);
+        };
+
+        let (destination, port) = match authority.rsplit_once(':') {
+            Some((dest, port)) => {
+                let port = port
+                    .parse::<u16>()
+                    .with_context(|| format!("Invalid port '{}' in '{}'", port, url))?;
+                (dest, Some(port))
+            }
+            None => (authority, None),
+        };
+        let host = destination.rsplit('@').next().unwrap_or_default();
+        if host.is_empty() {
+            bail!("Remote URL '{}' has no host", url);
+        }
+
+        let path = path.trim_end_matches('/');
+        Ok(Self {
+            destination: destination.to_string(),
+            port,
+            root: format!("/{}", path),
+        })
+    }
+}
+
+/// A library on a server reachable over SSH, mirrored under a local directory
+pub struct SshFs {
+    target: SshTarget,
+    ssh_command: Vec<String>,
+    mirror: PathBuf,
+    exclude: Vec<String>,
+    follow_symlinks: bool,
+    dotfiles: bool,
+    progress: Arc<ScanProgress>,
+}
+
+/// `find -printf` format: type, size, mtime, path, NUL-terminated
+const FIND_FORMAT: &str = r"'%y\t%s\t%T@\t%P\0'";
+
+/// Like `FIND_FORMAT`, but paths include the starting point
+const FIND_FORMAT_WITH_START: &str = r"'%y\t%s\t%T@\t%p\0'";
+
+/// Exit code the remote scripts use when the library root is missing, so it
+/// can't be mistaken for an empty library
+const MISSING_ROOT_EXIT: i32 = 3;
+
+/// Exit code of ssh itself when the connection fails
+const SSH_FAILURE_EXIT: i32 = 255;
+
+impl SshFs {
+    pub fn new(mirror: PathBuf, remote: &RemoteConfig) -> Result<Self> {
+        let target = SshTarget::parse(&remote.url)?;
+        let ssh_command: Vec<String> = remote
+            .ssh_command
+            .as_deref()
+            .unwrap_or("ssh")
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+        if ssh_command.is_empty() {
+            bail!("remote.ssh_command is empty");
+        }
+        Ok(Self {
+            target,
+            ssh_command,
+            mirror,
+            exclude: Vec::new(),
+            follow_symlinks: false,
+            dotfiles: false,
+            progress: Arc::default(),
+        })
+    }
+
+    /// Skip files and directories whose names match these patterns
+    pub fn with_excludes(mut self, exclude: Vec<String>) -> Self {
+        self.exclude = exclude;
+        self
+    }
+
+    /// Follow symlinks on the server (`find -L`, which skips loops it finds)
+    pub fn with_follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// The `find` to list with: without `-L` it reports symlinks as such,
+    /// and `-type d`/`-type f` leave them out
+    fn find(&self) -> &'static str {
+        if self.follow_symlinks {
+            "find -L"
+        } else {
+            "find"
+        }
+    }
+
+    /// Index dot-prefixed files and directories too
+    pub fn with_dotfiles(mut self, dotfiles: bool) -> Self {
+        self.dotfiles = dotfiles;
+        self
+    }
+
+    /// `find` test that prunes dot-entries, unless they are indexed
+    fn prune_dotfiles(&self) -> &'static str {
+        if self.dotfiles {
+            ""
+        } else {
+            "-name '.*' -prune -o "
+        }
+    }
+
+    /// Count entries into `progress` once each listing arrives
+    pub fn with_progress(mut self, progress: Arc<ScanProgress>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    fn command(&self, script: &str) -> Command {
+        let mut cmd = Command::new(&self.ssh_command[0]);
+        cmd.args(&self.ssh_command[1..]);
+        if let Some(port) = self.target.port {
+            cmd.arg("-p").arg(port.to_string());
+        }
+        cmd.args(["-o", "BatchMode=yes", "--"])
+            .arg(&self.target.destination)
+            .arg(script);
+        cmd
+    }
+
+    /// Prefix a script with a `cd` into `dir` below the library root
+    fn in_root(&self, dir: &str, script: &str) -> String {
+        let path = if dir.is_empty() {
+            self.target.root.clone()
+        } else {
+            format!("{}/{}", self.target.root, dir)
+        };
+        format!(
+            "cd -- {} || exit {}; {}",
+            shell_quote(&path),
+            MISSING_ROOT_EXIT,
+            script
+        )
+    }
+
+    /// Run a listing script and parse its `find` output. `find` exits
+    /// non-zero for unreadable subdirectories, so only a failed connection or
+    /// a missing root is treated as an error.
+    fn run_find(&self, script: &str) -> Result<Vec<FsEntry>> {
+        let output = self
+            .command(script)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(spawn_error)?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match output.status.code() {
+            Some(0) => {}
+            Some(MISSING_ROOT_EXIT) => {
+                bail!("Library root {} not found on {}", self.target.root, self.target.destination)
+            }
+            Some(SSH_FAILURE_EXIT) | None => {
+                bail!("ssh to {} failed: {}", self.target.destination, stderr.trim())
+            }
+            Some(code) => warn!(code, stderr = %stderr.trim(), "remote find reported errors"),
+        }
+        let mut entries = parse_find_output(&output.stdout);
+        // Filtered here rather than in `find`, so patterns need no shell quoting
+        entries.retain(|e| {
+            !is_excluded_path(&self.exclude, &e.relative_path) && !is_dotfile_path(&e.relative_path, self.dotfiles)
+        });
+        entries.iter().for_each(|e| self.progress.record(e));
+        Ok(entries)
+    }
+
+    /// Drop mirrored originals that changed on the server since they were
+    /// fetched, so the next read downloads the new version
+    fn drop_stale_copies(&self, entries: &[FsEntry]) {
+        for entry in entries.iter().filter(|e| !e.is_dir) {
+            let local = self.mirror.join(&entry.relative_path);
+            let Ok(metadata) = std::fs::metadata(&local) else { continue };
+            if mtime_secs(&metadata) != entry.mtime || metadata.len() != entry.size {
+                debug!(path = %entry.relative_path, "discarding stale local copy");
+                let _ = std::fs::remove_file(&local);
+            }
+        }
+    }
+
+    #[instrument(skip(self, local), fields(path = %relative))]
+    fn fetch(&self, relative: &str, local: &Path) -> Result<()> {
+        let script = self.in_root(
+            "",
+            &format!(
+                "stat -c %Y -- {0} && cat -- {0}",
+                shell_quote(relative)
+            ),
+        );
+        let mut child = self
+            .command(&script)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(spawn_error)?;
+
+        let parent = local.parent().context("Library path has no parent")?;
+        std::fs::create_dir_all(parent)?;
+        let partial = parent.join(format!(
+            ".{}.{}.part",
+            local.file_name().unwrap_or_default().to_string_lossy(),
+            std::process::id()
+        ));
+
+        let copied = (|| -> Result<i64> {
+            let mut stdout = BufReader::new(child.stdout.take().context("no stdout")?);
+            let mut line = String::new();
+            stdout.read_line(&mut line)?;
+            let mtime: i64 = line.trim().parse().context("Unexpected reply from server")?;
+            let mut file = std::fs::File::create(&partial)?;
+            std::io::copy(&mut stdout, &mut file)?;
+            file.set_modified(UNIX_EPOCH + Duration::from_secs(mtime.max(0) as u64))?;
+            Ok(mtime)
+        })();
+
+        let mut stderr = String::new();
+        if let Some(mut err) = child.stderr.take() {
+            let _ = err.read_to_string(&mut stderr);
+        }
+        let status = child.wait()?;
+        if !status.success() || copied.is_err() {
+            let _ = std::fs::remove_file(&partial);
+            if !status.success() {
+                bail!(
+                    "Failed to fetch {} from {}: {}",
+                    relative,
+                    self.target.destination,
+                    stderr.trim()
+                );
+            }
+        }
+        copied?;
+
+        std::fs::rename(&partial, local)?;
+        info!("fetched original");
+        Ok(())
+    }
+}
+
+impl LibraryFs for SshFs {
+    fn walk_directories(&self) -> Result<Vec<FsEntry>> {
+        self.run_find(&self.in_root(
+            "",
+            &format!("{} . -mindepth 1 {}-type d -printf {}", self.find(), self.prune_dotfiles(), FIND_FORMAT),
+        ))
+    }
+
+    fn walk(&self) -> Result<Vec<FsEntry>> {
+        let entries = self.run_find(&self.in_root(
+            "",
+            &format!(
+                "{} . -mindepth 1 {}'(' -type d -o -type f ')' -printf {}",
+                self.find(),
+                self.prune_dotfiles(),
+                FIND_FORMAT
+            ),
+        ))?;
+        self.drop_stale_copies(&entries);
+        Ok(entries)
+    }
+
+    fn walk_subtree(&self, dir: &str) -> Result<Vec<FsEntry>> {
+        // Start from the parent so `dir` itself is listed, then re-prefix
+        let (parent, name) = dir.rsplit_once('/').unwrap_or(("", dir));
+        let script = format!(
+            "cd -- {} 2>/dev/null && {} {} {}'(' -type d -o -type f ')' -printf {} 2>/dev/null; printf '\\001%s\\0' {}; true",
+            shell_quote(if parent.is_empty() { "." } else { parent }),
+            self.find(),
+            shell_quote(&format!("./{}", name)),
+            self.prune_dotfiles(),
+            FIND_FORMAT_WITH_START,
+            shell_quote(parent)
+        );
+        let entries = self.run_find(&self.in_root("", &script))?;
+        self.drop_stale_copies(&entries);
+        Ok(entries)
+    }
+
+    fn list_files(&self, dirs: &[&str]) -> Result<Vec<FsEntry>> {
+        if dirs.is_empty() {
+            return Ok(Vec::new());
+        }
+        // `%P` is relative to each starting point, so list one directory at a
+        // time and re-prefix; missing directories just produce no output
+        let script = dirs
+            .iter()
+            .map(|dir| {
+                format!(
+                    "{} {} -mindepth 1 -maxdepth 1 {}-type f -printf {} 2>/dev/null; printf '\\001%s\\0' {}",
+                    self.find(),
+                    shell_quote(if dir.is_empty() { "." } else { dir }),
+                    self.prune_dotfiles(),
+                    FIND_FORMAT,
+                    shell_quote(dir)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        let entries = self.run_find(&self.in_root("", &format!("{}; true", script)))?;
+        self.drop_stale_copies(&entries);
+        Ok(entries)
+    }
+
+    fn ensure_local(&self, path: &Path) -> Result<()> {
+        if path.exists() {
+            return Ok(());
+        }
+        let relative = path
+            .strip_prefix(&self.mirror)
+            .with_context(|| format!("{} is outside the library", path.display()))?;
+        self.fetch(&relative.to_string_lossy(), path)
+    }
+
+    fn is_remote(&self) -> bool {
+        true
+    }
+}
+
+fn spawn_error(e: std::io::Error) -> anyhow::Error {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        anyhow::anyhow!("ssh client not found; install OpenSSH or set remote.ssh_command")
+    } else {
+        anyhow::Error::new(e).context("Failed to run ssh")
+    }
+}
+
+/// Quote a string for a POSIX shell
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Parse NUL-separated `type\tsize\tmtime\tpath` records. A record starting
+/// with `\x01` switches the directory that following paths are relative to
+/// (used by `list_files`, which lists several directories in one call).
+/// Non-media files are dropped.
+pub fn parse_find_output(output: &[u8]) -> Vec<FsEntry> {
+    let mut entries = Vec::new();
+    let mut pending: Vec<FsEntry> = Vec::new();
+
+    for record in output.split(|&b| b == 0).filter(|r| !r.is_empty()) {
+        let record = String::from_utf8_lossy(record);
+        if let Some(dir) = record.strip_prefix('\u{1}') {
+            // Marker follows the listing it belongs to
+            for mut entry in pending.drain(..) {
+                entry.relative_path = join_relative(dir, &entry.relative_path);
+                entries.push(entry);
+            }
+            continue;
+        }
+
+        let mut fields = record.splitn(4, '\t');
+        let (Some(kind), Some(size), Some(mtime), Some(path)) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let is_dir = match kind {
+            "d" => true,
+            "f" => false,
+            _ => continue,
+        };
+        let path = path.trim_start_matches("./");
+        if path.is_empty() || (!is_dir && !is_media_file(Path::new(path))) {
+            continue;
+        }
+        let mtime = mtime
+            .split('.')
+            .next()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(0);
+        pending.push(FsEntry {
+            relative_path: path.to_string(),
+            is_dir,
+            size: if is_dir { 0 } else { size.parse().unwrap_or(0) },
+            mtime,
+        });
+    }
+
+    entries.append(&mut pending);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_walkdir_basic() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+
+        // Create test structure
+        fs::create_dir_all(root.join("photos")).unwrap();
+        fs::write(root.join("photos/image.jpg"), "data").unwrap();
+
+        // Check files exist
+        assert!(root.join("photos").exists(), "photos dir should exist");
+        assert!(root.join("photos/image.jpg").exists(), "image.jpg should exist");
+
+        // Check walkdir sees them
+        let entries: Vec<_> = WalkDir::new(root)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .collect();
+
+        // Debug: show what was found
+        let paths: Vec<_> = entries.iter().map(|e| e.path().display().to_string()).collect();
+        assert!(
+            entries.len() >= 3,
+            "Should find root, photos, and image.jpg. Found: {:?}",
+            paths
+        );
+
+        // Check file types
+        let files: Vec<_> = entries.iter().filter(|e| e.file_type().is_file()).collect();
+        assert!(
+            !files.is_empty(),
+            "Should find at least one file. All entries: {:?}",
+            paths
+        );
+    }
+
+    #[test]
+    fn test_is_hidden_respects_depth() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+
+        // Create a hidden directory
+        fs::create_dir_all(root.join(".hidden")).unwrap();
+        fs::write(root.join(".hidden/file.jpg"), "data").unwrap();
+
+        // Root should never be hidden regardless of name
+        let entries: Vec<_> = WalkDir::new(root)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .collect();
+
+        let root_entry = entries.iter().find(|e| e.depth() == 0).unwrap();
+        assert!(!is_hidden(root_entry), "Root at depth 0 should never be hidden");
+
+        // But child hidden dirs should be hidden
+        let hidden_dir = entries
+            .iter()
+            .find(|e| e.file_name().to_string_lossy() == ".hidden");
+        if let Some(hidden) = hidden_dir {
+            assert!(is_hidden(hidden), ".hidden dir should be detected as hidden");
+        }
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("@eaDir", "@eaDir"));
+        assert!(!wildcard_match("@eaDir", "@eaDir2"));
+        assert!(wildcard_match("*.tmp", "a.b.tmp"));
+        assert!(!wildcard_match("*.tmp", "a.tmp.jpg"));
+        assert!(wildcard_match("IMG_????.jpg", "IMG_0001.jpg"));
+        assert!(wildcard_match("*raw*", "raw"));
+        assert!(!wildcard_match("", "x"));
+    }
+
+    #[test]
+    fn test_local_fs_skips_excluded() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("trip/@eaDir")).unwrap();
+        fs::write(root.join("trip/@eaDir/thumb.jpg"), "x").unwrap();
+        fs::write(root.join("trip/a.jpg"), "x").unwrap();
+        fs::write(root.join("trip/a.tmp.jpg"), "x").unwrap();
+
+        let local = LocalFs::new(root.to_path_buf())
+            .with_excludes(vec!["@eaDir".to_string(), "*.tmp.jpg".to_string()]);
+        let mut walked: Vec<String> = local.walk().unwrap().into_iter().map(|e| e.relative_path).collect();
+        walked.sort();
+        assert_eq!(walked, vec!["trip", "trip/a.jpg"]);
+        assert_eq!(local.list_files(&["trip"]).unwrap().len(), 1);
+        assert!(local.walk_subtree("trip/@eaDir").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_local_fs_dotfiles_option() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join(".trip")).unwrap();
+        fs::create_dir_all(root.join(".picman-trash/old")).unwrap();
+        fs::write(root.join(".trip/a.jpg"), "x").unwrap();
+        fs::write(root.join(".b.jpg"), "x").unwrap();
+        fs::write(root.join(".c.jpg.picman-restore"), "x").unwrap();
+        fs::write(root.join(".picman-trash/old/d.jpg"), "x").unwrap();
+        fs::write(root.join("e.jpg"), "x").unwrap();
+
+        let walked = |local: &LocalFs| {
+            let mut paths: Vec<String> = local.walk().unwrap().into_iter().map(|e| e.relative_path).collect();
+            paths.sort();
+            paths
+        };
+        let local = LocalFs::new(root.to_path_buf());
+        assert_eq!(walked(&local), vec!["e.jpg"]);
+        assert!(local.walk_subtree(".trip").unwrap().is_empty());
+
+        // picman's own trash and temporary files stay out either way
+        let local = LocalFs::new(root.to_path_buf()).with_dotfiles(true);
+        assert_eq!(walked(&local), vec![".b.jpg", ".trip", ".trip/a.jpg", "e.jpg"]);
+        assert_eq!(local.list_files(&[""]).unwrap().len(), 2);
+        assert_eq!(local.walk_subtree(".trip").unwrap().len(), 2);
+        assert!(local.walk_subtree(".picman-trash/old").unwrap().is_empty());
+    }
+
+    /// `trip` with a link back to the library root inside it, a second name
+    /// for `trip`, a linked file and a linked directory outside the library
+    #[cfg(unix)]
+    fn symlinked_library(temp: &TempDir) -> PathBuf {
+        use std::os::unix::fs::symlink;
+        let root = temp.path().join("library");
+        let outside = temp.path().join("outside");
+        fs::create_dir_all(root.join("trip")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(root.join("trip/a.jpg"), "x").unwrap();
+        fs::write(outside.join("b.jpg"), "x").unwrap();
+        symlink("..", root.join("trip/loop")).unwrap();
+        symlink("trip", root.join("alias")).unwrap();
+        symlink("trip/a.jpg", root.join("link.jpg")).unwrap();
+        symlink(&outside, root.join("outside")).unwrap();
+        root
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_local_fs_skips_symlinks_by_default() {
+        let temp = TempDir::new().unwrap();
+        let root = symlinked_library(&temp);
+        let local = LocalFs::new(root);
+
+        let mut walked: Vec<String> = local.walk().unwrap().into_iter().map(|e| e.relative_path).collect();
+        walked.sort();
+        assert_eq!(walked, vec!["trip", "trip/a.jpg"]);
+        let listed: Vec<String> = local.list_files(&[""]).unwrap().into_iter().map(|e| e.relative_path).collect();
+        assert!(listed.is_empty(), "{:?}", listed);
+        assert!(local.walk_subtree("alias").unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_local_fs_follows_symlinks_once_each() {
+        let temp = TempDir::new().unwrap();
+        let root = symlinked_library(&temp);
+        let local = LocalFs::new(root).with_follow_symlinks(true);
+
+        // `trip/loop` leads back to the root and `alias` to `trip`, both
+        // already walked, so neither is entered
+        let mut walked: Vec<String> = local.walk().unwrap().into_iter().map(|e| e.relative_path).collect();
+        walked.sort();
+        assert_eq!(walked, vec!["link.jpg", "outside", "outside/b.jpg", "trip", "trip/a.jpg"]);
+        let mut dirs: Vec<String> = local
+            .walk_directories()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.relative_path)
+            .collect();
+        dirs.sort();
+        assert_eq!(dirs, vec!["outside", "trip"]);
+
+        let listed: Vec<String> = local.list_files(&[""]).unwrap().into_iter().map(|e| e.relative_path).collect();
+        assert_eq!(listed, vec!["link.jpg"]);
+        let subtree: Vec<String> = local
+            .walk_subtree("trip")
+            .unwrap()
+            .into_iter()
+            .map(|e| e.relative_path)
+            .collect();
+        assert_eq!(subtree, vec!["trip", "trip/a.jpg"]);
+    }
+
+    #[test]
+    fn test_parse_ssh_target() {
+        let target = SshTarget::parse("ssh://me@nas:2222/volume1/photos/").unwrap();
+        assert_eq!(target.destination, "me@nas");
+        assert_eq!(target.port, Some(2222));
+        assert_eq!(target.root, "/volume1/photos");
+
+        let target = SshTarget::parse("sftp://nas/photos").unwrap();
+        assert_eq!(target.destination, "nas");
+        assert_eq!(target.port, None);
+
+        assert!(SshTarget::parse("/mnt/photos").is_err());
+        assert!(SshTarget::parse("ssh://nas").is_err());
+        assert!(SshTarget::parse("ssh://me@/photos").is_err());
+        assert!(SshTarget::parse("ssh://nas:ssh/photos").is_err());
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn test_parse_find_output() {
+        let walked = b"d\t4096\t1700000000.5\tphotos\0\
+                       f\t12\t1700000001.0000000000\tphotos/a.jpg\0\
+                       f\t3\t1700000002.0\tphotos/notes.txt\0";
+        assert_eq!(
+            parse_find_output(walked),
+            vec![
+                FsEntry { relative_path: "photos".into(), is_dir: true, size: 0, mtime: 1700000000 },
+                FsEntry { relative_path: "photos/a.jpg".into(), is_dir: false, size: 12, mtime: 1700000001 },
+            ]
+        );
+
+        // Listings of several directories, each followed by its marker
+        let listed = b"f\t5\t1700000003.0\tb.jpg\0\x01trip\0\x01empty\0f\t7\t9.0\tc.mp4\0\x01\0";
+        let paths: Vec<String> =
+            parse_find_output(listed).into_iter().map(|e| e.relative_path).collect();
+        assert_eq!(paths, vec!["trip/b.jpg", "c.mp4"]);
+    }
+
+    /// An ssh stand-in that runs the remote script locally
+    #[cfg(unix)]
+    fn fake_ssh(dir: &Path) -> String {
+        let path = dir.join("fake-ssh");
+        fs::write(
+            &path,
+            "#!/bin/sh\nwhile [ \"$1\" != \"--\" ]; do shift; done\nshift 2\nexec sh -c \"$1\"\n",
+        )
+        .unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[cfg(unix)]
+    fn ssh_fs(remote: &Path, mirror: &Path, tools: &Path) -> SshFs {
+        SshFs::new(
+            mirror.to_path_buf(),
+            &RemoteConfig {
+                url: format!("ssh://localhost{}", remote.display()),
+                ssh_command: Some(fake_ssh(tools)),
+            },
+        )
+        .unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ssh_fs_matches_local_listing_and_fetches_lazily() {
+        let remote = TempDir::new().unwrap();
+        let mirror = TempDir::new().unwrap();
+        let tools = TempDir::new().unwrap();
+        fs::create_dir_all(remote.path().join("trip/day 1")).unwrap();
+        fs::create_dir_all(remote.path().join(".cache")).unwrap();
+        fs::write(remote.path().join("trip/day 1/it's.jpg"), "jpeg").unwrap();
+        fs::write(remote.path().join("trip/notes.txt"), "text").unwrap();
+        fs::write(remote.path().join("top.mp4"), "video").unwrap();
+        fs::write(remote.path().join(".cache/x.jpg"), "hidden").unwrap();
+
+        let local = LocalFs::new(remote.path().to_path_buf());
+        let ssh = ssh_fs(remote.path(), mirror.path(), tools.path());
+
+        let sorted = |mut v: Vec<FsEntry>| {
+            v.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+            v
+        };
+        let walked = sorted(ssh.walk().unwrap());
+        assert_eq!(walked, sorted(local.walk().unwrap()));
+        assert_eq!(walked.len(), 4);
+        assert_eq!(
+            sorted(ssh.walk_directories().unwrap()),
+            sorted(local.walk_directories().unwrap())
+        );
+        assert_eq!(
+            sorted(ssh.list_files(&["", "trip/day 1", "gone"]).unwrap()),
+            sorted(local.list_files(&["", "trip/day 1", "gone"]).unwrap())
+        );
+        let subtree = sorted(ssh.walk_subtree("trip").unwrap());
+        assert_eq!(subtree, sorted(local.walk_subtree("trip").unwrap()));
+        let paths: Vec<&str> = subtree.iter().map(|e| e.relative_path.as_str()).collect();
+        assert_eq!(paths, vec!["trip", "trip/day 1", "trip/day 1/it's.jpg"]);
+        assert_eq!(ssh.walk_subtree("trip/day 1").unwrap().len(), 2);
+        assert!(ssh.walk_subtree("gone").unwrap().is_empty());
+        assert!(local.walk_subtree("gone").unwrap().is_empty());
+
+        let excluding = ssh_fs(remote.path(), mirror.path(), tools.path()).with_excludes(vec!["day ?".to_string()]);
+        let paths: Vec<String> = excluding.walk().unwrap().into_iter().map(|e| e.relative_path).collect();
+        assert!(paths.iter().all(|p| !p.starts_with("trip/day 1")));
+        assert!(paths.contains(&"trip".to_string()));
+
+        let with_dotfiles = ssh_fs(remote.path(), mirror.path(), tools.path()).with_dotfiles(true);
+        let local_with_dotfiles = LocalFs::new(remote.path().to_path_buf()).with_dotfiles(true);
+        let walked = sorted(with_dotfiles.walk().unwrap());
+        assert_eq!(walked, sorted(local_with_dotfiles.walk().unwrap()));
+        assert_eq!(walked.len(), 6);
+        assert_eq!(with_dotfiles.list_files(&[".cache"]).unwrap().len(), 1);
+
+        let target = mirror.path().join("trip/day 1/it's.jpg");
+        assert!(!target.exists());
+        ssh.ensure_local(&target).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "jpeg");
+
+        // A changed original invalidates the mirrored copy on the next sync
+        fs::write(remote.path().join("trip/day 1/it's.jpg"), "edited jpeg").unwrap();
+        ssh.walk().unwrap();
+        assert!(!target.exists());
+
+        assert!(ssh.ensure_local(&mirror.path().join("missing.jpg")).is_err());
+        let leftovers = fs::read_dir(mirror.path())
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().ends_with(".part"))
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ssh_fs_missing_root_is_an_error() {
+        let mirror = TempDir::new().unwrap();
+        let tools = TempDir::new().unwrap();
+        let ssh = ssh_fs(&mirror.path().join("nope"), mirror.path(), tools.path());
+        assert!(ssh.walk().is_err());
+    }
+}
@@ -0,0 +1,267 @@
+//! Parallelism and IO limits for bulk work (thumbnails, previews, hashing).
+//!
+//! Set once at startup from the global `--jobs`, `--io-limit`, and `--nice`
+//! flags via [`configure`]. Operations build their rayon pool with [`pool`]
+//! and every bulk file read is charged against a shared [`IoLimiter`] via
+//! [`throttle_read`], so a maintenance run on a NAS leaves bandwidth for
+//! other users. Decodes that happen while the web server is up (on-demand
+//! previews, the daemon's thumbnails for new files) each hold a
+//! [`decode_permit`], so they never decode more images at once than
+//! `--jobs` (or one per core) between them.
+
+use std::path::Path;
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WorkerOptions {
+    /// Worker threads per operation (None = the operation's own default)
+    pub jobs: Option<usize>,
+    /// Max combined read throughput in bytes/second (None = unlimited)
+    pub io_limit: Option<u64>,
+    /// Lower CPU and IO scheduling priority of the whole process
+    pub nice: bool,
+}
+
+static OPTIONS: OnceLock<WorkerOptions> = OnceLock::new();
+static LIMITER: OnceLock<IoLimiter> = OnceLock::new();
+static DECODES: OnceLock<DecodeSlots> = OnceLock::new();
+
+/// Apply worker options for the rest of the process. Only the first call wins.
+pub fn configure(options: WorkerOptions) {
+    if OPTIONS.set(options).is_err() {
+        return;
+    }
+    if let Some(rate) = options.io_limit {
+        let _ = LIMITER.set(IoLimiter::new(rate));
+    }
+    if options.nice {
+        lower_priority();
+    }
+}
+
+pub fn options() -> WorkerOptions {
+    OPTIONS.get().copied().unwrap_or_default()
+}
+
+/// Build the rayon pool for one operation. Use `0` as the default to mean
+/// "one thread per core" (rayon's own default).
+pub fn pool(default_threads: usize) -> ThreadPool {
+    let n = match options().jobs {
+        Some(jobs) => jobs.max(1),
+        None => default_threads,
+    };
+    ThreadPoolBuilder::new()
+        .num_threads(n)
+        .build()
+        .expect("failed to create worker thread pool")
+}
+
+/// Block until `bytes` more may be read under `--io-limit` (no-op if unset)
+pub fn throttle(bytes: u64) {
+    if let Some(limiter) = LIMITER.get() {
+        limiter.acquire(bytes);
+    }
+}
+
+/// Charge a whole-file read of `path` against `--io-limit`
+pub fn throttle_read(path: &Path) {
+    if LIMITER.get().is_some() {
+        if let Ok(meta) = std::fs::metadata(path) {
+            throttle(meta.len());
+        }
+    }
+}
+
+/// Wait for one of the process-wide decode slots. The slot is freed when
+/// the permit is dropped.
+pub fn decode_permit() -> DecodePermit<'static> {
+    DECODES
+        .get_or_init(|| {
+            let slots = options().jobs.unwrap_or_else(|| {
+                std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+            });
+            DecodeSlots::new(slots)
+        })
+        .acquire()
+}
+
+/// Counting semaphore capping concurrent image decodes
+pub struct DecodeSlots {
+    free: Mutex<usize>,
+    freed: Condvar,
+}
+
+impl DecodeSlots {
+    pub fn new(slots: usize) -> Self {
+        Self {
+            free: Mutex::new(slots.max(1)),
+            freed: Condvar::new(),
+        }
+    }
+
+    /// Block until a slot is free
+    pub fn acquire(&self) -> DecodePermit<'_> {
+        let mut free = self.free.lock().unwrap_or_else(|e| e.into_inner());
+        while *free == 0 {
+            free = self.freed.wait(free).unwrap_or_else(|e| e.into_inner());
+        }
+        *free -= 1;
+        DecodePermit { slots: self }
+    }
+}
+
+/// A held decode slot, released on drop
+pub struct DecodePermit<'a> {
+    slots: &'a DecodeSlots,
+}
+
+impl Drop for DecodePermit<'_> {
+    fn drop(&mut self) {
+        let mut free = self.slots.free.lock().unwrap_or_else(|e| e.into_inner());
+        *free += 1;
+        self.slots.freed.notify_one();
+    }
+}
+
+/// Paces reads so the long-run average stays under `bytes_per_sec`.
+/// Shared by all worker threads.
+pub struct IoLimiter {
+    bytes_per_sec: u64,
+    /// (window start, bytes charged since then)
+    window: Mutex<(Instant, u64)>,
+}
+
+/// Idle time after which the window restarts, so a quiet period doesn't
+/// bank credit for a later burst
+const IDLE_RESET: Duration = Duration::from_secs(1);
+
+impl IoLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.max(1),
+            window: Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    pub fn acquire(&self, bytes: u64) {
+        let wait = match self.window.lock() {
+            Ok(mut window) => self.charge(&mut window, bytes, Instant::now()),
+            Err(_) => return,
+        };
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// Record `bytes` and return how long the caller must wait
+    fn charge(&self, window: &mut (Instant, u64), bytes: u64, now: Instant) -> Duration {
+        let due = Duration::from_secs_f64(window.1 as f64 / self.bytes_per_sec as f64);
+        if now.duration_since(window.0) > due + IDLE_RESET {
+            *window = (now, 0);
+        }
+
+        window.1 += bytes;
+        let due = Duration::from_secs_f64(window.1 as f64 / self.bytes_per_sec as f64);
+        due.saturating_sub(now.duration_since(window.0))
+    }
+}
+
+/// Drop to nice 10 and (on Linux) the idle IO scheduling class
+#[cfg(unix)]
+fn lower_priority() {
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, 0, 10);
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+        const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+        const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+        unsafe {
+            libc::syscall(
+                libc::SYS_ioprio_set,
+                IOPRIO_WHO_PROCESS,
+                0 as libc::c_long,
+                IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn lower_priority() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limiter_paces_to_rate() {
+        let limiter = IoLimiter::new(1000);
+        let start = Instant::now();
+        let mut window = (start, 0);
+
+        // First 500 bytes at 1000 B/s are due at t=0.5s
+        assert_eq!(
+            limiter.charge(&mut window, 500, start),
+            Duration::from_millis(500)
+        );
+        // Another 500 after 200ms: due at 1.0s, so wait 800ms
+        assert_eq!(
+            limiter.charge(&mut window, 500, start + Duration::from_millis(200)),
+            Duration::from_millis(800)
+        );
+        // Caught up: no wait for a read that fits the elapsed budget
+        assert_eq!(
+            limiter.charge(&mut window, 100, start + Duration::from_millis(1500)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_limiter_idle_does_not_bank_credit() {
+        let limiter = IoLimiter::new(1000);
+        let start = Instant::now();
+        let mut window = (start, 0);
+
+        // Ten idle seconds, then a burst: still paced from the burst's start
+        let later = start + Duration::from_secs(10);
+        assert_eq!(
+            limiter.charge(&mut window, 2000, later),
+            Duration::from_secs(2)
+        );
+    }
+
+    #[test]
+    fn test_decode_slots_cap_concurrency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let slots = DecodeSlots::new(2);
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..6 {
+                scope.spawn(|| {
+                    let _permit = slots.acquire();
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(*slots.free.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_pool_uses_operation_default_without_configure() {
+        // OPTIONS is never set in unit tests
+        assert_eq!(options(), WorkerOptions::default());
+        assert_eq!(pool(3).current_num_threads(), 3);
+    }
+}